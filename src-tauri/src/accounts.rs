@@ -0,0 +1,224 @@
+//! Saved Codex logins and switching between them.
+//!
+//! The Codex CLI keeps a single login in `$CODEX_HOME/auth.json`. Each saved
+//! account stores a copy of that file in the database; switching writes the
+//! chosen copy back and restarts the app-server so it picks up the new
+//! credentials. Accounts are matched to the auth file by identity (ChatGPT
+//! account id or a hash of the API key), so logins made from the CLI are
+//! recognized too.
+
+use std::path::PathBuf;
+
+use serde_json::Value as JsonValue;
+
+use crate::app_server::ipc_bridge::AccountInfo;
+use crate::database::{Database, SavedAccount};
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Turns started longer ago than this are assumed abandoned (e.g. the app
+/// crashed mid-turn) and do not block an automatic account switch
+const TURN_IN_FLIGHT_MAX_AGE_SECS: i64 = 2 * 3600;
+
+/// Path of the Codex CLI auth file
+fn auth_file_path() -> PathBuf {
+    std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(crate::codex_import::get_codex_dir)
+        .join("auth.json")
+}
+
+/// Raw contents of the auth file, or `None` when logged out
+fn read_auth_file() -> Result<Option<String>> {
+    match std::fs::read_to_string(auth_file_path()) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Replace the auth file, readable by the current user only
+fn write_auth_file(contents: &str) -> Result<()> {
+    let path = auth_file_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    crate::utils::atomic_write(&path, contents.as_bytes())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// (identity, auth mode) of an auth file; `None` when it holds no login
+pub(crate) fn auth_identity(auth: &str) -> Option<(String, &'static str)> {
+    let auth: JsonValue = serde_json::from_str(auth).ok()?;
+    if let Some(account_id) = auth
+        .pointer("/tokens/account_id")
+        .and_then(JsonValue::as_str)
+        .filter(|id| !id.is_empty())
+    {
+        return Some((format!("chatgpt:{account_id}"), "chatgpt"));
+    }
+    let api_key = auth
+        .get("OPENAI_API_KEY")
+        .and_then(JsonValue::as_str)
+        .filter(|key| !key.is_empty())?;
+    let hash = crate::utils::sha256_hex(api_key.as_bytes());
+    Some((format!("apikey:{}", &hash[..16]), "apiKey"))
+}
+
+fn active_identity() -> Option<String> {
+    read_auth_file()
+        .ok()
+        .flatten()
+        .and_then(|auth| auth_identity(&auth))
+        .map(|(identity, _)| identity)
+}
+
+/// Saved accounts with `is_active` set for the current login
+pub fn list(database: &Database) -> Result<Vec<SavedAccount>> {
+    let active = active_identity();
+    let mut accounts = database.list_accounts()?;
+    for account in &mut accounts {
+        account.is_active = active.as_deref() == Some(account.identity.as_str());
+    }
+    Ok(accounts)
+}
+
+fn find(database: &Database, account_id: &str) -> Result<SavedAccount> {
+    list(database)?
+        .into_iter()
+        .find(|account| account.id == account_id)
+        .ok_or_else(|| Error::Other(format!("Account not found: {account_id}")))
+}
+
+/// Save the current login so it can be switched back to later
+///
+/// Saving a login that is already saved refreshes its credentials and,
+/// if given, its label.
+pub async fn save_current(state: &AppState, label: Option<String>) -> Result<SavedAccount> {
+    let auth = crate::utils::spawn_blocking_io(read_auth_file)
+        .await?
+        .ok_or_else(|| Error::Other("Not logged in".to_string()))?;
+    let (identity, auth_mode) = auth_identity(&auth)
+        .ok_or_else(|| Error::Other("The Codex auth file holds no login".to_string()))?;
+
+    // Email and plan are best-effort; the credentials alone are enough to switch
+    let details = match state.start_app_server().await {
+        Ok(()) => {
+            let mut server = state.app_server.write().await;
+            match server.as_mut() {
+                Some(server) => server
+                    .send_request::<_, AccountInfo>("account/read", serde_json::json!({}))
+                    .await
+                    .ok()
+                    .and_then(|info| info.account),
+                None => None,
+            }
+        }
+        Err(_) => None,
+    };
+
+    let label = label.map(|label| label.trim().to_string()).filter(|label| !label.is_empty());
+    let account = state.database.upsert_account(
+        &identity,
+        label.as_deref(),
+        details.as_ref().and_then(|d| d.email.as_deref()),
+        auth_mode,
+        details.as_ref().and_then(|d| d.plan_type.as_deref()),
+        &auth,
+    )?;
+    tracing::info!("Saved account {} ({})", account.label, account.id);
+    find(&state.database, &account.id)
+}
+
+/// Make a saved account the active login and restart the app-server
+///
+/// The login being replaced has its saved credentials refreshed first, so
+/// tokens renewed since it was saved are kept.
+pub async fn switch(state: &AppState, account_id: &str) -> Result<SavedAccount> {
+    let auth = state
+        .database
+        .get_account_auth(account_id)?
+        .ok_or_else(|| Error::Other(format!("Account not found: {account_id}")))?;
+
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        if let Some(current) = read_auth_file()? {
+            if let Some((identity, _)) = auth_identity(&current) {
+                database.update_account_auth(&identity, &current)?;
+            }
+        }
+        write_auth_file(&auth)
+    })
+    .await?;
+
+    state.restart_app_server().await?;
+    state.database.mark_account_used(account_id)?;
+    let account = find(&state.database, account_id)?;
+    tracing::info!("Switched to account {} ({})", account.label, account.id);
+    state
+        .events
+        .emit("account-switched", serde_json::json!({ "accountId": account.id }))
+        .await;
+    Ok(account)
+}
+
+/// Switch to a project's default account before starting a thread in it
+///
+/// A no-op when the project has no default, the account was removed, or it
+/// is already active. Switching restarts the app-server, so it is refused
+/// while other turns are running.
+pub async fn ensure_project_account(state: &AppState, project_id: &str) -> Result<()> {
+    let Some(account_id) = state
+        .database
+        .get_project(project_id)?
+        .and_then(|project| project.settings().account_id)
+    else {
+        return Ok(());
+    };
+    let account = match find(&state.database, &account_id) {
+        Ok(account) => account,
+        Err(_) => {
+            tracing::warn!("Project {} uses removed account {}", project_id, account_id);
+            return Ok(());
+        }
+    };
+    if account.is_active {
+        return Ok(());
+    }
+
+    let since = chrono::Utc::now().timestamp() - TURN_IN_FLIGHT_MAX_AGE_SECS;
+    if state.database.count_turns_in_flight(since)? > 0 {
+        return Err(Error::Other(format!(
+            "This project uses the account '{}'; wait for running turns to finish or switch accounts manually",
+            account.label
+        )));
+    }
+    switch(state, &account_id).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_identity() {
+        let chatgpt = r#"{"OPENAI_API_KEY":null,"tokens":{"account_id":"org-1","id_token":"x"}}"#;
+        assert_eq!(
+            auth_identity(chatgpt),
+            Some(("chatgpt:org-1".to_string(), "chatgpt"))
+        );
+
+        let (identity, mode) = auth_identity(r#"{"OPENAI_API_KEY":"sk-test"}"#).unwrap();
+        assert_eq!(mode, "apiKey");
+        assert!(identity.starts_with("apikey:"));
+        assert!(!identity.contains("sk-test"));
+
+        assert!(auth_identity(r#"{"OPENAI_API_KEY":null,"tokens":null}"#).is_none());
+        assert!(auth_identity("not json").is_none());
+    }
+}
@@ -0,0 +1,309 @@
+//! Registry of the actions a user can invoke.
+//!
+//! Every command registered with `generate_handler!` is either an action
+//! here or listed in `INTERNAL_COMMANDS` (queries, settings plumbing and
+//! renderer bookkeeping); a test enforces it, so no command is left
+//! unclassified. The registry is served to the renderer by `list_actions`.
+//! Action ids are stable.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionCategory {
+    Project,
+    Files,
+    Git,
+    Session,
+    Turn,
+    Snapshot,
+    Account,
+    Server,
+    Approvals,
+    Settings,
+    Maintenance,
+    System,
+}
+
+/// What the caller must supply before the action can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ActionContext {
+    /// An open project
+    Project,
+    /// A project file or directory
+    File,
+    /// A session (thread)
+    Session,
+    /// A snapshot of the session's project
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Action {
+    pub id: &'static str,
+    /// Backend command that performs the action
+    pub command: &'static str,
+    pub title: &'static str,
+    pub category: ActionCategory,
+    pub context: &'static [ActionContext],
+    /// Deletes or overwrites data; ask before running
+    pub destructive: bool,
+}
+
+const fn action(
+    id: &'static str,
+    command: &'static str,
+    title: &'static str,
+    category: ActionCategory,
+    context: &'static [ActionContext],
+    destructive: bool,
+) -> Action {
+    Action {
+        id,
+        command,
+        title,
+        category,
+        context,
+        destructive,
+    }
+}
+
+/// Ids of the quick actions in the tray menu
+///
+/// Those with a backend command are registry actions; opening the window
+/// and quitting are handled by the shell itself.
+pub mod tray {
+    pub const OPEN_WINDOW: &str = "app.openWindow";
+    pub const QUIT: &str = "app.quit";
+    pub const PAUSE_QUEUE: &str = "turn.pauseQueue";
+    pub const STOP_TURNS: &str = "turn.interruptAll";
+    pub const KEEP_AWAKE: &str = "system.keepAwake";
+}
+
+use ActionCategory as C;
+use ActionContext as X;
+
+const NONE: &[ActionContext] = &[];
+const PROJECT: &[ActionContext] = &[X::Project];
+const FILE: &[ActionContext] = &[X::Project, X::File];
+const SESSION: &[ActionContext] = &[X::Session];
+const SESSION_PROJECT: &[ActionContext] = &[X::Session, X::Project];
+const SNAPSHOT: &[ActionContext] = &[X::Snapshot, X::Project];
+
+#[rustfmt::skip]
+pub const ACTIONS: &[Action] = &[
+    action("project.add", "add_project", "Add Project", C::Project, NONE, false),
+    action("project.edit", "update_project", "Edit Project Settings", C::Project, PROJECT, false),
+    action("project.appearance", "set_project_appearance", "Change Project Appearance", C::Project, PROJECT, false),
+    action("project.archive", "archive_project", "Archive Project", C::Project, PROJECT, false),
+    action("project.unarchive", "unarchive_project", "Restore Archived Project", C::Project, NONE, false),
+    action("project.remove", "remove_project", "Remove Project", C::Project, PROJECT, true),
+    action("project.watch", "watch_project", "Watch Project for Changes", C::Project, PROJECT, false),
+    action("project.unwatch", "unwatch_project", "Stop Watching Project", C::Project, PROJECT, false),
+    action("project.setEnv", "set_project_env", "Set Environment Variable", C::Project, PROJECT, false),
+    action("project.deleteEnv", "delete_project_env", "Delete Environment Variable", C::Project, PROJECT, true),
+    action("project.changeReport", "generate_change_report", "Generate Change Report", C::Project, PROJECT, false),
+    action("project.runCommand", "execute_terminal_command", "Run Terminal Command", C::Project, PROJECT, false),
+    action("project.startWorkflow", "start_workflow", "Start Workflow", C::Project, PROJECT, false),
+    action("files.find", "search_project_files", "Go to File", C::Files, PROJECT, false),
+    action("files.search", "search_project_content", "Search in Project", C::Files, PROJECT, false),
+    action("files.newFile", "create_project_file", "New File", C::Files, PROJECT, false),
+    action("files.newFolder", "create_directory", "New Folder", C::Files, PROJECT, false),
+    action("files.rename", "rename_project_path", "Rename", C::Files, FILE, false),
+    action("files.move", "move_project_path", "Move", C::Files, FILE, false),
+    action("files.duplicate", "duplicate_project_path", "Duplicate", C::Files, FILE, false),
+    action("git.stage", "git_stage_files", "Stage Changes", C::Git, PROJECT, false),
+    action("git.unstage", "git_unstage_files", "Unstage Changes", C::Git, PROJECT, false),
+    action("git.commit", "git_commit", "Commit", C::Git, PROJECT, false),
+    action("git.suggestCommitMessage", "suggest_commit_message", "Suggest Commit Message", C::Git, PROJECT, false),
+    action("git.push", "git_push", "Push", C::Git, PROJECT, false),
+    action("git.applyPatch", "git_apply_patch", "Apply Patch", C::Git, PROJECT, false),
+    action("git.checkout", "git_checkout_branch", "Check Out Branch", C::Git, PROJECT, false),
+    action("git.merge", "git_merge_no_ff", "Merge Branch", C::Git, PROJECT, false),
+    action("git.branchFromDetached", "create_branch_from_detached_head", "Create Branch from Detached HEAD", C::Git, PROJECT, false),
+    action("git.unshallow", "unshallow_repo", "Fetch Full History", C::Git, PROJECT, false),
+    action("git.createWorktree", "create_worktree", "Create Worktree", C::Git, PROJECT, false),
+    action("git.removeWorktree", "remove_worktree", "Remove Worktree", C::Git, PROJECT, true),
+    action("git.createPullRequest", "create_pull_request", "Create Pull Request", C::Git, PROJECT, false),
+    action("git.checkoutPullRequest", "checkout_pull_request", "Check Out Pull Request", C::Git, PROJECT, false),
+    action("git.replyToPrComment", "reply_to_pr_comment", "Reply to Pull Request Comment", C::Git, PROJECT, false),
+    action("git.watchCi", "watch_ci_status", "Watch CI Status", C::Git, PROJECT, false),
+    action("git.unwatchCi", "unwatch_ci_status", "Stop Watching CI Status", C::Git, PROJECT, false),
+    action("session.new", "start_thread", "New Session", C::Session, PROJECT, false),
+    action("session.fromPrReview", "start_pr_review_thread", "Address Review Comments", C::Session, PROJECT, false),
+    action("session.resume", "resume_thread", "Resume Session", C::Session, SESSION, false),
+    action("session.rename", "update_session_metadata", "Rename Session", C::Session, SESSION, false),
+    action("session.favorite", "set_session_favorite", "Toggle Favorite", C::Session, SESSION, false),
+    action("session.addTag", "add_session_tag", "Add Tag", C::Session, SESSION, false),
+    action("session.removeTag", "remove_session_tag", "Remove Tag", C::Session, SESSION, false),
+    action("session.archive", "archive_sessions", "Archive Session", C::Session, SESSION, false),
+    action("session.move", "move_sessions_to_project", "Move Session to Project", C::Session, SESSION, false),
+    action("session.adopt", "adopt_session", "Adopt Session", C::Session, SESSION, false),
+    action("session.handoff", "handoff_session", "Hand Off Session", C::Session, SESSION, false),
+    action("session.delete", "delete_session", "Delete Session", C::Session, SESSION, true),
+    action("session.deleteSelected", "delete_sessions", "Delete Selected Sessions", C::Session, SESSION, true),
+    action("session.search", "search_sessions", "Search Sessions", C::Session, NONE, false),
+    action("session.export", "export_session", "Export Session", C::Session, SESSION, false),
+    action("session.import", "import_session", "Import Session", C::Session, NONE, false),
+    action("session.clearLiveDiff", "clear_session_live_diff", "Clear Live Diff", C::Session, SESSION, false),
+    action("session.cleanupWorktree", "cleanup_session_worktree", "Clean Up Session Worktree", C::Session, SESSION, true),
+    action("session.deleteCodexSession", "delete_codex_session", "Delete Codex CLI Session", C::Session, NONE, true),
+    action("turn.send", "send_message", "Send Message", C::Turn, SESSION, false),
+    action("turn.interrupt", "interrupt_turn", "Stop Turn", C::Turn, SESSION, false),
+    action(tray::STOP_TURNS, "interrupt_all_turns", "Stop All Turns", C::Turn, NONE, false),
+    action("turn.review", "start_review", "Start Review", C::Turn, SESSION, false),
+    action("turn.shell", "run_user_shell_command", "Run Shell Command in Session", C::Turn, SESSION, false),
+    action("turn.queueFollowup", "queue_followup", "Queue Follow-up", C::Turn, SESSION, false),
+    action("turn.removeFollowup", "remove_followup", "Remove Follow-up", C::Turn, SESSION, false),
+    action("turn.clearFollowups", "clear_followups", "Clear Follow-ups", C::Turn, SESSION, true),
+    action("turn.enqueue", "enqueue_turn", "Add to Turn Queue", C::Turn, SESSION, false),
+    action("turn.cancelQueued", "cancel_queued_turn", "Cancel Queued Turn", C::Turn, SESSION, false),
+    action(tray::PAUSE_QUEUE, "set_queue_paused", "Pause or Resume Turn Queue", C::Turn, NONE, false),
+    action("turn.cancelRateLimited", "cancel_rate_limited_message", "Cancel Rate-Limited Message", C::Turn, SESSION, false),
+    action("turn.runBatch", "run_batch", "Run Batch", C::Turn, NONE, false),
+    action("turn.runComparison", "run_comparison", "Compare Runs of a Prompt", C::Turn, PROJECT, false),
+    action("turn.resolveComparison", "resolve_comparison", "Keep Comparison Run", C::Turn, NONE, true),
+    action("snapshot.create", "create_snapshot", "Create Snapshot", C::Snapshot, SESSION_PROJECT, false),
+    action("snapshot.revert", "revert_to_snapshot", "Revert to Snapshot", C::Snapshot, SNAPSHOT, true),
+    action("snapshot.restoreFiles", "restore_files_from_snapshot", "Restore Files from Snapshot", C::Snapshot, SNAPSHOT, true),
+    action("snapshot.undoRevert", "undo_last_revert", "Undo Last Revert", C::Snapshot, SESSION_PROJECT, true),
+    action("snapshot.compare", "diff_snapshots", "Compare Snapshots", C::Snapshot, SNAPSHOT, false),
+    action("snapshot.compareWorktree", "diff_snapshot_to_worktree", "Compare Snapshot with Working Tree", C::Snapshot, SNAPSHOT, false),
+    action("snapshot.exportPatch", "export_snapshot_patch", "Export Snapshot as Patch", C::Snapshot, SNAPSHOT, false),
+    action("snapshot.applyToPath", "apply_snapshot_to_path", "Apply Snapshot to Another Worktree", C::Snapshot, SNAPSHOT, true),
+    action("snapshot.cleanupOld", "cleanup_old_snapshots_by_age", "Delete Old Snapshots", C::Snapshot, NONE, true),
+    action("snapshot.cleanupSession", "cleanup_session_snapshots", "Delete Older Session Snapshots", C::Snapshot, SESSION, true),
+    action("snapshot.compact", "compact_snapshot_storage", "Compact Snapshot Storage", C::Snapshot, NONE, false),
+    action("account.login", "start_login", "Log In", C::Account, NONE, false),
+    action("account.reauth", "start_reauth", "Log In Again", C::Account, NONE, false),
+    action("account.logout", "logout", "Log Out", C::Account, NONE, false),
+    action("account.save", "save_current_account", "Save Current Account", C::Account, NONE, false),
+    action("account.switch", "switch_account", "Switch Account", C::Account, NONE, false),
+    action("account.remove", "remove_account", "Remove Saved Account", C::Account, NONE, true),
+    action("account.setBitbucketCredentials", "set_bitbucket_credentials", "Set Bitbucket App Password", C::Account, NONE, false),
+    action("provider.save", "save_model_provider", "Save Model Provider", C::Account, NONE, false),
+    action("provider.remove", "remove_model_provider", "Remove Model Provider", C::Account, NONE, true),
+    action("provider.setApiKey", "set_model_provider_api_key", "Set Provider API Key", C::Account, NONE, false),
+    action("provider.test", "test_provider_connection", "Test Provider Connection", C::Account, NONE, false),
+    action("server.restart", "restart_server", "Restart App Server", C::Server, NONE, false),
+    action("server.checkVersion", "check_codex_version", "Check Codex Version", C::Server, NONE, false),
+    action("server.upgrade", "upgrade_codex", "Upgrade Codex", C::Server, NONE, false),
+    action("server.clearRpcTrace", "clear_rpc_trace", "Clear RPC Trace", C::Server, NONE, false),
+    action("approvals.allow", "add_to_allowlist", "Allow Command", C::Approvals, PROJECT, false),
+    action("approvals.unallow", "remove_from_allowlist", "Remove Allowed Command", C::Approvals, PROJECT, false),
+    action("approvals.deny", "add_to_denylist", "Deny Command", C::Approvals, PROJECT, false),
+    action("approvals.undeny", "remove_from_denylist", "Remove Denied Command", C::Approvals, PROJECT, false),
+    action("settings.export", "export_settings", "Export Settings", C::Settings, NONE, false),
+    action("settings.import", "import_settings", "Import Settings", C::Settings, NONE, true),
+    action("maintenance.gc", "run_gc", "Clean Up Orphaned Data", C::Maintenance, NONE, true),
+    action("maintenance.exportUsage", "export_usage_csv", "Export Usage as CSV", C::Maintenance, NONE, false),
+    action("maintenance.exitSafeMode", "exit_safe_mode", "Exit Safe Mode", C::Maintenance, NONE, false),
+    action("maintenance.diagnostics", "run_diagnostics", "Run Diagnostics", C::Maintenance, NONE, false),
+    action(tray::KEEP_AWAKE, "start_keep_awake", "Keep Computer Awake", C::System, NONE, false),
+    action("system.allowSleep", "stop_keep_awake", "Allow Computer to Sleep", C::System, NONE, false),
+    action("system.lock", "lock_app", "Lock Codex Desktop", C::System, NONE, false),
+];
+
+/// Registered commands that are not user actions
+#[rustfmt::skip]
+pub const INTERNAL_COMMANDS: &[&str] = &[
+    // Queries
+    "list_projects", "list_archived_projects", "list_project_env", "get_project_git_info",
+    "get_project_git_diff", "git_diff_staged", "git_diff_branch", "list_project_files",
+    "get_directory_children", "get_recently_modified_files", "preview_ignore_effect",
+    "validate_project_directory", "read_project_file", "read_project_file_content",
+    "get_git_branches", "get_git_commits", "git_status", "git_remote_info", "get_signing_config",
+    "check_forge_cli", "list_git_hooks", "list_hook_bypasses", "preflight_patch",
+    "get_forge_links", "list_pull_requests", "get_pull_request", "list_pr_review_comments",
+    "get_ci_status", "get_current_branch", "get_head_state", "list_worktrees", "list_sessions",
+    "get_session", "list_session_tags", "preview_session_redaction", "get_imported_session_items",
+    "get_session_stats", "get_turn_timings", "list_turn_outcomes", "get_session_live_diff",
+    "list_recovered_turns", "get_session_baseline_diff", "get_session_worktree", "list_comparisons",
+    "get_comparison_results", "get_effective_thread_options", "list_active_turns", "list_threads",
+    "list_followups", "list_queue", "get_queue_status", "get_pending_work_summary",
+    "list_workflows", "list_snapshots",
+    "get_snapshot_storage_usage", "get_snapshot_sizes", "get_server_status", "detect_codex_installations",
+    "get_account_info", "list_accounts", "list_model_providers", "get_models", "list_skills",
+    "list_mcp_servers", "read_config", "get_account_rate_limits", "get_rate_limit_forecast",
+    "get_rpc_trace", "get_allowlist", "check_allowlist_match", "get_denylist", "classify_command",
+    "get_codex_config", "list_codex_sessions", "get_codex_session", "search_codex_sessions",
+    "get_codex_dir", "get_sandbox_support", "get_terminal_scrollback", "get_background_tasks",
+    "get_safe_mode_reason", "get_gc_preview", "get_usage_summary", "is_keep_awake_active",
+    "get_app_paths", "get_log_tail", "list_actions", "get_app_lock_status",
+    "list_pending_approvals", "get_quick_prompt_target",
+    // Settings pages
+    "get_redaction_settings", "set_redaction_settings", "get_secret_masking_settings",
+    "set_secret_masking_settings", "get_turn_concurrency_settings", "set_turn_concurrency_settings",
+    "get_turn_budget_settings", "set_turn_budget_settings", "get_attachment_budget_settings",
+    "set_attachment_budget_settings", "get_snapshot_settings", "set_snapshot_settings",
+    "get_app_server_launch_settings", "set_app_server_launch_settings", "set_rate_limit_settings",
+    "set_rpc_trace_config", "get_approval_policy", "set_approval_policy",
+    "get_backend_heartbeat_config", "set_backend_heartbeat_config", "set_background_task_settings",
+    "write_config", "set_app_lock_passphrase", "set_app_lock_settings",
+    "get_approval_shortcut_settings", "set_approval_shortcut_settings",
+    "get_terminal_output_settings", "set_terminal_output_settings", "get_locale", "set_locale",
+    "get_notification_preferences", "set_notification_preferences", "get_tray_settings",
+    "set_tray_settings", "get_summon_shortcut_settings", "set_summon_shortcut_settings",
+    // Editor and renderer bookkeeping
+    "cancel_project_content_search", "write_project_file", "update_session_status",
+    "set_session_first_message", "update_session_tasks", "respond_to_approval", "reorder_queue",
+    "prepare_attachments", "renderer_ready", "renderer_heartbeat", "unlock_app",
+    "report_user_activity",
+];
+
+/// Every action, in registry order
+pub fn list() -> Vec<Action> {
+    ACTIONS.to_vec()
+}
+
+/// The action with `id`
+pub fn get(id: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|action| action.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_every_command_is_classified() {
+        let registered: Vec<&str> = include_str!("lib.rs")
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("commands::"))
+            .filter_map(|path| path.trim_end_matches(',').rsplit("::").next())
+            .collect();
+        assert!(registered.len() > 100);
+
+        let actions: HashSet<&str> = ACTIONS.iter().map(|action| action.command).collect();
+        let internal: HashSet<&str> = INTERNAL_COMMANDS.iter().copied().collect();
+        for command in &registered {
+            assert!(
+                actions.contains(command) ^ internal.contains(command),
+                "{command} must be either an action or an internal command"
+            );
+        }
+        let registered: HashSet<&str> = registered.into_iter().collect();
+        for command in actions.iter().chain(&internal) {
+            assert!(
+                registered.contains(command),
+                "{command} is not a registered command"
+            );
+        }
+
+        let ids: HashSet<&str> = ACTIONS.iter().map(|action| action.id).collect();
+        assert_eq!(ids.len(), ACTIONS.len(), "action ids must be unique");
+    }
+
+    #[test]
+    fn test_tray_actions_are_registered() {
+        for id in [tray::PAUSE_QUEUE, tray::STOP_TURNS, tray::KEEP_AWAKE] {
+            assert!(get(id).is_some(), "{id} is not a registry action");
+        }
+        for id in [tray::OPEN_WINDOW, tray::QUIT] {
+            assert!(get(id).is_none(), "{id} must not shadow a registry action");
+        }
+    }
+}
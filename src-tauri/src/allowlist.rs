@@ -0,0 +1,275 @@
+//! Command allowlist patterns.
+//!
+//! Patterns are matched against one command segment at a time, as parsed by
+//! `approvals` (words joined by single spaces), so `npm *` cannot allowlist
+//! `npm test; rm -rf /`. Three forms are accepted:
+//!
+//! - literal: `cargo test` matches exactly that command
+//! - glob: `*` matches any run of characters and `?` a single one, with `\`
+//!   escaping the next character; a trailing ` *` also matches no arguments,
+//!   so `npm run *` covers `npm run` and `npm run build`
+//! - regex: `re:` followed by a regular expression for the whole segment
+//!
+//! Literal patterns also match a whole command line exactly, operators and
+//! all, which is how a compound command is allowlisted. No pattern matches
+//! a command with command or process substitution, backticks, variable
+//! expansion or variable assignments, since segments cannot show what
+//! those run.
+
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::{Error, Result};
+
+/// Prefix of regular expression patterns
+pub const REGEX_PREFIX: &str = "re:";
+
+/// Longest pattern accepted
+const MAX_PATTERN_LEN: usize = 500;
+
+/// Compiled size limit for pattern regexes
+const MAX_REGEX_SIZE: usize = 1 << 20;
+
+/// Characters that join or redirect commands; wildcard patterns match one
+/// command, so these only make sense in literal patterns
+const SHELL_OPERATORS: &[char] = &[';', '|', '&', '<', '>', '`', '\n'];
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// A validated allowlist pattern
+#[derive(Debug, Clone)]
+pub struct AllowlistPattern {
+    source: String,
+    matcher: Matcher,
+}
+
+impl AllowlistPattern {
+    /// Normalize and validate `pattern`
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let source = normalize(pattern);
+        if source.is_empty() {
+            return Err(invalid(pattern, "it is empty"));
+        }
+        if source.len() > MAX_PATTERN_LEN {
+            return Err(invalid(
+                pattern,
+                &format!("it is longer than {MAX_PATTERN_LEN} characters"),
+            ));
+        }
+
+        let matcher = if let Some(expression) = source.strip_prefix(REGEX_PREFIX) {
+            let regex = RegexBuilder::new(&format!("^(?:{expression})$"))
+                .size_limit(MAX_REGEX_SIZE)
+                .build()
+                .map_err(|err| invalid(pattern, &err.to_string()))?;
+            if regex.is_match("") {
+                return Err(invalid(pattern, "it matches an empty command"));
+            }
+            Matcher::Regex(regex)
+        } else if let Some(literal) = unescape_literal(&source) {
+            Matcher::Literal(literal)
+        } else {
+            if source.starts_with(['*', '?']) {
+                return Err(invalid(pattern, "the command name cannot be a wildcard"));
+            }
+            if source.contains(SHELL_OPERATORS) {
+                return Err(invalid(
+                    pattern,
+                    "wildcard patterns match a single command; add one pattern per command",
+                ));
+            }
+            let regex = RegexBuilder::new(&glob_to_regex(&source))
+                .size_limit(MAX_REGEX_SIZE)
+                .build()
+                .map_err(|err| invalid(pattern, &err.to_string()))?;
+            Matcher::Regex(regex)
+        };
+
+        Ok(Self { source, matcher })
+    }
+
+    /// The normalized pattern, as stored
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches one command segment
+    pub fn matches(&self, segment: &str) -> bool {
+        match &self.matcher {
+            Matcher::Literal(literal) => literal == segment,
+            Matcher::Regex(regex) => regex.is_match(segment),
+        }
+    }
+
+    /// Whether the pattern is exactly the whole command line
+    pub fn matches_whole(&self, command: &str) -> bool {
+        matches!(&self.matcher, Matcher::Literal(literal) if literal == command.trim())
+    }
+}
+
+/// Allowlist patterns matching one segment of a checked command
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentMatch {
+    /// The segment as parsed, words joined by spaces
+    pub text: String,
+    pub patterns: Vec<String>,
+}
+
+/// Result of checking a command against allowlist patterns
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowlistCheck {
+    /// The whole command, or every segment of it, is allowlisted
+    pub allowlisted: bool,
+    /// Patterns matching the command or one of its segments
+    pub matched_patterns: Vec<String>,
+    pub segments: Vec<SegmentMatch>,
+}
+
+/// Check `command` against `patterns` the way approval requests are checked
+pub fn check(command: &str, patterns: &[AllowlistPattern]) -> AllowlistCheck {
+    let sources: Vec<String> = patterns.iter().map(|p| p.as_str().to_string()).collect();
+    let context = crate::approvals::analyze(command, None, None, &sources);
+    let patterns = if context.expands { &[] } else { patterns };
+    let segments = context
+        .intents
+        .iter()
+        .map(|intent| SegmentMatch {
+            text: intent.text.clone(),
+            patterns: patterns
+                .iter()
+                .filter(|pattern| pattern.matches(&intent.text))
+                .map(|pattern| pattern.as_str().to_string())
+                .collect(),
+        })
+        .collect();
+    AllowlistCheck {
+        allowlisted: context.allowlisted,
+        matched_patterns: context.allowlist_matches,
+        segments,
+    }
+}
+
+/// Rewrite a pattern from before globs and regexes, where only `*` was
+/// special, so it keeps matching what it used to
+pub fn from_legacy(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if matches!(c, '\\' | '?') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if out.starts_with(REGEX_PREFIX) {
+        out.insert(0, '\\');
+    }
+    normalize(&out)
+}
+
+/// Trim, and collapse whitespace runs outside regexes to single spaces
+fn normalize(pattern: &str) -> String {
+    let pattern = pattern.trim();
+    if pattern.starts_with(REGEX_PREFIX) {
+        pattern.to_string()
+    } else {
+        pattern.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// The text a glob without wildcards stands for, or `None` if it has any
+fn unescape_literal(glob: &str) -> Option<String> {
+    let mut out = String::with_capacity(glob.len());
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => return None,
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    // A trailing unescaped " *" means "any arguments, or none"
+    let (body, any_args) = match glob.strip_suffix(" *") {
+        Some(body) if !body.ends_with('\\') => (body, true),
+        _ => (glob, false),
+    };
+    let mut out = String::from("^");
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    out.push_str(&regex::escape(&next.to_string()));
+                }
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if any_args {
+        out.push_str("(?: .*)?");
+    }
+    out.push('$');
+    out
+}
+
+fn invalid(pattern: &str, reason: &str) -> Error {
+    Error::Other(format!("Invalid allowlist pattern '{pattern}': {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        AllowlistPattern::parse(pattern).unwrap().matches(text)
+    }
+
+    #[test]
+    fn test_pattern_forms() {
+        assert!(matches("cargo  test ", "cargo test"));
+        assert!(!matches("git diff", "git diff --stat"));
+        assert!(matches("git diff*", "git diff --stat"));
+        assert!(matches("cargo test*", "cargo test --lib"));
+        assert!(matches("npm run *", "npm run"));
+        assert!(matches("npm run *", "npm run build"));
+        assert!(!matches("npm run *", "npm runner"));
+        assert!(matches("ls ?", "ls a"));
+        assert!(matches(r"echo \*", "echo *"));
+        assert!(!matches(r"echo \*", "echo hi"));
+        assert!(matches("re:git (status|diff)( .*)?", "git diff --stat"));
+        assert!(!matches("re:git (status|diff)", "git push"));
+
+        let compound = AllowlistPattern::parse("make && make install").unwrap();
+        assert!(compound.matches_whole(" make && make install"));
+        assert!(!AllowlistPattern::parse("npm *")
+            .unwrap()
+            .matches_whole("npm *"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in ["", "  ", "*", "? foo", "re:(", "re:.*", "npm * | sh"] {
+            assert!(AllowlistPattern::parse(pattern).is_err(), "{pattern}");
+        }
+        assert!(AllowlistPattern::parse(&"a".repeat(MAX_PATTERN_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_from_legacy_keeps_meaning() {
+        assert_eq!(from_legacy("npm  *"), "npm *");
+        assert_eq!(from_legacy("ls -d ?"), r"ls -d \?");
+        assert_eq!(from_legacy("re:x"), r"\re:x");
+        assert!(matches(&from_legacy("re:x"), "re:x"));
+        assert!(matches(&from_legacy(r"echo C:\dir?"), r"echo C:\dir?"));
+    }
+}
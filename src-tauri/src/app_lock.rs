@@ -0,0 +1,302 @@
+//! Optional passphrase lock for shared machines.
+//!
+//! Only a salted PBKDF2 hash of the passphrase is kept, in the keychain.
+//! While the app is locked, `gate` rejects every command outside
+//! `UNLOCKED_COMMANDS` before it reaches its handler, so transcripts,
+//! settings and credentials stay out of the renderer's reach; turns already
+//! running carry on, but their events are held back from the renderer until
+//! the app unlocks. The app locks on launch and after `idleTimeoutMins`
+//! without user activity reported by the renderer.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::Sha256;
+use tauri::ipc::Invoke;
+use tauri::{Manager, Runtime};
+
+use crate::global_state::AppLockSettings;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+const KEYCHAIN_SERVICE: &str = "codex-desktop.app-lock";
+const KEYCHAIN_ACCOUNT: &str = "passphrase";
+
+/// Stored hashes read "<scheme>$<rounds>$<salt>$<hash>", base64 parts
+const HASH_SCHEME: &str = "pbkdf2-sha256";
+/// Rounds for new hashes; stored hashes keep the count they were made with
+const PBKDF2_ROUNDS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+const MIN_PASSPHRASE_CHARS: usize = 6;
+
+const MISSING_PASSPHRASE: &str = "The app lock passphrase is missing from the keychain. \
+     Quit the app and set appLock.enabled to false in codex-global-state.json to turn the lock off";
+
+/// Failed unlocks are answered after this many seconds per recent failure
+const MAX_UNLOCK_DELAY_SECS: u32 = 5;
+
+/// Commands the renderer may call while the app is locked
+#[rustfmt::skip]
+const UNLOCKED_COMMANDS: &[&str] = &[
+    "get_app_lock_status", "unlock_app", "lock_app", "report_user_activity",
+    "renderer_ready", "renderer_heartbeat", "get_safe_mode_reason",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub locked: bool,
+    #[serde(flatten)]
+    pub settings: AppLockSettings,
+}
+
+/// Whether the app is locked, and when the user was last active
+pub struct AppLock {
+    locked: AtomicBool,
+    last_activity: Mutex<Instant>,
+    failed_unlocks: AtomicU32,
+    /// Held for the whole of an unlock attempt, failure delay included
+    unlock_attempt: tokio::sync::Mutex<()>,
+}
+
+impl AppLock {
+    /// Locked from the start when the settings ask for it
+    pub fn new(settings: &AppLockSettings) -> Self {
+        Self {
+            locked: AtomicBool::new(settings.enabled && settings.lock_on_launch),
+            last_activity: Mutex::new(Instant::now()),
+            failed_unlocks: AtomicU32::new(0),
+            unlock_attempt: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the app was unlocked before
+    pub fn lock(&self) -> bool {
+        !self.locked.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn unlock(&self) {
+        self.failed_unlocks.store(0, Ordering::SeqCst);
+        self.touch();
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    /// Wait for earlier unlock attempts to finish; keep the guard until
+    /// this attempt has been answered, so attempts cannot run in parallel
+    /// to sidestep the failure delay
+    pub async fn begin_unlock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.unlock_attempt.lock().await
+    }
+
+    /// How long to hold back the answer to a failed unlock
+    pub fn record_failed_unlock(&self) -> Duration {
+        let failures = self.failed_unlocks.fetch_add(1, Ordering::SeqCst) + 1;
+        Duration::from_secs(u64::from(failures.min(MAX_UNLOCK_DELAY_SECS)))
+    }
+
+    /// Note user activity, postponing the idle lock
+    pub fn touch(&self) {
+        *self.last_activity.lock() = Instant::now();
+    }
+
+    /// Whether the idle timeout in `settings` has run out
+    pub fn idle_expired(&self, settings: &AppLockSettings) -> bool {
+        let Some(minutes) = settings.idle_timeout_mins.filter(|minutes| *minutes > 0) else {
+            return false;
+        };
+        settings.enabled
+            && !self.is_locked()
+            && self.last_activity.lock().elapsed() >= Duration::from_secs(u64::from(minutes) * 60)
+    }
+
+    pub fn status(&self, settings: AppLockSettings) -> AppLockStatus {
+        AppLockStatus {
+            locked: self.is_locked(),
+            settings,
+        }
+    }
+}
+
+/// Wrap the invoke handler so that, while the app is locked, commands
+/// outside `UNLOCKED_COMMANDS` are rejected with `Error::Locked`
+pub fn gate<R, F>(handler: F) -> impl Fn(Invoke<R>) -> bool + Send + Sync + 'static
+where
+    R: Runtime,
+    F: Fn(Invoke<R>) -> bool + Send + Sync + 'static,
+{
+    move |invoke| {
+        let locked = invoke
+            .message
+            .webview()
+            .try_state::<AppState>()
+            .is_some_and(|state| state.app_lock.is_locked());
+        if locked && !UNLOCKED_COMMANDS.contains(&invoke.message.command()) {
+            invoke.resolver.reject(Error::Locked);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+fn keychain_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| Error::Other(format!("Keychain unavailable: {e}")))
+}
+
+fn stored_hash(entry: &keyring::Entry) -> Result<Option<String>> {
+    match entry.get_password() {
+        Ok(hash) => Ok(Some(hash)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(Error::Other(format!(
+            "Failed to read app lock passphrase: {e}"
+        ))),
+    }
+}
+
+/// Whether `passphrase` is the stored one. Fails while none is stored (the
+/// keychain entry was removed): the lock stays on rather than letting any
+/// passphrase through. Blocking.
+pub fn verify_passphrase(passphrase: &str) -> Result<bool> {
+    match stored_hash(&keychain_entry()?)? {
+        Some(stored) => Ok(matches(&stored, passphrase)),
+        None => {
+            tracing::warn!("App lock passphrase is missing from the keychain");
+            Err(Error::Other(MISSING_PASSPHRASE.to_string()))
+        }
+    }
+}
+
+/// Store `passphrase` (or with `None`, remove the stored one) once
+/// `current` matches the stored passphrase, if there is one. Blocking.
+pub fn set_passphrase(current: Option<&str>, passphrase: Option<&str>) -> Result<()> {
+    let entry = keychain_entry()?;
+    if let Some(stored) = stored_hash(&entry)? {
+        if !current.is_some_and(|current| matches(&stored, current)) {
+            return Err(Error::Other("The current passphrase is wrong".to_string()));
+        }
+    }
+
+    let result = match passphrase {
+        Some(passphrase) => {
+            if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+                return Err(Error::Other(format!(
+                    "The passphrase must have at least {MIN_PASSPHRASE_CHARS} characters"
+                )));
+            }
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            entry.set_password(&hash_passphrase(passphrase, &salt, PBKDF2_ROUNDS))
+        }
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            result => result,
+        },
+    };
+    result.map_err(|e| Error::Other(format!("Failed to store app lock passphrase: {e}")))
+}
+
+fn hash_passphrase(passphrase: &str, salt: &[u8], rounds: u32) -> String {
+    let mut hash = [0u8; HASH_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut hash);
+    format!(
+        "{HASH_SCHEME}${rounds}${}${}",
+        STANDARD.encode(salt),
+        STANDARD.encode(hash)
+    )
+}
+
+fn matches(stored: &str, passphrase: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some(HASH_SCHEME), Some(rounds), Some(salt), Some(hash), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return false;
+    };
+    let (Ok(rounds), Ok(salt), Ok(expected)) = (
+        rounds.parse::<u32>(),
+        STANDARD.decode(salt),
+        STANDARD.decode(hash),
+    ) else {
+        return false;
+    };
+    if rounds == 0 || expected.len() != HASH_LEN {
+        return false;
+    }
+
+    let mut actual = [0u8; HASH_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), &salt, rounds, &mut actual);
+    // Constant time, so the comparison does not leak how much matched
+    actual
+        .iter()
+        .zip(&expected)
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passphrase_hash_round_trip() {
+        let stored = hash_passphrase("correct horse", b"0123456789abcdef", 1_000);
+        assert!(stored.starts_with("pbkdf2-sha256$1000$"));
+        assert!(matches(&stored, "correct horse"));
+        assert!(!matches(&stored, "correct horsE"));
+        assert!(!matches(&stored, ""));
+        assert!(!matches("pbkdf2-sha256$0$AAAA$AAAA", "correct horse"));
+        assert!(!matches("garbage", "correct horse"));
+    }
+
+    #[test]
+    fn test_idle_lock() {
+        let settings = AppLockSettings {
+            enabled: true,
+            lock_on_launch: false,
+            idle_timeout_mins: Some(1),
+        };
+        let lock = AppLock::new(&settings);
+        assert!(!lock.is_locked());
+        assert!(!lock.idle_expired(&settings));
+
+        *lock.last_activity.lock() = Instant::now() - Duration::from_secs(61);
+        assert!(lock.idle_expired(&settings));
+        assert!(!lock.idle_expired(&AppLockSettings {
+            idle_timeout_mins: None,
+            ..settings.clone()
+        }));
+        assert!(!lock.idle_expired(&AppLockSettings {
+            enabled: false,
+            ..settings.clone()
+        }));
+
+        assert!(lock.lock());
+        assert!(!lock.lock());
+        assert!(!lock.idle_expired(&settings));
+        assert_eq!(lock.record_failed_unlock(), Duration::from_secs(1));
+        assert_eq!(lock.record_failed_unlock(), Duration::from_secs(2));
+        lock.unlock();
+        assert!(!lock.is_locked());
+        assert_eq!(lock.record_failed_unlock(), Duration::from_secs(1));
+        assert!(AppLock::new(&AppLockSettings {
+            lock_on_launch: true,
+            ..settings
+        })
+        .is_locked());
+    }
+}
@@ -0,0 +1,168 @@
+//! Turns running concurrently across threads.
+//!
+//! The app-server runs turns on different threads in parallel but only one
+//! turn per thread. This registry tracks which threads have a turn in
+//! flight so `turn/start` can enforce that and the configured concurrency
+//! limits up front, and so turns can be listed and interrupted by thread.
+//! A slot is reserved before `turn/start` is sent and released when the
+//! turn completes (or the request fails).
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::global_state::TurnConcurrencySettings;
+use crate::{Error, Result};
+
+/// A turn in flight, as reported by `list_active_turns`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveTurn {
+    pub thread_id: String,
+    /// `None` until the app-server reports the turn as started
+    pub turn_id: Option<String>,
+    pub project_id: Option<String>,
+    /// Unix milliseconds when the turn was requested
+    pub requested_at: i64,
+}
+
+#[derive(Debug, Default)]
+pub struct ActiveTurns {
+    turns: Mutex<HashMap<String, ActiveTurn>>,
+}
+
+impl ActiveTurns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve a slot for a turn on `thread_id`
+    ///
+    /// Fails when the thread already has a turn running or a concurrency
+    /// limit is reached.
+    pub fn reserve(
+        &self,
+        thread_id: &str,
+        project_id: Option<&str>,
+        limits: &TurnConcurrencySettings,
+        now_ms: i64,
+    ) -> Result<()> {
+        let mut turns = self.turns.lock();
+        if turns.contains_key(thread_id) {
+            return Err(Error::Other(format!(
+                "Thread {thread_id} already has a running turn; interrupt it or wait for it to finish"
+            )));
+        }
+        if limits.max_concurrent_turns > 0 && turns.len() >= limits.max_concurrent_turns as usize {
+            return Err(Error::Other(format!(
+                "{} turns are already running (limit {})",
+                turns.len(),
+                limits.max_concurrent_turns
+            )));
+        }
+        let max = limits.max_turns_per_project;
+        if let Some(project_id) = project_id.filter(|_| max > 0) {
+            let in_project = turns
+                .values()
+                .filter(|turn| turn.project_id.as_deref() == Some(project_id))
+                .count();
+            if in_project >= max as usize {
+                return Err(Error::Other(format!(
+                    "{in_project} turns are already running in this project (limit {max})"
+                )));
+            }
+        }
+
+        turns.insert(
+            thread_id.to_string(),
+            ActiveTurn {
+                thread_id: thread_id.to_string(),
+                turn_id: None,
+                project_id: project_id.map(str::to_string),
+                requested_at: now_ms,
+            },
+        );
+        Ok(())
+    }
+
+    /// Record the turn id once the turn has started
+    ///
+    /// Turns started outside `reserve` (e.g. by another client of the same
+    /// app-server) are tracked too.
+    pub fn started(&self, thread_id: &str, turn_id: &str, now_ms: i64) {
+        self.turns
+            .lock()
+            .entry(thread_id.to_string())
+            .or_insert_with(|| ActiveTurn {
+                thread_id: thread_id.to_string(),
+                turn_id: None,
+                project_id: None,
+                requested_at: now_ms,
+            })
+            .turn_id = Some(turn_id.to_string());
+    }
+
+    /// Free a thread's slot (turn completed, or `turn/start` failed)
+    pub fn release(&self, thread_id: &str) -> bool {
+        self.turns.lock().remove(thread_id).is_some()
+    }
+
+    pub fn get(&self, thread_id: &str) -> Option<ActiveTurn> {
+        self.turns.lock().get(thread_id).cloned()
+    }
+
+    /// Running turns, oldest first
+    pub fn list(&self) -> Vec<ActiveTurn> {
+        let mut turns: Vec<ActiveTurn> = self.turns.lock().values().cloned().collect();
+        turns.sort_by_key(|turn| turn.requested_at);
+        turns
+    }
+
+    /// Forget every turn (e.g. after the app-server disconnects)
+    pub fn clear(&self) {
+        self.turns.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_enforces_limits() {
+        let turns = ActiveTurns::new();
+        let limits = TurnConcurrencySettings {
+            max_concurrent_turns: 3,
+            max_turns_per_project: 2,
+        };
+        turns.reserve("a", Some("p1"), &limits, 1).unwrap();
+        assert!(turns.reserve("a", Some("p1"), &limits, 2).is_err());
+        turns.reserve("b", Some("p1"), &limits, 2).unwrap();
+        assert!(turns.reserve("c", Some("p1"), &limits, 3).is_err());
+        turns.reserve("c", Some("p2"), &limits, 3).unwrap();
+        assert!(turns.reserve("d", None, &limits, 4).is_err());
+
+        turns.started("b", "turn-b", 5);
+        assert!(turns.release("a"));
+        let listed = turns.list();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].turn_id.as_deref(), Some("turn-b"));
+        turns.reserve("d", None, &limits, 6).unwrap();
+    }
+
+    #[test]
+    fn test_unlimited_when_zero() {
+        let turns = ActiveTurns::new();
+        let limits = TurnConcurrencySettings {
+            max_concurrent_turns: 0,
+            max_turns_per_project: 0,
+        };
+        for i in 0..20 {
+            turns
+                .reserve(&i.to_string(), Some("p"), &limits, i)
+                .unwrap();
+        }
+        assert_eq!(turns.list().len(), 20);
+    }
+}
@@ -0,0 +1,204 @@
+//! Detection of logins that stop working mid-session.
+//!
+//! Unauthorized `error` notifications, failed turns carrying an
+//! unauthorized error, `account/updated` notifications without an auth mode
+//! and periodic `account/read` polls all feed one tracker. The first sign
+//! that a working login is gone yields a `login-required` payload naming
+//! the login type to restart with `start_login`; a later successful login
+//! yields `login-restored`. Nothing is reported before a login was ever
+//! seen, since the renderer already shows its login screen then.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use super::ipc_bridge::AccountInfo;
+
+/// Login type offered when the lost login's type is unknown
+const DEFAULT_LOGIN_TYPE: &str = "chatgpt";
+
+/// Payload of `login-required`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginRequired {
+    /// "unauthorized" (a request was rejected) or "loggedOut"
+    pub reason: &'static str,
+    pub thread_id: Option<String>,
+    pub message: Option<String>,
+    /// Pass to `start_login`
+    pub login_type: String,
+    /// Whether `start_reauth` can restart the login without user input;
+    /// API key logins need a new key
+    pub can_reauth: bool,
+}
+
+#[derive(Debug, Default)]
+struct AuthState {
+    /// Type of the last working login ("chatgpt" or "apiKey")
+    login_type: Option<String>,
+    required: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct AuthMonitor {
+    state: Mutex<AuthState>,
+}
+
+impl AuthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Type of login to restart with
+    pub fn login_type(&self) -> String {
+        self.state
+            .lock()
+            .login_type
+            .clone()
+            .unwrap_or_else(|| DEFAULT_LOGIN_TYPE.to_string())
+    }
+
+    /// A request failed as unauthorized; `Some` the first time
+    pub fn unauthorized(
+        &self,
+        thread_id: Option<String>,
+        message: Option<String>,
+    ) -> Option<LoginRequired> {
+        self.require("unauthorized", thread_id, message)
+    }
+
+    /// The app-server reports no login; `Some` if one was working
+    pub fn logged_out(&self) -> Option<LoginRequired> {
+        self.require("loggedOut", None, None)
+    }
+
+    /// A login works (again); returns whether one was required
+    pub fn logged_in(&self, login_type: Option<&str>) -> bool {
+        let mut state = self.state.lock();
+        if let Some(login_type) = login_type {
+            state.login_type = Some(login_type.to_string());
+        } else if state.login_type.is_none() {
+            state.login_type = Some(DEFAULT_LOGIN_TYPE.to_string());
+        }
+        std::mem::take(&mut state.required)
+    }
+
+    /// Apply an `account/read` result; returns (newly required, restored)
+    pub fn observe_account(&self, info: &AccountInfo) -> (Option<LoginRequired>, bool) {
+        match &info.account {
+            Some(account) => (None, self.logged_in(Some(&account.account_type))),
+            None if info.requires_openai_auth => (self.logged_out(), false),
+            None => (None, false),
+        }
+    }
+
+    fn require(
+        &self,
+        reason: &'static str,
+        thread_id: Option<String>,
+        message: Option<String>,
+    ) -> Option<LoginRequired> {
+        let mut state = self.state.lock();
+        let login_type = state.login_type.clone()?;
+        if state.required {
+            return None;
+        }
+        state.required = true;
+        Some(LoginRequired {
+            reason,
+            thread_id,
+            message,
+            can_reauth: login_type != "apiKey",
+            login_type,
+        })
+    }
+}
+
+/// Whether an app-server error object (an `error` notification's or a
+/// failed turn's `error`) means the login was rejected
+pub fn is_unauthorized_error(error: &JsonValue) -> bool {
+    let info = error.get("codexErrorInfo");
+    let info_says = match info {
+        Some(JsonValue::String(kind)) => kind.eq_ignore_ascii_case("unauthorized"),
+        Some(JsonValue::Object(object)) => object.keys().any(|key| key == "unauthorized"),
+        _ => false,
+    };
+    // The status sits in the error kind's details, e.g.
+    // `{ "responseStreamFailed": { "httpStatusCode": 401 } }`
+    let status = info.and_then(|info| {
+        info.get("httpStatusCode")
+            .and_then(JsonValue::as_u64)
+            .or_else(|| {
+                info.as_object()?
+                    .values()
+                    .find_map(|details| details.get("httpStatusCode")?.as_u64())
+            })
+    });
+    info_says
+        || status == Some(401)
+        || error
+            .get("message")
+            .and_then(JsonValue::as_str)
+            .is_some_and(|message| message.contains("401 Unauthorized"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_server::ipc_bridge::AccountDetails;
+
+    fn account(account_type: &str) -> AccountInfo {
+        AccountInfo {
+            account: Some(AccountDetails {
+                account_type: account_type.to_string(),
+                email: None,
+                plan_type: None,
+            }),
+            requires_openai_auth: true,
+        }
+    }
+
+    #[test]
+    fn test_reports_once_per_lost_login() {
+        let monitor = AuthMonitor::new();
+        // Never logged in: the login screen is already showing
+        assert!(monitor.unauthorized(None, None).is_none());
+
+        assert_eq!(monitor.observe_account(&account("chatgpt")), (None, false));
+        let required = monitor
+            .unauthorized(Some("t1".to_string()), Some("401 Unauthorized".to_string()))
+            .unwrap();
+        assert_eq!(required.reason, "unauthorized");
+        assert_eq!(required.login_type, "chatgpt");
+        assert!(required.can_reauth);
+        assert!(monitor.logged_out().is_none());
+
+        assert_eq!(monitor.observe_account(&account("apiKey")), (None, true));
+        let logged_out = AccountInfo {
+            account: None,
+            requires_openai_auth: true,
+        };
+        let (required, restored) = monitor.observe_account(&logged_out);
+        assert!(!restored);
+        let required = required.unwrap();
+        assert_eq!(required.reason, "loggedOut");
+        assert!(!required.can_reauth);
+    }
+
+    #[test]
+    fn test_is_unauthorized_error() {
+        let cases = [
+            (serde_json::json!({ "codexErrorInfo": "unauthorized" }), true),
+            (serde_json::json!({ "codexErrorInfo": { "unauthorized": null } }), true),
+            (
+                serde_json::json!({ "codexErrorInfo": { "responseStreamFailed": { "httpStatusCode": 401 } } }),
+                true,
+            ),
+            (serde_json::json!({ "message": "unexpected status 401 Unauthorized: token expired" }), true),
+            (serde_json::json!({ "codexErrorInfo": "usageLimitExceeded" }), false),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(is_unauthorized_error(&error), expected, "{error}");
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Degraded mode when the app-server cannot be started.
+//!
+//! A failed spawn or handshake marks the server degraded. Features that
+//! need it fail fast with the recorded reason instead of each waiting out
+//! another handshake, while database- and git-backed features keep working.
+//! A new start is attempted once `RETRY_AFTER` has passed since the last
+//! failure.
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::global_state::unix_timestamp_millis;
+
+/// Minimum time between start attempts while degraded
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// Why the app-server is unavailable, as reported to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DegradedInfo {
+    pub reason: String,
+    /// Unix milliseconds of the first failure
+    pub since: i64,
+    /// Start attempts that failed in a row
+    pub failed_attempts: u32,
+    /// Unix milliseconds after which the next start is attempted
+    pub retry_at: i64,
+}
+
+#[derive(Debug)]
+struct Degraded {
+    info: DegradedInfo,
+    /// `None` once the backoff was reset
+    last_attempt: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct ServerAvailability {
+    degraded: Mutex<Option<Degraded>>,
+}
+
+impl ServerAvailability {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a failed start; returns the updated degraded state
+    pub fn mark_failed(&self, reason: &str) -> DegradedInfo {
+        let now_ms = unix_timestamp_millis();
+        let mut degraded = self.degraded.lock();
+        let (since, failed_attempts) = degraded
+            .as_ref()
+            .map_or((now_ms, 1), |d| (d.info.since, d.info.failed_attempts + 1));
+        let info = DegradedInfo {
+            reason: reason.to_string(),
+            since,
+            failed_attempts,
+            retry_at: now_ms + RETRY_AFTER.as_millis() as i64,
+        };
+        *degraded = Some(Degraded {
+            info: info.clone(),
+            last_attempt: Some(Instant::now()),
+        });
+        info
+    }
+
+    /// Record a successful start; returns whether the server was degraded
+    pub fn mark_available(&self) -> bool {
+        self.degraded.lock().take().is_some()
+    }
+
+    pub fn degraded(&self) -> Option<DegradedInfo> {
+        self.degraded.lock().as_ref().map(|d| d.info.clone())
+    }
+
+    /// The degraded state, if a start should not be attempted yet
+    pub fn should_skip_start(&self) -> Option<DegradedInfo> {
+        self.degraded
+            .lock()
+            .as_ref()
+            .filter(|d| d.last_attempt.is_some_and(|at| at.elapsed() < RETRY_AFTER))
+            .map(|d| d.info.clone())
+    }
+
+    /// Allow the next start immediately (e.g. on an explicit restart)
+    pub fn reset_backoff(&self) {
+        if let Some(degraded) = self.degraded.lock().as_mut() {
+            degraded.last_attempt = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degraded_lifecycle() {
+        let availability = ServerAvailability::new();
+        assert!(availability.should_skip_start().is_none());
+
+        let first = availability.mark_failed("handshake timed out");
+        let second = availability.mark_failed("spawn failed");
+        assert_eq!(second.failed_attempts, 2);
+        assert_eq!(second.since, first.since);
+        assert_eq!(second.reason, "spawn failed");
+        assert!(availability.should_skip_start().is_some());
+
+        availability.reset_backoff();
+        assert!(availability.should_skip_start().is_none());
+        assert!(availability.degraded().is_some());
+
+        assert!(availability.mark_available());
+        assert!(!availability.mark_available());
+        assert!(availability.degraded().is_none());
+    }
+}
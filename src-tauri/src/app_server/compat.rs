@@ -0,0 +1,229 @@
+//! Codex CLI version compatibility.
+//!
+//! The GUI speaks the app-server protocol of a range of CLI releases. Older
+//! CLIs lack methods the GUI relies on, and releases past the tested range
+//! may have changed the protocol, so the version is checked at startup and
+//! the frontend is told how to upgrade when it falls outside the range.
+
+use std::cmp::Ordering;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::installations;
+use crate::global_state::AppServerLaunchSettings;
+use crate::{Error, Result};
+
+/// Oldest CLI release whose app-server protocol the GUI supports
+pub const MIN_SUPPORTED_VERSION: &str = "0.46.0";
+
+/// First CLI release past the tested range (exclusive)
+pub const MAX_SUPPORTED_VERSION: &str = "1.0.0";
+
+/// Installers taking longer than this are killed
+const UPGRADE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Result of `check_codex_version`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexVersionCheck {
+    /// "ok", "tooOld", "tooNew", "unknown" (version could not be read) or
+    /// "notFound" (no binary)
+    pub status: String,
+    pub compatible: bool,
+    pub version: Option<String>,
+    pub binary_path: Option<String>,
+    /// "homebrew", "npm", "cargo" or "other"
+    pub install_source: Option<String>,
+    pub min_version: String,
+    pub max_version: String,
+    /// Human-readable summary with upgrade instructions
+    pub message: String,
+    /// Shell command that upgrades (or installs) the CLI, when known
+    pub upgrade_command: Option<String>,
+    /// Whether `upgrade_codex` can run `upgrade_command` itself
+    pub can_auto_upgrade: bool,
+}
+
+/// Numeric release components and whether the version is a pre-release
+fn version_key(version: &str) -> (Vec<u64>, bool) {
+    let version = version.trim().trim_start_matches('v');
+    // "-" starts a pre-release, "+" build metadata (which does not count)
+    let (release, pre) = match version.find(['-', '+']) {
+        Some(i) => (&version[..i], version[i..].starts_with('-')),
+        None => (version, false),
+    };
+    let parts = release
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    (parts, pre)
+}
+
+/// Compare two versions; missing components count as zero and a
+/// pre-release sorts before its release
+pub(crate) fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_parts, a_pre) = version_key(a);
+    let (b_parts, b_pre) = version_key(b);
+    let len = a_parts.len().max(b_parts.len());
+    for i in 0..len {
+        let ordering = a_parts
+            .get(i)
+            .unwrap_or(&0)
+            .cmp(b_parts.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    // `true` (pre-release) sorts first
+    b_pre.cmp(&a_pre)
+}
+
+/// "ok", "tooOld" or "tooNew" for a parsed version
+pub(crate) fn version_status(version: &str) -> &'static str {
+    if compare_versions(version, MIN_SUPPORTED_VERSION) == Ordering::Less {
+        "tooOld"
+    } else if compare_versions(version, MAX_SUPPORTED_VERSION) != Ordering::Less {
+        "tooNew"
+    } else {
+        "ok"
+    }
+}
+
+/// Command that upgrades an installation from `source`
+///
+/// Returns the command and whether it is safe to run unattended.
+fn upgrade_command(source: &str) -> (&'static str, bool) {
+    match source {
+        "homebrew" => ("brew upgrade codex", true),
+        "npm" => ("npm install -g @openai/codex@latest", true),
+        // Unknown installs (e.g. a downloaded release) are not ours to replace
+        _ => ("npm install -g @openai/codex@latest", false),
+    }
+}
+
+fn install_source(path: &Path) -> &'static str {
+    let resolved = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    installations::classify_source(path, &resolved)
+}
+
+/// Check the binary the current launch settings select
+pub async fn check(settings: &AppServerLaunchSettings) -> CodexVersionCheck {
+    let mut check = CodexVersionCheck {
+        status: "notFound".to_string(),
+        compatible: false,
+        version: None,
+        binary_path: None,
+        install_source: None,
+        min_version: MIN_SUPPORTED_VERSION.to_string(),
+        max_version: MAX_SUPPORTED_VERSION.to_string(),
+        message: String::new(),
+        upgrade_command: None,
+        can_auto_upgrade: false,
+    };
+
+    let path = match installations::resolve_codex_binary(settings).await {
+        Ok(path) => path,
+        Err(err) => {
+            let (command, _) = upgrade_command("other");
+            check.message = format!("Codex CLI not available ({err}). Install it with: {command}");
+            check.upgrade_command = Some(command.to_string());
+            return check;
+        }
+    };
+    let source = install_source(&path);
+    let (command, can_auto_upgrade) = upgrade_command(source);
+    check.binary_path = Some(path.to_string_lossy().into_owned());
+    check.install_source = Some(source.to_string());
+    check.version = installations::version_of(&path).await;
+
+    let status = check.version.as_deref().map_or("unknown", version_status);
+    check.status = status.to_string();
+    // An unreadable version is let through; the app-server handshake is the
+    // real test
+    check.compatible = matches!(status, "ok" | "unknown");
+    check.message = match status {
+        "ok" => format!(
+            "Codex CLI {} is supported",
+            check.version.as_deref().unwrap_or_default()
+        ),
+        "tooOld" => format!(
+            "Codex CLI {} is older than the minimum supported version {MIN_SUPPORTED_VERSION}. Upgrade with: {command}",
+            check.version.as_deref().unwrap_or_default()
+        ),
+        "tooNew" => format!(
+            "Codex CLI {} is newer than the versions this app was tested with (below {MAX_SUPPORTED_VERSION}); some features may not work. Check for an app update, or pin an older CLI version in the app-server settings",
+            check.version.as_deref().unwrap_or_default()
+        ),
+        _ => format!("Could not determine the version of {}", path.display()),
+    };
+    // Upgrading cannot fix a CLI that is too new
+    if status != "tooNew" {
+        check.upgrade_command = Some(command.to_string());
+        check.can_auto_upgrade = can_auto_upgrade;
+    }
+    check
+}
+
+/// Upgrade the selected CLI with its package manager, returning the
+/// installer's output
+pub async fn upgrade(settings: &AppServerLaunchSettings) -> Result<String> {
+    let path = installations::resolve_codex_binary(settings).await?;
+    let (command, can_auto_upgrade) = upgrade_command(install_source(&path));
+    if !can_auto_upgrade {
+        return Err(Error::AppServer(format!(
+            "{} was not installed with Homebrew or npm; upgrade it manually",
+            path.display()
+        )));
+    }
+
+    let mut parts = command.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let installer = which::which(program)
+        .map_err(|_| Error::AppServer(format!("{program} not found on PATH")))?;
+    tracing::info!("Upgrading codex CLI: {}", command);
+    let output = tokio::time::timeout(
+        UPGRADE_TIMEOUT,
+        tokio::process::Command::new(installer)
+            .args(parts)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| Error::Timeout(format!("`{command}`")))??;
+
+    let text = [output.stdout, output.stderr]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !output.status.success() {
+        return Err(Error::AppServer(format!("`{command}` failed: {text}")));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.46.0", "0.46"), Ordering::Equal);
+        assert_eq!(compare_versions("0.9.0", "0.46.0"), Ordering::Less);
+        assert_eq!(compare_versions("v1.2.0", "1.10.0"), Ordering::Less);
+        assert_eq!(compare_versions("0.47.0-alpha.1", "0.47.0"), Ordering::Less);
+        assert_eq!(compare_versions("0.47.0+build", "0.47.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_version_status() {
+        assert_eq!(version_status("0.45.9"), "tooOld");
+        assert_eq!(version_status("0.46.0-alpha.2"), "tooOld");
+        assert_eq!(version_status(MIN_SUPPORTED_VERSION), "ok");
+        assert_eq!(version_status("0.99.3"), "ok");
+        assert_eq!(version_status(MAX_SUPPORTED_VERSION), "tooNew");
+    }
+}
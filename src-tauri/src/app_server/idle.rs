@@ -0,0 +1,137 @@
+//! Stopping the app-server while nothing uses it.
+//!
+//! With `idleShutdownMins` set, the supervisor stops the app-server once no
+//! turn, approval or request has been in flight for that long, freeing its
+//! memory. The next thread command starts it again; since a fresh process
+//! has no threads loaded, a turn on a thread from before the stop resumes
+//! that thread first. The disconnect caused by an idle stop is not treated
+//! as a crash and is counted apart from restarts, and neither is a
+//! disconnect from an earlier process that arrives after the next started.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Longest accepted idle timeout
+pub const MAX_IDLE_SHUTDOWN_MINS: u32 = 24 * 60;
+
+#[derive(Debug)]
+pub struct IdleShutdown {
+    last_activity: Mutex<Instant>,
+    /// The app-server was stopped for being idle and not started since
+    stopped: AtomicBool,
+    /// Threads resumed on a process started after an idle stop; `None` when
+    /// the running process replaced no idle-stopped one
+    resumed: Mutex<Option<HashSet<String>>>,
+    /// `AppServerProcess::generation` of the newest process
+    generation: AtomicU64,
+}
+
+impl Default for IdleShutdown {
+    fn default() -> Self {
+        Self {
+            last_activity: Mutex::new(Instant::now()),
+            stopped: AtomicBool::new(false),
+            resumed: Mutex::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+}
+
+impl IdleShutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the app-server is in use
+    pub fn touch(&self, now: Instant) {
+        *self.last_activity.lock() = now;
+    }
+
+    /// Whether the app-server has been unused for `minutes`; never when
+    /// `minutes` is unset or 0
+    pub fn is_due(&self, minutes: Option<u32>, now: Instant) -> bool {
+        let Some(minutes) = minutes.filter(|minutes| *minutes > 0) else {
+            return false;
+        };
+        now.saturating_duration_since(*self.last_activity.lock())
+            >= Duration::from_secs(u64::from(minutes) * 60)
+    }
+
+    pub fn mark_stopped(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Record a newly spawned process; returns whether it replaces one that
+    /// was stopped for being idle
+    pub fn process_started(&self, generation: u64) -> bool {
+        self.generation.store(generation, Ordering::SeqCst);
+        let recycled = self.stopped.swap(false, Ordering::SeqCst);
+        *self.resumed.lock() = recycled.then(HashSet::new);
+        recycled
+    }
+
+    /// Whether the process of `generation` was replaced by a newer one, so
+    /// its events are stale
+    pub fn is_replaced(&self, generation: u64) -> bool {
+        generation < self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Whether `thread_id` has to be resumed before it can take a turn
+    pub fn needs_resume(&self, thread_id: &str) -> bool {
+        self.resumed
+            .lock()
+            .as_ref()
+            .is_some_and(|resumed| !resumed.contains(thread_id))
+    }
+
+    pub fn mark_resumed(&self, thread_id: &str) {
+        if let Some(resumed) = self.resumed.lock().as_mut() {
+            resumed.insert(thread_id.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_shutdown() {
+        let idle = IdleShutdown::new();
+        let start = Instant::now();
+        idle.touch(start);
+        let later = start + Duration::from_secs(10 * 60);
+        assert!(!idle.is_due(None, later));
+        assert!(!idle.is_due(Some(0), later));
+        assert!(!idle.is_due(Some(11), later));
+        assert!(idle.is_due(Some(10), later));
+
+        // A process started without an idle stop has its threads loaded
+        assert!(!idle.process_started(1));
+        assert!(!idle.needs_resume("t1"));
+        assert!(!idle.is_replaced(1));
+
+        idle.mark_stopped();
+        assert!(idle.is_stopped());
+        assert!(idle.process_started(2));
+        assert!(idle.is_replaced(1));
+        assert!(!idle.is_replaced(2));
+        // Not yet recorded as started
+        assert!(!idle.is_replaced(3));
+        assert!(!idle.is_stopped());
+        assert!(idle.needs_resume("t1"));
+        idle.mark_resumed("t1");
+        assert!(!idle.needs_resume("t1"));
+        assert!(idle.needs_resume("t2"));
+
+        assert!(!idle.process_started(3));
+        assert!(!idle.needs_resume("t2"));
+    }
+}
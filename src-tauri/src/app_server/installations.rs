@@ -0,0 +1,310 @@
+//! Locating the codex binary the app-server is spawned from.
+//!
+//! By default the first `codex` on PATH (or in a common install location) is
+//! used. Users with several Codex versions can point the GUI at a specific
+//! binary, or pin a version and let the GUI pick the installation that
+//! reports it.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::MAX_IDLE_SHUTDOWN_MINS;
+use crate::global_state::AppServerLaunchSettings;
+use crate::{Error, Result};
+
+/// `codex --version` taking longer than this is treated as unknown
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A codex binary found by `detect_installations`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexInstallation {
+    pub path: String,
+    /// The binary after following symlinks
+    pub resolved_path: String,
+    /// "path", "homebrew", "npm", "cargo" or "other"
+    pub source: String,
+    /// Parsed from `codex --version`; `None` when it failed
+    pub version: Option<String>,
+    /// The binary the app-server is spawned from with the current settings
+    pub selected: bool,
+}
+
+#[cfg(windows)]
+const BINARY_NAME: &str = "codex.exe";
+#[cfg(not(windows))]
+const BINARY_NAME: &str = "codex";
+
+/// Install locations checked in addition to PATH
+fn known_locations() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("/opt/homebrew/bin"),
+        PathBuf::from("/usr/local/bin"),
+        PathBuf::from("/home/linuxbrew/.linuxbrew/bin"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        dirs.extend([
+            home.join(".cargo/bin"),
+            home.join(".local/bin"),
+            home.join(".npm-global/bin"),
+            home.join(".volta/bin"),
+            home.join(".bun/bin"),
+        ]);
+        // Each nvm-managed Node version has its own global packages
+        if let Ok(versions) = std::fs::read_dir(home.join(".nvm/versions/node")) {
+            dirs.extend(versions.flatten().map(|entry| entry.path().join("bin")));
+        }
+    }
+    if let Some(appdata) = std::env::var_os("APPDATA") {
+        dirs.push(PathBuf::from(appdata).join("npm"));
+    }
+    dirs.into_iter().map(|dir| dir.join(BINARY_NAME)).collect()
+}
+
+pub(crate) fn classify_source(path: &Path, resolved: &Path) -> &'static str {
+    let text = format!("{} {}", path.display(), resolved.display());
+    if text.contains("node_modules")
+        || text.contains(".nvm")
+        || text.contains(".npm")
+        || text.contains("volta")
+    {
+        "npm"
+    } else if text.contains("homebrew") || text.contains("Cellar") || text.contains("linuxbrew") {
+        "homebrew"
+    } else if text.contains(".cargo") {
+        "cargo"
+    } else {
+        "other"
+    }
+}
+
+/// The version number in `codex --version` output, e.g. "0.46.0" from
+/// "codex-cli 0.46.0"
+pub(crate) fn parse_version(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| {
+            word.split('.').count() >= 2
+                && word.chars().next().is_some_and(|c| c.is_ascii_digit())
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+        })
+        .map(str::to_string)
+}
+
+/// Whether `version` satisfies a pin; "0.46" pins every 0.46.x release
+pub(crate) fn version_matches(version: &str, pinned: &str) -> bool {
+    let pinned = pinned.trim().trim_start_matches('v');
+    version == pinned || version.starts_with(&format!("{pinned}."))
+}
+
+/// Raw `codex --version` output of a binary
+pub async fn version_output(path: &Path) -> Option<String> {
+    let output = tokio::time::timeout(
+        VERSION_TIMEOUT,
+        tokio::process::Command::new(path)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    [output.stdout, output.stderr]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .find(|text| !text.is_empty())
+}
+
+pub(crate) async fn version_of(path: &Path) -> Option<String> {
+    version_output(path)
+        .await
+        .as_deref()
+        .and_then(parse_version)
+}
+
+/// Every codex binary on PATH or in a known install location (plus
+/// `extra`), deduplicated by resolved path; `selected` is left unset
+async fn find_installations(extra: Option<&str>) -> Vec<CodexInstallation> {
+    let mut candidates: Vec<(PathBuf, &'static str)> = which::which_all("codex")
+        .map(|paths| paths.map(|path| (path, "path")).collect())
+        .unwrap_or_default();
+    candidates.extend(known_locations().into_iter().map(|path| (path, "other")));
+    if let Some(extra) = extra {
+        candidates.push((PathBuf::from(extra), "other"));
+    }
+
+    let mut seen = HashSet::new();
+    let mut installations = Vec::new();
+    for (path, source) in candidates {
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(resolved) = std::fs::canonicalize(&path) else {
+            continue;
+        };
+        if !seen.insert(resolved.clone()) {
+            continue;
+        }
+        let source = match (source, classify_source(&path, &resolved)) {
+            ("path", "other") => "path",
+            (_, detected) => detected,
+        };
+        installations.push(CodexInstallation {
+            path: path.to_string_lossy().into_owned(),
+            resolved_path: resolved.to_string_lossy().into_owned(),
+            source: source.to_string(),
+            version: version_of(&path).await,
+            selected: false,
+        });
+    }
+    installations
+}
+
+/// Installed codex binaries, with the one the current settings select marked
+pub async fn detect_installations(settings: &AppServerLaunchSettings) -> Vec<CodexInstallation> {
+    let selected = resolve_codex_binary(settings)
+        .await
+        .ok()
+        .and_then(|path| std::fs::canonicalize(path).ok());
+    let mut installations = find_installations(settings.codex_path.as_deref()).await;
+    for installation in &mut installations {
+        installation.selected = selected.as_deref() == Some(Path::new(&installation.resolved_path));
+    }
+    installations
+}
+
+/// The binary to spawn the app-server from
+///
+/// An explicit `codex_path` wins; otherwise a pinned version selects the
+/// first detected installation reporting it, and without either the first
+/// `codex` on PATH is used. A binary not matching the pin is an error rather
+/// than silently driving another version.
+pub async fn resolve_codex_binary(settings: &AppServerLaunchSettings) -> Result<PathBuf> {
+    let pinned = settings
+        .pinned_version
+        .as_deref()
+        .map(str::trim)
+        .filter(|pin| !pin.is_empty());
+
+    let path = match &settings.codex_path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if !path.is_file() {
+                return Err(Error::AppServer(format!(
+                    "Configured codex binary not found: {}",
+                    path.display()
+                )));
+            }
+            path
+        }
+        None => match pinned {
+            Some(pin) => find_installations(None)
+                .await
+                .into_iter()
+                .find(|installation| {
+                    installation
+                        .version
+                        .as_deref()
+                        .is_some_and(|version| version_matches(version, pin))
+                })
+                .map(|installation| PathBuf::from(installation.path))
+                .ok_or_else(|| {
+                    Error::AppServer(format!("No codex installation with version {pin} found"))
+                })?,
+            None => super::AppServerProcess::find_codex_binary()?,
+        },
+    };
+
+    if let Some(pin) = pinned {
+        match version_of(&path).await {
+            Some(version) if version_matches(&version, pin) => {}
+            version => {
+                return Err(Error::AppServer(format!(
+                    "{} is codex {}, but the app is pinned to {pin}",
+                    path.display(),
+                    version.as_deref().unwrap_or("of an unknown version")
+                )))
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Reject settings that could not be used to spawn the app-server
+pub fn validate_settings(settings: &AppServerLaunchSettings) -> Result<()> {
+    if let Some(path) = &settings.codex_path {
+        if !Path::new(path).is_absolute() {
+            return Err(Error::InvalidPath(format!(
+                "Codex path must be absolute: {path}"
+            )));
+        }
+    }
+    if settings.extra_args.iter().any(|arg| arg.contains('\0')) {
+        return Err(Error::Other(
+            "Arguments cannot contain NUL characters".to_string(),
+        ));
+    }
+    for (name, value) in &settings.env {
+        if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+            return Err(Error::Other(format!(
+                "Invalid environment variable: {name}"
+            )));
+        }
+    }
+    if settings
+        .idle_shutdown_mins
+        .is_some_and(|minutes| minutes > MAX_IDLE_SHUTDOWN_MINS)
+    {
+        return Err(Error::Other(format!(
+            "Idle shutdown must be at most {MAX_IDLE_SHUTDOWN_MINS} minutes"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("codex-cli 0.46.0").as_deref(), Some("0.46.0"));
+        assert_eq!(
+            parse_version("codex v1.2.3-alpha.1\n").as_deref(),
+            Some("1.2.3-alpha.1")
+        );
+        assert_eq!(parse_version("codex-cli"), None);
+    }
+
+    #[test]
+    fn test_version_matches_pin() {
+        assert!(version_matches("0.46.0", "0.46.0"));
+        assert!(version_matches("0.46.2", "0.46"));
+        assert!(version_matches("0.46.2", "v0.46"));
+        assert!(!version_matches("0.460.0", "0.46"));
+        assert!(!version_matches("0.47.0", "0.46"));
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let mut settings = AppServerLaunchSettings::default();
+        assert!(validate_settings(&settings).is_ok());
+        settings.codex_path = Some("relative/codex".to_string());
+        assert!(validate_settings(&settings).is_err());
+        settings.codex_path = None;
+        settings.env.insert("A=B".to_string(), "1".to_string());
+        assert!(validate_settings(&settings).is_err());
+        settings.env.clear();
+        settings.idle_shutdown_mins = Some(MAX_IDLE_SHUTDOWN_MINS + 1);
+        assert!(validate_settings(&settings).is_err());
+    }
+}
@@ -108,7 +108,10 @@ pub struct ThreadInfo {
 }
 
 /// Thread resume parameters
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// The model, policies and config apply when the thread is loaded into
+/// the app-server, not to a thread it already has loaded.
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ThreadResumeParams {
     pub thread_id: String,
@@ -118,6 +121,14 @@ pub struct ThreadResumeParams {
     /// Pagination cursor for fetching next page
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<JsonValue>,
 }
 
 /// Thread resume response
@@ -261,6 +272,13 @@ pub struct TurnInterruptParams {
     pub turn_id: Option<String>,
 }
 
+/// Thread archive parameters
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadArchiveParams {
+    pub thread_id: String,
+}
+
 /// Execpolicy amendment for persistent approvals
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -3,13 +3,82 @@
 //! This module handles spawning, monitoring, and communicating with the
 //! Codex app-server subprocess via JSON-RPC 2.0 over stdio.
 
+mod active_turns;
+mod auth_monitor;
+mod availability;
+mod idle;
+pub mod compat;
+pub mod installations;
 pub mod ipc_bridge;
 mod process;
+mod rate_limits;
+mod rpc_trace;
+mod transcript_journal;
+mod turn_budgets;
+mod turn_waiters;
 
+use crate::pending_approvals::PendingApproval;
+
+pub use active_turns::{ActiveTurn, ActiveTurns};
+pub use auth_monitor::{AuthMonitor, LoginRequired};
+pub use availability::{DegradedInfo, ServerAvailability};
+pub use idle::{IdleShutdown, MAX_IDLE_SHUTDOWN_MINS};
 pub use ipc_bridge::IpcBridge;
 pub use process::AppServerProcess;
+pub use rate_limits::{QueuedTurn, QueuedTurnInfo, RateLimitForecast, RateLimitTracker};
+pub use rpc_trace::{RpcDirection, RpcTrace, RpcTraceSnapshot};
+pub use transcript_journal::TranscriptJournal;
+pub use turn_budgets::{TurnBudgetExceeded, TurnBudgets};
+pub use turn_waiters::TurnWaiters;
 
 #[derive(Debug, Clone)]
 pub enum AppServerEvent {
-    Disconnected { reason: String },
+    /// The process with `AppServerProcess::generation` `generation` exited
+    Disconnected { reason: String, generation: u64 },
+    /// The app-server began running a turn
+    TurnStarted { thread_id: String, turn_id: String },
+    /// A turn finished; `status` is the app-server turn status (e.g. "completed")
+    TurnCompleted {
+        thread_id: String,
+        turn_id: String,
+        status: String,
+        /// The turn's error message, if it reported one
+        error: Option<String>,
+        /// When the oldest approval still unanswered at the end was requested
+        /// (Unix milliseconds)
+        approval_pending_since: Option<i64>,
+    },
+    /// A tool item began running (other item types are not forwarded)
+    ItemStarted { thread_id: String, item: serde_json::Value },
+    /// A message or tool item finished (other item types are not forwarded)
+    ItemCompleted { thread_id: String, item: serde_json::Value },
+    /// A model call finished; `usage` is the app-server `tokenUsage` object
+    /// with the thread's running `total` and the call's `last` counts
+    TokenUsageUpdated {
+        thread_id: String,
+        turn_id: String,
+        usage: serde_json::Value,
+    },
+    /// An `account/rateLimits/updated` notification
+    RateLimitsUpdated { rate_limits: serde_json::Value },
+    /// A request or turn failed because the login was rejected
+    Unauthorized {
+        thread_id: Option<String>,
+        message: Option<String>,
+    },
+    /// `account/updated` or a finished login; `login_type` is `None` when
+    /// unknown or, with `logged_in` false, when logged out
+    LoginStateChanged {
+        logged_in: bool,
+        login_type: Option<String>,
+    },
+    /// An approval request handed to the renderer
+    ApprovalRequested { approval: PendingApproval },
+    /// A command approval request the project's approval preset allows;
+    /// `params` are the request params with `_requestId` and `_commandContext`
+    ApprovalAutoApproved {
+        request_id: u64,
+        reason: String,
+        params: serde_json::Value,
+    },
 }
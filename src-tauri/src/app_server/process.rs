@@ -24,14 +24,25 @@ const PENDING_CLEANUP_THRESHOLD: usize = 800;
 /// Maximum age for a pending request before it's considered stale (in seconds)
 const STALE_REQUEST_AGE_SECS: u64 = 60;
 
+/// Time allowed for the `initialize` handshake before the spawn is abandoned
+const HANDSHAKE_TIMEOUT_SECS: u64 = 20;
+
+/// Generation of the next spawned process
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
 /// Pending request entry with timestamp for cleanup
 struct PendingRequest {
     sender: oneshot::Sender<Result<JsonValue>>,
     created_at: Instant,
 }
 
-use crate::app_server::AppServerEvent;
+use crate::app_server::{
+    auth_monitor, AppServerEvent, RpcDirection, RpcTrace, TranscriptJournal,
+};
+use crate::approvals::ApprovalContext;
 use crate::events::AppEventEmitter;
+use crate::global_state::AppServerLaunchSettings;
+use crate::pending_approvals::{is_approval_request, PendingApproval};
 use crate::{Error, Result};
 
 /// JSON-RPC request structure (without jsonrpc header as per app-server protocol)
@@ -62,12 +73,16 @@ struct JsonRpcError {
 }
 
 /// Manages the codex app-server subprocess
+///
+/// Requests take `&self` so callers holding a read lock can have several in
+/// flight at once (e.g. turns on different threads); writes to stdin are
+/// serialized per message.
 pub struct AppServerProcess {
     /// The child process
-    child: Child,
+    child: parking_lot::Mutex<Child>,
 
     /// Stdin writer for sending requests
-    stdin: ChildStdin,
+    stdin: Mutex<ChildStdin>,
 
     /// Request ID counter
     request_counter: AtomicU64,
@@ -77,6 +92,12 @@ pub struct AppServerProcess {
 
     /// Channel for shutdown signal
     shutdown_tx: Option<mpsc::Sender<()>>,
+
+    /// Debug recorder for traffic in both directions
+    trace: Arc<RpcTrace>,
+
+    /// Tells this process's events from those of the processes before it
+    generation: u64,
 }
 
 impl AppServerProcess {
@@ -84,15 +105,25 @@ impl AppServerProcess {
     pub async fn spawn(
         events: AppEventEmitter,
         event_tx: mpsc::Sender<AppServerEvent>,
+        trace: Arc<RpcTrace>,
+        approvals: Arc<ApprovalContext>,
+        journal: Arc<TranscriptJournal>,
+        launch: &AppServerLaunchSettings,
     ) -> Result<Self> {
         // Find the codex binary
-        let codex_path = Self::find_codex_binary()?;
+        let codex_path = super::installations::resolve_codex_binary(launch).await?;
 
-        tracing::info!("Spawning app-server from: {:?}", codex_path);
+        tracing::info!(
+            "Spawning app-server from: {:?} (extra args: {:?})",
+            codex_path,
+            launch.extra_args
+        );
 
         // Spawn the process
         let mut child = tokio::process::Command::new(&codex_path)
             .arg("app-server")
+            .args(&launch.extra_args)
+            .envs(&launch.env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit())
@@ -114,11 +145,13 @@ impl AppServerProcess {
             Arc::new(Mutex::new(HashMap::with_capacity(128)));
 
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::SeqCst);
 
         // Spawn stdout reader task
         let pending_clone = pending_requests.clone();
         let events_clone = events.clone();
         let event_tx_clone = event_tx.clone();
+        let trace_clone = trace.clone();
         tokio::spawn(async move {
             let reader = BufReader::new(stdout);
             let mut lines = reader.lines();
@@ -135,7 +168,15 @@ impl AppServerProcess {
                     line = lines.next_line() => {
                         match line {
                             Ok(Some(line)) => {
-                                Self::handle_message(&line, &pending_clone, &events_clone).await;
+                                trace_clone.record(RpcDirection::Incoming, &line);
+                                Self::handle_message(
+                                    &line,
+                                    &pending_clone,
+                                    &events_clone,
+                                    &event_tx_clone,
+                                    &approvals,
+                                    &journal,
+                                ).await;
                             }
                             Ok(None) => {
                                 tracing::info!("App server stdout closed (EOF)");
@@ -152,6 +193,9 @@ impl AppServerProcess {
                 }
             }
 
+            // Requests from this process can no longer be answered
+            approvals.pending.clear();
+
             // Clean up all pending requests with error
             if let Some(reason) = &disconnect_reason {
                 let mut pending = pending_clone.lock().await;
@@ -159,8 +203,8 @@ impl AppServerProcess {
                 if count > 0 {
                     tracing::warn!("Cleaning up {} pending requests due to disconnect", count);
                     for (id, pending_req) in pending.drain() {
-                        let _ = pending_req.sender.send(Err(Error::AppServer(format!(
-                            "Request {id} failed: {reason}"
+                        let _ = pending_req.sender.send(Err(Error::AppServerCrashed(format!(
+                            "request {id} failed: {reason}"
                         ))));
                     }
                 }
@@ -172,6 +216,7 @@ impl AppServerProcess {
                 if let Err(err) = event_tx_clone
                     .send(AppServerEvent::Disconnected {
                         reason: reason.clone(),
+                        generation,
                     })
                     .await
                 {
@@ -181,15 +226,38 @@ impl AppServerProcess {
         });
 
         let mut process = Self {
-            child,
-            stdin,
+            child: parking_lot::Mutex::new(child),
+            stdin: Mutex::new(stdin),
             request_counter: AtomicU64::new(1),
             pending_requests,
             shutdown_tx: Some(shutdown_tx),
+            trace,
+            generation,
         };
 
-        // Initialize the app-server (required before any other requests)
-        process.initialize().await?;
+        // Initialize the app-server (required before any other requests).
+        // A server that hangs here would otherwise leave callers waiting on
+        // the app-server lock, so the handshake is bounded and the process
+        // is torn down on failure.
+        let handshake = tokio::time::timeout(
+            std::time::Duration::from_secs(HANDSHAKE_TIMEOUT_SECS),
+            process.initialize(),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(Error::AppServer(format!(
+                "App server did not complete its handshake within {HANDSHAKE_TIMEOUT_SECS}s"
+            )))
+        });
+        if let Err(err) = handshake {
+            if let Err(shutdown_err) = process.shutdown().await {
+                tracing::warn!(
+                    "Failed to stop app server after failed handshake: {}",
+                    shutdown_err
+                );
+            }
+            return Err(err);
+        }
 
         Ok(process)
     }
@@ -261,6 +329,9 @@ impl AppServerProcess {
         line: &str,
         pending_requests: &Arc<Mutex<HashMap<u64, PendingRequest>>>,
         events: &AppEventEmitter,
+        event_tx: &mpsc::Sender<AppServerEvent>,
+        approvals: &ApprovalContext,
+        journal: &TranscriptJournal,
     ) {
         let message: JsonRpcMessage = match serde_json::from_str(line) {
             Ok(r) => r,
@@ -295,6 +366,31 @@ impl AppServerProcess {
                 if let JsonValue::Object(ref mut map) = params {
                     map.insert("_requestId".to_string(), JsonValue::Number(id.into()));
                 }
+                if method == "item/commandExecution/requestApproval" {
+                    if let Some(reason) = approvals.enrich(&mut params).await {
+                        // The supervisor answers it; the renderer is only told
+                        let event = AppServerEvent::ApprovalAutoApproved {
+                            request_id: id,
+                            reason: reason.to_string(),
+                            params,
+                        };
+                        if let Err(err) = event_tx.send(event).await {
+                            tracing::warn!("Failed to send auto-approval event: {}", err);
+                        }
+                        return;
+                    }
+                }
+
+                if is_approval_request(method) {
+                    let approval = PendingApproval::from_request(id, method, &params);
+                    approvals.pending.record(approval.clone());
+                    if let Err(err) = event_tx
+                        .send(AppServerEvent::ApprovalRequested { approval })
+                        .await
+                    {
+                        tracing::warn!("Failed to send approval requested event: {}", err);
+                    }
+                }
 
                 tracing::debug!("Emitting server request: {} with params: {:?}", event_name, params);
 
@@ -304,6 +400,8 @@ impl AppServerProcess {
             (None, Some(method), _, _) => {
                 let event_name = method.replace('/', "-");
                 let params = message.params.unwrap_or(JsonValue::Null);
+                // On disk first, so a crash from here on still leaves it behind
+                journal.record(method, &params);
 
                 // Log thread ID for debugging
                 if let Some(thread_id) = params.get("threadId") {
@@ -312,6 +410,44 @@ impl AppServerProcess {
                     tracing::debug!("Emitting event: {} (no threadId)", event_name);
                 }
 
+                match method.as_str() {
+                    "turn/started" => Self::notify_turn_started(&params, event_tx).await,
+                    "turn/completed" => {
+                        if let Some(error) = params.pointer("/turn/error") {
+                            Self::notify_if_unauthorized(&params, error, event_tx).await;
+                        }
+                        let approval_pending_since = params
+                            .get("threadId")
+                            .and_then(JsonValue::as_str)
+                            .and_then(|thread_id| approvals.pending.remove_thread(thread_id));
+                        Self::notify_turn_completed(&params, approval_pending_since, event_tx)
+                            .await
+                    }
+                    "error" => {
+                        if let Some(error) = params.get("error") {
+                            Self::notify_if_unauthorized(&params, error, event_tx).await;
+                        }
+                    }
+                    "account/updated" | "account/login/completed" => {
+                        Self::notify_login_state(method, &params, event_tx).await
+                    }
+                    "item/started" => {
+                        if let Some(item) = params.get("item") {
+                            approvals.remember_item(item);
+                        }
+                        Self::notify_item_started(&params, event_tx).await
+                    }
+                    "item/completed" => {
+                        if let Some(item_id) = params.pointer("/item/id").and_then(JsonValue::as_str) {
+                            approvals.pending.remove_item(item_id);
+                        }
+                        Self::notify_item_completed(&params, event_tx).await
+                    }
+                    "thread/tokenUsage/updated" => Self::notify_token_usage(&params, event_tx).await,
+                    "account/rateLimits/updated" => Self::notify_rate_limits(&params, event_tx).await,
+                    _ => {}
+                }
+
                 events.emit_json(&event_name, params).await;
             }
             _ => {
@@ -320,8 +456,205 @@ impl AppServerProcess {
         }
     }
 
+    /// Forward a `turn/started` notification to the supervisor
+    async fn notify_turn_started(params: &JsonValue, event_tx: &mpsc::Sender<AppServerEvent>) {
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        let Some(turn_id) = params.pointer("/turn/id").and_then(JsonValue::as_str) else {
+            return;
+        };
+
+        let event = AppServerEvent::TurnStarted {
+            thread_id: thread_id.to_string(),
+            turn_id: turn_id.to_string(),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send turn started event: {}", err);
+        }
+    }
+
+    /// Forward a `turn/completed` notification to the supervisor
+    async fn notify_turn_completed(
+        params: &JsonValue,
+        approval_pending_since: Option<i64>,
+        event_tx: &mpsc::Sender<AppServerEvent>,
+    ) {
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        let status = params
+            .get("turn")
+            .and_then(|turn| turn.get("status"))
+            .and_then(JsonValue::as_str)
+            .unwrap_or("completed");
+        let turn_id = params.pointer("/turn/id").and_then(JsonValue::as_str).unwrap_or_default();
+        let error = params
+            .pointer("/turn/error")
+            .filter(|error| !error.is_null())
+            .map(|error| {
+                error
+                    .get("message")
+                    .unwrap_or(error)
+                    .as_str()
+                    .map_or_else(|| error.to_string(), str::to_string)
+            });
+
+        let event = AppServerEvent::TurnCompleted {
+            thread_id: thread_id.to_string(),
+            turn_id: turn_id.to_string(),
+            status: status.to_string(),
+            error,
+            approval_pending_since,
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send turn completed event: {}", err);
+        }
+    }
+
+    /// Forward tool `item/started` notifications to the supervisor
+    async fn notify_item_started(params: &JsonValue, event_tx: &mpsc::Sender<AppServerEvent>) {
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        let Some(item) = params.get("item") else {
+            return;
+        };
+        if !matches!(
+            item.get("type").and_then(JsonValue::as_str),
+            Some("fileChange" | "commandExecution" | "mcpToolCall" | "webSearch")
+        ) {
+            return;
+        }
+
+        let event = AppServerEvent::ItemStarted {
+            thread_id: thread_id.to_string(),
+            item: item.clone(),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send item started event: {}", err);
+        }
+    }
+
+    /// Forward message and tool `item/completed` notifications to the supervisor
+    async fn notify_item_completed(params: &JsonValue, event_tx: &mpsc::Sender<AppServerEvent>) {
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        let Some(item) = params.get("item") else {
+            return;
+        };
+        if !matches!(
+            item.get("type").and_then(JsonValue::as_str),
+            Some(
+                "userMessage"
+                    | "agentMessage"
+                    | "fileChange"
+                    | "commandExecution"
+                    | "mcpToolCall"
+                    | "webSearch"
+            )
+        ) {
+            return;
+        }
+
+        let event = AppServerEvent::ItemCompleted {
+            thread_id: thread_id.to_string(),
+            item: item.clone(),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send item completed event: {}", err);
+        }
+    }
+
+    /// Forward a `thread/tokenUsage/updated` notification to the supervisor
+    async fn notify_token_usage(params: &JsonValue, event_tx: &mpsc::Sender<AppServerEvent>) {
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        let Some(usage) = params.get("tokenUsage") else {
+            return;
+        };
+        let turn_id = params.get("turnId").and_then(JsonValue::as_str).unwrap_or_default();
+
+        let event = AppServerEvent::TokenUsageUpdated {
+            thread_id: thread_id.to_string(),
+            turn_id: turn_id.to_string(),
+            usage: usage.clone(),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send token usage event: {}", err);
+        }
+    }
+
+    /// Forward an `account/rateLimits/updated` notification to the supervisor
+    async fn notify_rate_limits(params: &JsonValue, event_tx: &mpsc::Sender<AppServerEvent>) {
+        let event = AppServerEvent::RateLimitsUpdated {
+            rate_limits: params.clone(),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send rate limits event: {}", err);
+        }
+    }
+
+    /// Forward an error that means the login was rejected to the supervisor
+    async fn notify_if_unauthorized(
+        params: &JsonValue,
+        error: &JsonValue,
+        event_tx: &mpsc::Sender<AppServerEvent>,
+    ) {
+        if !auth_monitor::is_unauthorized_error(error) {
+            return;
+        }
+        let event = AppServerEvent::Unauthorized {
+            thread_id: params
+                .get("threadId")
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+            message: error
+                .get("message")
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send unauthorized event: {}", err);
+        }
+    }
+
+    /// Forward `account/updated` and successful `account/login/completed`
+    /// notifications to the supervisor
+    async fn notify_login_state(
+        method: &str,
+        params: &JsonValue,
+        event_tx: &mpsc::Sender<AppServerEvent>,
+    ) {
+        let event = if method == "account/updated" {
+            let login_type = params
+                .get("authMode")
+                .and_then(JsonValue::as_str)
+                .map(|mode| match mode {
+                    "apikey" => "apiKey".to_string(),
+                    mode => mode.to_string(),
+                });
+            AppServerEvent::LoginStateChanged {
+                logged_in: login_type.is_some(),
+                login_type,
+            }
+        } else if params.get("success").and_then(JsonValue::as_bool) == Some(true) {
+            AppServerEvent::LoginStateChanged {
+                logged_in: true,
+                login_type: None,
+            }
+        } else {
+            return;
+        };
+        if let Err(err) = event_tx.send(event).await {
+            tracing::warn!("Failed to send login state event: {}", err);
+        }
+    }
+
     /// Send a JSON-RPC request and wait for response
-    pub async fn send_request<T, R>(&mut self, method: &str, params: T) -> Result<R>
+    pub async fn send_request<T, R>(&self, method: &str, params: T) -> Result<R>
     where
         T: Serialize,
         R: DeserializeOwned,
@@ -363,15 +696,7 @@ impl AppServerProcess {
         }
 
         // Send request
-        self.stdin
-            .write_all(json.as_bytes())
-            .await
-            .map_err(|e| Error::AppServer(format!("Failed to write to stdin: {e}")))?;
-
-        self.stdin
-            .flush()
-            .await
-            .map_err(|e| Error::AppServer(format!("Failed to flush stdin: {e}")))?;
+        self.write_line(&json).await?;
 
         // Wait for response with timeout
         let result = tokio::time::timeout(std::time::Duration::from_secs(30), rx).await;
@@ -384,14 +709,14 @@ impl AppServerProcess {
             }
             Ok(Err(_)) => {
                 // Channel closed unexpectedly
-                Err(Error::AppServer("Response channel closed".to_string()))
+                Err(Error::AppServerCrashed("the request was dropped".to_string()))
             }
             Err(_) => {
                 // Timeout - remove the pending request to prevent memory leak
                 let mut pending = self.pending_requests.lock().await;
                 pending.remove(&id);
                 tracing::warn!("Request {} timed out and was cleaned up", id);
-                Err(Error::AppServer("Request timeout".to_string()))
+                Err(Error::Timeout(format!("app server request {id}")))
             }
         }
     }
@@ -420,7 +745,7 @@ impl AppServerProcess {
     }
 
     /// Send a JSON-RPC response to a server-initiated request
-    pub async fn send_response<T: Serialize>(&mut self, request_id: u64, result: T) -> Result<()> {
+    pub async fn send_response<T: Serialize>(&self, request_id: u64, result: T) -> Result<()> {
         self.ensure_running()?;
         #[derive(Serialize)]
         struct JsonRpcResponseMsg<T> {
@@ -436,21 +761,11 @@ impl AppServerProcess {
         let mut json = serde_json::to_string(&response)?;
         json.push('\n');
 
-        self.stdin
-            .write_all(json.as_bytes())
-            .await
-            .map_err(|e| Error::AppServer(format!("Failed to write to stdin: {e}")))?;
-
-        self.stdin
-            .flush()
-            .await
-            .map_err(|e| Error::AppServer(format!("Failed to flush stdin: {e}")))?;
-
-        Ok(())
+        self.write_line(&json).await
     }
 
     /// Send a JSON-RPC notification (no response expected)
-    pub async fn send_notification<T: Serialize>(&mut self, method: &str, params: T) -> Result<()> {
+    pub async fn send_notification<T: Serialize>(&self, method: &str, params: T) -> Result<()> {
         self.ensure_running()?;
         #[derive(Serialize)]
         struct JsonRpcNotification<T> {
@@ -466,12 +781,20 @@ impl AppServerProcess {
         let mut json = serde_json::to_string(&notification)?;
         json.push('\n');
 
-        self.stdin
+        self.write_line(&json).await
+    }
+
+    /// Write one newline-terminated JSON-RPC message to the app-server
+    async fn write_line(&self, json: &str) -> Result<()> {
+        let mut stdin = self.stdin.lock().await;
+        self.trace.record(RpcDirection::Outgoing, json);
+
+        stdin
             .write_all(json.as_bytes())
             .await
             .map_err(|e| Error::AppServer(format!("Failed to write to stdin: {e}")))?;
 
-        self.stdin
+        stdin
             .flush()
             .await
             .map_err(|e| Error::AppServer(format!("Failed to flush stdin: {e}")))?;
@@ -479,16 +802,26 @@ impl AppServerProcess {
         Ok(())
     }
 
+    /// Number of requests still awaiting a response
+    pub async fn pending_request_count(&self) -> usize {
+        self.pending_requests.lock().await.len()
+    }
+
+    /// Spawn counter value of this process, carried by its `Disconnected` event
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// Check if the process is still running
-    pub fn is_running(&mut self) -> bool {
-        match self.child.try_wait() {
+    pub fn is_running(&self) -> bool {
+        match self.child.lock().try_wait() {
             Ok(None) => true,  // Still running
             Ok(Some(_)) => false,  // Exited
             Err(_) => false,
         }
     }
 
-    fn ensure_running(&mut self) -> Result<()> {
+    fn ensure_running(&self) -> Result<()> {
         if self.is_running() {
             Ok(())
         } else {
@@ -505,15 +838,15 @@ impl AppServerProcess {
 
         // Try graceful shutdown first
         // The app-server should exit when stdin is closed
-        drop(self.stdin.shutdown().await);
+        drop(self.stdin.get_mut().shutdown().await);
 
         // Wait briefly for graceful exit
         tokio::select! {
             _ = tokio::time::sleep(std::time::Duration::from_secs(2)) => {
                 tracing::warn!("App server did not exit gracefully, killing...");
-                self.child.kill().await.ok();
+                self.child.get_mut().kill().await.ok();
             }
-            status = self.child.wait() => {
+            status = self.child.get_mut().wait() => {
                 tracing::info!("App server exited with status: {:?}", status);
             }
         }
@@ -0,0 +1,398 @@
+//! Account rate-limit tracking and turn queueing.
+//!
+//! The app-server reports usage windows (`primary`, `secondary`) as a percent
+//! of the account limit, both in `account/rateLimits/read` responses and in
+//! `account/rateLimits/updated` notifications. The tracker keeps the latest
+//! snapshot plus recent samples per window, so it can project when a window
+//! will run out, and holds turns back while a window is nearly exhausted.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::app_server::ipc_bridge::TurnStartParams;
+use crate::global_state::RateLimitSettings;
+use crate::{Error, Result};
+
+/// Samples kept per window for the burn-rate estimate
+const MAX_SAMPLES: usize = 64;
+
+/// Maximum number of turns held back at once
+const MAX_QUEUED_TURNS: usize = 50;
+
+/// One usage window as reported by the app-server
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowSnapshot {
+    used_percent: f64,
+    #[serde(default)]
+    window_duration_mins: Option<i64>,
+    /// Unix timestamp (seconds) the window resets
+    #[serde(default)]
+    resets_at: Option<i64>,
+}
+
+#[derive(Debug)]
+struct WindowState {
+    current: WindowSnapshot,
+    /// (unix seconds, used percent), oldest first, within the current period
+    samples: VecDeque<(i64, f64)>,
+}
+
+/// How close a window is to its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RateLimitLevel {
+    Ok,
+    /// Above the warning threshold, or projected to run out before it resets
+    Warning,
+    /// Above the queueing threshold; new turns are held back when enabled
+    Exhausted,
+}
+
+/// Projection for one usage window
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowForecast {
+    /// "primary" or "secondary"
+    pub name: String,
+    pub used_percent: f64,
+    pub window_duration_mins: Option<i64>,
+    pub resets_at: Option<i64>,
+    /// Percent of the limit used per hour over the recent samples
+    pub percent_per_hour: Option<f64>,
+    /// When the window would hit 100% at the current rate, if before reset
+    pub exhausted_at: Option<i64>,
+    pub level: RateLimitLevel,
+}
+
+/// A `send_message` turn held back until the limits recover
+#[derive(Debug)]
+pub struct QueuedTurn {
+    pub id: String,
+    pub params: TurnStartParams,
+    pub queued_at: i64,
+}
+
+/// Queued turn as reported to the renderer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTurnInfo {
+    pub id: String,
+    pub thread_id: String,
+    pub queued_at: i64,
+}
+
+/// Result of `get_rate_limit_forecast`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitForecast {
+    pub windows: Vec<WindowForecast>,
+    /// Worst level across the windows
+    pub level: RateLimitLevel,
+    /// Unix timestamp of the last rate-limit update, if any arrived yet
+    pub updated_at: Option<i64>,
+    /// Earliest time held-back turns can be sent, while any are limited
+    pub resumes_at: Option<i64>,
+    pub queued: Vec<QueuedTurnInfo>,
+    pub settings: RateLimitSettings,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    windows: BTreeMap<String, WindowState>,
+    updated_at: Option<i64>,
+    /// Windows already warned about in their current period
+    warned: HashSet<String>,
+    queued: VecDeque<QueuedTurn>,
+}
+
+/// Latest account rate limits and the turns waiting on them
+#[derive(Debug, Default)]
+pub struct RateLimitTracker {
+    state: Mutex<TrackerState>,
+}
+
+impl RateLimitTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a snapshot from a rate-limit response or notification
+    ///
+    /// Accepts either the `{ "rateLimits": {...} }` envelope or the inner
+    /// object. Returns the windows that newly crossed the warning level, so
+    /// each is reported once per reset period.
+    pub fn observe(
+        &self,
+        payload: &JsonValue,
+        now: i64,
+        settings: &RateLimitSettings,
+    ) -> Vec<WindowForecast> {
+        let limits = payload.get("rateLimits").unwrap_or(payload);
+        let Some(limits) = limits.as_object() else {
+            return Vec::new();
+        };
+
+        let mut state = self.state.lock();
+        let mut observed = false;
+        for (name, window) in limits {
+            let Ok(snapshot) = serde_json::from_value::<WindowSnapshot>(window.clone()) else {
+                continue;
+            };
+            observed = true;
+            let entry = state.windows.entry(name.clone()).or_insert_with(|| WindowState {
+                current: snapshot.clone(),
+                samples: VecDeque::new(),
+            });
+            // A drop in usage or a new reset time starts a new period
+            let reset = snapshot.used_percent < entry.current.used_percent
+                || snapshot.resets_at != entry.current.resets_at;
+            if reset {
+                entry.samples.clear();
+            }
+            if entry.samples.len() >= MAX_SAMPLES {
+                entry.samples.pop_front();
+            }
+            entry.samples.push_back((now, snapshot.used_percent));
+            entry.current = snapshot;
+            if reset {
+                state.warned.remove(name);
+            }
+        }
+        if !observed {
+            return Vec::new();
+        }
+        state.updated_at = Some(now);
+
+        let forecasts = forecast_windows(&state, now, settings);
+        let mut crossed = Vec::new();
+        for forecast in forecasts {
+            if forecast.level == RateLimitLevel::Ok {
+                state.warned.remove(&forecast.name);
+            } else if state.warned.insert(forecast.name.clone()) {
+                crossed.push(forecast);
+            }
+        }
+        crossed
+    }
+
+    /// Current projection for every known window
+    pub fn forecast(&self, now: i64, settings: &RateLimitSettings) -> RateLimitForecast {
+        let state = self.state.lock();
+        let windows = forecast_windows(&state, now, settings);
+        let level = windows
+            .iter()
+            .map(|window| window.level)
+            .max()
+            .unwrap_or(RateLimitLevel::Ok);
+        RateLimitForecast {
+            level,
+            updated_at: state.updated_at,
+            resumes_at: resumes_at(&windows),
+            queued: state
+                .queued
+                .iter()
+                .map(|turn| QueuedTurnInfo {
+                    id: turn.id.clone(),
+                    thread_id: turn.params.thread_id.clone(),
+                    queued_at: turn.queued_at,
+                })
+                .collect(),
+            windows,
+            settings: settings.clone(),
+        }
+    }
+
+    /// If new turns should be held back, the time they can be sent
+    ///
+    /// `Some(None)` means limited with no known reset time. A window whose
+    /// reset time has passed is assumed to have reset.
+    pub fn limited_until(&self, now: i64, settings: &RateLimitSettings) -> Option<Option<i64>> {
+        let state = self.state.lock();
+        let windows = forecast_windows(&state, now, settings);
+        windows
+            .iter()
+            .any(|window| window.level == RateLimitLevel::Exhausted)
+            .then(|| resumes_at(&windows))
+    }
+
+    /// Hold a turn back until the limits recover
+    pub fn enqueue(&self, params: TurnStartParams, now: i64) -> Result<QueuedTurnInfo> {
+        let mut state = self.state.lock();
+        if state.queued.len() >= MAX_QUEUED_TURNS {
+            return Err(Error::Other(format!(
+                "Rate-limit queue is full (max {MAX_QUEUED_TURNS} messages)"
+            )));
+        }
+        let turn = QueuedTurn {
+            id: uuid::Uuid::new_v4().to_string(),
+            params,
+            queued_at: now,
+        };
+        let info = QueuedTurnInfo {
+            id: turn.id.clone(),
+            thread_id: turn.params.thread_id.clone(),
+            queued_at: now,
+        };
+        state.queued.push_back(turn);
+        Ok(info)
+    }
+
+    /// Take the oldest held-back turn
+    pub fn pop(&self) -> Option<QueuedTurn> {
+        self.state.lock().queued.pop_front()
+    }
+
+    /// Put a turn back at the head of the queue (e.g. after a failed send)
+    pub fn requeue_front(&self, turn: QueuedTurn) {
+        self.state.lock().queued.push_front(turn);
+    }
+
+    /// Drop a held-back turn; returns whether it was still queued
+    pub fn cancel(&self, queued_id: &str) -> bool {
+        let mut state = self.state.lock();
+        let before = state.queued.len();
+        state.queued.retain(|turn| turn.id != queued_id);
+        state.queued.len() != before
+    }
+
+    /// Number of held-back turns
+    pub fn queued_len(&self) -> usize {
+        self.state.lock().queued.len()
+    }
+}
+
+/// When every exhausted window has reset; `None` if any reset time is unknown
+fn resumes_at(windows: &[WindowForecast]) -> Option<i64> {
+    windows
+        .iter()
+        .filter(|window| window.level == RateLimitLevel::Exhausted)
+        .map(|window| window.resets_at)
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .max()
+}
+
+fn forecast_windows(
+    state: &TrackerState,
+    now: i64,
+    settings: &RateLimitSettings,
+) -> Vec<WindowForecast> {
+    state
+        .windows
+        .iter()
+        .map(|(name, window)| forecast_window(name, window, now, settings))
+        .collect()
+}
+
+fn forecast_window(
+    name: &str,
+    window: &WindowState,
+    now: i64,
+    settings: &RateLimitSettings,
+) -> WindowForecast {
+    let current = &window.current;
+    let expired = current.resets_at.is_some_and(|resets_at| resets_at <= now);
+
+    let percent_per_hour = match (window.samples.front(), window.samples.back()) {
+        (Some(&(first_at, first)), Some(&(last_at, last))) if last_at > first_at => {
+            Some(((last - first) / (last_at - first_at) as f64 * 3600.0).max(0.0))
+        }
+        _ => None,
+    };
+    let exhausted_at = percent_per_hour
+        .filter(|rate| *rate > 0.0 && current.used_percent < 100.0)
+        .map(|rate| now + ((100.0 - current.used_percent) / rate * 3600.0) as i64)
+        .filter(|at| current.resets_at.is_none_or(|resets_at| *at < resets_at));
+
+    let level = if expired {
+        RateLimitLevel::Ok
+    } else if current.used_percent >= settings.queue_percent {
+        RateLimitLevel::Exhausted
+    } else if current.used_percent >= settings.warning_percent || exhausted_at.is_some() {
+        RateLimitLevel::Warning
+    } else {
+        RateLimitLevel::Ok
+    };
+
+    WindowForecast {
+        name: name.to_string(),
+        used_percent: current.used_percent,
+        window_duration_mins: current.window_duration_mins,
+        resets_at: current.resets_at,
+        percent_per_hour,
+        exhausted_at: exhausted_at.filter(|_| !expired),
+        level,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_server::ipc_bridge::UserInput;
+    use serde_json::json;
+
+    fn limits(used: f64, resets_at: i64) -> JsonValue {
+        json!({
+            "rateLimits": {
+                "primary": { "usedPercent": used, "windowDurationMins": 300, "resetsAt": resets_at },
+                "secondary": null,
+            }
+        })
+    }
+
+    fn params(thread_id: &str) -> TurnStartParams {
+        TurnStartParams {
+            thread_id: thread_id.to_string(),
+            input: vec![UserInput::Text {
+                text: "hi".to_string(),
+            }],
+            effort: None,
+            summary: None,
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model: None,
+        }
+    }
+
+    #[test]
+    fn test_forecast_projects_exhaustion_and_warns_once() {
+        let tracker = RateLimitTracker::new();
+        let settings = RateLimitSettings::default();
+
+        assert!(tracker.observe(&limits(40.0, 20_000), 0, &settings).is_empty());
+        // 20% per hour: 100% two hours after t=3600, before the reset
+        let crossed = tracker.observe(&limits(60.0, 20_000), 3600, &settings);
+        assert_eq!(crossed.len(), 1);
+        assert_eq!(crossed[0].level, RateLimitLevel::Warning);
+        assert_eq!(crossed[0].exhausted_at, Some(3600 + 7200));
+        assert!(tracker.observe(&limits(61.0, 20_000), 3700, &settings).is_empty());
+
+        let forecast = tracker.forecast(3700, &settings);
+        assert_eq!(forecast.windows.len(), 1);
+        assert_eq!(forecast.level, RateLimitLevel::Warning);
+        assert!(tracker.limited_until(3700, &settings).is_none());
+    }
+
+    #[test]
+    fn test_limited_until_reset_and_queue() {
+        let tracker = RateLimitTracker::new();
+        let settings = RateLimitSettings::default();
+        tracker.observe(&limits(97.0, 5000), 0, &settings);
+
+        assert_eq!(tracker.limited_until(100, &settings), Some(Some(5000)));
+        // Past the reset time the window is assumed to be fresh
+        assert_eq!(tracker.limited_until(5000, &settings), None);
+
+        let first = tracker.enqueue(params("t1"), 100).unwrap();
+        tracker.enqueue(params("t2"), 101).unwrap();
+        assert!(tracker.cancel(&first.id));
+        assert!(!tracker.cancel(&first.id));
+        assert_eq!(tracker.pop().map(|turn| turn.params.thread_id), Some("t2".to_string()));
+        assert_eq!(tracker.queued_len(), 0);
+    }
+}
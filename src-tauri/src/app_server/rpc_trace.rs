@@ -0,0 +1,462 @@
+//! JSON-RPC traffic recorder for debugging the app-server protocol.
+//!
+//! When enabled, every line written to or read from the app-server is kept in
+//! a ring buffer (and optionally appended to `logs/rpc-trace.jsonl`).
+//! Responses are paired with their request so the inspector can show the
+//! method and round-trip time. Credentials, the environment set on thread
+//! commands and anything `secret_mask` knows are redacted before a message
+//! is stored. Recording is off by default and costs one atomic load per message
+//! while off.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::global_state::{unix_timestamp_millis, RpcTraceSettings};
+use crate::project_env::SHELL_ENV_CONFIG_KEY;
+use crate::secret_mask;
+
+/// Bounds for the ring buffer size
+pub const MIN_TRACE_CAPACITY: usize = 100;
+pub const MAX_TRACE_CAPACITY: usize = 50_000;
+
+/// The trace file is rotated to `rpc-trace.1.jsonl` past this size
+const MAX_TRACE_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Requests still unanswered after this many are dropped from pairing
+const MAX_IN_FLIGHT: usize = 1000;
+
+/// Object keys whose string values are replaced before recording
+const REDACTED_KEYS: &[&str] = &[
+    "apikey",
+    "openai_api_key",
+    "accesstoken",
+    "access_token",
+    "refreshtoken",
+    "refresh_token",
+    "idtoken",
+    "id_token",
+    "authorization",
+    "password",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcDirection {
+    /// Sent by the app to the app-server
+    Outgoing,
+    /// Received from the app-server
+    Incoming,
+}
+
+impl RpcDirection {
+    fn opposite(self) -> Self {
+        match self {
+            Self::Outgoing => Self::Incoming,
+            Self::Incoming => Self::Outgoing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcMessageKind {
+    Request,
+    Response,
+    Error,
+    Notification,
+    /// Not valid JSON or not a JSON-RPC message
+    Invalid,
+}
+
+/// One recorded message
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTraceEntry {
+    pub seq: u64,
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub direction: RpcDirection,
+    pub kind: RpcMessageKind,
+    pub id: Option<u64>,
+    /// For responses and errors, the method of the request they answer
+    pub method: Option<String>,
+    /// For responses and errors, time since the request was recorded
+    pub duration_ms: Option<i64>,
+    /// Size of the raw line in bytes
+    pub size: usize,
+    /// The message with credentials redacted; the raw line if it was not JSON
+    pub message: JsonValue,
+}
+
+/// Result of `get_rpc_trace`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTraceSnapshot {
+    pub settings: RpcTraceSettings,
+    /// Oldest first
+    pub entries: Vec<RpcTraceEntry>,
+    /// Entries evicted from the ring buffer since the last clear
+    pub dropped: u64,
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    settings: RpcTraceSettings,
+    next_seq: u64,
+    entries: VecDeque<RpcTraceEntry>,
+    dropped: u64,
+    /// (direction, id) of unanswered requests -> (method, recorded at)
+    in_flight: HashMap<(RpcDirection, u64), (String, i64)>,
+    file: Option<File>,
+}
+
+/// Ring buffer of recent app-server traffic, shared by every app-server
+/// process spawned in this session
+#[derive(Debug)]
+pub struct RpcTrace {
+    enabled: AtomicBool,
+    file_path: PathBuf,
+    state: Mutex<TraceState>,
+}
+
+impl RpcTrace {
+    /// `log_dir` is where `rpc-trace.jsonl` is written when file logging is on
+    pub fn new(log_dir: &Path, settings: RpcTraceSettings) -> Self {
+        let trace = Self {
+            enabled: AtomicBool::new(false),
+            file_path: log_dir.join("rpc-trace.jsonl"),
+            state: Mutex::new(TraceState::default()),
+        };
+        trace.configure(settings);
+        trace
+    }
+
+    /// Apply new settings; shrinking the capacity evicts the oldest entries
+    pub fn configure(&self, mut settings: RpcTraceSettings) -> RpcTraceSettings {
+        settings.capacity = settings
+            .capacity
+            .clamp(MIN_TRACE_CAPACITY, MAX_TRACE_CAPACITY);
+        let mut state = self.state.lock();
+        while state.entries.len() > settings.capacity {
+            state.entries.pop_front();
+            state.dropped += 1;
+        }
+        if !settings.enabled {
+            state.in_flight.clear();
+        }
+        if !(settings.enabled && settings.log_to_file) {
+            state.file = None;
+        }
+        state.settings = settings.clone();
+        self.enabled.store(settings.enabled, Ordering::Relaxed);
+        settings
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Record a raw JSON-RPC line
+    pub fn record(&self, direction: RpcDirection, line: &str) {
+        if self.is_enabled() {
+            self.record_at(direction, line, unix_timestamp_millis());
+        }
+    }
+
+    fn record_at(&self, direction: RpcDirection, line: &str, now_ms: i64) {
+        let line = line.trim_end();
+        let (kind, id, mut method, message) = match serde_json::from_str::<JsonValue>(line) {
+            Ok(mut message) => {
+                redact(&mut message);
+                let id = message.get("id").and_then(JsonValue::as_u64);
+                let method = message
+                    .get("method")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string);
+                let kind = match (id, &method) {
+                    (Some(_), Some(_)) => RpcMessageKind::Request,
+                    (None, Some(_)) => RpcMessageKind::Notification,
+                    (Some(_), None) if message.get("error").is_some() => RpcMessageKind::Error,
+                    (Some(_), None) => RpcMessageKind::Response,
+                    (None, None) => RpcMessageKind::Invalid,
+                };
+                (kind, id, method, message)
+            }
+            Err(_) => (
+                RpcMessageKind::Invalid,
+                None,
+                None,
+                JsonValue::String(secret_mask::mask(line).into_owned()),
+            ),
+        };
+
+        let mut state = self.state.lock();
+        let mut duration_ms = None;
+        match (kind, id) {
+            (RpcMessageKind::Request, Some(id)) => {
+                if state.in_flight.len() >= MAX_IN_FLIGHT {
+                    state.in_flight.clear();
+                }
+                let request_method = method.clone().unwrap_or_default();
+                state
+                    .in_flight
+                    .insert((direction, id), (request_method, now_ms));
+            }
+            (RpcMessageKind::Response | RpcMessageKind::Error, Some(id)) => {
+                // A response travels the opposite way from its request
+                if let Some((request_method, sent_at)) =
+                    state.in_flight.remove(&(direction.opposite(), id))
+                {
+                    method = Some(request_method);
+                    duration_ms = Some(now_ms - sent_at);
+                }
+            }
+            _ => {}
+        }
+
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        let entry = RpcTraceEntry {
+            seq,
+            timestamp: now_ms,
+            direction,
+            kind,
+            id,
+            method,
+            duration_ms,
+            size: line.len(),
+            message,
+        };
+
+        if state.settings.log_to_file {
+            self.append_to_file(&mut state, &entry);
+        }
+        while state.entries.len() >= state.settings.capacity {
+            state.entries.pop_front();
+            state.dropped += 1;
+        }
+        state.entries.push_back(entry);
+    }
+
+    /// Failures disable file logging for the session instead of failing the request
+    fn append_to_file(&self, state: &mut TraceState, entry: &RpcTraceEntry) {
+        if let Err(err) = self.write_entry(state, entry) {
+            tracing::warn!("Disabling RPC trace file {:?}: {}", self.file_path, err);
+            state.file = None;
+            state.settings.log_to_file = false;
+        }
+    }
+
+    fn write_entry(&self, state: &mut TraceState, entry: &RpcTraceEntry) -> std::io::Result<()> {
+        let file = match &mut state.file {
+            Some(file) => file,
+            None => {
+                if let Some(parent) = self.file_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                if std::fs::metadata(&self.file_path).is_ok_and(|m| m.len() > MAX_TRACE_FILE_BYTES)
+                {
+                    std::fs::rename(&self.file_path, self.file_path.with_extension("1.jsonl"))?;
+                }
+                state.file.insert(
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&self.file_path)?,
+                )
+            }
+        };
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        if file.metadata()?.len() > MAX_TRACE_FILE_BYTES {
+            // Reopened (and rotated) on the next message
+            state.file = None;
+        }
+        Ok(())
+    }
+
+    /// The newest `limit` entries, oldest first, optionally only those whose
+    /// method contains `method`
+    pub fn snapshot(&self, limit: Option<usize>, method: Option<&str>) -> RpcTraceSnapshot {
+        let state = self.state.lock();
+        let matching: Vec<&RpcTraceEntry> = state
+            .entries
+            .iter()
+            .filter(|entry| match method {
+                Some(filter) => entry
+                    .method
+                    .as_deref()
+                    .is_some_and(|method| method.contains(filter)),
+                None => true,
+            })
+            .collect();
+        let skip = limit.map_or(0, |limit| matching.len().saturating_sub(limit));
+        RpcTraceSnapshot {
+            settings: state.settings.clone(),
+            entries: matching.into_iter().skip(skip).cloned().collect(),
+            dropped: state.dropped,
+            file_path: state
+                .settings
+                .log_to_file
+                .then(|| self.file_path.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// Empty the ring buffer; the trace file is left as is
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.entries.clear();
+        state.in_flight.clear();
+        state.dropped = 0;
+    }
+}
+
+/// Replace credential values and thread environment values anywhere in a
+/// message, then mask known secrets in what is left
+fn redact(value: &mut JsonValue) {
+    redact_keys(value);
+    secret_mask::mask_json(value);
+}
+
+fn redact_keys(value: &mut JsonValue) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == SHELL_ENV_CONFIG_KEY {
+                    // Project variables are decrypted into this table
+                    if let JsonValue::Object(vars) = value {
+                        for value in vars.values_mut() {
+                            *value = JsonValue::String("[redacted]".to_string());
+                        }
+                    }
+                } else if value.is_string()
+                    && REDACTED_KEYS.contains(&key.to_ascii_lowercase().as_str())
+                {
+                    *value = JsonValue::String("[redacted]".to_string());
+                } else {
+                    redact_keys(value);
+                }
+            }
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(redact_keys),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn trace(capacity: usize) -> RpcTrace {
+        RpcTrace::new(
+            Path::new("/nonexistent"),
+            RpcTraceSettings {
+                enabled: true,
+                capacity,
+                log_to_file: false,
+            },
+        )
+    }
+
+    #[test]
+    fn test_pairs_responses_with_requests() {
+        let trace = trace(MIN_TRACE_CAPACITY);
+        trace.record_at(
+            RpcDirection::Outgoing,
+            r#"{"id":7,"method":"thread/start","params":{}}"#,
+            1_000,
+        );
+        trace.record_at(
+            RpcDirection::Incoming,
+            r#"{"method":"turn/started","params":{}}"#,
+            1_100,
+        );
+        // Server request with the same id must not be confused with ours
+        trace.record_at(
+            RpcDirection::Incoming,
+            r#"{"id":7,"method":"item/commandExecution/requestApproval"}"#,
+            1_150,
+        );
+        trace.record_at(RpcDirection::Incoming, r#"{"id":7,"result":{}}"#, 1_250);
+        trace.record_at(RpcDirection::Incoming, "not json\n", 1_300);
+
+        let entries = trace.snapshot(None, None).entries;
+        let kinds: Vec<RpcMessageKind> = entries.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RpcMessageKind::Request,
+                RpcMessageKind::Notification,
+                RpcMessageKind::Request,
+                RpcMessageKind::Response,
+                RpcMessageKind::Invalid,
+            ]
+        );
+        assert_eq!(entries[3].method.as_deref(), Some("thread/start"));
+        assert_eq!(entries[3].duration_ms, Some(250));
+        assert_eq!(entries[4].message, json!("not json"));
+
+        let filtered = trace.snapshot(Some(1), Some("thread/")).entries;
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].seq, 3);
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let trace = trace(0);
+        for i in 0..MIN_TRACE_CAPACITY + 5 {
+            trace.record_at(RpcDirection::Incoming, r#"{"method":"x"}"#, i as i64);
+        }
+        let snapshot = trace.snapshot(None, None);
+        assert_eq!(snapshot.settings.capacity, MIN_TRACE_CAPACITY);
+        assert_eq!(snapshot.entries.len(), MIN_TRACE_CAPACITY);
+        assert_eq!(snapshot.entries[0].seq, 5);
+        assert_eq!(snapshot.dropped, 5);
+
+        trace.clear();
+        assert!(trace.snapshot(None, None).entries.is_empty());
+    }
+
+    #[test]
+    fn test_redacts_credentials() {
+        let mut message = json!({
+            "method": "account/login/start",
+            "params": { "type": "apiKey", "apiKey": "sk-secret", "nested": [{ "id_token": "t" }] },
+        });
+        redact(&mut message);
+        assert_eq!(message["params"]["apiKey"], "[redacted]");
+        assert_eq!(message["params"]["type"], "apiKey");
+        assert_eq!(message["params"]["nested"][0]["id_token"], "[redacted]");
+
+        let mut message = json!({
+            "method": "thread/start",
+            "params": { "config": { SHELL_ENV_CONFIG_KEY: { "DATABASE_URL": "postgres://u:p@db" } } },
+        });
+        redact(&mut message);
+        assert_eq!(
+            message["params"]["config"][SHELL_ENV_CONFIG_KEY]["DATABASE_URL"],
+            "[redacted]"
+        );
+    }
+
+    #[test]
+    fn test_disabled_records_nothing() {
+        let trace = trace(MIN_TRACE_CAPACITY);
+        trace.configure(RpcTraceSettings {
+            enabled: false,
+            ..Default::default()
+        });
+        trace.record(RpcDirection::Outgoing, r#"{"id":1,"method":"x"}"#);
+        assert!(trace.snapshot(None, None).entries.is_empty());
+    }
+}
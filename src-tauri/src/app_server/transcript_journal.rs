@@ -0,0 +1,403 @@
+//! Append-only on-disk journal of the turn in flight on each thread.
+//!
+//! Turn and item notifications are appended to
+//! `transcripts/<thread id>.jsonl` as they arrive from the app-server, before
+//! anything else handles them. A journal only ever holds the current turn:
+//! it is started afresh on `turn/started` and removed on `turn/completed`.
+//! A journal still on disk at launch therefore belongs to a turn the app
+//! never saw finish, and `recover` replays it into the database.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::database::{Database, RecoveredTurn};
+use crate::global_state::unix_timestamp_millis;
+
+/// Deltas are flushed at most this long after they arrive; turn and item
+/// boundaries are flushed at once
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Past this size a journal keeps item boundaries but drops deltas
+const MAX_JOURNAL_BYTES: u64 = 32 * 1024 * 1024;
+
+struct JournalFile {
+    writer: BufWriter<File>,
+    bytes: u64,
+    last_flush: Instant,
+}
+
+/// Journals of the turns in flight, keyed by thread id
+pub struct TranscriptJournal {
+    dir: PathBuf,
+    files: Mutex<HashMap<String, JournalFile>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalLine {
+    at: i64,
+    method: String,
+    #[serde(default)]
+    params: JsonValue,
+}
+
+impl TranscriptJournal {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Append a notification to its thread's journal; anything but turn
+    /// and item notifications is ignored
+    pub fn record(&self, method: &str, params: &JsonValue) {
+        if !(method.starts_with("turn/") || method.starts_with("item/")) {
+            return;
+        }
+        let Some(thread_id) = params.get("threadId").and_then(JsonValue::as_str) else {
+            return;
+        };
+        if !is_safe_id(thread_id) {
+            return;
+        }
+        if let Err(err) = self.append(thread_id, method, params) {
+            tracing::warn!(
+                "Failed to journal {} for thread {}: {}",
+                method,
+                thread_id,
+                err
+            );
+            self.files.lock().remove(thread_id);
+        }
+    }
+
+    fn append(&self, thread_id: &str, method: &str, params: &JsonValue) -> std::io::Result<()> {
+        let path = self.path(thread_id);
+        let mut files = self.files.lock();
+        if method == "turn/completed" {
+            // The app-server has the whole turn now
+            files.remove(thread_id);
+            return match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+
+        let starts_turn = method == "turn/started";
+        if starts_turn || !files.contains_key(thread_id) {
+            std::fs::create_dir_all(&self.dir)?;
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(!starts_turn)
+                .truncate(starts_turn)
+                .open(&path)?;
+            let bytes = file.metadata()?.len();
+            files.insert(
+                thread_id.to_string(),
+                JournalFile {
+                    writer: BufWriter::new(file),
+                    bytes,
+                    last_flush: Instant::now(),
+                },
+            );
+        }
+        let Some(journal) = files.get_mut(thread_id) else {
+            return Ok(());
+        };
+
+        let is_delta = method.ends_with("Delta") || method.ends_with("/delta");
+        if is_delta && journal.bytes > MAX_JOURNAL_BYTES {
+            return Ok(());
+        }
+        let mut params = params.clone();
+        crate::secret_mask::mask_json(&mut params);
+        let line = json!({ "at": unix_timestamp_millis(), "method": method, "params": params });
+        let mut line = serde_json::to_vec(&line)?;
+        line.push(b'\n');
+        journal.writer.write_all(&line)?;
+        journal.bytes += line.len() as u64;
+
+        if !is_delta || journal.last_flush.elapsed() >= FLUSH_INTERVAL {
+            journal.writer.flush()?;
+            journal.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+
+    fn path(&self, thread_id: &str) -> PathBuf {
+        self.dir.join(format!("{thread_id}.jsonl"))
+    }
+
+    /// Replay the journals left behind by the last run into the database
+    /// and remove them; returns how many turns were recovered
+    pub fn recover(&self, database: &Database) -> usize {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return 0,
+            Err(err) => {
+                tracing::warn!("Failed to list transcript journals: {}", err);
+                return 0;
+            }
+        };
+
+        let mut recovered = 0;
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let Some(thread_id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".jsonl"))
+                .filter(|id| is_safe_id(id))
+            else {
+                continue;
+            };
+            match recover_file(database, thread_id, &path) {
+                Ok(true) => recovered += 1,
+                Ok(false) => {}
+                Err(err) => {
+                    // Kept for the next launch
+                    tracing::warn!("Failed to recover transcript journal {:?}: {}", path, err);
+                    continue;
+                }
+            }
+            if let Err(err) = std::fs::remove_file(&path) {
+                tracing::warn!("Failed to remove transcript journal {:?}: {}", path, err);
+            }
+        }
+        recovered
+    }
+}
+
+/// Store the unfinished turn in one journal; `false` when there is none or
+/// the thread has no session
+fn recover_file(database: &Database, thread_id: &str, path: &Path) -> crate::Result<bool> {
+    if database.get_session_by_id(thread_id)?.is_none() {
+        return Ok(false);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    let Some(mut turn) = replay(thread_id, &contents) else {
+        return Ok(false);
+    };
+    turn.recovered_at = unix_timestamp_millis();
+    if let Some(status) = database.record_recovered_turn(&turn)? {
+        tracing::info!(
+            "Recovered unfinished turn {} of session {} ({} items), now {}",
+            turn.turn_id,
+            thread_id,
+            turn.items.len(),
+            status.as_str()
+        );
+    }
+    Ok(true)
+}
+
+/// The turn a journal ends in, if it never completed
+///
+/// Items are kept in the order they started. Items that never completed
+/// are marked `"partial": true`, with the text of agent messages rebuilt
+/// from their deltas. A torn last line is ignored.
+fn replay(thread_id: &str, contents: &str) -> Option<RecoveredTurn> {
+    let mut turn: Option<RecoveredTurn> = None;
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut completed: Vec<bool> = Vec::new();
+
+    for line in contents.lines() {
+        let Ok(line) = serde_json::from_str::<JournalLine>(line) else {
+            continue;
+        };
+        let params = &line.params;
+        if line.method == "turn/started" {
+            let Some(turn_id) = params.pointer("/turn/id").and_then(JsonValue::as_str) else {
+                continue;
+            };
+            turn = Some(RecoveredTurn {
+                session_id: thread_id.to_string(),
+                turn_id: turn_id.to_string(),
+                items: Vec::new(),
+                started_at: line.at,
+                last_event_at: line.at,
+                recovered_at: 0,
+            });
+            index.clear();
+            completed.clear();
+            continue;
+        }
+        if line.method == "turn/completed" {
+            turn = None;
+            continue;
+        }
+        let Some(turn) = turn.as_mut() else {
+            continue;
+        };
+        turn.last_event_at = line.at;
+
+        match line.method.as_str() {
+            "item/started" | "item/completed" => {
+                let Some(item) = params.get("item") else {
+                    continue;
+                };
+                let Some(item_id) = item.get("id").and_then(JsonValue::as_str) else {
+                    continue;
+                };
+                let done = line.method == "item/completed";
+                match index.get(item_id) {
+                    Some(&at) if done => {
+                        turn.items[at] = item.clone();
+                        completed[at] = true;
+                    }
+                    Some(_) => {}
+                    None => {
+                        index.insert(item_id.to_string(), turn.items.len());
+                        turn.items.push(item.clone());
+                        completed.push(done);
+                    }
+                }
+            }
+            "item/agentMessage/delta" => {
+                let Some(item_id) = params.get("itemId").and_then(JsonValue::as_str) else {
+                    continue;
+                };
+                let delta = params
+                    .get("delta")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or("");
+                let at = *index.entry(item_id.to_string()).or_insert_with(|| {
+                    turn.items
+                        .push(json!({ "type": "agentMessage", "id": item_id, "text": "" }));
+                    completed.push(false);
+                    turn.items.len() - 1
+                });
+                if completed[at] {
+                    continue;
+                }
+                let text = turn.items[at]
+                    .get("text")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or("");
+                turn.items[at]["text"] = JsonValue::String(format!("{text}{delta}"));
+            }
+            _ => {}
+        }
+    }
+
+    let mut turn = turn?;
+    for (item, done) in turn.items.iter_mut().zip(completed) {
+        if !done {
+            item["partial"] = JsonValue::Bool(true);
+        }
+    }
+    Some(turn)
+}
+
+/// Thread ids become file names; anything unusual is not journaled
+fn is_safe_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Project, SessionMetadata, SessionStatus};
+
+    fn item(id: &str, kind: &str, text: &str) -> JsonValue {
+        json!({ "id": id, "type": kind, "text": text })
+    }
+
+    #[test]
+    fn test_replay_rebuilds_unfinished_turn() {
+        let lines = [
+            json!({ "at": 1, "method": "turn/started", "params": { "turn": { "id": "t1" } } }),
+            json!({ "at": 2, "method": "turn/completed", "params": {} }),
+            json!({ "at": 3, "method": "turn/started", "params": { "turn": { "id": "t2" } } }),
+            json!({ "at": 4, "method": "item/started",
+                    "params": { "item": item("u", "userMessage", "Fix it") } }),
+            json!({ "at": 5, "method": "item/completed",
+                    "params": { "item": item("u", "userMessage", "Fix it") } }),
+            json!({ "at": 6, "method": "item/started",
+                    "params": { "item": item("m", "agentMessage", "") } }),
+            json!({ "at": 7, "method": "item/agentMessage/delta",
+                    "params": { "itemId": "m", "delta": "Looking" } }),
+            json!({ "at": 8, "method": "item/agentMessage/delta",
+                    "params": { "itemId": "m", "delta": " now" } }),
+        ];
+        let mut contents: String = lines.iter().map(|line| format!("{line}\n")).collect();
+        contents.push_str("{\"at\": 9, \"method\": \"item/agentMes");
+
+        let turn = replay("s1", &contents).unwrap();
+        assert_eq!((turn.turn_id.as_str(), turn.started_at), ("t2", 3));
+        assert_eq!(turn.last_event_at, 8);
+        assert_eq!(turn.items.len(), 2);
+        assert!(turn.items[0].get("partial").is_none());
+        assert_eq!(turn.items[1]["text"], "Looking now");
+        assert_eq!(turn.items[1]["partial"], true);
+
+        let finished: String = lines[..2].iter().map(|line| format!("{line}\n")).collect();
+        assert!(replay("s1", &finished).is_none());
+    }
+
+    #[test]
+    fn test_journal_recovered_into_database() {
+        let (temp_dir, db) = crate::database::test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        let journal = TranscriptJournal::new(temp_dir.path().join("transcripts"));
+        let notify = |method: &str, params: JsonValue| {
+            let mut params = params;
+            params["threadId"] = json!(params["threadId"].as_str().unwrap_or("s1"));
+            journal.record(method, &params);
+        };
+        notify("turn/started", json!({ "turn": { "id": "t1" } }));
+        notify("turn/completed", json!({ "turn": { "id": "t1" } }));
+        assert!(!journal.path("s1").exists());
+
+        notify("turn/started", json!({ "turn": { "id": "t2" } }));
+        notify(
+            "item/started",
+            json!({ "item": item("m", "agentMessage", "") }),
+        );
+        notify(
+            "item/agentMessage/delta",
+            json!({ "itemId": "m", "delta": "Half" }),
+        );
+        // Threads without a session are dropped
+        notify(
+            "turn/started",
+            json!({ "threadId": "agent", "turn": { "id": "a1" } }),
+        );
+        notify("thread/tokenUsage/updated", json!({ "threadId": "other" }));
+        journal.files.lock().clear();
+
+        let restarted = TranscriptJournal::new(temp_dir.path().join("transcripts"));
+        assert_eq!(restarted.recover(&db), 1);
+        assert!(!restarted.path("s1").exists() && !restarted.path("agent").exists());
+        assert!(!restarted.path("other").exists());
+
+        let recovered = db.list_recovered_turns("s1").unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].turn_id, "t2");
+        assert_eq!(recovered[0].items[0]["text"], "Half");
+        let session = db.get_session_by_id("s1").unwrap().unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+    }
+
+    #[test]
+    fn test_is_safe_id() {
+        assert!(is_safe_id("0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b"));
+        assert!(!is_safe_id("../escape"));
+        assert!(!is_safe_id(""));
+    }
+}
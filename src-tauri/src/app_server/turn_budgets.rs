@@ -0,0 +1,197 @@
+//! Per-turn token and cost ceilings.
+//!
+//! A turn's budget is fixed when the app-server reports it started: the
+//! one passed with its `send_message`, or the configured default. Each
+//! `thread/tokenUsage/updated` adds the finished model call to the turn's
+//! running total, and the first call that crosses a limit reports the
+//! overrun so the supervisor can interrupt the turn and emit
+//! `turn-budget-exceeded`.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::database::TokenUsage;
+use crate::global_state::TurnBudget;
+
+/// Payload of `turn-budget-exceeded`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnBudgetExceeded {
+    pub thread_id: String,
+    pub turn_id: String,
+    /// "tokens" or "cost"
+    pub limit: &'static str,
+    pub budget: TurnBudget,
+    pub tokens: u64,
+    /// `None` when the model has no known pricing
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug)]
+struct RunningBudget {
+    turn_id: String,
+    budget: TurnBudget,
+    tokens: u64,
+    cost_usd: Option<f64>,
+    exceeded: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct TurnBudgets {
+    /// Budgets passed with `send_message`, waiting for their turn to start
+    pending: Mutex<HashMap<String, TurnBudget>>,
+    /// Budgets of running turns, by thread
+    running: Mutex<HashMap<String, RunningBudget>>,
+}
+
+impl TurnBudgets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `budget` instead of the default for the thread's next turn
+    pub fn set_next(&self, thread_id: &str, budget: TurnBudget) {
+        self.pending.lock().insert(thread_id.to_string(), budget);
+    }
+
+    /// Drop a budget whose turn never started
+    pub fn cancel_next(&self, thread_id: &str) {
+        self.pending.lock().remove(thread_id);
+    }
+
+    pub fn turn_started(&self, thread_id: &str, turn_id: &str, default: &TurnBudget) {
+        let budget = self
+            .pending
+            .lock()
+            .remove(thread_id)
+            .unwrap_or_else(|| default.clone());
+        let mut running = self.running.lock();
+        if !budget.is_limited() {
+            running.remove(thread_id);
+            return;
+        }
+        running.insert(
+            thread_id.to_string(),
+            RunningBudget {
+                turn_id: turn_id.to_string(),
+                budget,
+                tokens: 0,
+                cost_usd: None,
+                exceeded: false,
+            },
+        );
+    }
+
+    /// Add a model call to the turn; returns the overrun the first time a
+    /// limit is crossed
+    pub fn record_usage(
+        &self,
+        thread_id: &str,
+        turn_id: &str,
+        usage: &TokenUsage,
+        cost_usd: Option<f64>,
+    ) -> Option<TurnBudgetExceeded> {
+        let mut running = self.running.lock();
+        let turn = running.get_mut(thread_id)?;
+        if !turn_id.is_empty() && turn.turn_id != turn_id {
+            return None;
+        }
+        turn.tokens += usage.input_tokens + usage.output_tokens;
+        if let Some(cost) = cost_usd {
+            *turn.cost_usd.get_or_insert(0.0) += cost;
+        }
+        if turn.exceeded {
+            return None;
+        }
+
+        let over_tokens = turn
+            .budget
+            .max_tokens
+            .is_some_and(|max| turn.tokens > max);
+        let over_cost = matches!(
+            (turn.budget.max_cost_usd, turn.cost_usd),
+            (Some(max), Some(cost)) if cost > max
+        );
+        if !over_tokens && !over_cost {
+            return None;
+        }
+        turn.exceeded = true;
+        Some(TurnBudgetExceeded {
+            thread_id: thread_id.to_string(),
+            turn_id: turn.turn_id.clone(),
+            limit: if over_tokens { "tokens" } else { "cost" },
+            budget: turn.budget.clone(),
+            tokens: turn.tokens,
+            cost_usd: turn.cost_usd,
+        })
+    }
+
+    pub fn turn_completed(&self, thread_id: &str) {
+        self.running.lock().remove(thread_id);
+    }
+
+    /// Forget running turns, e.g. when the app-server exits
+    pub fn clear(&self) {
+        self.running.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input_tokens: u64, output_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens,
+            output_tokens,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_token_ceiling_reported_once() {
+        let budgets = TurnBudgets::new();
+        let default = TurnBudget {
+            max_tokens: Some(1_000),
+            max_cost_usd: None,
+        };
+        budgets.turn_started("t1", "turn-1", &default);
+        assert!(budgets.record_usage("t1", "turn-1", &usage(600, 100), None).is_none());
+        assert!(budgets.record_usage("t1", "other", &usage(600, 100), None).is_none());
+
+        let exceeded = budgets
+            .record_usage("t1", "turn-1", &usage(300, 100), None)
+            .unwrap();
+        assert_eq!(exceeded.limit, "tokens");
+        assert_eq!(exceeded.tokens, 1_100);
+        assert!(budgets.record_usage("t1", "turn-1", &usage(1, 1), None).is_none());
+
+        budgets.turn_completed("t1");
+        assert!(budgets.record_usage("t1", "turn-1", &usage(5_000, 0), None).is_none());
+    }
+
+    #[test]
+    fn test_per_turn_budget_overrides_default() {
+        let budgets = TurnBudgets::new();
+        budgets.set_next(
+            "t1",
+            TurnBudget {
+                max_tokens: None,
+                max_cost_usd: Some(0.5),
+            },
+        );
+        budgets.turn_started("t1", "turn-1", &TurnBudget::default());
+        assert!(budgets.record_usage("t1", "", &usage(1_000_000, 0), None).is_none());
+        let exceeded = budgets
+            .record_usage("t1", "", &usage(10, 10), Some(0.6))
+            .unwrap();
+        assert_eq!(exceeded.limit, "cost");
+        assert_eq!(exceeded.cost_usd, Some(0.6));
+
+        // Without a pending budget or a default, nothing is tracked
+        budgets.turn_started("t1", "turn-2", &TurnBudget::default());
+        assert!(budgets.record_usage("t1", "turn-2", &usage(1, 1), Some(9.0)).is_none());
+    }
+}
@@ -0,0 +1,49 @@
+//! One-shot waiters for turn completion on specific threads.
+//!
+//! Backend flows that drive a turn themselves (rather than the renderer)
+//! register a waiter before starting the turn; the supervisor resolves it
+//! with the final turn status when `turn/completed` arrives.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+/// Pending turn-completion waiters keyed by thread id
+#[derive(Debug, Default)]
+pub struct TurnWaiters {
+    waiters: Mutex<HashMap<String, oneshot::Sender<String>>>,
+}
+
+impl TurnWaiters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register interest in the next completed turn of `thread_id`.
+    ///
+    /// The receiver yields the turn status (e.g. "completed", "failed").
+    /// Registering again for the same thread replaces the earlier waiter.
+    pub fn register(&self, thread_id: &str) -> oneshot::Receiver<String> {
+        let (tx, rx) = oneshot::channel();
+        self.waiters.lock().insert(thread_id.to_string(), tx);
+        rx
+    }
+
+    /// Resolve the waiter for `thread_id`, if any
+    pub fn complete(&self, thread_id: &str, status: &str) {
+        if let Some(tx) = self.waiters.lock().remove(thread_id) {
+            let _ = tx.send(status.to_string());
+        }
+    }
+
+    /// Number of turns currently being waited on
+    pub fn pending_count(&self) -> usize {
+        self.waiters.lock().len()
+    }
+
+    /// Drop a waiter that is no longer needed (e.g. after a timeout)
+    pub fn cancel(&self, thread_id: &str) {
+        self.waiters.lock().remove(thread_id);
+    }
+}
@@ -0,0 +1,115 @@
+//! System-wide shortcuts that approve or deny the newest pending approval.
+//!
+//! They let a user watching another window unblock the agent without
+//! switching back. The request answered is the latest one in
+//! `PendingApprovals`; a native notification confirms the answer and the
+//! renderer is told through `approval-answered-by-shortcut` so it can
+//! update the thread. High-risk and denylisted commands are never accepted
+//! unseen: the window is brought up instead. Shortcuts do nothing while
+//! the app is locked. They are registered together with the other
+//! shortcuts by `global_shortcuts`.
+
+use serde::Serialize;
+
+use crate::app_server::ipc_bridge::{ApprovalDecision, ApprovalResponseResult};
+use crate::i18n;
+use crate::notifications::NotificationKind;
+use crate::pending_approvals::PendingApproval;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutDecision {
+    Accept,
+    Decline,
+}
+
+/// Payload of `approval-answered-by-shortcut`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutAnswer {
+    #[serde(flatten)]
+    pub approval: PendingApproval,
+    pub decision: ShortcutDecision,
+}
+
+/// What a shortcut press did
+#[derive(Debug)]
+pub enum ShortcutOutcome {
+    /// Nothing was pending, or the request was answered elsewhere first
+    NothingPending,
+    Answered(PendingApproval),
+    /// An accept was refused because the request needs a look first
+    NeedsReview(PendingApproval),
+}
+
+/// Answer the newest pending approval
+///
+/// The request is removed from the pending list before the answer is sent,
+/// so a second press or the renderer cannot answer it again; it is put
+/// back if sending fails.
+pub async fn answer_latest(
+    state: &AppState,
+    decision: ShortcutDecision,
+    confirm: bool,
+) -> Result<ShortcutOutcome> {
+    if state.app_lock.is_locked() {
+        return Err(Error::Locked);
+    }
+    let Some(latest) = state.approvals.pending.latest() else {
+        return Ok(ShortcutOutcome::NothingPending);
+    };
+    if decision == ShortcutDecision::Accept && latest.needs_review() {
+        return Ok(ShortcutOutcome::NeedsReview(latest));
+    }
+    let Some(approval) = state.approvals.pending.remove(latest.request_id) else {
+        return Ok(ShortcutOutcome::NothingPending);
+    };
+
+    let response = ApprovalResponseResult {
+        decision: match decision {
+            ShortcutDecision::Accept => ApprovalDecision::Accept,
+            ShortcutDecision::Decline => ApprovalDecision::Decline,
+        },
+    };
+    let sent = {
+        let server = state.app_server.read().await;
+        match server.as_ref() {
+            Some(process) => process.send_response(approval.request_id, response).await,
+            None => Err(Error::AppServer("App server not running".to_string())),
+        }
+    };
+    if let Err(err) = sent {
+        state.approvals.pending.record(approval);
+        return Err(err);
+    }
+    tracing::info!(
+        "Answered approval request {} by shortcut: {:?}",
+        approval.request_id,
+        decision
+    );
+
+    if confirm {
+        let title = match decision {
+            ShortcutDecision::Accept => i18n::t("approval.shortcut.approved", &[]),
+            ShortcutDecision::Decline => i18n::t("approval.shortcut.denied", &[]),
+        };
+        let body = approval
+            .summary
+            .clone()
+            .unwrap_or_else(|| i18n::t("approval.shortcut.answered", &[]));
+        state
+            .notifier
+            .notify(NotificationKind::ApprovalAnswered, &title, &body);
+    }
+    let answer = ShortcutAnswer {
+        approval: approval.clone(),
+        decision,
+    };
+    state
+        .events
+        .emit("approval-answered-by-shortcut", answer)
+        .await;
+    Ok(ShortcutOutcome::Answered(approval))
+}
@@ -0,0 +1,309 @@
+//! Token budgeting for files attached to a prompt.
+//!
+//! Attachments are taken in order until the total budget runs out. A file
+//! over the per-file limit (or over what is left of the total) is truncated,
+//! reduced to an outline of its declarations, or left out, depending on the
+//! oversize policy. The breakdown tells the user what actually made it into
+//! the prompt; token counts are estimates (about four characters a token).
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::global_state::{AttachmentBudgetSettings, OversizePolicy};
+
+/// Below this many tokens a shortened file is not worth including
+const MIN_USEFUL_TOKENS: u32 = 64;
+
+/// Line prefixes kept in an outline
+const DECLARATION_PREFIXES: &[&str] = &[
+    "pub ",
+    "fn ",
+    "async fn ",
+    "struct ",
+    "enum ",
+    "trait ",
+    "impl",
+    "mod ",
+    "type ",
+    "const ",
+    "static ",
+    "class ",
+    "def ",
+    "async def ",
+    "function ",
+    "async function ",
+    "export ",
+    "interface ",
+    "func ",
+    "package ",
+    "#",
+];
+
+/// A file to attach, as read from the project
+#[derive(Debug, Clone)]
+pub struct AttachmentFile {
+    pub path: String,
+    /// Decoded text, or why the file cannot be attached
+    pub content: std::result::Result<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentAction {
+    Included,
+    Truncated,
+    Summarized,
+    Omitted,
+}
+
+/// What happened to one attachment
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentBreakdown {
+    pub path: String,
+    pub action: AttachmentAction,
+    /// Estimated tokens of the whole file; 0 when it could not be read
+    pub original_tokens: u32,
+    /// Estimated tokens that made it into the prompt
+    pub included_tokens: u32,
+    pub reason: Option<String>,
+}
+
+/// Attachment context for a prompt and how it was put together
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentBudget {
+    pub attachments: Vec<AttachmentBreakdown>,
+    /// Fenced blocks of the included files, ready to append to the prompt
+    pub context: String,
+    pub total_tokens: u32,
+    pub max_total_tokens: u32,
+}
+
+/// Rough token count of `text`
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(4) as u32
+}
+
+/// Fit `files` into the budget, in order
+pub fn plan(files: Vec<AttachmentFile>, settings: &AttachmentBudgetSettings) -> AttachmentBudget {
+    let mut remaining = settings.max_total_tokens;
+    let mut attachments = Vec::with_capacity(files.len());
+    let mut context = String::new();
+
+    for file in files {
+        let text = match file.content {
+            Ok(text) => text,
+            Err(reason) => {
+                attachments.push(omitted(file.path, 0, reason));
+                continue;
+            }
+        };
+        let original_tokens = estimate_tokens(&text);
+        let limit = settings.max_file_tokens.min(remaining);
+
+        let (action, body, reason) = if original_tokens <= limit {
+            (AttachmentAction::Included, text, None)
+        } else {
+            let reason = if original_tokens > settings.max_file_tokens {
+                format!(
+                    "About {original_tokens} tokens, over the per-file limit of {}",
+                    settings.max_file_tokens
+                )
+            } else {
+                format!("Only {remaining} tokens of the attachment budget were left")
+            };
+            if limit < MIN_USEFUL_TOKENS {
+                let reason = "Attachment budget used up by earlier files".to_string();
+                attachments.push(omitted(file.path, original_tokens, reason));
+                continue;
+            }
+            match settings.oversize_policy {
+                OversizePolicy::Omit => {
+                    attachments.push(omitted(file.path, original_tokens, reason));
+                    continue;
+                }
+                OversizePolicy::Truncate => (
+                    AttachmentAction::Truncated,
+                    truncate_middle(&text, limit),
+                    Some(reason),
+                ),
+                OversizePolicy::Summarize => match outline(&text, limit) {
+                    Some(outline) => (AttachmentAction::Summarized, outline, Some(reason)),
+                    None => (
+                        AttachmentAction::Truncated,
+                        truncate_middle(&text, limit),
+                        Some(format!("{reason}; no declarations to outline")),
+                    ),
+                },
+            }
+        };
+
+        let included_tokens = estimate_tokens(&body);
+        remaining = remaining.saturating_sub(included_tokens);
+        push_block(&mut context, &file.path, action, &body);
+        attachments.push(AttachmentBreakdown {
+            path: file.path,
+            action,
+            original_tokens,
+            included_tokens,
+            reason,
+        });
+    }
+
+    AttachmentBudget {
+        attachments,
+        context,
+        total_tokens: settings.max_total_tokens - remaining,
+        max_total_tokens: settings.max_total_tokens,
+    }
+}
+
+fn omitted(path: String, original_tokens: u32, reason: String) -> AttachmentBreakdown {
+    AttachmentBreakdown {
+        path,
+        action: AttachmentAction::Omitted,
+        original_tokens,
+        included_tokens: 0,
+        reason: Some(reason),
+    }
+}
+
+fn push_block(context: &mut String, path: &str, action: AttachmentAction, body: &str) {
+    let note = match action {
+        AttachmentAction::Truncated => " (truncated)",
+        AttachmentAction::Summarized => " (outline)",
+        _ => "",
+    };
+    let lang = crate::file_content::language_hint(Path::new(path)).unwrap_or("");
+    context.push_str(&format!(
+        "### {path}{note}\n```{lang}\n{}\n```\n\n",
+        body.trim_end()
+    ));
+}
+
+/// Keep the start and end of `text` within `max_tokens`, cutting at lines
+fn truncate_middle(text: &str, max_tokens: u32) -> String {
+    // Leave room for the omission marker
+    let budget = (max_tokens as usize * 4).saturating_sub(40);
+    let lines: Vec<&str> = text.lines().collect();
+    let head_budget = budget * 2 / 3;
+
+    // Characters a kept line costs, counting its newline
+    let cost = |line: &str| line.chars().count() + 1;
+
+    let mut used = 0;
+    let mut head = 0;
+    while head < lines.len() && used + cost(lines[head]) <= head_budget {
+        used += cost(lines[head]);
+        head += 1;
+    }
+    let mut tail = lines.len();
+    while tail > head && used + cost(lines[tail - 1]) <= budget {
+        used += cost(lines[tail - 1]);
+        tail -= 1;
+    }
+
+    let mut out = lines[..head].join("\n");
+    if head < tail {
+        out.push_str(&format!("\n[... {} lines omitted ...]\n", tail - head));
+    }
+    out.push_str(&lines[tail..].join("\n"));
+    out
+}
+
+/// Numbered declaration lines of `text` within `max_tokens`, if it has any
+fn outline(text: &str, max_tokens: u32) -> Option<String> {
+    let budget = max_tokens as usize * 4;
+    let mut out = String::new();
+    let mut kept = 0;
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if !DECLARATION_PREFIXES
+            .iter()
+            .any(|prefix| trimmed.starts_with(prefix))
+        {
+            continue;
+        }
+        let entry = format!("{}: {}\n", index + 1, line.trim_end());
+        if out.chars().count() + entry.chars().count() > budget {
+            out.push_str("[... outline cut short ...]\n");
+            break;
+        }
+        out.push_str(&entry);
+        kept += 1;
+    }
+    (kept > 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, text: &str) -> AttachmentFile {
+        AttachmentFile {
+            path: path.to_string(),
+            content: Ok(text.to_string()),
+        }
+    }
+
+    fn settings(total: u32, per_file: u32, policy: OversizePolicy) -> AttachmentBudgetSettings {
+        AttachmentBudgetSettings {
+            max_total_tokens: total,
+            max_file_tokens: per_file,
+            oversize_policy: policy,
+        }
+    }
+
+    #[test]
+    fn test_small_files_included() {
+        let budget = plan(
+            vec![
+                file("a.rs", "fn main() {}\n"),
+                AttachmentFile {
+                    path: "logo.png".to_string(),
+                    content: Err("Binary file".to_string()),
+                },
+            ],
+            &settings(1000, 500, OversizePolicy::Truncate),
+        );
+        assert_eq!(budget.attachments[0].action, AttachmentAction::Included);
+        assert_eq!(budget.attachments[1].action, AttachmentAction::Omitted);
+        assert!(budget
+            .context
+            .contains("### a.rs\n```rust\nfn main() {}\n```"));
+        assert_eq!(budget.total_tokens, budget.attachments[0].included_tokens);
+    }
+
+    #[test]
+    fn test_oversized_file_truncated_within_limit() {
+        let text: String = (0..200).map(|i| format!("line {i}\n")).collect();
+        let budget = plan(
+            vec![file("big.txt", &text)],
+            &settings(1000, 100, OversizePolicy::Truncate),
+        );
+        let entry = &budget.attachments[0];
+        assert_eq!(entry.action, AttachmentAction::Truncated);
+        assert!(entry.included_tokens <= 100);
+        assert!(budget.context.contains("line 0\n"));
+        assert!(budget.context.contains("line 199"));
+        assert!(budget.context.contains("lines omitted"));
+    }
+
+    #[test]
+    fn test_summarize_and_exhausted_budget() {
+        let mut text = String::from("struct Config {\n");
+        text.push_str(&"    field: u32,\n".repeat(100));
+        text.push_str("}\nfn load() -> Config {\n    todo!()\n}\n");
+        let budget = plan(
+            vec![file("config.rs", &text), file("other.rs", &text)],
+            &settings(70, 100, OversizePolicy::Summarize),
+        );
+        assert_eq!(budget.attachments[0].action, AttachmentAction::Summarized);
+        assert!(budget.context.contains("1: struct Config {"));
+        assert!(budget.context.contains("103: fn load() -> Config {"));
+        assert_eq!(budget.attachments[1].action, AttachmentAction::Omitted);
+        assert!(budget.total_tokens <= 70);
+    }
+}
@@ -0,0 +1,251 @@
+//! Forwarding of backend warnings and errors to the renderer.
+//!
+//! A tracing layer turns WARN and ERROR records into `backend-log` events
+//! so the UI can show backend problems as they happen. Records are redacted
+//! (API keys, bearer tokens, secret-looking `key=value` pairs) and
+//! rate-limited; records dropped by the limit are reported as a count with
+//! the next window's first record.
+//!
+//! The layer only queues records. A task started once the app state exists
+//! emits them, so logging never blocks on (or recurses into) the emitter.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+use crate::events::AppEventEmitter;
+use crate::global_state::unix_timestamp_millis;
+
+/// Records forwarded per window; the rest are counted as suppressed
+const MAX_RECORDS_PER_WINDOW: u32 = 20;
+const WINDOW_MS: i64 = 10_000;
+
+/// Records waiting for the forwarding task
+const QUEUE_CAPACITY: usize = 256;
+
+/// Longest message forwarded, in bytes
+const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Targets never forwarded, so emitting cannot feed back into the layer
+const SKIPPED_TARGETS: &[&str] = &[
+    "codex_desktop_lib::events",
+    "codex_desktop_lib::backend_log",
+];
+
+/// Credential patterns and their replacements; also used for export redaction
+pub(crate) static SECRET_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (r"sk-[A-Za-z0-9_\-]{16,}", "sk-[redacted]"),
+        (r"(?i)(bearer\s+)[A-Za-z0-9._\-]{8,}", "${1}[redacted]"),
+        (
+            r#"(?i)\b((?:api[_-]?key|token|secret|password|authorization)["']?\s*[:=]\s*["']?)[^\s"',}]+"#,
+            "${1}[redacted]",
+        ),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| (Regex::new(pattern).expect("valid regex"), replacement))
+    .collect()
+});
+
+/// A forwarded log record
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendLogRecord {
+    /// "warn" or "error"
+    pub level: &'static str,
+    pub target: String,
+    pub message: String,
+    /// Structured fields other than the message
+    pub fields: BTreeMap<String, String>,
+    /// Unix milliseconds
+    pub timestamp: i64,
+    /// Records dropped by the rate limit since the previous forwarded one
+    pub suppressed: u32,
+}
+
+/// Mask credentials in log text
+pub(crate) fn redact_text(text: &str) -> String {
+    SECRET_PATTERNS
+        .iter()
+        .fold(text.to_string(), |text, (pattern, replacement)| {
+            pattern.replace_all(&text, *replacement).into_owned()
+        })
+}
+
+#[derive(Debug, Default)]
+struct RateLimiter {
+    window_start: i64,
+    forwarded: u32,
+    suppressed: u32,
+}
+
+impl RateLimiter {
+    /// `Some(suppressed count)` when a record may be forwarded
+    fn admit(&mut self, now_ms: i64) -> Option<u32> {
+        if now_ms - self.window_start >= WINDOW_MS {
+            self.window_start = now_ms;
+            self.forwarded = 0;
+        }
+        if self.forwarded >= MAX_RECORDS_PER_WINDOW {
+            self.suppressed += 1;
+            return None;
+        }
+        self.forwarded += 1;
+        Some(std::mem::take(&mut self.suppressed))
+    }
+}
+
+#[derive(Default)]
+struct RecordVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl Visit for RecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields
+                .insert(field.name().to_string(), value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            self.fields
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+}
+
+/// Tracing layer queueing WARN and ERROR records for the renderer
+pub struct BackendLogLayer {
+    tx: mpsc::Sender<BackendLogRecord>,
+    limiter: Mutex<RateLimiter>,
+}
+
+impl BackendLogLayer {
+    /// The layer and the receiver to hand to `spawn_forwarder`
+    pub fn new() -> (Self, mpsc::Receiver<BackendLogRecord>) {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        let layer = Self {
+            tx,
+            limiter: Mutex::new(RateLimiter::default()),
+        };
+        (layer, rx)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for BackendLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            Level::ERROR => "error",
+            Level::WARN => "warn",
+            _ => return,
+        };
+        if SKIPPED_TARGETS.contains(&metadata.target()) {
+            return;
+        }
+
+        let now_ms = unix_timestamp_millis();
+        let Some(suppressed) = self.limiter.lock().admit(now_ms) else {
+            return;
+        };
+
+        let mut visitor = RecordVisitor::default();
+        event.record(&mut visitor);
+        let mut message = redact_text(&visitor.message);
+        if message.len() > MAX_MESSAGE_LEN {
+            let cut = (0..=MAX_MESSAGE_LEN)
+                .rev()
+                .find(|&i| message.is_char_boundary(i))
+                .unwrap_or(0);
+            message.truncate(cut);
+            message.push('…');
+        }
+        let fields = visitor
+            .fields
+            .into_iter()
+            .map(|(name, value)| (name, redact_text(&value)))
+            .collect();
+
+        // A full queue means the renderer is not keeping up; drop the record
+        let _ = self.tx.try_send(BackendLogRecord {
+            level,
+            target: metadata.target().to_string(),
+            message,
+            fields,
+            timestamp: now_ms,
+            suppressed,
+        });
+    }
+}
+
+/// Emit queued records as `backend-log` events until the layer is dropped
+pub fn spawn_forwarder(mut rx: mpsc::Receiver<BackendLogRecord>, events: AppEventEmitter) {
+    tauri::async_runtime::spawn(async move {
+        while let Some(record) = rx.recv().await {
+            events.emit("backend-log", record).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_text() {
+        let text =
+            "auth failed for sk-abcdefghijklmnopqrstu with Bearer eyJhbGciOi.x.y token=abc123 ok";
+        let redacted = redact_text(text);
+        assert!(!redacted.contains("abcdefghijklmnop"));
+        assert!(!redacted.contains("eyJhbGciOi"));
+        assert!(!redacted.contains("abc123"));
+        assert!(redacted.contains("token=[redacted]"));
+        assert!(redacted.ends_with(" ok"));
+        assert_eq!(redact_text("plain message"), "plain message");
+    }
+
+    #[test]
+    fn test_layer_forwards_warnings_only() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (layer, mut rx) = BackendLogLayer::new();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("not forwarded");
+            tracing::warn!(path = "a.txt", "bad key sk-abcdefghijklmnopqrstuvwx");
+        });
+
+        let record = rx.try_recv().unwrap();
+        assert_eq!(record.level, "warn");
+        assert_eq!(record.message, "bad key sk-[redacted]");
+        assert_eq!(record.fields.get("path").map(String::as_str), Some("a.txt"));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_counts_suppressed() {
+        let mut limiter = RateLimiter::default();
+        for _ in 0..MAX_RECORDS_PER_WINDOW {
+            assert_eq!(limiter.admit(WINDOW_MS), Some(0));
+        }
+        assert_eq!(limiter.admit(WINDOW_MS + 1), None);
+        assert_eq!(limiter.admit(WINDOW_MS + 2), None);
+        assert_eq!(limiter.admit(2 * WINDOW_MS), Some(2));
+    }
+}
@@ -0,0 +1,283 @@
+//! Registry of background workloads and the limits they run under.
+//!
+//! The periodic loops spawned by `start_background_tasks` and the on-demand
+//! workloads (project watchers, the Codex session index) are registered here
+//! by id. Each unit of work waits in `start` until its task is enabled,
+//! nothing pauses it and a concurrency slot is free. CPU-friendly mode runs
+//! one task at a time and stretches the loops' intervals. Settings come from
+//! the `backgroundTasks` section and apply without a restart.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::global_state::{unix_timestamp_millis, BackgroundTaskSettings};
+use crate::{Error, Result};
+
+pub const PERIODIC_GC: &str = "periodicGc";
+pub const SNAPSHOT_COMPACTION: &str = "snapshotCompaction";
+pub const TURN_QUEUE_SCHEDULE: &str = "turnQueueSchedule";
+pub const LOGIN_POLL: &str = "loginPoll";
+pub const PROJECT_WATCHERS: &str = "projectWatchers";
+pub const CODEX_SESSION_INDEX: &str = "codexSessionIndex";
+
+/// Loop intervals are multiplied by this in CPU-friendly mode
+const CPU_FRIENDLY_INTERVAL_FACTOR: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskKind {
+    Indexer,
+    Watcher,
+    Scheduler,
+    Maintenance,
+}
+
+struct TaskSpec {
+    id: &'static str,
+    kind: TaskKind,
+    description: &'static str,
+}
+
+const TASKS: &[TaskSpec] = &[
+    TaskSpec {
+        id: CODEX_SESSION_INDEX,
+        kind: TaskKind::Indexer,
+        description: "Re-index Codex CLI sessions as they are written",
+    },
+    TaskSpec {
+        id: PROJECT_WATCHERS,
+        kind: TaskKind::Watcher,
+        description: "Watch open projects for file changes",
+    },
+    TaskSpec {
+        id: TURN_QUEUE_SCHEDULE,
+        kind: TaskKind::Scheduler,
+        description: "Start queued turns when their scheduled time comes",
+    },
+    TaskSpec {
+        id: LOGIN_POLL,
+        kind: TaskKind::Scheduler,
+        description: "Check that the login still works",
+    },
+    TaskSpec {
+        id: PERIODIC_GC,
+        kind: TaskKind::Maintenance,
+        description: "Clean up orphaned data once a day",
+    },
+    TaskSpec {
+        id: SNAPSHOT_COMPACTION,
+        kind: TaskKind::Maintenance,
+        description: "Deduplicate and compact snapshot storage",
+    },
+];
+
+/// A registered task, for `get_background_tasks`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTaskStatus {
+    pub id: &'static str,
+    pub kind: TaskKind,
+    pub description: &'static str,
+    /// Not disabled and not paused
+    pub enabled: bool,
+    pub running: bool,
+    /// Unix milliseconds the last unit of work finished
+    pub last_run_at: Option<i64>,
+}
+
+#[derive(Default)]
+struct Registry {
+    settings: BackgroundTaskSettings,
+    running: usize,
+    active: HashMap<&'static str, usize>,
+    last_runs: HashMap<&'static str, i64>,
+}
+
+impl Registry {
+    fn is_enabled(&self, task: &str) -> bool {
+        !self.settings.paused && !self.settings.disabled.iter().any(|id| id == task)
+    }
+
+    /// Units of work allowed at once; 0 means unlimited
+    fn limit(&self) -> usize {
+        if self.settings.cpu_friendly {
+            1
+        } else {
+            self.settings.max_concurrent
+        }
+    }
+
+    fn try_start(&mut self, task: &'static str) -> bool {
+        let limit = self.limit();
+        if !self.is_enabled(task) || (limit > 0 && self.running >= limit) {
+            return false;
+        }
+        self.running += 1;
+        *self.active.entry(task).or_default() += 1;
+        true
+    }
+
+    fn finish(&mut self, task: &'static str, at_ms: i64) {
+        self.running = self.running.saturating_sub(1);
+        if let Some(count) = self.active.get_mut(task) {
+            *count = count.saturating_sub(1);
+        }
+        self.last_runs.insert(task, at_ms);
+    }
+}
+
+pub struct BackgroundTasks {
+    registry: Mutex<Registry>,
+    /// Woken when settings change or a slot frees up
+    changed: Notify,
+}
+
+impl BackgroundTasks {
+    pub fn new(settings: BackgroundTaskSettings) -> Self {
+        Self {
+            registry: Mutex::new(Registry {
+                settings,
+                ..Registry::default()
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Apply new settings; waiting tasks re-check them
+    pub fn configure(&self, settings: BackgroundTaskSettings) {
+        self.registry.lock().settings = settings;
+        self.changed.notify_waiters();
+    }
+
+    /// Whether `task` is allowed to run, ignoring the concurrency limit
+    pub fn is_enabled(&self, task: &str) -> bool {
+        self.registry.lock().is_enabled(task)
+    }
+
+    /// `base`, stretched in CPU-friendly mode
+    pub fn interval(&self, base: Duration) -> Duration {
+        if self.registry.lock().settings.cpu_friendly {
+            base * CPU_FRIENDLY_INTERVAL_FACTOR
+        } else {
+            base
+        }
+    }
+
+    /// Wait until `task` may run a unit of work; the slot is held until the
+    /// permit is dropped
+    pub async fn start(&self, task: &'static str) -> TaskPermit<'_> {
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            // Registered before checking, so a change in between is not missed
+            changed.as_mut().enable();
+            if self.registry.lock().try_start(task) {
+                return TaskPermit { tasks: self, task };
+            }
+            changed.await;
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<BackgroundTaskStatus> {
+        let registry = self.registry.lock();
+        TASKS
+            .iter()
+            .map(|spec| BackgroundTaskStatus {
+                id: spec.id,
+                kind: spec.kind,
+                description: spec.description,
+                enabled: registry.is_enabled(spec.id),
+                running: registry.active.get(spec.id).is_some_and(|count| *count > 0),
+                last_run_at: registry.last_runs.get(spec.id).copied(),
+            })
+            .collect()
+    }
+}
+
+/// A unit of background work in progress
+pub struct TaskPermit<'a> {
+    tasks: &'a BackgroundTasks,
+    task: &'static str,
+}
+
+impl Drop for TaskPermit<'_> {
+    fn drop(&mut self) {
+        self.tasks
+            .registry
+            .lock()
+            .finish(self.task, unix_timestamp_millis());
+        self.tasks.changed.notify_waiters();
+    }
+}
+
+/// Reject settings naming tasks that are not registered
+pub fn validate_settings(settings: &BackgroundTaskSettings) -> Result<()> {
+    for id in &settings.disabled {
+        if !TASKS.iter().any(|spec| spec.id == id) {
+            return Err(Error::Other(format!("Unknown background task: {id}")));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_start_waits_for_settings_and_slots() {
+        let tasks = std::sync::Arc::new(BackgroundTasks::new(BackgroundTaskSettings {
+            paused: true,
+            max_concurrent: 1,
+            ..BackgroundTaskSettings::default()
+        }));
+        assert!(!tasks.is_enabled(PERIODIC_GC));
+
+        let waiting = {
+            let tasks = tasks.clone();
+            tokio::spawn(async move {
+                let _permit = tasks.start(PERIODIC_GC).await;
+            })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        let mut settings = BackgroundTaskSettings {
+            max_concurrent: 1,
+            ..BackgroundTaskSettings::default()
+        };
+        tasks.configure(settings.clone());
+        let permit = tasks.start(LOGIN_POLL).await;
+        // The only slot is taken until the login poll finishes
+        let statuses = tasks.statuses();
+        let login = statuses.iter().find(|s| s.id == LOGIN_POLL).unwrap();
+        assert!(login.running);
+        drop(permit);
+        waiting.await.unwrap();
+        let statuses = tasks.statuses();
+        assert!(statuses.iter().all(|status| !status.running));
+        assert!(statuses
+            .iter()
+            .find(|s| s.id == PERIODIC_GC)
+            .unwrap()
+            .last_run_at
+            .is_some());
+
+        settings.disabled = vec![PROJECT_WATCHERS.to_string()];
+        settings.cpu_friendly = true;
+        tasks.configure(settings.clone());
+        assert!(!tasks.is_enabled(PROJECT_WATCHERS));
+        assert_eq!(
+            tasks.interval(Duration::from_secs(15)),
+            Duration::from_secs(60)
+        );
+
+        assert!(validate_settings(&settings).is_ok());
+        settings.disabled.push("gitCache".to_string());
+        assert!(validate_settings(&settings).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Batch runs of one prompt across several projects.
+//!
+//! Each target (a project, or one of its git worktrees) gets its own thread
+//! and turn; targets run side by side within the turn concurrency limits.
+//! When a target's turn finishes, its working-tree diff is summarized so the
+//! caller can see at a glance which repositories were changed.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::global_state::TurnConcurrencySettings;
+use crate::{Error, Result};
+
+/// Targets accepted in one batch
+pub const MAX_TARGETS: usize = 50;
+
+/// How long a single target's turn may run before it is interrupted
+pub const TARGET_TIMEOUT_SECS: u64 = 60 * 60;
+
+/// Approval policy used when the caller gives none; nobody is watching a
+/// batch run to answer approval requests
+pub const DEFAULT_APPROVAL_POLICY: &str = "never";
+
+/// Sandbox used when the caller gives none
+pub const DEFAULT_SANDBOX: &str = "workspace-write";
+
+/// A project, or one of its worktrees, to run the batch prompt in
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchTarget {
+    pub project_id: String,
+    /// Worktree of the project to run in instead of the project root
+    #[serde(default)]
+    pub worktree_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchTargetStatus {
+    Running,
+    Completed,
+    Failed,
+    Interrupted,
+    TimedOut,
+}
+
+impl BatchTargetStatus {
+    /// Target status for a finished turn's status
+    pub fn from_turn_status(status: &str) -> Self {
+        match status {
+            "completed" => Self::Completed,
+            "interrupted" => Self::Interrupted,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// Files and lines changed in a working tree
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffSummary {
+    pub files: Vec<String>,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Outcome of the batch prompt in one target
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchTargetResult {
+    pub project_id: String,
+    pub worktree_path: Option<String>,
+    /// Directory the thread ran in
+    pub cwd: String,
+    pub thread_id: Option<String>,
+    pub status: BatchTargetStatus,
+    pub error: Option<String>,
+    /// Working-tree changes after the turn; `None` outside git repositories
+    pub diff: Option<DiffSummary>,
+    /// The working tree already had changes before the turn, so `diff`
+    /// includes more than the turn's own edits
+    pub had_prior_changes: bool,
+    pub duration_ms: i64,
+}
+
+impl BatchTargetResult {
+    pub fn running(target: &BatchTarget, cwd: &str) -> Self {
+        Self {
+            project_id: target.project_id.clone(),
+            worktree_path: target.worktree_path.clone(),
+            cwd: cwd.to_string(),
+            thread_id: None,
+            status: BatchTargetStatus::Running,
+            error: None,
+            diff: None,
+            had_prior_changes: false,
+            duration_ms: 0,
+        }
+    }
+}
+
+/// Running totals of a batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress {
+    pub total: usize,
+    pub finished: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+}
+
+impl BatchProgress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, status: BatchTargetStatus) {
+        match status {
+            BatchTargetStatus::Running => return,
+            BatchTargetStatus::Completed => self.succeeded += 1,
+            _ => self.failed += 1,
+        }
+        self.finished += 1;
+    }
+}
+
+/// Payload of `batch-progress`, emitted when a target starts or finishes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgressEvent {
+    pub batch_id: String,
+    #[serde(flatten)]
+    pub progress: BatchProgress,
+    pub target: BatchTargetResult,
+}
+
+/// Results of a finished batch, in target order
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub batch_id: String,
+    #[serde(flatten)]
+    pub progress: BatchProgress,
+    pub results: Vec<BatchTargetResult>,
+}
+
+/// Reject empty, oversized or duplicate target lists
+pub fn validate_targets(targets: &[BatchTarget]) -> Result<()> {
+    if targets.is_empty() {
+        return Err(Error::Other("Batch needs at least one target".to_string()));
+    }
+    if targets.len() > MAX_TARGETS {
+        return Err(Error::Other(format!(
+            "Batch has too many targets (max {MAX_TARGETS})"
+        )));
+    }
+    let mut seen = HashSet::new();
+    for target in targets {
+        if !seen.insert((&target.project_id, &target.worktree_path)) {
+            return Err(Error::Other(format!(
+                "Batch lists project {} twice",
+                target.project_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Targets run at the same time, kept within the global turn limit
+pub fn parallelism(limits: &TurnConcurrencySettings, targets: usize) -> usize {
+    let limit = match limits.max_concurrent_turns {
+        0 => targets,
+        max => targets.min(max as usize),
+    };
+    limit.max(1)
+}
+
+/// Count files and lines changed in a unified diff
+pub fn summarize_diff(diff: &str) -> DiffSummary {
+    let mut summary = DiffSummary::default();
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            // "a/<path> b/<path>"; take the new side
+            let path = header
+                .rsplit_once(" b/")
+                .map(|(_, path)| path)
+                .unwrap_or(header);
+            summary.files.push(path.to_string());
+        } else if line.starts_with("+++") || line.starts_with("---") {
+            continue;
+        } else if line.starts_with('+') {
+            summary.insertions += 1;
+        } else if line.starts_with('-') {
+            summary.deletions += 1;
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(project_id: &str, worktree_path: Option<&str>) -> BatchTarget {
+        BatchTarget {
+            project_id: project_id.to_string(),
+            worktree_path: worktree_path.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_summarize_diff() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n\
+                    index 1..2 100644\n\
+                    --- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -1,2 +1,2 @@\n\
+                    -old\n\
+                    +new\n\
+                    +added\n\
+                    diff --git a/notes.md b/notes.md\n\
+                    new file mode 100644\n\
+                    --- /dev/null\n\
+                    +++ b/notes.md\n\
+                    @@ -0,0 +1 @@\n\
+                    +hello\n";
+        let summary = summarize_diff(diff);
+        assert_eq!(summary.files, vec!["src/lib.rs", "notes.md"]);
+        assert_eq!(summary.insertions, 3);
+        assert_eq!(summary.deletions, 1);
+        assert_eq!(summarize_diff(""), DiffSummary::default());
+    }
+
+    #[test]
+    fn test_validate_targets() {
+        assert!(validate_targets(&[]).is_err());
+        assert!(validate_targets(&[target("a", None), target("a", Some("/wt"))]).is_ok());
+        assert!(validate_targets(&[target("a", None), target("a", None)]).is_err());
+        let many: Vec<_> = (0..=MAX_TARGETS)
+            .map(|i| target(&i.to_string(), None))
+            .collect();
+        assert!(validate_targets(&many).is_err());
+    }
+
+    #[test]
+    fn test_progress_and_parallelism() {
+        let mut progress = BatchProgress::new(3);
+        progress.record(BatchTargetStatus::Running);
+        progress.record(BatchTargetStatus::Completed);
+        progress.record(BatchTargetStatus::TimedOut);
+        assert_eq!(progress.finished, 2);
+        assert_eq!(progress.succeeded, 1);
+        assert_eq!(progress.failed, 1);
+
+        let limits = TurnConcurrencySettings {
+            max_concurrent_turns: 4,
+            max_turns_per_project: 0,
+        };
+        assert_eq!(parallelism(&limits, 10), 4);
+        assert_eq!(parallelism(&limits, 2), 2);
+        let unlimited = TurnConcurrencySettings {
+            max_concurrent_turns: 0,
+            max_turns_per_project: 0,
+        };
+        assert_eq!(parallelism(&unlimited, 7), 7);
+    }
+}
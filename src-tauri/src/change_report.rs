@@ -0,0 +1,709 @@
+//! Project change reports for standups and sprint reviews.
+//!
+//! Gathers a project's sessions, commits (on any local branch), pull
+//! requests and per-file diff stats within a date range, then renders them
+//! to Markdown or standalone HTML with the transcript exporter's helpers;
+//! JSON is the gathered report as is. Pull requests come from the GitHub
+//! CLI and are left out with a note when it is missing or not logged in.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::database::{Database, Project, SessionFilter, SessionMetadata};
+use crate::transcript_export::{code_block, escape_html, html_diff, ExportFormat, HTML_STYLE};
+use crate::{Error, Result};
+
+/// Commits listed at most; older ones in the range are counted but omitted
+const MAX_REPORT_COMMITS: usize = 500;
+
+/// Pull requests listed at most
+const MAX_REPORT_PULL_REQUESTS: usize = 100;
+
+/// Patch text kept in a report; the rest is cut off
+const MAX_REPORT_DIFF_BYTES: usize = 200_000;
+
+/// Field and record separators in `git log` output
+const FIELD_SEP: char = '\u{1f}';
+const RECORD_SEP: char = '\u{1e}';
+
+/// Unix seconds, inclusive at both ends
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSession {
+    pub session_id: String,
+    pub title: String,
+    pub status: String,
+    pub created_at: i64,
+    pub last_active_at: i64,
+    pub turn_count: u32,
+    pub files_changed_count: u32,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportCommit {
+    pub sha: String,
+    pub short_sha: String,
+    pub title: String,
+    pub author: String,
+    pub committed_at: i64,
+    pub insertions: u64,
+    pub deletions: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportPullRequest {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub state: String,
+    pub author: Option<String>,
+    pub created_at: String,
+}
+
+/// Lines changed in one file across the range's commits
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportFileStat {
+    pub path: String,
+    pub commits: usize,
+    pub insertions: u64,
+    pub deletions: u64,
+    pub binary: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeReport {
+    pub project_name: String,
+    pub project_path: String,
+    pub range: ReportRange,
+    /// Unix seconds
+    pub generated_at: i64,
+    pub sessions: Vec<ReportSession>,
+    pub commits: Vec<ReportCommit>,
+    /// Commits in the range beyond `MAX_REPORT_COMMITS`
+    pub omitted_commits: usize,
+    pub pull_requests: Vec<ReportPullRequest>,
+    /// Most changed first
+    pub files: Vec<ReportFileStat>,
+    /// Patches of the range's commits, oldest first, if requested
+    pub diff: Option<String>,
+    pub diff_truncated: bool,
+    /// Parts that could not be gathered
+    pub notes: Vec<String>,
+}
+
+/// Gather a report of `project` within `range`. Blocking.
+pub fn generate(
+    db: &Database,
+    project: &Project,
+    range: ReportRange,
+    include_diff: bool,
+    now: i64,
+) -> Result<ChangeReport> {
+    if range.from > range.to {
+        return Err(Error::Other(
+            "Report range must not end before it starts".to_string(),
+        ));
+    }
+    let path = Path::new(&project.path);
+    let mut report = ChangeReport {
+        project_name: project
+            .display_name
+            .clone()
+            .unwrap_or_else(|| project.path.clone()),
+        project_path: project.path.clone(),
+        range,
+        generated_at: now,
+        sessions: sessions_in_range(db, &project.id, range)?,
+        commits: Vec::new(),
+        omitted_commits: 0,
+        pull_requests: Vec::new(),
+        files: Vec::new(),
+        diff: None,
+        diff_truncated: false,
+        notes: Vec::new(),
+    };
+
+    if !path.join(".git").exists() {
+        report
+            .notes
+            .push("Not a git repository; commits and diffs are left out".to_string());
+        return Ok(report);
+    }
+
+    let range_args = git_range_args(range);
+    let mut args = vec!["log", "--branches", "--numstat"];
+    args.extend(range_args.iter().map(String::as_str));
+    let format =
+        format!("--format={RECORD_SEP}%H{FIELD_SEP}%h{FIELD_SEP}%s{FIELD_SEP}%an{FIELD_SEP}%ct");
+    args.push(&format);
+    match run(path, "git", &args) {
+        Ok(log) => {
+            let (commits, files) = parse_log(&log, range);
+            report.omitted_commits = commits.len().saturating_sub(MAX_REPORT_COMMITS);
+            report.commits = commits.into_iter().take(MAX_REPORT_COMMITS).collect();
+            report.files = files;
+        }
+        Err(err) => report.notes.push(format!("Commits unavailable: {err}")),
+    }
+
+    if include_diff && !report.commits.is_empty() {
+        let mut args = vec![
+            "log",
+            "--branches",
+            "--reverse",
+            "--patch",
+            "--format=commit %h %s",
+        ];
+        args.extend(range_args.iter().map(String::as_str));
+        match run(path, "git", &args) {
+            Ok(mut diff) => {
+                if diff.len() > MAX_REPORT_DIFF_BYTES {
+                    let mut end = MAX_REPORT_DIFF_BYTES;
+                    while !diff.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    diff.truncate(end);
+                    report.diff_truncated = true;
+                }
+                report.diff = Some(diff);
+            }
+            Err(err) => report.notes.push(format!("Diff unavailable: {err}")),
+        }
+    }
+
+    match pull_requests(path, range) {
+        Ok(pull_requests) => report.pull_requests = pull_requests,
+        Err(err) => report
+            .notes
+            .push(format!("Pull requests unavailable: {err}")),
+    }
+
+    Ok(report)
+}
+
+/// Sessions, active or archived, with activity overlapping `range`
+fn sessions_in_range(
+    db: &Database,
+    project_id: &str,
+    range: ReportRange,
+) -> Result<Vec<ReportSession>> {
+    let mut sessions = Vec::new();
+    for archived in [false, true] {
+        let filter = SessionFilter {
+            archived,
+            ..SessionFilter::default()
+        };
+        sessions.extend(db.get_sessions_for_project(project_id, &filter)?);
+    }
+    let mut sessions: Vec<ReportSession> = sessions
+        .into_iter()
+        .map(report_session)
+        .filter(|session| session.created_at <= range.to && session.last_active_at >= range.from)
+        .collect();
+    sessions.sort_by_key(|session| session.created_at);
+    Ok(sessions)
+}
+
+fn report_session(session: SessionMetadata) -> ReportSession {
+    let title = session
+        .title
+        .or(session.first_message)
+        .map(|title| title.lines().next().unwrap_or_default().trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "Untitled session".to_string());
+    ReportSession {
+        title,
+        status: session.status.as_str().to_string(),
+        last_active_at: session
+            .last_accessed_at
+            .unwrap_or(session.created_at)
+            .max(session.created_at),
+        created_at: session.created_at,
+        turn_count: session.turn_count,
+        files_changed_count: session.files_changed_count,
+        model: session.last_model,
+        session_id: session.session_id,
+    }
+}
+
+fn git_range_args(range: ReportRange) -> Vec<String> {
+    let date = |secs: i64| {
+        chrono::DateTime::from_timestamp(secs, 0)
+            .map(|date| date.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+            .unwrap_or_default()
+    };
+    vec![
+        format!("--since={}", date(range.from)),
+        format!("--until={}", date(range.to)),
+    ]
+}
+
+/// Parse `git log --numstat` output into commits (newest first) and
+/// per-file totals
+fn parse_log(log: &str, range: ReportRange) -> (Vec<ReportCommit>, Vec<ReportFileStat>) {
+    let mut commits = Vec::new();
+    let mut files: BTreeMap<String, ReportFileStat> = BTreeMap::new();
+    for record in log
+        .split(RECORD_SEP)
+        .filter(|record| !record.trim().is_empty())
+    {
+        let mut lines = record.lines();
+        let header: Vec<&str> = lines.next().unwrap_or_default().split(FIELD_SEP).collect();
+        let [sha, short_sha, title, author, committed_at] = header[..] else {
+            continue;
+        };
+        let committed_at = committed_at.trim().parse().unwrap_or_default();
+        // git applies --since/--until loosely to out-of-order history
+        if committed_at < range.from || committed_at > range.to {
+            continue;
+        }
+        let mut commit = ReportCommit {
+            sha: sha.to_string(),
+            short_sha: short_sha.to_string(),
+            title: title.to_string(),
+            author: author.to_string(),
+            committed_at,
+            insertions: 0,
+            deletions: 0,
+        };
+        for line in lines {
+            let mut parts = line.splitn(3, '\t');
+            let (Some(added), Some(removed), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let file = files
+                .entry(path.to_string())
+                .or_insert_with(|| ReportFileStat {
+                    path: path.to_string(),
+                    commits: 0,
+                    insertions: 0,
+                    deletions: 0,
+                    binary: false,
+                });
+            file.commits += 1;
+            // Binary files show "-" for both counts
+            match (added.parse::<u64>(), removed.parse::<u64>()) {
+                (Ok(added), Ok(removed)) => {
+                    file.insertions += added;
+                    file.deletions += removed;
+                    commit.insertions += added;
+                    commit.deletions += removed;
+                }
+                _ => file.binary = true,
+            }
+        }
+        commits.push(commit);
+    }
+
+    let mut files: Vec<ReportFileStat> = files.into_values().collect();
+    files.sort_by(|a, b| {
+        (b.insertions + b.deletions)
+            .cmp(&(a.insertions + a.deletions))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    (commits, files)
+}
+
+fn pull_requests(path: &Path, range: ReportRange) -> Result<Vec<ReportPullRequest>> {
+    let day = |secs: i64| {
+        chrono::DateTime::from_timestamp(secs, 0)
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let search = format!("created:{}..{}", day(range.from), day(range.to));
+    let limit = MAX_REPORT_PULL_REQUESTS.to_string();
+    let output = run(
+        path,
+        "gh",
+        &[
+            "pr",
+            "list",
+            "--state",
+            "all",
+            "--limit",
+            &limit,
+            "--search",
+            &search,
+            "--json",
+            "number,title,url,state,author,createdAt",
+        ],
+    )?;
+    let items: Vec<JsonValue> = serde_json::from_str(&output)?;
+    Ok(items
+        .iter()
+        .map(|item| ReportPullRequest {
+            number: item
+                .get("number")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or_default(),
+            title: json_str(item, "title"),
+            url: json_str(item, "url"),
+            state: json_str(item, "state"),
+            author: item
+                .pointer("/author/login")
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+            created_at: json_str(item, "createdAt"),
+        })
+        .collect())
+}
+
+fn json_str(item: &JsonValue, key: &str) -> String {
+    item.get(key)
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn run(path: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(path)
+        .output()
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                Error::Other(format!("{program} is not installed"))
+            } else {
+                Error::Other(format!("Failed to run {program}: {err}"))
+            }
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Other(format!(
+            "{program} {} failed: {}",
+            args.first().unwrap_or(&""),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Render `report` in `format`
+pub fn render(format: ExportFormat, report: &ChangeReport) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Markdown => render_markdown(report),
+        ExportFormat::Html => render_html(report),
+        ExportFormat::Json => serde_json::to_string_pretty(report)?,
+    })
+}
+
+fn date(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn title(report: &ChangeReport) -> String {
+    format!("Changes in {}", report.project_name)
+}
+
+fn header_lines(report: &ChangeReport) -> Vec<(&'static str, String)> {
+    let insertions: u64 = report.commits.iter().map(|commit| commit.insertions).sum();
+    let deletions: u64 = report.commits.iter().map(|commit| commit.deletions).sum();
+    vec![
+        ("Project", report.project_path.clone()),
+        (
+            "Range",
+            format!("{} – {}", date(report.range.from), date(report.range.to)),
+        ),
+        (
+            "Summary",
+            format!(
+                "{} sessions, {} commits, {} pull requests, {} files (+{insertions} −{deletions})",
+                report.sessions.len(),
+                report.commits.len() + report.omitted_commits,
+                report.pull_requests.len(),
+                report.files.len(),
+            ),
+        ),
+        ("Generated", date(report.generated_at)),
+    ]
+}
+
+/// Markdown table cell text
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_markdown(report: &ChangeReport) -> String {
+    let mut out = format!("# {}\n\n", title(report));
+    for (label, value) in header_lines(report) {
+        out.push_str(&format!("- **{label}**: {value}\n"));
+    }
+    out.push('\n');
+    for note in &report.notes {
+        out.push_str(&format!("> {}\n\n", cell(note)));
+    }
+
+    out.push_str("## Sessions\n\n");
+    if report.sessions.is_empty() {
+        out.push_str("*None*\n\n");
+    } else {
+        out.push_str(
+            "| Session | Status | Turns | Files changed | Last active |\n|---|---|---|---|---|\n",
+        );
+        for session in &report.sessions {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                cell(&session.title),
+                session.status,
+                session.turn_count,
+                session.files_changed_count,
+                date(session.last_active_at)
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Commits\n\n");
+    if report.commits.is_empty() {
+        out.push_str("*None*\n\n");
+    } else {
+        for commit in &report.commits {
+            out.push_str(&format!(
+                "- `{}` {} — {}, {} (+{} −{})\n",
+                commit.short_sha,
+                commit.title,
+                commit.author,
+                date(commit.committed_at),
+                commit.insertions,
+                commit.deletions
+            ));
+        }
+        if report.omitted_commits > 0 {
+            out.push_str(&format!(
+                "- *{} older commits omitted*\n",
+                report.omitted_commits
+            ));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Pull requests\n\n");
+    if report.pull_requests.is_empty() {
+        out.push_str("*None*\n\n");
+    } else {
+        for pr in &report.pull_requests {
+            out.push_str(&format!(
+                "- [#{} {}]({}) — {}{}\n",
+                pr.number,
+                pr.title,
+                pr.url,
+                pr.state.to_lowercase(),
+                pr.author
+                    .as_ref()
+                    .map(|author| format!(", @{author}"))
+                    .unwrap_or_default()
+            ));
+        }
+        out.push('\n');
+    }
+
+    if !report.files.is_empty() {
+        out.push_str("## Files\n\n| File | Commits | + | − |\n|---|---|---|---|\n");
+        for file in &report.files {
+            let (added, removed) = if file.binary {
+                ("binary".to_string(), String::new())
+            } else {
+                (file.insertions.to_string(), file.deletions.to_string())
+            };
+            out.push_str(&format!(
+                "| `{}` | {} | {added} | {removed} |\n",
+                cell(&file.path),
+                file.commits
+            ));
+        }
+        out.push('\n');
+    }
+
+    if let Some(diff) = &report.diff {
+        out.push_str("## Diff\n\n");
+        out.push_str(&code_block("diff", diff));
+        if report.diff_truncated {
+            out.push_str("*Diff truncated*\n\n");
+        }
+    }
+    out
+}
+
+fn render_html(report: &ChangeReport) -> String {
+    let title = escape_html(&title(report));
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}table{{border-collapse:collapse}}\
+         td,th{{text-align:left;padding:.25rem .75rem .25rem 0}}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<dl>"
+    );
+    for (label, value) in header_lines(report) {
+        out.push_str(&format!("<dt>{label}</dt><dd>{}</dd>", escape_html(&value)));
+    }
+    out.push_str("</dl>\n");
+    for note in &report.notes {
+        out.push_str(&format!("<p class=\"status\">{}</p>\n", escape_html(note)));
+    }
+
+    out.push_str("<section><h2>Sessions</h2>");
+    if report.sessions.is_empty() {
+        out.push_str("<p>None</p>");
+    } else {
+        out.push_str("<table><tr><th>Session</th><th>Status</th><th>Turns</th><th>Files changed</th><th>Last active</th></tr>");
+        for session in &report.sessions {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&session.title),
+                session.status,
+                session.turn_count,
+                session.files_changed_count,
+                date(session.last_active_at)
+            ));
+        }
+        out.push_str("</table>");
+    }
+    out.push_str("</section>\n<section><h2>Commits</h2>");
+    if report.commits.is_empty() {
+        out.push_str("<p>None</p>");
+    } else {
+        out.push_str("<ul>");
+        for commit in &report.commits {
+            out.push_str(&format!(
+                "<li><code>{}</code> {} — {}, {} (<span class=\"add\">+{}</span> <span class=\"del\">−{}</span>)</li>",
+                escape_html(&commit.short_sha),
+                escape_html(&commit.title),
+                escape_html(&commit.author),
+                date(commit.committed_at),
+                commit.insertions,
+                commit.deletions
+            ));
+        }
+        if report.omitted_commits > 0 {
+            out.push_str(&format!(
+                "<li class=\"status\">{} older commits omitted</li>",
+                report.omitted_commits
+            ));
+        }
+        out.push_str("</ul>");
+    }
+    out.push_str("</section>\n<section><h2>Pull requests</h2>");
+    if report.pull_requests.is_empty() {
+        out.push_str("<p>None</p>");
+    } else {
+        out.push_str("<ul>");
+        for pr in &report.pull_requests {
+            out.push_str(&format!(
+                "<li><a href=\"{}\">#{} {}</a> — {}{}</li>",
+                escape_html(&pr.url),
+                pr.number,
+                escape_html(&pr.title),
+                escape_html(&pr.state.to_lowercase()),
+                pr.author
+                    .as_ref()
+                    .map(|author| format!(", @{}", escape_html(author)))
+                    .unwrap_or_default()
+            ));
+        }
+        out.push_str("</ul>");
+    }
+    out.push_str("</section>\n");
+
+    if !report.files.is_empty() {
+        out.push_str("<section><h2>Files</h2><table><tr><th>File</th><th>Commits</th><th>+</th><th>−</th></tr>");
+        for file in &report.files {
+            let (added, removed) = if file.binary {
+                ("binary".to_string(), String::new())
+            } else {
+                (file.insertions.to_string(), file.deletions.to_string())
+            };
+            out.push_str(&format!(
+                "<tr><td><code>{}</code></td><td>{}</td><td class=\"add\">{added}</td><td class=\"del\">{removed}</td></tr>",
+                escape_html(&file.path),
+                file.commits
+            ));
+        }
+        out.push_str("</table></section>\n");
+    }
+
+    if let Some(diff) = &report.diff {
+        out.push_str("<section><h2>Diff</h2>");
+        out.push_str(&html_diff(diff));
+        if report.diff_truncated {
+            out.push_str("<p class=\"status\">Diff truncated</p>");
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_and_render() {
+        let log = format!(
+            "{RECORD_SEP}aaa111{FIELD_SEP}aaa{FIELD_SEP}Add parser{FIELD_SEP}Ana{FIELD_SEP}200\n\n\
+             10\t2\tsrc/parser.rs\n-\t-\tlogo.png\n\
+             {RECORD_SEP}bbb222{FIELD_SEP}bbb{FIELD_SEP}Fix | pipe{FIELD_SEP}Ben{FIELD_SEP}150\n\n\
+             1\t1\tsrc/parser.rs\n\
+             {RECORD_SEP}ccc333{FIELD_SEP}ccc{FIELD_SEP}Too old{FIELD_SEP}Ana{FIELD_SEP}50\n\n\
+             5\t0\tREADME.md\n"
+        );
+        let range = ReportRange { from: 100, to: 300 };
+        let (commits, files) = parse_log(&log, range);
+        assert_eq!(commits.len(), 2);
+        assert_eq!((commits[0].insertions, commits[0].deletions), (10, 2));
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/parser.rs");
+        assert_eq!((files[0].commits, files[0].insertions), (2, 11));
+        assert!(files[1].binary);
+
+        let report = ChangeReport {
+            project_name: "demo".to_string(),
+            project_path: "/work/demo".to_string(),
+            range,
+            generated_at: 400,
+            sessions: Vec::new(),
+            commits,
+            omitted_commits: 0,
+            pull_requests: vec![ReportPullRequest {
+                number: 7,
+                title: "Parser <v2>".to_string(),
+                url: "https://github.com/o/demo/pull/7".to_string(),
+                state: "MERGED".to_string(),
+                author: Some("ana".to_string()),
+                created_at: "1970-01-01T00:02:30Z".to_string(),
+            }],
+            files,
+            diff: Some("+new line\n".to_string()),
+            diff_truncated: false,
+            notes: Vec::new(),
+        };
+        let markdown = render(ExportFormat::Markdown, &report).unwrap();
+        assert!(markdown.starts_with("# Changes in demo\n"));
+        assert!(markdown.contains("0 sessions, 2 commits, 1 pull requests, 2 files (+11 −3)"));
+        assert!(markdown
+            .contains("- [#7 Parser <v2>](https://github.com/o/demo/pull/7) — merged, @ana"));
+        assert!(markdown.contains("| `logo.png` | 1 | binary |  |"));
+
+        let html = render(ExportFormat::Html, &report).unwrap();
+        assert!(html.contains("#7 Parser &lt;v2&gt;"));
+        assert!(html.contains("<span class=\"add\">+new line</span>"));
+    }
+}
@@ -0,0 +1,350 @@
+//! CI status of a project's current branch, through `gh`.
+//!
+//! Combines the branch's recent GitHub Actions runs with the checks of its
+//! pull request, if it has one, into a single passing / failing / pending
+//! verdict. `CiWatchers` polls it for watched projects and emits
+//! `ci-status-changed` whenever it changes, so a push that breaks CI shows
+//! up without leaving the app.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::events::AppEventEmitter;
+use crate::git;
+use crate::pull_requests::{self, PullRequestCheck};
+use crate::{Error, Result};
+
+/// Workflow runs listed per status
+const RUN_LIMIT: u32 = 20;
+
+/// Poll interval while runs or checks are still going
+const PENDING_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Poll interval once everything has settled, or after an error
+const SETTLED_POLL_INTERVAL: Duration = Duration::from_secs(180);
+
+const RUN_FIELDS: &str =
+    "databaseId,name,displayTitle,status,conclusion,event,headSha,url,createdAt,updatedAt";
+
+/// Overall CI verdict for a branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CiState {
+    Passing,
+    Failing,
+    Pending,
+    /// No runs or checks for the branch
+    Unknown,
+}
+
+/// A GitHub Actions workflow run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRun {
+    pub id: u64,
+    /// Workflow name
+    pub name: String,
+    pub title: String,
+    /// "queued", "in_progress", "completed", ...
+    pub status: String,
+    /// "success", "failure", "cancelled", ...; `None` until completed
+    pub conclusion: Option<String>,
+    pub event: String,
+    pub head_sha: String,
+    pub url: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// CI status of a branch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CiStatus {
+    pub branch: String,
+    /// Local commit the branch points at
+    pub head_sha: Option<String>,
+    /// Open pull request for the branch
+    pub pull_request: Option<u64>,
+    pub state: CiState,
+    /// Newest first
+    pub runs: Vec<WorkflowRun>,
+    /// Checks of `pull_request`; empty without one
+    pub checks: Vec<PullRequestCheck>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhRun {
+    database_id: u64,
+    name: String,
+    display_title: String,
+    status: String,
+    conclusion: Option<String>,
+    event: String,
+    head_sha: String,
+    url: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhPullRequestNumber {
+    number: u64,
+}
+
+impl GhRun {
+    fn into_run(self) -> WorkflowRun {
+        WorkflowRun {
+            id: self.database_id,
+            name: self.name,
+            title: self.display_title,
+            status: self.status,
+            conclusion: self.conclusion.filter(|conclusion| !conclusion.is_empty()),
+            event: self.event,
+            head_sha: self.head_sha,
+            url: self.url,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+/// CI status of the branch checked out in `dir`. Blocking.
+pub fn get(dir: &Path) -> Result<CiStatus> {
+    let branch = git::run(dir, &["symbolic-ref", "-q", "--short", "HEAD"])
+        .map_err(|_| Error::Git("HEAD is not on a branch".to_string()))?
+        .trim()
+        .to_string();
+    let head_sha = git::run(dir, &["rev-parse", "-q", "--verify", "HEAD"])
+        .ok()
+        .map(|sha| sha.trim().to_string())
+        .filter(|sha| !sha.is_empty());
+
+    let limit = RUN_LIMIT.to_string();
+    let json = pull_requests::gh(
+        dir,
+        &[
+            "run", "list", "--branch", &branch, "--limit", &limit, "--json", RUN_FIELDS,
+        ],
+    )?;
+    let runs: Vec<GhRun> = pull_requests::parse("run list", &json)?;
+    let runs: Vec<WorkflowRun> = runs.into_iter().map(GhRun::into_run).collect();
+
+    // `gh pr view` fails when the branch has no pull request
+    let pull_request = pull_requests::gh(dir, &["pr", "view", &branch, "--json", "number"])
+        .ok()
+        .and_then(|json| pull_requests::parse::<GhPullRequestNumber>("pr view", &json).ok())
+        .map(|pull_request| pull_request.number);
+    let checks = match pull_request {
+        Some(number) => pull_requests::checks(dir, &number.to_string()),
+        None => Vec::new(),
+    };
+
+    let state = rollup(&runs, &checks, head_sha.as_deref());
+    Ok(CiStatus {
+        branch,
+        head_sha,
+        pull_request,
+        state,
+        runs,
+        checks,
+    })
+}
+
+/// Overall verdict: the pull request's checks when there are any, else the
+/// latest run of each workflow on the newest pushed commit
+fn rollup(runs: &[WorkflowRun], checks: &[PullRequestCheck], head_sha: Option<&str>) -> CiState {
+    if !checks.is_empty() {
+        let buckets = checks.iter().map(|check| check.bucket.as_deref());
+        return combine(buckets.map(|bucket| match bucket {
+            Some("fail") | Some("cancel") => CiState::Failing,
+            Some("pending") => CiState::Pending,
+            _ => CiState::Passing,
+        }));
+    }
+
+    // Runs for the local HEAD when it was pushed, else the newest ones
+    let Some(sha) = runs
+        .iter()
+        .find(|run| Some(run.head_sha.as_str()) == head_sha)
+        .or_else(|| runs.first())
+        .map(|run| run.head_sha.as_str())
+    else {
+        return CiState::Unknown;
+    };
+    let mut seen = Vec::new();
+    let latest = runs.iter().filter(|run| run.head_sha == sha).filter(|run| {
+        let first = !seen.contains(&run.name.as_str());
+        seen.push(run.name.as_str());
+        first
+    });
+    combine(latest.map(run_state))
+}
+
+fn run_state(run: &WorkflowRun) -> CiState {
+    match (run.status.as_str(), run.conclusion.as_deref()) {
+        ("completed", Some("success" | "skipped" | "neutral")) => CiState::Passing,
+        ("completed", _) => CiState::Failing,
+        _ => CiState::Pending,
+    }
+}
+
+/// Failing beats pending beats passing
+fn combine(states: impl Iterator<Item = CiState>) -> CiState {
+    states.fold(CiState::Unknown, |overall, state| match (overall, state) {
+        (CiState::Failing, _) | (_, CiState::Failing) => CiState::Failing,
+        (CiState::Pending, _) | (_, CiState::Pending) => CiState::Pending,
+        _ => CiState::Passing,
+    })
+}
+
+/// Projects whose CI status is being polled, keyed by project id
+pub struct CiWatchers {
+    tasks: Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>,
+}
+
+impl Default for CiWatchers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CiWatchers {
+    pub fn new() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start polling the CI status of `root` for a project; a no-op if it
+    /// is already polled
+    pub fn watch(&self, project_id: &str, root: PathBuf, events: AppEventEmitter) {
+        let mut tasks = self.tasks.lock();
+        if tasks.contains_key(project_id) {
+            return;
+        }
+        let project_id_owned = project_id.to_string();
+        let task = tauri::async_runtime::spawn(async move {
+            poll(project_id_owned, root, events).await;
+        });
+        tracing::info!("Watching CI status of project {}", project_id);
+        tasks.insert(project_id.to_string(), task);
+    }
+
+    /// Stop polling a project; returns whether it was polled
+    pub fn unwatch(&self, project_id: &str) -> bool {
+        let Some(task) = self.tasks.lock().remove(project_id) else {
+            return false;
+        };
+        task.abort();
+        tracing::info!("Stopped watching CI status of project {}", project_id);
+        true
+    }
+}
+
+/// Emit `ci-status-changed` with the first status and every change after
+async fn poll(project_id: String, root: PathBuf, events: AppEventEmitter) {
+    let mut last: Option<CiStatus> = None;
+    loop {
+        let dir = root.clone();
+        let interval = match crate::utils::spawn_blocking_io(move || get(&dir)).await {
+            Ok(status) => {
+                let interval = if status.state == CiState::Pending {
+                    PENDING_POLL_INTERVAL
+                } else {
+                    SETTLED_POLL_INTERVAL
+                };
+                if last.as_ref() != Some(&status) {
+                    events
+                        .emit(
+                            "ci-status-changed",
+                            json!({ "projectId": project_id, "status": status }),
+                        )
+                        .await;
+                    last = Some(status);
+                }
+                interval
+            }
+            Err(err) => {
+                tracing::debug!("CI status of project {} unavailable: {}", project_id, err);
+                SETTLED_POLL_INTERVAL
+            }
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, sha: &str, status: &str, conclusion: Option<&str>) -> WorkflowRun {
+        WorkflowRun {
+            id: 1,
+            name: name.to_string(),
+            title: "Fix build".to_string(),
+            status: status.to_string(),
+            conclusion: conclusion.map(str::to_string),
+            event: "push".to_string(),
+            head_sha: sha.to_string(),
+            url: String::new(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn check(bucket: &str) -> PullRequestCheck {
+        PullRequestCheck {
+            name: "test".to_string(),
+            workflow: None,
+            state: String::new(),
+            bucket: Some(bucket.to_string()),
+            link: None,
+        }
+    }
+
+    #[test]
+    fn test_rollup_runs() {
+        assert_eq!(rollup(&[], &[], Some("abc")), CiState::Unknown);
+
+        // Newest first: the rerun of "ci" passed, so the old failure is moot
+        let runs = [
+            run("ci", "abc", "completed", Some("success")),
+            run("lint", "abc", "completed", Some("skipped")),
+            run("ci", "abc", "completed", Some("failure")),
+            run("ci", "old", "completed", Some("failure")),
+        ];
+        assert_eq!(rollup(&runs, &[], Some("abc")), CiState::Passing);
+        // An unpushed HEAD falls back to the newest pushed commit
+        assert_eq!(rollup(&runs, &[], Some("local")), CiState::Passing);
+        assert_eq!(rollup(&runs, &[], Some("old")), CiState::Failing);
+
+        let runs = [
+            run("ci", "abc", "in_progress", None),
+            run("lint", "abc", "completed", Some("success")),
+        ];
+        assert_eq!(rollup(&runs, &[], Some("abc")), CiState::Pending);
+    }
+
+    #[test]
+    fn test_rollup_prefers_checks() {
+        let runs = [run("ci", "abc", "completed", Some("success"))];
+        assert_eq!(
+            rollup(&runs, &[check("pass"), check("pending")], Some("abc")),
+            CiState::Pending
+        );
+        assert_eq!(
+            rollup(&runs, &[check("pending"), check("fail")], Some("abc")),
+            CiState::Failing
+        );
+        assert_eq!(
+            rollup(&runs, &[check("pass"), check("skipping")], Some("abc")),
+            CiState::Passing
+        );
+    }
+}
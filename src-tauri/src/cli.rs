@@ -0,0 +1,295 @@
+//! Headless commands in the app binary, for scripts and support.
+//!
+//! `codex-desktop <command>` runs one of the commands in `USAGE` and exits
+//! without opening a window, starting an app server or asking the
+//! single-instance plugin, so it also works while the app is open. The
+//! commands leave the app data directory as they found it: the database is
+//! opened read-only and a settings file that cannot be parsed is an error
+//! rather than repaired. The only file written is the one named by
+//! `export-session --output`. With no app server to ask, sessions are
+//! exported from the transcripts the app imported or else from the Codex
+//! CLI rollout files. While the app lock is on, commands that print project
+//! or session data take the passphrase on stdin. Any other arguments start
+//! the app as usual.
+
+use std::path::{Path, PathBuf};
+
+use crate::app_lock;
+use crate::codex_import;
+use crate::commands::projects::validate_id;
+use crate::commands::sessions::{redact_transcript, transcript_meta};
+use crate::database::Database;
+use crate::diagnostics::{self, DiagnosticsReport};
+use crate::global_state::{GlobalStateFile, GlobalStateStore};
+use crate::state::{DATABASE_FILE, GLOBAL_STATE_FILE};
+use crate::transcript_export::{self, ExportFormat};
+use crate::transcript_import;
+use crate::{Error, Result};
+
+/// `identifier` in tauri.conf.json, which names the app data directory
+const APP_IDENTIFIER: &str = "com.codex.desktop";
+
+const USAGE: &str = "\
+Usage:
+  codex-desktop export-session <session id> [--format markdown|html|json] [--output <file>]
+                               [--no-redact] [--passphrase-stdin]
+  codex-desktop list-projects [--json] [--passphrase-stdin]
+  codex-desktop doctor [--json]";
+
+/// Exit code of a command line that could not be understood
+const EXIT_USAGE: i32 = 2;
+
+/// Run the command named in `args`, which start with the program, and
+/// return its exit code; `None` when they name no command
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.get(1)?;
+    let rest = &args[2..];
+    let run: fn(&Path, &[String]) -> Result<i32> = match command.as_str() {
+        "export-session" => export_session,
+        "list-projects" => list_projects,
+        "doctor" => doctor,
+        "help" | "--help" => |_, _| {
+            println!("{USAGE}");
+            Ok(0)
+        },
+        _ => return None,
+    };
+    #[cfg(windows)]
+    attach_console();
+    let result = app_data_dir().and_then(|app_data_dir| run(&app_data_dir, rest));
+    Some(result.unwrap_or_else(|err| {
+        eprintln!("codex-desktop {command}: {err}");
+        1
+    }))
+}
+
+/// Release builds on Windows have no console; write to the one the command
+/// was typed in
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: no preconditions; it fails harmlessly without a parent console
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+fn usage(problem: &str) -> Result<i32> {
+    eprintln!("{problem}\n\n{USAGE}");
+    Ok(EXIT_USAGE)
+}
+
+/// Whether `args` are exactly the optional `--json` flag
+fn json_flag(args: &[String]) -> Option<bool> {
+    match args {
+        [] => Some(false),
+        [flag] if flag == "--json" => Some(true),
+        _ => None,
+    }
+}
+
+fn app_data_dir() -> Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| Error::Other("Cannot find the app data directory".to_string()))
+}
+
+fn open_database(app_data_dir: &Path) -> Result<Database> {
+    let path = app_data_dir.join(DATABASE_FILE);
+    if !path.exists() {
+        return Err(Error::Other(format!(
+            "No app database at {}; start the app once first",
+            path.display()
+        )));
+    }
+    Database::open_read_only(&path)
+}
+
+fn load_settings(app_data_dir: &Path) -> Result<GlobalStateFile> {
+    GlobalStateStore::read_file(&app_data_dir.join(GLOBAL_STATE_FILE))
+}
+
+/// With the app lock on, read the passphrase from the first line of stdin
+/// and check it; `passphrase_stdin` says the caller supplied one there
+fn check_app_lock(settings: &GlobalStateFile, passphrase_stdin: bool) -> Result<()> {
+    if !settings.app_lock.enabled {
+        return Ok(());
+    }
+    if !passphrase_stdin {
+        return Err(Error::Other(
+            "The app lock is on; pass the passphrase on stdin with --passphrase-stdin".to_string(),
+        ));
+    }
+    let mut passphrase = String::new();
+    std::io::stdin().read_line(&mut passphrase)?;
+    let passphrase = passphrase.trim_end_matches(['\r', '\n']);
+    if !app_lock::verify_passphrase(passphrase)? {
+        return Err(Error::Other("Wrong passphrase".to_string()));
+    }
+    Ok(())
+}
+
+fn export_session(app_data_dir: &Path, args: &[String]) -> Result<i32> {
+    let mut session_id = None;
+    let mut format = ExportFormat::Markdown;
+    let mut output = None;
+    let mut redact = None;
+    let mut passphrase_stdin = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("markdown") => ExportFormat::Markdown,
+                    Some("html") => ExportFormat::Html,
+                    Some("json") => ExportFormat::Json,
+                    _ => return usage("--format takes markdown, html or json"),
+                }
+            }
+            "--output" => match args.next() {
+                Some(path) => output = Some(PathBuf::from(path)),
+                None => return usage("--output takes a file path"),
+            },
+            "--no-redact" => redact = Some(false),
+            "--passphrase-stdin" => passphrase_stdin = true,
+            _ if session_id.is_none() && !arg.starts_with('-') => session_id = Some(arg.clone()),
+            _ => return usage(&format!("Unexpected argument {arg}")),
+        }
+    }
+    let Some(session_id) = session_id else {
+        return usage("export-session takes a session id");
+    };
+    validate_id(&session_id, "session_id")?;
+    let settings = load_settings(app_data_dir)?;
+    check_app_lock(&settings, passphrase_stdin)?;
+
+    let database = open_database(app_data_dir)?;
+    let session = database.get_session_by_id(&session_id)?;
+    let (title, cwd, model, mut items) = match database.get_imported_transcript(&session_id)? {
+        Some(imported) => (None, imported.cwd.unwrap_or_default(), None, imported.items),
+        None => {
+            let rollout = codex_import::session_file(&session_id)?;
+            let parsed = transcript_import::parse(&std::fs::read_to_string(rollout)?)?;
+            let cwd = parsed.cwd.unwrap_or_default();
+            (parsed.title, cwd, parsed.model, parsed.items)
+        }
+    };
+    let mut meta = transcript_meta(session_id, session, title, cwd, model);
+    let settings = settings.redaction;
+    if redact.unwrap_or(settings.enabled) {
+        redact_transcript(&settings, &mut meta, &mut items)?;
+    }
+
+    let rendered = transcript_export::render(format, &meta, &items)?;
+    match output {
+        Some(path) => {
+            crate::utils::atomic_write(&path, rendered.as_bytes())?;
+            eprintln!("Exported {} items to {}", items.len(), path.display());
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(0)
+}
+
+fn list_projects(app_data_dir: &Path, args: &[String]) -> Result<i32> {
+    let mut json = false;
+    let mut passphrase_stdin = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--passphrase-stdin" => passphrase_stdin = true,
+            _ => return usage(&format!("Unexpected argument {arg}")),
+        }
+    }
+    check_app_lock(&load_settings(app_data_dir)?, passphrase_stdin)?;
+
+    let projects = open_database(app_data_dir)?.get_all_projects()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&projects)?);
+    } else {
+        for project in projects {
+            let name = project.display_name.as_deref().unwrap_or_default();
+            println!("{}\t{}\t{}", project.id, name, project.path);
+        }
+    }
+    Ok(0)
+}
+
+/// Exits with 1 when a check failed
+fn doctor(app_data_dir: &Path, args: &[String]) -> Result<i32> {
+    let Some(json) = json_flag(args) else {
+        return usage("doctor only takes --json");
+    };
+    let launch = load_settings(app_data_dir)?.app_server_launch;
+    let report = tauri::async_runtime::block_on(async {
+        DiagnosticsReport::new(vec![
+            diagnostics::codex_cli(&launch).await,
+            diagnostics::git().await,
+            diagnostics::gh_auth().await,
+            diagnostics::database(&app_data_dir.join(DATABASE_FILE), true),
+            diagnostics::disk_space(app_data_dir),
+        ])
+    });
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.text);
+    }
+    Ok(i32::from(report.has_failures()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_run_leaves_other_arguments_to_the_app() {
+        assert_eq!(run(&args(&["codex-desktop"])), None);
+        assert_eq!(run(&args(&["codex-desktop", "/tmp/repo"])), None);
+        assert_eq!(run(&args(&["codex-desktop", "codex-desktop://new"])), None);
+        assert_eq!(
+            run(&args(&["codex-desktop", "list-projects", "--yaml"])),
+            Some(EXIT_USAGE)
+        );
+    }
+
+    #[test]
+    fn test_app_lock_needs_the_passphrase() {
+        let mut settings = GlobalStateFile::default();
+        assert!(check_app_lock(&settings, false).is_ok());
+        settings.app_lock.enabled = true;
+        assert!(check_app_lock(&settings, false).is_err());
+    }
+
+    #[test]
+    fn test_locked_commands_need_the_passphrase() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app_data_dir = temp_dir.path();
+        std::fs::write(
+            app_data_dir.join(GLOBAL_STATE_FILE),
+            r#"{"appLock": {"enabled": true}}"#,
+        )
+        .unwrap();
+        Database::new(&app_data_dir.join(DATABASE_FILE)).unwrap();
+
+        let locked = |result: Result<i32>| {
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("--passphrase-stdin")
+        };
+        assert!(locked(list_projects(app_data_dir, &args(&["--json"]))));
+        assert!(locked(export_session(app_data_dir, &args(&["s1"]))));
+    }
+
+    #[test]
+    fn test_app_identifier_matches_tauri_conf() {
+        let config: serde_json::Value =
+            serde_json::from_str(include_str!("../tauri.conf.json")).unwrap();
+        assert_eq!(config["identifier"], APP_IDENTIFIER);
+    }
+}
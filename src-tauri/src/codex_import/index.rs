@@ -0,0 +1,286 @@
+//! Persistent search index over Codex CLI rollouts
+//!
+//! The first search walks `~/.codex/sessions` once, indexing only rollouts
+//! whose size or modification time changed since the last run, and then
+//! watches the directory so new and updated rollouts are re-indexed as they
+//! are written. Searches read the index instead of every rollout file.
+//! Re-indexing runs as the `codexSessionIndex` background task.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use notify::{RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use super::session::{self, CodexSessionSummary};
+use crate::background_tasks::{self, BackgroundTasks};
+use crate::database::{CodexIndexEntry, Database};
+use crate::{Error, Result};
+
+/// Message text indexed per rollout
+const MAX_INDEXED_CONTENT_BYTES: usize = 256 * 1024;
+
+/// Rollouts are appended to while a session runs; wait for writes to settle
+const REINDEX_QUIET: Duration = Duration::from_secs(2);
+const REINDEX_MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Rollouts parsed per database transaction during a refresh
+const INDEX_BATCH_SIZE: usize = 100;
+
+pub struct CodexSessionIndex {
+    sessions_dir: PathBuf,
+    tasks: Arc<BackgroundTasks>,
+    /// Set once the index has been brought up to date and is being kept so
+    watcher: Mutex<Option<notify::RecommendedWatcher>>,
+}
+
+impl CodexSessionIndex {
+    pub fn new(tasks: Arc<BackgroundTasks>) -> Self {
+        Self::with_dir(super::get_codex_dir().join("sessions"), tasks)
+    }
+
+    pub fn with_dir(sessions_dir: PathBuf, tasks: Arc<BackgroundTasks>) -> Self {
+        Self {
+            sessions_dir,
+            tasks,
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Search indexed sessions, most relevant first
+    ///
+    /// A blank query lists the most recent sessions.
+    pub fn search(
+        &self,
+        database: &Arc<Database>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<CodexSessionSummary>> {
+        self.ensure_ready(database)?;
+        Ok(database
+            .search_codex_index(query, limit)?
+            .into_iter()
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect())
+    }
+
+    /// Catch the index up with the sessions directory and start watching it;
+    /// a no-op once the watcher runs
+    ///
+    /// Until the directory exists, every call refreshes (cheaply) instead.
+    fn ensure_ready(&self, database: &Arc<Database>) -> Result<()> {
+        let mut watcher = self.watcher.lock();
+        if watcher.is_some() {
+            return Ok(());
+        }
+        refresh(database, &self.sessions_dir)?;
+        if self.sessions_dir.is_dir() {
+            *watcher = Some(watch_sessions(
+                &self.sessions_dir,
+                database.clone(),
+                self.tasks.clone(),
+            )?);
+        }
+        Ok(())
+    }
+}
+
+/// Index new and changed rollouts under `sessions_dir` and drop vanished
+/// ones; returns (indexed, removed)
+pub fn refresh(database: &Database, sessions_dir: &Path) -> Result<(usize, usize)> {
+    let mut stale = database.get_codex_index_stamps()?;
+    let mut changed = Vec::new();
+    if sessions_dir.is_dir() {
+        for entry in walkdir::WalkDir::new(sessions_dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+            if !entry.file_type().is_file() || !session::is_rollout_file(path) {
+                continue;
+            }
+            let key = path.to_string_lossy().to_string();
+            let indexed = stale.remove(&key);
+            if indexed.is_none() || indexed != file_stamp(path) {
+                changed.push(path.to_path_buf());
+            }
+        }
+    }
+
+    let mut indexed = 0;
+    for batch in changed.chunks(INDEX_BATCH_SIZE) {
+        let entries: Vec<CodexIndexEntry> =
+            batch.iter().filter_map(|path| index_entry(path)).collect();
+        database.upsert_codex_index_entries(&entries)?;
+        indexed += entries.len();
+    }
+    let vanished: Vec<String> = stale.into_keys().collect();
+    let removed = database.remove_codex_index_entries(&vanished)?;
+    if indexed > 0 || removed > 0 {
+        tracing::info!(
+            "Codex session index: {} rollouts indexed, {} removed",
+            indexed,
+            removed
+        );
+    }
+    Ok((indexed, removed))
+}
+
+/// Re-index the given rollouts, or drop them if they no longer exist
+fn update_paths(database: &Database, paths: &HashSet<PathBuf>) -> Result<()> {
+    let mut entries = Vec::new();
+    let mut removed = Vec::new();
+    for path in paths.iter().filter(|path| session::is_rollout_file(path)) {
+        if path.is_file() {
+            entries.extend(index_entry(path));
+        } else {
+            removed.push(path.to_string_lossy().to_string());
+        }
+    }
+    database.upsert_codex_index_entries(&entries)?;
+    database.remove_codex_index_entries(&removed)?;
+    Ok(())
+}
+
+/// Unix ms of the last modification and size in bytes
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as i64;
+    Some((modified, metadata.len() as i64))
+}
+
+/// `None` for rollouts that cannot be parsed (yet), e.g. still missing
+/// their metadata line
+fn index_entry(path: &Path) -> Option<CodexIndexEntry> {
+    let (modified_at, file_size) = file_stamp(path)?;
+    let (summary, content) = match session::parse_for_index(path, MAX_INDEXED_CONTENT_BYTES) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            tracing::debug!("Skipping rollout {:?} in search index: {}", path, err);
+            return None;
+        }
+    };
+    Some(CodexIndexEntry {
+        file_path: path.to_string_lossy().to_string(),
+        modified_at,
+        file_size,
+        timestamp: summary.timestamp.clone(),
+        summary_json: serde_json::to_string(&summary).ok()?,
+        project_name: summary.project_name,
+        cwd: summary.cwd,
+        git_branch: summary.git_branch,
+        first_message: summary.first_message,
+        content,
+    })
+}
+
+/// Watch the sessions directory, re-indexing changed rollouts once their
+/// writes settle; stops when the returned watcher is dropped
+fn watch_sessions(
+    sessions_dir: &Path,
+    database: Arc<Database>,
+    tasks: Arc<BackgroundTasks>,
+) -> Result<notify::RecommendedWatcher> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    let handler = move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, notify::EventKind::Access(_)) {
+            return;
+        }
+        for path in event.paths {
+            let _ = tx.send(path.to_string_lossy().to_string());
+        }
+    };
+    let mut watcher = notify::recommended_watcher(handler)
+        .map_err(|err| Error::Other(format!("Failed to create Codex sessions watcher: {err}")))?;
+    watcher
+        .watch(sessions_dir, RecursiveMode::Recursive)
+        .map_err(|err| {
+            Error::Other(format!("Failed to watch {}: {err}", sessions_dir.display()))
+        })?;
+
+    tauri::async_runtime::spawn(async move {
+        while let Some(paths) =
+            crate::watcher::next_batch(&mut rx, REINDEX_QUIET, REINDEX_MAX_WAIT).await
+        {
+            let paths: HashSet<PathBuf> = paths.into_iter().map(PathBuf::from).collect();
+            let _permit = tasks.start(background_tasks::CODEX_SESSION_INDEX).await;
+            let database = database.clone();
+            let result =
+                crate::utils::spawn_blocking_io(move || update_paths(&database, &paths)).await;
+            if let Err(err) = result {
+                tracing::warn!("Failed to update Codex session index: {}", err);
+            }
+        }
+    });
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_rollout(dir: &Path, id: &str, message: &str) -> PathBuf {
+        let path = dir.join(format!("rollout-2025-01-01T00-00-00-{id}.jsonl"));
+        let lines = [
+            serde_json::json!({
+                "timestamp": "2025-01-01T00:00:00Z",
+                "type": "session_meta",
+                "payload": { "id": id, "timestamp": "2025-01-01T00:00:00Z", "cwd": "/work/demo" },
+            }),
+            serde_json::json!({
+                "timestamp": "2025-01-01T00:00:01Z",
+                "type": "response_item",
+                "payload": {
+                    "type": "message",
+                    "role": "user",
+                    "content": [{ "type": "input_text", "text": message }],
+                },
+            }),
+        ];
+        let text: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        std::fs::write(&path, text.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_refresh_indexes_changes_only() {
+        let (temp_dir, database) = crate::database::test_db();
+        let sessions_dir = temp_dir.path().join("sessions");
+        std::fs::create_dir_all(sessions_dir.join("2025/01/01")).unwrap();
+        let first = write_rollout(
+            &sessions_dir.join("2025/01/01"),
+            "a1",
+            "refactor the parser",
+        );
+        write_rollout(&sessions_dir, "b2", "write release notes");
+
+        assert_eq!(refresh(&database, &sessions_dir).unwrap(), (2, 0));
+        assert_eq!(refresh(&database, &sessions_dir).unwrap(), (0, 0));
+
+        let hits = database.search_codex_index("pars", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        let summary: CodexSessionSummary = serde_json::from_str(&hits[0]).unwrap();
+        assert_eq!(summary.id, "a1");
+        assert_eq!(database.search_codex_index("demo", 10).unwrap().len(), 2);
+        assert_eq!(database.search_codex_index("", 1).unwrap().len(), 1);
+
+        std::fs::remove_file(&first).unwrap();
+        assert_eq!(refresh(&database, &sessions_dir).unwrap(), (0, 1));
+        assert!(database
+            .search_codex_index("parser", 10)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -4,9 +4,11 @@
 //! enabling session recovery, continuation, and management.
 
 mod config;
+mod index;
 mod session;
 
 pub use config::{CodexConfig, CodexProject};
+pub use index::CodexSessionIndex;
 pub use session::{
     CodexSession, CodexSessionMeta, CodexSessionSummary, ResponseItem, SessionMessage,
 };
@@ -36,9 +38,9 @@ pub fn get_session(session_id: &str) -> Result<CodexSession> {
     session::get_session(session_id)
 }
 
-/// Search sessions by keyword
-pub fn search_sessions(query: &str, limit: usize) -> Result<Vec<CodexSessionSummary>> {
-    session::search_sessions(query, limit)
+/// Find the rollout file of a session
+pub fn session_file(session_id: &str) -> Result<PathBuf> {
+    session::session_file(session_id)
 }
 
 /// Delete a session file
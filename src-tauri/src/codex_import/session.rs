@@ -278,6 +278,11 @@ pub fn get_session(session_id: &str) -> Result<CodexSession> {
     parse_full_session(&file_path)
 }
 
+/// Rollout file of a session in ~/.codex/sessions/
+pub fn session_file(session_id: &str) -> Result<PathBuf> {
+    find_session_file(&super::get_codex_dir().join("sessions"), session_id)
+}
+
 /// Find session file by ID
 fn find_session_file(dir: &Path, session_id: &str) -> Result<PathBuf> {
     if !dir.is_dir() {
@@ -389,27 +394,47 @@ fn parse_full_session(path: &Path) -> Result<CodexSession> {
     })
 }
 
-/// Search sessions by keyword
-pub fn search_sessions(query: &str, limit: usize) -> Result<Vec<CodexSessionSummary>> {
-    let all_sessions = list_sessions()?;
-    let query_lower = query.to_lowercase();
+/// Parse a rollout for the search index: its summary and the text of its
+/// user and assistant messages, cut off at `max_bytes`
+pub(super) fn parse_for_index(path: &Path, max_bytes: usize) -> Result<(CodexSessionSummary, String)> {
+    let session = parse_full_session(path)?;
+    let mut content = String::new();
+    for message in &session.messages {
+        if message.message_type != "message" {
+            continue;
+        }
+        let Some(parts) = message.content.as_array() else {
+            continue;
+        };
+        for text in parts.iter().filter_map(|part| part.get("text")?.as_str()) {
+            if text.starts_with("<user_instructions>") || text.starts_with("<environment_context>")
+            {
+                continue;
+            }
+            if !content.is_empty() {
+                content.push('\n');
+            }
+            content.push_str(text);
+        }
+        if content.len() >= max_bytes {
+            let mut end = max_bytes;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content.truncate(end);
+            break;
+        }
+    }
+    Ok((session.summary, content))
+}
 
-    let filtered: Vec<_> = all_sessions
-        .into_iter()
-        .filter(|s| {
-            s.project_name.to_lowercase().contains(&query_lower)
-                || s.cwd.to_lowercase().contains(&query_lower)
-                || s.first_message
-                    .as_ref()
-                    .is_some_and(|m| m.to_lowercase().contains(&query_lower))
-                || s.git_branch
-                    .as_ref()
-                    .is_some_and(|b| b.to_lowercase().contains(&query_lower))
-        })
-        .take(limit)
-        .collect();
-
-    Ok(filtered)
+/// Whether `path` is a rollout file under the sessions directory
+pub(super) fn is_rollout_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonl")
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("rollout-"))
 }
 
 /// Delete a session file
@@ -1,9 +1,15 @@
-//! Command allowlist management commands
+//! Command allowlist, denylist and approval preset commands
+
+use std::path::PathBuf;
 
 use tauri::State;
 
+use crate::allowlist::{self, AllowlistCheck, AllowlistPattern};
+use crate::approvals::{self, CommandContext};
+use crate::commands::projects::validate_id;
+use crate::database::{ApprovalPreset, ProjectUpdate};
 use crate::state::AppState;
-use crate::Result;
+use crate::{Error, Result};
 
 /// Get command allowlist for a project
 #[tauri::command]
@@ -15,13 +21,20 @@ pub async fn get_allowlist(
 }
 
 /// Add a command pattern to the allowlist
+///
+/// Accepts literal commands, globs (`npm run *`, `cargo test*`) and `re:`
+/// regexes; see `crate::allowlist`. Returns the pattern as stored.
 #[tauri::command]
 pub async fn add_to_allowlist(
     state: State<'_, AppState>,
     project_id: String,
     command_pattern: String,
-) -> Result<()> {
-    state.database.add_to_allowlist(&project_id, &command_pattern)
+) -> Result<String> {
+    let pattern = AllowlistPattern::parse(&command_pattern)?;
+    state
+        .database
+        .add_to_allowlist(&project_id, pattern.as_str())?;
+    Ok(pattern.as_str().to_string())
 }
 
 /// Remove a command pattern from the allowlist
@@ -33,3 +46,133 @@ pub async fn remove_from_allowlist(
 ) -> Result<()> {
     state.database.remove_from_allowlist(&project_id, &command_pattern)
 }
+
+/// Get command denylist for a project
+#[tauri::command]
+pub async fn get_denylist(state: State<'_, AppState>, project_id: String) -> Result<Vec<String>> {
+    validate_id(&project_id, "project_id")?;
+    state.database.get_denylist(&project_id)
+}
+
+/// Add a command pattern to the denylist
+///
+/// Uses the allowlist pattern syntax. Denylisted commands are flagged high
+/// risk and never auto-approved, even when allowlisted. Returns the pattern
+/// as stored.
+#[tauri::command]
+pub async fn add_to_denylist(
+    state: State<'_, AppState>,
+    project_id: String,
+    command_pattern: String,
+) -> Result<String> {
+    validate_id(&project_id, "project_id")?;
+    let pattern = AllowlistPattern::parse(&command_pattern)?;
+    state
+        .database
+        .add_to_denylist(&project_id, pattern.as_str())?;
+    Ok(pattern.as_str().to_string())
+}
+
+/// Remove a command pattern from the denylist
+#[tauri::command]
+pub async fn remove_from_denylist(
+    state: State<'_, AppState>,
+    project_id: String,
+    command_pattern: String,
+) -> Result<()> {
+    validate_id(&project_id, "project_id")?;
+    state
+        .database
+        .remove_from_denylist(&project_id, &command_pattern)
+}
+
+/// Classify a command the way approval requests are classified
+///
+/// With `project_id`, paths are checked against the project root and the
+/// project's allowlist and denylist apply.
+#[tauri::command]
+pub async fn classify_command(
+    state: State<'_, AppState>,
+    command: String,
+    cwd: Option<String>,
+    project_id: Option<String>,
+) -> Result<CommandContext> {
+    let Some(project_id) = project_id else {
+        return Ok(approvals::analyze(&command, cwd.as_deref(), None, &[]));
+    };
+    validate_id(&project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+    let allowlist = state.database.get_allowlist(&project_id)?;
+    let denylist = state.database.get_denylist(&project_id)?;
+
+    let root = PathBuf::from(&project.path);
+    let mut context = approvals::analyze(&command, cwd.as_deref(), Some(&root), &allowlist);
+    approvals::apply_denylist(&mut context, &denylist);
+    Ok(context)
+}
+
+/// Check which allowlist patterns a command matches
+///
+/// Tests `pattern` alone when given (to try a pattern before adding it),
+/// otherwise the project's allowlist.
+#[tauri::command]
+pub async fn check_allowlist_match(
+    state: State<'_, AppState>,
+    project_id: String,
+    command: String,
+    pattern: Option<String>,
+) -> Result<AllowlistCheck> {
+    let patterns = match pattern {
+        Some(pattern) => vec![AllowlistPattern::parse(&pattern)?],
+        None => {
+            validate_id(&project_id, "project_id")?;
+            state
+                .database
+                .get_allowlist(&project_id)?
+                .iter()
+                .filter_map(|pattern| AllowlistPattern::parse(pattern).ok())
+                .collect()
+        }
+    };
+    Ok(allowlist::check(&command, &patterns))
+}
+
+/// Get which command approvals a project answers without asking
+#[tauri::command]
+pub async fn get_approval_policy(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ApprovalPreset> {
+    validate_id(&project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or(Error::ProjectNotFound(project_id))?;
+    Ok(project.settings().approval_preset.unwrap_or_default())
+}
+
+/// Set which command approvals a project answers without asking
+///
+/// Applies to approval requests arriving from now on.
+#[tauri::command]
+pub async fn set_approval_policy(
+    state: State<'_, AppState>,
+    project_id: String,
+    preset: ApprovalPreset,
+) -> Result<ApprovalPreset> {
+    validate_id(&project_id, "project_id")?;
+    let update = ProjectUpdate {
+        display_name: None,
+        settings_patch: Some(serde_json::json!({ "approvalPreset": preset })),
+        last_opened_at: None,
+    };
+    state
+        .database
+        .update_project(&project_id, &update)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+    tracing::info!("Set approval preset of project {} to {:?}", project_id, preset);
+    Ok(preset)
+}
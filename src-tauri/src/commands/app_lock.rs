@@ -0,0 +1,114 @@
+//! App lock commands
+
+use tauri::State;
+
+use crate::app_lock::{self, AppLockStatus};
+use crate::global_state::AppLockSettings;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+fn status(state: &AppState) -> AppLockStatus {
+    let settings = state.global_state.read(|global| global.app_lock.clone());
+    state.app_lock.status(settings)
+}
+
+/// Whether the app is locked, with the lock settings
+#[tauri::command]
+pub async fn get_app_lock_status(state: State<'_, AppState>) -> Result<AppLockStatus> {
+    Ok(status(&state))
+}
+
+/// Lock the app now; fails when no passphrase is set
+#[tauri::command]
+pub async fn lock_app(state: State<'_, AppState>) -> Result<AppLockStatus> {
+    if !state.global_state.read(|global| global.app_lock.enabled) {
+        return Err(Error::Other(
+            "Set an app lock passphrase before locking".to_string(),
+        ));
+    }
+    if state.app_lock.lock() {
+        state.events.hold();
+        tracing::info!("App locked");
+        state.events.emit("app-lock-changed", status(&state)).await;
+    }
+    Ok(status(&state))
+}
+
+/// Unlock the app with the passphrase
+///
+/// Attempts run one at a time, and a wrong passphrase is answered after a
+/// delay that grows with each failed attempt. Events held back while the
+/// app was locked are sent once it unlocks.
+#[tauri::command]
+pub async fn unlock_app(state: State<'_, AppState>, passphrase: String) -> Result<AppLockStatus> {
+    let _attempt = state.app_lock.begin_unlock().await;
+    if !state.app_lock.is_locked() {
+        return Ok(status(&state));
+    }
+    let valid =
+        crate::utils::spawn_blocking_io(move || app_lock::verify_passphrase(&passphrase)).await?;
+    if !valid {
+        tokio::time::sleep(state.app_lock.record_failed_unlock()).await;
+        tracing::warn!("Failed attempt to unlock the app");
+        return Err(Error::Other("Wrong passphrase".to_string()));
+    }
+    state.app_lock.unlock();
+    tracing::info!("App unlocked");
+    state.events.release().await;
+    state.events.emit("app-lock-changed", status(&state)).await;
+    Ok(status(&state))
+}
+
+/// Set, change or (with no `passphrase`) remove the app lock passphrase
+///
+/// `current` must match the passphrase already set, if any. The lock is
+/// enabled exactly while a passphrase is set.
+#[tauri::command]
+pub async fn set_app_lock_passphrase(
+    state: State<'_, AppState>,
+    current: Option<String>,
+    passphrase: Option<String>,
+) -> Result<AppLockStatus> {
+    let enabled = passphrase.is_some();
+    crate::utils::spawn_blocking_io(move || {
+        app_lock::set_passphrase(current.as_deref(), passphrase.as_deref())
+    })
+    .await?;
+    state
+        .global_state
+        .update(|global| global.app_lock.enabled = enabled);
+    state.global_state.flush()?;
+    state.app_lock.touch();
+    Ok(status(&state))
+}
+
+/// Update when the app locks itself; `enabled` is kept as is
+#[tauri::command]
+pub async fn set_app_lock_settings(
+    state: State<'_, AppState>,
+    settings: AppLockSettings,
+) -> Result<AppLockStatus> {
+    if settings.idle_timeout_mins == Some(0) {
+        return Err(Error::Other(
+            "The idle timeout must be at least a minute".to_string(),
+        ));
+    }
+    state.global_state.update(|global| {
+        global.app_lock = AppLockSettings {
+            enabled: global.app_lock.enabled,
+            ..settings
+        }
+    });
+    state.global_state.flush()?;
+    state.app_lock.touch();
+    Ok(status(&state))
+}
+
+/// Note user activity in the renderer, postponing the idle lock
+#[tauri::command]
+pub async fn report_user_activity(state: State<'_, AppState>) -> Result<()> {
+    if !state.app_lock.is_locked() {
+        state.app_lock.touch();
+    }
+    Ok(())
+}
@@ -2,60 +2,102 @@
 
 use serde::Serialize;
 use tauri::State;
-use tokio::process::Command;
-use tokio::sync::OnceCell;
+use tokio::sync::Mutex;
 
 use crate::app_server::ipc_bridge::{AccountInfo, TurnStartResponse};
-use crate::app_server::AppServerProcess;
+use crate::app_server::compat::{self, CodexVersionCheck};
+use crate::app_server::installations::{self, CodexInstallation};
+use crate::app_server::{DegradedInfo, RateLimitForecast, RpcTraceSnapshot};
+use crate::commands::projects::validate_id;
+use crate::database::SavedAccount;
+use crate::global_state::{
+    unix_timestamp_secs, AppServerLaunchSettings, CustomModelProvider, RateLimitSettings,
+    RpcTraceSettings,
+};
+use crate::model_providers::{self, ModelProviderInfo, ProviderConnectionTest};
 use crate::state::AppState;
 use crate::Result;
 
+/// Whether the app-server can take requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerState {
+    Running,
+    /// Not started yet, or stopped
+    Stopped,
+    /// Stopped after being idle; starts again on the next thread command
+    Idle,
+    /// The last start failed; only features not needing the server work
+    Degraded,
+}
+
+/// Whether the app-server can take requests, with why not when degraded
+pub(crate) async fn server_state(state: &AppState) -> (ServerState, Option<DegradedInfo>) {
+    // Only hold the lock briefly to check running status
+    let is_running = {
+        let server = state.app_server.read().await;
+        server.as_ref().is_some_and(|s| s.is_running())
+    };
+
+    let degraded = if is_running {
+        None
+    } else {
+        state.availability.degraded()
+    };
+    let server_state = match (is_running, &degraded) {
+        (true, _) => ServerState::Running,
+        (false, Some(_)) => ServerState::Degraded,
+        (false, None) if state.idle.is_stopped() => ServerState::Idle,
+        (false, None) => ServerState::Stopped,
+    };
+    (server_state, degraded)
+}
+
 /// Server status information
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerStatus {
     pub is_running: bool,
+    pub state: ServerState,
+    /// Set when `state` is `Degraded`
+    pub degraded: Option<DegradedInfo>,
     pub version: Option<String>,
 }
 
-static CODEX_CLI_VERSION: OnceCell<Option<String>> = OnceCell::const_new();
+/// `codex --version` output for the launch settings it was read with
+static CODEX_CLI_VERSION: Mutex<Option<(AppServerLaunchSettings, Option<String>)>> =
+    Mutex::const_new(None);
 
-async fn get_codex_cli_version() -> Option<String> {
-    let codex_path = AppServerProcess::find_codex_binary().ok()?;
-    let output = Command::new(codex_path).arg("--version").output().await.ok()?;
-    if !output.status.success() {
-        return None;
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    if !stdout.is_empty() {
-        return Some(stdout);
-    }
-    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-    if !stderr.is_empty() {
-        return Some(stderr);
-    }
-    None
+async fn get_codex_cli_version(launch: &AppServerLaunchSettings) -> Option<String> {
+    let codex_path = installations::resolve_codex_binary(launch).await.ok()?;
+    installations::version_output(&codex_path).await
 }
 
-async fn get_codex_cli_version_cached() -> Option<String> {
-    CODEX_CLI_VERSION
-        .get_or_init(|| async { get_codex_cli_version().await })
-        .await
-        .clone()
+async fn get_codex_cli_version_cached(launch: AppServerLaunchSettings) -> Option<String> {
+    let mut cached = CODEX_CLI_VERSION.lock().await;
+    if let Some((settings, version)) = cached.as_ref() {
+        if *settings == launch {
+            return version.clone();
+        }
+    }
+    let version = get_codex_cli_version(&launch).await;
+    *cached = Some((launch, version.clone()));
+    version
 }
 
 /// Get the app server status
 #[tauri::command]
 pub async fn get_server_status(state: State<'_, AppState>) -> Result<ServerStatus> {
-    // Only hold the lock briefly to check running status
-    let is_running = {
-        let mut server = state.app_server.write().await;
-        server.as_mut().map(|s| s.is_running()).unwrap_or(false)
-    };
+    let (server_state, degraded) = server_state(&state).await;
 
     Ok(ServerStatus {
-        is_running,
-        version: get_codex_cli_version_cached().await,
+        is_running: server_state == ServerState::Running,
+        state: server_state,
+        degraded,
+        version: get_codex_cli_version_cached(
+            state.global_state.read(|global| global.app_server_launch.clone()),
+        )
+        .await,
     })
 }
 
@@ -66,6 +108,79 @@ pub async fn restart_server(state: State<'_, AppState>) -> Result<()> {
     Ok(())
 }
 
+/// Current codex binary, args and environment used for the app-server
+#[tauri::command]
+pub async fn get_app_server_launch_settings(
+    state: State<'_, AppState>,
+) -> Result<AppServerLaunchSettings> {
+    Ok(state.global_state.read(|global| global.app_server_launch.clone()))
+}
+
+/// Change how the app-server is launched
+///
+/// Takes effect on the next (re)start; a binary that is missing or does not
+/// match `pinned_version` is rejected up front so the app is not left unable
+/// to start its server.
+#[tauri::command]
+pub async fn set_app_server_launch_settings(
+    state: State<'_, AppState>,
+    settings: AppServerLaunchSettings,
+) -> Result<AppServerLaunchSettings> {
+    let mut settings = settings;
+    settings.codex_path = settings
+        .codex_path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty());
+    settings.pinned_version = settings
+        .pinned_version
+        .map(|version| version.trim().trim_start_matches('v').to_string())
+        .filter(|version| !version.is_empty());
+    installations::validate_settings(&settings)?;
+    installations::resolve_codex_binary(&settings).await?;
+
+    state.global_state.update(|global| global.app_server_launch = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Codex binaries found on PATH and in Homebrew, npm and cargo locations,
+/// with their versions
+#[tauri::command]
+pub async fn detect_codex_installations(
+    state: State<'_, AppState>,
+) -> Result<Vec<CodexInstallation>> {
+    let launch = state.global_state.read(|global| global.app_server_launch.clone());
+    Ok(installations::detect_installations(&launch).await)
+}
+
+/// Check the selected codex CLI against the supported version range
+#[tauri::command]
+pub async fn check_codex_version(state: State<'_, AppState>) -> Result<CodexVersionCheck> {
+    let launch = state.global_state.read(|global| global.app_server_launch.clone());
+    Ok(compat::check(&launch).await)
+}
+
+/// Upgrade the selected codex CLI with Homebrew or npm
+///
+/// A running app-server is restarted so it uses the new binary. Returns the
+/// version check after the upgrade.
+#[tauri::command]
+pub async fn upgrade_codex(state: State<'_, AppState>) -> Result<CodexVersionCheck> {
+    let launch = state.global_state.read(|global| global.app_server_launch.clone());
+    let output = compat::upgrade(&launch).await?;
+    tracing::info!("Codex CLI upgrade finished: {}", output);
+    *CODEX_CLI_VERSION.lock().await = None;
+
+    let is_running = {
+        let mut server = state.app_server.write().await;
+        server.as_mut().map(|s| s.is_running()).unwrap_or(false)
+    };
+    if is_running {
+        state.restart_app_server().await?;
+    }
+    Ok(compat::check(&launch).await)
+}
+
 /// Get account information
 #[tauri::command]
 pub async fn get_account_info(state: State<'_, AppState>) -> Result<AccountInfo> {
@@ -81,6 +196,8 @@ pub async fn get_account_info(state: State<'_, AppState>) -> Result<AccountInfo>
     let response: AccountInfo = server
         .send_request("account/read", serde_json::json!({}))
         .await?;
+    drop(server);
+    state.observe_account(&response).await;
 
     Ok(response)
 }
@@ -103,6 +220,26 @@ pub async fn start_login(
     state: State<'_, AppState>,
     login_type: String,
     api_key: Option<String>,
+) -> Result<LoginResponse> {
+    begin_login(&state, login_type, api_key).await
+}
+
+/// Restart the login that stopped working, as reported by `login-required`
+#[tauri::command]
+pub async fn start_reauth(state: State<'_, AppState>) -> Result<LoginResponse> {
+    let login_type = state.auth.login_type();
+    if login_type == "apiKey" {
+        return Err(crate::Error::Other(
+            "API key logins need a new key; log in again with start_login".to_string(),
+        ));
+    }
+    begin_login(&state, login_type, None).await
+}
+
+async fn begin_login(
+    state: &AppState,
+    login_type: String,
+    api_key: Option<String>,
 ) -> Result<LoginResponse> {
     // Ensure app-server is running
     state.start_app_server().await?;
@@ -145,6 +282,169 @@ pub async fn logout(state: State<'_, AppState>) -> Result<()> {
     Ok(())
 }
 
+/// Saved logins, with the one currently in use marked `isActive`
+#[tauri::command]
+pub async fn list_accounts(state: State<'_, AppState>) -> Result<Vec<SavedAccount>> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || crate::accounts::list(&database)).await
+}
+
+/// Save the current login for switching; call after a successful login
+#[tauri::command]
+pub async fn save_current_account(
+    state: State<'_, AppState>,
+    label: Option<String>,
+) -> Result<SavedAccount> {
+    crate::accounts::save_current(&state, label).await
+}
+
+/// Switch to a saved login
+///
+/// Restarts the app-server, which interrupts any running turns.
+#[tauri::command]
+pub async fn switch_account(state: State<'_, AppState>, account_id: String) -> Result<SavedAccount> {
+    validate_id(&account_id, "account_id")?;
+    crate::accounts::switch(&state, &account_id).await
+}
+
+/// Forget a saved login; the active login itself is not logged out
+#[tauri::command]
+pub async fn remove_account(state: State<'_, AppState>, account_id: String) -> Result<bool> {
+    validate_id(&account_id, "account_id")?;
+    state.database.delete_account(&account_id)
+}
+
+// ==================== Model Providers ====================
+
+/// Custom model providers, with whether an API key is stored for each
+#[tauri::command]
+pub async fn list_model_providers(state: State<'_, AppState>) -> Result<Vec<ModelProviderInfo>> {
+    let providers = state
+        .global_state
+        .read(|global| global.model_providers.providers.clone());
+    crate::utils::spawn_blocking_io(move || Ok(model_providers::list(&providers))).await
+}
+
+/// Add or replace a custom model provider and write it to the Codex config
+///
+/// `api_key`, when given, is stored in the keychain (an empty string removes
+/// it), which restarts a running app-server.
+#[tauri::command]
+pub async fn save_model_provider(
+    state: State<'_, AppState>,
+    provider: CustomModelProvider,
+    api_key: Option<String>,
+) -> Result<ModelProviderInfo> {
+    let provider = model_providers::normalize(provider)?;
+    state.global_state.read(|global| {
+        model_providers::check_unique(&global.model_providers.providers, &provider)
+    })?;
+    model_providers::write_to_config(&state, &provider.id, Some(&provider)).await?;
+    state.global_state.update(|global| {
+        let providers = &mut global.model_providers.providers;
+        match providers.iter_mut().find(|p| p.id == provider.id) {
+            Some(existing) => *existing = provider.clone(),
+            None => providers.push(provider.clone()),
+        }
+    });
+    state.global_state.flush()?;
+
+    if let Some(api_key) = api_key {
+        model_providers::update_api_key(&state, &provider.id, Some(api_key)).await?;
+    }
+    crate::utils::spawn_blocking_io(move || {
+        Ok(model_providers::list(std::slice::from_ref(&provider)).remove(0))
+    })
+    .await
+}
+
+/// Remove a custom model provider from the Codex config and forget its key
+///
+/// Projects still selecting it fail to start threads until another provider
+/// is picked.
+#[tauri::command]
+pub async fn remove_model_provider(state: State<'_, AppState>, provider_id: String) -> Result<bool> {
+    let known = state.global_state.read(|global| {
+        global
+            .model_providers
+            .providers
+            .iter()
+            .any(|provider| provider.id == provider_id)
+    });
+    if !known {
+        return Ok(false);
+    }
+    model_providers::write_to_config(&state, &provider_id, None).await?;
+    state
+        .global_state
+        .update(|global| global.model_providers.providers.retain(|p| p.id != provider_id));
+    state.global_state.flush()?;
+    let id = provider_id.clone();
+    crate::utils::spawn_blocking_io(move || model_providers::set_api_key(&id, None)).await?;
+    Ok(true)
+}
+
+/// Store (or with `None`, remove) a provider's API key in the keychain
+///
+/// Restarts a running app-server so it picks the key up; refused while
+/// turns are running.
+#[tauri::command]
+pub async fn set_model_provider_api_key(
+    state: State<'_, AppState>,
+    provider_id: String,
+    api_key: Option<String>,
+) -> Result<()> {
+    let known = state.global_state.read(|global| {
+        global
+            .model_providers
+            .providers
+            .iter()
+            .any(|provider| provider.id == provider_id)
+    });
+    if !known {
+        return Err(crate::Error::Other(format!(
+            "Model provider not found: {provider_id}"
+        )));
+    }
+    model_providers::update_api_key(&state, &provider_id, api_key).await
+}
+
+/// Check a provider's endpoint answers `GET /models` with its key
+///
+/// Works on unsaved settings: `provider` is tested as given, with `api_key`.
+/// Without one, the key stored for its id is sent only when the base URL is
+/// the saved one, so a stored key never goes to an endpoint it was not
+/// saved for.
+#[tauri::command]
+pub async fn test_provider_connection(
+    state: State<'_, AppState>,
+    provider: CustomModelProvider,
+    api_key: Option<String>,
+) -> Result<ProviderConnectionTest> {
+    let provider = model_providers::normalize(provider)?;
+    let saved_url = state.global_state.read(|global| {
+        global
+            .model_providers
+            .providers
+            .iter()
+            .any(|saved| saved.id == provider.id && saved.base_url == provider.base_url)
+    });
+    let api_key = match api_key.filter(|key| !key.trim().is_empty()) {
+        Some(key) => Some(key.trim().to_string()),
+        None if saved_url => {
+            let id = provider.id.clone();
+            crate::utils::spawn_blocking_io(move || model_providers::api_key(&id)).await?
+        }
+        None => None,
+    };
+    let mut test = model_providers::test_connection(&provider, api_key.as_deref()).await;
+    if api_key.is_none() && !saved_url && matches!(test.status_code, Some(401 | 403)) {
+        test.message =
+            "Authentication failed; enter the API key to test an unsaved base URL".to_string();
+    }
+    Ok(test)
+}
+
 /// Reasoning effort option
 #[derive(Debug, Clone, Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -352,12 +652,33 @@ pub struct UserShellCommandParams {
 }
 
 /// Run a local shell command (like CLI's ! prefix)
+///
+/// The command runs in the sandbox of the thread's project, if it has one.
 #[tauri::command]
 pub async fn run_user_shell_command(
     state: State<'_, AppState>,
     thread_id: String,
     command: String,
 ) -> Result<TurnStartResponse> {
+    let project = state
+        .database
+        .get_session_by_id(&thread_id)?
+        .map(|session| state.database.get_project(&session.project_id))
+        .transpose()?
+        .flatten();
+    let command = match project {
+        Some(project) => {
+            let argv = vec!["/bin/sh".to_string(), "-c".to_string(), command.clone()];
+            let wrapped = crate::sandbox::wrap_for_project(&project, argv.clone())?;
+            if wrapped == argv {
+                command
+            } else {
+                crate::sandbox::shell_join(&wrapped)
+            }
+        }
+        None => command,
+    };
+
     state.start_app_server().await?;
     let mut server = state.app_server.write().await;
     let server = server
@@ -458,14 +779,105 @@ pub async fn get_account_rate_limits(
     // Ensure app-server is running
     state.start_app_server().await?;
 
-    let mut server = state.app_server.write().await;
-    let server = server
-        .as_mut()
-        .ok_or_else(|| crate::Error::AppServer("App server not running".to_string()))?;
-
-    let response: serde_json::Value = server
-        .send_request("account/rateLimits/read", serde_json::json!({}))
-        .await?;
+    let response: serde_json::Value = {
+        let mut server = state.app_server.write().await;
+        let server = server
+            .as_mut()
+            .ok_or_else(|| crate::Error::AppServer("App server not running".to_string()))?;
+        server
+            .send_request("account/rateLimits/read", serde_json::json!({}))
+            .await?
+    };
 
+    state.observe_rate_limits(&response).await;
     Ok(response)
 }
+
+/// Projected rate-limit usage and the messages held back by it
+///
+/// Built from the latest `account/rateLimits/updated` notification or
+/// `get_account_rate_limits` response; empty until one of those arrives.
+#[tauri::command]
+pub async fn get_rate_limit_forecast(state: State<'_, AppState>) -> Result<RateLimitForecast> {
+    let settings = state.global_state.read(|global| global.rate_limits.clone());
+    Ok(state.rate_limits.forecast(unix_timestamp_secs(), &settings))
+}
+
+/// Configure rate-limit warnings and whether `send_message` queues turns
+/// while a window is nearly exhausted
+#[tauri::command]
+pub async fn set_rate_limit_settings(
+    state: State<'_, AppState>,
+    queue_when_limited: bool,
+    warning_percent: Option<f64>,
+    queue_percent: Option<f64>,
+) -> Result<RateLimitSettings> {
+    let mut settings = state.global_state.read(|global| global.rate_limits.clone());
+    settings.queue_when_limited = queue_when_limited;
+    settings.warning_percent = warning_percent.unwrap_or(settings.warning_percent);
+    settings.queue_percent = queue_percent.unwrap_or(settings.queue_percent);
+    if !(0.0 < settings.warning_percent
+        && settings.warning_percent <= settings.queue_percent
+        && settings.queue_percent <= 100.0)
+    {
+        return Err(crate::Error::Other(
+            "Rate limit thresholds must satisfy 0 < warning <= queue <= 100".to_string(),
+        ));
+    }
+
+    state.global_state.update(|global| global.rate_limits = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Drop a message held back by the rate limits; returns whether it was
+/// still queued
+#[tauri::command]
+pub async fn cancel_rate_limited_message(
+    state: State<'_, AppState>,
+    queued_id: String,
+) -> Result<bool> {
+    Ok(state.rate_limits.cancel(&queued_id))
+}
+
+// ==================== RPC Trace ====================
+
+/// Recorded app-server traffic for the protocol inspector
+///
+/// Returns the newest `limit` messages, oldest first; `method` keeps only
+/// messages whose method (or, for responses, whose request's method)
+/// contains it.
+#[tauri::command]
+pub async fn get_rpc_trace(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    method: Option<String>,
+) -> Result<RpcTraceSnapshot> {
+    Ok(state.rpc_trace.snapshot(limit, method.as_deref()))
+}
+
+/// Empty the in-memory trace; `rpc-trace.jsonl` is kept
+#[tauri::command]
+pub async fn clear_rpc_trace(state: State<'_, AppState>) -> Result<()> {
+    state.rpc_trace.clear();
+    Ok(())
+}
+
+/// Turn traffic recording on or off; `capacity` is clamped to 100-50000
+#[tauri::command]
+pub async fn set_rpc_trace_config(
+    state: State<'_, AppState>,
+    enabled: bool,
+    capacity: Option<usize>,
+    log_to_file: Option<bool>,
+) -> Result<RpcTraceSettings> {
+    let mut settings = state.global_state.read(|global| global.rpc_trace.clone());
+    settings.enabled = enabled;
+    settings.capacity = capacity.unwrap_or(settings.capacity);
+    settings.log_to_file = log_to_file.unwrap_or(settings.log_to_file);
+    let settings = state.rpc_trace.configure(settings);
+
+    state.global_state.update(|global| global.rpc_trace = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
@@ -0,0 +1,51 @@
+//! Approval shortcut commands
+
+use tauri::State;
+
+use crate::global_shortcuts;
+use crate::global_state::ApprovalShortcutSettings;
+use crate::pending_approvals::PendingApproval;
+use crate::state::AppState;
+use crate::Result;
+
+/// Approval requests the app-server is waiting on, oldest first
+#[tauri::command]
+pub async fn list_pending_approvals(state: State<'_, AppState>) -> Result<Vec<PendingApproval>> {
+    Ok(state.approvals.pending.list())
+}
+
+/// Get the approval shortcut settings
+#[tauri::command]
+pub async fn get_approval_shortcut_settings(
+    state: State<'_, AppState>,
+) -> Result<ApprovalShortcutSettings> {
+    Ok(state
+        .global_state
+        .read(|global| global.approval_shortcuts.clone()))
+}
+
+/// Update the approval shortcut settings and register the new shortcuts
+///
+/// Nothing is saved when a shortcut cannot be registered, e.g. because
+/// another app holds it; the previous shortcuts stay in place.
+#[tauri::command]
+pub async fn set_approval_shortcut_settings(
+    state: State<'_, AppState>,
+    settings: ApprovalShortcutSettings,
+) -> Result<ApprovalShortcutSettings> {
+    let summon = state
+        .global_state
+        .read(|global| global.summon_shortcuts.clone());
+    global_shortcuts::validate(&settings, &summon)?;
+    if let Err(err) = global_shortcuts::register(&state.app_handle, &settings, &summon) {
+        if let Err(restore_err) = global_shortcuts::register_saved(&state.app_handle) {
+            tracing::warn!("Failed to restore approval shortcuts: {}", restore_err);
+        }
+        return Err(err);
+    }
+    state
+        .global_state
+        .update(|global| global.approval_shortcuts = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
@@ -0,0 +1,88 @@
+//! Attachment budget commands - fit attached files into a prompt
+
+use std::collections::HashSet;
+
+use tauri::State;
+
+use crate::attachments::{self, AttachmentBudget, AttachmentFile};
+use crate::commands::projects::{read_project_file_bytes, validate_id};
+use crate::global_state::AttachmentBudgetSettings;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Files accepted in one request
+const MAX_ATTACHMENTS: usize = 200;
+
+/// Read attached project files and fit them into the token budget
+///
+/// Returns the context to add to the prompt and a per-file breakdown of
+/// what was included, shortened or left out. `settings` overrides the
+/// saved budget for this request only.
+#[tauri::command]
+pub async fn prepare_attachments(
+    state: State<'_, AppState>,
+    project_id: String,
+    paths: Vec<String>,
+    settings: Option<AttachmentBudgetSettings>,
+) -> Result<AttachmentBudget> {
+    validate_id(&project_id, "project_id")?;
+    if paths.len() > MAX_ATTACHMENTS {
+        return Err(Error::Other(format!(
+            "Too many attachments (max {MAX_ATTACHMENTS})"
+        )));
+    }
+    let settings = match settings {
+        Some(settings) => settings,
+        None => state
+            .global_state
+            .read(|global| global.attachment_budget.clone()),
+    };
+
+    let mut seen = HashSet::new();
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+        let content = match read_project_file_bytes(&state, &project_id, &path).await {
+            Ok((full_path, bytes)) => {
+                let analyzed = crate::file_content::analyze(&full_path, &bytes);
+                analyzed
+                    .text
+                    .ok_or_else(|| format!("Binary file ({})", analyzed.mime_type))
+            }
+            Err(err) => Err(err.to_string()),
+        };
+        files.push(AttachmentFile { path, content });
+    }
+
+    Ok(attachments::plan(files, &settings))
+}
+
+/// Get the saved attachment budget
+#[tauri::command]
+pub async fn get_attachment_budget_settings(
+    state: State<'_, AppState>,
+) -> Result<AttachmentBudgetSettings> {
+    Ok(state
+        .global_state
+        .read(|global| global.attachment_budget.clone()))
+}
+
+/// Update the saved attachment budget
+#[tauri::command]
+pub async fn set_attachment_budget_settings(
+    state: State<'_, AppState>,
+    settings: AttachmentBudgetSettings,
+) -> Result<AttachmentBudgetSettings> {
+    if settings.max_total_tokens == 0 || settings.max_file_tokens == 0 {
+        return Err(Error::Other(
+            "Attachment token limits must be greater than zero".to_string(),
+        ));
+    }
+    state
+        .global_state
+        .update(|global| global.attachment_budget = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
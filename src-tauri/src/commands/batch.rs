@@ -0,0 +1,278 @@
+//! Batch run command - one prompt across several projects
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::app_server::ipc_bridge::{ThreadStartParams, TurnStartParams, UserInput};
+use crate::batch::{
+    self, BatchProgress, BatchProgressEvent, BatchReport, BatchTarget, BatchTargetResult,
+    BatchTargetStatus,
+};
+use crate::commands::projects::{get_project_git_diff, git_worktree_list, validate_id};
+use crate::commands::thread::{interrupt_thread_turn, start_thread_with_params, start_turn};
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Settings shared by every target of one batch
+struct BatchJob {
+    batch_id: String,
+    prompt: String,
+    model: Option<String>,
+    sandbox: String,
+    approval_policy: String,
+    progress: Mutex<BatchProgress>,
+}
+
+/// Run a prompt in several projects (or worktrees) at once
+///
+/// Every target gets a new thread; targets run side by side within the turn
+/// concurrency limits. Emits `batch-progress` as targets start and finish
+/// and returns once all of them are done. Without an explicit policy the
+/// turns run with approvals disabled in a workspace-write sandbox, since no
+/// one is there to answer approval requests.
+#[tauri::command]
+pub async fn run_batch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    prompt: String,
+    targets: Vec<BatchTarget>,
+    model: Option<String>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+) -> Result<BatchReport> {
+    if prompt.trim().is_empty() {
+        return Err(Error::Other("Batch prompt cannot be empty".to_string()));
+    }
+    batch::validate_targets(&targets)?;
+
+    // Resolve every target first so a bad one fails the batch before any runs
+    let mut resolved = Vec::with_capacity(targets.len());
+    for target in targets {
+        validate_id(&target.project_id, "project_id")?;
+        let cwd = resolve_target_cwd(&state, &target).await?;
+        resolved.push((target, cwd));
+    }
+
+    state.start_app_server().await?;
+
+    let total = resolved.len();
+    let job = Arc::new(BatchJob {
+        batch_id: uuid::Uuid::new_v4().to_string(),
+        prompt,
+        model,
+        sandbox: sandbox.unwrap_or_else(|| batch::DEFAULT_SANDBOX.to_string()),
+        approval_policy: approval_policy
+            .unwrap_or_else(|| batch::DEFAULT_APPROVAL_POLICY.to_string()),
+        progress: Mutex::new(BatchProgress::new(total)),
+    });
+    let limits = state.global_state.read(|g| g.turn_concurrency.clone());
+    let slots = Arc::new(Semaphore::new(batch::parallelism(&limits, total)));
+    tracing::info!("Starting batch {} across {} targets", job.batch_id, total);
+
+    let mut tasks = JoinSet::new();
+    for (index, (target, cwd)) in resolved.into_iter().enumerate() {
+        let app = app.clone();
+        let job = job.clone();
+        let slots = slots.clone();
+        tasks.spawn(async move {
+            // The semaphore is never closed, so acquiring cannot fail
+            let _slot = slots.acquire_owned().await;
+            let state = app.state::<AppState>();
+            (index, run_target(&state, &job, &target, &cwd).await)
+        });
+    }
+
+    let mut results: Vec<Option<BatchTargetResult>> = vec![None; total];
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(err) => tracing::error!("Batch {} target task failed: {}", job.batch_id, err),
+        }
+    }
+
+    let progress = *job.progress.lock();
+    tracing::info!(
+        "Batch {} finished: {} succeeded, {} failed",
+        job.batch_id,
+        progress.succeeded,
+        progress.failed
+    );
+    Ok(BatchReport {
+        batch_id: job.batch_id.clone(),
+        progress,
+        results: results.into_iter().flatten().collect(),
+    })
+}
+
+/// Directory a target runs in: the project root, or a worktree of it
+async fn resolve_target_cwd(state: &AppState, target: &BatchTarget) -> Result<PathBuf> {
+    let project = state
+        .database
+        .get_project(&target.project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(target.project_id.clone()))?;
+    let project_root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+    let Some(worktree_path) = &target.worktree_path else {
+        return Ok(project_root);
+    };
+
+    let worktree = crate::utils::validate_and_canonicalize_path(worktree_path)?;
+    let is_project_worktree = git_worktree_list(project.path.clone())
+        .await?
+        .iter()
+        .filter_map(|info| crate::utils::validate_and_canonicalize_path(&info.path).ok())
+        .any(|path| path == worktree);
+    if !is_project_worktree {
+        return Err(Error::Other(format!(
+            "{worktree_path} is not a worktree of project {}",
+            target.project_id
+        )));
+    }
+    Ok(worktree)
+}
+
+/// Run the batch prompt in one target and summarize what it changed
+async fn run_target(
+    state: &AppState,
+    job: &BatchJob,
+    target: &BatchTarget,
+    cwd: &Path,
+) -> BatchTargetResult {
+    let started = Instant::now();
+    let mut result = BatchTargetResult::running(target, &cwd.to_string_lossy());
+    result.had_prior_changes = working_tree_diff(cwd)
+        .await
+        .is_some_and(|diff| !diff.trim().is_empty());
+    emit_progress(state, job, &result).await;
+
+    let run = PromptRun {
+        project_id: &target.project_id,
+        cwd,
+        prompt: &job.prompt,
+        model: job.model.clone(),
+        effort: None,
+        sandbox: &job.sandbox,
+        approval_policy: &job.approval_policy,
+    };
+    match run_prompt(state, &run, &mut result.thread_id).await {
+        Ok(status) => result.status = status,
+        Err(err) => {
+            tracing::warn!(
+                "Batch {} target {} failed: {}",
+                job.batch_id,
+                target.project_id,
+                err
+            );
+            result.status = BatchTargetStatus::Failed;
+            result.error = Some(err.to_string());
+        }
+    }
+    if result.thread_id.is_some() {
+        result.diff = working_tree_diff(cwd)
+            .await
+            .map(|diff| batch::summarize_diff(&diff));
+    }
+    result.duration_ms = started.elapsed().as_millis() as i64;
+
+    job.progress.lock().record(result.status);
+    emit_progress(state, job, &result).await;
+    result
+}
+
+/// A prompt to run unattended in a new thread
+pub(crate) struct PromptRun<'a> {
+    pub project_id: &'a str,
+    pub cwd: &'a Path,
+    pub prompt: &'a str,
+    pub model: Option<String>,
+    pub effort: Option<String>,
+    pub sandbox: &'a str,
+    pub approval_policy: &'a str,
+}
+
+/// Start a thread and its turn in `run.cwd` and wait for the turn to finish
+///
+/// `thread_id_out` is set as soon as the thread exists, so callers can
+/// report it even when the turn fails.
+pub(crate) async fn run_prompt(
+    state: &AppState,
+    run: &PromptRun<'_>,
+    thread_id_out: &mut Option<String>,
+) -> Result<BatchTargetStatus> {
+    let thread_params = ThreadStartParams {
+        cwd: Some(run.cwd.to_string_lossy().to_string()),
+        model: run.model.clone(),
+        model_provider: None,
+        sandbox: Some(run.sandbox.to_string()),
+        approval_policy: Some(run.approval_policy.to_string()),
+        base_instructions: None,
+        developer_instructions: None,
+        config: None,
+    };
+    let thread = start_thread_with_params(state, run.project_id, None, thread_params).await?;
+    let thread_id = thread.thread.id;
+    *thread_id_out = Some(thread_id.clone());
+
+    // Register before starting the turn so the completion cannot be missed
+    let completed = state.turn_waiters.register(&thread_id);
+    let turn_params = TurnStartParams {
+        thread_id: thread_id.clone(),
+        input: vec![UserInput::Text {
+            text: run.prompt.to_string(),
+        }],
+        effort: run.effort.clone(),
+        summary: None,
+        cwd: None,
+        approval_policy: None,
+        sandbox_policy: None,
+        model: run.model.clone(),
+    };
+    if let Err(err) = start_turn(state, turn_params).await {
+        state.turn_waiters.cancel(&thread_id);
+        return Err(err);
+    }
+
+    match tokio::time::timeout(Duration::from_secs(batch::TARGET_TIMEOUT_SECS), completed).await {
+        Ok(Ok(status)) => Ok(BatchTargetStatus::from_turn_status(&status)),
+        Ok(Err(_)) => Err(Error::AppServer("Turn was abandoned".to_string())),
+        Err(_) => {
+            state.turn_waiters.cancel(&thread_id);
+            if let Err(err) = interrupt_thread_turn(state, thread_id).await {
+                tracing::warn!("Failed to interrupt timed-out turn: {}", err);
+            }
+            Ok(BatchTargetStatus::TimedOut)
+        }
+    }
+}
+
+/// Working-tree diff of `cwd`, or `None` outside a git repository
+async fn working_tree_diff(cwd: &Path) -> Option<String> {
+    match get_project_git_diff(cwd.to_string_lossy().to_string()).await {
+        Ok(diff) if diff.is_git_repo => Some(diff.diff),
+        Ok(_) => None,
+        Err(err) => {
+            tracing::warn!("Failed to read diff of {}: {}", cwd.display(), err);
+            None
+        }
+    }
+}
+
+async fn emit_progress(state: &AppState, job: &BatchJob, target: &BatchTargetResult) {
+    let progress = *job.progress.lock();
+    state
+        .events
+        .emit(
+            "batch-progress",
+            BatchProgressEvent {
+                batch_id: job.batch_id.clone(),
+                progress,
+                target: target.clone(),
+            },
+        )
+        .await;
+}
@@ -2,9 +2,12 @@
 //!
 //! Tauri commands for importing and managing Codex CLI sessions.
 
+use tauri::State;
+
 use crate::codex_import::{
     CodexConfig, CodexSession, CodexSessionSummary,
 };
+use crate::state::AppState;
 use crate::Result;
 
 /// Read Codex CLI configuration from ~/.codex/config.toml
@@ -32,13 +35,19 @@ pub async fn get_codex_session(session_id: String) -> Result<CodexSession> {
 }
 
 /// Search Codex CLI sessions by keyword
+///
+/// Reads the persistent session index; the first search builds or catches
+/// it up, after which it follows the sessions directory.
 #[tauri::command]
 pub async fn search_codex_sessions(
+    state: State<'_, AppState>,
     query: String,
     limit: Option<usize>,
 ) -> Result<Vec<CodexSessionSummary>> {
     let limit = limit.unwrap_or(50);
-    tokio::task::spawn_blocking(move || crate::codex_import::search_sessions(&query, limit))
+    let index = state.codex_index.clone();
+    let database = state.database.clone();
+    tokio::task::spawn_blocking(move || index.search(&database, &query, limit))
         .await
         .map_err(|e| crate::Error::Other(format!("Task join error: {e}")))?
 }
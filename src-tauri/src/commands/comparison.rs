@@ -0,0 +1,386 @@
+//! Comparison run commands - one prompt side by side in several worktrees
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::batch;
+use crate::commands::batch::{run_prompt, PromptRun};
+use crate::commands::projects::{
+    create_worktree, git_merge_no_ff, remove_worktree, validate_id, GitMergeResult,
+};
+use crate::comparison::{
+    self, ComparisonResults, ComparisonRunResult, ComparisonUpdatedEvent, ComparisonVariant,
+};
+use crate::database::{ComparisonGroup, ComparisonRun, ComparisonRunStatus};
+use crate::session_worktrees;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Settings shared by every run of one comparison
+struct ComparisonJob {
+    group_id: String,
+    project_id: String,
+    prompt: String,
+    sandbox: String,
+    approval_policy: String,
+}
+
+/// Result of `resolve_comparison`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonResolution {
+    pub group_id: String,
+    pub winner_run_id: Option<String>,
+    /// Outcome of merging the winner's branch; on conflicts nothing is removed
+    pub merge: Option<GitMergeResult>,
+    /// The runs' worktrees and branches are gone
+    pub resolved: bool,
+}
+
+/// Run a prompt several times side by side, each run in a worktree of its own
+///
+/// Every variant gets a branch and worktree made from the project's HEAD
+/// and a new thread with the variant's model and reasoning effort. Returns
+/// once the worktrees exist; the turns run in the background within the
+/// turn concurrency limits, emitting `comparison-updated` as each run starts
+/// and finishes. As with batch runs, turns default to approvals disabled in
+/// a workspace-write sandbox.
+#[tauri::command]
+pub async fn run_comparison(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    project_id: String,
+    prompt: String,
+    variants: Vec<ComparisonVariant>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+) -> Result<ComparisonResults> {
+    validate_id(&project_id, "project_id")?;
+    if prompt.trim().is_empty() {
+        return Err(Error::Other(
+            "Comparison prompt cannot be empty".to_string(),
+        ));
+    }
+    comparison::validate_variants(&variants)?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+
+    let project_path = project.path.clone();
+    let (base_branch, base_commit) = crate::utils::spawn_blocking_io(move || {
+        let root = Path::new(&project_path);
+        Ok((
+            session_worktrees::current_branch(root),
+            session_worktrees::head_commit(root)?,
+        ))
+    })
+    .await?;
+
+    state.start_app_server().await?;
+
+    let group = ComparisonGroup {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: project.id.clone(),
+        prompt,
+        base_branch,
+        base_commit,
+        winner_run_id: None,
+        resolved_at: None,
+        created_at: crate::global_state::unix_timestamp_secs(),
+    };
+    let mut runs: Vec<ComparisonRun> = Vec::with_capacity(variants.len());
+    for (position, variant) in (0u32..).zip(variants) {
+        let variant = variant.normalized();
+        let branch = comparison::branch_name(&group.id, position);
+        let info = match create_worktree(project.path.clone(), branch, None).await {
+            Ok(info) => info,
+            Err(err) => {
+                remove_run_worktrees(&project.path, &runs, None).await;
+                return Err(err);
+            }
+        };
+        runs.push(ComparisonRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            group_id: group.id.clone(),
+            position,
+            model: variant.model,
+            effort: variant.effort,
+            worktree_path: info.path,
+            branch: info.branch,
+            thread_id: None,
+            status: ComparisonRunStatus::Running,
+            error: None,
+            duration_ms: 0,
+        });
+    }
+    if let Err(err) = state.database.insert_comparison(&group, &runs) {
+        remove_run_worktrees(&project.path, &runs, None).await;
+        return Err(err);
+    }
+
+    let job = Arc::new(ComparisonJob {
+        group_id: group.id.clone(),
+        project_id: project.id,
+        prompt: group.prompt.clone(),
+        sandbox: sandbox.unwrap_or_else(|| batch::DEFAULT_SANDBOX.to_string()),
+        approval_policy: approval_policy
+            .unwrap_or_else(|| batch::DEFAULT_APPROVAL_POLICY.to_string()),
+    });
+    let limits = state.global_state.read(|g| g.turn_concurrency.clone());
+    let slots = Arc::new(Semaphore::new(batch::parallelism(&limits, runs.len())));
+    tracing::info!("Starting comparison {} with {} runs", group.id, runs.len());
+    for run in runs.clone() {
+        let app = app.clone();
+        let job = job.clone();
+        let slots = slots.clone();
+        tauri::async_runtime::spawn(async move {
+            // The semaphore is never closed, so acquiring cannot fail
+            let _slot = slots.acquire_owned().await;
+            let state = app.state::<AppState>();
+            drive_run(&state, &job, run).await;
+        });
+    }
+
+    Ok(ComparisonResults {
+        group,
+        runs: runs
+            .into_iter()
+            .map(|run| ComparisonRunResult {
+                run,
+                diff: None,
+                summary: None,
+                diff_truncated: false,
+            })
+            .collect(),
+    })
+}
+
+/// Run the prompt in one run's worktree and record how it went
+async fn drive_run(state: &AppState, job: &ComparisonJob, mut run: ComparisonRun) {
+    emit_updated(state, job, &run).await;
+    let started = Instant::now();
+    let worktree_path = run.worktree_path.clone();
+    let prompt_run = PromptRun {
+        project_id: &job.project_id,
+        cwd: Path::new(&worktree_path),
+        prompt: &job.prompt,
+        model: run.model.clone(),
+        effort: run.effort.clone(),
+        sandbox: &job.sandbox,
+        approval_policy: &job.approval_policy,
+    };
+    match run_prompt(state, &prompt_run, &mut run.thread_id).await {
+        Ok(status) => run.status = status.into(),
+        Err(err) => {
+            tracing::warn!(
+                "Comparison {} run {} failed: {}",
+                job.group_id,
+                run.position + 1,
+                err
+            );
+            run.status = ComparisonRunStatus::Failed;
+            run.error = Some(err.to_string());
+        }
+    }
+    run.duration_ms = started.elapsed().as_millis() as i64;
+
+    if let Err(err) = state.database.update_comparison_run(&run) {
+        tracing::warn!("Failed to save comparison run {}: {}", run.id, err);
+    }
+    emit_updated(state, job, &run).await;
+}
+
+async fn emit_updated(state: &AppState, job: &ComparisonJob, run: &ComparisonRun) {
+    state
+        .events
+        .emit(
+            "comparison-updated",
+            ComparisonUpdatedEvent {
+                group_id: job.group_id.clone(),
+                run: run.clone(),
+            },
+        )
+        .await;
+}
+
+/// A project's comparisons, newest first
+#[tauri::command]
+pub async fn list_comparisons(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<ComparisonGroup>> {
+    validate_id(&project_id, "project_id")?;
+    state.database.list_comparisons(&project_id)
+}
+
+/// A comparison with each run's status and its diff against the commit the
+/// runs started from
+#[tauri::command]
+pub async fn get_comparison_results(
+    state: State<'_, AppState>,
+    group_id: String,
+) -> Result<ComparisonResults> {
+    validate_id(&group_id, "group_id")?;
+    let group = get_group(&state, &group_id)?;
+    let runs = state.database.list_comparison_runs(&group_id)?;
+    let base = group.base_commit.clone();
+    let runs = crate::utils::spawn_blocking_io(move || {
+        Ok(runs.into_iter().map(|run| run_result(run, &base)).collect())
+    })
+    .await?;
+    Ok(ComparisonResults { group, runs })
+}
+
+/// Diff of one run's worktree since `base`. Blocking.
+fn run_result(run: ComparisonRun, base: &str) -> ComparisonRunResult {
+    let worktree = Path::new(&run.worktree_path);
+    let diff = if worktree.is_dir() {
+        session_worktrees::diff_since(worktree, base)
+            .map_err(|err| tracing::warn!("Failed to diff comparison run {}: {}", run.id, err))
+            .ok()
+    } else {
+        None
+    };
+    let summary = diff.as_deref().map(batch::summarize_diff);
+    let (diff, diff_truncated) = match diff.map(comparison::truncate_diff) {
+        Some((diff, truncated)) => (Some(diff), truncated),
+        None => (None, false),
+    };
+    ComparisonRunResult {
+        run,
+        diff,
+        summary,
+        diff_truncated,
+    }
+}
+
+/// Keep one run and discard the rest, or discard them all
+///
+/// With a winner, whatever its worktree still has uncommitted is committed
+/// and its branch is merged into the project's current branch. A merge with
+/// conflicts is aborted and leaves everything in place. Then every run's
+/// worktree and branch is removed. Runs must have finished first.
+#[tauri::command]
+pub async fn resolve_comparison(
+    state: State<'_, AppState>,
+    group_id: String,
+    winner_run_id: Option<String>,
+    message: Option<String>,
+) -> Result<ComparisonResolution> {
+    validate_id(&group_id, "group_id")?;
+    let group = get_group(&state, &group_id)?;
+    if group.resolved_at.is_some() {
+        return Err(Error::Other(format!(
+            "Comparison {group_id} is already resolved"
+        )));
+    }
+    let project = state
+        .database
+        .get_project(&group.project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(group.project_id.clone()))?;
+    let runs = state.database.list_comparison_runs(&group_id)?;
+    if runs
+        .iter()
+        .any(|run| run.status == ComparisonRunStatus::Running)
+    {
+        return Err(Error::Other(
+            "Wait for every run of the comparison to finish".to_string(),
+        ));
+    }
+    let winner = match &winner_run_id {
+        Some(id) => Some(runs.iter().find(|run| &run.id == id).ok_or_else(|| {
+            Error::Other(format!("Run {id} is not part of comparison {group_id}"))
+        })?),
+        None => None,
+    };
+
+    let mut merge = None;
+    if let Some(winner) = winner {
+        if Path::new(&winner.worktree_path).is_dir() {
+            let path = winner.worktree_path.clone();
+            let commit_message = format!("Changes from comparison run {}", winner.position + 1);
+            crate::utils::spawn_blocking_io(move || {
+                session_worktrees::commit_all(Path::new(&path), &commit_message)
+            })
+            .await?;
+        }
+        let message = message
+            .filter(|message| !message.trim().is_empty())
+            .unwrap_or_else(|| format!("Merge branch '{}'", winner.branch));
+        let result = git_merge_no_ff(project.path.clone(), winner.branch.clone(), message).await?;
+        if !result.success {
+            return Ok(ComparisonResolution {
+                group_id,
+                winner_run_id,
+                merge: Some(result),
+                resolved: false,
+            });
+        }
+        merge = Some(result);
+    }
+
+    remove_run_worktrees(&project.path, &runs, winner_run_id.as_deref()).await;
+    state.database.resolve_comparison(
+        &group_id,
+        winner_run_id.as_deref(),
+        crate::global_state::unix_timestamp_secs(),
+    )?;
+    tracing::info!(
+        "Resolved comparison {} (winner: {:?})",
+        group_id,
+        winner_run_id
+    );
+
+    Ok(ComparisonResolution {
+        group_id,
+        winner_run_id,
+        merge,
+        resolved: true,
+    })
+}
+
+fn get_group(state: &AppState, group_id: &str) -> Result<ComparisonGroup> {
+    state
+        .database
+        .get_comparison(group_id)?
+        .ok_or_else(|| Error::Other(format!("Comparison {group_id} not found")))
+}
+
+/// Remove the runs' worktrees and branches, logging what cannot be removed;
+/// only the merged winner's branch is deleted without force
+async fn remove_run_worktrees(project_path: &str, runs: &[ComparisonRun], merged: Option<&str>) {
+    for run in runs {
+        if Path::new(&run.worktree_path).is_dir() {
+            if let Err(err) =
+                remove_worktree(project_path.to_string(), run.worktree_path.clone()).await
+            {
+                tracing::warn!("Failed to remove worktree {}: {}", run.worktree_path, err);
+            }
+        }
+    }
+    let project_path = project_path.to_string();
+    let branches: Vec<(String, bool)> = runs
+        .iter()
+        .map(|run| (run.branch.clone(), merged != Some(run.id.as_str())))
+        .collect();
+    let result = crate::utils::spawn_blocking_io(move || {
+        let root = Path::new(&project_path);
+        session_worktrees::prune(root)?;
+        for (branch, force) in branches {
+            if let Err(err) = session_worktrees::delete_branch(root, &branch, force) {
+                tracing::warn!("Failed to delete branch {}: {}", branch, err);
+            }
+        }
+        Ok(())
+    })
+    .await;
+    if let Err(err) = result {
+        tracing::warn!("Failed to prune worktrees: {}", err);
+    }
+}
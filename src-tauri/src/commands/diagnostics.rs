@@ -0,0 +1,48 @@
+//! Self-diagnostics command
+
+use tauri::{Manager, State};
+
+use crate::diagnostics::{self, DiagnosticsReport};
+use crate::state::{AppState, DATABASE_FILE};
+use crate::Result;
+
+/// Check the codex CLI, git, the GitHub CLI login, the database, free disk
+/// space, the app-server and file permissions
+///
+/// The checks run concurrently and none of them starts the app-server.
+/// `text` in the result is the report to copy into a bug report.
+#[tauri::command]
+pub async fn run_diagnostics(state: State<'_, AppState>) -> Result<DiagnosticsReport> {
+    let app_data_dir = state
+        .app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| crate::Error::Tauri(e.to_string()))?;
+    let launch = state
+        .global_state
+        .read(|global| global.app_server_launch.clone());
+
+    let local = {
+        let app_data_dir = app_data_dir.clone();
+        crate::utils::spawn_blocking_io(move || {
+            Ok(vec![
+                diagnostics::database(&app_data_dir.join(DATABASE_FILE), false),
+                diagnostics::disk_space(&app_data_dir),
+                diagnostics::permissions(&app_data_dir),
+            ])
+        })
+    };
+    let (codex_cli, git, gh_auth, app_server, local) = tokio::join!(
+        diagnostics::codex_cli(&launch),
+        diagnostics::git(),
+        diagnostics::gh_auth(),
+        diagnostics::app_server(&state),
+        local,
+    );
+
+    let mut checks = vec![codex_cli, app_server, git, gh_auth];
+    checks.extend(local?);
+    let report = DiagnosticsReport::new(checks);
+    tracing::info!("Diagnostics:\n{}", report.text);
+    Ok(report)
+}
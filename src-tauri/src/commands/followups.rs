@@ -0,0 +1,49 @@
+//! Follow-up queue commands
+
+use tauri::State;
+
+use crate::commands::projects::validate_id;
+use crate::followups::QueuedFollowup;
+use crate::state::AppState;
+use crate::Result;
+
+/// Queue a prompt to send after the session's current turn completes successfully
+#[tauri::command]
+pub async fn queue_followup(
+    state: State<'_, AppState>,
+    session_id: String,
+    prompt: String,
+) -> Result<QueuedFollowup> {
+    validate_id(&session_id, "session_id")?;
+    let followup = state.followups.enqueue(&session_id, prompt)?;
+    tracing::info!("Queued follow-up {} for session {}", followup.id, session_id);
+    Ok(followup)
+}
+
+/// List queued follow-ups for a session in send order
+#[tauri::command]
+pub async fn list_followups(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<QueuedFollowup>> {
+    validate_id(&session_id, "session_id")?;
+    Ok(state.followups.list(&session_id))
+}
+
+/// Remove a single queued follow-up; returns whether it was still queued
+#[tauri::command]
+pub async fn remove_followup(
+    state: State<'_, AppState>,
+    session_id: String,
+    followup_id: String,
+) -> Result<bool> {
+    validate_id(&session_id, "session_id")?;
+    Ok(state.followups.remove(&session_id, &followup_id))
+}
+
+/// Drop all queued follow-ups for a session; returns how many were dropped
+#[tauri::command]
+pub async fn clear_followups(state: State<'_, AppState>, session_id: String) -> Result<usize> {
+    validate_id(&session_id, "session_id")?;
+    Ok(state.followups.clear(&session_id))
+}
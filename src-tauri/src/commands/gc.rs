@@ -0,0 +1,22 @@
+//! Garbage collection commands
+
+use tauri::State;
+
+use crate::gc::GcReport;
+use crate::state::AppState;
+use crate::Result;
+
+/// Report orphaned data that `run_gc` would remove, with approximate sizes
+#[tauri::command]
+pub async fn get_gc_preview(state: State<'_, AppState>) -> Result<GcReport> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || crate::gc::preview(&database)).await
+}
+
+/// Remove orphaned snapshots, session rows, allowlist entries, index rows and
+/// stale attachments; returns what was removed
+#[tauri::command]
+pub async fn run_gc(state: State<'_, AppState>) -> Result<GcReport> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || crate::gc::run(&database)).await
+}
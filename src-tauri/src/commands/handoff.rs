@@ -0,0 +1,113 @@
+//! Inter-session handoff command
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::app_server::ipc_bridge::{
+    ThreadStartParams, ThreadStartResponse, TurnStartParams, TurnStartResponse, UserInput,
+};
+use crate::commands::projects::validate_id;
+use crate::commands::thread::{
+    ask_read_only, fetch_thread_items, start_thread_with_params, start_turn,
+};
+use crate::handoff;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// How long to wait for the summarizer turn to finish
+const SUMMARY_TIMEOUT_SECS: u64 = 300;
+
+/// Result of a handoff: the summary and the new thread seeded with it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandoffResponse {
+    pub summary: String,
+    pub thread: ThreadStartResponse,
+    pub turn: TurnStartResponse,
+}
+
+/// Summarize a session and continue it as a new thread in another project
+///
+/// The summary is produced by a throwaway read-only thread in the source
+/// project, so the source session itself is left untouched.
+#[tauri::command]
+pub async fn handoff_session(
+    state: State<'_, AppState>,
+    from_session: String,
+    to_project: String,
+    instructions: Option<String>,
+    model: Option<String>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+) -> Result<HandoffResponse> {
+    validate_id(&from_session, "from_session")?;
+    validate_id(&to_project, "to_project")?;
+
+    let target = state
+        .database
+        .get_project(&to_project)?
+        .ok_or_else(|| Error::ProjectNotFound(to_project.clone()))?;
+    let target_root = crate::utils::validate_and_canonicalize_path(&target.path)?;
+
+    let (source, items) = fetch_thread_items(&state, &from_session).await?;
+    let transcript = handoff::render_transcript(&items);
+    if transcript.is_empty() {
+        return Err(Error::Other(format!(
+            "Session {from_session} has no messages to hand off"
+        )));
+    }
+
+    let summary = ask_read_only(
+        &state,
+        &source.cwd,
+        handoff::summary_prompt(&transcript),
+        model.clone(),
+        None,
+        Duration::from_secs(SUMMARY_TIMEOUT_SECS),
+        "Summarizer",
+    )
+    .await?;
+
+    let thread_params = ThreadStartParams {
+        cwd: Some(target_root.to_string_lossy().to_string()),
+        model: model.clone(),
+        model_provider: None,
+        sandbox,
+        approval_policy,
+        base_instructions: None,
+        developer_instructions: None,
+        config: None,
+    };
+    let thread = start_thread_with_params(&state, &to_project, None, thread_params).await?;
+
+    let seed = handoff::seed_prompt(&source.cwd, &summary, instructions.as_deref());
+    let turn = start_turn(
+        &state,
+        TurnStartParams {
+            thread_id: thread.thread.id.clone(),
+            input: vec![UserInput::Text { text: seed }],
+            effort: None,
+            summary: None,
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model,
+        },
+    )
+    .await?;
+
+    tracing::info!(
+        "Handed off session {} to project {} as thread {}",
+        from_session,
+        to_project,
+        thread.thread.id
+    );
+
+    Ok(HandoffResponse {
+        summary,
+        thread,
+        turn,
+    })
+}
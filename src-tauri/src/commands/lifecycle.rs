@@ -1,9 +1,14 @@
 //! Renderer lifecycle commands.
 
+use serde::Serialize;
 use tauri::State;
 
-use crate::global_state::{unix_timestamp_millis, unix_timestamp_secs};
-use crate::state::AppState;
+use crate::background_tasks::{self, BackgroundTaskStatus};
+use crate::global_state::{
+    unix_timestamp_millis, unix_timestamp_secs, BackendHeartbeatSettings, BackgroundTaskSettings,
+};
+use crate::safe_mode::{self, SafeModeReason};
+use crate::state::{clamp_heartbeat_interval, AppState};
 use crate::Result;
 
 /// Signal that renderer has finished loading and is ready to receive events.
@@ -38,3 +43,84 @@ pub async fn renderer_heartbeat(state: State<'_, AppState>) -> Result<()> {
     });
     Ok(())
 }
+
+/// Current `backend-heartbeat` settings.
+#[tauri::command]
+pub async fn get_backend_heartbeat_config(
+    state: State<'_, AppState>,
+) -> Result<BackendHeartbeatSettings> {
+    Ok(state.global_state.read(|global| global.backend_heartbeat.clone()))
+}
+
+/// Enable/disable the `backend-heartbeat` event and set its interval (clamped to 1-300s).
+#[tauri::command]
+pub async fn set_backend_heartbeat_config(
+    state: State<'_, AppState>,
+    enabled: bool,
+    interval_secs: Option<u64>,
+) -> Result<BackendHeartbeatSettings> {
+    state.global_state.update(|global| {
+        global.backend_heartbeat.enabled = enabled;
+        if let Some(secs) = interval_secs {
+            global.backend_heartbeat.interval_secs = clamp_heartbeat_interval(secs);
+        }
+    });
+    state.global_state.flush()?;
+    Ok(state.global_state.read(|global| global.backend_heartbeat.clone()))
+}
+
+/// Background task settings and the state of each registered task.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTasksInfo {
+    pub settings: BackgroundTaskSettings,
+    pub tasks: Vec<BackgroundTaskStatus>,
+}
+
+/// Current background task settings and task states.
+#[tauri::command]
+pub async fn get_background_tasks(state: State<'_, AppState>) -> Result<BackgroundTasksInfo> {
+    Ok(BackgroundTasksInfo {
+        settings: state.global_state.read(|global| global.background_tasks.clone()),
+        tasks: state.background_tasks.statuses(),
+    })
+}
+
+/// Pause, resume, limit or turn off background tasks; applies immediately.
+///
+/// Turning project watchers off (or pausing everything) stops the running
+/// watchers; the renderer watches projects again once they are back on.
+#[tauri::command]
+pub async fn set_background_task_settings(
+    state: State<'_, AppState>,
+    settings: BackgroundTaskSettings,
+) -> Result<BackgroundTasksInfo> {
+    background_tasks::validate_settings(&settings)?;
+    state.global_state.update(|global| global.background_tasks = settings.clone());
+    state.global_state.flush()?;
+    state.background_tasks.configure(settings.clone());
+    if !state.background_tasks.is_enabled(background_tasks::PROJECT_WATCHERS) {
+        state.watchers.unwatch_all();
+    }
+    Ok(BackgroundTasksInfo {
+        settings,
+        tasks: state.background_tasks.statuses(),
+    })
+}
+
+/// Why the app booted in safe mode, or `None` for a normal boot.
+#[tauri::command]
+pub async fn get_safe_mode_reason(state: State<'_, AppState>) -> Result<Option<SafeModeReason>> {
+    Ok(state.safe_mode.clone())
+}
+
+/// Reset the crash-loop counter and relaunch the app normally.
+#[tauri::command]
+pub async fn exit_safe_mode(state: State<'_, AppState>) -> Result<()> {
+    state
+        .global_state
+        .update(|global| safe_mode::mark_stable(&mut global.startup));
+    state.global_state.flush()?;
+    tracing::info!("Leaving safe mode, relaunching");
+    state.app_handle.restart()
+}
@@ -0,0 +1,44 @@
+//! Locale commands
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::i18n::{self, Locale};
+use crate::state::AppState;
+use crate::Result;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleInfo {
+    pub locale: Locale,
+    /// Locales with bundled translations
+    pub available: Vec<Locale>,
+}
+
+fn locale_info() -> LocaleInfo {
+    LocaleInfo {
+        locale: i18n::current(),
+        available: Locale::ALL.to_vec(),
+    }
+}
+
+/// Get the locale of error messages and notifications from the backend
+#[tauri::command]
+pub async fn get_locale() -> Result<LocaleInfo> {
+    Ok(locale_info())
+}
+
+/// Set the backend locale from a tag like "zh-CN" or the renderer's
+/// `navigator.language`; languages without translations fall back to
+/// English. Applies to everything sent from now on.
+#[tauri::command]
+pub async fn set_locale(state: State<'_, AppState>, tag: String) -> Result<LocaleInfo> {
+    let locale = Locale::from_tag(&tag).unwrap_or_default();
+    i18n::set(locale);
+    state
+        .global_state
+        .update(|global| global.locale.locale = locale);
+    state.global_state.flush()?;
+    tracing::info!("Backend locale set to {}", locale.tag());
+    Ok(locale_info())
+}
@@ -3,12 +3,32 @@
 //! Exposes Tauri commands for the frontend to invoke.
 
 pub mod allowlist;
+pub mod app_lock;
+pub mod approval_shortcuts;
 pub mod app_server;
+pub mod attachments;
+pub mod batch;
 pub mod codex_import;
+pub mod comparison;
+pub mod diagnostics;
+pub mod followups;
+pub mod gc;
+pub mod handoff;
 pub mod lifecycle;
+pub mod locale;
+pub mod notifications;
+pub mod pending_work;
 pub mod projects;
+pub mod pull_requests;
+pub mod quick_prompt;
 pub mod sessions;
+pub mod settings_transfer;
 pub mod snapshots;
 pub mod system;
 pub mod terminal;
 pub mod thread;
+pub mod tray;
+pub mod turn_queue;
+pub mod usage;
+pub mod watcher;
+pub mod workflows;
@@ -0,0 +1,32 @@
+//! Notification preference commands
+
+use tauri::State;
+
+use crate::global_state::NotificationSettings;
+use crate::notifications;
+use crate::state::AppState;
+use crate::Result;
+
+/// Get which native notifications are shown, and when
+#[tauri::command]
+pub async fn get_notification_preferences(
+    state: State<'_, AppState>,
+) -> Result<NotificationSettings> {
+    Ok(state
+        .global_state
+        .read(|global| global.notifications.clone()))
+}
+
+/// Update the notification preferences; they apply to the next notification
+#[tauri::command]
+pub async fn set_notification_preferences(
+    state: State<'_, AppState>,
+    settings: NotificationSettings,
+) -> Result<NotificationSettings> {
+    notifications::validate(&settings)?;
+    state
+        .global_state
+        .update(|global| global.notifications = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
@@ -0,0 +1,60 @@
+//! Pending work digest commands
+
+use tauri::State;
+
+use crate::global_state::unix_timestamp_millis;
+use crate::pending_work::{self, PendingWorkSummary};
+use crate::state::AppState;
+use crate::Result;
+
+/// Unfinished turns, queued messages, pending approvals, failed queue
+/// entries and unpushed worktree commits across all projects
+///
+/// Cheap enough to call whenever the window regains focus.
+#[tauri::command]
+pub async fn get_pending_work_summary(state: State<'_, AppState>) -> Result<PendingWorkSummary> {
+    let unfinished_turns = state.active_turns.list();
+    let pending_approvals = state.approvals.pending.list();
+    let followups = state.followups.counts();
+    let paused_projects: Vec<String> = state
+        .global_state
+        .read(|global| global.scheduler.paused_projects.iter().cloned().collect());
+    let database = state.database.clone();
+    let cache = state.pending_work.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let followups = followups
+            .into_iter()
+            .map(|(session_id, count)| {
+                let project_id = database
+                    .get_session_by_id(&session_id)
+                    .ok()
+                    .flatten()
+                    .map(|session| session.project_id);
+                (session_id, project_id, count)
+            })
+            .collect();
+        let queue = database.list_turn_queue(None, false)?;
+        let queued_messages = pending_work::group_queued_messages(followups, &queue);
+
+        let mut failed_tasks = Vec::new();
+        for project_id in &paused_projects {
+            let failed = database
+                .list_turn_queue(Some(project_id), true)?
+                .into_iter()
+                .find(|entry| entry.status == "failed");
+            failed_tasks.extend(failed);
+        }
+
+        let unpushed_commits = cache.unpushed_commits(&database)?;
+        Ok(PendingWorkSummary::new(
+            unfinished_turns,
+            queued_messages,
+            pending_approvals,
+            failed_tasks,
+            unpushed_commits,
+            unix_timestamp_millis(),
+        ))
+    })
+    .await
+}
@@ -1,12 +1,24 @@
 //! Project management commands
 
 use std::path::Path;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 
 use tauri::State;
 
-use crate::database::{Project, ProjectSettings};
+use crate::change_report::{self, ReportRange};
+use crate::commit_message::{self, CommitMessageSuggestion};
+use crate::commit_signing::SigningConfig;
+use crate::conflict_markers::{self, ConflictedFile};
+use crate::database::{ArchivedProject, Database, HookBypass, Project, ProjectUpdate};
+use crate::file_index::{IgnoreExplanation, IgnoreRules};
+use crate::forge::{self, CreatedPullRequest, ForgeProvider, ForgeStatus, NewPullRequest};
+use crate::git_hooks::{self, GitHooks, HookOperation};
+use crate::patch_preflight::{self, ApplyOptions, PatchIssue, PatchPreflight};
+use crate::project_env::ProjectEnvVar;
+use crate::redaction::{RedactionReport, Redactor};
 use crate::state::AppState;
+use crate::transcript_export::ExportFormat;
 use crate::Result;
 
 /// Validate ID parameter (project_id, session_id, thread_id, etc.)
@@ -63,7 +75,7 @@ fn validate_arg_safe(arg: &str) -> Result<()> {
 /// Validate git branch name
 /// Only allows safe characters: alphanumeric, underscore, hyphen, dot, and forward slash
 /// This prevents command injection through malicious branch names
-fn validate_branch_name(branch: &str) -> Result<()> {
+pub(crate) fn validate_branch_name(branch: &str) -> Result<()> {
     if branch.is_empty() {
         return Err(crate::Error::Other(
             "Branch name cannot be empty".to_string(),
@@ -111,7 +123,6 @@ fn validate_branch_name(branch: &str) -> Result<()> {
 }
 
 /// Validate git commit SHA (hexadecimal string only)
-#[allow(dead_code)]
 fn validate_commit_sha(sha: &str) -> Result<()> {
     // Only allow hexadecimal characters (0-9, a-f, A-F)
     if !sha.chars().all(|c| c.is_ascii_hexdigit()) {
@@ -154,7 +165,7 @@ fn validate_git_file_path(path: &str) -> Result<()> {
 
 /// Validate a project-relative file path
 /// Prevents path traversal and absolute paths
-fn validate_relative_project_path(path: &str) -> Result<String> {
+pub(crate) fn validate_relative_project_path(path: &str) -> Result<String> {
     if path.is_empty() {
         return Err(crate::Error::InvalidPath(
             "File path cannot be empty".to_string(),
@@ -261,39 +272,261 @@ pub async fn add_project(state: State<'_, AppState>, path: String) -> Result<Pro
 #[tauri::command]
 pub async fn remove_project(state: State<'_, AppState>, id: String) -> Result<()> {
     validate_id(&id, "project_id")?;
+    if let Some(project) = state.database.get_project(&id)? {
+        if let Ok(root) = Path::new(&project.path).canonicalize() {
+            state.file_index.remove(&root);
+        }
+    }
+    state.watchers.unwatch(&id);
+    state.ci_watchers.unwatch(&id);
     state.database.delete_project(&id)?;
     tracing::info!("Removed project: {}", id);
     Ok(())
 }
 
-/// Update project settings
+/// Move a project's sessions, snapshots and settings to a compressed archive
+///
+/// `destination` is a directory (a file name is generated) or a file path.
+/// The archive is written before anything is removed from the database.
 #[tauri::command]
-pub async fn update_project(
+pub async fn archive_project(
     state: State<'_, AppState>,
     id: String,
-    display_name: Option<String>,
-    settings: Option<ProjectSettings>,
-) -> Result<Project> {
+    destination: String,
+) -> Result<ArchivedProject> {
     validate_id(&id, "project_id")?;
+    let destination = Path::new(&destination);
+    if !destination.is_absolute() {
+        return Err(crate::Error::InvalidPath(format!(
+            "Archive destination must be absolute: {}",
+            destination.display()
+        )));
+    }
     let project = state
         .database
         .get_project(&id)?
         .ok_or_else(|| crate::Error::ProjectNotFound(id.clone()))?;
+    let path = crate::project_archive::archive_path(destination, &project);
 
-    // For now, we'll need to delete and re-insert
-    // A proper UPDATE query would be better
-    let mut updated = project;
-    if let Some(name) = display_name {
-        updated.display_name = Some(name);
+    if let Ok(root) = Path::new(&project.path).canonicalize() {
+        state.file_index.remove(&root);
     }
-    if let Some(s) = settings {
-        updated.settings_json = Some(serde_json::to_string(&s).unwrap_or_default());
+    state.watchers.unwatch(&id);
+    state.ci_watchers.unwatch(&id);
+
+    let database = state.database.clone();
+    let archived = crate::utils::spawn_blocking_io(move || {
+        let archive = database
+            .export_project(&id)?
+            .ok_or_else(|| crate::Error::ProjectNotFound(id.clone()))?;
+        crate::project_archive::write(&path, &archive)?;
+        database.mark_project_archived(&archive, &path.to_string_lossy())
+    })
+    .await?;
+
+    tracing::info!(
+        "Archived project {} ({} sessions) to {}",
+        archived.id,
+        archived.session_count,
+        archived.archive_path
+    );
+    Ok(archived)
+}
+
+/// Restore a project from an archive written by `archive_project`
+///
+/// The archive file is left in place.
+#[tauri::command]
+pub async fn unarchive_project(state: State<'_, AppState>, archive_path: String) -> Result<Project> {
+    let path = crate::utils::validate_and_canonicalize_path(&archive_path)?;
+    let database = state.database.clone();
+    let project = crate::utils::spawn_blocking_io(move || {
+        let archive = crate::project_archive::read(&path)?;
+        database.restore_project(&archive)?;
+        Ok(archive.project)
+    })
+    .await?;
+
+    tracing::info!("Restored archived project: {}", project.id);
+    Ok(project)
+}
+
+/// Projects moved to cold storage, most recently archived first
+#[tauri::command]
+pub async fn list_archived_projects(state: State<'_, AppState>) -> Result<Vec<ArchivedProject>> {
+    state.database.list_archived_projects()
+}
+
+/// Update a project's display name, settings and/or last-opened time
+///
+/// `settings` is a JSON merge patch over the stored settings: omitted keys
+/// are kept and `null` clears a key. Linked sessions and allowlist entries
+/// are untouched.
+#[tauri::command]
+pub async fn update_project(
+    state: State<'_, AppState>,
+    id: String,
+    display_name: Option<String>,
+    settings: Option<serde_json::Value>,
+    last_opened_at: Option<i64>,
+) -> Result<Project> {
+    validate_id(&id, "project_id")?;
+    if let Some(patch) = &settings {
+        if !patch.is_object() {
+            return Err(crate::Error::Other(
+                "settings must be a JSON object".to_string(),
+            ));
+        }
+        if let Some(rules) = patch.get("ignoreRules").filter(|rules| !rules.is_null()) {
+            let rules: Vec<String> = serde_json::from_value(rules.clone())
+                .map_err(|e| crate::Error::Other(format!("Invalid ignoreRules: {e}")))?;
+            IgnoreRules::new(Path::new("/"), &rules)?;
+        }
+        if let Some(profile) = patch.get("sandboxProfile").filter(|p| !p.is_null()) {
+            let profile: crate::sandbox::SandboxProfile = serde_json::from_value(profile.clone())
+                .map_err(|e| crate::Error::Other(format!("Invalid sandboxProfile: {e}")))?;
+            crate::sandbox::validate_profile(&profile)?;
+        }
+        if let Some(account_id) = patch.get("accountId").filter(|id| !id.is_null()) {
+            let account_id = account_id.as_str().ok_or_else(|| {
+                crate::Error::Other("accountId must be a string".to_string())
+            })?;
+            if !state.database.list_accounts()?.iter().any(|a| a.id == account_id) {
+                return Err(crate::Error::Other(format!("Account not found: {account_id}")));
+            }
+        }
     }
 
-    state.database.delete_project(&id)?;
-    state.database.insert_project(&updated)?;
+    let update = ProjectUpdate {
+        display_name,
+        settings_patch: settings,
+        last_opened_at,
+    };
+    state
+        .database
+        .update_project(&id, &update)?
+        .ok_or(crate::Error::ProjectNotFound(id))
+}
+
+/// Set (or replace) a project environment variable; the value is stored
+/// encrypted
+///
+/// Variables reach terminal commands run for the project and threads
+/// started in it afterwards. `secret` (the default) masks the value in logs
+/// and events.
+#[tauri::command]
+pub async fn set_project_env(
+    state: State<'_, AppState>,
+    project_id: String,
+    name: String,
+    value: String,
+    secret: Option<bool>,
+) -> Result<()> {
+    validate_id(&project_id, "project_id")?;
+    let secret = secret.unwrap_or(true);
+    let name = name.trim().to_string();
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        if database.get_project(&project_id)?.is_none() {
+            return Err(crate::Error::ProjectNotFound(project_id));
+        }
+        crate::project_env::set(&database, &project_id, &name, &value, secret)
+    })
+    .await
+}
+
+/// A project's environment variables; values are included only with `reveal`
+#[tauri::command]
+pub async fn list_project_env(
+    state: State<'_, AppState>,
+    project_id: String,
+    reveal: Option<bool>,
+) -> Result<Vec<ProjectEnvVar>> {
+    validate_id(&project_id, "project_id")?;
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        crate::project_env::list(&database, &project_id, reveal.unwrap_or(false))
+    })
+    .await
+}
+
+/// Returns whether the variable existed
+#[tauri::command]
+pub async fn delete_project_env(
+    state: State<'_, AppState>,
+    project_id: String,
+    name: String,
+) -> Result<bool> {
+    validate_id(&project_id, "project_id")?;
+    state.database.delete_project_env(&project_id, name.trim())
+}
+
+/// Maximum length of a project icon name
+const MAX_PROJECT_ICON_LEN: usize = 64;
+
+/// Maximum length (in chars) of a project emoji, allowing ZWJ sequences
+const MAX_PROJECT_EMOJI_CHARS: usize = 16;
+
+fn validate_project_color(color: &str) -> Result<()> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(crate::Error::Other(format!(
+            "Invalid color '{color}': expected #rrggbb"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_project_icon(icon: &str) -> Result<()> {
+    let valid = !icon.is_empty()
+        && icon.len() <= MAX_PROJECT_ICON_LEN
+        && icon.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        return Err(crate::Error::Other(format!(
+            "Invalid icon '{icon}': use lowercase letters, digits and hyphens"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_project_emoji(emoji: &str) -> Result<()> {
+    let count = emoji.chars().count();
+    if count == 0 || count > MAX_PROJECT_EMOJI_CHARS || emoji.chars().any(char::is_whitespace) {
+        return Err(crate::Error::Other("Invalid emoji".to_string()));
+    }
+    Ok(())
+}
+
+/// Set a project's color, icon and emoji
+///
+/// Each call replaces all three; omit `color` to restore the default color
+/// derived from the project path, and omit `icon`/`emoji` to clear them.
+#[tauri::command]
+pub async fn set_project_appearance(
+    state: State<'_, AppState>,
+    id: String,
+    color: Option<String>,
+    icon: Option<String>,
+    emoji: Option<String>,
+) -> Result<Project> {
+    validate_id(&id, "project_id")?;
+    let color = color.map(|c| c.to_ascii_lowercase());
+    if let Some(color) = &color {
+        validate_project_color(color)?;
+    }
+    if let Some(icon) = &icon {
+        validate_project_icon(icon)?;
+    }
+    if let Some(emoji) = &emoji {
+        validate_project_emoji(emoji)?;
+    }
 
-    Ok(updated)
+    state
+        .database
+        .set_project_appearance(&id, color.as_deref(), icon.as_deref(), emoji.as_deref())?
+        .ok_or(crate::Error::ProjectNotFound(id))
 }
 
 /// Get git information for a project
@@ -309,19 +542,17 @@ pub async fn get_project_git_info(path: String) -> Result<GitInfo> {
                 branch: None,
                 is_dirty: None,
                 last_commit: None,
+                head: None,
+                is_shallow: None,
             });
         }
 
-        // Get current branch
-        let branch_output = std::process::Command::new("git")
-            .args(["rev-parse", "--abbrev-ref", "HEAD"])
-            .current_dir(&canonical_path)
-            .output()
-            .ok();
-
-        let branch = branch_output
-            .filter(|o| o.status.success())
-            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+        // Resolve HEAD (branch, detached, or unborn)
+        let head = resolve_head_state(&canonical_path).ok();
+        let branch = head
+            .as_ref()
+            .and_then(|h| h.branch_name())
+            .map(str::to_string);
 
         // Check if dirty
         let status_output = std::process::Command::new("git")
@@ -350,6 +581,8 @@ pub async fn get_project_git_info(path: String) -> Result<GitInfo> {
             branch,
             is_dirty,
             last_commit,
+            head,
+            is_shallow: Some(is_shallow_repo(&canonical_path)),
         })
     })
     .await
@@ -370,22 +603,7 @@ pub async fn get_project_git_diff(path: String) -> Result<GitDiff> {
         }
 
         let tracked_diff = run_git_capture_diff(&canonical_path, &["diff"])?;
-        let untracked_output =
-            run_git_capture_stdout(&canonical_path, &["ls-files", "--others", "--exclude-standard"])?;
-
-        let mut untracked_diff = String::new();
-        let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
-
-        for file in untracked_output.lines().map(str::trim).filter(|s| !s.is_empty()) {
-            // Security: Validate file path argument to prevent command injection
-            validate_git_file_path(file)?;
-
-            // Use safe argument construction with "--" separator to prevent option injection
-            let diff_result = run_git_diff_file(&canonical_path, null_path, file);
-            if let Ok(diff) = diff_result {
-                untracked_diff.push_str(&diff);
-            }
-        }
+        let untracked_diff = untracked_files_diff(&canonical_path)?;
 
         Ok(GitDiff {
             is_git_repo: true,
@@ -395,6 +613,65 @@ pub async fn get_project_git_diff(path: String) -> Result<GitDiff> {
     .await
 }
 
+/// Hash of git's empty tree, the baseline of a repository with no commits
+const EMPTY_TREE_SHA: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// The commit HEAD points at, to diff a session's changes against later
+///
+/// Repositories without commits yet get the empty tree. Returns `None`
+/// outside a git repository. Blocking.
+pub(crate) fn resolve_baseline_commit(path: &Path) -> Result<Option<String>> {
+    if !inside_git_repo(path)? {
+        return Ok(None);
+    }
+    let head = match resolve_head_state(path)? {
+        GitHeadState::Unborn { .. } => EMPTY_TREE_SHA.to_string(),
+        GitHeadState::Branch { .. } | GitHeadState::Detached { .. } => {
+            crate::git::value(path, &["rev-parse", "HEAD"])?
+        }
+    };
+    Ok(Some(head))
+}
+
+/// Diff of the working tree (tracked and untracked files) against `baseline`
+///
+/// Commits made since the baseline are included, so this is everything
+/// that changed since then. Blocking.
+pub(crate) fn git_diff_against(path: &Path, baseline: &str) -> Result<String> {
+    validate_commit_sha(baseline)?;
+    let tree = format!("{baseline}^{{tree}}");
+    if crate::git::run(path, &["rev-parse", "--verify", "-q", &tree]).is_err() {
+        return Err(crate::Error::Git(format!(
+            "Baseline commit {} is no longer in the repository",
+            &baseline[..baseline.len().min(7)]
+        )));
+    }
+    let tracked_diff = run_git_capture_diff(path, &["diff", "--no-color", baseline, "--"])?;
+    let untracked_diff = untracked_files_diff(path)?;
+    Ok(format!("{tracked_diff}{untracked_diff}"))
+}
+
+/// Diffs of untracked, non-ignored files against an empty file
+fn untracked_files_diff(project_path: &Path) -> Result<String> {
+    let untracked_output =
+        crate::git::run(project_path, &["ls-files", "--others", "--exclude-standard"])?;
+
+    let mut untracked_diff = String::new();
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+
+    for file in untracked_output.lines().map(str::trim).filter(|s| !s.is_empty()) {
+        // Security: Validate file path argument to prevent command injection
+        validate_git_file_path(file)?;
+
+        // Use safe argument construction with "--" separator to prevent option injection
+        let diff_result = run_git_diff_file(project_path, null_path, file);
+        if let Ok(diff) = diff_result {
+            untracked_diff.push_str(&diff);
+        }
+    }
+    Ok(untracked_diff)
+}
+
 /// Git repository information
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -403,6 +680,43 @@ pub struct GitInfo {
     pub branch: Option<String>,
     pub is_dirty: Option<bool>,
     pub last_commit: Option<String>,
+    /// Resolved HEAD state (branch, detached, or unborn)
+    pub head: Option<GitHeadState>,
+    /// Whether the repository is a shallow clone
+    pub is_shallow: Option<bool>,
+}
+
+/// Resolved state of HEAD, used by branch-dependent commands
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum GitHeadState {
+    /// HEAD points at a local branch with at least one commit
+    Branch { name: String },
+    /// HEAD is detached at a specific commit
+    Detached { sha: String },
+    /// HEAD points at a branch that has no commits yet
+    Unborn { name: String },
+}
+
+impl GitHeadState {
+    /// Branch name for attached (or unborn) HEAD
+    pub fn branch_name(&self) -> Option<&str> {
+        match self {
+            GitHeadState::Branch { name } | GitHeadState::Unborn { name } => Some(name.as_str()),
+            GitHeadState::Detached { .. } => None,
+        }
+    }
+
+    /// Why a branch-dependent operation cannot proceed on this HEAD
+    fn unusable(&self) -> Option<crate::Error> {
+        match self {
+            GitHeadState::Branch { .. } => None,
+            GitHeadState::Detached { sha } => Some(crate::Error::DetachedHead {
+                sha: sha[..sha.len().min(7)].to_string(),
+            }),
+            GitHeadState::Unborn { name } => Some(crate::Error::UnbornBranch(name.clone())),
+        }
+    }
 }
 
 /// Git diff response
@@ -413,7 +727,7 @@ pub struct GitDiff {
     pub diff: String,
 }
 
-fn inside_git_repo(project_path: &Path) -> Result<bool> {
+pub(crate) fn inside_git_repo(project_path: &Path) -> Result<bool> {
     let status = std::process::Command::new("git")
         .args(["rev-parse", "--is-inside-work-tree"])
         .current_dir(project_path)
@@ -422,28 +736,69 @@ fn inside_git_repo(project_path: &Path) -> Result<bool> {
     match status {
         Ok(s) if s.success() => Ok(true),
         Ok(_) => Ok(false),
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        // Spawning in a missing directory fails the same way as a missing git
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            if project_path.is_dir() {
+                Err(crate::Error::GitNotFound)
+            } else {
+                Ok(false)
+            }
+        }
         Err(err) => Err(crate::Error::Other(format!(
             "Failed to check git repo: {err}"
         ))),
     }
 }
 
-fn run_git_capture_stdout(project_path: &Path, args: &[&str]) -> Result<String> {
+/// Resolve HEAD into a branch, detached, or unborn state
+pub(crate) fn resolve_head_state(project_path: &Path) -> Result<GitHeadState> {
     let output = std::process::Command::new("git")
-        .args(args)
+        .args(["symbolic-ref", "-q", "--short", "HEAD"])
         .current_dir(project_path)
         .output()
-        .map_err(|err| crate::Error::Other(format!("Failed to run git: {err}")))?;
+        .map_err(|err| crate::Error::Git(format!("Failed to run git: {err}")))?;
 
     if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
-    } else {
-        Err(crate::Error::Other(format!(
-            "git {:?} failed with status {}",
-            args, output.status
-        )))
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let has_commits = std::process::Command::new("git")
+            .args(["rev-parse", "--verify", "-q", "HEAD"])
+            .current_dir(project_path)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+
+        return Ok(if has_commits {
+            GitHeadState::Branch { name }
+        } else {
+            GitHeadState::Unborn { name }
+        });
+    }
+
+    // symbolic-ref exits with 1 when HEAD is not a symbolic ref (detached)
+    if output.status.code() == Some(1) {
+        let sha = crate::git::value(project_path, &["rev-parse", "HEAD"])?;
+        return Ok(GitHeadState::Detached { sha });
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(crate::Error::Git(format!("Failed to resolve HEAD: {}", stderr.trim())))
+}
+
+/// Resolve HEAD and require it to be on a branch with commits, for
+/// operations that act on the current branch rather than a named one
+fn require_branch(project_path: &Path) -> Result<String> {
+    let head = resolve_head_state(project_path)?;
+    if let Some(err) = head.unusable() {
+        return Err(err);
     }
+    Ok(head.branch_name().unwrap_or_default().to_string())
+}
+
+/// Check whether the repository is a shallow clone
+fn is_shallow_repo(project_path: &Path) -> bool {
+    crate::git::run(project_path, &["rev-parse", "--is-shallow-repository"])
+        .map(|s| s.trim() == "true")
+        .unwrap_or(false)
 }
 
 fn run_git_capture_diff(project_path: &Path, args: &[&str]) -> Result<String> {
@@ -487,219 +842,1097 @@ fn run_git_diff_file(project_path: &Path, null_path: &str, file_path: &str) -> R
     }
 }
 
-/// File entry for @ mention autocomplete
+/// File entry for @ mention autocomplete
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileEntry {
+    /// Relative path from project root
+    pub path: String,
+    /// File name only
+    pub name: String,
+    /// Whether it's a directory
+    pub is_dir: bool,
+}
+
+/// Default and maximum traversal depth for file listing
+const DEFAULT_LIST_DEPTH: usize = 32;
+const MAX_LIST_DEPTH: usize = 128;
+
+/// Default and maximum number of entries returned by file listing
+const DEFAULT_LIST_LIMIT: usize = 100;
+const MAX_LIST_LIMIT: usize = 10_000;
+
+/// File listing response
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileListResponse {
+    pub files: Vec<FileEntry>,
+    /// Whether more entries matched than `limit` allowed
+    pub truncated: bool,
+}
+
+/// List project files for @ mention autocomplete
+///
+/// Respects .gitignore, .git/info/exclude and global git excludes.
+#[tauri::command]
+pub async fn list_project_files(
+    state: State<'_, AppState>,
+    path: String,
+    query: Option<String>,
+    limit: Option<usize>,
+    max_depth: Option<usize>,
+) -> Result<FileListResponse> {
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        // Security: Canonicalize path to prevent traversal attacks
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        let rules = project_ignore_rules(&database, &canonical_path)?;
+
+        let query_lower = query.as_ref().map(|q| q.to_lowercase());
+        let max_files = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        let max_depth = max_depth.unwrap_or(DEFAULT_LIST_DEPTH).min(MAX_LIST_DEPTH);
+
+        let (mut files, truncated) = walk_project_files(
+            &canonical_path,
+            &rules,
+            query_lower.as_deref(),
+            max_files,
+            max_depth,
+        );
+
+        // Sort: directories first, then by path
+        files.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.path.cmp(&b.path),
+        });
+
+        Ok(FileListResponse { files, truncated })
+    })
+    .await
+}
+
+/// Ranked fuzzy search over the project's in-memory file index
+///
+/// The index is built on first use and refreshed after filesystem changes,
+/// so repeated keystrokes do not re-walk the disk.
+#[tauri::command]
+pub async fn search_project_files(
+    state: State<'_, AppState>,
+    project_id: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<crate::file_index::FileMatch>> {
+    validate_id(&project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
+    let file_index = state.file_index.clone();
+    let watchers = state.watchers.clone();
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+        let rules = project_ignore_rules(&database, &root)?;
+        let watched = watchers.is_watching(&project_id, &root);
+        let max_results = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+        Ok(file_index.search(&root, &rules, watched, &query, max_results))
+    })
+    .await
+}
+
+/// Default and maximum context lines around each content match
+const DEFAULT_SEARCH_CONTEXT_LINES: usize = 2;
+const MAX_SEARCH_CONTEXT_LINES: usize = 10;
+
+/// Default and maximum number of content matches per search
+const DEFAULT_SEARCH_MAX_RESULTS: usize = 1000;
+const MAX_SEARCH_MAX_RESULTS: usize = 10_000;
+
+/// Regex or literal search across project file contents
+///
+/// Respects the same ignore rules as file listing and skips binary files.
+/// Matches are streamed per file as `project-search:match` events, followed
+/// by a single `project-search:done` event; the returned summary matches the
+/// final event. Cancel with `cancel_project_content_search`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_project_content(
+    state: State<'_, AppState>,
+    search_id: String,
+    path: String,
+    query: String,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    context_lines: Option<usize>,
+    max_results: Option<usize>,
+) -> Result<crate::content_search::SearchSummary> {
+    validate_id(&search_id, "search_id")?;
+    let options = crate::content_search::SearchOptions {
+        query,
+        is_regex: is_regex.unwrap_or(false),
+        case_sensitive: case_sensitive.unwrap_or(false),
+        context_lines: context_lines
+            .unwrap_or(DEFAULT_SEARCH_CONTEXT_LINES)
+            .min(MAX_SEARCH_CONTEXT_LINES),
+        max_results: max_results
+            .unwrap_or(DEFAULT_SEARCH_MAX_RESULTS)
+            .clamp(1, MAX_SEARCH_MAX_RESULTS),
+    };
+    let matcher = crate::content_search::build_matcher(&options)?;
+
+    let cancel = state.content_searches.start(&search_id)?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let database = state.database.clone();
+    let walk = crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        let rules = project_ignore_rules(&database, &canonical_path)?;
+        Ok(crate::content_search::search(
+            &canonical_path,
+            &rules,
+            &matcher,
+            &options,
+            &cancel,
+            |file| {
+                let _ = tx.send(file);
+            },
+        ))
+    });
+    tokio::pin!(walk);
+
+    // Forward matches while the walk runs, then drain whatever is left
+    let result = loop {
+        tokio::select! {
+            Some(file) = rx.recv() => emit_search_matches(&state, &search_id, file).await,
+            result = &mut walk => break result,
+        }
+    };
+    while let Ok(file) = rx.try_recv() {
+        emit_search_matches(&state, &search_id, file).await;
+    }
+    state.content_searches.finish(&search_id);
+
+    let summary = result?;
+    state
+        .events
+        .emit(
+            "project-search:done",
+            serde_json::json!({
+                "searchId": search_id,
+                "totalMatches": summary.total_matches,
+                "filesWithMatches": summary.files_with_matches,
+                "filesSearched": summary.files_searched,
+                "truncated": summary.truncated,
+                "cancelled": summary.cancelled,
+            }),
+        )
+        .await;
+    Ok(summary)
+}
+
+async fn emit_search_matches(
+    state: &AppState,
+    search_id: &str,
+    file: crate::content_search::FileMatches,
+) {
+    state
+        .events
+        .emit(
+            "project-search:match",
+            serde_json::json!({
+                "searchId": search_id,
+                "path": file.path,
+                "matches": file.matches,
+            }),
+        )
+        .await;
+}
+
+/// Cancel a running content search; returns whether it was still running
+#[tauri::command]
+pub async fn cancel_project_content_search(
+    state: State<'_, AppState>,
+    search_id: String,
+) -> Result<bool> {
+    validate_id(&search_id, "search_id")?;
+    Ok(state.content_searches.cancel(&search_id))
+}
+
+/// Explain whether a path is listed and which ignore rule decides it
+///
+/// `rules` previews unsaved rules; without it the project's saved rules
+/// are used.
+#[tauri::command]
+pub async fn preview_ignore_effect(
+    state: State<'_, AppState>,
+    path: String,
+    rules: Option<Vec<String>>,
+) -> Result<IgnoreExplanation> {
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        let (project_root, saved_rules) = owning_project(&database, &canonical_path)?
+            .ok_or_else(|| {
+                crate::Error::InvalidPath(format!("Path is not inside a project: {path}"))
+            })?;
+        let patterns = rules.or(saved_rules).unwrap_or_default();
+        let rules = IgnoreRules::new(&project_root, &patterns)?;
+        Ok(crate::file_index::explain(&project_root, &rules, &canonical_path))
+    })
+    .await
+}
+
+/// The innermost project containing `path`, as (canonical root, saved ignore rules)
+fn owning_project(
+    database: &Database,
+    path: &Path,
+) -> Result<Option<(std::path::PathBuf, Option<Vec<String>>)>> {
+    let mut best: Option<(std::path::PathBuf, Option<Vec<String>>)> = None;
+    for project in database.get_all_projects()? {
+        let Ok(root) = Path::new(&project.path).canonicalize() else {
+            continue;
+        };
+        let deeper = best
+            .as_ref()
+            .is_none_or(|(current, _)| root.components().count() > current.components().count());
+        if path.starts_with(&root) && deeper {
+            best = Some((root, project.settings().ignore_rules));
+        }
+    }
+    Ok(best)
+}
+
+/// Ignore rules of the project containing `path` (defaults outside projects)
+fn project_ignore_rules(database: &Database, path: &Path) -> Result<IgnoreRules> {
+    match owning_project(database, path)? {
+        Some((root, Some(patterns))) => IgnoreRules::new(&root, &patterns),
+        _ => Ok(IgnoreRules::default()),
+    }
+}
+
+/// Walk project files, returning matches and whether the limit cut the walk short
+fn walk_project_files(
+    root: &Path,
+    rules: &IgnoreRules,
+    query: Option<&str>,
+    max_files: usize,
+    max_depth: usize,
+) -> (Vec<FileEntry>, bool) {
+    let mut files: Vec<FileEntry> = Vec::new();
+
+    for entry in crate::file_index::project_walker(root, max_depth, rules).filter_map(|e| e.ok()) {
+        if entry.depth() == 0 {
+            continue;
+        }
+
+        let path = entry.path();
+        let file_name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(n) => n.to_string(),
+            None => continue,
+        };
+
+        // Get relative path
+        let relative_path = match path.strip_prefix(root) {
+            Ok(p) => p.to_string_lossy().to_string(),
+            Err(_) => continue,
+        };
+
+        // Apply query filter (fuzzy match on path and name)
+        let matches_query = match query {
+            Some(q) => {
+                let path_lower = relative_path.to_lowercase();
+                let name_lower = file_name.to_lowercase();
+                path_lower.contains(q) || name_lower.contains(q) || fuzzy_match(&name_lower, q)
+            }
+            None => true,
+        };
+
+        if !matches_query {
+            continue;
+        }
+
+        if files.len() >= max_files {
+            return (files, true);
+        }
+
+        files.push(FileEntry {
+            path: relative_path,
+            name: file_name,
+            is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+        });
+    }
+
+    (files, false)
+}
+
+/// One entry of `get_directory_children`
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryEntry {
+    /// Relative path from project root
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+    /// Size in bytes (files only)
+    pub size: Option<u64>,
+    /// Unix timestamp of the last modification
+    pub modified_at: Option<i64>,
+    /// Git status code ("M", "A", "D", "R", "?"); directories report
+    /// "M" for any change beneath them, or "?" when all of it is untracked
+    pub git_status: Option<String>,
+}
+
+/// Response from `get_directory_children`
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryChildren {
+    pub entries: Vec<DirectoryEntry>,
+    /// True when the directory has more entries than were returned
+    pub truncated: bool,
+}
+
+/// Maximum entries returned for one directory
+const MAX_DIRECTORY_CHILDREN: usize = 5000;
+
+/// List one level of a project directory for lazy tree expansion
+///
+/// `relative_dir` is relative to the project root; empty lists the root.
+/// Applies the same ignore rules as `list_project_files`. Directories come
+/// first, then files, each sorted by name.
+#[tauri::command]
+pub async fn get_directory_children(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_dir: String,
+) -> Result<DirectoryChildren> {
+    validate_id(&project_id, "project_id")?;
+    let relative_dir = match relative_dir.trim_matches('/') {
+        "" | "." => String::new(),
+        dir => validate_relative_project_path(dir)?,
+    };
+
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
+    let settings = project.settings();
+
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+        let dir = if relative_dir.is_empty() {
+            project_root.clone()
+        } else {
+            crate::utils::resolve_project_path(
+                &project_root,
+                &relative_dir,
+                settings.symlink_policy.unwrap_or_default(),
+            )?
+        };
+        if !dir.is_dir() {
+            return Err(crate::Error::InvalidPath(format!(
+                "Not a directory: {relative_dir}"
+            )));
+        }
+        let rules = IgnoreRules::new(&project_root, &settings.ignore_rules.unwrap_or_default())?;
+        let statuses = directory_git_statuses(&project_root, &relative_dir);
+
+        let mut entries = Vec::new();
+        let mut truncated = false;
+        for entry in crate::file_index::project_walker(&dir, 1, &rules).filter_map(|e| e.ok()) {
+            if entry.depth() == 0 {
+                continue;
+            }
+            if entries.len() >= MAX_DIRECTORY_CHILDREN {
+                truncated = true;
+                break;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = if relative_dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{relative_dir}/{name}")
+            };
+            // Follow symlinks so linked directories can be expanded
+            let metadata = std::fs::metadata(entry.path()).ok();
+            let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+            entries.push(DirectoryEntry {
+                git_status: child_git_status(&statuses, &path, is_dir),
+                size: metadata.as_ref().filter(|m| m.is_file()).map(|m| m.len()),
+                modified_at: metadata
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_secs() as i64),
+                path,
+                name,
+                is_dir,
+            });
+        }
+
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        Ok(DirectoryChildren { entries, truncated })
+    })
+    .await
+}
+
+/// Git changes under `relative_dir`, with paths relative to the project root
+///
+/// Empty outside a git repository or when git fails.
+fn directory_git_statuses(project_root: &Path, relative_dir: &str) -> Vec<GitFileStatus> {
+    if !inside_git_repo(project_root).unwrap_or(false) {
+        return Vec::new();
+    }
+    // Porcelain paths are relative to the repository root, which may be above the project
+    let Ok(prefix) = crate::git::run(project_root, &["rev-parse", "--show-prefix"]) else {
+        return Vec::new();
+    };
+    let prefix = prefix.trim();
+    let pathspec = if relative_dir.is_empty() { "." } else { relative_dir };
+    let args = ["status", "--porcelain=v1", "--", pathspec];
+    let Ok(output) = crate::git::run(project_root, &args) else {
+        return Vec::new();
+    };
+
+    parse_git_status(&output)
+        .into_iter()
+        .filter_map(|mut status| {
+            status.path = status.path.strip_prefix(prefix)?.to_string();
+            Some(status)
+        })
+        .collect()
+}
+
+/// Status code shown for one tree entry, preferring unstaged changes
+fn child_git_status(statuses: &[GitFileStatus], path: &str, is_dir: bool) -> Option<String> {
+    if !is_dir {
+        return statuses
+            .iter()
+            .filter(|status| status.path == path)
+            .min_by_key(|status| status.is_staged)
+            .map(|status| status.status.clone());
+    }
+    let dir_prefix = format!("{path}/");
+    let mut beneath = statuses
+        .iter()
+        .filter(|status| status.path.starts_with(&dir_prefix))
+        .peekable();
+    beneath.peek()?;
+    Some(if beneath.all(|status| status.status == "?") { "?" } else { "M" }.to_string())
+}
+
+/// One entry of `get_recently_modified_files`
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+    /// Relative path from project root
+    pub path: String,
+    /// Unix timestamp of the last change: the file's mtime, or when the
+    /// watcher saw it change if that is later
+    pub modified_at: i64,
+    /// Git status code, when the file has uncommitted changes
+    pub git_status: Option<String>,
+}
+
+/// Default and maximum number of files returned by `get_recently_modified_files`
+const DEFAULT_RECENT_FILES: usize = 20;
+const MAX_RECENT_FILES: usize = 200;
+
+/// Files checked for mtimes when the project is not being watched
+const RECENT_SCAN_MAX_FILES: usize = 20_000;
+
+/// Files most recently modified in a project, newest first
+///
+/// Candidates are the paths the watcher reported plus files with
+/// uncommitted git changes. Projects that are not watched fall back to a
+/// walk of the tree (same ignore rules as `list_project_files`), ranked by
+/// mtime. Deleted files and directories are skipped.
+#[tauri::command]
+pub async fn get_recently_modified_files(
+    state: State<'_, AppState>,
+    project_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<RecentFile>> {
+    validate_id(&project_id, "project_id")?;
+    let limit = limit.map_or(DEFAULT_RECENT_FILES, |l| l as usize).clamp(1, MAX_RECENT_FILES);
+
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
+    let settings = project.settings();
+    let watched = state.watchers.recent_changes(&project_id);
+
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+        let statuses = directory_git_statuses(&project_root, "");
+
+        // Candidate path -> when the watcher last saw it change
+        let mut candidates: HashMap<String, Option<i64>> = HashMap::new();
+        match watched {
+            Some(changes) => {
+                candidates.extend(changes.into_iter().map(|(path, at)| (path, Some(at))));
+            }
+            None => {
+                let rules =
+                    IgnoreRules::new(&project_root, &settings.ignore_rules.unwrap_or_default())?;
+                let (files, _) = walk_project_files(
+                    &project_root,
+                    &rules,
+                    None,
+                    RECENT_SCAN_MAX_FILES,
+                    MAX_LIST_DEPTH,
+                );
+                candidates.extend(
+                    files
+                        .into_iter()
+                        .filter(|file| !file.is_dir)
+                        .map(|file| (file.path.replace('\\', "/"), None)),
+                );
+            }
+        }
+        for status in &statuses {
+            candidates.entry(status.path.clone()).or_default();
+        }
+
+        let mut recent: Vec<RecentFile> = candidates
+            .into_iter()
+            .filter_map(|(path, seen_at)| {
+                let metadata = std::fs::metadata(project_root.join(&path)).ok()?;
+                if !metadata.is_file() {
+                    return None;
+                }
+                let modified_at = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|elapsed| elapsed.as_secs() as i64);
+                let modified_at = modified_at.max(seen_at)?;
+                Some(RecentFile {
+                    git_status: child_git_status(&statuses, &path, false),
+                    path,
+                    modified_at,
+                })
+            })
+            .collect();
+
+        recent.sort_by(|a, b| b.modified_at.cmp(&a.modified_at).then_with(|| a.path.cmp(&b.path)));
+        recent.truncate(limit);
+        Ok(recent)
+    })
+    .await
+}
+
+/// Maximum size returned by `read_project_file` / `read_project_file_content`
+const MAX_READ_SIZE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Resolve and read a project file, enforcing containment and the size cap
+pub(crate) async fn read_project_file_bytes(
+    state: &AppState,
+    project_id: &str,
+    relative_path: &str,
+) -> Result<(std::path::PathBuf, Vec<u8>)> {
+    validate_id(project_id, "project_id")?;
+    let normalized_path = validate_relative_project_path(relative_path)?;
+
+    let project = state
+        .database
+        .get_project(project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.to_string()))?;
+    let project_path = project.path.clone();
+    let symlink_policy = project.settings().symlink_policy.unwrap_or_default();
+    let relative_path_for_error = relative_path.to_string();
+
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let canonical_file =
+            crate::utils::resolve_project_path(&project_root, &normalized_path, symlink_policy)?;
+
+        let metadata = std::fs::metadata(&canonical_file).map_err(|_| {
+            crate::Error::InvalidPath(format!("File does not exist: {relative_path_for_error}"))
+        })?;
+        if metadata.is_dir() {
+            return Err(crate::Error::InvalidPath(
+                "Path is a directory".to_string(),
+            ));
+        }
+
+        if metadata.len() > MAX_READ_SIZE_BYTES {
+            let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
+            return Err(crate::Error::Other(format!(
+                "File too large: {size_mb:.1}MB (max 5MB)"
+            )));
+        }
+
+        let bytes = std::fs::read(&canonical_file)?;
+        Ok((canonical_file, bytes))
+    })
+    .await
+}
+
+/// Read a file inside a project directory (restricted to project root)
+#[tauri::command]
+pub async fn read_project_file(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<Vec<u8>> {
+    let (_, bytes) = read_project_file_bytes(&state, &project_id, &relative_path).await?;
+    Ok(bytes)
+}
+
+/// Read a project file for display: mime type, binary/text classification,
+/// decoded text with its encoding, line count and a syntax language hint
+#[tauri::command]
+pub async fn read_project_file_content(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<crate::file_content::FileContent> {
+    let (path, bytes) = read_project_file_bytes(&state, &project_id, &relative_path).await?;
+    crate::utils::spawn_blocking_io(move || Ok(crate::file_content::analyze(&path, &bytes))).await
+}
+
+/// Maximum size accepted by `write_project_file` / `create_project_file`
+const MAX_WRITE_SIZE_BYTES: usize = 10 * 1024 * 1024;
+
+/// Result of saving a project file
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileWriteResult {
+    /// SHA-256 of the written contents; pass back as `expected_hash` on the next save
+    pub hash: String,
+    /// Size of the written file in bytes
+    pub size: u64,
+}
+
+/// Resolve a project-relative path for writing, applying the project's symlink policy
+fn resolve_project_write_target(
+    state: &AppState,
+    project_id: &str,
+    relative_path: &str,
+    contents: &str,
+) -> Result<(String, String, crate::database::SymlinkPolicy)> {
+    validate_id(project_id, "project_id")?;
+    let normalized_path = validate_relative_project_path(relative_path)?;
+
+    if contents.len() > MAX_WRITE_SIZE_BYTES {
+        return Err(crate::Error::Other(format!(
+            "File too large: {} bytes (max {MAX_WRITE_SIZE_BYTES})",
+            contents.len()
+        )));
+    }
+
+    let project = state
+        .database
+        .get_project(project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.to_string()))?;
+    let symlink_policy = project.settings().symlink_policy.unwrap_or_default();
+
+    Ok((project.path, normalized_path, symlink_policy))
+}
+
+/// Save an existing file inside a project directory (restricted to project root)
+///
+/// When `expected_hash` is given, the write is refused with a conflict error if
+/// the file on disk no longer matches it, so edits made concurrently (e.g. by
+/// Codex) are not silently overwritten.
+#[tauri::command]
+pub async fn write_project_file(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+    contents: String,
+    expected_hash: Option<String>,
+) -> Result<FileWriteResult> {
+    let (project_path, normalized_path, symlink_policy) =
+        resolve_project_write_target(&state, &project_id, &relative_path, &contents)?;
+
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let target =
+            crate::utils::resolve_project_path(&project_root, &normalized_path, symlink_policy)?;
+
+        let metadata = std::fs::metadata(&target).map_err(|_| {
+            crate::Error::InvalidPath(format!("File does not exist: {normalized_path}"))
+        })?;
+        if metadata.is_dir() {
+            return Err(crate::Error::InvalidPath("Path is a directory".to_string()));
+        }
+
+        if let Some(expected) = expected_hash {
+            let current = crate::utils::sha256_hex(&std::fs::read(&target)?);
+            if !current.eq_ignore_ascii_case(expected.trim()) {
+                return Err(crate::Error::FileConflict(normalized_path));
+            }
+        }
+
+        crate::utils::atomic_write(&target, contents.as_bytes())?;
+        tracing::info!("Wrote project file: {}", normalized_path);
+
+        Ok(FileWriteResult {
+            hash: crate::utils::sha256_hex(contents.as_bytes()),
+            size: contents.len() as u64,
+        })
+    })
+    .await
+}
+
+/// Create a new file inside a project directory, creating parent directories
+///
+/// Fails if the file already exists.
+#[tauri::command]
+pub async fn create_project_file(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+    contents: Option<String>,
+) -> Result<FileWriteResult> {
+    let contents = contents.unwrap_or_default();
+    let (project_path, normalized_path, symlink_policy) =
+        resolve_project_write_target(&state, &project_id, &relative_path, &contents)?;
+
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let target =
+            crate::utils::resolve_project_path(&project_root, &normalized_path, symlink_policy)?;
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // create_new checks and creates in one step, so a file (or symlink)
+        // appearing in between is never overwritten
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => crate::Error::InvalidPath(format!(
+                    "File already exists: {normalized_path}"
+                )),
+                _ => e.into(),
+            })?;
+        if let Err(e) = file.write_all(contents.as_bytes()).and_then(|()| file.sync_all()) {
+            drop(file);
+            let _ = std::fs::remove_file(&target);
+            return Err(e.into());
+        }
+        tracing::info!("Created project file: {}", normalized_path);
+
+        Ok(FileWriteResult {
+            hash: crate::utils::sha256_hex(contents.as_bytes()),
+            size: contents.len() as u64,
+        })
+    })
+    .await
+}
+
+/// Result of a file-explorer path operation
 #[derive(Debug, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct FileEntry {
-    /// Relative path from project root
+pub struct ProjectPathResult {
+    /// New path relative to the project root
     pub path: String,
-    /// File name only
-    pub name: String,
-    /// Whether it's a directory
-    pub is_dir: bool,
+    /// Whether the change went through `git mv`
+    pub used_git: bool,
 }
 
-/// List project files for @ mention autocomplete
-#[tauri::command]
-pub async fn list_project_files(
-    path: String,
-    query: Option<String>,
-    limit: Option<usize>,
-) -> Result<Vec<FileEntry>> {
-    crate::utils::spawn_blocking_io(move || {
-        // Security: Canonicalize path to prevent traversal attacks
-        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+/// Look up a project's path and symlink policy
+fn project_root_and_policy(
+    state: &AppState,
+    project_id: &str,
+) -> Result<(String, crate::database::SymlinkPolicy)> {
+    validate_id(project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.to_string()))?;
+    let symlink_policy = project.settings().symlink_policy.unwrap_or_default();
+    Ok((project.path, symlink_policy))
+}
 
-        // Directories to ignore
-        let ignore_dirs: HashSet<&str> = [
-            "node_modules",
-            ".git",
-            ".svn",
-            ".hg",
-            "target",
-            "dist",
-            "build",
-            ".next",
-            ".nuxt",
-            "__pycache__",
-            ".pytest_cache",
-            ".mypy_cache",
-            "venv",
-            ".venv",
-            "env",
-            ".env",
-            "vendor",
-            ".idea",
-            ".vscode",
-            "coverage",
-            ".cache",
-            ".parcel-cache",
-            ".turbo",
-        ]
-        .into_iter()
-        .collect();
+/// Validate a single file or directory name (no separators)
+fn validate_file_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(crate::Error::InvalidPath(format!("Invalid name: '{name}'")));
+    }
+    if name.contains(['/', '\\', '\0']) {
+        return Err(crate::Error::InvalidPath(format!(
+            "Name cannot contain path separators: {name}"
+        )));
+    }
+    Ok(())
+}
 
-        let query_lower = query.as_ref().map(|q| q.to_lowercase());
-        let max_files = limit.unwrap_or(100);
-        let mut files: Vec<FileEntry> = Vec::new();
+/// Split a normalized relative path into its parent ("" for the root) and name
+fn split_relative_path(path: &str) -> (&str, &str) {
+    match path.rsplit_once('/') {
+        Some((parent, name)) => (parent, name),
+        None => ("", path),
+    }
+}
 
-        // Collect files recursively
-        collect_files_recursive(
-            &canonical_path,
-            &canonical_path,
-            &ignore_dirs,
-            &query_lower,
-            &mut files,
-            max_files,
-            0,
-            5, // max depth
-        );
+/// Resolve an entry without following a symlink in its final component,
+/// so renaming or moving a link affects the link itself
+fn resolve_project_entry(
+    project_root: &Path,
+    relative_path: &str,
+    symlink_policy: crate::database::SymlinkPolicy,
+) -> Result<std::path::PathBuf> {
+    let (parent, name) = split_relative_path(relative_path);
+    let parent = if parent.is_empty() {
+        project_root.to_path_buf()
+    } else {
+        crate::utils::resolve_project_path(project_root, parent, symlink_policy)?
+    };
+    Ok(parent.join(name))
+}
 
-        // Sort: directories first, then by path
-        files.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.path.cmp(&b.path),
-        });
+/// Whether git tracks `relative_path` (or anything under it)
+fn is_git_tracked(project_root: &Path, relative_path: &str) -> bool {
+    inside_git_repo(project_root).unwrap_or(false)
+        && crate::git::run(project_root, &["ls-files", "-z", "--", relative_path])
+            .is_ok_and(|output| !output.is_empty())
+}
 
-        Ok(files)
-    })
-    .await
+/// Move `from` to `to` inside the project, using `git mv` for tracked paths
+///
+/// Both paths are normalized and relative to `project_root`. Fails if the
+/// source is missing, the destination exists or its directory does not, or
+/// a directory would move into itself.
+fn move_project_entry(
+    project_root: &Path,
+    from: &str,
+    to: &str,
+    symlink_policy: crate::database::SymlinkPolicy,
+) -> Result<bool> {
+    let source = resolve_project_entry(project_root, from, symlink_policy)?;
+    let target = resolve_project_entry(project_root, to, symlink_policy)?;
+
+    if std::fs::symlink_metadata(&source).is_err() {
+        return Err(crate::Error::InvalidPath(format!("Path does not exist: {from}")));
+    }
+    if std::fs::symlink_metadata(&target).is_ok() {
+        return Err(crate::Error::InvalidPath(format!("Destination already exists: {to}")));
+    }
+    if !target.parent().is_some_and(Path::is_dir) {
+        return Err(crate::Error::InvalidPath(format!(
+            "Destination directory does not exist: {to}"
+        )));
+    }
+    if target.starts_with(&source) {
+        return Err(crate::Error::InvalidPath(format!(
+            "Cannot move {from} into itself"
+        )));
+    }
+
+    // git needs paths relative to the work tree; links followed outside the
+    // project fall back to a plain rename
+    let git_paths = source
+        .strip_prefix(project_root)
+        .ok()
+        .zip(target.strip_prefix(project_root).ok());
+    if let Some((git_from, git_to)) = git_paths {
+        let (git_from, git_to) = (git_from.to_string_lossy(), git_to.to_string_lossy());
+        if is_git_tracked(project_root, &git_from) {
+            crate::git::run(project_root, &["mv", "--", &git_from, &git_to])?;
+            return Ok(true);
+        }
+    }
+
+    std::fs::rename(&source, &target)?;
+    Ok(false)
 }
 
-/// Read a file inside a project directory (restricted to project root)
+/// Rename a file or directory in place
+///
+/// `new_name` is a bare name, not a path. Tracked files are renamed with
+/// `git mv` so history follows them.
 #[tauri::command]
-pub async fn read_project_file(
+pub async fn rename_project_path(
     state: State<'_, AppState>,
     project_id: String,
     relative_path: String,
-) -> Result<Vec<u8>> {
-    validate_id(&project_id, "project_id")?;
-    let normalized_path = validate_relative_project_path(&relative_path)?;
-
-    let project = state
-        .database
-        .get_project(&project_id)?
-        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
-    let project_path = project.path.clone();
-    let relative_path_for_error = relative_path.clone();
+    new_name: String,
+) -> Result<ProjectPathResult> {
+    let normalized_path = validate_relative_project_path(relative_path.trim_matches('/'))?;
+    validate_file_name(&new_name)?;
+    let (project_path, symlink_policy) = project_root_and_policy(&state, &project_id)?;
 
     crate::utils::spawn_blocking_io(move || {
         let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
-        let resolved_path = project_root.join(normalized_path);
-        let canonical_file = resolved_path
-            .canonicalize()
-            .map_err(|_| crate::Error::InvalidPath(format!(
-                "File does not exist: {relative_path_for_error}"
-            )))?;
-
-        if !canonical_file.starts_with(&project_root) {
-            return Err(crate::Error::InvalidPath(
-                "File is outside project directory".to_string(),
-            ));
-        }
-
-        let metadata = std::fs::metadata(&canonical_file)?;
-        if metadata.is_dir() {
-            return Err(crate::Error::InvalidPath(
-                "Path is a directory".to_string(),
-            ));
-        }
+        let (parent, _) = split_relative_path(&normalized_path);
+        let renamed = if parent.is_empty() {
+            new_name
+        } else {
+            format!("{parent}/{new_name}")
+        };
+        let used_git = move_project_entry(&project_root, &normalized_path, &renamed, symlink_policy)?;
+        tracing::info!("Renamed project path: {} -> {}", normalized_path, renamed);
+        Ok(ProjectPathResult {
+            path: renamed,
+            used_git,
+        })
+    })
+    .await
+}
 
-        const MAX_IMAGE_SIZE_BYTES: u64 = 5 * 1024 * 1024;
-        if metadata.len() > MAX_IMAGE_SIZE_BYTES {
-            let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
-            return Err(crate::Error::Other(format!(
-                "File too large: {size_mb:.1}MB (max 5MB)"
-            )));
-        }
+/// Move a file or directory into another project directory
+///
+/// `destination_dir` is relative to the project root; empty moves to the
+/// root. The entry keeps its name.
+#[tauri::command]
+pub async fn move_project_path(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+    destination_dir: String,
+) -> Result<ProjectPathResult> {
+    let normalized_path = validate_relative_project_path(relative_path.trim_matches('/'))?;
+    let destination_dir = match destination_dir.trim_matches('/') {
+        "" | "." => String::new(),
+        dir => validate_relative_project_path(dir)?,
+    };
+    let (project_path, symlink_policy) = project_root_and_policy(&state, &project_id)?;
 
-        Ok(std::fs::read(&canonical_file)?)
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let (_, name) = split_relative_path(&normalized_path);
+        let moved = if destination_dir.is_empty() {
+            name.to_string()
+        } else {
+            format!("{destination_dir}/{name}")
+        };
+        let used_git = move_project_entry(&project_root, &normalized_path, &moved, symlink_policy)?;
+        tracing::info!("Moved project path: {} -> {}", normalized_path, moved);
+        Ok(ProjectPathResult {
+            path: moved,
+            used_git,
+        })
     })
     .await
 }
 
-#[allow(clippy::too_many_arguments)]
-fn collect_files_recursive(
-    root: &Path,
-    current: &Path,
-    ignore_dirs: &HashSet<&str>,
-    query: &Option<String>,
-    files: &mut Vec<FileEntry>,
-    max_files: usize,
-    depth: usize,
-    max_depth: usize,
-) {
-    if files.len() >= max_files || depth > max_depth {
-        return;
-    }
+/// Maximum attempts at finding a free "copy N" name
+const MAX_DUPLICATE_SUFFIX: u32 = 1000;
 
-    let entries = match std::fs::read_dir(current) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
+/// Name of the `n`th copy: "a copy.txt", "a copy 2.txt", ...
+fn duplicate_name(name: &str, n: u32) -> String {
+    let suffix = if n == 1 { " copy".to_string() } else { format!(" copy {n}") };
+    let path = Path::new(name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => {
+            format!("{}{suffix}.{}", stem.to_string_lossy(), ext.to_string_lossy())
+        }
+        _ => format!("{name}{suffix}"),
+    }
+}
 
-    for entry in entries.filter_map(|e| e.ok()) {
-        if files.len() >= max_files {
-            break;
+/// Recursively copy `source` to `target`, recreating symlinks rather than
+/// following them
+fn copy_entry(source: &Path, target: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(source)?;
+    if metadata.file_type().is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(std::fs::read_link(source)?, target)?;
+        #[cfg(not(unix))]
+        tracing::warn!("Skipping symlink while duplicating: {}", source.display());
+    } else if metadata.is_dir() {
+        std::fs::create_dir(target)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_entry(&entry.path(), &target.join(entry.file_name()))?;
         }
+    } else {
+        std::fs::copy(source, target)?;
+    }
+    Ok(())
+}
 
-        let path = entry.path();
-        let file_name = match path.file_name().and_then(|n| n.to_str()) {
-            Some(n) => n.to_string(),
-            None => continue,
-        };
+/// Copy a file or directory next to itself as "name copy", "name copy 2", ...
+///
+/// Copies are left untracked.
+#[tauri::command]
+pub async fn duplicate_project_path(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<ProjectPathResult> {
+    let normalized_path = validate_relative_project_path(relative_path.trim_matches('/'))?;
+    let (project_path, symlink_policy) = project_root_and_policy(&state, &project_id)?;
 
-        // Skip hidden files (except specific ones)
-        if file_name.starts_with('.') && !matches!(file_name.as_str(), ".env" | ".gitignore" | ".eslintrc" | ".prettierrc") {
-            continue;
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let source = resolve_project_entry(&project_root, &normalized_path, symlink_policy)?;
+        if std::fs::symlink_metadata(&source).is_err() {
+            return Err(crate::Error::InvalidPath(format!(
+                "Path does not exist: {normalized_path}"
+            )));
         }
 
-        let is_dir = path.is_dir();
+        let (parent, name) = split_relative_path(&normalized_path);
+        let (copy_name, target) = (1..=MAX_DUPLICATE_SUFFIX)
+            .map(|n| duplicate_name(name, n))
+            .map(|copy_name| {
+                let target = source.with_file_name(&copy_name);
+                (copy_name, target)
+            })
+            .find(|(_, target)| std::fs::symlink_metadata(target).is_err())
+            .ok_or_else(|| {
+                crate::Error::Other(format!("Too many copies of {normalized_path}"))
+            })?;
 
-        // Skip ignored directories
-        if is_dir && ignore_dirs.contains(file_name.as_str()) {
-            continue;
+        if let Err(err) = copy_entry(&source, &target) {
+            // Do not leave a half-copied directory behind
+            let _ = std::fs::remove_dir_all(&target).or_else(|_| std::fs::remove_file(&target));
+            return Err(err);
         }
 
-        // Get relative path
-        let relative_path = match path.strip_prefix(root) {
-            Ok(p) => p.to_string_lossy().to_string(),
-            Err(_) => continue,
+        let path = if parent.is_empty() {
+            copy_name
+        } else {
+            format!("{parent}/{copy_name}")
         };
+        tracing::info!("Duplicated project path: {} -> {}", normalized_path, path);
+        Ok(ProjectPathResult {
+            path,
+            used_git: false,
+        })
+    })
+    .await
+}
 
-        // Apply query filter (fuzzy match on path and name)
-        let matches_query = match query {
-            Some(q) => {
-                let path_lower = relative_path.to_lowercase();
-                let name_lower = file_name.to_lowercase();
-                path_lower.contains(q) || name_lower.contains(q) || fuzzy_match(&name_lower, q)
-            }
-            None => true,
-        };
+/// Create a directory (and any missing parents) inside a project
+///
+/// Fails if the path already exists.
+#[tauri::command]
+pub async fn create_directory(
+    state: State<'_, AppState>,
+    project_id: String,
+    relative_path: String,
+) -> Result<ProjectPathResult> {
+    let normalized_path = validate_relative_project_path(relative_path.trim_matches('/'))?;
+    let (project_path, symlink_policy) = project_root_and_policy(&state, &project_id)?;
 
-        if matches_query {
-            files.push(FileEntry {
-                path: relative_path,
-                name: file_name,
-                is_dir,
-            });
+    crate::utils::spawn_blocking_io(move || {
+        let project_root = crate::utils::validate_and_canonicalize_path(&project_path)?;
+        let target =
+            crate::utils::resolve_project_path(&project_root, &normalized_path, symlink_policy)?;
+        if std::fs::symlink_metadata(&target).is_ok() {
+            return Err(crate::Error::InvalidPath(format!(
+                "Path already exists: {normalized_path}"
+            )));
         }
 
-        // Recurse into directories
-        if is_dir {
-            collect_files_recursive(
-                root,
-                &path,
-                ignore_dirs,
-                query,
-                files,
-                max_files,
-                depth + 1,
-                max_depth,
-            );
-        }
-    }
+        std::fs::create_dir_all(&target)?;
+        tracing::info!("Created project directory: {}", normalized_path);
+        Ok(ProjectPathResult {
+            path: normalized_path,
+            used_git: false,
+        })
+    })
+    .await
 }
 
 /// Simple fuzzy match: check if all characters in query appear in order in target
@@ -809,7 +2042,7 @@ pub async fn git_status(path: String) -> Result<Vec<GitFileStatus>> {
             return Ok(Vec::new());
         }
 
-        let output = run_git_capture_stdout(&canonical_path, &["status", "--porcelain=v1"])?;
+        let output = crate::git::run(&canonical_path, &["status", "--porcelain=v1"])?;
         Ok(parse_git_status(&output))
     })
     .await
@@ -822,7 +2055,7 @@ pub async fn git_stage_files(path: String, files: Vec<String>) -> Result<()> {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
         }
 
         if files.is_empty() {
@@ -863,7 +2096,7 @@ pub async fn git_unstage_files(path: String, files: Vec<String>) -> Result<()> {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
         }
 
         if files.is_empty() {
@@ -921,78 +2154,253 @@ fn validate_commit_message(message: &str) -> Result<()> {
 }
 
 /// Execute git commit
+///
+/// `sign` forces signing on (`-S`) or off; without it the repository's
+/// `commit.gpgSign` decides. A commit that fails because it could not be
+/// signed returns `Error::Signing` saying why.
+///
+/// Hook output is emitted as `git-hook-output` while the commit runs.
+/// `no_verify` skips the hooks and is recorded as a hook bypass.
 #[tauri::command]
-pub async fn git_commit(path: String, message: String) -> Result<String> {
-    crate::utils::spawn_blocking_io(move || {
+pub async fn git_commit(
+    state: State<'_, AppState>,
+    path: String,
+    message: String,
+    sign: Option<bool>,
+    no_verify: Option<bool>,
+) -> Result<String> {
+    validate_commit_message(&message)?;
+    let no_verify = no_verify.unwrap_or(false);
+    let (canonical_path, hooks) = crate::utils::spawn_blocking_io(move || {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
         }
 
-        validate_commit_message(&message)?;
+        let hooks = git_hooks::list(&canonical_path)?;
+        Ok((canonical_path, hooks))
+    })
+    .await?;
 
-        let output = std::process::Command::new("git")
-            .arg("commit")
-            .arg("-m")
-            .arg(&message)
-            .current_dir(&canonical_path)
-            .output()
-            .map_err(|err| crate::Error::Other(format!("Failed to run git commit: {err}")))?;
+    let mut args = vec!["commit"];
+    match sign {
+        Some(true) => args.push("-S"),
+        Some(false) => args.push("--no-gpg-sign"),
+        None => {}
+    }
+    if no_verify {
+        args.push("--no-verify");
+        let subject = message.lines().next().unwrap_or_default();
+        record_hook_bypass(&state, &canonical_path, HookOperation::Commit, subject, &hooks).await?;
+    }
+    args.extend(["-m", &message]);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(crate::Error::Other(format!("git commit failed: {stderr}")));
+    let run = git_hooks::run_git(&canonical_path, &args, HookOperation::Commit, &state.events)
+        .await?;
+    if !run.status.success() {
+        if let Some(failure) = crate::commit_signing::classify_failure(&run.stderr) {
+            return Err(crate::Error::Signing(failure));
         }
+        return Err(crate::Error::Other(format!("git commit failed: {}", run.stderr)));
+    }
 
-        // Return the commit SHA
-        let sha_output = std::process::Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(&canonical_path)
-            .output()
+    // Return the commit SHA
+    crate::utils::spawn_blocking_io(move || {
+        let sha = crate::git::value(&canonical_path, &["rev-parse", "HEAD"])
             .map_err(|err| crate::Error::Other(format!("Failed to get commit SHA: {err}")))?;
-
-        let sha = String::from_utf8_lossy(&sha_output.stdout).trim().to_string();
         tracing::info!("Git commit created: {}", sha);
         Ok(sha)
     })
     .await
 }
 
-/// Execute git push
+/// Store a `--no-verify` commit or push in the hook bypass audit trail
+async fn record_hook_bypass(
+    state: &AppState,
+    repo_path: &Path,
+    operation: HookOperation,
+    detail: &str,
+    hooks: &GitHooks,
+) -> Result<()> {
+    let bypass = HookBypass {
+        id: uuid::Uuid::new_v4().to_string(),
+        repo_path: repo_path.to_string_lossy().into_owned(),
+        operation: operation.as_str().to_string(),
+        detail: detail.to_string(),
+        hooks: hooks.active_for(operation),
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    tracing::warn!(
+        "Skipping git hooks for {} in {} ({}): {:?}",
+        bypass.operation,
+        bypass.repo_path,
+        bypass.detail,
+        bypass.hooks
+    );
+    let db = state.database.clone();
+    crate::utils::spawn_blocking_io(move || db.insert_hook_bypass(&bypass)).await
+}
+
+/// Hooks installed in a repository, and which of them run on commit or push
+#[tauri::command]
+pub async fn list_git_hooks(path: String) -> Result<GitHooks> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+        git_hooks::list(&canonical_path)
+    })
+    .await
+}
+
+/// Commits and pushes made in a repository without running its hooks,
+/// newest first
 #[tauri::command]
-pub async fn git_push(path: String, remote: String, branch: String) -> Result<()> {
+pub async fn list_hook_bypasses(
+    state: State<'_, AppState>,
+    path: String,
+    limit: Option<u32>,
+) -> Result<Vec<HookBypass>> {
+    let db = state.database.clone();
     crate::utils::spawn_blocking_io(move || {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        db.list_hook_bypasses(&canonical_path.to_string_lossy(), limit.unwrap_or(50).min(500))
+    })
+    .await
+}
 
+/// How the repository signs commits and whether the key is there, so the
+/// commit dialog can warn before a commit that would fail
+#[tauri::command]
+pub async fn get_signing_config(path: String) -> Result<SigningConfig> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
         }
+        Ok(crate::commit_signing::config(&canonical_path))
+    })
+    .await
+}
+
+/// How long the commit message turn may take before the fallback is used
+const COMMIT_MESSAGE_TIMEOUT_SECS: u64 = 90;
 
-        // Validate remote name (simple identifier)
-        if remote.is_empty() || !remote.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
-            return Err(crate::Error::Other("Invalid remote name".to_string()));
+/// Suggest a conventional-commit message for the staged changes
+///
+/// Asks a throwaway read-only turn at low reasoning effort unless
+/// `use_model` is false; when that fails or answers with something else, the
+/// message is built from the staged file stats instead.
+#[tauri::command]
+pub async fn suggest_commit_message(
+    state: State<'_, AppState>,
+    path: String,
+    use_model: Option<bool>,
+    model: Option<String>,
+) -> Result<CommitMessageSuggestion> {
+    let (canonical_path, diff, files) = crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+        let numstat = crate::git::run(
+            &canonical_path,
+            &["diff", "--cached", "--no-renames", "--numstat"],
+        )?;
+        let name_status = crate::git::run(
+            &canonical_path,
+            &["diff", "--cached", "--no-renames", "--name-status"],
+        )?;
+        let files = commit_message::parse_staged(&numstat, &name_status);
+        if files.is_empty() {
+            return Err(crate::Error::Other("No staged changes".to_string()));
+        }
+        let diff = run_git_capture_diff(&canonical_path, &["diff", "--cached", "--no-renames"])?;
+        Ok((canonical_path, diff, files))
+    })
+    .await?;
+
+    if use_model == Some(false) {
+        return Ok(commit_message::fallback(files, None));
+    }
+    let answer = crate::commands::thread::ask_read_only(
+        &state,
+        &canonical_path.to_string_lossy(),
+        commit_message::prompt(&diff, &files),
+        model,
+        Some("low".to_string()),
+        std::time::Duration::from_secs(COMMIT_MESSAGE_TIMEOUT_SECS),
+        "Commit message",
+    )
+    .await;
+    Ok(match answer {
+        Ok(answer) => match commit_message::from_answer(&answer, files.clone()) {
+            Some(suggestion) => suggestion,
+            None => commit_message::fallback(
+                files,
+                Some("The answer was not a conventional commit message".to_string()),
+            ),
+        },
+        Err(err) => {
+            tracing::warn!("Falling back to a stats-based commit message: {}", err);
+            commit_message::fallback(files, Some(err.to_string()))
         }
+    })
+}
 
-        validate_branch_name(&branch)?;
+/// Execute git push
+///
+/// Like `git_commit`, streams hook output and skips the hooks only when
+/// `no_verify` is set.
+#[tauri::command]
+pub async fn git_push(
+    state: State<'_, AppState>,
+    path: String,
+    remote: String,
+    branch: String,
+    no_verify: Option<bool>,
+) -> Result<()> {
+    let no_verify = no_verify.unwrap_or(false);
+    let (canonical_path, hooks) = crate::utils::spawn_blocking_io({
+        let remote = remote.clone();
+        let branch = branch.clone();
+        move || {
+            let canonical_path = crate::utils::validate_and_canonicalize_path(&path)?;
+
+            if !inside_git_repo(&canonical_path)? {
+                return Err(crate::Error::NotARepo);
+            }
 
-        let output = std::process::Command::new("git")
-            .arg("push")
-            .arg(&remote)
-            .arg(&branch)
-            .current_dir(&canonical_path)
-            .output()
-            .map_err(|err| crate::Error::Other(format!("Failed to run git push: {err}")))?;
+            // Validate remote name (simple identifier)
+            if remote.is_empty() || !remote.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+                return Err(crate::Error::Other("Invalid remote name".to_string()));
+            }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(crate::Error::Other(format!("git push failed: {stderr}")));
+            validate_branch_name(&branch)?;
+            let hooks = git_hooks::list(&canonical_path)?;
+            Ok((canonical_path, hooks))
         }
-
-        tracing::info!("Git push completed: {} -> {}/{}", canonical_path.display(), remote, branch);
-        Ok(())
     })
-    .await
+    .await?;
+
+    let mut args = vec!["push"];
+    if no_verify {
+        args.push("--no-verify");
+        let detail = format!("{remote}/{branch}");
+        record_hook_bypass(&state, &canonical_path, HookOperation::Push, &detail, &hooks).await?;
+    }
+    args.extend([remote.as_str(), branch.as_str()]);
+
+    let run = git_hooks::run_git(&canonical_path, &args, HookOperation::Push, &state.events)
+        .await?;
+    if !run.status.success() {
+        return Err(crate::Error::Other(format!("git push failed: {}", run.stderr)));
+    }
+
+    tracing::info!("Git push completed: {} -> {}/{}", canonical_path.display(), remote, branch);
+    Ok(())
 }
 
 /// Get the current remote tracking info
@@ -1007,17 +2415,20 @@ pub async fn git_remote_info(path: String) -> Result<GitRemoteInfo> {
                 branch: None,
                 ahead: 0,
                 behind: 0,
+                head: None,
             });
         }
 
-        // Get current branch
-        let branch = run_git_capture_stdout(&canonical_path, &["rev-parse", "--abbrev-ref", "HEAD"])
-            .ok()
-            .map(|s| s.trim().to_string());
+        // Get current branch (None when HEAD is detached or unborn)
+        let head = resolve_head_state(&canonical_path).ok();
+        let branch = match &head {
+            Some(GitHeadState::Branch { name }) => Some(name.clone()),
+            _ => None,
+        };
 
         // Get remote for current branch
         let remote = if let Some(ref b) = branch {
-            run_git_capture_stdout(
+            crate::git::run(
                 &canonical_path,
                 &["config", &format!("branch.{b}.remote")],
             )
@@ -1031,7 +2442,7 @@ pub async fn git_remote_info(path: String) -> Result<GitRemoteInfo> {
         // Get ahead/behind counts
         let (ahead, behind) = if let (Some(ref r), Some(ref b)) = (&remote, &branch) {
             let upstream = format!("{r}/{b}");
-            let rev_list = run_git_capture_stdout(
+            let rev_list = crate::git::run(
                 &canonical_path,
                 &["rev-list", "--left-right", "--count", &format!("HEAD...{upstream}")],
             );
@@ -1058,6 +2469,7 @@ pub async fn git_remote_info(path: String) -> Result<GitRemoteInfo> {
             branch,
             ahead,
             behind,
+            head,
         })
     })
     .await
@@ -1071,6 +2483,7 @@ pub struct GitRemoteInfo {
     pub branch: Option<String>,
     pub ahead: u32,
     pub behind: u32,
+    pub head: Option<GitHeadState>,
 }
 
 /// Git branch entry
@@ -1201,7 +2614,92 @@ pub async fn get_git_commits(path: String, limit: Option<u32>) -> Result<Vec<Git
             }
         }
 
-        Ok(commits)
+        Ok(commits)
+    })
+    .await
+}
+
+/// Result of `generate_change_report`
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeReportExport {
+    pub content: String,
+    /// Where the report was written, if a path was given
+    pub path: Option<String>,
+    pub sessions: usize,
+    pub commits: usize,
+    pub pull_requests: usize,
+    pub files: usize,
+    /// Values masked by the redaction pass (0 when it was skipped)
+    pub redactions: usize,
+}
+
+/// Report a project's sessions, commits, pull requests and diffs within a
+/// date range as Markdown, standalone HTML or JSON
+///
+/// The report is redacted with the saved redaction settings unless
+/// `redact` is false (or redaction is turned off and `redact` is not
+/// given). With `path` (absolute, in an existing directory) it is also
+/// written there, replacing any existing file.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn generate_change_report(
+    state: State<'_, AppState>,
+    project_id: String,
+    range: ReportRange,
+    format: ExportFormat,
+    include_diff: Option<bool>,
+    redact: Option<bool>,
+    path: Option<String>,
+) -> Result<ChangeReportExport> {
+    if let Some(path) = &path {
+        let target = Path::new(path);
+        let parent = target.parent().filter(|parent| parent.is_dir());
+        if !target.is_absolute() || parent.is_none() || target.is_dir() {
+            return Err(crate::Error::InvalidPath(format!(
+                "Report path must be an absolute file path in an existing directory: {path}"
+            )));
+        }
+    }
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
+    let settings = state.global_state.read(|g| g.redaction.clone());
+    let redactor = if redact.unwrap_or(settings.enabled) {
+        Some(Redactor::new(&settings, Some(&project.path))?)
+    } else {
+        None
+    };
+
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        let now = chrono::Utc::now().timestamp();
+        let report = change_report::generate(
+            &database,
+            &project,
+            range,
+            include_diff.unwrap_or(true),
+            now,
+        )?;
+        let mut content = change_report::render(format, &report)?;
+        let mut redaction = RedactionReport::default();
+        if let Some(redactor) = &redactor {
+            content = redactor.redact(&content, &mut redaction);
+        }
+        if let Some(path) = &path {
+            crate::utils::atomic_write(Path::new(path), content.as_bytes())?;
+            tracing::info!("Wrote change report for {} to {}", project.id, path);
+        }
+        Ok(ChangeReportExport {
+            content,
+            path,
+            sessions: report.sessions.len(),
+            commits: report.commits.len() + report.omitted_commits,
+            pull_requests: report.pull_requests.len(),
+            files: report.files.len(),
+            redactions: redaction.total,
+        })
     })
     .await
 }
@@ -1225,6 +2723,25 @@ pub struct WorktreeInfo {
     pub head_commit: String,
 }
 
+/// A worktree with what the worktree dashboard shows about it
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeStatus {
+    #[serde(flatten)]
+    pub info: WorktreeInfo,
+    /// Uncommitted changes, including untracked files
+    pub dirty: bool,
+    /// Commits on this worktree's HEAD that the main worktree's branch lacks;
+    /// `None` for the main worktree or when there is nothing to compare with
+    pub ahead: Option<u32>,
+    /// Commits on the main worktree's branch that this HEAD lacks
+    pub behind: Option<u32>,
+    /// Session started in this worktree by `worktreePerSession`
+    pub session_id: Option<String>,
+    /// Bytes of files in the worktree, not counting `.git`
+    pub disk_usage: u64,
+}
+
 /// Create a git worktree
 #[tauri::command]
 pub async fn create_worktree(
@@ -1238,9 +2755,7 @@ pub async fn create_worktree(
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other(
-                "Not a git repository".to_string(),
-            ));
+            return Err(crate::Error::NotARepo);
         }
 
         // Determine worktree path: use provided path or default to .worktrees/<branch>
@@ -1320,9 +2835,7 @@ pub async fn remove_worktree(
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other(
-                "Not a git repository".to_string(),
-            ));
+            return Err(crate::Error::NotARepo);
         }
 
         let canonical_wt = crate::utils::validate_and_canonicalize_path(&worktree_path)?;
@@ -1351,9 +2864,105 @@ pub async fn remove_worktree(
     .await
 }
 
-/// List all git worktrees
+/// List all git worktrees, with their state
 #[tauri::command]
-pub async fn list_worktrees(project_path: String) -> Result<Vec<WorktreeInfo>> {
+pub async fn list_worktrees(
+    state: State<'_, AppState>,
+    project_path: String,
+) -> Result<Vec<WorktreeStatus>> {
+    let worktrees = git_worktree_list(project_path.clone()).await?;
+    let sessions = session_worktree_paths(&state, &project_path)?;
+
+    crate::utils::spawn_blocking_io(move || {
+        let main_branch = worktrees
+            .iter()
+            .find(|worktree| worktree.is_main && worktree.branch != "(detached)")
+            .map(|worktree| worktree.branch.clone());
+        Ok(worktrees
+            .into_iter()
+            .map(|info| worktree_status(info, main_branch.as_deref(), &sessions))
+            .collect())
+    })
+    .await
+}
+
+/// Canonical worktree paths of a project's sessions, mapped to session IDs
+fn session_worktree_paths(
+    state: &AppState,
+    project_path: &str,
+) -> Result<HashMap<std::path::PathBuf, String>> {
+    let Ok(canonical_path) = crate::utils::validate_and_canonicalize_path(project_path) else {
+        return Ok(HashMap::new());
+    };
+    let mut sessions = HashMap::new();
+    for project in state.database.get_all_projects()? {
+        if crate::utils::validate_and_canonicalize_path(&project.path).ok()
+            != Some(canonical_path.clone())
+        {
+            continue;
+        }
+        for worktree in state.database.list_session_worktrees(&project.id)? {
+            if let Ok(path) = crate::utils::validate_and_canonicalize_path(&worktree.path) {
+                sessions.insert(path, worktree.session_id);
+            }
+        }
+    }
+    Ok(sessions)
+}
+
+/// Dirty state, divergence from `main_branch` and size of a worktree. Blocking.
+fn worktree_status(
+    info: WorktreeInfo,
+    main_branch: Option<&str>,
+    sessions: &HashMap<std::path::PathBuf, String>,
+) -> WorktreeStatus {
+    let path = Path::new(&info.path);
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let dirty = git(&["status", "--porcelain"]).is_some_and(|status| !status.is_empty());
+    // `main...HEAD` counts commits only on main, then only on HEAD
+    let range = main_branch
+        .filter(|_| !info.is_main)
+        .map(|main| format!("{main}...HEAD"));
+    let counts = range.and_then(|range| git(&["rev-list", "--left-right", "--count", &range]));
+    let (behind, ahead) = counts
+        .as_deref()
+        .and_then(|counts| counts.split_once('\t'))
+        .map(|(behind, ahead)| (behind.parse().ok(), ahead.parse().ok()))
+        .unwrap_or((None, None));
+    let session_id = crate::utils::validate_and_canonicalize_path(&info.path)
+        .ok()
+        .and_then(|path| sessions.get(&path).cloned());
+    let disk_usage = walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git")
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    WorktreeStatus {
+        info,
+        dirty,
+        ahead,
+        behind,
+        session_id,
+        disk_usage,
+    }
+}
+
+/// Worktrees of the repository at `project_path`, as `git worktree list`
+/// reports them
+pub(crate) async fn git_worktree_list(project_path: String) -> Result<Vec<WorktreeInfo>> {
     crate::utils::spawn_blocking_io(move || {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
@@ -1430,71 +3039,107 @@ pub async fn list_worktrees(project_path: String) -> Result<Vec<WorktreeInfo>> {
     .await
 }
 
+/// Result of applying a patch
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchApplyResult {
+    /// Files a three-way apply left conflict markers in
+    pub conflicts: Vec<ConflictedFile>,
+    /// Warnings from the preflight check, e.g. whitespace
+    pub warnings: Vec<PatchIssue>,
+}
+
 /// Apply a patch via stdin to `git apply`
 /// If `cached` is true, applies with `--cached` (stages the changes).
 /// If `reverse` is true, applies with `--reverse` (reverts the changes).
+/// If `three_way` is true, applies with `--3way`: hunks whose context
+/// drifted are merged, and the ones that conflict are written with conflict
+/// markers and returned in `conflicts`.
+///
+/// The patch is checked first; when the check finds errors nothing is
+/// applied and `Error::PatchRejected` carries the preflight report.
 #[tauri::command]
 pub async fn git_apply_patch(
     project_path: String,
     patch: String,
     cached: bool,
     reverse: bool,
-) -> Result<()> {
-    use std::io::Write;
-    use std::process::{Command, Stdio};
-
+    three_way: Option<bool>,
+) -> Result<PatchApplyResult> {
+    let options = ApplyOptions {
+        cached,
+        reverse,
+        three_way: three_way.unwrap_or(false),
+    };
     crate::utils::spawn_blocking_io(move || {
-        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
-
-        if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
-        }
-
-        if patch.trim().is_empty() {
-            return Err(crate::Error::Other("Patch content is empty".to_string()));
-        }
-
-        let mut args = vec!["apply"];
-        if cached {
-            args.push("--cached");
-        }
-        if reverse {
-            args.push("--reverse");
-        }
+        let canonical_path = patch_target(&project_path, &patch)?;
 
-        let mut child = Command::new("git")
-            .args(&args)
-            .current_dir(&canonical_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|err| crate::Error::Other(format!("Failed to spawn git apply: {err}")))?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(patch.as_bytes())
-                .map_err(|err| crate::Error::Other(format!("Failed to write patch to stdin: {err}")))?;
+        let preflight = patch_preflight::check(&canonical_path, &patch, options)?;
+        if !preflight.applies {
+            return Err(crate::Error::PatchRejected(Box::new(preflight)));
         }
 
-        let output = child.wait_with_output()
-            .map_err(|err| crate::Error::Other(format!("Failed to wait for git apply: {err}")))?;
+        let output = patch_preflight::git_apply(&canonical_path, &patch, &options.args())?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let conflicted = conflict_markers::conflicted_paths(&stderr);
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() && conflicted.is_empty() {
             return Err(crate::Error::Other(format!("git apply failed: {stderr}")));
         }
 
         tracing::info!(
-            "Applied patch (cached={}, reverse={}) in {}",
+            "Applied patch (cached={}, reverse={}, three_way={}) in {} with {} conflicted files",
             cached,
             reverse,
-            canonical_path.display()
+            options.three_way,
+            canonical_path.display(),
+            conflicted.len()
         );
-        Ok(())
+        Ok(PatchApplyResult {
+            conflicts: conflict_markers::read(&canonical_path, conflicted),
+            warnings: preflight.issues,
+        })
+    })
+    .await
+}
+
+/// Check a patch the way `git_apply_patch` would apply it, without
+/// applying it: paths outside the project, line ending mismatches against
+/// `core.autocrlf` and `.gitattributes`, stale context and whitespace
+#[tauri::command]
+pub async fn preflight_patch(
+    project_path: String,
+    patch: String,
+    cached: bool,
+    reverse: bool,
+    three_way: Option<bool>,
+) -> Result<PatchPreflight> {
+    let options = ApplyOptions {
+        cached,
+        reverse,
+        three_way: three_way.unwrap_or(false),
+    };
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = patch_target(&project_path, &patch)?;
+        patch_preflight::check(&canonical_path, &patch, options)
     })
     .await
 }
 
+/// Canonical repository path for applying a non-empty patch
+fn patch_target(project_path: &str, patch: &str) -> Result<std::path::PathBuf> {
+    let canonical_path = crate::utils::validate_and_canonicalize_path(project_path)?;
+
+    if !inside_git_repo(&canonical_path)? {
+        return Err(crate::Error::NotARepo);
+    }
+
+    if patch.trim().is_empty() {
+        return Err(crate::Error::Other("Patch content is empty".to_string()));
+    }
+    Ok(canonical_path)
+}
+
 /// Get git diff for staged changes only (git diff --cached)
 #[tauri::command]
 pub async fn git_diff_staged(path: String) -> Result<GitDiff> {
@@ -1527,7 +3172,7 @@ pub async fn git_diff_branch(project_path: String, base_branch: String) -> Resul
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
         }
 
         let diff_range = format!("{base_branch}...HEAD");
@@ -1538,65 +3183,196 @@ pub async fn git_diff_branch(project_path: String, base_branch: String) -> Resul
     .await
 }
 
-/// Check if GitHub CLI (gh) is installed and authenticated.
-/// Returns one of: "ready", "not-installed", "not-authenticated".
+/// Check that the CLI or credentials for the project's git host are set up
+///
+/// The host (GitHub, GitLab or Bitbucket) is detected from the current
+/// branch's remote, falling back to `origin`.
 #[tauri::command]
-pub async fn check_gh_cli(project_path: String) -> Result<String> {
+pub async fn check_forge_cli(project_path: String) -> Result<ForgeStatus> {
     crate::utils::spawn_blocking_io(move || {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
-        // Step 1: Check if gh is installed
-        let version_output = std::process::Command::new("gh")
-            .arg("--version")
-            .current_dir(&canonical_path)
-            .output();
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
 
-        match version_output {
-            Ok(_) => {}
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                return Ok("not-installed".to_string())
-            }
-            Err(err) => {
-                return Err(crate::Error::Other(format!(
-                    "Failed to check gh CLI: {err}"
-                )))
-            }
+        let head = resolve_head_state(&canonical_path).ok();
+        let remote = forge::detect(&canonical_path, head.as_ref().and_then(|h| h.branch_name()))?;
+        forge::auth_status(&canonical_path, &remote)
+    })
+    .await
+}
+
+/// Web links for the project's git host
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeLinks {
+    pub provider: ForgeProvider,
+    pub repository_url: String,
+    pub branch_url: String,
+    /// Page that starts a pull request from the head into the base branch
+    pub new_pull_request_url: String,
+}
+
+/// Links to the repository, `head_branch` and a new pull request on the
+/// project's git host, for opening in the browser
+#[tauri::command]
+pub async fn get_forge_links(
+    project_path: String,
+    base_branch: String,
+    head_branch: String,
+) -> Result<ForgeLinks> {
+    validate_branch_name(&base_branch)?;
+    validate_branch_name(&head_branch)?;
+
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
+
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+
+        let remote = forge::detect(&canonical_path, Some(&head_branch))?;
+        Ok(ForgeLinks {
+            provider: remote.provider,
+            repository_url: remote.web_url(),
+            branch_url: remote.branch_url(&head_branch),
+            new_pull_request_url: remote.new_pull_request_url(&base_branch, &head_branch),
+        })
+    })
+    .await
+}
+
+/// Store the Bitbucket username and app password used to open pull
+/// requests, or with no `app_password`, remove them
+#[tauri::command]
+pub async fn set_bitbucket_credentials(
+    username: String,
+    app_password: Option<String>,
+) -> Result<()> {
+    let username = username.trim().to_string();
+    let app_password = app_password.filter(|password| !password.trim().is_empty());
+    if app_password.is_some() && (username.is_empty() || username.contains(':')) {
+        return Err(crate::Error::Other("Invalid Bitbucket username".to_string()));
+    }
+
+    crate::utils::spawn_blocking_io(move || {
+        forge::set_bitbucket_credentials(
+            app_password
+                .as_deref()
+                .map(|password| (username.as_str(), password.trim())),
+        )
+    })
+    .await
+}
+
+/// Get the current git branch name
+#[tauri::command]
+pub async fn get_current_branch(project_path: String) -> Result<String> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
+
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+
+        require_branch(&canonical_path)
+    })
+    .await
+}
+
+/// Get the resolved HEAD state (branch, detached, or unborn)
+#[tauri::command]
+pub async fn get_head_state(project_path: String) -> Result<GitHeadState> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
+
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+
+        resolve_head_state(&canonical_path)
+    })
+    .await
+}
+
+/// Fetch the full history of a shallow clone (no-op for complete repositories)
+#[tauri::command]
+pub async fn unshallow_repo(project_path: String) -> Result<bool> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
+
+        if !inside_git_repo(&canonical_path)? {
+            return Err(crate::Error::NotARepo);
+        }
+
+        if !is_shallow_repo(&canonical_path) {
+            return Ok(false);
         }
 
-        // Step 2: Check auth status via `gh auth status`
-        let auth_output = std::process::Command::new("gh")
-            .args(["auth", "status"])
+        let output = std::process::Command::new("git")
+            .args(["fetch", "--unshallow"])
             .current_dir(&canonical_path)
             .output()
-            .map_err(|err| crate::Error::Other(format!("Failed to check gh CLI: {err}")))?;
+            .map_err(|err| crate::Error::Git(format!("Failed to run git fetch: {err}")))?;
 
-        if auth_output.status.success() {
-            Ok("ready".to_string())
-        } else {
-            Ok("not-authenticated".to_string())
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::Error::Git(format!("git fetch --unshallow failed: {stderr}")));
         }
+
+        tracing::info!("Unshallowed repository at {}", canonical_path.display());
+        Ok(true)
     })
     .await
 }
 
-/// Get the current git branch name
+/// Create and switch to a new branch at the current detached HEAD
 #[tauri::command]
-pub async fn get_current_branch(project_path: String) -> Result<String> {
+pub async fn create_branch_from_detached_head(
+    project_path: String,
+    name: String,
+) -> Result<GitHeadState> {
+    validate_branch_name(&name)?;
+
     crate::utils::spawn_blocking_io(move || {
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            return Err(crate::Error::NotARepo);
+        }
+
+        match resolve_head_state(&canonical_path)? {
+            GitHeadState::Detached { .. } => {}
+            other => {
+                return Err(crate::Error::Git(format!(
+                    "HEAD is not detached (currently on '{}')",
+                    other.branch_name().unwrap_or_default()
+                )));
+            }
+        }
+
+        let output = std::process::Command::new("git")
+            .args(["checkout", "-b", &name])
+            .current_dir(&canonical_path)
+            .output()
+            .map_err(|err| crate::Error::Git(format!("Failed to run git checkout: {err}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(crate::Error::Git(format!("git checkout -b failed: {stderr}")));
         }
 
-        let output = run_git_capture_stdout(&canonical_path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
-        Ok(output.trim().to_string())
+        tracing::info!("Created branch {} from detached HEAD in {}", name, canonical_path.display());
+        resolve_head_state(&canonical_path)
     })
     .await
 }
 
-/// Create a pull request using GitHub CLI
-/// Title and body are passed via stdin to avoid shell injection
+/// Create a pull request on the project's git host
+///
+/// Uses `gh` for GitHub, `glab` for GitLab (as a merge request) and the
+/// REST API for Bitbucket. Title and body are never passed through a shell.
 #[tauri::command]
 pub async fn create_pull_request(
     project_path: String,
@@ -1605,10 +3381,7 @@ pub async fn create_pull_request(
     base_branch: String,
     head_branch: String,
     draft: bool,
-) -> Result<String> {
-    use std::io::Write;
-    use std::process::{Command, Stdio};
-
+) -> Result<CreatedPullRequest> {
     // Validate branch names
     validate_branch_name(&base_branch)?;
     validate_branch_name(&head_branch)?;
@@ -1632,73 +3405,35 @@ pub async fn create_pull_request(
         ));
     }
 
-    crate::utils::spawn_blocking_io(move || {
-        let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
+    let (canonical_path, remote) = crate::utils::spawn_blocking_io({
+        let head_branch = head_branch.clone();
+        move || {
+            let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
-        if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other("Not a git repository".to_string()));
+            if !inside_git_repo(&canonical_path)? {
+                return Err(crate::Error::NotARepo);
+            }
+
+            let remote = forge::detect(&canonical_path, Some(&head_branch))?;
+            Ok((canonical_path, remote))
         }
+    })
+    .await?;
 
-        // Build gh pr create command args
-        // Title and body are passed as direct arguments (not through shell)
-        let mut args = vec![
-            "pr".to_string(),
-            "create".to_string(),
-            "--title".to_string(),
+    let created = forge::create(
+        &canonical_path,
+        &remote,
+        NewPullRequest {
             title,
-            "--base".to_string(),
+            body,
             base_branch,
-            "--head".to_string(),
             head_branch,
-        ];
-
-        if draft {
-            args.push("--draft".to_string());
-        }
-
-        // Pass body via --body-file - using stdin to avoid any shell interpretation
-        args.push("--body-file".to_string());
-        args.push("-".to_string()); // Read from stdin
-
-        let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
-
-        let mut child = Command::new("gh")
-            .args(&arg_refs)
-            .current_dir(&canonical_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|err| {
-                if err.kind() == std::io::ErrorKind::NotFound {
-                    crate::Error::Other(
-                        "GitHub CLI (gh) is not installed. Install it with: brew install gh".to_string(),
-                    )
-                } else {
-                    crate::Error::Other(format!("Failed to run gh: {err}"))
-                }
-            })?;
-
-        // Write body to stdin
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(body.as_bytes())
-                .map_err(|err| crate::Error::Other(format!("Failed to write PR body: {err}")))?;
-        }
-
-        let output = child.wait_with_output()
-            .map_err(|err| crate::Error::Other(format!("Failed to wait for gh: {err}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(crate::Error::Other(format!("gh pr create failed: {stderr}")));
-        }
-
-        // gh pr create outputs the PR URL on stdout
-        let pr_url = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        tracing::info!("Created PR: {}", pr_url);
-        Ok(pr_url)
-    })
-    .await
+            draft,
+        },
+    )
+    .await?;
+    tracing::info!("Created {:?} pull request: {}", created.provider, created.url);
+    Ok(created)
 }
 
 /// Checkout an existing git branch
@@ -1713,9 +3448,7 @@ pub async fn git_checkout_branch(
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other(
-                "Not a git repository".to_string(),
-            ));
+            return Err(crate::Error::NotARepo);
         }
 
         let output = std::process::Command::new("git")
@@ -1750,9 +3483,7 @@ pub async fn git_merge_no_ff(
         let canonical_path = crate::utils::validate_and_canonicalize_path(&project_path)?;
 
         if !inside_git_repo(&canonical_path)? {
-            return Err(crate::Error::Other(
-                "Not a git repository".to_string(),
-            ));
+            return Err(crate::Error::NotARepo);
         }
 
         let output = std::process::Command::new("git")
@@ -2000,6 +3731,187 @@ mod tests {
         assert!(validate_limit(u32::MAX).is_err());
     }
 
+    // ==================== walk_project_files tests ====================
+
+    #[test]
+    fn test_walk_project_files_respects_gitignore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::write(root.join(".gitignore"), "*.log\nvendor/\n").unwrap();
+        std::fs::write(root.join("main.rs"), "").unwrap();
+        std::fs::write(root.join("debug.log"), "").unwrap();
+        std::fs::create_dir_all(root.join("vendor/lib")).unwrap();
+        std::fs::write(root.join("vendor/lib/dep.rs"), "").unwrap();
+
+        let rules = IgnoreRules::default();
+        let (files, truncated) = walk_project_files(&root, &rules, None, 100, DEFAULT_LIST_DEPTH);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+
+        assert!(!truncated);
+        assert!(paths.contains(&"main.rs"));
+        assert!(paths.contains(&".gitignore"));
+        assert!(!paths.contains(&"debug.log"));
+        assert!(!paths.iter().any(|p| p.starts_with("vendor")));
+    }
+
+    #[test]
+    fn test_walk_project_files_applies_project_rules() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join(".github/workflows")).unwrap();
+        std::fs::write(root.join(".github/workflows/ci.yml"), "").unwrap();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(root.join("dist/app.js"), "").unwrap();
+
+        let patterns = ["dist/".to_string(), "!.github/".to_string()];
+        let rules = IgnoreRules::new(&root, &patterns).unwrap();
+        let (files, _) = walk_project_files(&root, &rules, None, 100, DEFAULT_LIST_DEPTH);
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+
+        assert!(paths.contains(&".github/workflows/ci.yml"));
+        assert!(!paths.iter().any(|p| p.starts_with("dist")));
+    }
+
+    #[test]
+    fn test_child_git_status() {
+        let statuses = parse_git_status("M  src/lib.rs\n M src/lib.rs\n?? notes/\nA  docs/new.md\n");
+
+        assert_eq!(child_git_status(&statuses, "src/lib.rs", false).as_deref(), Some("M"));
+        assert_eq!(child_git_status(&statuses, "src", true).as_deref(), Some("M"));
+        assert_eq!(child_git_status(&statuses, "notes", true).as_deref(), Some("?"));
+        assert_eq!(child_git_status(&statuses, "docs", true).as_deref(), Some("M"));
+        assert_eq!(child_git_status(&statuses, "README.md", false), None);
+    }
+
+    #[test]
+    fn test_duplicate_name() {
+        assert_eq!(duplicate_name("main.rs", 1), "main copy.rs");
+        assert_eq!(duplicate_name("main.rs", 3), "main copy 3.rs");
+        assert_eq!(duplicate_name("src", 1), "src copy");
+        assert_eq!(duplicate_name(".env", 2), ".env copy 2");
+        assert!(validate_file_name("a/b").is_err());
+        assert!(validate_file_name("..").is_err());
+        assert!(validate_file_name("notes.md").is_ok());
+    }
+
+    #[test]
+    fn test_move_project_entry_without_git() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let policy = crate::database::SymlinkPolicy::default();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        std::fs::write(root.join("docs/lib.rs"), "").unwrap();
+
+        assert!(!move_project_entry(&root, "src/lib.rs", "src/main.rs", policy).unwrap());
+        assert!(root.join("src/main.rs").is_file());
+
+        assert!(move_project_entry(&root, "src/main.rs", "docs/lib.rs", policy).is_err());
+        assert!(move_project_entry(&root, "src", "src/nested/src", policy).is_err());
+        assert!(move_project_entry(&root, "missing.rs", "x.rs", policy).is_err());
+        assert!(move_project_entry(&root, "src/main.rs", "nowhere/main.rs", policy).is_err());
+
+        move_project_entry(&root, "src", "docs/src", policy).unwrap();
+        assert!(root.join("docs/src/main.rs").is_file());
+    }
+
+    #[test]
+    fn test_worktree_status() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let repo = root.join("repo");
+        let worktree = root.join("wt");
+        std::fs::create_dir(&repo).unwrap();
+        let git = |dir: &Path, args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        std::fs::write(repo.join("a.txt"), "12345").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", "--", "../wt"]);
+        std::fs::write(worktree.join("b.txt"), "1").unwrap();
+        git(&worktree, &["add", "-A"]);
+        git(&worktree, &["commit", "-q", "-m", "feature"]);
+        std::fs::write(worktree.join("c.txt"), "1").unwrap();
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "one"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "two"]);
+
+        let info = |path: &Path, branch: &str, is_main| WorktreeInfo {
+            path: path.to_string_lossy().to_string(),
+            branch: branch.to_string(),
+            is_main,
+            head_commit: String::new(),
+        };
+        let sessions = HashMap::from([(worktree.clone(), "s1".to_string())]);
+        let status = worktree_status(info(&worktree, "feature", false), Some("main"), &sessions);
+        assert!(status.dirty);
+        assert_eq!((status.ahead, status.behind), (Some(1), Some(2)));
+        assert_eq!(status.session_id.as_deref(), Some("s1"));
+        assert_eq!(status.disk_usage, 7);
+
+        let status = worktree_status(info(&repo, "main", true), Some("main"), &sessions);
+        assert!(!status.dirty);
+        assert_eq!((status.ahead, status.behind), (None, None));
+        assert_eq!(status.session_id, None);
+        assert_eq!(status.disk_usage, 5);
+    }
+
+    #[test]
+    fn test_walk_project_files_reaches_deep_files_and_truncates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let deep = root.join("a/b/c/d/e/f/g");
+        std::fs::create_dir_all(&deep).unwrap();
+        std::fs::write(deep.join("deep.txt"), "").unwrap();
+
+        let rules = IgnoreRules::default();
+        let (files, truncated) = walk_project_files(&root, &rules, Some("deep"), 100, DEFAULT_LIST_DEPTH);
+        assert!(!truncated);
+        assert!(files.iter().any(|f| f.path.ends_with("deep.txt")));
+
+        let (files, truncated) = walk_project_files(&root, &rules, None, 2, DEFAULT_LIST_DEPTH);
+        assert_eq!(files.len(), 2);
+        assert!(truncated);
+    }
+
+    // ==================== GitHeadState tests ====================
+
+    #[test]
+    fn test_head_state_branch_name() {
+        let branch = GitHeadState::Branch { name: "main".to_string() };
+        assert_eq!(branch.branch_name(), Some("main"));
+        assert!(branch.unusable().is_none());
+
+        let unborn = GitHeadState::Unborn { name: "main".to_string() };
+        assert_eq!(unborn.branch_name(), Some("main"));
+        assert!(matches!(unborn.unusable(), Some(crate::Error::UnbornBranch(_))));
+
+        let detached = GitHeadState::Detached {
+            sha: "abc1234567890def1234567890abc1234567890a".to_string(),
+        };
+        assert_eq!(detached.branch_name(), None);
+        assert!(matches!(
+            detached.unusable(),
+            Some(crate::Error::DetachedHead { sha }) if sha == "abc1234"
+        ));
+    }
+
+    #[test]
+    fn test_head_state_serialization() {
+        let value = serde_json::to_value(GitHeadState::Detached { sha: "abc1234".to_string() }).unwrap();
+        assert_eq!(value["state"], "detached");
+        assert_eq!(value["sha"], "abc1234");
+    }
+
     // ==================== Integration security tests ====================
 
     #[test]
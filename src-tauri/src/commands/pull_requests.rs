@@ -0,0 +1,286 @@
+//! Pull request commands - list, fetch, check out and discuss PRs through
+//! `gh`, and follow the CI status of the current branch
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::app_server::ipc_bridge::{
+    ThreadStartParams, ThreadStartResponse, TurnStartParams, TurnStartResponse, UserInput,
+};
+use crate::ci_status::{self, CiStatus};
+use crate::commands::projects::{
+    inside_git_repo, resolve_head_state, validate_branch_name, validate_id, GitHeadState,
+};
+use crate::commands::thread::{start_thread_with_params, start_turn};
+use crate::pull_requests::{
+    self, PullRequestComment, PullRequestDetail, PullRequestFilter, PullRequestSummary,
+    DEFAULT_LIST_LIMIT, MAX_LIST_LIMIT,
+};
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Longest comment `reply_to_pr_comment` posts, as GitHub allows
+const MAX_COMMENT_LEN: usize = 65536;
+
+/// Result of `start_pr_review_thread`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrReviewThread {
+    pub pull_request: PullRequestSummary,
+    /// Checkout the thread runs in, on the pull request's head branch
+    pub cwd: String,
+    pub prompt: String,
+    pub thread: ThreadStartResponse,
+    pub turn: TurnStartResponse,
+}
+
+fn require_repo(project_path: &str) -> Result<std::path::PathBuf> {
+    let canonical_path = crate::utils::validate_and_canonicalize_path(project_path)?;
+    if !inside_git_repo(&canonical_path)? {
+        return Err(Error::NotARepo);
+    }
+    Ok(canonical_path)
+}
+
+fn validate_number(number: u64) -> Result<()> {
+    if number == 0 {
+        return Err(Error::Other("Invalid pull request number".to_string()));
+    }
+    Ok(())
+}
+
+/// List the pull requests of the project's GitHub repository
+#[tauri::command]
+pub async fn list_pull_requests(
+    project_path: String,
+    state: Option<PullRequestFilter>,
+    limit: Option<u32>,
+) -> Result<Vec<PullRequestSummary>> {
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, MAX_LIST_LIMIT);
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        pull_requests::list(&canonical_path, state.unwrap_or_default(), limit)
+    })
+    .await
+}
+
+/// Get a pull request with its diff, comments and checks
+#[tauri::command]
+pub async fn get_pull_request(project_path: String, number: u64) -> Result<PullRequestDetail> {
+    validate_number(number)?;
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        pull_requests::get(&canonical_path, number)
+    })
+    .await
+}
+
+/// Check out a pull request's branch in the project
+///
+/// `branch` names the local branch; by default `gh` uses the PR's head
+/// branch name. Fails, leaving the checkout alone, when local changes
+/// would be overwritten.
+#[tauri::command]
+pub async fn checkout_pull_request(
+    project_path: String,
+    number: u64,
+    branch: Option<String>,
+) -> Result<GitHeadState> {
+    validate_number(number)?;
+    let branch = branch
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty());
+    if let Some(branch) = &branch {
+        validate_branch_name(branch)?;
+    }
+
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        pull_requests::checkout(&canonical_path, number, branch.as_deref())?;
+        tracing::info!(
+            "Checked out pull request #{} in {}",
+            number,
+            canonical_path.display()
+        );
+        resolve_head_state(&canonical_path)
+    })
+    .await
+}
+
+/// List the inline review comments of a pull request, replies included
+#[tauri::command]
+pub async fn list_pr_review_comments(
+    project_path: String,
+    number: u64,
+) -> Result<Vec<PullRequestComment>> {
+    validate_number(number)?;
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        pull_requests::review_comments(&canonical_path, number)
+    })
+    .await
+}
+
+/// Post a comment on a pull request
+///
+/// With `comment_id` the comment is a reply in that review comment's
+/// thread; without, it goes on the pull request's conversation.
+#[tauri::command]
+pub async fn reply_to_pr_comment(
+    project_path: String,
+    number: u64,
+    comment_id: Option<u64>,
+    body: String,
+) -> Result<PullRequestComment> {
+    validate_number(number)?;
+    if body.trim().is_empty() {
+        return Err(Error::Other("Comment cannot be empty".to_string()));
+    }
+    if body.len() > MAX_COMMENT_LEN {
+        return Err(Error::Other(format!(
+            "Comment exceeds maximum length of {MAX_COMMENT_LEN} characters"
+        )));
+    }
+
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        let comment = pull_requests::reply(&canonical_path, number, comment_id, &body)?;
+        tracing::info!("Commented on pull request #{}", number);
+        Ok(comment)
+    })
+    .await
+}
+
+/// Start a thread that addresses the selected review comments
+///
+/// The thread runs on the pull request's head branch: in the checkout that
+/// already has it, or else in a new worktree next to the project, so the
+/// project's own checkout is left alone. The selected comments become the
+/// first prompt, followed by `instructions` when given.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_pr_review_thread(
+    state: State<'_, AppState>,
+    project_id: String,
+    number: u64,
+    mut comment_ids: Vec<u64>,
+    instructions: Option<String>,
+    model: Option<String>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+) -> Result<PrReviewThread> {
+    validate_id(&project_id, "project_id")?;
+    validate_number(number)?;
+    comment_ids.sort_unstable();
+    comment_ids.dedup();
+    if comment_ids.is_empty() {
+        return Err(Error::Other(
+            "Select at least one review comment".to_string(),
+        ));
+    }
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+
+    let (summary, comments, cwd) = crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project.path)?;
+        let summary = pull_requests::summary(&canonical_path, number)?;
+        let comments: Vec<PullRequestComment> =
+            pull_requests::review_comments(&canonical_path, number)?
+                .into_iter()
+                .filter(|comment| comment.id.is_some_and(|id| comment_ids.contains(&id)))
+                .collect();
+        if comments.len() != comment_ids.len() {
+            return Err(Error::Other(format!(
+                "Some of the selected comments are not on pull request #{number}"
+            )));
+        }
+        let cwd = pull_requests::review_worktree(&canonical_path, &summary)?;
+        Ok((summary, comments, cwd))
+    })
+    .await?;
+
+    let mut prompt = pull_requests::address_comments_prompt(&summary, &comments);
+    if let Some(instructions) = instructions.filter(|text| !text.trim().is_empty()) {
+        prompt.push_str(&format!("\n{}\n", instructions.trim()));
+    }
+
+    state.start_app_server().await?;
+    let cwd = cwd.to_string_lossy().to_string();
+    let thread_params = ThreadStartParams {
+        cwd: Some(cwd.clone()),
+        model: model.clone(),
+        model_provider: None,
+        sandbox,
+        approval_policy,
+        base_instructions: None,
+        developer_instructions: None,
+        config: None,
+    };
+    let thread = start_thread_with_params(&state, &project_id, None, thread_params).await?;
+    let turn = start_turn(
+        &state,
+        TurnStartParams {
+            thread_id: thread.thread.id.clone(),
+            input: vec![UserInput::Text {
+                text: prompt.clone(),
+            }],
+            effort: None,
+            summary: None,
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model,
+        },
+    )
+    .await?;
+
+    tracing::info!(
+        "Started thread {} on {} for {} comments of pull request #{}",
+        thread.thread.id,
+        summary.head_branch,
+        comments.len(),
+        number
+    );
+    Ok(PrReviewThread {
+        pull_request: summary,
+        cwd,
+        prompt,
+        thread,
+        turn,
+    })
+}
+
+/// CI status of the project's current branch: its workflow runs and the
+/// checks of its pull request
+#[tauri::command]
+pub async fn get_ci_status(project_path: String) -> Result<CiStatus> {
+    crate::utils::spawn_blocking_io(move || {
+        let canonical_path = require_repo(&project_path)?;
+        ci_status::get(&canonical_path)
+    })
+    .await
+}
+
+/// Start emitting `ci-status-changed` events for a project's current branch
+///
+/// Calling this for a project that is already watched is a no-op.
+#[tauri::command]
+pub async fn watch_ci_status(state: State<'_, AppState>, project_id: String) -> Result<()> {
+    validate_id(&project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+    let root = crate::utils::spawn_blocking_io(move || require_repo(&project.path)).await?;
+    state.ci_watchers.watch(&project_id, root, state.events.clone());
+    Ok(())
+}
+
+/// Stop polling a project's CI status; returns whether it was watched
+#[tauri::command]
+pub async fn unwatch_ci_status(state: State<'_, AppState>, project_id: String) -> Result<bool> {
+    validate_id(&project_id, "project_id")?;
+    Ok(state.ci_watchers.unwatch(&project_id))
+}
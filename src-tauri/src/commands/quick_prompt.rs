@@ -0,0 +1,54 @@
+//! Summon shortcut and quick prompt commands
+
+use tauri::State;
+
+use crate::global_shortcuts;
+use crate::global_state::SummonShortcutSettings;
+use crate::quick_prompt::{self, QuickPromptTarget};
+use crate::state::AppState;
+use crate::Result;
+
+/// Get the shortcuts that summon the window and the quick prompt palette
+#[tauri::command]
+pub async fn get_summon_shortcut_settings(
+    state: State<'_, AppState>,
+) -> Result<SummonShortcutSettings> {
+    Ok(state
+        .global_state
+        .read(|global| global.summon_shortcuts.clone()))
+}
+
+/// Update the summon shortcuts and register them
+///
+/// Nothing is saved when a shortcut cannot be registered or is already an
+/// approval shortcut; the previous shortcuts stay in place.
+#[tauri::command]
+pub async fn set_summon_shortcut_settings(
+    state: State<'_, AppState>,
+    settings: SummonShortcutSettings,
+) -> Result<SummonShortcutSettings> {
+    let approvals = state
+        .global_state
+        .read(|global| global.approval_shortcuts.clone());
+    global_shortcuts::validate(&approvals, &settings)?;
+    if let Err(err) = global_shortcuts::register(&state.app_handle, &approvals, &settings) {
+        if let Err(restore_err) = global_shortcuts::register_saved(&state.app_handle) {
+            tracing::warn!("Failed to restore summon shortcuts: {}", restore_err);
+        }
+        return Err(err);
+    }
+    state
+        .global_state
+        .update(|global| global.summon_shortcuts = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// The project and thread a quick prompt goes to: the most recently opened
+/// project and its latest unarchived thread
+#[tauri::command]
+pub async fn get_quick_prompt_target(
+    state: State<'_, AppState>,
+) -> Result<Option<QuickPromptTarget>> {
+    quick_prompt::resolve_target(&state).await
+}
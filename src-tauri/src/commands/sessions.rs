@@ -1,21 +1,52 @@
 //! Session management commands
 
+use std::path::Path;
+
+use serde::Serialize;
 use tauri::State;
 
-use crate::database::{SessionMetadata, SessionStatus};
+use crate::database::{
+    ImportedTranscript, RecoveredTurn, SessionFilter, SessionMetadata, SessionSearchHit,
+    SessionStatus, SessionTagCount, TurnOutcomeRecord, TurnTimings,
+};
+use crate::global_state::{RedactionSettings, SecretMaskingSettings};
+use crate::live_diff::LiveDiff;
+use crate::redaction::{self, RedactionReport, Redactor};
+use crate::secret_mask;
+use crate::session_stats::{self, SessionStatsReport};
+use crate::session_worktrees::{self, SessionWorktreeStatus, WorktreeCleanup};
 use crate::state::AppState;
-use crate::Result;
+use crate::{Error, Result};
 
 // Import validation function from projects module
-use crate::commands::projects::validate_id;
+use crate::commands::projects::{git_merge_no_ff, remove_worktree, validate_id, GitMergeResult};
+use crate::commands::thread::fetch_thread_items;
+use crate::transcript_export::{self, ExportFormat, TranscriptMeta};
+use crate::transcript_import;
 
 /// List sessions for a project
+///
+/// Favorites come first. `tags` keeps only sessions carrying every listed
+/// tag; `favorites_only` keeps only favorited sessions; `archived` lists
+/// archived sessions instead of active ones.
 #[tauri::command]
 pub async fn list_sessions(
     state: State<'_, AppState>,
     project_id: String,
+    tags: Option<Vec<String>>,
+    favorites_only: Option<bool>,
+    archived: Option<bool>,
 ) -> Result<Vec<SessionMetadata>> {
-    state.database.get_sessions_for_project(&project_id)
+    let filter = SessionFilter {
+        tags: tags
+            .unwrap_or_default()
+            .iter()
+            .map(|tag| normalize_tag(tag))
+            .collect::<Result<_>>()?,
+        favorites_only: favorites_only.unwrap_or(false),
+        archived: archived.unwrap_or(false),
+    };
+    state.database.get_sessions_for_project(&project_id, &filter)
 }
 
 /// Get session metadata
@@ -78,11 +109,25 @@ pub async fn update_session_metadata(
         metadata.title = Some(t);
     }
     if let Some(t) = tags {
-        metadata.set_tags(t);
+        if t.len() > MAX_TAGS_PER_SESSION {
+            return Err(crate::Error::Other(format!(
+                "Too many tags: {} (max {MAX_TAGS_PER_SESSION})",
+                t.len()
+            )));
+        }
+        let mut normalized: Vec<String> = Vec::with_capacity(t.len());
+        for tag in &t {
+            let tag = normalize_tag(tag)?;
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        metadata.set_tags(normalized);
     }
     if let Some(f) = is_favorite {
         metadata.is_favorite = f;
     }
+    let newly_archived = is_archived == Some(true) && !metadata.is_archived;
     if let Some(a) = is_archived {
         metadata.is_archived = a;
     }
@@ -92,7 +137,7 @@ pub async fn update_session_metadata(
     if let Some(fm) = first_message {
         // Only set first message if not already set
         if metadata.first_message.is_none() {
-            metadata.first_message = Some(fm);
+            metadata.first_message = Some(crate::secret_mask::mask(&fm).into_owned());
         }
     }
     if let Some(tj) = tasks_json {
@@ -101,19 +146,224 @@ pub async fn update_session_metadata(
     metadata.last_accessed_at = Some(chrono::Utc::now().timestamp());
 
     state.database.upsert_session_metadata(&metadata)?;
+    if newly_archived {
+        offer_worktree_cleanup(&state, std::slice::from_ref(&session_id)).await;
+    }
 
     Ok(metadata)
 }
 
-/// Delete session metadata
+/// Delete session metadata and its snapshots
 #[tauri::command]
 pub async fn delete_session(state: State<'_, AppState>, session_id: String) -> Result<()> {
     validate_id(&session_id, "session_id")?;
-    state.database.delete_session_metadata(&session_id)?;
+    state.database.delete_session(&session_id)?;
+    state.followups.clear(&session_id);
+    Ok(())
+}
+
+/// Maximum number of sessions accepted by a bulk operation
+const MAX_BULK_SESSIONS: usize = 1000;
+
+/// Event emitted once after each bulk session operation
+const SESSIONS_BULK_EVENT: &str = "sessions:bulk-updated";
+
+/// Payload of `sessions:bulk-updated`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkSessionSummary {
+    /// `delete`, `archive`, `unarchive` or `move`
+    pub action: &'static str,
+    pub session_ids: Vec<String>,
+    /// Sessions actually changed (missing or already-matching ones are skipped)
+    pub affected: usize,
+    /// Destination project for `move`
+    pub project_id: Option<String>,
+}
+
+fn validate_session_ids(session_ids: &[String]) -> Result<()> {
+    if session_ids.len() > MAX_BULK_SESSIONS {
+        return Err(crate::Error::Other(format!(
+            "Too many sessions: {} (max {MAX_BULK_SESSIONS})",
+            session_ids.len()
+        )));
+    }
+    for session_id in session_ids {
+        validate_id(session_id, "session_id")?;
+    }
+    Ok(())
+}
+
+async fn emit_bulk_summary(state: &AppState, summary: &BulkSessionSummary) {
+    state.events.emit(SESSIONS_BULK_EVENT, summary).await;
+}
+
+/// Delete several sessions at once (all or nothing)
+#[tauri::command]
+pub async fn delete_sessions(
+    state: State<'_, AppState>,
+    session_ids: Vec<String>,
+) -> Result<BulkSessionSummary> {
+    validate_session_ids(&session_ids)?;
+    let affected = state.database.delete_sessions(&session_ids)?;
+    for session_id in &session_ids {
+        state.followups.clear(session_id);
+    }
+
+    let summary = BulkSessionSummary {
+        action: "delete",
+        session_ids,
+        affected,
+        project_id: None,
+    };
+    emit_bulk_summary(&state, &summary).await;
+    Ok(summary)
+}
+
+/// Archive or unarchive several sessions at once (all or nothing)
+#[tauri::command]
+pub async fn archive_sessions(
+    state: State<'_, AppState>,
+    session_ids: Vec<String>,
+    archived: bool,
+) -> Result<BulkSessionSummary> {
+    validate_session_ids(&session_ids)?;
+    let affected = state.database.set_sessions_archived(&session_ids, archived)?;
+    if archived {
+        offer_worktree_cleanup(&state, &session_ids).await;
+    }
+
+    let summary = BulkSessionSummary {
+        action: if archived { "archive" } else { "unarchive" },
+        session_ids,
+        affected,
+        project_id: None,
+    };
+    emit_bulk_summary(&state, &summary).await;
+    Ok(summary)
+}
+
+/// Move several sessions to another project at once (all or nothing)
+#[tauri::command]
+pub async fn move_sessions_to_project(
+    state: State<'_, AppState>,
+    session_ids: Vec<String>,
+    project_id: String,
+) -> Result<BulkSessionSummary> {
+    validate_session_ids(&session_ids)?;
+    validate_id(&project_id, "project_id")?;
+    let affected = state
+        .database
+        .move_sessions_to_project(&session_ids, &project_id)?;
+
+    let summary = BulkSessionSummary {
+        action: "move",
+        session_ids,
+        affected,
+        project_id: Some(project_id),
+    };
+    emit_bulk_summary(&state, &summary).await;
+    Ok(summary)
+}
+
+/// Attach an imported session to a project, creating its metadata if needed
+#[tauri::command]
+pub async fn adopt_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    project_id: String,
+) -> Result<SessionMetadata> {
+    validate_id(&session_id, "session_id")?;
+    validate_id(&project_id, "project_id")?;
+    state.database.adopt_session(&session_id, &project_id)
+}
+
+/// Maximum length of a tag, in characters
+const MAX_TAG_LEN: usize = 64;
+
+/// Maximum number of tags on one session
+const MAX_TAGS_PER_SESSION: usize = 32;
+
+/// Trim a tag and reject empty, overlong or control-character tags
+fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(crate::Error::Other("Tag cannot be empty".to_string()));
+    }
+    if tag.chars().count() > MAX_TAG_LEN {
+        return Err(crate::Error::Other(format!(
+            "Tag too long (max {MAX_TAG_LEN} characters)"
+        )));
+    }
+    if tag.chars().any(char::is_control) {
+        return Err(crate::Error::Other(
+            "Tag cannot contain control characters".to_string(),
+        ));
+    }
+    Ok(tag.to_string())
+}
+
+/// Add a tag to a session; returns the session's tags
+#[tauri::command]
+pub async fn add_session_tag(
+    state: State<'_, AppState>,
+    session_id: String,
+    tag: String,
+) -> Result<Vec<String>> {
+    validate_id(&session_id, "session_id")?;
+    let tag = normalize_tag(&tag)?;
+    if let Some(session) = state.database.get_session_by_id(&session_id)? {
+        let tags = session.get_tags();
+        if !tags.contains(&tag) && tags.len() >= MAX_TAGS_PER_SESSION {
+            return Err(crate::Error::Other(format!(
+                "Session already has {MAX_TAGS_PER_SESSION} tags"
+            )));
+        }
+    }
+    state.database.add_session_tag(&session_id, &tag)
+}
+
+/// Remove a tag from a session; returns the session's tags
+#[tauri::command]
+pub async fn remove_session_tag(
+    state: State<'_, AppState>,
+    session_id: String,
+    tag: String,
+) -> Result<Vec<String>> {
+    validate_id(&session_id, "session_id")?;
+    state.database.remove_session_tag(&session_id, tag.trim())
+}
+
+/// Tags in use with their session counts, across all projects or within one
+#[tauri::command]
+pub async fn list_session_tags(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+) -> Result<Vec<SessionTagCount>> {
+    if let Some(ref project_id) = project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    state.database.list_session_tags(project_id.as_deref())
+}
+
+/// Pin or unpin a session as a favorite
+#[tauri::command]
+pub async fn set_session_favorite(
+    state: State<'_, AppState>,
+    session_id: String,
+    favorite: bool,
+) -> Result<()> {
+    validate_id(&session_id, "session_id")?;
+    if !state.database.set_session_favorite(&session_id, favorite)? {
+        return Err(crate::Error::SessionNotFound(session_id));
+    }
     Ok(())
 }
 
 /// Update session status only (lightweight update)
+///
+/// Turn starts and outcomes update the status on their own; this is for
+/// states the backend cannot observe, like a renderer-side turn timeout.
 #[tauri::command]
 pub async fn update_session_status(
     state: State<'_, AppState>,
@@ -134,6 +384,7 @@ pub async fn set_session_first_message(
     first_message: String,
 ) -> Result<()> {
     validate_id(&session_id, "session_id")?;
+    let first_message = crate::secret_mask::mask(&first_message);
     state.database.update_session_first_message(&session_id, &first_message)?;
     Ok(())
 }
@@ -150,130 +401,580 @@ pub async fn update_session_tasks(
     Ok(())
 }
 
-/// Calculate relevance score for a session based on query match
-/// Scoring:
-/// - Exact title match: 100 points
-/// - Title prefix match: 80 points
-/// - Title contains match: 60 points
-/// - firstMessage match: 40 points
-/// - Tag match: 30 points
-/// - sessionId match: 10 points
-fn calculate_relevance_score(session: &SessionMetadata, query_lower: &str) -> i32 {
-    let mut score = 0i32;
-
-    // Title matching (highest priority)
-    if let Some(title) = &session.title {
-        let title_lower = title.to_lowercase();
-        if title_lower == query_lower {
-            // Exact match
-            score += 100;
-        } else if title_lower.starts_with(query_lower) {
-            // Prefix match
-            score += 80;
-        } else if title_lower.contains(query_lower) {
-            // Contains match
-            score += 60;
-        }
+/// Result of `export_session`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionExport {
+    pub path: String,
+    pub bytes: usize,
+    pub items: usize,
+    /// Values masked by the redaction pass (0 when it was skipped)
+    pub redactions: usize,
+}
+
+/// Render a whole session to Markdown, standalone HTML or raw JSON and
+/// write it to `path`
+///
+/// `path` must be absolute and its directory must exist; an existing file
+/// is replaced. The transcript is redacted with the saved redaction
+/// settings first unless `redact` is false (or redaction is turned off and
+/// `redact` is not given).
+#[tauri::command]
+pub async fn export_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    format: ExportFormat,
+    path: String,
+    redact: Option<bool>,
+) -> Result<SessionExport> {
+    validate_id(&session_id, "session_id")?;
+    let target = std::path::Path::new(&path);
+    let parent = target.parent().filter(|parent| parent.is_dir());
+    if !target.is_absolute() || parent.is_none() || target.is_dir() {
+        return Err(crate::Error::InvalidPath(format!(
+            "Export path must be an absolute file path in an existing directory: {path}"
+        )));
     }
 
-    // First message matching
-    if let Some(first_msg) = &session.first_message {
-        let first_msg_lower = first_msg.to_lowercase();
-        if first_msg_lower == query_lower {
-            score += 50; // Exact match bonus
-        } else if first_msg_lower.starts_with(query_lower) {
-            score += 45; // Prefix match
-        } else if first_msg_lower.contains(query_lower) {
-            score += 40;
+    let (mut meta, mut items) = load_transcript(&state, session_id).await?;
+    let settings = state.global_state.read(|g| g.redaction.clone());
+    let redactions = if redact.unwrap_or(settings.enabled) {
+        redact_transcript(&settings, &mut meta, &mut items)?
+    } else {
+        0
+    };
+
+    let item_count = items.len();
+    let target = target.to_path_buf();
+    let bytes = crate::utils::spawn_blocking_io(move || {
+        let rendered = transcript_export::render(format, &meta, &items)?;
+        crate::utils::atomic_write(&target, rendered.as_bytes())?;
+        Ok(rendered.len())
+    })
+    .await?;
+
+    tracing::info!(
+        "Exported {} items to {} ({} values redacted)",
+        item_count,
+        path,
+        redactions
+    );
+    Ok(SessionExport {
+        path,
+        bytes,
+        items: item_count,
+        redactions,
+    })
+}
+
+/// Show what `export_session` would mask in a session
+///
+/// `settings` overrides the saved redaction settings for this preview only.
+#[tauri::command]
+pub async fn preview_session_redaction(
+    state: State<'_, AppState>,
+    session_id: String,
+    settings: Option<RedactionSettings>,
+) -> Result<RedactionReport> {
+    validate_id(&session_id, "session_id")?;
+    let settings = match settings {
+        Some(settings) => settings,
+        None => state.global_state.read(|g| g.redaction.clone()),
+    };
+    let (meta, mut items) = load_transcript(&state, session_id).await?;
+    let redactor = Redactor::new(&settings, Some(&meta.cwd))?;
+    Ok(redactor.redact_items(&mut items))
+}
+
+/// Get the saved export redaction settings
+#[tauri::command]
+pub async fn get_redaction_settings(state: State<'_, AppState>) -> Result<RedactionSettings> {
+    Ok(state.global_state.read(|g| g.redaction.clone()))
+}
+
+/// Update the saved export redaction settings
+#[tauri::command]
+pub async fn set_redaction_settings(
+    state: State<'_, AppState>,
+    settings: RedactionSettings,
+) -> Result<RedactionSettings> {
+    redaction::validate_settings(&settings)?;
+    state
+        .global_state
+        .update(|g| g.redaction = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Get the settings for masking secrets in logs, events and session text
+#[tauri::command]
+pub async fn get_secret_masking_settings(
+    state: State<'_, AppState>,
+) -> Result<SecretMaskingSettings> {
+    Ok(state.global_state.read(|g| g.secret_masking.clone()))
+}
+
+/// Update the secret masking settings; they apply immediately
+#[tauri::command]
+pub async fn set_secret_masking_settings(
+    state: State<'_, AppState>,
+    settings: SecretMaskingSettings,
+) -> Result<SecretMaskingSettings> {
+    secret_mask::validate_settings(&settings)?;
+    secret_mask::configure(&settings)?;
+    state
+        .global_state
+        .update(|g| g.secret_masking = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Export header and thread items of a session
+async fn load_transcript(
+    state: &AppState,
+    session_id: String,
+) -> Result<(TranscriptMeta, Vec<serde_json::Value>)> {
+    let session = state.database.get_session_by_id(&session_id)?;
+    // Imported sessions have no app-server thread; export their stored items
+    let (title, cwd, model, items) = match state.database.get_imported_transcript(&session_id)? {
+        Some(imported) => (None, imported.cwd.unwrap_or_default(), None, imported.items),
+        None => {
+            let (thread, items) = fetch_thread_items(state, &session_id).await?;
+            let preview = thread.preview.filter(|preview| !preview.is_empty());
+            (preview, thread.cwd, thread.model, items)
         }
+    };
+    let meta = transcript_meta(session_id, session, title, cwd, model);
+    Ok((meta, items))
+}
+
+/// Export header of a session, preferring the names the GUI shows
+pub(crate) fn transcript_meta(
+    session_id: String,
+    session: Option<SessionMetadata>,
+    title: Option<String>,
+    cwd: String,
+    model: Option<String>,
+) -> TranscriptMeta {
+    TranscriptMeta {
+        title: session
+            .as_ref()
+            .map(SessionMetadata::get_display_name)
+            .or(title)
+            .unwrap_or_else(|| format!("Session {session_id}")),
+        session_id,
+        cwd,
+        model: model.or_else(|| session.and_then(|s| s.last_model)),
+        exported_at: chrono::Utc::now().timestamp(),
     }
+}
 
-    // Tag matching
-    let session_tags = session.get_tags();
-    for tag in &session_tags {
-        let tag_lower = tag.to_lowercase();
-        if tag_lower == query_lower {
-            score += 35; // Exact tag match
-        } else if tag_lower.contains(query_lower) {
-            score += 30;
-        }
+/// Mask secrets in a transcript about to be exported; returns how many
+/// values were masked
+pub(crate) fn redact_transcript(
+    settings: &RedactionSettings,
+    meta: &mut TranscriptMeta,
+    items: &mut [serde_json::Value],
+) -> Result<usize> {
+    let redactor = Redactor::new(settings, Some(&meta.cwd))?;
+    let mut report = RedactionReport::default();
+    meta.title = redactor.redact(&meta.title, &mut report);
+    meta.cwd = redactor.redact(&meta.cwd, &mut report);
+    Ok(report.total + redactor.redact_items(items).total)
+}
+
+/// Prefix of session IDs created by `import_session`
+pub const IMPORTED_SESSION_PREFIX: &str = "imported-";
+
+/// Largest transcript file accepted by `import_session`
+const MAX_IMPORT_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Create a read-only session in `project_id` from an exported JSON
+/// transcript or a Codex CLI rollout file
+///
+/// The session gets a new `imported-` ID; its items are stored locally and
+/// loaded with `get_imported_session_items`, and it is searchable and
+/// exportable like any other session.
+#[tauri::command]
+pub async fn import_session(
+    state: State<'_, AppState>,
+    project_id: String,
+    path: String,
+) -> Result<SessionMetadata> {
+    validate_id(&project_id, "project_id")?;
+    let source = std::path::PathBuf::from(&path);
+    if !source.is_absolute() || !source.is_file() {
+        return Err(crate::Error::InvalidPath(format!(
+            "Import path must be an absolute path to a file: {path}"
+        )));
     }
 
-    // Session ID matching (lowest priority)
-    if session.session_id.to_lowercase().contains(query_lower) {
-        score += 10;
+    let database = state.database.clone();
+    let session = crate::utils::spawn_blocking_io(move || {
+        if std::fs::metadata(&source)?.len() > MAX_IMPORT_BYTES {
+            return Err(crate::Error::Other(format!(
+                "Transcript is larger than {} MB: {path}",
+                MAX_IMPORT_BYTES / (1024 * 1024)
+            )));
+        }
+        let text = String::from_utf8_lossy(&std::fs::read(&source)?).into_owned();
+        let parsed = transcript_import::parse(&text)?;
+
+        let session_id = format!("{IMPORTED_SESSION_PREFIX}{}", uuid::Uuid::new_v4());
+        let mut session = SessionMetadata::new(&session_id, &project_id);
+        let preview = crate::session_preview::summarize(&parsed.items);
+        session.title = parsed.title;
+        session.status = SessionStatus::Completed;
+        session.first_message = parsed
+            .items
+            .iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("userMessage"))
+            .find_map(crate::session_preview::message_text)
+            .and_then(|text| crate::session_preview::preview_text(&text));
+        session.last_message_preview = preview.last_message_preview;
+        session.turn_count = preview.turn_count;
+        session.files_changed_count = preview.files_changed_count;
+        session.last_model = parsed.model;
+        if let Some(created_at) = parsed.created_at {
+            session.created_at = created_at;
+        }
+
+        let content = crate::handoff::render_transcript(&parsed.items);
+        let transcript = ImportedTranscript {
+            source: parsed.source.to_string(),
+            cwd: parsed.cwd,
+            items: parsed.items,
+            imported_at: chrono::Utc::now().timestamp(),
+        };
+        database.insert_imported_session(&session, &transcript, &content)?;
+        Ok(session)
+    })
+    .await?;
+
+    tracing::info!("Imported session {} into project {}", session.session_id, session.project_id);
+    Ok(session)
+}
+
+/// Thread items of a session created by `import_session`
+#[tauri::command]
+pub async fn get_imported_session_items(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<serde_json::Value>> {
+    validate_id(&session_id, "session_id")?;
+    state
+        .database
+        .get_imported_transcript(&session_id)?
+        .map(|transcript| transcript.items)
+        .ok_or(crate::Error::SessionNotFound(session_id))
+}
+
+/// Usage metrics per session plus totals by day and project
+///
+/// Filters are optional: `project_id` and `session_id` narrow the sessions,
+/// `since` keeps sessions created at or after that Unix timestamp. Token
+/// usage and active time cover turns run since analytics were added.
+#[tauri::command]
+pub async fn get_session_stats(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    session_id: Option<String>,
+    since: Option<i64>,
+) -> Result<SessionStatsReport> {
+    if let Some(project_id) = &project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    if let Some(session_id) = &session_id {
+        validate_id(session_id, "session_id")?;
     }
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let sessions =
+            database.get_session_stats(project_id.as_deref(), session_id.as_deref(), since)?;
+        Ok(session_stats::aggregate(sessions))
+    })
+    .await
+}
 
-    // Bonus for favorites
-    if session.is_favorite {
-        score += 5;
+/// Phase breakdown (queued, thinking, tools, patches) of a turn
+///
+/// Running turns report their timings so far with no `completedAt`; returns
+/// `None` for turns that ran before timings were recorded.
+#[tauri::command]
+pub async fn get_turn_timings(
+    state: State<'_, AppState>,
+    turn_id: String,
+) -> Result<Option<TurnTimings>> {
+    validate_id(&turn_id, "turn_id")?;
+    if let Some(timings) = state
+        .turn_timings
+        .snapshot(&turn_id, crate::global_state::unix_timestamp_millis())
+    {
+        return Ok(Some(timings));
     }
+    let database = state.database.clone();
 
-    score
+    crate::utils::spawn_blocking_io(move || database.get_turn_timings(&turn_id)).await
 }
 
-/// Search sessions across all projects with relevance scoring
+/// Default and maximum number of turn outcomes listed per session
+const DEFAULT_OUTCOME_LIMIT: usize = 50;
+const MAX_OUTCOME_LIMIT: usize = 1000;
+
+/// How a session's turns ended (completed, interrupted, error or
+/// needs-approval-timeout), newest first
 #[tauri::command]
-pub async fn search_sessions(
+pub async fn list_turn_outcomes(
     state: State<'_, AppState>,
-    query: String,
-    tags_filter: Option<Vec<String>>,
-    favorites_only: Option<bool>,
-) -> Result<Vec<SessionMetadata>> {
-    let projects = state.database.get_all_projects()?;
-    let mut all_sessions = Vec::new();
+    session_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<TurnOutcomeRecord>> {
+    validate_id(&session_id, "session_id")?;
+    let limit = limit.unwrap_or(DEFAULT_OUTCOME_LIMIT).clamp(1, MAX_OUTCOME_LIMIT);
+    let database = state.database.clone();
 
-    for project in projects {
-        let sessions = state.database.get_sessions_for_project(&project.id)?;
-        all_sessions.extend(sessions);
-    }
+    crate::utils::spawn_blocking_io(move || database.list_turn_outcomes(&session_id, limit)).await
+}
 
-    let query_lower = query.to_lowercase();
-
-    // Filter and score sessions - calculate score once per session
-    let mut scored_sessions: Vec<(SessionMetadata, i32)> = all_sessions
-        .into_iter()
-        .filter_map(|s| {
-            // Filter by tags first
-            if let Some(ref filter_tags) = tags_filter {
-                let session_tags = s.get_tags();
-                if !filter_tags.iter().all(|ft| session_tags.contains(ft)) {
-                    return None;
-                }
-            }
+/// Turns of a session the app exited in the middle of, as far as their
+/// transcript journals got, oldest first
+#[tauri::command]
+pub async fn list_recovered_turns(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Vec<RecoveredTurn>> {
+    validate_id(&session_id, "session_id")?;
+    let database = state.database.clone();
 
-            // Filter by favorites
-            if let Some(true) = favorites_only {
-                if !s.is_favorite {
-                    return None;
-                }
-            }
+    crate::utils::spawn_blocking_io(move || database.list_recovered_turns(&session_id)).await
+}
 
-            // Calculate score once and check if matches
-            let score = calculate_relevance_score(&s, &query_lower);
-            if score > 0 {
-                Some((s, score))
-            } else {
-                None
+/// Files the agent has changed in a session so far, with their diffs
+///
+/// Built from applied patches and files written by commands since the app
+/// started; `live-diff-updated` events carry later changes.
+#[tauri::command]
+pub async fn get_session_live_diff(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<LiveDiff> {
+    validate_id(&session_id, "session_id")?;
+    Ok(state.live_diffs.get(&session_id))
+}
+
+/// Everything that changed since a session started
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBaselineDiff {
+    /// HEAD when the session started
+    pub baseline_commit: String,
+    /// Unix seconds
+    pub recorded_at: i64,
+    /// HEAD now; differs from the baseline once commits were made
+    pub head_commit: Option<String>,
+    /// Working tree, including untracked files, against the baseline
+    pub diff: String,
+}
+
+/// Diff the session's working directory against the commit it started from
+///
+/// Commits made since then are included, so this answers what changed over
+/// the whole session. Returns `None` for sessions started before baselines
+/// were recorded or outside a git repository.
+#[tauri::command]
+pub async fn get_session_baseline_diff(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<SessionBaselineDiff>> {
+    validate_id(&session_id, "session_id")?;
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        let Some(baseline) = database.get_session_baseline(&session_id)? else {
+            return Ok(None);
+        };
+        let cwd = crate::utils::validate_and_canonicalize_path(&baseline.cwd)?;
+        let diff = crate::commands::projects::git_diff_against(&cwd, &baseline.commit_sha)?;
+        let head_commit = crate::commands::projects::resolve_baseline_commit(&cwd)?;
+        Ok(Some(SessionBaselineDiff {
+            baseline_commit: baseline.commit_sha,
+            recorded_at: baseline.recorded_at,
+            head_commit,
+            diff,
+        }))
+    })
+    .await
+}
+
+/// Forget a session's live diff, e.g. after its changes were committed
+#[tauri::command]
+pub async fn clear_session_live_diff(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<bool> {
+    validate_id(&session_id, "session_id")?;
+    Ok(state.live_diffs.clear(&session_id))
+}
+
+/// Emitted with a `SessionWorktreeStatus` when an archived session still
+/// has a worktree of its own, so the user can merge or delete it
+const WORKTREE_CLEANUP_EVENT: &str = "session-worktree-cleanup-offered";
+
+/// Result of `cleanup_session_worktree`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeCleanupReport {
+    pub session_id: String,
+    pub action: WorktreeCleanup,
+    /// Outcome of merging the branch back; on conflicts nothing is removed
+    pub merge: Option<GitMergeResult>,
+    /// The worktree and branch are gone
+    pub removed: bool,
+}
+
+/// Offer cleanup for the worktrees of sessions that were just archived
+async fn offer_worktree_cleanup(state: &AppState, session_ids: &[String]) {
+    for session_id in session_ids {
+        let worktree = match state.database.get_session_worktree(session_id) {
+            Ok(Some(worktree)) => worktree,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Failed to look up worktree of {}: {}", session_id, e);
+                continue;
             }
-        })
-        .collect();
-
-    // Sort by relevance score (descending), then by last_accessed_at (descending)
-    scored_sessions.sort_by(|a, b| {
-        // First compare by score (higher is better)
-        let score_cmp = b.1.cmp(&a.1);
-        if score_cmp != std::cmp::Ordering::Equal {
-            return score_cmp;
+        };
+        let status =
+            crate::utils::spawn_blocking_io(move || Ok(session_worktrees::status(worktree))).await;
+        if let Ok(status) = status {
+            state.events.emit(WORKTREE_CLEANUP_EVENT, status).await;
         }
-        // Then by last_accessed_at (more recent is better)
-        let a_time = a.0.last_accessed_at.unwrap_or(0);
-        let b_time = b.0.last_accessed_at.unwrap_or(0);
-        b_time.cmp(&a_time)
-    });
+    }
+}
 
-    // Extract just the sessions (without scores)
-    let result: Vec<SessionMetadata> = scored_sessions.into_iter().map(|(s, _)| s).collect();
+/// The worktree made for a session, with its state, if it has one
+#[tauri::command]
+pub async fn get_session_worktree(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<Option<SessionWorktreeStatus>> {
+    validate_id(&session_id, "session_id")?;
+    let Some(worktree) = state.database.get_session_worktree(&session_id)? else {
+        return Ok(None);
+    };
+    crate::utils::spawn_blocking_io(move || Ok(Some(session_worktrees::status(worktree)))).await
+}
+
+/// Merge a session's branch back into the project's current branch, or
+/// delete it, then remove the session's worktree
+///
+/// Merging commits whatever the worktree still has uncommitted first. A
+/// merge with conflicts is aborted and leaves the worktree and branch in
+/// place.
+#[tauri::command]
+pub async fn cleanup_session_worktree(
+    state: State<'_, AppState>,
+    session_id: String,
+    action: WorktreeCleanup,
+    message: Option<String>,
+) -> Result<WorktreeCleanupReport> {
+    validate_id(&session_id, "session_id")?;
+    let worktree = state
+        .database
+        .get_session_worktree(&session_id)?
+        .ok_or_else(|| Error::Other(format!("Session {session_id} has no worktree")))?;
+    let project = state
+        .database
+        .get_project(&worktree.project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(worktree.project_id.clone()))?;
+    let worktree_exists = Path::new(&worktree.path).is_dir();
+
+    let mut merge = None;
+    if action == WorktreeCleanup::Merge {
+        if worktree_exists {
+            let path = worktree.path.clone();
+            let commit_message = format!("Changes from Codex session {session_id}");
+            crate::utils::spawn_blocking_io(move || {
+                session_worktrees::commit_all(Path::new(&path), &commit_message)
+            })
+            .await?;
+        }
+        let message = message
+            .filter(|message| !message.trim().is_empty())
+            .unwrap_or_else(|| format!("Merge branch '{}'", worktree.branch));
+        let result =
+            git_merge_no_ff(project.path.clone(), worktree.branch.clone(), message).await?;
+        if !result.success {
+            return Ok(WorktreeCleanupReport {
+                session_id,
+                action,
+                merge: Some(result),
+                removed: false,
+            });
+        }
+        merge = Some(result);
+    }
+
+    if worktree_exists {
+        remove_worktree(project.path.clone(), worktree.path.clone()).await?;
+    }
+    let project_path = project.path.clone();
+    let branch = worktree.branch.clone();
+    crate::utils::spawn_blocking_io(move || {
+        let root = Path::new(&project_path);
+        session_worktrees::prune(root)?;
+        session_worktrees::delete_branch(root, &branch, action == WorktreeCleanup::Delete)
+    })
+    .await?;
+    state.database.delete_session_worktree(&session_id)?;
+    state.pending_work.invalidate();
+    tracing::info!(
+        "Cleaned up worktree {} of session {} ({:?})",
+        worktree.path,
+        session_id,
+        action
+    );
+
+    Ok(WorktreeCleanupReport {
+        session_id,
+        action,
+        merge,
+        removed: true,
+    })
+}
+
+/// Default and maximum number of session search results
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_SEARCH_LIMIT: usize = 500;
+
+/// Full-text search across all projects' sessions
+///
+/// Matches titles, first messages, tags and indexed conversation content,
+/// archived sessions included. Every term must match and the last term
+/// matches as a prefix. Results are ranked by relevance, boosted for titles
+/// starting with the query, recent use and sessions of `project_id` (the
+/// project searched from), and carry snippet and title highlight offsets.
+/// Sessions whose id, title, first message or tags contain the query as
+/// typed follow the ranked matches.
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, AppState>,
+    query: String,
+    tags_filter: Option<Vec<String>>,
+    favorites_only: Option<bool>,
+    project_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SessionSearchHit>> {
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+    let database = state.database.clone();
 
-    Ok(result)
+    crate::utils::spawn_blocking_io(move || {
+        database.search_sessions(
+            &query,
+            &tags_filter.unwrap_or_default(),
+            favorites_only.unwrap_or(false),
+            project_id.as_deref(),
+            limit,
+        )
+    })
+    .await
 }
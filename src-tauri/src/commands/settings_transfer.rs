@@ -0,0 +1,100 @@
+//! Settings transfer commands - export and import workspace settings
+
+use std::path::PathBuf;
+
+use serde_json::Value as JsonValue;
+use tauri::State;
+
+use crate::settings_transfer::{self, ImportSummary};
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Write global settings and allowlists to `path`, an absolute file path in
+/// an existing directory
+///
+/// Projects (paths, names and settings) are included only when
+/// `include_projects` is set. `renderer` carries renderer-owned settings such
+/// as the prompt library and keybindings, and is stored as given.
+#[tauri::command]
+pub async fn export_settings(
+    state: State<'_, AppState>,
+    path: String,
+    include_projects: Option<bool>,
+    renderer: Option<JsonValue>,
+) -> Result<String> {
+    let target = PathBuf::from(&path);
+    let parent = target.parent().filter(|parent| parent.is_dir());
+    if !target.is_absolute() || parent.is_none() || target.is_dir() {
+        return Err(Error::InvalidPath(format!(
+            "Export path must be an absolute file path in an existing directory: {path}"
+        )));
+    }
+    let global = state
+        .global_state
+        .read(|global| global.portable_sections())?;
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let bundle = settings_transfer::export(
+            &database,
+            global,
+            include_projects.unwrap_or(false),
+            renderer,
+        )?;
+        settings_transfer::write(&target, &bundle)
+    })
+    .await?;
+
+    tracing::info!("Exported settings to {}", path);
+    Ok(path)
+}
+
+/// Merge a settings file written by `export_settings` into this workspace
+///
+/// Global sections in the file replace the current ones; projects and
+/// allowlist patterns are added to what is there. The summary returns the
+/// file's renderer-owned settings for the renderer to restore.
+#[tauri::command]
+pub async fn import_settings(state: State<'_, AppState>, path: String) -> Result<ImportSummary> {
+    let source = PathBuf::from(&path);
+    if !source.is_absolute() || !source.is_file() {
+        return Err(Error::InvalidPath(format!(
+            "Import path must be an absolute path to a file: {path}"
+        )));
+    }
+    let mut bundle =
+        crate::utils::spawn_blocking_io(move || settings_transfer::read(&source)).await?;
+
+    // Global sections go first: an unreadable one fails the import untouched
+    let global = std::mem::take(&mut bundle.global);
+    let mut applied = Ok(Vec::new());
+    state
+        .global_state
+        .update(|current| applied = current.apply_portable_sections(&global));
+    let global_sections = applied?;
+    state.global_state.flush()?;
+    let masking = state.global_state.read(|global| global.secret_masking.clone());
+    if let Err(err) = crate::secret_mask::configure(&masking) {
+        tracing::warn!("Ignoring imported secret masking patterns: {}", err);
+    }
+    crate::i18n::set(state.global_state.read(|global| global.locale.locale));
+    if let Err(err) = crate::global_shortcuts::register_saved(&state.app_handle) {
+        tracing::warn!("Failed to register imported shortcuts: {}", err);
+    }
+
+    let database = state.database.clone();
+    let mut summary =
+        crate::utils::spawn_blocking_io(move || settings_transfer::import(&database, bundle))
+            .await?;
+    summary.global_sections = global_sections;
+
+    tracing::info!(
+        "Imported settings from {}: {} sections, {} projects added, {} updated, {} allowlist patterns",
+        path,
+        summary.global_sections.len(),
+        summary.projects_added,
+        summary.projects_updated,
+        summary.allowlist_patterns
+    );
+    Ok(summary)
+}
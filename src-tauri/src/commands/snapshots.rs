@@ -5,8 +5,13 @@ use std::path::Path;
 use tauri::{Manager, State};
 
 use crate::database::Snapshot;
+use crate::global_state::SnapshotSettings;
+use crate::snapshots::{
+    CompactionReport, FileRestoreReport, PatchApplyReport, RetentionPolicy, SnapshotDiff,
+    SnapshotPatch, SnapshotSize, SnapshotStorageUsage,
+};
 use crate::state::AppState;
-use crate::Result;
+use crate::{Error, Result};
 
 /// Get the snapshots directory under the app data dir
 fn get_snapshots_dir(state: &AppState) -> Option<std::path::PathBuf> {
@@ -18,6 +23,57 @@ fn get_snapshots_dir(state: &AppState) -> Option<std::path::PathBuf> {
         .map(|d| d.join("snapshots"))
 }
 
+/// Prune snapshots to the configured retention policy after taking one
+///
+/// Failures are logged; the new snapshot is kept either way. Blocking.
+fn prune_snapshots(state: &AppState) {
+    let policy = state
+        .global_state
+        .read(|global| RetentionPolicy::from(&global.snapshots));
+    let snapshots_dir = get_snapshots_dir(state);
+    if let Err(e) =
+        crate::snapshots::apply_retention(&state.database, &policy, snapshots_dir.as_deref())
+    {
+        tracing::warn!("Failed to prune old snapshots: {}", e);
+    }
+}
+
+/// Snapshot a thread's project before a turn starts, if the project has
+/// `autoSnapshot` on
+///
+/// Failures are logged; the turn starts either way.
+pub(crate) async fn snapshot_before_turn(state: &AppState, thread_id: &str) {
+    let project = state
+        .database
+        .get_session_by_id(thread_id)
+        .ok()
+        .flatten()
+        .and_then(|session| state.database.get_project(&session.project_id).ok().flatten());
+    let Some(project) = project else {
+        return;
+    };
+    if project.settings().auto_snapshot != Some(true) {
+        return;
+    }
+
+    let database = state.database.clone();
+    let session_id = thread_id.to_string();
+    let snapshots_dir = get_snapshots_dir(state);
+    let result = crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::create_turn_snapshot(
+            &database,
+            &session_id,
+            Path::new(&project.path),
+            snapshots_dir.as_deref(),
+        )
+    })
+    .await;
+    match result {
+        Ok(_) => prune_snapshots(state),
+        Err(e) => tracing::warn!("Failed to snapshot project before turn on {}: {}", thread_id, e),
+    }
+}
+
 /// Create a snapshot for a session
 #[tauri::command]
 pub async fn create_snapshot(
@@ -27,23 +83,214 @@ pub async fn create_snapshot(
 ) -> Result<Snapshot> {
     let path = Path::new(&project_path);
     let snapshots_dir = get_snapshots_dir(&state);
-    crate::snapshots::create_snapshot(
+    let snapshot = crate::snapshots::create_snapshot(
         &state.database,
         &session_id,
         path,
         snapshots_dir.as_deref(),
-    )
+    )?;
+    prune_snapshots(&state);
+    Ok(snapshot)
 }
 
 /// Revert to a snapshot
+///
+/// Returns the snapshot of the state before the revert, unless
+/// `backup_before_revert` is turned off.
 #[tauri::command]
 pub async fn revert_to_snapshot(
     state: State<'_, AppState>,
     snapshot_id: String,
     project_path: String,
-) -> Result<()> {
+) -> Result<Option<Snapshot>> {
     let path = Path::new(&project_path);
-    crate::snapshots::revert_to_snapshot(&state.database, &snapshot_id, path)
+    let backup = state
+        .global_state
+        .read(|global| global.snapshots.backup_before_revert);
+    let snapshots_dir = get_snapshots_dir(&state);
+    let backup = crate::snapshots::revert_to_snapshot(
+        &state.database,
+        &snapshot_id,
+        path,
+        backup,
+        snapshots_dir.as_deref(),
+    )?;
+    if backup.is_some() {
+        prune_snapshots(&state);
+    }
+    Ok(backup)
+}
+
+/// Restore only `paths` from a snapshot; with `dry_run`, report what
+/// would change without touching anything
+///
+/// Takes a pre-revert snapshot first unless `backup_before_revert` is off,
+/// so `undo_last_revert` applies.
+#[tauri::command]
+pub async fn restore_files_from_snapshot(
+    state: State<'_, AppState>,
+    snapshot_id: String,
+    project_path: String,
+    paths: Vec<String>,
+    dry_run: Option<bool>,
+) -> Result<FileRestoreReport> {
+    let database = state.database.clone();
+    let backup = state
+        .global_state
+        .read(|global| global.snapshots.backup_before_revert);
+    let snapshots_dir = get_snapshots_dir(&state);
+    let report = crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::restore_files_from_snapshot(
+            &database,
+            &snapshot_id,
+            Path::new(&project_path),
+            &paths,
+            dry_run.unwrap_or(false),
+            backup,
+            snapshots_dir.as_deref(),
+        )
+    })
+    .await?;
+    if report.backup.is_some() {
+        prune_snapshots(&state);
+    }
+    Ok(report)
+}
+
+/// Undo the session's most recent revert, returning the restored snapshot
+#[tauri::command]
+pub async fn undo_last_revert(
+    state: State<'_, AppState>,
+    session_id: String,
+    project_path: String,
+) -> Result<Snapshot> {
+    let path = Path::new(&project_path);
+    let snapshots_dir = get_snapshots_dir(&state);
+    crate::snapshots::undo_last_revert(
+        &state.database,
+        &session_id,
+        path,
+        snapshots_dir.as_deref(),
+    )
+}
+
+/// Diff two snapshots of a project, from `snapshot_a` to `snapshot_b`
+#[tauri::command]
+pub async fn diff_snapshots(
+    state: State<'_, AppState>,
+    snapshot_a: String,
+    snapshot_b: String,
+    project_path: String,
+) -> Result<SnapshotDiff> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::diff_snapshots(&database, &snapshot_a, &snapshot_b, Path::new(&project_path))
+    })
+    .await
+}
+
+/// Diff a snapshot to the working tree, previewing what a revert would undo
+#[tauri::command]
+pub async fn diff_snapshot_to_worktree(
+    state: State<'_, AppState>,
+    snapshot_id: String,
+    project_path: String,
+) -> Result<SnapshotDiff> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::diff_snapshot_to_worktree(&database, &snapshot_id, Path::new(&project_path))
+    })
+    .await
+}
+
+/// Export a snapshot's changes on top of its base as a patch for `git apply`
+///
+/// The base is the commit a git snapshot was taken on, or
+/// `base_snapshot_id`, which file backups require. With `path` (absolute,
+/// in an existing directory) the patch is also written there.
+#[tauri::command]
+pub async fn export_snapshot_patch(
+    state: State<'_, AppState>,
+    snapshot_id: String,
+    project_path: String,
+    base_snapshot_id: Option<String>,
+    path: Option<String>,
+) -> Result<SnapshotPatch> {
+    if let Some(path) = &path {
+        let target = Path::new(path);
+        let parent = target.parent().filter(|parent| parent.is_dir());
+        if !target.is_absolute() || parent.is_none() || target.is_dir() {
+            return Err(Error::InvalidPath(format!(
+                "Patch path must be an absolute file path in an existing directory: {path}"
+            )));
+        }
+    }
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        let patch = crate::snapshots::export_snapshot_patch(
+            &database,
+            &snapshot_id,
+            Path::new(&project_path),
+            base_snapshot_id.as_deref(),
+        )?;
+        if let Some(path) = &path {
+            crate::utils::atomic_write(Path::new(path), patch.patch.as_bytes())?;
+            tracing::info!("Wrote patch of snapshot {} to {}", patch.snapshot_id, path);
+        }
+        Ok(patch)
+    })
+    .await
+}
+
+/// Apply a snapshot's patch to another worktree or directory; with
+/// `dry_run`, only check that it applies
+///
+/// A patch that does not apply cleanly leaves the target untouched and is
+/// reported in the result.
+#[tauri::command]
+pub async fn apply_snapshot_to_path(
+    state: State<'_, AppState>,
+    snapshot_id: String,
+    project_path: String,
+    target_path: String,
+    base_snapshot_id: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<PatchApplyReport> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::apply_snapshot_to_path(
+            &database,
+            &snapshot_id,
+            Path::new(&project_path),
+            base_snapshot_id.as_deref(),
+            Path::new(&target_path),
+            dry_run.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+/// Get snapshot settings
+#[tauri::command]
+pub async fn get_snapshot_settings(state: State<'_, AppState>) -> Result<SnapshotSettings> {
+    Ok(state.global_state.read(|global| global.snapshots.clone()))
+}
+
+/// Update snapshot settings
+#[tauri::command]
+pub async fn set_snapshot_settings(
+    state: State<'_, AppState>,
+    settings: SnapshotSettings,
+) -> Result<SnapshotSettings> {
+    if settings.max_age_days == Some(0) || settings.max_disk_bytes == Some(0) {
+        return Err(Error::Other(
+            "Snapshot age and disk limits must be above zero".to_string(),
+        ));
+    }
+    state.global_state.update(|global| global.snapshots = settings.clone());
+    state.global_state.flush()?;
+    prune_snapshots(&state);
+    Ok(settings)
 }
 
 /// List snapshots for a session
@@ -55,18 +302,56 @@ pub async fn list_snapshots(
     state.database.get_snapshots_for_session(&session_id)
 }
 
-/// Clean up old snapshots by age
+/// Clean up old snapshots by age, along with their file backups
 #[tauri::command]
 pub async fn cleanup_old_snapshots_by_age(
     state: State<'_, AppState>,
     max_age_days: Option<i64>,
 ) -> Result<String> {
-    let days = max_age_days.unwrap_or(30); // Default 30 days
-    let count = state.database.cleanup_snapshots_older_than(days)?;
+    let days = max_age_days.unwrap_or(30).max(0); // Default 30 days
+    let policy = RetentionPolicy {
+        max_age_secs: Some(days.saturating_mul(86_400)),
+        ..RetentionPolicy::default()
+    };
+    let database = state.database.clone();
+    let snapshots_dir = get_snapshots_dir(&state);
+    let count = crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::apply_retention(&database, &policy, snapshots_dir.as_deref())
+    })
+    .await?;
 
     Ok(format!("Deleted {count} old snapshots (older than {days} days)"))
 }
 
+/// Report how much space snapshots take
+#[tauri::command]
+pub async fn get_snapshot_storage_usage(state: State<'_, AppState>) -> Result<SnapshotStorageUsage> {
+    let database = state.database.clone();
+    let snapshots_dir = get_snapshots_dir(&state);
+    crate::utils::spawn_blocking_io(move || {
+        crate::snapshots::storage_usage(&database, snapshots_dir.as_deref())
+    })
+    .await
+}
+
+/// Report each snapshot's logical and on-disk size
+#[tauri::command]
+pub async fn get_snapshot_sizes(state: State<'_, AppState>) -> Result<Vec<SnapshotSize>> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || crate::snapshots::snapshot_sizes(&database)).await
+}
+
+/// Move snapshot files into the shared object store and delete files no
+/// snapshot references any more
+#[tauri::command]
+pub async fn compact_snapshot_storage(state: State<'_, AppState>) -> Result<CompactionReport> {
+    let database = state.database.clone();
+    let snapshots_dir = get_snapshots_dir(&state)
+        .ok_or_else(|| Error::Other("Snapshots directory is unavailable".to_string()))?;
+    crate::utils::spawn_blocking_io(move || crate::snapshots::compact(&database, &snapshots_dir))
+        .await
+}
+
 /// Clean up old snapshots for a specific session
 #[tauri::command]
 pub async fn cleanup_session_snapshots(
@@ -26,6 +26,12 @@ pub struct LogTailResponse {
     pub truncated: bool,
 }
 
+/// Every action in the registry, for the renderer.
+#[tauri::command]
+pub fn list_actions() -> Vec<crate::actions::Action> {
+    crate::actions::list()
+}
+
 /// Get app-specific paths for diagnostics.
 #[tauri::command]
 pub fn get_app_paths(app: tauri::AppHandle) -> AppPaths {
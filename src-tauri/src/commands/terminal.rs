@@ -1,9 +1,17 @@
 use serde::Serialize;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::Command;
-use tauri::{Emitter, Window};
+use tauri::{Emitter, State, Window};
 
+use crate::database::{Database, Project};
+use crate::global_state::TerminalOutputSettings;
+use crate::sandbox::{self, SandboxSupport};
+use crate::state::AppState;
+use crate::terminal_output::{
+    self, ScrollbackPage, TerminalScrollback, TerminalStream, DEFAULT_PAGE_LINES, MAX_PAGE_LINES,
+};
 use crate::Result;
 
 /// Maximum allowed command length in characters
@@ -16,17 +24,28 @@ const COMMAND_TIMEOUT_SECS: u64 = 30;
 #[serde(rename_all = "camelCase")]
 pub struct TerminalOutput {
     pub exit_code: Option<i32>,
+    /// Scrollback run holding the whole output
+    pub run_id: String,
+    /// Lines cut to `max_line_chars` in the streamed events
+    pub truncated_lines: usize,
 }
 
 /// Execute a shell command in the given working directory.
 /// Streams output via events and returns the exit code.
 ///
-/// Security: validates command length and cwd, enforces execution timeout.
+/// Security: validates command length and cwd, enforces execution timeout,
+/// and masks secrets in the streamed output unless the terminal output
+/// settings turn that off. Streamed lines are cut to the configured length;
+/// `get_terminal_scrollback` returns them whole.
+/// Runs in the sandbox and with the environment variables of `project_id`,
+/// or of the project containing `cwd` when no project is given.
 #[tauri::command]
 pub async fn execute_terminal_command(
+    state: State<'_, AppState>,
     window: Window,
     cwd: String,
     command: String,
+    project_id: Option<String>,
 ) -> Result<TerminalOutput> {
     // Validate command length
     if command.len() > MAX_COMMAND_LENGTH {
@@ -58,9 +77,33 @@ pub async fn execute_terminal_command(
         "-c"
     };
 
-    let mut child = Command::new(shell)
-        .arg(shell_arg)
-        .arg(&command)
+    let project = match project_id {
+        Some(project_id) => {
+            crate::commands::projects::validate_id(&project_id, "project_id")?;
+            Some(
+                state
+                    .database
+                    .get_project(&project_id)?
+                    .ok_or(crate::Error::ProjectNotFound(project_id))?,
+            )
+        }
+        None => project_containing(&state.database, cwd_path)?,
+    };
+    let mut argv = vec![shell.to_string(), shell_arg.to_string(), command.clone()];
+    let mut env = Vec::new();
+    if let Some(project) = &project {
+        argv = sandbox::wrap_for_project(project, argv)?;
+        let database = state.database.clone();
+        let project_id = project.id.clone();
+        env = crate::utils::spawn_blocking_io(move || {
+            crate::project_env::load(&database, &project_id)
+        })
+        .await?;
+    }
+
+    let mut child = Command::new(&argv[0])
+        .args(&argv[1..])
+        .envs(env)
         .current_dir(&cwd)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -70,38 +113,44 @@ pub async fn execute_terminal_command(
     let stdout = child.stdout.take();
     let stderr = child.stderr.take();
 
+    let settings = state.global_state.read(|g| g.terminal_output.clone());
+    let scrollback = state.terminal_scrollback.clone();
+    let run_id = scrollback.start();
+    let output = OutputSink {
+        window: window.clone(),
+        scrollback: scrollback.clone(),
+        run_id: run_id.clone(),
+        settings,
+    };
+
     // Stream stdout and stderr concurrently
-    let stdout_window = window.clone();
+    let stdout_sink = output.clone();
     let stdout_handle = tokio::spawn(async move {
-        if let Some(stdout) = stdout {
-            let mut reader = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                let _ = stdout_window.emit("terminal:stdout", &line);
-            }
+        match stdout {
+            Some(stdout) => stdout_sink.stream(stdout, TerminalStream::Stdout).await,
+            None => 0,
         }
     });
 
-    let stderr_window = window.clone();
+    let stderr_sink = output.clone();
     let stderr_handle = tokio::spawn(async move {
-        if let Some(stderr) = stderr {
-            let mut reader = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                let _ = stderr_window.emit("terminal:stderr", &line);
-            }
+        match stderr {
+            Some(stderr) => stderr_sink.stream(stderr, TerminalStream::Stderr).await,
+            None => 0,
         }
     });
 
     // Wait with timeout for both streams and process exit
     let timeout_duration = std::time::Duration::from_secs(COMMAND_TIMEOUT_SECS);
     let result = tokio::time::timeout(timeout_duration, async {
-        let _ = stdout_handle.await;
-        let _ = stderr_handle.await;
-        child.wait().await
+        let truncated_lines = stdout_handle.await.unwrap_or(0) + stderr_handle.await.unwrap_or(0);
+        child.wait().await.map(|status| (status, truncated_lines))
     })
     .await;
+    scrollback.finish(&run_id);
 
-    let exit_code = match result {
-        Ok(Ok(status)) => status.code(),
+    let (exit_code, truncated_lines) = match result {
+        Ok(Ok((status, truncated_lines))) => (status.code(), truncated_lines),
         Ok(Err(e)) => {
             return Err(crate::Error::Other(format!(
                 "Failed to wait for command: {}",
@@ -111,7 +160,7 @@ pub async fn execute_terminal_command(
         Err(_) => {
             // Timeout: kill the process
             let _ = child.kill().await;
-            let _ = window.emit("terminal:stderr", "Command timed out after 30 seconds");
+            output.send(TerminalStream::Stderr, "Command timed out after 30 seconds");
             let _ = window.emit("terminal:exit", Option::<i32>::None);
             return Err(crate::Error::Other(format!(
                 "Command timed out after {} seconds",
@@ -122,5 +171,104 @@ pub async fn execute_terminal_command(
 
     let _ = window.emit("terminal:exit", exit_code);
 
-    Ok(TerminalOutput { exit_code })
+    Ok(TerminalOutput {
+        exit_code,
+        run_id,
+        truncated_lines,
+    })
+}
+
+/// Where a run's output goes: terminal events and the scrollback
+#[derive(Clone)]
+struct OutputSink {
+    window: Window,
+    scrollback: Arc<TerminalScrollback>,
+    run_id: String,
+    settings: TerminalOutputSettings,
+}
+
+impl OutputSink {
+    /// Send each line of `reader`; returns how many were truncated
+    async fn stream<R: AsyncRead + Unpin>(&self, reader: R, stream: TerminalStream) -> usize {
+        let mut truncated = 0;
+        let mut reader = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            if self.send(stream, &line) {
+                truncated += 1;
+            }
+        }
+        truncated
+    }
+
+    /// Emit `line` masked and cut to length and keep it whole in the
+    /// scrollback; returns whether it was cut
+    fn send(&self, stream: TerminalStream, line: &str) -> bool {
+        let line = terminal_output::redact(line, &self.settings).into_owned();
+        let shown = terminal_output::truncate_line(&line, self.settings.max_line_chars as usize);
+        let truncated = matches!(shown, std::borrow::Cow::Owned(_));
+        let _ = self.window.emit(stream.event(), shown.as_ref());
+        self.scrollback.push(&self.run_id, stream, line);
+        truncated
+    }
+}
+
+/// Whole lines of a terminal run, `limit` at a time
+///
+/// The scrollback keeps the most recent runs only; a forgotten run is an
+/// error.
+#[tauri::command]
+pub async fn get_terminal_scrollback(
+    state: State<'_, AppState>,
+    run_id: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<ScrollbackPage> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LINES).clamp(1, MAX_PAGE_LINES);
+    state
+        .terminal_scrollback
+        .page(&run_id, offset.unwrap_or(0), limit)
+        .ok_or_else(|| crate::Error::Other(format!("No scrollback for terminal run {run_id}")))
+}
+
+/// Get the saved terminal output settings
+#[tauri::command]
+pub async fn get_terminal_output_settings(
+    state: State<'_, AppState>,
+) -> Result<TerminalOutputSettings> {
+    Ok(state.global_state.read(|g| g.terminal_output.clone()))
+}
+
+/// Update the saved terminal output settings; applies to commands started
+/// afterwards
+#[tauri::command]
+pub async fn set_terminal_output_settings(
+    state: State<'_, AppState>,
+    settings: TerminalOutputSettings,
+) -> Result<TerminalOutputSettings> {
+    terminal_output::validate_settings(&settings)?;
+    state
+        .global_state
+        .update(|g| g.terminal_output = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Which sandbox restrictions this system can enforce for project commands
+#[tauri::command]
+pub async fn get_sandbox_support() -> Result<SandboxSupport> {
+    crate::utils::spawn_blocking_io(|| Ok(sandbox::support())).await
+}
+
+/// The project whose directory most closely contains `path`
+fn project_containing(database: &Database, path: &std::path::Path) -> Result<Option<Project>> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    Ok(database
+        .get_all_projects()?
+        .into_iter()
+        .filter(|project| {
+            let root = std::path::Path::new(&project.path);
+            let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+            path.starts_with(root)
+        })
+        .max_by_key(|project| project.path.len()))
 }
@@ -3,15 +3,24 @@
 use base64::{engine::general_purpose::STANDARD, Engine as _};
 use serde_json::Value as JsonValue;
 use std::io::Write;
+use std::sync::Arc;
 use tauri::State;
 
 use crate::app_server::ipc_bridge::{
-    ApprovalDecision, ApprovalResponseResult, ThreadListParams, ThreadListResponse,
+    ApprovalDecision, ApprovalResponseResult, ThreadArchiveParams, ThreadInfo, ThreadListParams,
+    ThreadListResponse,
     ThreadResumeParams, ThreadResumeResponse, ThreadStartParams, ThreadStartResponse,
-    TurnInterruptParams, TurnStartParams, TurnStartResponse, UserInput, SandboxPolicy,
+    TurnInfo, TurnInterruptParams, TurnStartParams, TurnStartResponse, UserInput, SandboxPolicy,
 };
-use crate::database::SessionMetadata;
+use crate::app_server::ActiveTurn;
+use crate::commands::projects::validate_relative_project_path;
+use crate::database::{
+    Database, ProjectSettings, SessionBaseline, SessionMetadata, SessionWorktree,
+};
+use crate::global_state::{TurnBudget, TurnConcurrencySettings};
+use crate::session_worktrees;
 use crate::state::AppState;
+use crate::utils::resolve_project_path;
 use crate::{Error, Result};
 
 fn parse_sandbox_policy(policy: Option<String>) -> Option<SandboxPolicy> {
@@ -31,7 +40,173 @@ fn parse_sandbox_policy(policy: Option<String>) -> Option<SandboxPolicy> {
     }
 }
 
+/// Reasoning efforts accepted as project defaults
+const REASONING_EFFORTS: &[&str] = &["none", "minimal", "low", "medium", "high", "xhigh"];
+
+/// Codex config key that sets a thread's reasoning effort
+const REASONING_EFFORT_CONFIG_KEY: &str = "model_reasoning_effort";
+
+/// Options a project sets for its new threads
+///
+/// `None` leaves the option to the Codex configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadOptions {
+    pub model: Option<String>,
+    pub reasoning_effort: Option<String>,
+    pub sandbox: Option<String>,
+    pub approval_policy: Option<String>,
+}
+
+impl ThreadOptions {
+    /// The project's defaults; unrecognized values are ignored
+    pub fn from_project(settings: &ProjectSettings) -> Self {
+        Self {
+            model: settings
+                .model
+                .as_deref()
+                .map(str::trim)
+                .filter(|model| !model.is_empty())
+                .map(str::to_string),
+            reasoning_effort: settings
+                .reasoning_effort
+                .as_deref()
+                .map(|effort| match normalize_option(effort).as_str() {
+                    "x-high" => "xhigh".to_string(),
+                    effort => effort.to_string(),
+                })
+                .filter(|effort| REASONING_EFFORTS.contains(&effort.as_str())),
+            sandbox: settings
+                .sandbox_mode
+                .as_deref()
+                .map(normalize_option)
+                .filter(|mode| parse_sandbox_policy(Some(mode.clone())).is_some())
+                .or_else(|| {
+                    // Keep the agent's own commands within the project's command sandbox
+                    settings
+                        .sandbox_profile
+                        .as_ref()
+                        .and_then(|profile| profile.codex_sandbox_mode())
+                        .map(str::to_string)
+                }),
+            approval_policy: settings
+                .ask_for_approval
+                .as_deref()
+                .map(|policy| match normalize_option(policy).as_str() {
+                    "unless-trusted" => "untrusted".to_string(),
+                    policy => policy.to_string(),
+                })
+                .filter(|policy| {
+                    matches!(policy.as_str(), "on-request" | "on-failure" | "never" | "untrusted")
+                }),
+        }
+    }
+
+    /// Fill in what `params` leaves unset (or empty) from these options
+    fn apply_to(&self, params: &mut ThreadStartParams) {
+        self.fill(
+            &mut params.model,
+            &mut params.sandbox,
+            &mut params.approval_policy,
+            &mut params.config,
+        );
+    }
+
+    /// Like `apply_to`, for loading an existing thread again
+    fn apply_to_resume(&self, params: &mut ThreadResumeParams) {
+        self.fill(
+            &mut params.model,
+            &mut params.sandbox,
+            &mut params.approval_policy,
+            &mut params.config,
+        );
+    }
+
+    fn fill(
+        &self,
+        model: &mut Option<String>,
+        sandbox: &mut Option<String>,
+        approval_policy: &mut Option<String>,
+        config: &mut Option<JsonValue>,
+    ) {
+        fn fill(value: &mut Option<String>, default: &Option<String>) {
+            if value.as_deref().map(str::trim).unwrap_or_default().is_empty() {
+                *value = default.clone();
+            }
+        }
+        fill(model, &self.model);
+        fill(sandbox, &self.sandbox);
+        fill(approval_policy, &self.approval_policy);
+
+        let Some(effort) = &self.reasoning_effort else {
+            return;
+        };
+        let config = config.get_or_insert_with(|| JsonValue::Object(Default::default()));
+        if let Some(config) = config.as_object_mut() {
+            config
+                .entry(REASONING_EFFORT_CONFIG_KEY)
+                .or_insert_with(|| JsonValue::String(effort.clone()));
+        }
+    }
+}
+
+/// `workspaceWrite`, `workspace_write` and `Workspace-Write` all become
+/// `workspace-write`, as the frontend normalizes them
+fn normalize_option(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 4);
+    let mut prev_lower = false;
+    for c in value.trim().chars() {
+        if c.is_ascii_uppercase() && prev_lower {
+            out.push('-');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        out.push(if c == '_' { '-' } else { c.to_ascii_lowercase() });
+    }
+    out
+}
+
+/// Resolve a project subdirectory to use as the agent cwd.
+///
+/// The subpath must be relative, stay inside the project root (following the
+/// project's symlink policy) and point at an existing directory. Returns the
+/// absolute cwd and the normalized subpath to persist.
+fn resolve_thread_subpath(
+    state: &AppState,
+    project_id: &str,
+    subpath: &str,
+) -> Result<(String, String)> {
+    let project = state
+        .database
+        .get_project(project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.to_string()))?;
+
+    let normalized = validate_relative_project_path(subpath.trim())?;
+    let normalized = normalized.trim_end_matches('/').to_string();
+
+    let project_root = std::path::Path::new(&project.path)
+        .canonicalize()
+        .map_err(|_| {
+            Error::InvalidPath(format!("Project path does not exist: {}", project.path))
+        })?;
+    let symlink_policy = project.settings().symlink_policy.unwrap_or_default();
+    let resolved = resolve_project_path(&project_root, &normalized, symlink_policy)?;
+
+    if !resolved.is_dir() {
+        return Err(Error::InvalidPath(format!(
+            "Subpath is not a directory: {}",
+            normalized
+        )));
+    }
+
+    Ok((resolved.to_string_lossy().to_string(), normalized))
+}
+
 /// Start a new thread
+///
+/// `subpath` optionally targets a subdirectory of the project (e.g. a package
+/// in a monorepo) as the agent cwd; the session stays attached to the project.
+/// With the project's `worktreePerSession` on, the thread runs in a new
+/// worktree and branch of its own instead of the project root.
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 pub async fn start_thread(
@@ -44,12 +219,30 @@ pub async fn start_thread(
     base_instructions: Option<String>,
     developer_instructions: Option<String>,
     config: Option<JsonValue>,
+    subpath: Option<String>,
 ) -> Result<ThreadStartResponse> {
-    // Ensure app-server is running
-    state.start_app_server().await?;
+    // Validate the subpath before spinning anything up
+    let subpath = subpath.filter(|s| !s.trim().is_empty());
+    let (cwd, cwd_subpath) = match subpath {
+        Some(subpath) => {
+            let (cwd, normalized) = resolve_thread_subpath(&state, &project_id, &subpath)?;
+            (cwd, Some(normalized))
+        }
+        None => (cwd, None),
+    };
+
+    let worktree = create_session_worktree(&state, &project_id).await?;
+    let cwd = match (&worktree, &cwd_subpath) {
+        (Some(worktree), Some(subpath)) => std::path::Path::new(&worktree.path)
+            .join(subpath)
+            .to_string_lossy()
+            .to_string(),
+        (Some(worktree), None) => worktree.path.clone(),
+        (None, _) => cwd,
+    };
 
     let params = ThreadStartParams {
-        cwd: Some(cwd.clone()),
+        cwd: Some(cwd),
         model,
         model_provider: None,
         sandbox,
@@ -59,24 +252,161 @@ pub async fn start_thread(
         config,
     };
 
-    let mut guard = state.app_server.write().await;
-    let server = guard
-        .as_mut()
-        .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
-    let response: ThreadStartResponse = server.send_request("thread/start", params).await?;
+    let result = start_thread_with_params(&state, &project_id, cwd_subpath, params).await;
+    if let Some(mut worktree) = worktree {
+        match &result {
+            Ok(response) => {
+                worktree.session_id = response.thread.id.clone();
+                if let Err(e) = state.database.insert_session_worktree(&worktree) {
+                    tracing::warn!("Failed to record session worktree: {}", e);
+                }
+            }
+            Err(_) => discard_session_worktree(&state, &worktree).await,
+        }
+    }
+    result
+}
+
+/// Create a worktree and branch for a new thread when the project has
+/// `worktreePerSession` on; the session ID is filled in once the thread
+/// has started
+async fn create_session_worktree(
+    state: &AppState,
+    project_id: &str,
+) -> Result<Option<SessionWorktree>> {
+    let Some(project) = state.database.get_project(project_id)? else {
+        return Ok(None);
+    };
+    let settings = project.settings();
+    if settings.worktree_per_session != Some(true) {
+        return Ok(None);
+    }
+
+    let project_name = project.display_name.clone().unwrap_or_else(|| {
+        std::path::Path::new(&project.path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let date = chrono::Local::now().format("%Y%m%d").to_string();
+    let branch = session_worktrees::branch_name(
+        settings.worktree_branch_template.as_deref(),
+        &project_name,
+        &id,
+        &date,
+    );
+    let project_path = project.path.clone();
+    let base_branch = crate::utils::spawn_blocking_io(move || {
+        Ok(session_worktrees::current_branch(std::path::Path::new(&project_path)))
+    })
+    .await?;
+
+    let info =
+        crate::commands::projects::create_worktree(project.path.clone(), branch, None).await?;
+    tracing::info!("Created worktree {} for a new session", info.path);
+    Ok(Some(SessionWorktree {
+        session_id: String::new(),
+        project_id: project.id,
+        path: info.path,
+        branch: info.branch,
+        base_branch,
+        base_commit: Some(info.head_commit).filter(|commit| !commit.is_empty()),
+        created_at: crate::global_state::unix_timestamp_secs(),
+    }))
+}
+
+/// Remove the worktree made for a thread that failed to start
+async fn discard_session_worktree(state: &AppState, worktree: &SessionWorktree) {
+    let Ok(Some(project)) = state.database.get_project(&worktree.project_id) else {
+        return;
+    };
+    if let Err(e) =
+        crate::commands::projects::remove_worktree(project.path.clone(), worktree.path.clone())
+            .await
+    {
+        tracing::warn!("Failed to remove worktree {}: {}", worktree.path, e);
+        return;
+    }
+    let branch = worktree.branch.clone();
+    let result = crate::utils::spawn_blocking_io(move || {
+        session_worktrees::delete_branch(std::path::Path::new(&project.path), &branch, true)
+    })
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to delete branch {}: {}", worktree.branch, e);
+    }
+}
+
+/// Options a new thread in the project gets when `start_thread` is not
+/// given them explicitly
+#[tauri::command]
+pub async fn get_effective_thread_options(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ThreadOptions> {
+    crate::commands::projects::validate_id(&project_id, "project_id")?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+    Ok(ThreadOptions::from_project(&project.settings()))
+}
+
+/// Start a thread via the app-server and record session metadata for the project
+pub(crate) async fn start_thread_with_params(
+    state: &AppState,
+    project_id: &str,
+    cwd_subpath: Option<String>,
+    params: ThreadStartParams,
+) -> Result<ThreadStartResponse> {
+    let mut params = params;
+    if !project_id.is_empty() {
+        if let Some(project) = state.database.get_project(project_id)? {
+            ThreadOptions::from_project(&project.settings()).apply_to(&mut params);
+            let database = state.database.clone();
+            let project_id = project.id.clone();
+            let env = crate::utils::spawn_blocking_io(move || {
+                crate::project_env::load(&database, &project_id)
+            })
+            .await?;
+            crate::project_env::apply_to_thread_config(&mut params.config, env);
+        }
+    }
+    let cwd = params.cwd.clone();
+    crate::model_providers::apply_to_thread_start(state, project_id, &mut params)?;
+    if !project_id.is_empty() {
+        crate::accounts::ensure_project_account(state, project_id).await?;
+    }
+
+    // Ensure app-server is running
+    state.start_app_server().await?;
+
+    let response: ThreadStartResponse = {
+        let mut guard = state.app_server.write().await;
+        let server = guard
+            .as_mut()
+            .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
+        server.send_request("thread/start", params).await?
+    };
+    state.idle.mark_resumed(&response.thread.id);
 
     // Create session metadata only if project_id is valid (non-empty and exists in database)
     // Agent threads may not have a project_id, so we skip session metadata creation in that case
     if !project_id.is_empty() {
         // Check if project exists before creating session metadata
-        if let Ok(Some(_)) = state.database.get_project(&project_id) {
-            let metadata = SessionMetadata::new(&response.thread.id, &project_id);
+        if let Ok(Some(_)) = state.database.get_project(project_id) {
+            let mut metadata = SessionMetadata::new(&response.thread.id, project_id);
+            metadata.cwd_subpath = cwd_subpath;
             if let Err(e) = state.database.upsert_session_metadata(&metadata) {
                 tracing::warn!("Failed to create session metadata: {}", e);
                 // Don't fail the thread start - session metadata is optional for agent threads
             }
+            if let Some(cwd) = cwd {
+                record_session_baseline(state, &response.thread.id, cwd).await;
+            }
             // Update project last opened time
-            if let Err(e) = state.database.update_project_last_opened(&project_id) {
+            if let Err(e) = state.database.update_project_last_opened(project_id) {
                 tracing::warn!("Failed to update project last opened time: {}", e);
             }
         } else {
@@ -91,6 +421,30 @@ pub async fn start_thread(
     Ok(response)
 }
 
+/// Remember the commit the session's working tree starts from, for
+/// `get_session_baseline_diff`; sessions outside git repositories get none
+async fn record_session_baseline(state: &AppState, session_id: &str, cwd: String) {
+    let database = state.database.clone();
+    let session_id = session_id.to_string();
+    let result = crate::utils::spawn_blocking_io(move || {
+        let Some(commit_sha) =
+            crate::commands::projects::resolve_baseline_commit(std::path::Path::new(&cwd))?
+        else {
+            return Ok(());
+        };
+        database.insert_session_baseline(&SessionBaseline {
+            session_id,
+            cwd,
+            commit_sha,
+            recorded_at: crate::global_state::unix_timestamp_secs(),
+        })
+    })
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record session baseline: {}", e);
+    }
+}
+
 /// Resume an existing thread
 ///
 /// Supports pagination for large threads:
@@ -103,20 +457,25 @@ pub async fn resume_thread(
     limit: Option<u32>,
     cursor: Option<String>,
 ) -> Result<ThreadResumeResponse> {
+    if thread_id.starts_with(crate::commands::sessions::IMPORTED_SESSION_PREFIX) {
+        return Err(Error::Other(format!(
+            "{thread_id} is an imported transcript; load it with get_imported_session_items"
+        )));
+    }
+
     // Ensure app-server is running
     state.start_app_server().await?;
 
-    let params = ThreadResumeParams {
-        thread_id,
-        limit,
-        cursor,
-    };
+    // Only the newest page reflects the whole recent conversation
+    let index_content = cursor.is_none();
+    let params = resume_params(state.database.clone(), &thread_id, limit, cursor).await?;
 
     let mut guard = state.app_server.write().await;
     let server = guard
         .as_mut()
         .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
     let response: ThreadResumeResponse = server.send_request("thread/resume", params).await?;
+    state.idle.mark_resumed(&response.thread.id);
 
     tracing::info!(
         "Resumed thread: {}, items: {}, has_more: {:?}",
@@ -124,10 +483,120 @@ pub async fn resume_thread(
         response.items.len(),
         response.has_more
     );
+    drop(guard);
+
+    if index_content {
+        // Items are indexed as they complete; a page holding the whole thread
+        // replaces that, a partial one only fills in threads never indexed
+        let content = crate::handoff::render_transcript(&response.items);
+        let content = crate::secret_mask::mask(&content);
+        let indexed = if response.has_more == Some(true) {
+            state
+                .database
+                .seed_session_content(&response.thread.id, &content)
+        } else {
+            state
+                .database
+                .index_session_content(&response.thread.id, &content)
+        };
+        if let Err(e) = indexed {
+            tracing::warn!("Failed to index session content for search: {}", e);
+        }
+        // Counts are only exact when the whole thread fit in one page
+        if response.has_more != Some(true) {
+            let preview = crate::session_preview::summarize(&response.items);
+            if let Err(e) = state.database.set_session_preview(&response.thread.id, &preview) {
+                tracing::warn!("Failed to update session preview: {}", e);
+            }
+        }
+    }
 
     Ok(response)
 }
 
+/// `thread/resume` parameters with what the app-server does not keep with a
+/// thread: the project's thread options and environment, which a thread
+/// loaded again (after a restart, say) would otherwise lose
+pub(crate) async fn resume_params(
+    database: Arc<Database>,
+    thread_id: &str,
+    limit: Option<u32>,
+    cursor: Option<String>,
+) -> Result<ThreadResumeParams> {
+    let mut params = ThreadResumeParams {
+        thread_id: thread_id.to_string(),
+        limit,
+        cursor,
+        ..Default::default()
+    };
+    let session_id = thread_id.to_string();
+    let project = crate::utils::spawn_blocking_io(move || {
+        let Some(session) = database.get_session_by_id(&session_id)? else {
+            return Ok(None);
+        };
+        let Some(project) = database.get_project(&session.project_id)? else {
+            return Ok(None);
+        };
+        let env = crate::project_env::load(&database, &project.id)?;
+        Ok(Some((project.settings(), env)))
+    })
+    .await?;
+    if let Some((settings, env)) = project {
+        ThreadOptions::from_project(&settings).apply_to_resume(&mut params);
+        crate::project_env::apply_to_thread_config(&mut params.config, env);
+    }
+    Ok(params)
+}
+
+/// Items requested per `thread/resume` page when loading a whole thread
+const FULL_THREAD_PAGE_SIZE: u32 = 500;
+
+/// Upper bound on pages loaded by `fetch_thread_items`
+const MAX_THREAD_PAGES: usize = 100;
+
+/// Load every item of a thread, oldest first
+///
+/// The first page holds the newest items and each cursor page the ones
+/// before it, so pages are stitched together in reverse.
+pub(crate) async fn fetch_thread_items(
+    state: &AppState,
+    thread_id: &str,
+) -> Result<(ThreadInfo, Vec<JsonValue>)> {
+    state.start_app_server().await?;
+
+    let mut pages = Vec::new();
+    let mut cursor = None;
+    let mut thread = None;
+    for _ in 0..MAX_THREAD_PAGES {
+        let params = resume_params(
+            state.database.clone(),
+            thread_id,
+            Some(FULL_THREAD_PAGE_SIZE),
+            cursor.take(),
+        )
+        .await?;
+        let response: ThreadResumeResponse = {
+            let mut guard = state.app_server.write().await;
+            let server = guard
+                .as_mut()
+                .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
+            server.send_request("thread/resume", params).await?
+        };
+        pages.push(response.items);
+        thread.get_or_insert(response.thread);
+        match response.next_cursor {
+            Some(next) if response.has_more == Some(true) => cursor = Some(next),
+            _ => break,
+        }
+    }
+    if cursor.is_some() {
+        tracing::warn!("Thread {} exceeds {} pages; oldest items omitted", thread_id, MAX_THREAD_PAGES);
+    }
+
+    let thread = thread.ok_or_else(|| Error::SessionNotFound(thread_id.to_string()))?;
+    Ok((thread, pages.into_iter().rev().flatten().collect()))
+}
+
 /// Skill input for send_message
 #[derive(Debug, serde::Deserialize)]
 pub struct SkillInput {
@@ -149,7 +618,11 @@ pub async fn send_message(
     model: Option<String>,
     approval_policy: Option<String>,
     sandbox_policy: Option<String>,
+    budget: Option<TurnBudget>,
 ) -> Result<TurnStartResponse> {
+    if let Some(budget) = &budget {
+        validate_turn_budget(budget)?;
+    }
     let mut input: Vec<UserInput> = vec![UserInput::Text { text }];
 
     // Add skills if provided (skills should come before images in input)
@@ -188,30 +661,297 @@ pub async fn send_message(
         model,
     };
 
-    let mut guard = state.app_server.write().await;
-    let server = guard
-        .as_mut()
-        .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
-    let response: TurnStartResponse = server.send_request("turn/start", params).await?;
+    // Held until the app-server reports the turn started
+    let thread_id = params.thread_id.clone();
+    if let Some(budget) = budget {
+        state.turn_budgets.set_next(&thread_id, budget);
+    }
+
+    let settings = state.global_state.read(|global| global.rate_limits.clone());
+    if settings.queue_when_limited {
+        let now = crate::global_state::unix_timestamp_secs();
+        if let Some(resumes_at) = state.rate_limits.limited_until(now, &settings) {
+            return queue_rate_limited_turn(&state, params, resumes_at).await;
+        }
+    }
+
+    let result = start_turn(&state, params).await;
+    if result.is_err() {
+        state.turn_budgets.cancel_next(&thread_id);
+    }
+    result
+}
+
+/// Hold a turn back until the account rate limits recover
+///
+/// The returned turn has status "queued" and the queue entry's id; the
+/// supervisor emits `rate-limit-queue-released` with the real turn id once
+/// it is sent.
+async fn queue_rate_limited_turn(
+    state: &AppState,
+    params: TurnStartParams,
+    resumes_at: Option<i64>,
+) -> Result<TurnStartResponse> {
+    let queued = state
+        .rate_limits
+        .enqueue(params, crate::global_state::unix_timestamp_secs())?;
+    tracing::info!(
+        "Queued message {} for session {} until rate limits reset",
+        queued.id,
+        queued.thread_id
+    );
+    state
+        .events
+        .emit(
+            "rate-limit-warning",
+            serde_json::json!({
+                "kind": "queued",
+                "sessionId": queued.thread_id,
+                "queuedId": queued.id,
+                "resumesAt": resumes_at,
+            }),
+        )
+        .await;
+
+    Ok(TurnStartResponse {
+        turn: TurnInfo {
+            id: queued.id,
+            status: "queued".to_string(),
+            items: Vec::new(),
+            error: None,
+        },
+    })
+}
 
-    tracing::info!("Started turn: {}", response.turn.id);
+/// Start a turn on an existing thread
+///
+/// Turns on different threads run in parallel, up to the configured
+/// concurrency limits; a thread with a turn already running is refused.
+/// Projects with `autoSnapshot` on are snapshotted once the turn's slot is
+/// reserved, so a refused turn leaves no snapshot behind.
+pub(crate) async fn start_turn(state: &AppState, params: TurnStartParams) -> Result<TurnStartResponse> {
+    state.reserve_turn(&params.thread_id).await?;
+    crate::commands::snapshots::snapshot_before_turn(state, &params.thread_id).await;
+    let response = state
+        .send_reserved_turn_start(&params, crate::global_state::unix_timestamp_millis())
+        .await?;
+
+    tracing::info!("Started turn {} on thread {}", response.turn.id, params.thread_id);
+    crate::session_preview::record_turn_started(&state.database, &params);
 
     Ok(response)
 }
 
-/// Interrupt the current turn
+/// Run `prompt` in a throwaway read-only thread in `cwd` and return the
+/// last agent message
+///
+/// The thread is started on the app-server directly, so it gets no session
+/// record, and it is archived once answered, failed or timed out. `what`
+/// names the turn in errors.
+pub(crate) async fn ask_read_only(
+    state: &AppState,
+    cwd: &str,
+    prompt: String,
+    model: Option<String>,
+    effort: Option<String>,
+    timeout: std::time::Duration,
+    what: &str,
+) -> Result<String> {
+    state.start_app_server().await?;
+    let params = ThreadStartParams {
+        cwd: Some(cwd.to_string()),
+        model,
+        model_provider: None,
+        sandbox: Some("read-only".to_string()),
+        approval_policy: Some("never".to_string()),
+        base_instructions: None,
+        developer_instructions: None,
+        config: None,
+    };
+    let thread: ThreadStartResponse = {
+        let mut guard = state.app_server.write().await;
+        let server = guard
+            .as_mut()
+            .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
+        server.send_request("thread/start", params).await?
+    };
+    let thread_id = thread.thread.id;
+
+    let answer = answer_read_only(state, &thread_id, prompt, effort, timeout, what).await;
+    archive_throwaway_thread(state, &thread_id).await;
+    answer
+}
+
+/// The turn of `ask_read_only`, on its freshly started thread
+async fn answer_read_only(
+    state: &AppState,
+    thread_id: &str,
+    prompt: String,
+    effort: Option<String>,
+    timeout: std::time::Duration,
+    what: &str,
+) -> Result<String> {
+    // Register before starting the turn so the completion cannot be missed
+    let completed = state.turn_waiters.register(thread_id);
+    let turn_params = TurnStartParams {
+        thread_id: thread_id.to_string(),
+        input: vec![UserInput::Text { text: prompt }],
+        effort,
+        summary: None,
+        cwd: None,
+        approval_policy: None,
+        sandbox_policy: None,
+        model: None,
+    };
+    if let Err(err) = start_turn(state, turn_params).await {
+        state.turn_waiters.cancel(thread_id);
+        return Err(err);
+    }
+
+    let status = match tokio::time::timeout(timeout, completed).await {
+        Ok(Ok(status)) => status,
+        Ok(Err(_)) => return Err(Error::AppServer(format!("{what} turn was abandoned"))),
+        Err(_) => {
+            state.turn_waiters.cancel(thread_id);
+            if let Err(err) = interrupt_thread_turn(state, thread_id.to_string()).await {
+                tracing::warn!("Failed to interrupt timed out {} turn: {}", what, err);
+            }
+            return Err(Error::AppServer(format!(
+                "{what} turn timed out after {} seconds",
+                timeout.as_secs()
+            )));
+        }
+    };
+    if status != "completed" {
+        return Err(Error::AppServer(format!("{what} turn {status}")));
+    }
+
+    let params = ThreadResumeParams {
+        thread_id: thread_id.to_string(),
+        ..Default::default()
+    };
+    let result: ThreadResumeResponse = {
+        let mut guard = state.app_server.write().await;
+        let server = guard
+            .as_mut()
+            .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
+        server.send_request("thread/resume", params).await?
+    };
+    crate::handoff::last_agent_message(&result.items)
+        .ok_or_else(|| Error::AppServer(format!("{what} turn returned no answer")))
+}
+
+/// Archive a thread started for an internal request so it does not show up
+/// among the user's threads; failures are only logged
+async fn archive_throwaway_thread(state: &AppState, thread_id: &str) {
+    let params = ThreadArchiveParams {
+        thread_id: thread_id.to_string(),
+    };
+    let guard = state.app_server.read().await;
+    let Some(server) = guard.as_ref() else {
+        return;
+    };
+    let archived: Result<JsonValue> = server.send_request("thread/archive", params).await;
+    if let Err(err) = archived {
+        tracing::warn!("Failed to archive throwaway thread {}: {}", thread_id, err);
+    }
+}
+
+/// Interrupt the running turn of a thread
 #[tauri::command]
 pub async fn interrupt_turn(state: State<'_, AppState>, thread_id: String) -> Result<()> {
-    let params = TurnInterruptParams { thread_id, turn_id: None };
+    interrupt_thread_turn(&state, thread_id).await
+}
 
-    let mut guard = state.app_server.write().await;
+pub(crate) async fn interrupt_thread_turn(state: &AppState, thread_id: String) -> Result<()> {
+    // Target the tracked turn so a turn started meanwhile is not interrupted
+    let turn_id = state.active_turns.get(&thread_id).and_then(|turn| turn.turn_id);
+    let params = TurnInterruptParams {
+        thread_id: thread_id.clone(),
+        turn_id,
+    };
+
+    let guard = state.app_server.read().await;
     let server = guard
-        .as_mut()
+        .as_ref()
         .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
     let _: JsonValue = server.send_request("turn/interrupt", params).await?;
 
-    tracing::info!("Interrupted turn");
+    tracing::info!("Interrupted turn on thread {}", thread_id);
+
+    Ok(())
+}
+
+/// Turns currently running across all threads, oldest first
+#[tauri::command]
+pub async fn list_active_turns(state: State<'_, AppState>) -> Result<Vec<ActiveTurn>> {
+    Ok(state.active_turns.list())
+}
+
+/// Interrupt every running turn; returns how many were interrupted
+#[tauri::command]
+pub async fn interrupt_all_turns(state: State<'_, AppState>) -> Result<usize> {
+    let mut interrupted = 0;
+    for turn in state.active_turns.list() {
+        match interrupt_thread_turn(&state, turn.thread_id).await {
+            Ok(()) => interrupted += 1,
+            Err(err) => tracing::warn!("Failed to interrupt turn: {}", err),
+        }
+    }
+    Ok(interrupted)
+}
+
+/// Get the limits on turns running at the same time
+#[tauri::command]
+pub async fn get_turn_concurrency_settings(
+    state: State<'_, AppState>,
+) -> Result<TurnConcurrencySettings> {
+    Ok(state.global_state.read(|global| global.turn_concurrency.clone()))
+}
+
+/// Update the limits on turns running at the same time
+///
+/// Lower limits only apply to turns started afterwards.
+#[tauri::command]
+pub async fn set_turn_concurrency_settings(
+    state: State<'_, AppState>,
+    settings: TurnConcurrencySettings,
+) -> Result<TurnConcurrencySettings> {
+    state.global_state.update(|global| global.turn_concurrency = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
+
+/// Get the default token and cost ceiling of every turn
+#[tauri::command]
+pub async fn get_turn_budget_settings(state: State<'_, AppState>) -> Result<TurnBudget> {
+    Ok(state.global_state.read(|global| global.turn_budget.clone()))
+}
+
+/// Update the default token and cost ceiling of every turn
+///
+/// Turns already running keep the budget they started with.
+#[tauri::command]
+pub async fn set_turn_budget_settings(
+    state: State<'_, AppState>,
+    settings: TurnBudget,
+) -> Result<TurnBudget> {
+    validate_turn_budget(&settings)?;
+    state.global_state.update(|global| global.turn_budget = settings.clone());
+    state.global_state.flush()?;
+    Ok(settings)
+}
 
+fn validate_turn_budget(budget: &TurnBudget) -> Result<()> {
+    if budget
+        .max_cost_usd
+        .is_some_and(|cost| !cost.is_finite() || cost <= 0.0)
+    {
+        return Err(Error::Other("Turn cost ceiling must be a positive amount".to_string()));
+    }
+    if budget.max_tokens == Some(0) {
+        return Err(Error::Other("Turn token ceiling must be at least 1".to_string()));
+    }
     Ok(())
 }
 
@@ -253,6 +993,7 @@ pub async fn respond_to_approval(
         .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
     // Send JSON-RPC response with the original request ID
     server.send_response(request_id, result).await?;
+    state.approvals.pending.remove(request_id);
 
     tracing::info!("Responded to approval request {}", request_id);
 
@@ -333,3 +1074,61 @@ fn save_base64_image_to_temp(data_url: &str) -> Result<String> {
 
     Ok(temp_path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_thread_options_fill_unset_params() {
+        let settings = ProjectSettings {
+            model: Some(" gpt-5 ".to_string()),
+            reasoning_effort: Some("x_high".to_string()),
+            sandbox_mode: Some("workspaceWrite".to_string()),
+            ask_for_approval: Some("sometimes".to_string()),
+            ..ProjectSettings::default()
+        };
+        let options = ThreadOptions::from_project(&settings);
+        assert_eq!(
+            options,
+            ThreadOptions {
+                model: Some("gpt-5".to_string()),
+                reasoning_effort: Some("xhigh".to_string()),
+                sandbox: Some("workspace-write".to_string()),
+                approval_policy: None,
+            }
+        );
+
+        let mut params = ThreadStartParams {
+            cwd: None,
+            model: Some(String::new()),
+            model_provider: None,
+            sandbox: Some("read-only".to_string()),
+            approval_policy: None,
+            base_instructions: None,
+            developer_instructions: None,
+            config: Some(serde_json::json!({ "model_reasoning_effort": "low" })),
+        };
+        options.apply_to(&mut params);
+        assert_eq!(params.model.as_deref(), Some("gpt-5"));
+        assert_eq!(params.sandbox.as_deref(), Some("read-only"));
+        assert_eq!(params.approval_policy, None);
+        assert_eq!(params.config.unwrap()["model_reasoning_effort"], "low");
+
+        let mut params = ThreadStartParams {
+            config: None,
+            ..params
+        };
+        options.apply_to(&mut params);
+        assert_eq!(params.config.unwrap()["model_reasoning_effort"], "xhigh");
+
+        let mut params = ThreadResumeParams {
+            thread_id: "thread-1".to_string(),
+            ..Default::default()
+        };
+        options.apply_to_resume(&mut params);
+        assert_eq!(params.model.as_deref(), Some("gpt-5"));
+        assert_eq!(params.sandbox.as_deref(), Some("workspace-write"));
+        assert_eq!(params.config.unwrap()["model_reasoning_effort"], "xhigh");
+    }
+}
@@ -0,0 +1,33 @@
+//! Tray commands
+
+use tauri::State;
+
+use crate::global_state::TraySettings;
+use crate::state::AppState;
+use crate::tray;
+use crate::Result;
+
+/// Get whether the tray icon is shown and how closing the window behaves
+#[tauri::command]
+pub async fn get_tray_settings(state: State<'_, AppState>) -> Result<TraySettings> {
+    Ok(state.global_state.read(|global| global.tray.clone()))
+}
+
+/// Update the tray settings, adding or removing the icon right away. The
+/// icon stays out in safe mode.
+#[tauri::command]
+pub async fn set_tray_settings(
+    state: State<'_, AppState>,
+    settings: TraySettings,
+) -> Result<TraySettings> {
+    state
+        .global_state
+        .update(|global| global.tray = settings.clone());
+    state.global_state.flush()?;
+    if settings.enabled && state.safe_mode.is_none() {
+        tray::create(&state.app_handle).await?;
+    } else {
+        tray::remove(&state.app_handle);
+    }
+    Ok(settings)
+}
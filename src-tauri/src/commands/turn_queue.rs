@@ -0,0 +1,125 @@
+//! Turn queue commands
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::projects::validate_id;
+use crate::database::QueuedTurn;
+use crate::state::AppState;
+use crate::Result;
+
+/// Whether queued turns are held back, globally or per project
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnQueueStatus {
+    pub paused: bool,
+    pub paused_projects: Vec<String>,
+}
+
+fn queue_status(state: &AppState) -> TurnQueueStatus {
+    state.global_state.read(|global| TurnQueueStatus {
+        paused: global.scheduler.paused,
+        paused_projects: global.scheduler.paused_projects.iter().cloned().collect(),
+    })
+}
+
+/// Line up a prompt to run on a thread after the project's earlier entries
+///
+/// `run_after` (Unix ms) holds the entry back until that time. Starts right
+/// away when the project's queue is idle.
+#[tauri::command]
+pub async fn enqueue_turn(
+    state: State<'_, AppState>,
+    project_id: String,
+    thread_id: String,
+    prompt: String,
+    run_after: Option<i64>,
+) -> Result<QueuedTurn> {
+    validate_id(&project_id, "project_id")?;
+    validate_id(&thread_id, "thread_id")?;
+    let entry = state
+        .turn_queue
+        .enqueue(&project_id, &thread_id, &prompt, run_after)?;
+    tracing::info!("Queued turn {} for project {}", entry.id, project_id);
+    state.events.emit("turn-queue-updated", &entry).await;
+    state.dispatch_queued_turns();
+    Ok(entry)
+}
+
+/// Running and waiting entries in run order, optionally followed by
+/// finished ones; all projects when `project_id` is omitted
+#[tauri::command]
+pub async fn list_queue(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    include_finished: Option<bool>,
+) -> Result<Vec<QueuedTurn>> {
+    if let Some(project_id) = &project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    state
+        .turn_queue
+        .list(project_id.as_deref(), include_finished.unwrap_or(false))
+}
+
+/// Put a project's waiting entries in the given order
+///
+/// `queued_ids` must list every waiting entry of the project.
+#[tauri::command]
+pub async fn reorder_queue(
+    state: State<'_, AppState>,
+    project_id: String,
+    queued_ids: Vec<String>,
+) -> Result<Vec<QueuedTurn>> {
+    validate_id(&project_id, "project_id")?;
+    state.turn_queue.reorder(&project_id, &queued_ids)
+}
+
+/// Cancel a waiting entry; returns whether it had not started yet
+#[tauri::command]
+pub async fn cancel_queued_turn(state: State<'_, AppState>, queued_id: String) -> Result<bool> {
+    validate_id(&queued_id, "queued_id")?;
+    match state.turn_queue.cancel(&queued_id)? {
+        Some(entry) => {
+            state.events.emit("turn-queue-updated", &entry).await;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub async fn get_queue_status(state: State<'_, AppState>) -> Result<TurnQueueStatus> {
+    Ok(queue_status(&state))
+}
+
+/// Pause or resume one project's queue, or with no `project_id` all queues
+///
+/// Resuming starts the next due entries.
+#[tauri::command]
+pub async fn set_queue_paused(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    paused: bool,
+) -> Result<TurnQueueStatus> {
+    if let Some(project_id) = &project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    state.global_state.update(|global| {
+        let scheduler = &mut global.scheduler;
+        match &project_id {
+            Some(project_id) if paused => {
+                scheduler.paused_projects.insert(project_id.clone());
+            }
+            Some(project_id) => {
+                scheduler.paused_projects.remove(project_id);
+            }
+            None => scheduler.paused = paused,
+        }
+    });
+    state.global_state.flush()?;
+    if !paused {
+        state.dispatch_queued_turns();
+    }
+    Ok(queue_status(&state))
+}
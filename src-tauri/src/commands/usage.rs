@@ -0,0 +1,73 @@
+//! Token usage and cost commands
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::projects::validate_id;
+use crate::state::AppState;
+use crate::usage::UsageSummary;
+use crate::Result;
+
+/// Token usage and estimated cost grouped by project, model and day
+///
+/// `since` and `until` are Unix timestamps bounding when each turn started.
+#[tauri::command]
+pub async fn get_usage_summary(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<UsageSummary> {
+    if let Some(project_id) = &project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    let database = state.database.clone();
+
+    crate::utils::spawn_blocking_io(move || {
+        let turns = database.get_turn_usage(project_id.as_deref(), since, until)?;
+        Ok(crate::usage::summarize(&turns))
+    })
+    .await
+}
+
+/// Result of `export_usage_csv`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExport {
+    pub path: String,
+    /// Number of turns written
+    pub rows: usize,
+}
+
+/// Write one CSV row per turn to `path`, an absolute file path in an
+/// existing directory; filters match `get_usage_summary`
+#[tauri::command]
+pub async fn export_usage_csv(
+    state: State<'_, AppState>,
+    path: String,
+    project_id: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+) -> Result<UsageExport> {
+    if let Some(project_id) = &project_id {
+        validate_id(project_id, "project_id")?;
+    }
+    let target = std::path::PathBuf::from(&path);
+    let parent = target.parent().filter(|parent| parent.is_dir());
+    if !target.is_absolute() || parent.is_none() || target.is_dir() {
+        return Err(crate::Error::InvalidPath(format!(
+            "Export path must be an absolute file path in an existing directory: {path}"
+        )));
+    }
+    let database = state.database.clone();
+
+    let rows = crate::utils::spawn_blocking_io(move || {
+        let turns = database.get_turn_usage(project_id.as_deref(), since, until)?;
+        crate::utils::atomic_write(&target, crate::usage::to_csv(&turns).as_bytes())?;
+        Ok(turns.len())
+    })
+    .await?;
+
+    tracing::info!("Exported {} usage rows to {}", rows, path);
+    Ok(UsageExport { path, rows })
+}
@@ -0,0 +1,40 @@
+//! Filesystem watcher commands
+
+use tauri::State;
+
+use crate::background_tasks::PROJECT_WATCHERS;
+use crate::commands::projects::validate_id;
+use crate::state::AppState;
+use crate::Result;
+
+/// Start emitting `project:file-changed` events for a project
+///
+/// Calling this for a project that is already watched is a no-op, as is
+/// calling it while the `projectWatchers` background task is off.
+#[tauri::command]
+pub async fn watch_project(state: State<'_, AppState>, project_id: String) -> Result<()> {
+    validate_id(&project_id, "project_id")?;
+    if !state.background_tasks.is_enabled(PROJECT_WATCHERS) {
+        tracing::debug!("Project watchers are off; not watching {}", project_id);
+        return Ok(());
+    }
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| crate::Error::ProjectNotFound(project_id.clone()))?;
+
+    let watchers = state.watchers.clone();
+    let events = state.events.clone();
+    crate::utils::spawn_blocking_io(move || {
+        let root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+        watchers.watch(&project_id, &root, events)
+    })
+    .await
+}
+
+/// Stop watching a project; returns whether a watcher was running
+#[tauri::command]
+pub async fn unwatch_project(state: State<'_, AppState>, project_id: String) -> Result<bool> {
+    validate_id(&project_id, "project_id")?;
+    Ok(state.watchers.unwatch(&project_id))
+}
@@ -0,0 +1,84 @@
+//! Workflow commands - start threads from built-in templates
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::app_server::ipc_bridge::{
+    ThreadStartParams, ThreadStartResponse, TurnStartParams, TurnStartResponse, UserInput,
+};
+use crate::commands::projects::validate_id;
+use crate::commands::thread::{start_thread_with_params, start_turn};
+use crate::state::AppState;
+use crate::workflows::{self, WorkflowDefinition};
+use crate::{Error, Result};
+
+/// Thread and first turn started for a workflow
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStartResponse {
+    pub thread: ThreadStartResponse,
+    pub turn: TurnStartResponse,
+}
+
+/// List the built-in workflows and their parameters
+#[tauri::command]
+pub async fn list_workflows() -> Result<Vec<WorkflowDefinition>> {
+    Ok(workflows::builtin_workflows())
+}
+
+/// Start a new thread in a project from a workflow template
+///
+/// Context (diff, test output, file contents) is gathered before the thread
+/// is created, so a bad parameter fails fast without leaving an empty thread.
+#[tauri::command]
+pub async fn start_workflow(
+    state: State<'_, AppState>,
+    project_id: String,
+    workflow_id: String,
+    params: HashMap<String, String>,
+    model: Option<String>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+) -> Result<WorkflowStartResponse> {
+    validate_id(&project_id, "project_id")?;
+    let workflow = workflows::find_workflow(&workflow_id)?;
+    let project = state
+        .database
+        .get_project(&project_id)?
+        .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+    let symlink_policy = project.settings().symlink_policy.unwrap_or_default();
+    let project_root = crate::utils::validate_and_canonicalize_path(&project.path)?;
+
+    let prompt =
+        workflows::assemble_prompt(&workflow, &project_root, symlink_policy, &params).await?;
+
+    let thread_params = ThreadStartParams {
+        cwd: Some(project_root.to_string_lossy().to_string()),
+        model: model.clone(),
+        model_provider: None,
+        sandbox,
+        approval_policy,
+        base_instructions: None,
+        developer_instructions: None,
+        config: None,
+    };
+    let thread = start_thread_with_params(&state, &project_id, None, thread_params).await?;
+
+    let turn_params = TurnStartParams {
+        thread_id: thread.thread.id.clone(),
+        input: vec![UserInput::Text { text: prompt }],
+        effort: None,
+        summary: None,
+        cwd: None,
+        approval_policy: None,
+        sandbox_policy: None,
+        model,
+    };
+    let turn = start_turn(&state, turn_params).await?;
+
+    tracing::info!("Started workflow {} on thread {}", workflow.id, thread.thread.id);
+
+    Ok(WorkflowStartResponse { thread, turn })
+}
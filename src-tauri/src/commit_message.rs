@@ -0,0 +1,395 @@
+//! Conventional-commit messages for staged changes.
+//!
+//! The staged diff goes to a throwaway read-only turn that answers with a
+//! message in Conventional Commits form. An answer that does not parse as
+//! one, or no answer at all, is replaced by a message built from the file
+//! stats alone. The type and scope hinted to the model, and used by the
+//! fallback, are guessed from the changed paths.
+
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+/// Staged diff bytes sent to the model; the rest is cut off
+const MAX_PROMPT_DIFF_BYTES: usize = 48 * 1024;
+
+/// Files listed in the body of a fallback message
+const MAX_BODY_FILES: usize = 15;
+
+pub const COMMIT_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+/// Directories that say nothing about what part of a project changed
+const GENERIC_DIRS: &[&str] = &["src", "lib", "source", "sources", "app", "internal", "pkg"];
+
+/// Directories whose children are separate packages
+const PACKAGE_DIRS: &[&str] = &["crates", "packages", "apps", "libs", "modules"];
+
+const BUILD_FILES: &[&str] = &[
+    "Cargo.toml",
+    "Cargo.lock",
+    "build.rs",
+    "package.json",
+    "package-lock.json",
+    "pnpm-lock.yaml",
+    "yarn.lock",
+    "Makefile",
+    "Dockerfile",
+];
+
+const PROMPT_INSTRUCTIONS: &str = "Write a git commit message for the staged changes below, \
+following the Conventional Commits specification. Do not run any commands or edit files. \
+Respond with the commit message only, no commentary and no code fences:\n\
+- a header `type(scope): subject`, where type is one of feat, fix, docs, style, refactor, \
+perf, test, build, ci, chore or revert, the scope is optional and the subject is imperative, \
+lowercase and at most 72 characters without a trailing period\n\
+- optionally a blank line and a short body explaining what changed and why";
+
+static HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([a-z]+)(?:\(([^()\s]+)\))?!?: \S").expect("valid regex"));
+
+/// A staged file with its line counts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedFile {
+    pub path: String,
+    /// `git diff --name-status` letter: "A", "M", "D", ...
+    pub status: String,
+    /// `None` for binary files
+    pub additions: Option<u32>,
+    pub deletions: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommitMessageSource {
+    Model,
+    /// Built from the file stats
+    Fallback,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitMessageSuggestion {
+    pub message: String,
+    pub commit_type: String,
+    pub scope: Option<String>,
+    pub source: CommitMessageSource,
+    /// Why the model's message was not used
+    pub fallback_reason: Option<String>,
+    pub files: Vec<StagedFile>,
+}
+
+/// Staged files from `git diff --cached --no-renames --numstat` and
+/// `--name-status` output
+pub fn parse_staged(numstat: &str, name_status: &str) -> Vec<StagedFile> {
+    let counts: Vec<(&str, Option<u32>, Option<u32>)> = numstat
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let additions = fields.next()?.parse().ok();
+            let deletions = fields.next()?.parse().ok();
+            Some((fields.next()?, additions, deletions))
+        })
+        .collect();
+    name_status
+        .lines()
+        .filter_map(|line| {
+            let (status, path) = line.split_once('\t')?;
+            let (_, additions, deletions) = counts
+                .iter()
+                .find(|(counted, _, _)| *counted == path)
+                .copied()
+                .unwrap_or((path, None, None));
+            Some(StagedFile {
+                path: path.to_string(),
+                status: status.chars().take(1).collect(),
+                additions,
+                deletions,
+            })
+        })
+        .collect()
+}
+
+fn scope_of(path: &str) -> Option<String> {
+    let dirs: Vec<&str> = Path::new(path)
+        .parent()?
+        .iter()
+        .filter_map(|dir| dir.to_str())
+        .collect();
+    if let [root, package, ..] = dirs.as_slice() {
+        if PACKAGE_DIRS.contains(root) {
+            return Some(package.to_lowercase());
+        }
+    }
+    dirs.into_iter()
+        .find(|dir| !GENERIC_DIRS.contains(dir) && !dir.starts_with('.'))
+        .map(str::to_lowercase)
+}
+
+/// The part of the project every file is in, if they share one
+pub fn detect_scope(files: &[StagedFile]) -> Option<String> {
+    let mut scopes = files.iter().map(|file| scope_of(&file.path));
+    let first = scopes.next()??;
+    scopes
+        .all(|scope| scope.as_ref() == Some(&first))
+        .then_some(first)
+}
+
+fn is_docs(path: &str) -> bool {
+    path.starts_with("docs/")
+        || [".md", ".mdx", ".rst", ".txt"]
+            .iter()
+            .any(|ext| path.to_lowercase().ends_with(ext))
+}
+
+fn is_test(path: &str) -> bool {
+    let name = file_name(path);
+    path.starts_with("tests/")
+        || path.contains("/tests/")
+        || path.contains("/__tests__/")
+        || [".test.", ".spec.", "_test."]
+            .iter()
+            .any(|marker| name.contains(marker))
+}
+
+fn is_ci(path: &str) -> bool {
+    path.starts_with(".github/workflows/")
+        || path.starts_with(".circleci/")
+        || path == ".gitlab-ci.yml"
+        || path == "bitbucket-pipelines.yml"
+}
+
+fn is_build(path: &str) -> bool {
+    let name = file_name(path);
+    BUILD_FILES.contains(&name) || name.starts_with("tsconfig") || name.starts_with("vite.config")
+}
+
+/// The commit type the changed paths suggest
+pub fn detect_type(files: &[StagedFile]) -> &'static str {
+    let all = |check: fn(&str) -> bool| files.iter().all(|file| check(&file.path));
+    if files.is_empty() {
+        "chore"
+    } else if all(is_docs) {
+        "docs"
+    } else if all(is_test) {
+        "test"
+    } else if all(is_ci) {
+        "ci"
+    } else if all(is_build) {
+        "build"
+    } else if files
+        .iter()
+        .any(|file| file.status == "A" && !is_test(&file.path))
+    {
+        "feat"
+    } else {
+        let added: u32 = files.iter().filter_map(|file| file.additions).sum();
+        let deleted: u32 = files.iter().filter_map(|file| file.deletions).sum();
+        if deleted > added.saturating_mul(2) {
+            "refactor"
+        } else {
+            "chore"
+        }
+    }
+}
+
+fn header(commit_type: &str, scope: Option<&str>, subject: &str) -> String {
+    match scope {
+        Some(scope) => format!("{commit_type}({scope}): {subject}"),
+        None => format!("{commit_type}: {subject}"),
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn fallback_subject(files: &[StagedFile]) -> String {
+    let verb = |status: &str| match status {
+        "A" => "add",
+        "D" => "remove",
+        _ => "update",
+    };
+    match files {
+        [file] => format!("{} {}", verb(&file.status), file_name(&file.path)),
+        _ => {
+            let first = files.first().map_or("M", |file| file.status.as_str());
+            let verb = if files.iter().all(|file| file.status == first) {
+                verb(first)
+            } else {
+                "update"
+            };
+            format!("{verb} {} files", files.len())
+        }
+    }
+}
+
+/// A message built from the file stats alone
+pub fn fallback(files: Vec<StagedFile>, reason: Option<String>) -> CommitMessageSuggestion {
+    let commit_type = detect_type(&files);
+    let scope = detect_scope(&files);
+    let added: u32 = files.iter().filter_map(|file| file.additions).sum();
+    let deleted: u32 = files.iter().filter_map(|file| file.deletions).sum();
+
+    let mut message = header(commit_type, scope.as_deref(), &fallback_subject(&files));
+    message.push_str(&format!(
+        "\n\n{} file{} changed, {added} insertions(+), {deleted} deletions(-)\n",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" }
+    ));
+    for file in files.iter().take(MAX_BODY_FILES) {
+        match (file.additions, file.deletions) {
+            (Some(added), Some(deleted)) => {
+                message.push_str(&format!("\n- {} (+{added} -{deleted})", file.path))
+            }
+            _ => message.push_str(&format!("\n- {} (binary)", file.path)),
+        }
+    }
+    if files.len() > MAX_BODY_FILES {
+        message.push_str(&format!(
+            "\n- and {} more files",
+            files.len() - MAX_BODY_FILES
+        ));
+    }
+
+    CommitMessageSuggestion {
+        message,
+        commit_type: commit_type.to_string(),
+        scope,
+        source: CommitMessageSource::Fallback,
+        fallback_reason: reason,
+        files,
+    }
+}
+
+/// Prompt asking for a message for `diff`, with the guessed type and scope
+pub fn prompt(diff: &str, files: &[StagedFile]) -> String {
+    let mut end = diff.len().min(MAX_PROMPT_DIFF_BYTES);
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut prompt = format!(
+        "{PROMPT_INSTRUCTIONS}\n\nThe paths suggest the type `{}`",
+        detect_type(files)
+    );
+    match detect_scope(files) {
+        Some(scope) => prompt.push_str(&format!(" and the scope `{scope}`")),
+        None => prompt.push_str(" and no single scope"),
+    }
+    prompt.push_str("; use them unless the diff says otherwise.\n\n<diff>\n");
+    prompt.push_str(&diff[..end]);
+    if end < diff.len() {
+        prompt.push_str("\n[... diff truncated ...]");
+    }
+    prompt.push_str("\n</diff>");
+    prompt
+}
+
+/// The model's answer as a suggestion, or `None` when it is not a
+/// conventional commit message
+pub fn from_answer(answer: &str, files: Vec<StagedFile>) -> Option<CommitMessageSuggestion> {
+    let message: Vec<&str> = answer
+        .trim()
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("```"))
+        .map(str::trim_end)
+        .collect();
+    let message = message.join("\n").trim().to_string();
+    let captures = HEADER.captures(message.lines().next()?)?;
+    let commit_type = captures.get(1)?.as_str();
+    if !COMMIT_TYPES.contains(&commit_type) {
+        return None;
+    }
+    Some(CommitMessageSuggestion {
+        commit_type: commit_type.to_string(),
+        scope: captures.get(2).map(|scope| scope.as_str().to_string()),
+        message,
+        source: CommitMessageSource::Model,
+        fallback_reason: None,
+        files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, status: &str, additions: u32, deletions: u32) -> StagedFile {
+        StagedFile {
+            path: path.to_string(),
+            status: status.to_string(),
+            additions: Some(additions),
+            deletions: Some(deletions),
+        }
+    }
+
+    #[test]
+    fn test_parse_staged() {
+        let files = parse_staged(
+            "3\t1\tsrc/main.rs\n-\t-\tlogo.png\n",
+            "M\tsrc/main.rs\nA\tlogo.png\n",
+        );
+        assert_eq!(files[0], file("src/main.rs", "M", 3, 1));
+        assert_eq!(files[1].status, "A");
+        assert_eq!(files[1].additions, None);
+    }
+
+    #[test]
+    fn test_detect_scope_and_type() {
+        let files = [
+            file("src/components/dialogs/CommitDialog.tsx", "M", 10, 2),
+            file("src/components/ui/Button.tsx", "M", 1, 1),
+        ];
+        assert_eq!(detect_scope(&files).as_deref(), Some("components"));
+        assert_eq!(detect_type(&files), "chore");
+
+        let files = [
+            file("crates/parser/src/lib.rs", "A", 40, 0),
+            file("crates/parser/tests/basic.rs", "A", 12, 0),
+        ];
+        assert_eq!(detect_scope(&files).as_deref(), Some("parser"));
+        assert_eq!(detect_type(&files), "feat");
+
+        let files = [
+            file("README.md", "M", 2, 2),
+            file("docs/setup.md", "M", 5, 0),
+        ];
+        assert_eq!(detect_scope(&files), None);
+        assert_eq!(detect_type(&files), "docs");
+        assert_eq!(
+            detect_type(&[file(".github/workflows/ci.yml", "M", 1, 1)]),
+            "ci"
+        );
+        assert_eq!(detect_type(&[file("src/old.rs", "M", 2, 90)]), "refactor");
+    }
+
+    #[test]
+    fn test_fallback_message() {
+        let suggestion = fallback(vec![file("src/api/client.ts", "D", 0, 30)], None);
+        assert_eq!(suggestion.source, CommitMessageSource::Fallback);
+        assert!(suggestion
+            .message
+            .starts_with("refactor(api): remove client.ts\n\n1 file changed, 0 insertions(+)"));
+        assert!(suggestion.message.ends_with("- src/api/client.ts (+0 -30)"));
+    }
+
+    #[test]
+    fn test_from_answer() {
+        let answer =
+            "```\nfix(parser): handle empty input\n\nReturn early instead of panicking.\n```";
+        let suggestion = from_answer(answer, Vec::new()).unwrap();
+        assert_eq!(
+            suggestion.message,
+            "fix(parser): handle empty input\n\nReturn early instead of panicking."
+        );
+        assert_eq!(suggestion.scope.as_deref(), Some("parser"));
+
+        assert!(from_answer("feat!: drop the v1 API", Vec::new()).is_some());
+        assert!(from_answer("Here is a commit message: fix bug", Vec::new()).is_none());
+        assert!(from_answer("update: things", Vec::new()).is_none());
+    }
+}
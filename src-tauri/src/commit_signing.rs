@@ -0,0 +1,303 @@
+//! GPG, SSH and X.509 commit signing.
+//!
+//! `config` reads how a repository signs commits and whether the key it
+//! would sign with can be found, so the commit dialog can warn before a
+//! commit that is bound to fail. `classify_failure` turns the stderr of a
+//! failed `git commit` into a `SigningFailure` the renderer can act on.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::git;
+
+/// Signature format, from `gpg.format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SigningFormat {
+    Openpgp,
+    Ssh,
+    X509,
+}
+
+/// How a repository signs commits
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningConfig {
+    /// `commit.gpgSign` is on, so every commit is signed unless told not to
+    pub required: bool,
+    pub format: SigningFormat,
+    /// `user.signingKey`
+    pub signing_key: Option<String>,
+    /// Program that signs, from `gpg.program`, `gpg.ssh.program` or
+    /// `gpg.x509.program`
+    pub program: String,
+    /// Whether the key was found; `None` when that cannot be checked
+    pub key_available: Option<bool>,
+    /// What would make a signed commit fail
+    pub problem: Option<SigningFailure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SigningFailureKind {
+    /// No signing key configured, or the configured key is not there
+    MissingKey,
+    /// The signing program could not be run
+    ProgramNotFound,
+    /// The key needs a passphrase and there is no way to ask for it
+    PassphraseRequired,
+    Other,
+}
+
+/// Why a commit could not be signed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningFailure {
+    pub kind: SigningFailureKind,
+    pub message: String,
+}
+
+impl SigningFailure {
+    fn new(kind: SigningFailureKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// Signing setup of the repository at `dir`. Blocking.
+pub fn config(dir: &Path) -> SigningConfig {
+    let required = git::run(dir, &["config", "--type=bool", "--get", "commit.gpgsign"])
+        .is_ok_and(|value| value.trim() == "true");
+    let format = match git::config_value(dir, "gpg.format").as_deref() {
+        Some("ssh") => SigningFormat::Ssh,
+        Some("x509") => SigningFormat::X509,
+        _ => SigningFormat::Openpgp,
+    };
+    let signing_key = git::config_value(dir, "user.signingkey");
+    let program = match format {
+        SigningFormat::Openpgp => git::config_value(dir, "gpg.openpgp.program")
+            .or_else(|| git::config_value(dir, "gpg.program"))
+            .unwrap_or_else(|| "gpg".to_string()),
+        SigningFormat::Ssh => {
+            git::config_value(dir, "gpg.ssh.program").unwrap_or_else(|| "ssh-keygen".to_string())
+        }
+        SigningFormat::X509 => {
+            git::config_value(dir, "gpg.x509.program").unwrap_or_else(|| "gpgsm".to_string())
+        }
+    };
+
+    let (key_available, problem) = if !program_runs(&program, format) {
+        (
+            None,
+            Some(SigningFailure::new(
+                SigningFailureKind::ProgramNotFound,
+                format!("Cannot run the signing program `{program}`"),
+            )),
+        )
+    } else {
+        match check_key(dir, format, signing_key.as_deref(), &program) {
+            Ok(available) => (available, None),
+            Err(failure) => (Some(false), Some(failure)),
+        }
+    };
+
+    SigningConfig {
+        required,
+        format,
+        signing_key,
+        program,
+        key_available,
+        problem,
+    }
+}
+
+fn program_runs(program: &str, format: SigningFormat) -> bool {
+    // ssh-keygen has no --version and exits 1 on -h; running at all is enough
+    let arg = if format == SigningFormat::Ssh {
+        "-?"
+    } else {
+        "--version"
+    };
+    Command::new(program).arg(arg).output().is_ok()
+}
+
+/// Whether the signing key is there: `Ok(None)` when it cannot be told
+fn check_key(
+    dir: &Path,
+    format: SigningFormat,
+    signing_key: Option<&str>,
+    program: &str,
+) -> std::result::Result<Option<bool>, SigningFailure> {
+    match format {
+        SigningFormat::Ssh => {
+            if git::config_value(dir, "gpg.ssh.defaultkeycommand").is_some() {
+                return Ok(None);
+            }
+            let Some(key) = signing_key else {
+                return Err(SigningFailure::new(
+                    SigningFailureKind::MissingKey,
+                    "SSH signing needs user.signingKey to be set",
+                ));
+            };
+            // A literal public key; the agent holds the private half
+            if key.starts_with("key::") || key.starts_with("ssh-") {
+                return Ok(None);
+            }
+            if expand_home(key).is_file() {
+                Ok(Some(true))
+            } else {
+                Err(SigningFailure::new(
+                    SigningFailureKind::MissingKey,
+                    format!("SSH signing key {key} does not exist"),
+                ))
+            }
+        }
+        SigningFormat::Openpgp => {
+            // Without a configured key gpg picks one by committer email
+            let key = signing_key
+                .map(str::to_string)
+                .or_else(|| git::config_value(dir, "user.email"));
+            let Some(key) = key else {
+                return Err(SigningFailure::new(
+                    SigningFailureKind::MissingKey,
+                    "Neither user.signingKey nor user.email is set",
+                ));
+            };
+            let found = Command::new(program)
+                .args(["--batch", "--list-secret-keys", "--with-colons", &key])
+                .output()
+                .is_ok_and(|output| {
+                    output.status.success()
+                        && String::from_utf8_lossy(&output.stdout)
+                            .lines()
+                            .any(|line| line.starts_with("sec"))
+                });
+            if found {
+                Ok(Some(true))
+            } else {
+                Err(SigningFailure::new(
+                    SigningFailureKind::MissingKey,
+                    format!("No secret GPG key for {key}"),
+                ))
+            }
+        }
+        SigningFormat::X509 => Ok(None),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The signing failure in a failed `git commit`'s stderr, if signing is
+/// what failed
+pub fn classify_failure(stderr: &str) -> Option<SigningFailure> {
+    let lower = stderr.to_lowercase();
+    let signing = lower.contains("failed to sign")
+        || lower.contains("signing failed")
+        || lower.contains("gpg failed")
+        || lower.contains("signingkey")
+        || lower.contains("couldn't load public key")
+        || lower.contains("cannot run gpg")
+        || lower.contains("cannot run ssh-keygen")
+        || lower.contains("cannot run gpgsm");
+    if !signing {
+        return None;
+    }
+
+    let kind = if lower.contains("cannot run") {
+        SigningFailureKind::ProgramNotFound
+    } else if lower.contains("no secret key")
+        || lower.contains("secret key not available")
+        || lower.contains("no such file or directory")
+        || lower.contains("couldn't load public key")
+        || lower.contains("needs to be set")
+        || lower.contains("no default identity")
+    {
+        SigningFailureKind::MissingKey
+    } else if lower.contains("inappropriate ioctl")
+        || lower.contains("pinentry")
+        || lower.contains("passphrase")
+    {
+        SigningFailureKind::PassphraseRequired
+    } else {
+        SigningFailureKind::Other
+    };
+    let message = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(SigningFailure::new(kind, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_failure() {
+        let kind = |stderr: &str| classify_failure(stderr).map(|failure| failure.kind);
+        let cases = [
+            (
+                "gpg: skipped \"ABC\": No secret key\n\
+                 gpg: signing failed: No secret key\n\
+                 error: gpg failed to sign the data\n",
+                SigningFailureKind::MissingKey,
+            ),
+            (
+                "error: cannot run gpg: No such file or directory\n\
+                 error: gpg failed to sign the data\n",
+                SigningFailureKind::ProgramNotFound,
+            ),
+            (
+                "error: Couldn't load public key /home/me/.ssh/id_ed25519.pub: \
+                 No such file or directory?\n\nfatal: failed to write commit object\n",
+                SigningFailureKind::MissingKey,
+            ),
+            (
+                "gpg: signing failed: Inappropriate ioctl for device\n\
+                 error: gpg failed to sign the data\n",
+                SigningFailureKind::PassphraseRequired,
+            ),
+        ];
+        for (stderr, expected) in cases {
+            assert_eq!(kind(stderr), Some(expected), "{stderr}");
+        }
+        assert_eq!(kind("nothing to commit, working tree clean\n"), None);
+    }
+
+    #[test]
+    fn test_config_of_unsigned_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        git::run(temp_dir.path(), &["init", "-q"]).unwrap();
+        git::run(temp_dir.path(), &["config", "gpg.format", "ssh"]).unwrap();
+        git::run(
+            temp_dir.path(),
+            &["config", "user.signingkey", "/nonexistent/key"],
+        )
+        .unwrap();
+
+        let config = config(temp_dir.path());
+        assert!(!config.required);
+        assert_eq!(config.format, SigningFormat::Ssh);
+        assert_eq!(config.program, "ssh-keygen");
+        if config.key_available.is_some() {
+            assert_eq!(config.key_available, Some(false));
+            assert_eq!(
+                config.problem.map(|problem| problem.kind),
+                Some(SigningFailureKind::MissingKey)
+            );
+        }
+    }
+}
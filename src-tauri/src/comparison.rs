@@ -0,0 +1,169 @@
+//! Comparison runs of one prompt in several worktrees.
+//!
+//! `run_comparison` gives every run a worktree and branch of its own, made
+//! from the project's HEAD, and runs the prompt in all of them side by side,
+//! optionally with a different model or reasoning effort per run. The group
+//! and its runs are kept in the database so their diffs can be compared
+//! after the fact; resolving the comparison merges the chosen run's branch
+//! back into the project and removes every run's worktree and branch.
+
+use serde::{Deserialize, Serialize};
+
+use crate::batch::{BatchTargetStatus, DiffSummary};
+use crate::database::{ComparisonGroup, ComparisonRun, ComparisonRunStatus};
+use crate::{Error, Result};
+
+/// Runs accepted in one comparison
+pub const MIN_RUNS: usize = 2;
+pub const MAX_RUNS: usize = 8;
+
+/// Diff text returned per run is cut off beyond this many bytes
+pub const MAX_DIFF_BYTES: usize = 512 * 1024;
+
+/// Model and reasoning effort for one run; `None` leaves them to the
+/// project's defaults
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonVariant {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub effort: Option<String>,
+}
+
+impl ComparisonVariant {
+    /// Blank values mean "not set"
+    pub fn normalized(self) -> Self {
+        let clean = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            model: clean(self.model),
+            effort: clean(self.effort),
+        }
+    }
+}
+
+impl From<BatchTargetStatus> for ComparisonRunStatus {
+    fn from(status: BatchTargetStatus) -> Self {
+        match status {
+            BatchTargetStatus::Running => Self::Running,
+            BatchTargetStatus::Completed => Self::Completed,
+            BatchTargetStatus::Failed => Self::Failed,
+            BatchTargetStatus::Interrupted => Self::Interrupted,
+            BatchTargetStatus::TimedOut => Self::TimedOut,
+        }
+    }
+}
+
+/// A run with what it changed since the comparison started
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonRunResult {
+    #[serde(flatten)]
+    pub run: ComparisonRun,
+    /// Unified diff against the base commit, committed and uncommitted
+    /// changes alike; `None` once the worktree is gone
+    pub diff: Option<String>,
+    pub summary: Option<DiffSummary>,
+    /// `diff` was cut off at `MAX_DIFF_BYTES`; `summary` still covers it all
+    pub diff_truncated: bool,
+}
+
+/// A comparison and its runs, in the order they were requested
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonResults {
+    pub group: ComparisonGroup,
+    pub runs: Vec<ComparisonRunResult>,
+}
+
+/// Payload of `comparison-updated`, emitted when a run starts or finishes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonUpdatedEvent {
+    pub group_id: String,
+    pub run: ComparisonRun,
+}
+
+/// Reject too few or too many runs
+pub fn validate_variants(variants: &[ComparisonVariant]) -> Result<()> {
+    if variants.len() < MIN_RUNS {
+        return Err(Error::Other(format!(
+            "A comparison needs at least {MIN_RUNS} runs"
+        )));
+    }
+    if variants.len() > MAX_RUNS {
+        return Err(Error::Other(format!(
+            "A comparison has too many runs (max {MAX_RUNS})"
+        )));
+    }
+    Ok(())
+}
+
+/// Branch of the run at `position` (0-based) in the comparison `group_id`
+pub fn branch_name(group_id: &str, position: u32) -> String {
+    let short: String = group_id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect();
+    format!("codex/compare-{short}-{}", position + 1)
+}
+
+/// Cut `diff` to at most `MAX_DIFF_BYTES` on a line boundary
+pub fn truncate_diff(mut diff: String) -> (String, bool) {
+    if diff.len() <= MAX_DIFF_BYTES {
+        return (diff, false);
+    }
+    let mut end = MAX_DIFF_BYTES;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = diff[..end].rfind('\n').map_or(end, |newline| newline + 1);
+    diff.truncate(end);
+    (diff, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variants_and_names() {
+        let variant = |model: &str| ComparisonVariant {
+            model: Some(model.to_string()),
+            effort: Some(" ".to_string()),
+        };
+        assert!(validate_variants(&[variant("a")]).is_err());
+        assert!(validate_variants(&[variant("a"), variant("a")]).is_ok());
+        assert!(validate_variants(&vec![variant("a"); MAX_RUNS + 1]).is_err());
+
+        let normalized = variant(" gpt-5 ").normalized();
+        assert_eq!(normalized.model.as_deref(), Some("gpt-5"));
+        assert_eq!(normalized.effort, None);
+
+        assert_eq!(
+            branch_name("1a2b3c4d-5e6f-7a8b-9c0d-1e2f3a4b5c6d", 0),
+            "codex/compare-1a2b3c4d-1"
+        );
+    }
+
+    #[test]
+    fn test_truncate_diff() {
+        assert_eq!(
+            truncate_diff("+a\n".to_string()),
+            ("+a\n".to_string(), false)
+        );
+
+        let line = "+ü".repeat(100) + "\n";
+        let diff = line.repeat(MAX_DIFF_BYTES / line.len() + 2);
+        let (truncated, cut) = truncate_diff(diff);
+        assert!(cut);
+        assert!(truncated.len() <= MAX_DIFF_BYTES);
+        assert!(truncated.ends_with('\n'));
+        assert_eq!(truncated.len() % line.len(), 0);
+    }
+}
@@ -0,0 +1,167 @@
+//! Conflict markers left by `git apply --3way`.
+//!
+//! A three-way apply that cannot merge a hunk cleanly still writes the file,
+//! with `<<<<<<<`/`=======`/`>>>>>>>` around the two versions (and a
+//! `|||||||` base section with `merge.conflictStyle=diff3`). `parse` finds
+//! those regions so the renderer can offer to resolve them inline.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+/// One conflicted region of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictHunk {
+    /// 1-based line of the `<<<<<<<` marker
+    pub start_line: u32,
+    /// 1-based line of the `>>>>>>>` marker
+    pub end_line: u32,
+    /// What the file had, without the trailing newline
+    pub ours: String,
+    /// The common ancestor, with `diff3` conflict style only
+    pub base: Option<String>,
+    /// What the patch wanted
+    pub theirs: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedFile {
+    /// Relative to the repository
+    pub path: String,
+    pub hunks: Vec<ConflictHunk>,
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+enum Section {
+    Ours,
+    Base,
+    Theirs,
+}
+
+/// A region whose closing marker has not been seen yet
+struct OpenHunk<'a> {
+    start_line: u32,
+    section: Section,
+    ours: Vec<&'a str>,
+    base: Option<Vec<&'a str>>,
+    theirs: Vec<&'a str>,
+}
+
+/// Conflicted regions of `text`, in file order. A region missing its
+/// closing marker is left out.
+pub fn parse(text: &str) -> Vec<ConflictHunk> {
+    let mut hunks = Vec::new();
+    let mut open: Option<OpenHunk> = None;
+    for (index, line) in text.lines().enumerate() {
+        let number = index as u32 + 1;
+        if is_marker(line, "<<<<<<<") {
+            open = Some(OpenHunk {
+                start_line: number,
+                section: Section::Ours,
+                ours: Vec::new(),
+                base: None,
+                theirs: Vec::new(),
+            });
+            continue;
+        }
+        let Some(hunk) = &mut open else {
+            continue;
+        };
+        match hunk.section {
+            Section::Ours if is_marker(line, "|||||||") => {
+                hunk.section = Section::Base;
+                hunk.base = Some(Vec::new());
+            }
+            Section::Ours | Section::Base if is_marker(line, "=======") => {
+                hunk.section = Section::Theirs;
+            }
+            Section::Theirs if is_marker(line, ">>>>>>>") => {
+                hunks.push(ConflictHunk {
+                    start_line: hunk.start_line,
+                    end_line: number,
+                    ours: hunk.ours.join("\n"),
+                    base: hunk.base.as_ref().map(|base| base.join("\n")),
+                    theirs: hunk.theirs.join("\n"),
+                });
+                open = None;
+            }
+            Section::Ours => hunk.ours.push(line),
+            Section::Base => hunk.base.get_or_insert_with(Vec::new).push(line),
+            Section::Theirs => hunk.theirs.push(line),
+        }
+    }
+    hunks
+}
+
+/// Files `git apply --3way` reported as "Applied patch to '<path>' with
+/// conflicts."
+pub fn conflicted_paths(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.trim()
+                .strip_prefix("Applied patch to '")?
+                .strip_suffix("' with conflicts.")
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+/// Conflicted regions of each of `paths` under `root`; a file that can no
+/// longer be read is listed without hunks
+pub fn read(root: &Path, paths: Vec<String>) -> Vec<ConflictedFile> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let hunks = std::fs::read_to_string(root.join(&path))
+                .map(|text| parse(&text))
+                .unwrap_or_default();
+            ConflictedFile { path, hunks }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "a\n<<<<<<< ours\nX\n=======\nB\n>>>>>>> theirs\nc\n\
+                    <<<<<<< ours\none\n||||||| base\nzero\n=======\n>>>>>>> theirs\n\
+                    <<<<<<< unterminated\n";
+        let hunks = parse(text);
+        assert_eq!(
+            hunks,
+            vec![
+                ConflictHunk {
+                    start_line: 2,
+                    end_line: 6,
+                    ours: "X".to_string(),
+                    base: None,
+                    theirs: "B".to_string(),
+                },
+                ConflictHunk {
+                    start_line: 8,
+                    end_line: 13,
+                    ours: "one".to_string(),
+                    base: Some("zero".to_string()),
+                    theirs: String::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conflicted_paths() {
+        let output = "Applied patch to 'src/a b.rs' with conflicts.\nU src/a b.rs\n\
+                      Applied patch to 'lib.rs' cleanly.\n";
+        assert_eq!(conflicted_paths(output), vec!["src/a b.rs"]);
+    }
+}
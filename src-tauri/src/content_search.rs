@@ -0,0 +1,307 @@
+//! Project-wide content search (ripgrep-style).
+//!
+//! Walks the project with the same ignore rules as the file list, matches
+//! each text file line by line, and hands matches back one file at a time so
+//! callers can stream them. Searches are cancellable through a shared flag.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+use crate::file_index::IgnoreRules;
+use crate::{Error, Result};
+
+/// Traversal depth for content search
+const SEARCH_MAX_DEPTH: usize = 128;
+
+/// Files larger than this are skipped
+const MAX_SEARCH_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Leading bytes checked for NUL to detect binary files
+const BINARY_SNIFF_BYTES: usize = 8192;
+
+/// Longest line (in chars) returned; longer lines are clipped
+const MAX_LINE_CHARS: usize = 500;
+
+/// Upper bound on the compiled regex size, to reject pathological patterns
+const REGEX_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Options for a single content search
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    pub query: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    pub context_lines: usize,
+    pub max_results: usize,
+}
+
+/// A matching line with surrounding context
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentMatch {
+    /// 1-based line number
+    pub line_number: usize,
+    pub line: String,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// All matches found in one file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMatches {
+    /// Path relative to the project root
+    pub path: String,
+    pub matches: Vec<ContentMatch>,
+}
+
+/// Totals reported when a search finishes
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub total_matches: usize,
+    pub files_with_matches: usize,
+    pub files_searched: usize,
+    /// Whether `max_results` stopped the search early
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Compile the query into a regex, escaping it for literal searches
+pub fn build_matcher(options: &SearchOptions) -> Result<Regex> {
+    if options.query.is_empty() {
+        return Err(Error::Other("Search query cannot be empty".to_string()));
+    }
+    let pattern = if options.is_regex {
+        options.query.clone()
+    } else {
+        regex::escape(&options.query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| Error::Other(format!("Invalid search pattern: {e}")))
+}
+
+/// Search every text file under `root`, calling `on_file` for each file with matches
+pub fn search(
+    root: &Path,
+    rules: &IgnoreRules,
+    matcher: &Regex,
+    options: &SearchOptions,
+    cancel: &AtomicBool,
+    mut on_file: impl FnMut(FileMatches),
+) -> SearchSummary {
+    let mut summary = SearchSummary::default();
+
+    let walker = crate::file_index::project_walker(root, SEARCH_MAX_DEPTH, rules);
+    for entry in walker.filter_map(|e| e.ok()) {
+        if cancel.load(Ordering::Relaxed) {
+            summary.cancelled = true;
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let Some(text) = read_text_file(path) else {
+            continue;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        summary.files_searched += 1;
+
+        let remaining = options.max_results - summary.total_matches;
+        let (matches, hit_limit) = search_text(&text, matcher, options.context_lines, remaining);
+        if !matches.is_empty() {
+            summary.total_matches += matches.len();
+            summary.files_with_matches += 1;
+            on_file(FileMatches {
+                path: relative.to_string_lossy().replace('\\', "/"),
+                matches,
+            });
+        }
+        if hit_limit {
+            summary.truncated = true;
+            break;
+        }
+    }
+
+    summary
+}
+
+/// Read a file as UTF-8 text, skipping large, binary or undecodable files
+fn read_text_file(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.len() > MAX_SEARCH_FILE_BYTES {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(metadata.len() as usize);
+    std::fs::File::open(path).ok()?.read_to_end(&mut bytes).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Match `text` line by line; returns the matches and whether `limit` was hit
+fn search_text(
+    text: &str,
+    matcher: &Regex,
+    context_lines: usize,
+    limit: usize,
+) -> (Vec<ContentMatch>, bool) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+    let mut before: VecDeque<&str> = VecDeque::with_capacity(context_lines);
+
+    for (idx, line) in lines.iter().enumerate() {
+        if matcher.is_match(line) {
+            if matches.len() >= limit {
+                return (matches, true);
+            }
+            let after_end = (idx + 1 + context_lines).min(lines.len());
+            matches.push(ContentMatch {
+                line_number: idx + 1,
+                line: clip_line(line),
+                context_before: before.iter().map(|l| clip_line(l)).collect(),
+                context_after: lines[idx + 1..after_end].iter().map(|l| clip_line(l)).collect(),
+            });
+        }
+        if context_lines > 0 {
+            if before.len() == context_lines {
+                before.pop_front();
+            }
+            before.push_back(line);
+        }
+    }
+
+    (matches, false)
+}
+
+fn clip_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((idx, _)) => format!("{}…", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+/// Cancellation flags for in-flight searches keyed by search id
+#[derive(Debug, Default)]
+pub struct ContentSearches {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ContentSearches {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a search and return its cancellation flag
+    pub fn start(&self, search_id: &str) -> Result<Arc<AtomicBool>> {
+        let mut active = self.active.lock();
+        if active.contains_key(search_id) {
+            return Err(Error::Other(format!("Search {search_id} is already running")));
+        }
+        let flag = Arc::new(AtomicBool::new(false));
+        active.insert(search_id.to_string(), flag.clone());
+        Ok(flag)
+    }
+
+    /// Request cancellation; returns whether the search was running
+    pub fn cancel(&self, search_id: &str) -> bool {
+        match self.active.lock().get(search_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a finished search
+    pub fn finish(&self, search_id: &str) {
+        self.active.lock().remove(search_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(query: &str, is_regex: bool) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            is_regex,
+            case_sensitive: false,
+            context_lines: 1,
+            max_results: 100,
+        }
+    }
+
+    #[test]
+    fn test_search_text_with_context_and_limit() {
+        let text = "alpha\nfn foo() {}\nbeta\nFOO bar\ngamma";
+        let matcher = build_matcher(&options("foo", false)).unwrap();
+
+        let (matches, hit_limit) = search_text(text, &matcher, 1, 10);
+        assert!(!hit_limit);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].context_before, vec!["alpha"]);
+        assert_eq!(matches[0].context_after, vec!["beta"]);
+        assert_eq!(matches[1].line, "FOO bar");
+
+        let (matches, hit_limit) = search_text(text, &matcher, 0, 1);
+        assert!(hit_limit);
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].context_before.is_empty());
+    }
+
+    #[test]
+    fn test_build_matcher_literal_vs_regex() {
+        let literal = build_matcher(&options("a.b(", false)).unwrap();
+        assert!(literal.is_match("x a.b( y"));
+        assert!(!literal.is_match("axb("));
+
+        let regex = build_matcher(&options(r"fn \w+_test", true)).unwrap();
+        assert!(regex.is_match("fn parse_test()"));
+        assert!(build_matcher(&options("(", true)).is_err());
+        assert!(build_matcher(&options("", false)).is_err());
+    }
+
+    #[test]
+    fn test_search_respects_ignore_and_cancel() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn needle() {}\n").unwrap();
+        std::fs::write(root.join("target/out.rs"), "needle\n").unwrap();
+        std::fs::write(root.join("blob.bin"), b"needle\0\0").unwrap();
+
+        let opts = options("needle", false);
+        let matcher = build_matcher(&opts).unwrap();
+        let mut found = Vec::new();
+        let summary = search(&root, &IgnoreRules::default(), &matcher, &opts, &AtomicBool::new(false), |file| {
+            found.push(file.path)
+        });
+        assert_eq!(found, vec!["src/lib.rs"]);
+        assert_eq!(summary.total_matches, 1);
+        assert!(!summary.cancelled);
+
+        let summary = search(&root, &IgnoreRules::default(), &matcher, &opts, &AtomicBool::new(true), |_| {});
+        assert!(summary.cancelled);
+        assert_eq!(summary.files_searched, 0);
+    }
+}
@@ -0,0 +1,772 @@
+//! Versioned schema migrations
+//!
+//! Each migration runs once, in version order, inside its own transaction and
+//! is recorded in `schema_migrations`. A failing migration is rolled back and
+//! stops the run, leaving the database at the last good version. Add new
+//! schema changes by appending to `MIGRATIONS` with the next version number;
+//! never edit or reorder a migration that has shipped.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::database::default_project_color;
+use crate::Result;
+
+/// A single schema migration
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub up: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations in version order
+///
+/// Versions 1 and 2 predate the migrations table, so they check for existing
+/// columns before altering.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "session_status_columns",
+        up: |conn| {
+            if !has_column(conn, "session_metadata", "status")? {
+                conn.execute_batch(
+                    r#"
+                    ALTER TABLE session_metadata ADD COLUMN status TEXT NOT NULL DEFAULT 'idle';
+                    ALTER TABLE session_metadata ADD COLUMN first_message TEXT;
+                    ALTER TABLE session_metadata ADD COLUMN tasks_json TEXT;
+                    "#,
+                )?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 2,
+        name: "session_cwd_subpath",
+        up: |conn| {
+            if !has_column(conn, "session_metadata", "cwd_subpath")? {
+                conn.execute_batch("ALTER TABLE session_metadata ADD COLUMN cwd_subpath TEXT;")?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 3,
+        name: "session_fts",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE VIRTUAL TABLE session_fts USING fts5(
+                    session_id UNINDEXED,
+                    title,
+                    first_message,
+                    tags,
+                    content,
+                    tokenize = 'unicode61 remove_diacritics 2',
+                    prefix = '2 3'
+                );
+
+                INSERT INTO session_fts (session_id, title, first_message, tags, content)
+                    SELECT session_id, title, first_message, tags, '' FROM session_metadata;
+
+                CREATE TRIGGER session_fts_insert AFTER INSERT ON session_metadata BEGIN
+                    INSERT INTO session_fts (session_id, title, first_message, tags, content)
+                    VALUES (new.session_id, new.title, new.first_message, new.tags, '');
+                END;
+
+                CREATE TRIGGER session_fts_update
+                AFTER UPDATE OF title, first_message, tags ON session_metadata BEGIN
+                    UPDATE session_fts
+                    SET title = new.title, first_message = new.first_message, tags = new.tags
+                    WHERE session_id = old.session_id;
+                END;
+
+                CREATE TRIGGER session_fts_delete AFTER DELETE ON session_metadata BEGIN
+                    DELETE FROM session_fts WHERE session_id = old.session_id;
+                END;
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 4,
+        name: "project_appearance",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                ALTER TABLE projects ADD COLUMN color TEXT;
+                ALTER TABLE projects ADD COLUMN icon TEXT;
+                ALTER TABLE projects ADD COLUMN emoji TEXT;
+                "#,
+            )?;
+
+            let projects: Vec<(String, String)> = conn
+                .prepare("SELECT id, path FROM projects")?
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<std::result::Result<_, _>>()?;
+            for (id, path) in projects {
+                conn.execute(
+                    "UPDATE projects SET color = ?2 WHERE id = ?1",
+                    params![id, default_project_color(&path)],
+                )?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 5,
+        name: "session_tags",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE session_tags (
+                    session_id TEXT NOT NULL REFERENCES session_metadata(session_id) ON DELETE CASCADE,
+                    tag TEXT NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (session_id, tag)
+                );
+                CREATE INDEX idx_session_tags_tag ON session_tags(tag);
+
+                INSERT OR IGNORE INTO session_tags (session_id, tag)
+                    SELECT m.session_id, trim(t.value)
+                    FROM session_metadata m, json_each(m.tags) t
+                    WHERE m.tags IS NOT NULL AND json_valid(m.tags)
+                      AND t.type = 'text' AND trim(t.value) <> '';
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 6,
+        name: "session_preview_columns",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                ALTER TABLE session_metadata ADD COLUMN last_message_preview TEXT;
+                ALTER TABLE session_metadata ADD COLUMN turn_count INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE session_metadata ADD COLUMN files_changed_count INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE session_metadata ADD COLUMN last_model TEXT;
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 7,
+        name: "archived_projects",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE archived_projects (
+                    id TEXT PRIMARY KEY,
+                    path TEXT NOT NULL,
+                    display_name TEXT,
+                    archive_path TEXT NOT NULL,
+                    archived_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    session_count INTEGER NOT NULL DEFAULT 0
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 8,
+        name: "imported_transcripts",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE imported_transcripts (
+                    session_id TEXT PRIMARY KEY REFERENCES session_metadata(session_id) ON DELETE CASCADE,
+                    source TEXT NOT NULL,
+                    cwd TEXT,
+                    items_json TEXT NOT NULL,
+                    imported_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 9,
+        name: "session_stats",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE session_stats (
+                    session_id TEXT PRIMARY KEY REFERENCES session_metadata(session_id) ON DELETE CASCADE,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    cached_input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    reasoning_output_tokens INTEGER NOT NULL DEFAULT 0,
+                    total_tokens INTEGER NOT NULL DEFAULT 0,
+                    tool_calls_json TEXT NOT NULL DEFAULT '{}',
+                    active_seconds INTEGER NOT NULL DEFAULT 0,
+                    turn_started_at INTEGER,
+                    last_activity_at INTEGER
+                );
+
+                CREATE TABLE session_files (
+                    session_id TEXT NOT NULL REFERENCES session_metadata(session_id) ON DELETE CASCADE,
+                    path TEXT NOT NULL,
+                    PRIMARY KEY (session_id, path)
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 10,
+        name: "turn_usage",
+        up: |conn| {
+            // No foreign keys: usage history outlives deleted sessions and projects
+            conn.execute_batch(
+                r#"
+                CREATE TABLE turn_usage (
+                    session_id TEXT NOT NULL,
+                    turn_id TEXT NOT NULL,
+                    project_id TEXT,
+                    model TEXT,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    cached_input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    reasoning_output_tokens INTEGER NOT NULL DEFAULT 0,
+                    total_tokens INTEGER NOT NULL DEFAULT 0,
+                    cost_usd REAL,
+                    recorded_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (session_id, turn_id)
+                );
+                CREATE INDEX idx_turn_usage_recorded_at ON turn_usage(recorded_at);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 11,
+        name: "accounts",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE accounts (
+                    id TEXT PRIMARY KEY,
+                    identity TEXT NOT NULL UNIQUE,
+                    label TEXT NOT NULL,
+                    email TEXT,
+                    auth_mode TEXT NOT NULL,
+                    plan_type TEXT,
+                    auth_json TEXT NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    last_used_at INTEGER
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 12,
+        name: "turn_timings",
+        up: |conn| {
+            // No foreign key: agent threads have no session row
+            conn.execute_batch(
+                r#"
+                CREATE TABLE turn_timings (
+                    turn_id TEXT PRIMARY KEY,
+                    session_id TEXT NOT NULL,
+                    status TEXT,
+                    requested_at INTEGER,
+                    started_at INTEGER NOT NULL,
+                    completed_at INTEGER,
+                    elapsed_ms INTEGER NOT NULL DEFAULT 0,
+                    queued_ms INTEGER NOT NULL DEFAULT 0,
+                    thinking_ms INTEGER NOT NULL DEFAULT 0,
+                    tool_ms INTEGER NOT NULL DEFAULT 0,
+                    patch_ms INTEGER NOT NULL DEFAULT 0,
+                    tool_calls INTEGER NOT NULL DEFAULT 0,
+                    patches INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE INDEX idx_turn_timings_session ON turn_timings(session_id);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 13,
+        name: "turn_queue",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE turn_queue (
+                    id TEXT PRIMARY KEY,
+                    project_id TEXT NOT NULL,
+                    thread_id TEXT NOT NULL,
+                    prompt TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'queued',
+                    run_after INTEGER,
+                    created_at INTEGER NOT NULL,
+                    started_at INTEGER,
+                    finished_at INTEGER,
+                    turn_id TEXT,
+                    error TEXT
+                );
+                CREATE INDEX idx_turn_queue_project ON turn_queue(project_id, status, position);
+                CREATE INDEX idx_turn_queue_thread ON turn_queue(thread_id);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 14,
+        name: "allowlist_pattern_syntax",
+        up: |conn| {
+            // `?`, `\` and a leading `re:` became special; escape them in old entries
+            if !has_column(conn, "command_allowlist", "command_pattern")? {
+                return Ok(());
+            }
+            let entries = conn
+                .prepare("SELECT project_id, command_pattern FROM command_allowlist")?
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for (project_id, pattern) in entries {
+                let migrated = crate::allowlist::from_legacy(&pattern);
+                if migrated == pattern {
+                    continue;
+                }
+                conn.execute(
+                    "DELETE FROM command_allowlist WHERE project_id = ?1 AND command_pattern = ?2",
+                    params![project_id, pattern],
+                )?;
+                if !migrated.is_empty() {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO command_allowlist (project_id, command_pattern) VALUES (?1, ?2)",
+                        params![project_id, migrated],
+                    )?;
+                }
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 15,
+        name: "command_denylist",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE command_denylist (
+                    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                    command_pattern TEXT NOT NULL,
+                    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (project_id, command_pattern)
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 16,
+        name: "session_baselines",
+        up: |conn| {
+            // No foreign key, like turn timings; deleted with the session
+            conn.execute_batch(
+                r#"
+                CREATE TABLE session_baselines (
+                    session_id TEXT PRIMARY KEY,
+                    cwd TEXT NOT NULL,
+                    commit_sha TEXT NOT NULL,
+                    recorded_at INTEGER NOT NULL
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 17,
+        name: "project_env",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE project_env (
+                    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                    name TEXT NOT NULL,
+                    value_encrypted TEXT NOT NULL,
+                    updated_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+                    PRIMARY KEY (project_id, name)
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 18,
+        name: "codex_session_index",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE codex_session_index (
+                    file_path TEXT PRIMARY KEY,
+                    modified_at INTEGER NOT NULL,
+                    file_size INTEGER NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    summary_json TEXT NOT NULL
+                );
+
+                CREATE VIRTUAL TABLE codex_session_fts USING fts5(
+                    file_path UNINDEXED,
+                    project_name,
+                    cwd,
+                    git_branch,
+                    first_message,
+                    content,
+                    tokenize = 'unicode61 remove_diacritics 2',
+                    prefix = '2 3'
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 19,
+        name: "project_env_secret",
+        up: |conn| {
+            if !has_column(conn, "project_env", "is_secret")? {
+                conn.execute_batch(
+                    "ALTER TABLE project_env ADD COLUMN is_secret INTEGER NOT NULL DEFAULT 1;",
+                )?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 20,
+        name: "session_worktrees",
+        up: |conn| {
+            // No foreign key, like session baselines; deleted with the session
+            conn.execute_batch(
+                r#"
+                CREATE TABLE session_worktrees (
+                    session_id TEXT PRIMARY KEY,
+                    project_id TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    branch TEXT NOT NULL,
+                    base_branch TEXT,
+                    base_commit TEXT,
+                    created_at INTEGER NOT NULL
+                );
+                CREATE INDEX idx_session_worktrees_project ON session_worktrees(project_id);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 21,
+        name: "comparisons",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE comparison_groups (
+                    id TEXT PRIMARY KEY,
+                    project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                    prompt TEXT NOT NULL,
+                    base_branch TEXT,
+                    base_commit TEXT NOT NULL,
+                    winner_run_id TEXT,
+                    resolved_at INTEGER,
+                    created_at INTEGER NOT NULL
+                );
+                CREATE INDEX idx_comparison_groups_project ON comparison_groups(project_id);
+
+                CREATE TABLE comparison_runs (
+                    id TEXT PRIMARY KEY,
+                    group_id TEXT NOT NULL REFERENCES comparison_groups(id) ON DELETE CASCADE,
+                    position INTEGER NOT NULL,
+                    model TEXT,
+                    effort TEXT,
+                    worktree_path TEXT NOT NULL,
+                    branch TEXT NOT NULL,
+                    thread_id TEXT,
+                    status TEXT NOT NULL DEFAULT 'running',
+                    error TEXT,
+                    duration_ms INTEGER NOT NULL DEFAULT 0
+                );
+                CREATE INDEX idx_comparison_runs_group ON comparison_runs(group_id, position);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 22,
+        name: "turn_outcomes",
+        up: |conn| {
+            // No foreign key, like turn_timings: agent threads have no session row
+            conn.execute_batch(
+                r#"
+                CREATE TABLE turn_outcomes (
+                    turn_id TEXT NOT NULL,
+                    session_id TEXT NOT NULL,
+                    outcome TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    error TEXT,
+                    completed_at INTEGER NOT NULL,
+                    PRIMARY KEY (session_id, turn_id)
+                );
+                CREATE INDEX idx_turn_outcomes_session
+                    ON turn_outcomes(session_id, completed_at DESC);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        version: 23,
+        name: "recovered_turns",
+        up: |conn| {
+            conn.execute_batch(
+                r#"
+                CREATE TABLE recovered_turns (
+                    session_id TEXT NOT NULL REFERENCES session_metadata(session_id) ON DELETE CASCADE,
+                    turn_id TEXT NOT NULL,
+                    items_json TEXT NOT NULL,
+                    started_at INTEGER NOT NULL,
+                    last_event_at INTEGER NOT NULL,
+                    recovered_at INTEGER NOT NULL,
+                    PRIMARY KEY (session_id, turn_id)
+                );
+                "#,
+            )?;
+            Ok(())
+        },
+    },    Migration {
+        version: 24,
+        name: "hook_bypasses",
+        up: |conn| {
+            // Kept when the project is removed: it is an audit trail
+            conn.execute_batch(
+                r#"
+                CREATE TABLE hook_bypasses (
+                    id TEXT PRIMARY KEY,
+                    repo_path TEXT NOT NULL,
+                    operation TEXT NOT NULL,
+                    detail TEXT NOT NULL,
+                    hooks_json TEXT NOT NULL,
+                    created_at INTEGER NOT NULL
+                );
+                CREATE INDEX idx_hook_bypasses_repo ON hook_bypasses(repo_path, created_at DESC);
+                "#,
+            )?;
+            Ok(())
+        },
+    },
+];
+
+/// Applied and pending migration versions
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub current_version: u32,
+    pub latest_version: u32,
+    pub pending: Vec<String>,
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(conn
+        .prepare("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")?
+        .exists(params![table, column])?)
+}
+
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+/// Highest applied migration version (0 for a database without migrations)
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    ensure_migrations_table(conn)?;
+    let version: Option<u32> =
+        conn.query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))?;
+    Ok(version.unwrap_or(0))
+}
+
+fn pending(migrations: &'static [Migration], current: u32) -> impl Iterator<Item = &'static Migration> {
+    migrations.iter().filter(move |m| m.version > current)
+}
+
+/// Report the current version and the migrations still to apply
+pub fn status(conn: &Connection) -> Result<MigrationStatus> {
+    let current_version = current_version(conn)?;
+    Ok(MigrationStatus {
+        current_version,
+        latest_version: MIGRATIONS.last().map_or(0, |m| m.version),
+        pending: pending(MIGRATIONS, current_version)
+            .map(|m| format!("{:04}_{}", m.version, m.name))
+            .collect(),
+    })
+}
+
+/// Apply every pending migration; returns how many were applied
+pub fn run(conn: &mut Connection) -> Result<usize> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+/// Apply all pending migrations in one transaction and roll it back
+///
+/// Returns the names that would be applied, or the first failure.
+pub fn dry_run(conn: &mut Connection) -> Result<Vec<String>> {
+    dry_run_migrations(conn, MIGRATIONS)
+}
+
+fn run_migrations(conn: &mut Connection, migrations: &'static [Migration]) -> Result<usize> {
+    let current = current_version(conn)?;
+    let latest = migrations.last().map_or(0, |m| m.version);
+    if current > latest {
+        tracing::warn!(
+            "Database schema version {} is newer than this build supports ({})",
+            current,
+            latest
+        );
+        return Ok(0);
+    }
+
+    let mut applied = 0;
+    for migration in pending(migrations, current) {
+        let tx = conn.transaction()?;
+        let result = (migration.up)(&tx).and_then(|()| {
+            tx.execute(
+                "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+                params![migration.version, migration.name],
+            )?;
+            Ok(())
+        });
+        match result {
+            Ok(()) => tx.commit()?,
+            Err(err) => {
+                let _ = tx.rollback();
+                tracing::error!(
+                    "Migration {:04}_{} failed and was rolled back: {}",
+                    migration.version,
+                    migration.name,
+                    err
+                );
+                return Err(err);
+            }
+        }
+        tracing::info!("Applied migration {:04}_{}", migration.version, migration.name);
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+fn dry_run_migrations(conn: &mut Connection, migrations: &'static [Migration]) -> Result<Vec<String>> {
+    let current = current_version(conn)?;
+    let tx = conn.transaction()?;
+    let mut names = Vec::new();
+    for migration in pending(migrations, current) {
+        (migration.up)(&tx)?;
+        names.push(format!("{:04}_{}", migration.version, migration.name));
+    }
+    tx.rollback()?;
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            name: "create_items",
+            up: |conn| {
+                conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY);")?;
+                Ok(())
+            },
+        },
+        Migration {
+            version: 2,
+            name: "broken",
+            up: |conn| {
+                conn.execute_batch("ALTER TABLE items ADD COLUMN label TEXT;")?;
+                conn.execute_batch("ALTER TABLE missing ADD COLUMN x TEXT;")?;
+                Ok(())
+            },
+        },
+    ];
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        assert!(dry_run_migrations(&mut conn, TEST_MIGRATIONS).is_err());
+        assert!(!has_column(&conn, "items", "id").unwrap());
+
+        assert!(run_migrations(&mut conn, TEST_MIGRATIONS).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        assert!(has_column(&conn, "items", "id").unwrap());
+        assert!(!has_column(&conn, "items", "label").unwrap());
+    }
+
+    #[test]
+    fn test_run_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE projects (id TEXT PRIMARY KEY, path TEXT NOT NULL);
+            CREATE TABLE session_metadata (session_id TEXT PRIMARY KEY, title TEXT, tags TEXT);
+            INSERT INTO projects (id, path) VALUES ('p1', '/tmp/example');
+            INSERT INTO session_metadata (session_id, title, tags)
+                VALUES ('s1', 'Fix parser', '["bug", "parser", "bug"]');
+            CREATE TABLE command_allowlist (
+                project_id TEXT NOT NULL,
+                command_pattern TEXT NOT NULL,
+                PRIMARY KEY (project_id, command_pattern)
+            );
+            INSERT INTO command_allowlist (project_id, command_pattern) VALUES ('p1', 'ls  ?');
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(dry_run(&mut conn).unwrap().len(), MIGRATIONS.len());
+        assert!(!has_column(&conn, "session_metadata", "status").unwrap());
+
+        assert_eq!(run(&mut conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(run(&mut conn).unwrap(), 0);
+        assert!(has_column(&conn, "session_metadata", "cwd_subpath").unwrap());
+        assert!(status(&conn).unwrap().pending.is_empty());
+
+        let color: String = conn
+            .query_row("SELECT color FROM projects WHERE id = 'p1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(color, default_project_color("/tmp/example"));
+
+        let tags: i64 = conn
+            .query_row("SELECT COUNT(*) FROM session_tags WHERE session_id = 's1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tags, 2);
+
+        let pattern: String = conn
+            .query_row("SELECT command_pattern FROM command_allowlist", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(pattern, r"ls \?");
+    }
+}
@@ -3,11 +3,15 @@
 //! Uses SQLite to store project metadata, session extensions (tags, favorites),
 //! snapshots, and command allowlists.
 
+mod migrations;
 mod models;
+mod search;
 
+pub use migrations::MigrationStatus;
 pub use models::*;
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use parking_lot::Mutex;
 
@@ -21,6 +25,14 @@ pub struct Database {
 impl Database {
     /// Create a new database connection and initialize schema
     pub fn new(path: &Path) -> Result<Self> {
+        let database = Self::open(path)?;
+        database.migrate()?;
+        Ok(database)
+    }
+
+    /// Open the database without touching its schema; `migrate` has to run
+    /// before it is used
+    pub fn open(path: &Path) -> Result<Self> {
         let conn = Connection::open(path)?;
 
         // Enable WAL mode for better concurrent read performance
@@ -29,16 +41,30 @@ impl Database {
         // Enable foreign keys
         conn.execute_batch("PRAGMA foreign_keys = ON;")?;
 
-        // Initialize schema
-        Self::init_schema(&conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
 
+    /// Open the database for reading only, leaving its journal mode and
+    /// schema as they are; any write fails
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
         Ok(Self {
             conn: Mutex::new(conn),
         })
     }
 
+    /// Create missing tables and apply pending migrations
+    pub fn migrate(&self) -> Result<()> {
+        Self::init_schema(&mut self.conn.lock())
+    }
+
     /// Initialize the database schema
-    fn init_schema(conn: &Connection) -> Result<()> {
+    fn init_schema(conn: &mut Connection) -> Result<()> {
         conn.execute_batch(
             r#"
             -- Projects table
@@ -63,7 +89,8 @@ impl Database {
                 created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
                 status TEXT NOT NULL DEFAULT 'idle',
                 first_message TEXT,
-                tasks_json TEXT
+                tasks_json TEXT,
+                cwd_subpath TEXT
             );
 
             -- Snapshots for revert functionality
@@ -93,8 +120,8 @@ impl Database {
             "#,
         )?;
 
-        // Run migrations for existing databases
-        Self::run_migrations(conn)?;
+        // Apply versioned migrations for existing databases
+        migrations::run(conn)?;
 
         // Create status-dependent indexes after migrations
         conn.execute_batch(
@@ -109,28 +136,6 @@ impl Database {
         Ok(())
     }
 
-    /// Run database migrations to add new columns to existing tables
-    fn run_migrations(conn: &Connection) -> Result<()> {
-        // Check if status column exists in session_metadata
-        let has_status: bool = conn
-            .prepare("SELECT 1 FROM pragma_table_info('session_metadata') WHERE name = 'status'")?
-            .exists([])?;
-
-        if !has_status {
-            // Add new columns for session status tracking
-            conn.execute_batch(
-                r#"
-                ALTER TABLE session_metadata ADD COLUMN status TEXT NOT NULL DEFAULT 'idle';
-                ALTER TABLE session_metadata ADD COLUMN first_message TEXT;
-                ALTER TABLE session_metadata ADD COLUMN tasks_json TEXT;
-                CREATE INDEX IF NOT EXISTS idx_session_metadata_status ON session_metadata(status);
-                "#,
-            )?;
-        }
-
-        Ok(())
-    }
-
     /// Execute a closure within a database transaction
     /// If the closure returns Ok, the transaction is committed
     /// If the closure returns Err, the transaction is rolled back
@@ -155,14 +160,25 @@ impl Database {
         }
     }
 
+    /// Current schema version and pending migrations
+    pub fn migration_status(&self) -> Result<MigrationStatus> {
+        migrations::status(&self.conn.lock())
+    }
+
+    /// Check that pending migrations would apply cleanly, without changing the schema
+    pub fn dry_run_migrations(&self) -> Result<Vec<String>> {
+        migrations::dry_run(&mut self.conn.lock())
+    }
+
     // ==================== Project Operations ====================
 
     /// Insert a new project
     pub fn insert_project(&self, project: &Project) -> Result<()> {
         let conn = self.conn.lock();
         conn.execute(
-            r#"INSERT INTO projects (id, path, display_name, created_at, last_opened_at, settings_json)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            r#"INSERT INTO projects
+               (id, path, display_name, created_at, last_opened_at, settings_json, color, icon, emoji)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
             params![
                 project.id,
                 project.path,
@@ -170,6 +186,9 @@ impl Database {
                 project.created_at,
                 project.last_opened_at,
                 project.settings_json,
+                project.color,
+                project.icon,
+                project.emoji,
             ],
         )?;
         Ok(())
@@ -179,21 +198,13 @@ impl Database {
     pub fn get_all_projects(&self) -> Result<Vec<Project>> {
         let conn = self.conn.lock();
         let mut stmt = conn.prepare_cached(
-            r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json
+            r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json,
+                      color, icon, emoji
                FROM projects ORDER BY last_opened_at DESC NULLS LAST"#,
         )?;
 
         let projects = stmt
-            .query_map([], |row| {
-                Ok(Project {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    display_name: row.get(2)?,
-                    created_at: row.get(3)?,
-                    last_opened_at: row.get(4)?,
-                    settings_json: row.get(5)?,
-                })
-            })?
+            .query_map([], project_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(projects)
@@ -203,20 +214,14 @@ impl Database {
     pub fn get_project(&self, id: &str) -> Result<Option<Project>> {
         let conn = self.conn.lock();
         let mut stmt = conn.prepare(
-            r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json
+            r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json,
+                      color, icon, emoji
                FROM projects WHERE id = ?1"#,
         )?;
 
         let mut rows = stmt.query(params![id])?;
         if let Some(row) = rows.next()? {
-            Ok(Some(Project {
-                id: row.get(0)?,
-                path: row.get(1)?,
-                display_name: row.get(2)?,
-                created_at: row.get(3)?,
-                last_opened_at: row.get(4)?,
-                settings_json: row.get(5)?,
-            }))
+            Ok(Some(project_from_row(row)?))
         } else {
             Ok(None)
         }
@@ -232,89 +237,190 @@ impl Database {
         Ok(())
     }
 
-    /// Delete a project
+    /// Apply a partial update to a project in one transaction
+    ///
+    /// Returns `None` when no project has the given id. Settings are merged
+    /// into the stored JSON, so fields the caller did not send are preserved.
+    pub fn update_project(&self, id: &str, update: &ProjectUpdate) -> Result<Option<Project>> {
+        self.with_transaction(|conn| {
+            let settings_json: Option<Option<String>> = conn
+                .query_row(
+                    "SELECT settings_json FROM projects WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(settings_json) = settings_json else {
+                return Ok(None);
+            };
+
+            let settings_json = match &update.settings_patch {
+                Some(patch) => {
+                    let current: ProjectSettings = settings_json
+                        .as_deref()
+                        .and_then(|json| serde_json::from_str(json).ok())
+                        .unwrap_or_default();
+                    Some(serde_json::to_string(&current.merge_patch(patch)?)?)
+                }
+                None => settings_json,
+            };
+
+            conn.execute(
+                r#"UPDATE projects
+                   SET display_name = COALESCE(?2, display_name),
+                       settings_json = ?3,
+                       last_opened_at = COALESCE(?4, last_opened_at)
+                   WHERE id = ?1"#,
+                params![id, update.display_name, settings_json, update.last_opened_at],
+            )?;
+
+            let project = conn.query_row(
+                r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json,
+                          color, icon, emoji
+                   FROM projects WHERE id = ?1"#,
+                params![id],
+                project_from_row,
+            )?;
+            Ok(Some(project))
+        })
+    }
+
+    /// Replace a project's color, icon and emoji
+    ///
+    /// A missing color falls back to the project's deterministic default.
+    /// Returns `None` when no project has the given id.
+    pub fn set_project_appearance(
+        &self,
+        id: &str,
+        color: Option<&str>,
+        icon: Option<&str>,
+        emoji: Option<&str>,
+    ) -> Result<Option<Project>> {
+        self.with_transaction(|conn| {
+            let path: Option<String> = conn
+                .query_row("SELECT path FROM projects WHERE id = ?1", params![id], |row| row.get(0))
+                .optional()?;
+            let Some(path) = path else {
+                return Ok(None);
+            };
+
+            let color = color.unwrap_or_else(|| default_project_color(&path));
+            conn.execute(
+                "UPDATE projects SET color = ?2, icon = ?3, emoji = ?4 WHERE id = ?1",
+                params![id, color, icon, emoji],
+            )?;
+
+            let project = conn.query_row(
+                r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json,
+                          color, icon, emoji
+                   FROM projects WHERE id = ?1"#,
+                params![id],
+                project_from_row,
+            )?;
+            Ok(Some(project))
+        })
+    }
+
+    /// Delete a project together with its sessions, their snapshots and its allowlist
     pub fn delete_project(&self, id: &str) -> Result<()> {
-        let conn = self.conn.lock();
-        conn.execute("DELETE FROM projects WHERE id = ?1", params![id])?;
-        Ok(())
+        self.with_transaction(|conn| delete_project_rows(conn, id))
     }
 
     // ==================== Session Metadata Operations ====================
 
     /// Upsert session metadata
+    ///
+    /// The `tags` JSON replaces the session's rows in `session_tags`.
     pub fn upsert_session_metadata(&self, metadata: &SessionMetadata) -> Result<()> {
-        let conn = self.conn.lock();
-        conn.execute(
-            r#"INSERT INTO session_metadata
-               (session_id, project_id, title, tags, is_favorite, is_archived, last_accessed_at, created_at, status, first_message, tasks_json)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
-               ON CONFLICT(session_id) DO UPDATE SET
-                   title = excluded.title,
-                   tags = excluded.tags,
-                   is_favorite = excluded.is_favorite,
-                   is_archived = excluded.is_archived,
-                   last_accessed_at = excluded.last_accessed_at,
-                   status = excluded.status,
-                   first_message = COALESCE(session_metadata.first_message, excluded.first_message),
-                   tasks_json = excluded.tasks_json"#,
-            params![
-                metadata.session_id,
-                metadata.project_id,
-                metadata.title,
-                metadata.tags,
-                metadata.is_favorite,
-                metadata.is_archived,
-                metadata.last_accessed_at,
-                metadata.created_at,
-                metadata.status.as_str(),
-                metadata.first_message,
-                metadata.tasks_json,
-            ],
-        )?;
-        Ok(())
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT INTO session_metadata
+                   (session_id, project_id, title, tags, is_favorite, is_archived, last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                   ON CONFLICT(session_id) DO UPDATE SET
+                       title = excluded.title,
+                       tags = excluded.tags,
+                       is_favorite = excluded.is_favorite,
+                       is_archived = excluded.is_archived,
+                       last_accessed_at = excluded.last_accessed_at,
+                       status = excluded.status,
+                       first_message = COALESCE(session_metadata.first_message, excluded.first_message),
+                       tasks_json = excluded.tasks_json,
+                       cwd_subpath = COALESCE(excluded.cwd_subpath, session_metadata.cwd_subpath)"#,
+                params![
+                    metadata.session_id,
+                    metadata.project_id,
+                    metadata.title,
+                    metadata.tags,
+                    metadata.is_favorite,
+                    metadata.is_archived,
+                    metadata.last_accessed_at,
+                    metadata.created_at,
+                    metadata.status.as_str(),
+                    metadata.first_message,
+                    metadata.tasks_json,
+                    metadata.cwd_subpath,
+                ],
+            )?;
+
+            conn.execute(
+                "DELETE FROM session_tags WHERE session_id = ?1",
+                params![metadata.session_id],
+            )?;
+            let mut insert = conn.prepare_cached(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+            )?;
+            for tag in metadata.get_tags() {
+                insert.execute(params![metadata.session_id, tag])?;
+            }
+            Ok(())
+        })
     }
 
-    /// Get sessions for a project
-    pub fn get_sessions_for_project(&self, project_id: &str) -> Result<Vec<SessionMetadata>> {
+    /// Get a project's active (or, with `filter.archived`, archived) sessions,
+    /// optionally limited to favorites and/or sessions carrying every tag in
+    /// `filter.tags`
+    pub fn get_sessions_for_project(
+        &self,
+        project_id: &str,
+        filter: &SessionFilter,
+    ) -> Result<Vec<SessionMetadata>> {
         let conn = self.conn.lock();
         let mut stmt = conn.prepare_cached(
             r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
-                      last_accessed_at, created_at, status, first_message, tasks_json
+                      last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath,
+                      last_message_preview, turn_count, files_changed_count, last_model
                FROM session_metadata
-               WHERE project_id = ?1 AND is_archived = 0
-               ORDER BY last_accessed_at DESC NULLS LAST"#,
+               WHERE project_id = ?1 AND is_archived = ?4
+                 AND (?2 = 0 OR is_favorite = 1)
+                 AND (json_array_length(?3) = 0 OR session_id IN (
+                     SELECT session_id FROM session_tags
+                     WHERE tag IN (SELECT value FROM json_each(?3))
+                     GROUP BY session_id
+                     HAVING COUNT(*) = (SELECT COUNT(DISTINCT value) FROM json_each(?3))
+                 ))
+               ORDER BY is_favorite DESC, last_accessed_at DESC NULLS LAST"#,
         )?;
+        let tags_json = serde_json::to_string(&filter.tags)?;
 
         let sessions = stmt
-            .query_map(params![project_id], |row| {
-                let status_str: String = row.get::<_, Option<String>>(8)?.unwrap_or_else(|| "idle".to_string());
-                Ok(SessionMetadata {
-                    session_id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    title: row.get(2)?,
-                    tags: row.get(3)?,
-                    is_favorite: row.get(4)?,
-                    is_archived: row.get(5)?,
-                    last_accessed_at: row.get(6)?,
-                    created_at: row.get(7)?,
-                    status: SessionStatus::from_str(&status_str),
-                    first_message: row.get(9)?,
-                    tasks_json: row.get(10)?,
-                })
-            })?
+            .query_map(
+                params![project_id, filter.favorites_only, tags_json, filter.archived],
+                session_from_row,
+            )?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(sessions)
     }
 
-    /// Update session status
-    pub fn update_session_status(&self, session_id: &str, status: &SessionStatus) -> Result<()> {
+    /// Update session status; returns whether the session exists
+    pub fn update_session_status(&self, session_id: &str, status: &SessionStatus) -> Result<bool> {
         let conn = self.conn.lock();
-        conn.execute(
+        let updated = conn.execute(
             r#"UPDATE session_metadata SET status = ?1, last_accessed_at = strftime('%s', 'now') WHERE session_id = ?2"#,
             params![status.as_str(), session_id],
         )?;
-        Ok(())
+        Ok(updated > 0)
     }
 
     /// Get a session by ID (optimized direct lookup)
@@ -322,7 +428,8 @@ impl Database {
         let conn = self.conn.lock();
         let mut stmt = conn.prepare_cached(
             r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
-                      last_accessed_at, created_at, status, first_message, tasks_json
+                      last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath,
+                      last_message_preview, turn_count, files_changed_count, last_model
                FROM session_metadata
                WHERE session_id = ?1"#,
         )?;
@@ -330,24 +437,42 @@ impl Database {
         let mut rows = stmt.query(params![session_id])?;
 
         if let Some(row) = rows.next()? {
-            Ok(Some(SessionMetadata {
-                session_id: row.get(0)?,
-                project_id: row.get(1)?,
-                title: row.get(2)?,
-                tags: row.get(3)?,
-                is_favorite: row.get(4)?,
-                is_archived: row.get(5)?,
-                last_accessed_at: row.get(6)?,
-                created_at: row.get(7)?,
-                status: SessionStatus::from_str(row.get::<_, String>(8)?.as_str()),
-                first_message: row.get(9)?,
-                tasks_json: row.get(10)?,
-            }))
+            Ok(Some(session_from_row(row)?))
         } else {
             Ok(None)
         }
     }
 
+    /// The most recently opened project and its most recently used
+    /// unarchived session, read with two indexed lookups
+    pub fn get_most_recent_session(&self) -> Result<Option<(Project, Option<SessionMetadata>)>> {
+        let conn = self.conn.lock();
+        let project = conn
+            .prepare_cached(
+                r#"SELECT id, path, display_name, created_at, last_opened_at, settings_json,
+                          color, icon, emoji
+                   FROM projects ORDER BY last_opened_at DESC NULLS LAST LIMIT 1"#,
+            )?
+            .query_row([], project_from_row)
+            .optional()?;
+        let Some(project) = project else {
+            return Ok(None);
+        };
+        let session = conn
+            .prepare_cached(
+                r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
+                          last_accessed_at, created_at, status, first_message, tasks_json,
+                          cwd_subpath, last_message_preview, turn_count, files_changed_count,
+                          last_model
+                   FROM session_metadata
+                   WHERE project_id = ?1 AND is_archived = 0
+                   ORDER BY last_accessed_at DESC NULLS LAST LIMIT 1"#,
+            )?
+            .query_row(params![project.id], session_from_row)
+            .optional()?;
+        Ok(Some((project, session)))
+    }
+
     /// Update session first message (only if not already set)
     pub fn update_session_first_message(&self, session_id: &str, first_message: &str) -> Result<()> {
         let conn = self.conn.lock();
@@ -368,207 +493,3290 @@ impl Database {
         Ok(())
     }
 
-    /// Delete session metadata
-    pub fn delete_session_metadata(&self, session_id: &str) -> Result<()> {
+    /// Count a newly started turn and record its message preview and model
+    ///
+    /// `None` keeps the stored preview or model.
+    pub fn record_turn_started(
+        &self,
+        session_id: &str,
+        preview: Option<&str>,
+        model: Option<&str>,
+    ) -> Result<()> {
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"UPDATE session_metadata SET
+                       turn_count = turn_count + 1,
+                       last_message_preview = COALESCE(?2, last_message_preview),
+                       last_model = COALESCE(?3, last_model)
+                   WHERE session_id = ?1"#,
+                params![session_id, preview, model],
+            )?;
+            ensure_session_stats_row(conn, session_id)?;
+            conn.execute(
+                r#"UPDATE session_stats SET turn_started_at = ?2, last_activity_at = ?2
+                   WHERE session_id = ?1"#,
+                params![session_id, chrono::Utc::now().timestamp()],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record a completed item's message preview and file changes
+    pub fn record_session_item(
+        &self,
+        session_id: &str,
+        preview: Option<&str>,
+        files_changed: u32,
+    ) -> Result<()> {
         let conn = self.conn.lock();
         conn.execute(
-            "DELETE FROM session_metadata WHERE session_id = ?1",
-            params![session_id],
+            r#"UPDATE session_metadata SET
+                   last_message_preview = COALESCE(?2, last_message_preview),
+                   files_changed_count = files_changed_count + ?3
+               WHERE session_id = ?1"#,
+            params![session_id, preview, files_changed],
         )?;
         Ok(())
     }
 
-    // ==================== Snapshot Operations ====================
-
-    /// Insert a snapshot
-    pub fn insert_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+    /// Overwrite preview fields recomputed from a full transcript
+    pub fn set_session_preview(&self, session_id: &str, preview: &SessionPreview) -> Result<()> {
         let conn = self.conn.lock();
         conn.execute(
-            r#"INSERT INTO snapshots (id, session_id, created_at, snapshot_type, metadata_json)
-               VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            r#"UPDATE session_metadata SET
+                   last_message_preview = COALESCE(?2, last_message_preview),
+                   turn_count = ?3,
+                   files_changed_count = ?4
+               WHERE session_id = ?1"#,
             params![
-                snapshot.id,
-                snapshot.session_id,
-                snapshot.created_at,
-                snapshot.snapshot_type,
-                snapshot.metadata_json,
+                session_id,
+                preview.last_message_preview,
+                preview.turn_count,
+                preview.files_changed_count,
             ],
         )?;
         Ok(())
     }
 
-    /// Delete old snapshots, keeping only N most recent per session
-    pub fn cleanup_old_snapshots(&self, session_id: &str, keep_count: usize) -> Result<usize> {
-        let conn = self.conn.lock();
-
-        // Get snapshots sorted by created_at descending
-        let mut stmt = conn.prepare(
-            "SELECT id FROM snapshots WHERE session_id = ?1 ORDER BY created_at DESC"
-        )?;
+    /// Delete a session's metadata (title, tags, tasks) and its snapshots atomically
+    ///
+    /// Returns the number of snapshot rows removed.
+    pub fn delete_session(&self, session_id: &str) -> Result<usize> {
+        self.with_transaction(|conn| delete_session_rows(conn, session_id))
+    }
 
-        let snapshot_ids: Vec<String> = stmt.query(params![session_id])?
-            .mapped(|row| row.get(0))
-            .collect::<std::result::Result<Vec<_>, _>>()?;
+    /// Delete several sessions in one transaction; nothing is deleted if any step fails
+    ///
+    /// Returns the number of sessions that existed and were removed.
+    pub fn delete_sessions(&self, session_ids: &[String]) -> Result<usize> {
+        self.with_transaction(|conn| {
+            let mut exists =
+                conn.prepare_cached("SELECT 1 FROM session_metadata WHERE session_id = ?1")?;
+            let mut removed = 0;
+            for session_id in session_ids {
+                if exists.exists(params![session_id])? {
+                    removed += 1;
+                }
+                delete_session_rows(conn, session_id)?;
+            }
+            Ok(removed)
+        })
+    }
 
-        // Keep only the first N snapshots
-        if snapshot_ids.len() <= keep_count {
-            return Ok(0);
-        }
+    /// Archive or unarchive several sessions in one transaction
+    ///
+    /// Returns how many sessions were updated.
+    pub fn set_sessions_archived(&self, session_ids: &[String], archived: bool) -> Result<usize> {
+        self.with_transaction(|conn| {
+            let mut stmt = conn.prepare_cached(
+                r#"UPDATE session_metadata SET is_archived = ?1 WHERE session_id = ?2"#,
+            )?;
+            let mut updated = 0;
+            for session_id in session_ids {
+                updated += stmt.execute(params![archived, session_id])?;
+            }
+            Ok(updated)
+        })
+    }
 
-        // Single SQL delete: remove all snapshots for this session except the N most recent
-        let deleted_count = conn.execute(
-            r#"DELETE FROM snapshots 
-               WHERE session_id = ?1 
-               AND id NOT IN (
-                   SELECT id FROM snapshots 
-                   WHERE session_id = ?1 
-                   ORDER BY created_at DESC 
-                   LIMIT ?2
-               )"#,
-            params![session_id, keep_count],
-        )?;
+    /// Move several sessions to another project in one transaction
+    ///
+    /// Clears each session's `cwd_subpath`, which was relative to the old
+    /// project. Fails without changes when the project does not exist; returns
+    /// how many sessions were moved.
+    pub fn move_sessions_to_project(&self, session_ids: &[String], project_id: &str) -> Result<usize> {
+        self.with_transaction(|conn| {
+            let project_exists = conn
+                .prepare_cached("SELECT 1 FROM projects WHERE id = ?1")?
+                .exists(params![project_id])?;
+            if !project_exists {
+                return Err(crate::Error::ProjectNotFound(project_id.to_string()));
+            }
 
-        Ok(deleted_count)
+            let mut stmt = conn.prepare_cached(
+                r#"UPDATE session_metadata SET project_id = ?1, cwd_subpath = NULL
+                   WHERE session_id = ?2 AND project_id <> ?1"#,
+            )?;
+            // Unfinished queue entries follow their thread to the new project
+            let mut move_queued = conn.prepare_cached(
+                r#"UPDATE turn_queue SET project_id = ?1
+                   WHERE thread_id = ?2 AND status IN ('queued', 'running')"#,
+            )?;
+            let mut moved = 0;
+            for session_id in session_ids {
+                moved += stmt.execute(params![project_id, session_id])?;
+                move_queued.execute(params![project_id, session_id])?;
+            }
+            Ok(moved)
+        })
     }
 
-    /// Delete all snapshots older than a specific date
-    pub fn cleanup_snapshots_older_than(&self, days: i64) -> Result<usize> {
-        let conn = self.conn.lock();
-        let cutoff = chrono::Utc::now().timestamp() - (days * 86400);
+    /// Attach an imported (or orphaned) session to a project
+    ///
+    /// Creates the metadata row if missing, otherwise moves the existing row
+    /// (keeping title, tags and tasks) to `project_id`. Fails without changes
+    /// when the project does not exist.
+    pub fn adopt_session(&self, session_id: &str, project_id: &str) -> Result<SessionMetadata> {
+        self.with_transaction(|conn| {
+            let project_exists = conn
+                .prepare_cached("SELECT 1 FROM projects WHERE id = ?1")?
+                .exists(params![project_id])?;
+            if !project_exists {
+                return Err(crate::Error::ProjectNotFound(project_id.to_string()));
+            }
 
-        let count = conn.execute(
-            "DELETE FROM snapshots WHERE created_at < ?1",
-            params![cutoff],
-        )?;
+            let now = chrono::Utc::now().timestamp();
+            conn.execute(
+                r#"INSERT INTO session_metadata (session_id, project_id, created_at, last_accessed_at, status)
+                   VALUES (?1, ?2, ?3, ?3, 'idle')
+                   ON CONFLICT(session_id) DO UPDATE SET
+                       project_id = excluded.project_id,
+                       last_accessed_at = excluded.last_accessed_at"#,
+                params![session_id, project_id, now],
+            )?;
 
-        Ok(count)
+            let metadata = conn.query_row(
+                r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
+                          last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath,
+                          last_message_preview, turn_count, files_changed_count, last_model
+                   FROM session_metadata WHERE session_id = ?1"#,
+                params![session_id],
+                session_from_row,
+            )?;
+            Ok(metadata)
+        })
     }
 
-    /// Get snapshots for a session
-    pub fn get_snapshots_for_session(&self, session_id: &str) -> Result<Vec<Snapshot>> {
+    /// Add a tag to a session; returns the session's tags afterwards
+    pub fn add_session_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>> {
+        self.with_transaction(|conn| {
+            ensure_session_exists(conn, session_id)?;
+            conn.execute(
+                "INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)",
+                params![session_id, tag],
+            )?;
+            sync_session_tags_json(conn, session_id)
+        })
+    }
+
+    /// Remove a tag from a session; returns the session's tags afterwards
+    pub fn remove_session_tag(&self, session_id: &str, tag: &str) -> Result<Vec<String>> {
+        self.with_transaction(|conn| {
+            ensure_session_exists(conn, session_id)?;
+            conn.execute(
+                "DELETE FROM session_tags WHERE session_id = ?1 AND tag = ?2",
+                params![session_id, tag],
+            )?;
+            sync_session_tags_json(conn, session_id)
+        })
+    }
+
+    /// Tags in use with their session counts, optionally within one project
+    ///
+    /// Archived sessions are not counted.
+    pub fn list_session_tags(&self, project_id: Option<&str>) -> Result<Vec<SessionTagCount>> {
         let conn = self.conn.lock();
         let mut stmt = conn.prepare_cached(
-            r#"SELECT id, session_id, created_at, snapshot_type, metadata_json
-               FROM snapshots WHERE session_id = ?1 ORDER BY created_at DESC"#,
+            r#"SELECT t.tag, COUNT(*)
+               FROM session_tags t
+               JOIN session_metadata m ON m.session_id = t.session_id
+               WHERE m.is_archived = 0 AND (?1 IS NULL OR m.project_id = ?1)
+               GROUP BY t.tag
+               ORDER BY COUNT(*) DESC, t.tag"#,
         )?;
 
-        let snapshots = stmt
-            .query_map(params![session_id], |row| {
-                Ok(Snapshot {
-                    id: row.get(0)?,
-                    session_id: row.get(1)?,
-                    created_at: row.get(2)?,
-                    snapshot_type: row.get(3)?,
-                    metadata_json: row.get(4)?,
+        let tags = stmt
+            .query_map(params![project_id], |row| {
+                Ok(SessionTagCount {
+                    tag: row.get(0)?,
+                    count: row.get::<_, i64>(1)? as usize,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        Ok(snapshots)
+        Ok(tags)
     }
 
-    /// Get a snapshot by ID
-    pub fn get_snapshot(&self, id: &str) -> Result<Option<Snapshot>> {
+    /// Mark or unmark a session as favorite; returns whether the session exists
+    pub fn set_session_favorite(&self, session_id: &str, favorite: bool) -> Result<bool> {
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(
-            r#"SELECT id, session_id, created_at, snapshot_type, metadata_json
-               FROM snapshots WHERE id = ?1"#,
+        let updated = conn.execute(
+            "UPDATE session_metadata SET is_favorite = ?2 WHERE session_id = ?1",
+            params![session_id, favorite],
         )?;
-
-        let mut rows = stmt.query(params![id])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(Snapshot {
-                id: row.get(0)?,
-                session_id: row.get(1)?,
-                created_at: row.get(2)?,
-                snapshot_type: row.get(3)?,
-                metadata_json: row.get(4)?,
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(updated > 0)
     }
 
-    // ==================== Command Allowlist Operations ====================
-
-    /// Add command to allowlist
-    pub fn add_to_allowlist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+    /// Replace the indexed conversation text of a session
+    pub fn index_session_content(&self, session_id: &str, content: &str) -> Result<()> {
         let conn = self.conn.lock();
         conn.execute(
-            r#"INSERT OR IGNORE INTO command_allowlist (project_id, command_pattern)
-               VALUES (?1, ?2)"#,
-            params![project_id, command_pattern],
+            "UPDATE session_fts SET content = ?2 WHERE session_id = ?1",
+            params![session_id, content],
         )?;
         Ok(())
     }
 
-    /// Get allowlist for a project
-    pub fn get_allowlist(&self, project_id: &str) -> Result<Vec<String>> {
+    /// Index a session's conversation text unless some is indexed already
+    pub fn seed_session_content(&self, session_id: &str, content: &str) -> Result<()> {
         let conn = self.conn.lock();
-        let mut stmt = conn.prepare(
-            "SELECT command_pattern FROM command_allowlist WHERE project_id = ?1",
+        conn.execute(
+            "UPDATE session_fts SET content = ?2 WHERE session_id = ?1 AND content = ''",
+            params![session_id, content],
         )?;
-
-        let patterns = stmt
-            .query_map(params![project_id], |row| row.get(0))?
-            .collect::<std::result::Result<Vec<String>, _>>()?;
-
-        Ok(patterns)
+        Ok(())
     }
 
-    /// Remove command from allowlist
-    pub fn remove_from_allowlist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+    /// Add the text of a newly completed item to a session's indexed
+    /// conversation, dropping the oldest text past `MAX_INDEXED_CONTENT_CHARS`
+    pub fn append_session_content(&self, session_id: &str, text: &str) -> Result<()> {
         let conn = self.conn.lock();
         conn.execute(
-            "DELETE FROM command_allowlist WHERE project_id = ?1 AND command_pattern = ?2",
-            params![project_id, command_pattern],
+            r#"UPDATE session_fts
+               SET content = substr(
+                   CASE WHEN content = '' THEN ?2 ELSE content || char(10, 10) || ?2 END,
+                   -?3
+               )
+               WHERE session_id = ?1"#,
+            params![session_id, text, MAX_INDEXED_CONTENT_CHARS],
         )?;
         Ok(())
     }
 
-    // ==================== Maintenance Operations ====================
+    /// Create a session from an imported transcript, with its items and
+    /// search content, in one transaction
+    pub fn insert_imported_session(
+        &self,
+        session: &SessionMetadata,
+        transcript: &ImportedTranscript,
+        content: &str,
+    ) -> Result<()> {
+        self.with_transaction(|conn| {
+            let project_exists = conn
+                .prepare_cached("SELECT 1 FROM projects WHERE id = ?1")?
+                .exists(params![session.project_id])?;
+            if !project_exists {
+                return Err(crate::Error::ProjectNotFound(session.project_id.clone()));
+            }
+            insert_session_row(conn, session)?;
+            insert_imported_transcript(conn, &session.session_id, transcript)?;
+            conn.execute(
+                "UPDATE session_fts SET content = ?2 WHERE session_id = ?1",
+                params![session.session_id, content],
+            )?;
+            Ok(())
+        })
+    }
 
-    /// Run VACUUM if at least `interval_days` have passed since the last vacuum.
-    /// Tracks the last vacuum timestamp in an `app_metadata` table.
-    /// Returns `true` if VACUUM was executed.
-    pub fn vacuum_if_needed(&self, interval_days: i64) -> Result<bool> {
+    /// Stored items of an imported session; `None` for live sessions
+    pub fn get_imported_transcript(&self, session_id: &str) -> Result<Option<ImportedTranscript>> {
         let conn = self.conn.lock();
-
-        // Ensure metadata table exists
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS app_metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);"
-        )?;
-
-        // Check last vacuum time
-        let last_vacuum: Option<i64> = conn
+        let row = conn
             .query_row(
-                "SELECT CAST(value AS INTEGER) FROM app_metadata WHERE key = 'last_vacuum_at'",
-                [],
-                |row| row.get(0),
+                r#"SELECT source, cwd, items_json, imported_at
+                   FROM imported_transcripts WHERE session_id = ?1"#,
+                params![session_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
             )
-            .ok();
+            .optional()?;
+
+        row.map(|(source, cwd, items_json, imported_at)| {
+            Ok(ImportedTranscript {
+                source,
+                cwd,
+                items: serde_json::from_str(&items_json)?,
+                imported_at,
+            })
+        })
+        .transpose()
+    }
+
+    /// Ranked full-text search over session titles, first messages, tags and content
+    ///
+    /// Archived sessions are included. FTS5 picks the best BM25 matches, with
+    /// titles weighted highest, and those are reranked by `search::score`:
+    /// titles starting with the query, recent use and, given `project_id`,
+    /// sessions of that project move up. Sessions whose id, title, first
+    /// message or tags merely contain the query follow, most recent first.
+    /// Only sessions carrying every tag in `tags` are returned.
+    pub fn search_sessions(
+        &self,
+        query: &str,
+        tags: &[String],
+        favorites_only: bool,
+        project_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SessionSearchHit>> {
+        let Some(fts_query) = search::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let terms = search::query_terms(query);
+        let candidates = limit
+            .saturating_mul(search::CANDIDATES_PER_RESULT)
+            .max(search::MIN_CANDIDATES);
+        let tags_json = serde_json::to_string(tags)?;
+
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT m.session_id, m.project_id, m.title, m.tags, m.is_favorite, m.is_archived,
+                      m.last_accessed_at, m.created_at, m.status, m.first_message, m.tasks_json,
+                      m.cwd_subpath, m.last_message_preview, m.turn_count,
+                      m.files_changed_count, m.last_model,
+                      bm25(session_fts, 0.0, 10.0, 5.0, 4.0, 1.0) AS rank,
+                      snippet(session_fts, -1, ?2, ?3, '…', 16)
+               FROM session_fts
+               JOIN session_metadata m ON m.session_id = session_fts.session_id
+               WHERE session_fts MATCH ?1
+                 AND (?4 = 0 OR m.is_favorite = 1)
+                 AND (json_array_length(?6) = 0 OR m.session_id IN (
+                     SELECT session_id FROM session_tags
+                     WHERE tag IN (SELECT value FROM json_each(?6))
+                     GROUP BY session_id
+                     HAVING COUNT(*) = (SELECT COUNT(DISTINCT value) FROM json_each(?6))
+                 ))
+               ORDER BY rank, m.last_accessed_at DESC NULLS LAST
+               LIMIT ?5"#,
+        )?;
 
         let now = chrono::Utc::now().timestamp();
-        let threshold = interval_days * 86400;
+        let mut hits = stmt
+            .query_map(
+                params![
+                    fts_query,
+                    search::HIGHLIGHT_START.to_string(),
+                    search::HIGHLIGHT_END.to_string(),
+                    favorites_only,
+                    candidates as i64,
+                    tags_json,
+                ],
+                |row| {
+                    let snippet: Option<String> = row.get(17)?;
+                    let (snippet, highlights) = match snippet.filter(|s| !s.is_empty()) {
+                        Some(raw) => {
+                            let (text, highlights) = search::parse_snippet(&raw);
+                            (Some(text), highlights)
+                        }
+                        None => (None, Vec::new()),
+                    };
+                    let session = session_from_row(row)?;
+                    let rank: f64 = row.get(16)?;
+                    let score = search::score(
+                        &search::RankInput {
+                            bm25: rank,
+                            title: session.title.as_deref(),
+                            last_used_at: session.last_accessed_at.unwrap_or(session.created_at),
+                            in_current_project: project_id == Some(session.project_id.as_str()),
+                        },
+                        &terms,
+                        now,
+                    );
+                    let title_highlights = session
+                        .title
+                        .as_deref()
+                        .map(|title| search::title_highlights(title, &terms))
+                        .unwrap_or_default();
+                    Ok(SessionSearchHit {
+                        session,
+                        rank,
+                        score,
+                        snippet,
+                        highlights,
+                        title_highlights,
+                    })
+                },
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
 
-        if let Some(last) = last_vacuum {
-            if now - last < threshold {
-                return Ok(false);
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits.truncate(limit);
+
+        let Some(needle) = search::substring_needle(query).filter(|_| hits.len() < limit) else {
+            return Ok(hits);
+        };
+        let found: HashSet<String> = hits
+            .iter()
+            .map(|hit| hit.session.session_id.clone())
+            .collect();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
+                      last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath,
+                      last_message_preview, turn_count, files_changed_count, last_model
+               FROM session_metadata
+               WHERE (instr(lower(session_id), lower(?1)) > 0
+                      OR instr(lower(COALESCE(title, '')), lower(?1)) > 0
+                      OR instr(lower(COALESCE(first_message, '')), lower(?1)) > 0
+                      OR instr(lower(COALESCE(tags, '')), lower(?1)) > 0)
+                 AND (?2 = 0 OR is_favorite = 1)
+                 AND (json_array_length(?3) = 0 OR session_id IN (
+                     SELECT session_id FROM session_tags
+                     WHERE tag IN (SELECT value FROM json_each(?3))
+                     GROUP BY session_id
+                     HAVING COUNT(*) = (SELECT COUNT(DISTINCT value) FROM json_each(?3))
+                 ))
+               ORDER BY COALESCE(last_accessed_at, created_at) DESC
+               LIMIT ?4"#,
+        )?;
+        let fallback = stmt
+            .query_map(
+                params![needle, favorites_only, tags_json, limit as i64],
+                session_from_row,
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        for session in fallback {
+            if hits.len() >= limit {
+                break;
             }
+            if found.contains(&session.session_id) {
+                continue;
+            }
+            let title_highlights = session
+                .title
+                .as_deref()
+                .and_then(|title| search::substring_range(title, needle))
+                .into_iter()
+                .collect();
+            let (snippet, highlights) = session
+                .first_message
+                .as_deref()
+                .and_then(|message| search::excerpt(message, needle))
+                .map_or((None, Vec::new()), |(text, range)| {
+                    (Some(text), vec![range])
+                });
+            hits.push(SessionSearchHit {
+                session,
+                rank: 0.0,
+                score: 0.0,
+                snippet,
+                highlights,
+                title_highlights,
+            });
         }
+        Ok(hits)
+    }
 
-        // Run VACUUM (must be outside a transaction)
-        tracing::info!("Running database VACUUM");
-        conn.execute_batch("VACUUM;")?;
+    // ==================== Session Stats ====================
+
+    /// Store a thread's cumulative token usage as last reported by the app-server
+    pub fn record_token_usage(&self, session_id: &str, usage: &TokenUsage) -> Result<()> {
+        self.with_transaction(|conn| {
+            ensure_session_stats_row(conn, session_id)?;
+            conn.execute(
+                r#"UPDATE session_stats SET
+                       input_tokens = ?2,
+                       cached_input_tokens = ?3,
+                       output_tokens = ?4,
+                       reasoning_output_tokens = ?5,
+                       total_tokens = ?6
+                   WHERE session_id = ?1"#,
+                params![
+                    session_id,
+                    usage.input_tokens as i64,
+                    usage.cached_input_tokens as i64,
+                    usage.output_tokens as i64,
+                    usage.reasoning_output_tokens as i64,
+                    usage.total_tokens as i64,
+                ],
+            )?;
+            Ok(())
+        })
+    }
 
-        // Update last vacuum time
-        conn.execute(
-            "INSERT INTO app_metadata (key, value) VALUES ('last_vacuum_at', ?1)
-             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
-            params![now.to_string()],
-        )?;
+    /// Count a completed tool item of `kind` and remember the files it changed
+    pub fn record_tool_call(&self, session_id: &str, kind: &str, paths: &[String]) -> Result<()> {
+        self.with_transaction(|conn| {
+            ensure_session_stats_row(conn, session_id)?;
+            let updated = conn.execute(
+                r#"UPDATE session_stats SET
+                       tool_calls_json = json_set(
+                           tool_calls_json, '$.' || ?2,
+                           COALESCE(json_extract(tool_calls_json, '$.' || ?2), 0) + 1),
+                       last_activity_at = ?3
+                   WHERE session_id = ?1"#,
+                params![session_id, kind, chrono::Utc::now().timestamp()],
+            )?;
+            if updated == 0 {
+                return Ok(());
+            }
+            let mut insert = conn.prepare_cached(
+                "INSERT OR IGNORE INTO session_files (session_id, path) VALUES (?1, ?2)",
+            )?;
+            for path in paths {
+                insert.execute(params![session_id, path])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Add the running turn's duration to the session's active time
+    pub fn record_turn_finished(&self, session_id: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE session_stats SET
+                   active_seconds = active_seconds + MAX(0, ?2 - turn_started_at),
+                   turn_started_at = NULL,
+                   last_activity_at = ?2
+               WHERE session_id = ?1 AND turn_started_at IS NOT NULL"#,
+            params![session_id, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Usage metrics per session, newest first
+    ///
+    /// Each filter is optional; `since` keeps sessions created at or after
+    /// that Unix timestamp.
+    pub fn get_session_stats(
+        &self,
+        project_id: Option<&str>,
+        session_id: Option<&str>,
+        since: Option<i64>,
+    ) -> Result<Vec<SessionStats>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT m.session_id, m.project_id, m.title, m.first_message, m.last_model,
+                      m.created_at, m.turn_count,
+                      COALESCE(s.input_tokens, 0), COALESCE(s.cached_input_tokens, 0),
+                      COALESCE(s.output_tokens, 0), COALESCE(s.reasoning_output_tokens, 0),
+                      COALESCE(s.total_tokens, 0), COALESCE(s.tool_calls_json, '{}'),
+                      COALESCE(s.active_seconds, 0), s.last_activity_at,
+                      (SELECT COUNT(*) FROM session_files f WHERE f.session_id = m.session_id)
+               FROM session_metadata m
+               LEFT JOIN session_stats s ON s.session_id = m.session_id
+               WHERE (?1 IS NULL OR m.project_id = ?1)
+                 AND (?2 IS NULL OR m.session_id = ?2)
+                 AND (?3 IS NULL OR m.created_at >= ?3)
+               ORDER BY m.created_at DESC"#,
+        )?;
+
+        let stats = stmt
+            .query_map(params![project_id, session_id, since], |row| {
+                let mut metadata = SessionMetadata::new(&row.get::<_, String>(0)?, "");
+                metadata.title = row.get(2)?;
+                metadata.first_message = row.get(3)?;
+                let tool_calls: String = row.get(12)?;
+                Ok(SessionStats {
+                    title: metadata.get_display_name(),
+                    session_id: metadata.session_id,
+                    project_id: row.get(1)?,
+                    model: row.get(4)?,
+                    created_at: row.get(5)?,
+                    turn_count: row.get(6)?,
+                    token_usage: TokenUsage {
+                        input_tokens: row.get::<_, i64>(7)? as u64,
+                        cached_input_tokens: row.get::<_, i64>(8)? as u64,
+                        output_tokens: row.get::<_, i64>(9)? as u64,
+                        reasoning_output_tokens: row.get::<_, i64>(10)? as u64,
+                        total_tokens: row.get::<_, i64>(11)? as u64,
+                    },
+                    tool_calls: serde_json::from_str(&tool_calls).unwrap_or_default(),
+                    active_seconds: row.get(13)?,
+                    last_activity_at: row.get(14)?,
+                    files_touched: row.get(15)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    // ==================== Turn Usage ====================
+
+    /// Add one model call's tokens and cost to its turn's usage row
+    ///
+    /// The row takes the session's project and last model when first created.
+    pub fn add_turn_usage(
+        &self,
+        session_id: &str,
+        turn_id: &str,
+        usage: &TokenUsage,
+        cost_usd: Option<f64>,
+    ) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT INTO turn_usage
+               (session_id, turn_id, project_id, model, input_tokens, cached_input_tokens,
+                output_tokens, reasoning_output_tokens, total_tokens, cost_usd)
+               VALUES (?1, ?2,
+                   (SELECT project_id FROM session_metadata WHERE session_id = ?1),
+                   (SELECT last_model FROM session_metadata WHERE session_id = ?1),
+                   ?3, ?4, ?5, ?6, ?7, ?8)
+               ON CONFLICT(session_id, turn_id) DO UPDATE SET
+                   input_tokens = input_tokens + excluded.input_tokens,
+                   cached_input_tokens = cached_input_tokens + excluded.cached_input_tokens,
+                   output_tokens = output_tokens + excluded.output_tokens,
+                   reasoning_output_tokens = reasoning_output_tokens + excluded.reasoning_output_tokens,
+                   total_tokens = total_tokens + excluded.total_tokens,
+                   cost_usd = cost_usd + excluded.cost_usd"#,
+            params![
+                session_id,
+                turn_id,
+                usage.input_tokens as i64,
+                usage.cached_input_tokens as i64,
+                usage.output_tokens as i64,
+                usage.reasoning_output_tokens as i64,
+                usage.total_tokens as i64,
+                cost_usd,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Per-turn usage rows, oldest first
+    ///
+    /// `since` and `until` bound `recorded_at` (inclusive, exclusive).
+    pub fn get_turn_usage(
+        &self,
+        project_id: Option<&str>,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<Vec<TurnUsage>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT session_id, turn_id, project_id, model, input_tokens, cached_input_tokens,
+                      output_tokens, reasoning_output_tokens, total_tokens, cost_usd, recorded_at
+               FROM turn_usage
+               WHERE (?1 IS NULL OR project_id = ?1)
+                 AND (?2 IS NULL OR recorded_at >= ?2)
+                 AND (?3 IS NULL OR recorded_at < ?3)
+               ORDER BY recorded_at, session_id, turn_id"#,
+        )?;
+
+        let rows = stmt
+            .query_map(params![project_id, since, until], |row| {
+                Ok(TurnUsage {
+                    session_id: row.get(0)?,
+                    turn_id: row.get(1)?,
+                    project_id: row.get(2)?,
+                    model: row.get(3)?,
+                    token_usage: TokenUsage {
+                        input_tokens: row.get::<_, i64>(4)? as u64,
+                        cached_input_tokens: row.get::<_, i64>(5)? as u64,
+                        output_tokens: row.get::<_, i64>(6)? as u64,
+                        reasoning_output_tokens: row.get::<_, i64>(7)? as u64,
+                        total_tokens: row.get::<_, i64>(8)? as u64,
+                    },
+                    cost_usd: row.get(9)?,
+                    recorded_at: row.get(10)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(rows)
+    }
+
+    // ==================== Turn Timings ====================
+
+    pub fn insert_turn_timings(&self, timings: &TurnTimings) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT OR REPLACE INTO turn_timings
+               (turn_id, session_id, status, requested_at, started_at, completed_at, elapsed_ms,
+                queued_ms, thinking_ms, tool_ms, patch_ms, tool_calls, patches)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"#,
+            params![
+                timings.turn_id,
+                timings.session_id,
+                timings.status,
+                timings.requested_at,
+                timings.started_at,
+                timings.completed_at,
+                timings.elapsed_ms,
+                timings.queued_ms,
+                timings.thinking_ms,
+                timings.tool_ms,
+                timings.patch_ms,
+                timings.tool_calls,
+                timings.patches,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_turn_timings(&self, turn_id: &str) -> Result<Option<TurnTimings>> {
+        let conn = self.conn.lock();
+        let timings = conn
+            .query_row(
+                r#"SELECT turn_id, session_id, status, requested_at, started_at, completed_at,
+                          elapsed_ms, queued_ms, thinking_ms, tool_ms, patch_ms, tool_calls, patches
+                   FROM turn_timings WHERE turn_id = ?1"#,
+                params![turn_id],
+                |row| {
+                    Ok(TurnTimings {
+                        turn_id: row.get(0)?,
+                        session_id: row.get(1)?,
+                        status: row.get(2)?,
+                        requested_at: row.get(3)?,
+                        started_at: row.get(4)?,
+                        completed_at: row.get(5)?,
+                        elapsed_ms: row.get(6)?,
+                        queued_ms: row.get(7)?,
+                        thinking_ms: row.get(8)?,
+                        tool_ms: row.get(9)?,
+                        patch_ms: row.get(10)?,
+                        tool_calls: row.get(11)?,
+                        patches: row.get(12)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(timings)
+    }
+
+    /// Store a finished turn's outcome and roll it up into its session's status
+    ///
+    /// The session takes the status of its latest outcome. Returns the new
+    /// status, or `None` when the thread has no session row (the outcome is
+    /// still stored).
+    pub fn record_turn_outcome(&self, record: &TurnOutcomeRecord) -> Result<Option<SessionStatus>> {
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT OR REPLACE INTO turn_outcomes
+                   (turn_id, session_id, outcome, status, error, completed_at)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    record.turn_id,
+                    record.session_id,
+                    record.outcome.as_str(),
+                    record.status,
+                    record.error,
+                    record.completed_at,
+                ],
+            )?;
+            let status = record.outcome.session_status();
+            let updated = conn.execute(
+                "UPDATE session_metadata SET status = ?1 WHERE session_id = ?2",
+                params![status.as_str(), record.session_id],
+            )?;
+            Ok((updated > 0).then_some(status))
+        })
+    }
+
+    /// Store a recovered turn and, unless the turn already has an outcome,
+    /// record it as ended in error; returns the session's new status
+    pub fn record_recovered_turn(&self, turn: &RecoveredTurn) -> Result<Option<SessionStatus>> {
+        let items_json = serde_json::to_string(&turn.items)?;
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT OR REPLACE INTO recovered_turns
+                   (session_id, turn_id, items_json, started_at, last_event_at, recovered_at)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    turn.session_id,
+                    turn.turn_id,
+                    items_json,
+                    turn.started_at,
+                    turn.last_event_at,
+                    turn.recovered_at,
+                ],
+            )?;
+            // An app-server disconnect may already have classified it
+            let inserted = conn.execute(
+                r#"INSERT OR IGNORE INTO turn_outcomes
+                   (turn_id, session_id, outcome, status, error, completed_at)
+                   VALUES (?1, ?2, ?3, 'crashed', 'The app exited before the turn finished', ?4)"#,
+                params![
+                    turn.turn_id,
+                    turn.session_id,
+                    TurnOutcome::Error.as_str(),
+                    turn.last_event_at,
+                ],
+            )?;
+            if inserted == 0 {
+                return Ok(None);
+            }
+            let status = TurnOutcome::Error.session_status();
+            conn.execute(
+                "UPDATE session_metadata SET status = ?1 WHERE session_id = ?2",
+                params![status.as_str(), turn.session_id],
+            )?;
+            Ok(Some(status))
+        })
+    }
+
+    /// Record that the hooks of `repo_path` were skipped
+    pub fn insert_hook_bypass(&self, bypass: &HookBypass) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT INTO hook_bypasses (id, repo_path, operation, detail, hooks_json, created_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![
+                bypass.id,
+                bypass.repo_path,
+                bypass.operation,
+                bypass.detail,
+                serde_json::to_string(&bypass.hooks)?,
+                bypass.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Hook bypasses of a repository, newest first
+    pub fn list_hook_bypasses(&self, repo_path: &str, limit: u32) -> Result<Vec<HookBypass>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT id, operation, detail, hooks_json, created_at
+               FROM hook_bypasses WHERE repo_path = ?1
+               ORDER BY created_at DESC, rowid DESC LIMIT ?2"#,
+        )?;
+        let rows = stmt
+            .query_map(params![repo_path, limit], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(id, operation, detail, hooks_json, created_at)| {
+                Ok(HookBypass {
+                    id,
+                    repo_path: repo_path.to_string(),
+                    operation,
+                    detail,
+                    hooks: serde_json::from_str(&hooks_json)?,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// A session's recovered turns, oldest first
+    pub fn list_recovered_turns(&self, session_id: &str) -> Result<Vec<RecoveredTurn>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT turn_id, items_json, started_at, last_event_at, recovered_at
+               FROM recovered_turns WHERE session_id = ?1
+               ORDER BY started_at"#,
+        )?;
+        let rows = stmt
+            .query_map(params![session_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(turn_id, items_json, started_at, last_event_at, recovered_at)| {
+                Ok(RecoveredTurn {
+                    session_id: session_id.to_string(),
+                    turn_id,
+                    items: serde_json::from_str(&items_json)?,
+                    started_at,
+                    last_event_at,
+                    recovered_at,
+                })
+            })
+            .collect()
+    }
+
+    /// A session's turn outcomes, newest first
+    pub fn list_turn_outcomes(
+        &self,
+        session_id: &str,
+        limit: usize,
+    ) -> Result<Vec<TurnOutcomeRecord>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT turn_id, session_id, outcome, status, error, completed_at
+               FROM turn_outcomes WHERE session_id = ?1
+               ORDER BY completed_at DESC LIMIT ?2"#,
+        )?;
+        let records = stmt
+            .query_map(params![session_id, limit as i64], |row| {
+                Ok(TurnOutcomeRecord {
+                    turn_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    outcome: TurnOutcome::from_str(&row.get::<_, String>(2)?),
+                    status: row.get(3)?,
+                    error: row.get(4)?,
+                    completed_at: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(records)
+    }
+
+    /// Insert or replace a project environment variable
+    pub fn set_project_env(
+        &self,
+        project_id: &str,
+        name: &str,
+        value_encrypted: &str,
+        is_secret: bool,
+    ) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT INTO project_env (project_id, name, value_encrypted, is_secret, updated_at)
+               VALUES (?1, ?2, ?3, ?4, strftime('%s', 'now'))
+               ON CONFLICT(project_id, name) DO UPDATE SET
+                   value_encrypted = excluded.value_encrypted,
+                   is_secret = excluded.is_secret,
+                   updated_at = excluded.updated_at"#,
+            params![project_id, name, value_encrypted, is_secret],
+        )?;
+        Ok(())
+    }
+
+    /// A project's environment variables, sorted by name
+    pub fn get_project_env(&self, project_id: &str) -> Result<Vec<ProjectEnvRow>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            r#"SELECT name, value_encrypted, is_secret, updated_at FROM project_env
+               WHERE project_id = ?1 ORDER BY name"#,
+        )?;
+        let rows = stmt
+            .query_map(params![project_id], |row| {
+                Ok(ProjectEnvRow {
+                    name: row.get(0)?,
+                    value_encrypted: row.get(1)?,
+                    is_secret: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Returns whether the variable existed
+    pub fn delete_project_env(&self, project_id: &str, name: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let removed = conn.execute(
+            "DELETE FROM project_env WHERE project_id = ?1 AND name = ?2",
+            params![project_id, name],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Modification time and size of every indexed Codex CLI rollout, by path
+    pub fn get_codex_index_stamps(&self) -> Result<HashMap<String, (i64, i64)>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT file_path, modified_at, file_size FROM codex_session_index")?;
+        let stamps = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?;
+        Ok(stamps)
+    }
+
+    /// Add or replace Codex CLI rollouts in the search index
+    pub fn upsert_codex_index_entries(&self, entries: &[CodexIndexEntry]) -> Result<()> {
+        self.with_transaction(|conn| {
+            for entry in entries {
+                conn.execute(
+                    r#"INSERT INTO codex_session_index
+                           (file_path, modified_at, file_size, timestamp, summary_json)
+                       VALUES (?1, ?2, ?3, ?4, ?5)
+                       ON CONFLICT(file_path) DO UPDATE SET
+                           modified_at = excluded.modified_at,
+                           file_size = excluded.file_size,
+                           timestamp = excluded.timestamp,
+                           summary_json = excluded.summary_json"#,
+                    params![
+                        entry.file_path,
+                        entry.modified_at,
+                        entry.file_size,
+                        entry.timestamp,
+                        entry.summary_json,
+                    ],
+                )?;
+                conn.execute(
+                    "DELETE FROM codex_session_fts WHERE file_path = ?1",
+                    params![entry.file_path],
+                )?;
+                conn.execute(
+                    r#"INSERT INTO codex_session_fts
+                           (file_path, project_name, cwd, git_branch, first_message, content)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                    params![
+                        entry.file_path,
+                        entry.project_name,
+                        entry.cwd,
+                        entry.git_branch,
+                        entry.first_message,
+                        entry.content,
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Drop rollouts from the search index; returns how many were indexed
+    pub fn remove_codex_index_entries(&self, file_paths: &[String]) -> Result<usize> {
+        self.with_transaction(|conn| {
+            let mut removed = 0;
+            for file_path in file_paths {
+                removed += conn.execute(
+                    "DELETE FROM codex_session_index WHERE file_path = ?1",
+                    params![file_path],
+                )?;
+                conn.execute(
+                    "DELETE FROM codex_session_fts WHERE file_path = ?1",
+                    params![file_path],
+                )?;
+            }
+            Ok(removed)
+        })
+    }
+
+    /// Summaries of indexed rollouts matching `query`, best match first,
+    /// or the most recent ones when the query is blank
+    pub fn search_codex_index(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let summaries = match search::fts_query(query) {
+            Some(fts_query) => {
+                let mut stmt = conn.prepare_cached(
+                    r#"SELECT i.summary_json
+                       FROM codex_session_fts
+                       JOIN codex_session_index i ON i.file_path = codex_session_fts.file_path
+                       WHERE codex_session_fts MATCH ?1
+                       ORDER BY bm25(codex_session_fts, 0.0, 5.0, 3.0, 3.0, 4.0, 1.0),
+                                i.timestamp DESC
+                       LIMIT ?2"#,
+                )?;
+                let rows = stmt
+                    .query_map(params![fts_query, limit as i64], |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<String>, _>>()?;
+                rows
+            }
+            None => {
+                let mut stmt = conn.prepare_cached(
+                    "SELECT summary_json FROM codex_session_index ORDER BY timestamp DESC LIMIT ?1",
+                )?;
+                let rows = stmt
+                    .query_map(params![limit as i64], |row| row.get(0))?
+                    .collect::<std::result::Result<Vec<String>, _>>()?;
+                rows
+            }
+        };
+        Ok(summaries)
+    }
+
+    /// Record the commit a session started from; an existing baseline is kept
+    pub fn insert_session_baseline(&self, baseline: &SessionBaseline) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT OR IGNORE INTO session_baselines (session_id, cwd, commit_sha, recorded_at)
+               VALUES (?1, ?2, ?3, ?4)"#,
+            params![
+                baseline.session_id,
+                baseline.cwd,
+                baseline.commit_sha,
+                baseline.recorded_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_session_baseline(&self, session_id: &str) -> Result<Option<SessionBaseline>> {
+        let conn = self.conn.lock();
+        let baseline = conn
+            .query_row(
+                r#"SELECT session_id, cwd, commit_sha, recorded_at
+                   FROM session_baselines WHERE session_id = ?1"#,
+                params![session_id],
+                |row| {
+                    Ok(SessionBaseline {
+                        session_id: row.get(0)?,
+                        cwd: row.get(1)?,
+                        commit_sha: row.get(2)?,
+                        recorded_at: row.get(3)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(baseline)
+    }
+
+    /// Record the worktree made for a session
+    pub fn insert_session_worktree(&self, worktree: &SessionWorktree) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT OR REPLACE INTO session_worktrees
+               (session_id, project_id, path, branch, base_branch, base_commit, created_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![
+                worktree.session_id,
+                worktree.project_id,
+                worktree.path,
+                worktree.branch,
+                worktree.base_branch,
+                worktree.base_commit,
+                worktree.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_session_worktree(&self, session_id: &str) -> Result<Option<SessionWorktree>> {
+        let conn = self.conn.lock();
+        let worktree = conn
+            .query_row(
+                &format!("{SESSION_WORKTREE_SELECT} WHERE session_id = ?1"),
+                params![session_id],
+                session_worktree_from_row,
+            )
+            .optional()?;
+        Ok(worktree)
+    }
+
+    /// Worktrees made for a project's sessions, oldest first
+    pub fn list_session_worktrees(&self, project_id: &str) -> Result<Vec<SessionWorktree>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "{SESSION_WORKTREE_SELECT} WHERE project_id = ?1 ORDER BY created_at"
+        ))?;
+        let worktrees = stmt
+            .query_map(params![project_id], session_worktree_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(worktrees)
+    }
+
+    /// Worktrees of every project's sessions, oldest first
+    pub fn list_all_session_worktrees(&self) -> Result<Vec<SessionWorktree>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!("{SESSION_WORKTREE_SELECT} ORDER BY created_at"))?;
+        let worktrees = stmt
+            .query_map([], session_worktree_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(worktrees)
+    }
+
+    /// Forget a session's worktree once it is cleaned up
+    pub fn delete_session_worktree(&self, session_id: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let deleted = conn.execute(
+            "DELETE FROM session_worktrees WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // ==================== Comparisons ====================
+
+    /// Record a comparison and its runs
+    pub fn insert_comparison(&self, group: &ComparisonGroup, runs: &[ComparisonRun]) -> Result<()> {
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT INTO comparison_groups
+                   (id, project_id, prompt, base_branch, base_commit, winner_run_id, resolved_at, created_at)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+                params![
+                    group.id,
+                    group.project_id,
+                    group.prompt,
+                    group.base_branch,
+                    group.base_commit,
+                    group.winner_run_id,
+                    group.resolved_at,
+                    group.created_at,
+                ],
+            )?;
+            for run in runs {
+                conn.execute(
+                    r#"INSERT INTO comparison_runs
+                       (id, group_id, position, model, effort, worktree_path, branch, thread_id,
+                        status, error, duration_ms)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+                    params![
+                        run.id,
+                        run.group_id,
+                        run.position,
+                        run.model,
+                        run.effort,
+                        run.worktree_path,
+                        run.branch,
+                        run.thread_id,
+                        run.status.as_str(),
+                        run.error,
+                        run.duration_ms,
+                    ],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Save a run's thread and outcome
+    pub fn update_comparison_run(&self, run: &ComparisonRun) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE comparison_runs
+               SET thread_id = ?2, status = ?3, error = ?4, duration_ms = ?5
+               WHERE id = ?1"#,
+            params![
+                run.id,
+                run.thread_id,
+                run.status.as_str(),
+                run.error,
+                run.duration_ms,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Mark runs left running by a previous app session as interrupted
+    pub fn interrupt_stale_comparison_runs(&self) -> Result<usize> {
+        let conn = self.conn.lock();
+        let updated = conn.execute(
+            r#"UPDATE comparison_runs
+               SET status = 'interrupted', error = 'The app quit before the run finished'
+               WHERE status = 'running'"#,
+            [],
+        )?;
+        Ok(updated)
+    }
+
+    /// Mark a comparison resolved, with the run that was kept if any
+    pub fn resolve_comparison(
+        &self,
+        group_id: &str,
+        winner_run_id: Option<&str>,
+        resolved_at: i64,
+    ) -> Result<bool> {
+        let conn = self.conn.lock();
+        let updated = conn.execute(
+            "UPDATE comparison_groups SET winner_run_id = ?2, resolved_at = ?3 WHERE id = ?1",
+            params![group_id, winner_run_id, resolved_at],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn get_comparison(&self, group_id: &str) -> Result<Option<ComparisonGroup>> {
+        let conn = self.conn.lock();
+        let group = conn
+            .query_row(
+                &format!("{COMPARISON_GROUP_SELECT} WHERE id = ?1"),
+                params![group_id],
+                comparison_group_from_row,
+            )
+            .optional()?;
+        Ok(group)
+    }
+
+    /// A project's comparisons, newest first
+    pub fn list_comparisons(&self, project_id: &str) -> Result<Vec<ComparisonGroup>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(&format!(
+            "{COMPARISON_GROUP_SELECT} WHERE project_id = ?1 ORDER BY created_at DESC"
+        ))?;
+        let groups = stmt
+            .query_map(params![project_id], comparison_group_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(groups)
+    }
+
+    /// Runs of a comparison, in the order they were requested
+    pub fn list_comparison_runs(&self, group_id: &str) -> Result<Vec<ComparisonRun>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            r#"SELECT id, group_id, position, model, effort, worktree_path, branch, thread_id,
+                      status, error, duration_ms
+               FROM comparison_runs WHERE group_id = ?1 ORDER BY position"#,
+        )?;
+        let runs = stmt
+            .query_map(params![group_id], |row| {
+                Ok(ComparisonRun {
+                    id: row.get(0)?,
+                    group_id: row.get(1)?,
+                    position: row.get(2)?,
+                    model: row.get(3)?,
+                    effort: row.get(4)?,
+                    worktree_path: row.get(5)?,
+                    branch: row.get(6)?,
+                    thread_id: row.get(7)?,
+                    status: ComparisonRunStatus::from_str(&row.get::<_, String>(8)?),
+                    error: row.get(9)?,
+                    duration_ms: row.get(10)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(runs)
+    }
+
+    // ==================== Turn Queue ====================
+
+    /// Append a prompt to the end of a project's queue
+    pub fn insert_queued_turn(
+        &self,
+        project_id: &str,
+        thread_id: &str,
+        prompt: &str,
+        run_after: Option<i64>,
+        now_ms: i64,
+    ) -> Result<QueuedTurn> {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT INTO turn_queue
+                   (id, project_id, thread_id, prompt, position, run_after, created_at)
+                   VALUES (?1, ?2, ?3, ?4,
+                           (SELECT COALESCE(MAX(position), 0) + 1 FROM turn_queue WHERE project_id = ?2),
+                           ?5, ?6)"#,
+                params![id, project_id, thread_id, prompt, run_after, now_ms],
+            )?;
+            Ok(())
+        })?;
+        self.get_queued_turn(&id)?
+            .ok_or_else(|| crate::Error::Other("Queued turn disappeared".to_string()))
+    }
+
+    pub fn get_queued_turn(&self, id: &str) -> Result<Option<QueuedTurn>> {
+        let conn = self.conn.lock();
+        let turn = conn
+            .query_row(
+                &format!("SELECT {QUEUED_TURN_COLUMNS} FROM turn_queue WHERE id = ?1"),
+                params![id],
+                queued_turn_from_row,
+            )
+            .optional()?;
+        Ok(turn)
+    }
+
+    /// Queue entries, running and queued first in run order, then finished
+    /// ones newest first
+    ///
+    /// `project_id` limits the list to one project; finished entries are
+    /// only included with `include_finished`.
+    pub fn list_turn_queue(
+        &self,
+        project_id: Option<&str>,
+        include_finished: bool,
+    ) -> Result<Vec<QueuedTurn>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(&format!(
+            r#"SELECT {QUEUED_TURN_COLUMNS} FROM turn_queue
+               WHERE (?1 IS NULL OR project_id = ?1)
+                 AND (?2 OR status IN ('queued', 'running'))
+               ORDER BY status NOT IN ('queued', 'running'),
+                        status <> 'running',
+                        CASE WHEN status IN ('queued', 'running') THEN position END,
+                        finished_at DESC"#
+        ))?;
+        let rows = stmt
+            .query_map(params![project_id, include_finished], queued_turn_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Give a project's queued entries the order of `ids`
+    ///
+    /// `ids` must name every queued entry of the project exactly once.
+    pub fn reorder_turn_queue(&self, project_id: &str, ids: &[String]) -> Result<()> {
+        self.with_transaction(|conn| {
+            let mut queued: Vec<String> = conn
+                .prepare_cached(
+                    "SELECT id FROM turn_queue WHERE project_id = ?1 AND status = 'queued'",
+                )?
+                .query_map(params![project_id], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            let mut requested = ids.to_vec();
+            queued.sort();
+            requested.sort();
+            if queued != requested {
+                return Err(crate::Error::Other(
+                    "The queue changed; reload it and try again".to_string(),
+                ));
+            }
+
+            let base: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(position), 0) FROM turn_queue WHERE project_id = ?1",
+                params![project_id],
+                |row| row.get(0),
+            )?;
+            let mut stmt =
+                conn.prepare_cached("UPDATE turn_queue SET position = ?2 WHERE id = ?1")?;
+            for (offset, id) in ids.iter().enumerate() {
+                stmt.execute(params![id, base + 1 + offset as i64])?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Cancel an entry that has not started; returns whether it was queued
+    pub fn cancel_queued_turn(&self, id: &str, now_ms: i64) -> Result<bool> {
+        let conn = self.conn.lock();
+        let updated = conn.execute(
+            r#"UPDATE turn_queue SET status = 'cancelled', finished_at = ?2
+               WHERE id = ?1 AND status = 'queued'"#,
+            params![id, now_ms],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Projects whose queue can start its next entry: something is due and
+    /// nothing is running
+    pub fn projects_with_due_turns(&self, now_ms: i64) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT DISTINCT project_id FROM turn_queue q
+               WHERE status = 'queued' AND (run_after IS NULL OR run_after <= ?1)
+                 AND NOT EXISTS (SELECT 1 FROM turn_queue r
+                                 WHERE r.project_id = q.project_id AND r.status = 'running')"#,
+        )?;
+        let rows = stmt
+            .query_map(params![now_ms], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// The first due entry in a project's queue
+    pub fn next_due_turn(&self, project_id: &str, now_ms: i64) -> Result<Option<QueuedTurn>> {
+        let conn = self.conn.lock();
+        let turn = conn
+            .query_row(
+                &format!(
+                    r#"SELECT {QUEUED_TURN_COLUMNS} FROM turn_queue
+                       WHERE project_id = ?1 AND status = 'queued'
+                         AND (run_after IS NULL OR run_after <= ?2)
+                       ORDER BY position LIMIT 1"#
+                ),
+                params![project_id, now_ms],
+                queued_turn_from_row,
+            )
+            .optional()?;
+        Ok(turn)
+    }
+
+    /// The running entry on a thread, if a queued turn is in flight there
+    pub fn running_queued_turn(&self, thread_id: &str) -> Result<Option<QueuedTurn>> {
+        let conn = self.conn.lock();
+        let turn = conn
+            .query_row(
+                &format!(
+                    "SELECT {QUEUED_TURN_COLUMNS} FROM turn_queue \
+                     WHERE thread_id = ?1 AND status = 'running'"
+                ),
+                params![thread_id],
+                queued_turn_from_row,
+            )
+            .optional()?;
+        Ok(turn)
+    }
+
+    /// Mark a queued entry as started with `turn_id`
+    pub fn mark_queued_turn_running(&self, id: &str, turn_id: &str, now_ms: i64) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE turn_queue SET status = 'running', turn_id = ?2, started_at = ?3
+               WHERE id = ?1 AND status = 'queued'"#,
+            params![id, turn_id, now_ms],
+        )?;
+        Ok(())
+    }
+
+    /// Record how an entry ended ("completed", "failed" or "cancelled")
+    pub fn finish_queued_turn(
+        &self,
+        id: &str,
+        status: &str,
+        error: Option<&str>,
+        now_ms: i64,
+    ) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE turn_queue SET status = ?2, error = ?3, finished_at = ?4
+               WHERE id = ?1 AND status IN ('queued', 'running')"#,
+            params![id, status, error, now_ms],
+        )?;
+        Ok(())
+    }
+
+    /// Fail entries left running by a previous run of the app; returns the
+    /// projects they belong to
+    pub fn fail_interrupted_queued_turns(&self, now_ms: i64) -> Result<Vec<String>> {
+        self.with_transaction(|conn| {
+            let projects: Vec<String> = conn
+                .prepare_cached("SELECT DISTINCT project_id FROM turn_queue WHERE status = 'running'")?
+                .query_map([], |row| row.get(0))?
+                .collect::<std::result::Result<_, _>>()?;
+            conn.execute(
+                r#"UPDATE turn_queue
+                   SET status = 'failed', error = 'Interrupted by an app restart', finished_at = ?1
+                   WHERE status = 'running'"#,
+                params![now_ms],
+            )?;
+            Ok(projects)
+        })
+    }
+
+    /// Number of unfinished entries in a project's queue
+    pub fn count_queued_turns(&self, project_id: &str) -> Result<usize> {
+        let conn = self.conn.lock();
+        let count: i64 = conn.query_row(
+            r#"SELECT COUNT(*) FROM turn_queue
+               WHERE project_id = ?1 AND status IN ('queued', 'running')"#,
+            params![project_id],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    // ==================== Accounts ====================
+
+    /// Save a login, or refresh the stored credentials if its identity is
+    /// already saved
+    ///
+    /// `label` replaces the saved label when given.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upsert_account(
+        &self,
+        identity: &str,
+        label: Option<&str>,
+        email: Option<&str>,
+        auth_mode: &str,
+        plan_type: Option<&str>,
+        auth_json: &str,
+    ) -> Result<SavedAccount> {
+        let default_label = email.unwrap_or(auth_mode);
+        {
+            let conn = self.conn.lock();
+            conn.execute(
+                r#"INSERT INTO accounts (id, identity, label, email, auth_mode, plan_type, auth_json)
+                   VALUES (?1, ?2, COALESCE(?3, ?4), ?5, ?6, ?7, ?8)
+                   ON CONFLICT(identity) DO UPDATE SET
+                       label = COALESCE(?3, label),
+                       email = COALESCE(excluded.email, email),
+                       plan_type = COALESCE(excluded.plan_type, plan_type),
+                       auth_json = excluded.auth_json"#,
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    identity,
+                    label,
+                    default_label,
+                    email,
+                    auth_mode,
+                    plan_type,
+                    auth_json,
+                ],
+            )?;
+        }
+        self.list_accounts()?
+            .into_iter()
+            .find(|account| account.identity == identity)
+            .ok_or_else(|| crate::Error::Other("Saved account disappeared".to_string()))
+    }
+
+    /// Saved accounts, by label; `is_active` is left false for the caller
+    pub fn list_accounts(&self) -> Result<Vec<SavedAccount>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT id, identity, label, email, auth_mode, plan_type, created_at, last_used_at
+               FROM accounts ORDER BY label COLLATE NOCASE, created_at"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(SavedAccount {
+                    id: row.get(0)?,
+                    identity: row.get(1)?,
+                    label: row.get(2)?,
+                    email: row.get(3)?,
+                    auth_mode: row.get(4)?,
+                    plan_type: row.get(5)?,
+                    created_at: row.get(6)?,
+                    last_used_at: row.get(7)?,
+                    is_active: false,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Stored Codex auth file contents for an account
+    pub fn get_account_auth(&self, id: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock();
+        let auth = conn
+            .query_row(
+                r#"SELECT auth_json FROM accounts WHERE id = ?1"#,
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(auth)
+    }
+
+    /// Replace the stored credentials of the account with `identity`, if saved
+    ///
+    /// Keeps refreshed tokens from being lost when switching away.
+    pub fn update_account_auth(&self, identity: &str, auth_json: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE accounts SET auth_json = ?2 WHERE identity = ?1"#,
+            params![identity, auth_json],
+        )?;
+        Ok(())
+    }
+
+    pub fn mark_account_used(&self, id: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"UPDATE accounts SET last_used_at = strftime('%s', 'now') WHERE id = ?1"#,
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a saved account; returns whether it existed
+    pub fn delete_account(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let deleted = conn.execute(r#"DELETE FROM accounts WHERE id = ?1"#, params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Sessions with a turn started at or after `since` that has not finished
+    pub fn count_turns_in_flight(&self, since: i64) -> Result<u32> {
+        let conn = self.conn.lock();
+        let count = conn.query_row(
+            r#"SELECT COUNT(*) FROM session_stats
+               WHERE turn_started_at IS NOT NULL AND turn_started_at >= ?1"#,
+            params![since],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    // ==================== Project Archives ====================
+
+    /// Collect a project's rows for archiving; `None` if the project does not exist
+    pub fn export_project(&self, id: &str) -> Result<Option<ProjectArchive>> {
+        let Some(project) = self.get_project(id)? else {
+            return Ok(None);
+        };
+
+        let conn = self.conn.lock();
+        let sessions = conn
+            .prepare(
+                r#"SELECT session_id, project_id, title, tags, is_favorite, is_archived,
+                          last_accessed_at, created_at, status, first_message, tasks_json, cwd_subpath,
+                          last_message_preview, turn_count, files_changed_count, last_model
+                   FROM session_metadata WHERE project_id = ?1 ORDER BY created_at"#,
+            )?
+            .query_map(params![id], session_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let snapshots = conn
+            .prepare(
+                r#"SELECT s.id, s.session_id, s.created_at, s.snapshot_type, s.metadata_json
+                   FROM snapshots s
+                   JOIN session_metadata m ON m.session_id = s.session_id
+                   WHERE m.project_id = ?1 ORDER BY s.created_at"#,
+            )?
+            .query_map(params![id], |row| {
+                Ok(Snapshot {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    created_at: row.get(2)?,
+                    snapshot_type: row.get(3)?,
+                    metadata_json: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let allowlist = conn
+            .prepare("SELECT command_pattern FROM command_allowlist WHERE project_id = ?1")?
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let denylist = conn
+            .prepare("SELECT command_pattern FROM command_denylist WHERE project_id = ?1")?
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let session_content = conn
+            .prepare(
+                r#"SELECT f.session_id, f.content
+                   FROM session_fts f
+                   JOIN session_metadata m ON m.session_id = f.session_id
+                   WHERE m.project_id = ?1 AND f.content <> ''"#,
+            )?
+            .query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<_, _>>()?;
+
+        let imported_transcripts = conn
+            .prepare(
+                r#"SELECT t.session_id, t.source, t.cwd, t.items_json, t.imported_at
+                   FROM imported_transcripts t
+                   JOIN session_metadata m ON m.session_id = t.session_id
+                   WHERE m.project_id = ?1"#,
+            )?
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })?
+            .map(|row| {
+                let (session_id, source, cwd, items_json, imported_at) = row?;
+                let transcript = ImportedTranscript {
+                    source,
+                    cwd,
+                    items: serde_json::from_str(&items_json)?,
+                    imported_at,
+                };
+                Ok((session_id, transcript))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Some(ProjectArchive {
+            format_version: PROJECT_ARCHIVE_VERSION,
+            exported_at: chrono::Utc::now().timestamp(),
+            project,
+            sessions,
+            snapshots,
+            allowlist,
+            denylist,
+            session_content,
+            imported_transcripts,
+        }))
+    }
+
+    /// Remove an exported project from the live tables and record where its
+    /// archive was written, in one transaction
+    pub fn mark_project_archived(
+        &self,
+        archive: &ProjectArchive,
+        archive_path: &str,
+    ) -> Result<ArchivedProject> {
+        let project = &archive.project;
+        let archived = ArchivedProject {
+            id: project.id.clone(),
+            path: project.path.clone(),
+            display_name: project.display_name.clone(),
+            archive_path: archive_path.to_string(),
+            archived_at: archive.exported_at,
+            session_count: archive.sessions.len(),
+        };
+
+        self.with_transaction(|conn| {
+            conn.execute(
+                r#"INSERT OR REPLACE INTO archived_projects
+                   (id, path, display_name, archive_path, archived_at, session_count)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    archived.id,
+                    archived.path,
+                    archived.display_name,
+                    archived.archive_path,
+                    archived.archived_at,
+                    archived.session_count as i64,
+                ],
+            )?;
+            delete_project_rows(conn, &project.id)
+        })?;
+        Ok(archived)
+    }
+
+    /// Restore an archived project's rows in one transaction
+    ///
+    /// Fails without changes if the project's ID or path is already in use or
+    /// any of its sessions already exists.
+    pub fn restore_project(&self, archive: &ProjectArchive) -> Result<()> {
+        if archive.format_version > PROJECT_ARCHIVE_VERSION {
+            return Err(crate::Error::Other(format!(
+                "Archive format {} is newer than this build supports ({PROJECT_ARCHIVE_VERSION})",
+                archive.format_version
+            )));
+        }
+        let project = &archive.project;
+
+        self.with_transaction(|conn| {
+            let in_use = conn
+                .prepare_cached("SELECT 1 FROM projects WHERE id = ?1 OR path = ?2")?
+                .exists(params![project.id, project.path])?;
+            if in_use {
+                return Err(crate::Error::Other(format!(
+                    "A project at {} is already open",
+                    project.path
+                )));
+            }
+
+            conn.execute(
+                r#"INSERT INTO projects
+                   (id, path, display_name, created_at, last_opened_at, settings_json, color, icon, emoji)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+                params![
+                    project.id,
+                    project.path,
+                    project.display_name,
+                    project.created_at,
+                    project.last_opened_at,
+                    project.settings_json,
+                    project.color,
+                    project.icon,
+                    project.emoji,
+                ],
+            )?;
+            for session in &archive.sessions {
+                if session.project_id != project.id {
+                    return Err(crate::Error::Other(format!(
+                        "Archived session {} belongs to another project",
+                        session.session_id
+                    )));
+                }
+                insert_session_row(conn, session)?;
+            }
+            for snapshot in &archive.snapshots {
+                conn.execute(
+                    r#"INSERT INTO snapshots (id, session_id, created_at, snapshot_type, metadata_json)
+                       VALUES (?1, ?2, ?3, ?4, ?5)"#,
+                    params![
+                        snapshot.id,
+                        snapshot.session_id,
+                        snapshot.created_at,
+                        snapshot.snapshot_type,
+                        snapshot.metadata_json,
+                    ],
+                )?;
+            }
+            for pattern in &archive.allowlist {
+                conn.execute(
+                    "INSERT OR IGNORE INTO command_allowlist (project_id, command_pattern) VALUES (?1, ?2)",
+                    params![project.id, pattern],
+                )?;
+            }
+            for pattern in &archive.denylist {
+                conn.execute(
+                    "INSERT OR IGNORE INTO command_denylist (project_id, command_pattern) VALUES (?1, ?2)",
+                    params![project.id, pattern],
+                )?;
+            }
+            for (session_id, content) in &archive.session_content {
+                conn.execute(
+                    "UPDATE session_fts SET content = ?2 WHERE session_id = ?1",
+                    params![session_id, content],
+                )?;
+            }
+            for (session_id, transcript) in &archive.imported_transcripts {
+                insert_imported_transcript(conn, session_id, transcript)?;
+            }
+            conn.execute("DELETE FROM archived_projects WHERE id = ?1", params![project.id])?;
+            Ok(())
+        })
+    }
+
+    /// Projects moved to archive files, most recently archived first
+    pub fn list_archived_projects(&self) -> Result<Vec<ArchivedProject>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT id, path, display_name, archive_path, archived_at, session_count
+               FROM archived_projects ORDER BY archived_at DESC"#,
+        )?;
+
+        let archived = stmt
+            .query_map([], |row| {
+                Ok(ArchivedProject {
+                    id: row.get(0)?,
+                    path: row.get(1)?,
+                    display_name: row.get(2)?,
+                    archive_path: row.get(3)?,
+                    archived_at: row.get(4)?,
+                    session_count: row.get::<_, i64>(5)? as usize,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(archived)
+    }
+
+    // ==================== Orphan Cleanup ====================
+
+    /// Count rows left behind by deleted sessions and projects
+    pub fn orphan_counts(&self) -> Result<OrphanCounts> {
+        let conn = self.conn.lock();
+        orphan_counts(&conn)
+    }
+
+    /// Delete rows left behind by deleted sessions and projects in one transaction
+    ///
+    /// Returns what was removed.
+    pub fn delete_orphans(&self) -> Result<OrphanCounts> {
+        self.with_transaction(|conn| {
+            let counts = orphan_counts(conn)?;
+            conn.execute(&format!("DELETE {ORPHAN_SNAPSHOTS}"), [])?;
+            conn.execute(&format!("DELETE {ORPHAN_SEARCH_INDEX}"), [])?;
+            conn.execute(&format!("DELETE {ORPHAN_SESSIONS}"), [])?;
+            conn.execute(&format!("DELETE {ORPHAN_ALLOWLIST}"), [])?;
+            Ok(counts)
+        })
+    }
+
+    // ==================== Snapshot Operations ====================
+
+    /// Insert a snapshot
+    pub fn insert_snapshot(&self, snapshot: &Snapshot) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT INTO snapshots (id, session_id, created_at, snapshot_type, metadata_json)
+               VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            params![
+                snapshot.id,
+                snapshot.session_id,
+                snapshot.created_at,
+                snapshot.snapshot_type,
+                snapshot.metadata_json,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete old snapshots, keeping only N most recent per session
+    pub fn cleanup_old_snapshots(&self, session_id: &str, keep_count: usize) -> Result<usize> {
+        let conn = self.conn.lock();
+
+        // Get snapshots sorted by created_at descending
+        let mut stmt = conn.prepare(
+            "SELECT id FROM snapshots WHERE session_id = ?1 ORDER BY created_at DESC"
+        )?;
+
+        let snapshot_ids: Vec<String> = stmt.query(params![session_id])?
+            .mapped(|row| row.get(0))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // Keep only the first N snapshots
+        if snapshot_ids.len() <= keep_count {
+            return Ok(0);
+        }
+
+        // Single SQL delete: remove all snapshots for this session except the N most recent
+        let deleted_count = conn.execute(
+            r#"DELETE FROM snapshots 
+               WHERE session_id = ?1 
+               AND id NOT IN (
+                   SELECT id FROM snapshots 
+                   WHERE session_id = ?1 
+                   ORDER BY created_at DESC 
+                   LIMIT ?2
+               )"#,
+            params![session_id, keep_count],
+        )?;
+
+        Ok(deleted_count)
+    }
+
+    /// Delete all snapshots older than a specific date
+    pub fn cleanup_snapshots_older_than(&self, days: i64) -> Result<usize> {
+        let conn = self.conn.lock();
+        let cutoff = chrono::Utc::now().timestamp() - (days * 86400);
+
+        let count = conn.execute(
+            "DELETE FROM snapshots WHERE created_at < ?1",
+            params![cutoff],
+        )?;
+
+        Ok(count)
+    }
+
+    /// Get snapshots for a session
+    pub fn get_snapshots_for_session(&self, session_id: &str) -> Result<Vec<Snapshot>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare_cached(
+            r#"SELECT id, session_id, created_at, snapshot_type, metadata_json
+               FROM snapshots WHERE session_id = ?1 ORDER BY created_at DESC, rowid DESC"#,
+        )?;
+
+        let snapshots = stmt
+            .query_map(params![session_id], |row| {
+                Ok(Snapshot {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    created_at: row.get(2)?,
+                    snapshot_type: row.get(3)?,
+                    metadata_json: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(snapshots)
+    }
+
+    /// Get every snapshot, oldest first
+    pub fn get_all_snapshots(&self) -> Result<Vec<Snapshot>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            r#"SELECT id, session_id, created_at, snapshot_type, metadata_json
+               FROM snapshots ORDER BY created_at ASC, rowid ASC"#,
+        )?;
+
+        let snapshots = stmt
+            .query_map([], |row| {
+                Ok(Snapshot {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    created_at: row.get(2)?,
+                    snapshot_type: row.get(3)?,
+                    metadata_json: row.get(4)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(snapshots)
+    }
+
+    /// Delete snapshots by ID in one transaction; returns how many existed
+    pub fn delete_snapshots(&self, ids: &[String]) -> Result<usize> {
+        self.with_transaction(|conn| {
+            let mut stmt = conn.prepare_cached("DELETE FROM snapshots WHERE id = ?1")?;
+            let mut deleted = 0;
+            for id in ids {
+                deleted += stmt.execute(params![id])?;
+            }
+            Ok(deleted)
+        })
+    }
+
+    /// Delete one snapshot; returns whether it existed
+    pub fn delete_snapshot(&self, id: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let deleted = conn.execute("DELETE FROM snapshots WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Replace a snapshot's metadata; false when the snapshot is gone
+    pub fn update_snapshot_metadata(&self, id: &str, metadata_json: &str) -> Result<bool> {
+        let conn = self.conn.lock();
+        let updated = conn.execute(
+            "UPDATE snapshots SET metadata_json = ?1 WHERE id = ?2",
+            params![metadata_json, id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Get a snapshot by ID
+    pub fn get_snapshot(&self, id: &str) -> Result<Option<Snapshot>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            r#"SELECT id, session_id, created_at, snapshot_type, metadata_json
+               FROM snapshots WHERE id = ?1"#,
+        )?;
+
+        let mut rows = stmt.query(params![id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(Snapshot {
+                id: row.get(0)?,
+                session_id: row.get(1)?,
+                created_at: row.get(2)?,
+                snapshot_type: row.get(3)?,
+                metadata_json: row.get(4)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // ==================== Command Allowlist Operations ====================
+
+    /// Add command to allowlist
+    pub fn add_to_allowlist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT OR IGNORE INTO command_allowlist (project_id, command_pattern)
+               VALUES (?1, ?2)"#,
+            params![project_id, command_pattern],
+        )?;
+        Ok(())
+    }
+
+    /// Get allowlist for a project
+    pub fn get_allowlist(&self, project_id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare(
+            "SELECT command_pattern FROM command_allowlist WHERE project_id = ?1",
+        )?;
+
+        let patterns = stmt
+            .query_map(params![project_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        Ok(patterns)
+    }
+
+    /// Remove command from allowlist
+    pub fn remove_from_allowlist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM command_allowlist WHERE project_id = ?1 AND command_pattern = ?2",
+            params![project_id, command_pattern],
+        )?;
+        Ok(())
+    }
+
+    // ==================== Command Denylist Operations ====================
+
+    /// Add command to denylist
+    pub fn add_to_denylist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            r#"INSERT OR IGNORE INTO command_denylist (project_id, command_pattern)
+               VALUES (?1, ?2)"#,
+            params![project_id, command_pattern],
+        )?;
+        Ok(())
+    }
+
+    /// Get denylist for a project
+    pub fn get_denylist(&self, project_id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt =
+            conn.prepare("SELECT command_pattern FROM command_denylist WHERE project_id = ?1")?;
+
+        let patterns = stmt
+            .query_map(params![project_id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        Ok(patterns)
+    }
+
+    /// Remove command from denylist
+    pub fn remove_from_denylist(&self, project_id: &str, command_pattern: &str) -> Result<()> {
+        let conn = self.conn.lock();
+        conn.execute(
+            "DELETE FROM command_denylist WHERE project_id = ?1 AND command_pattern = ?2",
+            params![project_id, command_pattern],
+        )?;
+        Ok(())
+    }
+
+    // ==================== Maintenance Operations ====================
+
+    /// Run VACUUM if at least `interval_days` have passed since the last vacuum.
+    /// Tracks the last vacuum timestamp in an `app_metadata` table.
+    /// Returns `true` if VACUUM was executed.
+    pub fn vacuum_if_needed(&self, interval_days: i64) -> Result<bool> {
+        let conn = self.conn.lock();
+
+        // Ensure metadata table exists
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS app_metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);"
+        )?;
+
+        // Check last vacuum time
+        let last_vacuum: Option<i64> = conn
+            .query_row(
+                "SELECT CAST(value AS INTEGER) FROM app_metadata WHERE key = 'last_vacuum_at'",
+                [],
+                |row| row.get(0),
+            )
+            .ok();
+
+        let now = chrono::Utc::now().timestamp();
+        let threshold = interval_days * 86400;
+
+        if let Some(last) = last_vacuum {
+            if now - last < threshold {
+                return Ok(false);
+            }
+        }
+
+        // Run VACUUM (must be outside a transaction)
+        tracing::info!("Running database VACUUM");
+        conn.execute_batch("VACUUM;")?;
+
+        // Update last vacuum time
+        conn.execute(
+            "INSERT INTO app_metadata (key, value) VALUES ('last_vacuum_at', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![now.to_string()],
+        )?;
 
         tracing::info!("Database VACUUM completed successfully");
         Ok(true)
     }
+
+    /// Problems `PRAGMA integrity_check` reports; empty when the file is sound
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock();
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let problems = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(problems.into_iter().filter(|line| line != "ok").collect())
+    }
+}
+
+/// Current `ProjectArchive::format_version`
+pub const PROJECT_ARCHIVE_VERSION: u32 = 1;
+
+/// Most conversation text kept in a session's search index, in characters
+const MAX_INDEXED_CONTENT_CHARS: i64 = 64 * 1024;
+
+/// Session metadata whose project is gone
+const ORPHAN_SESSIONS: &str =
+    "FROM session_metadata WHERE project_id NOT IN (SELECT id FROM projects)";
+
+/// Snapshots whose session is gone or belongs to a deleted project
+const ORPHAN_SNAPSHOTS: &str = "FROM snapshots WHERE session_id NOT IN \
+     (SELECT session_id FROM session_metadata WHERE project_id IN (SELECT id FROM projects))";
+
+/// Search index rows whose session is gone or belongs to a deleted project
+const ORPHAN_SEARCH_INDEX: &str = "FROM session_fts WHERE session_id NOT IN \
+     (SELECT session_id FROM session_metadata WHERE project_id IN (SELECT id FROM projects))";
+
+/// Allowlist entries whose project is gone
+const ORPHAN_ALLOWLIST: &str =
+    "FROM command_allowlist WHERE project_id NOT IN (SELECT id FROM projects)";
+
+fn orphan_group(conn: &Connection, from: &str, size_expr: &str) -> Result<OrphanGroup> {
+    let (count, bytes): (i64, i64) = conn.query_row(
+        &format!("SELECT COUNT(*), COALESCE(SUM({size_expr}), 0) {from}"),
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok(OrphanGroup {
+        count: count as usize,
+        bytes: bytes as u64,
+    })
+}
+
+fn orphan_counts(conn: &Connection) -> Result<OrphanCounts> {
+    Ok(OrphanCounts {
+        sessions: orphan_group(
+            conn,
+            ORPHAN_SESSIONS,
+            "LENGTH(CAST(COALESCE(title, '') || COALESCE(tags, '') || \
+             COALESCE(first_message, '') || COALESCE(tasks_json, '') AS BLOB))",
+        )?,
+        snapshots: orphan_group(
+            conn,
+            ORPHAN_SNAPSHOTS,
+            "LENGTH(CAST(COALESCE(metadata_json, '') AS BLOB))",
+        )?,
+        allowlist: orphan_group(conn, ORPHAN_ALLOWLIST, "LENGTH(CAST(command_pattern AS BLOB))")?,
+        search_index: orphan_group(
+            conn,
+            ORPHAN_SEARCH_INDEX,
+            "LENGTH(CAST(COALESCE(title, '') || COALESCE(first_message, '') || \
+             COALESCE(tags, '') || COALESCE(content, '') AS BLOB))",
+        )?,
+    })
+}
+
+/// Columns read by `queued_turn_from_row`, in order
+const QUEUED_TURN_COLUMNS: &str = "id, project_id, thread_id, prompt, position, status, \
+     run_after, created_at, started_at, finished_at, turn_id, error";
+
+fn queued_turn_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<QueuedTurn> {
+    Ok(QueuedTurn {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        thread_id: row.get(2)?,
+        prompt: row.get(3)?,
+        position: row.get(4)?,
+        status: row.get(5)?,
+        run_after: row.get(6)?,
+        created_at: row.get(7)?,
+        started_at: row.get(8)?,
+        finished_at: row.get(9)?,
+        turn_id: row.get(10)?,
+        error: row.get(11)?,
+    })
+}
+
+/// Map a `projects` row selected as id, path, display_name, created_at,
+/// last_opened_at, settings_json, color, icon, emoji
+fn project_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        display_name: row.get(2)?,
+        created_at: row.get(3)?,
+        last_opened_at: row.get(4)?,
+        settings_json: row.get(5)?,
+        color: row.get(6)?,
+        icon: row.get(7)?,
+        emoji: row.get(8)?,
+    })
+}
+
+/// Map a full `session_metadata` row (columns in declaration order)
+fn session_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionMetadata> {
+    Ok(SessionMetadata {
+        session_id: row.get(0)?,
+        project_id: row.get(1)?,
+        title: row.get(2)?,
+        tags: row.get(3)?,
+        is_favorite: row.get(4)?,
+        is_archived: row.get(5)?,
+        last_accessed_at: row.get(6)?,
+        created_at: row.get(7)?,
+        status: SessionStatus::from_str(row.get::<_, String>(8)?.as_str()),
+        first_message: row.get(9)?,
+        tasks_json: row.get(10)?,
+        cwd_subpath: row.get(11)?,
+        last_message_preview: row.get(12)?,
+        turn_count: row.get(13)?,
+        files_changed_count: row.get(14)?,
+        last_model: row.get(15)?,
+    })
+}
+
+fn delete_project_rows(conn: &Connection, id: &str) -> Result<()> {
+    // Snapshots, turn timings and outcomes, and baselines have no foreign key,
+    // so they would outlive the cascade
+    conn.execute(
+        r#"DELETE FROM snapshots WHERE session_id IN
+           (SELECT session_id FROM session_metadata WHERE project_id = ?1)"#,
+        params![id],
+    )?;
+    conn.execute(
+        r#"DELETE FROM turn_timings WHERE session_id IN
+           (SELECT session_id FROM session_metadata WHERE project_id = ?1)"#,
+        params![id],
+    )?;
+    conn.execute(
+        r#"DELETE FROM turn_outcomes WHERE session_id IN
+           (SELECT session_id FROM session_metadata WHERE project_id = ?1)"#,
+        params![id],
+    )?;
+    conn.execute(
+        r#"DELETE FROM session_baselines WHERE session_id IN
+           (SELECT session_id FROM session_metadata WHERE project_id = ?1)"#,
+        params![id],
+    )?;
+    conn.execute("DELETE FROM session_worktrees WHERE project_id = ?1", params![id])?;
+    conn.execute(
+        r#"DELETE FROM comparison_runs WHERE group_id IN
+           (SELECT id FROM comparison_groups WHERE project_id = ?1)"#,
+        params![id],
+    )?;
+    conn.execute("DELETE FROM comparison_groups WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM session_metadata WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM command_allowlist WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM command_denylist WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM project_env WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM turn_queue WHERE project_id = ?1", params![id])?;
+    conn.execute("DELETE FROM projects WHERE id = ?1", params![id])?;
+    Ok(())
+}
+
+const SESSION_WORKTREE_SELECT: &str = r#"SELECT session_id, project_id, path, branch,
+    base_branch, base_commit, created_at FROM session_worktrees"#;
+
+fn session_worktree_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionWorktree> {
+    Ok(SessionWorktree {
+        session_id: row.get(0)?,
+        project_id: row.get(1)?,
+        path: row.get(2)?,
+        branch: row.get(3)?,
+        base_branch: row.get(4)?,
+        base_commit: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+const COMPARISON_GROUP_SELECT: &str = r#"SELECT id, project_id, prompt, base_branch,
+    base_commit, winner_run_id, resolved_at, created_at FROM comparison_groups"#;
+
+fn comparison_group_from_row(row: &rusqlite::Row) -> rusqlite::Result<ComparisonGroup> {
+    Ok(ComparisonGroup {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        prompt: row.get(2)?,
+        base_branch: row.get(3)?,
+        base_commit: row.get(4)?,
+        winner_run_id: row.get(5)?,
+        resolved_at: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// Insert a session row with every column, plus its `session_tags` rows
+fn insert_session_row(conn: &Connection, session: &SessionMetadata) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO session_metadata
+           (session_id, project_id, title, tags, is_favorite, is_archived, last_accessed_at,
+            created_at, status, first_message, tasks_json, cwd_subpath, last_message_preview,
+            turn_count, files_changed_count, last_model)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"#,
+        params![
+            session.session_id,
+            session.project_id,
+            session.title,
+            session.tags,
+            session.is_favorite,
+            session.is_archived,
+            session.last_accessed_at,
+            session.created_at,
+            session.status.as_str(),
+            session.first_message,
+            session.tasks_json,
+            session.cwd_subpath,
+            session.last_message_preview,
+            session.turn_count,
+            session.files_changed_count,
+            session.last_model,
+        ],
+    )?;
+    let mut insert = conn
+        .prepare_cached("INSERT OR IGNORE INTO session_tags (session_id, tag) VALUES (?1, ?2)")?;
+    for tag in session.get_tags() {
+        insert.execute(params![session.session_id, tag])?;
+    }
+    Ok(())
+}
+
+fn insert_imported_transcript(
+    conn: &Connection,
+    session_id: &str,
+    transcript: &ImportedTranscript,
+) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO imported_transcripts (session_id, source, cwd, items_json, imported_at)
+           VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        params![
+            session_id,
+            transcript.source,
+            transcript.cwd,
+            serde_json::to_string(&transcript.items)?,
+            transcript.imported_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Create a session's `session_stats` row if missing
+///
+/// Sessions without metadata (agent threads) get no row, so the updates that
+/// follow are no-ops for them.
+fn ensure_session_stats_row(conn: &Connection, session_id: &str) -> Result<()> {
+    conn.prepare_cached(
+        r#"INSERT OR IGNORE INTO session_stats (session_id)
+           SELECT ?1 WHERE EXISTS (SELECT 1 FROM session_metadata WHERE session_id = ?1)"#,
+    )?
+    .execute(params![session_id])?;
+    Ok(())
+}
+
+fn ensure_session_exists(conn: &Connection, session_id: &str) -> Result<()> {
+    let exists = conn
+        .prepare_cached("SELECT 1 FROM session_metadata WHERE session_id = ?1")?
+        .exists(params![session_id])?;
+    if !exists {
+        return Err(crate::Error::SessionNotFound(session_id.to_string()));
+    }
+    Ok(())
+}
+
+/// Rewrite `session_metadata.tags` from `session_tags`; returns the tags
+fn sync_session_tags_json(conn: &Connection, session_id: &str) -> Result<Vec<String>> {
+    let tags: Vec<String> = conn
+        .prepare_cached(
+            "SELECT tag FROM session_tags WHERE session_id = ?1 ORDER BY created_at, rowid",
+        )?
+        .query_map(params![session_id], |row| row.get(0))?
+        .collect::<std::result::Result<_, _>>()?;
+    conn.execute(
+        "UPDATE session_metadata SET tags = ?2 WHERE session_id = ?1",
+        params![session_id, serde_json::to_string(&tags)?],
+    )?;
+    Ok(tags)
+}
+
+/// Remove a session's snapshots and metadata row; returns snapshots removed
+fn delete_session_rows(conn: &Connection, session_id: &str) -> Result<usize> {
+    let snapshots = conn.execute(
+        "DELETE FROM snapshots WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    conn.execute("DELETE FROM turn_timings WHERE session_id = ?1", params![session_id])?;
+    conn.execute("DELETE FROM turn_outcomes WHERE session_id = ?1", params![session_id])?;
+    conn.execute("DELETE FROM session_baselines WHERE session_id = ?1", params![session_id])?;
+    conn.execute("DELETE FROM session_worktrees WHERE session_id = ?1", params![session_id])?;
+    conn.execute("DELETE FROM turn_queue WHERE thread_id = ?1", params![session_id])?;
+    conn.execute(
+        "DELETE FROM session_metadata WHERE session_id = ?1",
+        params![session_id],
+    )?;
+    Ok(snapshots)
+}
+
+/// A fresh database in a temporary directory, removed when the returned
+/// `TempDir` is dropped
+#[cfg(test)]
+pub(crate) fn test_db() -> (tempfile::TempDir, Database) {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+    (temp_dir, db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_project_keeps_linked_rows() {
+        let (_temp_dir, db) = test_db();
+
+        let mut project = Project::new("/tmp/example");
+        project.settings_json = Some(r#"{"model":"gpt-5","futureFlag":1}"#.to_string());
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        let updated = db
+            .update_project(
+                &project.id,
+                &ProjectUpdate {
+                    display_name: Some("Renamed".to_string()),
+                    settings_patch: Some(serde_json::json!({ "sandboxMode": "read-only" })),
+                    last_opened_at: Some(42),
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(updated.display_name.as_deref(), Some("Renamed"));
+        assert_eq!(updated.created_at, project.created_at);
+        assert_eq!(updated.last_opened_at, Some(42));
+        let settings = updated.settings();
+        assert_eq!(settings.model.as_deref(), Some("gpt-5"));
+        assert_eq!(settings.sandbox_mode.as_deref(), Some("read-only"));
+        assert!(settings.extra.contains_key("futureFlag"));
+        assert!(db.get_session_by_id("s1").unwrap().is_some());
+
+        assert!(db
+            .update_project("missing", &ProjectUpdate::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let (_temp_dir, db) = test_db();
+        assert!(db.integrity_check().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let (temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        drop(db);
+
+        let db = Database::open_read_only(&temp_dir.path().join("test.db")).unwrap();
+        assert_eq!(db.get_all_projects().unwrap().len(), 1);
+        assert!(db.migration_status().unwrap().pending.is_empty());
+        assert!(db.insert_project(&Project::new("/tmp/other")).is_err());
+    }
+
+    #[test]
+    fn test_most_recent_session() {
+        let (_temp_dir, db) = test_db();
+        assert!(db.get_most_recent_session().unwrap().is_none());
+
+        let mut older = Project::new("/tmp/older");
+        older.last_opened_at = Some(10);
+        let mut newer = Project::new("/tmp/newer");
+        newer.last_opened_at = Some(20);
+        db.insert_project(&older).unwrap();
+        db.insert_project(&newer).unwrap();
+        let (project, session) = db.get_most_recent_session().unwrap().unwrap();
+        assert_eq!(project.id, newer.id);
+        assert!(session.is_none());
+
+        for (id, accessed, archived) in [("s1", 100, false), ("s2", 200, true), ("s3", 50, false)] {
+            let mut metadata = SessionMetadata::new(id, &newer.id);
+            metadata.last_accessed_at = Some(accessed);
+            metadata.is_archived = archived;
+            db.upsert_session_metadata(&metadata).unwrap();
+        }
+        let (_, session) = db.get_most_recent_session().unwrap().unwrap();
+        assert_eq!(session.unwrap().session_id, "s1");
+    }
+
+    #[test]
+    fn test_session_deletes_remove_snapshots() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        for id in ["s1", "s2", "s3"] {
+            db.upsert_session_metadata(&SessionMetadata::new(id, &project.id))
+                .unwrap();
+            db.insert_snapshot(&Snapshot::new_git_ghost(id, "abc123")).unwrap();
+        }
+
+        assert_eq!(db.delete_session("s1").unwrap(), 1);
+        assert!(db.get_session_by_id("s1").unwrap().is_none());
+        assert!(db.get_snapshots_for_session("s1").unwrap().is_empty());
+
+        assert_eq!(
+            db.set_sessions_archived(&["s2".to_string(), "missing".to_string()], true)
+                .unwrap(),
+            1
+        );
+        assert!(db.get_session_by_id("s2").unwrap().unwrap().is_archived);
+
+        db.delete_project(&project.id).unwrap();
+        assert!(db.get_session_by_id("s3").unwrap().is_none());
+        assert!(db.get_snapshots_for_session("s3").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_move_sessions_to_project() {
+        let (_temp_dir, db) = test_db();
+
+        let source = Project::new("/tmp/source");
+        let target = Project::new("/tmp/target");
+        db.insert_project(&source).unwrap();
+        db.insert_project(&target).unwrap();
+        let mut metadata = SessionMetadata::new("s1", &source.id);
+        metadata.cwd_subpath = Some("crates/core".to_string());
+        db.upsert_session_metadata(&metadata).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s2", &source.id))
+            .unwrap();
+        db.set_sessions_archived(&["s2".to_string()], true).unwrap();
+
+        let ids = vec!["s1".to_string(), "s2".to_string()];
+        assert!(matches!(
+            db.move_sessions_to_project(&ids, "missing"),
+            Err(crate::Error::ProjectNotFound(_))
+        ));
+        assert_eq!(db.move_sessions_to_project(&ids, &target.id).unwrap(), 2);
+        assert_eq!(db.move_sessions_to_project(&ids, &target.id).unwrap(), 0);
+
+        let moved = db.get_session_by_id("s1").unwrap().unwrap();
+        assert_eq!(moved.project_id, target.id);
+        assert!(moved.cwd_subpath.is_none());
+
+        let archived = SessionFilter {
+            archived: true,
+            ..Default::default()
+        };
+        let archived = db.get_sessions_for_project(&target.id, &archived).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].session_id, "s2");
+
+        let ids = vec!["s1".to_string(), "missing".to_string()];
+        assert_eq!(db.delete_sessions(&ids).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_adopt_session_requires_project() {
+        let (_temp_dir, db) = test_db();
+
+        assert!(matches!(
+            db.adopt_session("imported", "missing"),
+            Err(crate::Error::ProjectNotFound(_))
+        ));
+        assert!(db.get_session_by_id("imported").unwrap().is_none());
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        let mut metadata = SessionMetadata::new("imported", &project.id);
+        metadata.title = Some("Kept title".to_string());
+        db.upsert_session_metadata(&metadata).unwrap();
+
+        let other = Project::new("/tmp/other");
+        db.insert_project(&other).unwrap();
+        let adopted = db.adopt_session("imported", &other.id).unwrap();
+        assert_eq!(adopted.project_id, other.id);
+        assert_eq!(adopted.title.as_deref(), Some("Kept title"));
+    }
+
+    #[test]
+    fn test_search_sessions_ranks_and_highlights() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        let mut titled = SessionMetadata::new("s1", &project.id);
+        titled.title = Some("Refactor config parser".to_string());
+        db.upsert_session_metadata(&titled).unwrap();
+
+        db.upsert_session_metadata(&SessionMetadata::new("s2", &project.id))
+            .unwrap();
+        db.index_session_content("s2", "AGENT: updated the config loader to use serde")
+            .unwrap();
+
+        let hits = db.search_sessions("config", &[], false, None, 10).unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.session.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["s1", "s2"]);
+        assert_eq!(hits[0].snippet.as_deref(), Some("Refactor config parser"));
+        assert_eq!(hits[0].highlights, vec![(9, 15)]);
+
+        assert_eq!(db.search_sessions("conf", &[], false, None, 10).unwrap().len(), 2);
+        assert!(db.search_sessions("config", &[], true, None, 10).unwrap().is_empty());
+
+        db.delete_session("s1").unwrap();
+        assert_eq!(db.search_sessions("parser", &[], false, None, 10).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_search_sessions_boosts_title_prefix_and_project() {
+        let (_temp_dir, db) = test_db();
+
+        let here = Project::new("/tmp/here");
+        let elsewhere = Project::new("/tmp/elsewhere");
+        db.insert_project(&here).unwrap();
+        db.insert_project(&elsewhere).unwrap();
+
+        for (id, project, title) in [
+            ("mid", &elsewhere, "Fix the deploy script"),
+            ("lead", &elsewhere, "Deploy pipeline"),
+            ("local", &here, "Fix the deploy script"),
+        ] {
+            let mut session = SessionMetadata::new(id, &project.id);
+            session.title = Some(title.to_string());
+            db.upsert_session_metadata(&session).unwrap();
+        }
+
+        let hits = db.search_sessions("depl", &[], false, None, 10).unwrap();
+        assert_eq!(hits[0].session.session_id, "lead");
+        assert_eq!(hits[0].title_highlights, vec![(0, 4)]);
+        assert!(hits.windows(2).all(|pair| pair[0].score >= pair[1].score));
+
+        let hits = db.search_sessions("fix depl", &[], false, Some(&here.id), 10).unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.session.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["local", "mid"]);
+        assert_eq!(hits[0].title_highlights, vec![(0, 3), (8, 12)]);
+    }
+
+    #[test]
+    fn test_search_sessions_substrings_tags_and_archived() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        let mut tagged = SessionMetadata::new("019a-f3c2", &project.id);
+        tagged.title = Some("Refactor config parser".to_string());
+        db.upsert_session_metadata(&tagged).unwrap();
+        db.add_session_tag("019a-f3c2", "backend").unwrap();
+
+        let mut archived = SessionMetadata::new("s2", &project.id);
+        archived.title = Some("Old config work".to_string());
+        db.upsert_session_metadata(&archived).unwrap();
+        db.set_sessions_archived(&["s2".to_string()], true).unwrap();
+
+        let hits = db.search_sessions("config", &[], false, None, 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().any(|hit| hit.session.is_archived));
+
+        let backend = ["backend".to_string()];
+        let hits = db.search_sessions("config", &backend, false, None, 10).unwrap();
+        let ids: Vec<_> = hits.iter().map(|h| h.session.session_id.as_str()).collect();
+        assert_eq!(ids, vec!["019a-f3c2"]);
+
+        let hits = db.search_sessions("f3c2", &[], false, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].score, 0.0);
+        let hits = db.search_sessions("factor", &[], false, None, 10).unwrap();
+        assert_eq!(hits[0].title_highlights, vec![(2, 8)]);
+        assert!(db
+            .search_sessions("factor", &["frontend".to_string()], false, None, 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_session_content_appends_and_seeds() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        db.append_session_content("s1", "USER: bump tokio").unwrap();
+        db.append_session_content("s1", "AGENT: bumped the lockfile")
+            .unwrap();
+        db.seed_session_content("s1", "USER: unrelated").unwrap();
+
+        let hits = db.search_sessions("tokio lockfile", &[], false, None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        let hits = db.search_sessions("unrelated", &[], false, None, 10).unwrap();
+        assert!(hits.is_empty());
+    }
+
+    #[test]
+    fn test_delete_orphans_keeps_live_rows() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("live", &project.id))
+            .unwrap();
+        db.insert_snapshot(&Snapshot::new_git_ghost("live", "abc123")).unwrap();
+        db.insert_snapshot(&Snapshot::new_git_ghost("gone", "def456")).unwrap();
+
+        let preview = db.orphan_counts().unwrap();
+        assert_eq!(preview.snapshots.count, 1);
+        assert!(preview.snapshots.bytes > 0);
+        assert_eq!(preview.sessions.count, 0);
+
+        let removed = db.delete_orphans().unwrap();
+        assert_eq!(removed.snapshots.count, 1);
+        assert_eq!(db.orphan_counts().unwrap().total_bytes(), 0);
+        assert_eq!(db.get_snapshots_for_session("live").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_session_tags_and_favorites() {
+        let (_temp_dir, db) = test_db();
+
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        for id in ["s1", "s2", "s3"] {
+            db.upsert_session_metadata(&SessionMetadata::new(id, &project.id))
+                .unwrap();
+        }
+
+        db.add_session_tag("s1", "bug").unwrap();
+        assert_eq!(db.add_session_tag("s1", "ui").unwrap(), vec!["bug", "ui"]);
+        db.add_session_tag("s2", "bug").unwrap();
+        assert!(matches!(
+            db.add_session_tag("missing", "bug"),
+            Err(crate::Error::SessionNotFound(_))
+        ));
+        assert!(db.set_session_favorite("s2", true).unwrap());
+
+        let ids = |filter: SessionFilter| -> Vec<String> {
+            db.get_sessions_for_project(&project.id, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.session_id)
+                .collect()
+        };
+        assert_eq!(ids(SessionFilter::default())[0], "s2");
+        let bug_ui = SessionFilter {
+            tags: vec!["bug".to_string(), "ui".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(ids(bug_ui), vec!["s1"]);
+        let favorite_bugs = SessionFilter {
+            tags: vec!["bug".to_string()],
+            favorites_only: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(favorite_bugs), vec!["s2"]);
+
+        let counts = db.list_session_tags(Some(&project.id)).unwrap();
+        assert_eq!((counts[0].tag.as_str(), counts[0].count), ("bug", 2));
+
+        assert_eq!(db.remove_session_tag("s1", "bug").unwrap(), vec!["ui"]);
+        assert_eq!(db.get_session_by_id("s1").unwrap().unwrap().get_tags(), vec!["ui"]);
+
+        db.delete_session("s1").unwrap();
+        assert_eq!(db.list_session_tags(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_imported_session_round_trip() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        let transcript = ImportedTranscript {
+            source: "rollout".to_string(),
+            cwd: Some("/work/app".to_string()),
+            items: vec![serde_json::json!({ "type": "agentMessage", "id": "i0", "text": "hi" })],
+            imported_at: 1,
+        };
+        let session = SessionMetadata::new("imported-1", &project.id);
+        db.insert_imported_session(&session, &transcript, "AGENT: tokenizer rewrite")
+            .unwrap();
+        assert!(db
+            .insert_imported_session(&SessionMetadata::new("imported-2", "missing"), &transcript, "")
+            .is_err());
+
+        let stored = db.get_imported_transcript("imported-1").unwrap().unwrap();
+        assert_eq!(stored.items, transcript.items);
+        assert_eq!(db.search_sessions("tokenizer", &[], false, None, 10).unwrap().len(), 1);
+
+        db.delete_session("imported-1").unwrap();
+        assert!(db.get_imported_transcript("imported-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_stats_recording() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        db.record_turn_started("s1", Some("hi"), Some("gpt-5")).unwrap();
+        let usage = TokenUsage {
+            input_tokens: 120,
+            output_tokens: 30,
+            total_tokens: 150,
+            ..Default::default()
+        };
+        db.record_token_usage("s1", &usage).unwrap();
+        let paths = vec!["a.rs".to_string(), "b.rs".to_string()];
+        db.record_tool_call("s1", "fileChange", &paths).unwrap();
+        db.record_tool_call("s1", "fileChange", &paths[..1]).unwrap();
+        db.record_tool_call("s1", "commandExecution", &[]).unwrap();
+        db.record_turn_finished("s1").unwrap();
+        // Threads without session metadata are ignored
+        db.record_tool_call("agent-thread", "webSearch", &[]).unwrap();
+
+        let stats = db.get_session_stats(Some(&project.id), None, None).unwrap();
+        assert_eq!(stats.len(), 1);
+        let stats = &stats[0];
+        assert_eq!(stats.turn_count, 1);
+        assert_eq!(stats.model.as_deref(), Some("gpt-5"));
+        assert_eq!(stats.token_usage, usage);
+        assert_eq!(stats.tool_calls["fileChange"], 2);
+        assert_eq!(stats.tool_calls["commandExecution"], 1);
+        assert_eq!(stats.files_touched, 2);
+        assert!(stats.last_activity_at.is_some());
+
+        assert!(db.get_session_stats(None, None, Some(i64::MAX)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_turn_usage_accumulates_per_turn() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+        db.record_turn_started("s1", None, Some("gpt-5")).unwrap();
+
+        let call = TokenUsage {
+            input_tokens: 100,
+            output_tokens: 10,
+            total_tokens: 110,
+            ..Default::default()
+        };
+        db.add_turn_usage("s1", "t1", &call, Some(0.5)).unwrap();
+        db.add_turn_usage("s1", "t1", &call, Some(0.25)).unwrap();
+        db.add_turn_usage("agent", "t1", &call, None).unwrap();
+
+        let turns = db.get_turn_usage(Some(&project.id), None, None).unwrap();
+        assert_eq!(turns.len(), 1);
+        assert_eq!(turns[0].model.as_deref(), Some("gpt-5"));
+        assert_eq!(turns[0].token_usage.total_tokens, 220);
+        assert_eq!(turns[0].cost_usd, Some(0.75));
+
+        // Usage history is kept after the session is deleted
+        db.delete_session("s1").unwrap();
+        assert_eq!(db.get_turn_usage(None, None, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_turn_timings_round_trip_and_session_delete() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        let timings = TurnTimings {
+            turn_id: "turn-1".to_string(),
+            session_id: "s1".to_string(),
+            status: Some("completed".to_string()),
+            requested_at: Some(900),
+            started_at: 1_000,
+            completed_at: Some(5_000),
+            elapsed_ms: 4_100,
+            queued_ms: 100,
+            thinking_ms: 2_000,
+            tool_ms: 1_500,
+            patch_ms: 500,
+            tool_calls: 3,
+            patches: 1,
+        };
+        db.insert_turn_timings(&timings).unwrap();
+        let stored = db.get_turn_timings("turn-1").unwrap().unwrap();
+        assert_eq!(stored.tool_ms, 1_500);
+        assert_eq!(stored.tool_calls, 3);
+
+        db.delete_session("s1").unwrap();
+        assert!(db.get_turn_timings("turn-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_turn_outcomes_roll_up_into_session_status() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        let record = |turn_id: &str, outcome, completed_at| TurnOutcomeRecord {
+            turn_id: turn_id.to_string(),
+            session_id: "s1".to_string(),
+            outcome,
+            status: "interrupted".to_string(),
+            error: None,
+            completed_at,
+        };
+        assert_eq!(
+            db.record_turn_outcome(&record("t1", TurnOutcome::Error, 1_000))
+                .unwrap(),
+            Some(SessionStatus::Failed)
+        );
+        db.record_turn_outcome(&record("t2", TurnOutcome::NeedsApprovalTimeout, 2_000))
+            .unwrap();
+        let session = db.get_session_by_id("s1").unwrap().unwrap();
+        assert_eq!(session.status, SessionStatus::Interrupted);
+
+        let outcomes = db.list_turn_outcomes("s1", 10).unwrap();
+        let ids: Vec<_> = outcomes.iter().map(|o| o.turn_id.as_str()).collect();
+        assert_eq!(ids, vec!["t2", "t1"]);
+        assert_eq!(outcomes[0].outcome, TurnOutcome::NeedsApprovalTimeout);
+
+        // Agent threads have no session row
+        let mut agent = record("t3", TurnOutcome::Completed, 3_000);
+        agent.session_id = "agent".to_string();
+        assert_eq!(db.record_turn_outcome(&agent).unwrap(), None);
+
+        db.delete_session("s1").unwrap();
+        assert!(db.list_turn_outcomes("s1", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_project_env_upsert_and_project_delete() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        db.set_project_env(&project.id, "TOKEN", "sealed-1", true).unwrap();
+        db.set_project_env(&project.id, "TOKEN", "sealed-2", true).unwrap();
+        db.set_project_env(&project.id, "API_URL", "sealed-3", false).unwrap();
+        let rows = db.get_project_env(&project.id).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].name, "API_URL");
+        assert!(!rows[0].is_secret);
+        assert_eq!(rows[1].value_encrypted, "sealed-2");
+        assert!(rows[1].is_secret);
+
+        assert!(db.delete_project_env(&project.id, "API_URL").unwrap());
+        assert!(!db.delete_project_env(&project.id, "API_URL").unwrap());
+        db.delete_project(&project.id).unwrap();
+        assert!(db.get_project_env(&project.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_baseline_is_kept_and_deleted_with_session() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        let baseline = |sha: &str| SessionBaseline {
+            session_id: "s1".to_string(),
+            cwd: "/tmp/example".to_string(),
+            commit_sha: sha.to_string(),
+            recorded_at: 1_000,
+        };
+        db.insert_session_baseline(&baseline("abc1234")).unwrap();
+        db.insert_session_baseline(&baseline("def5678")).unwrap();
+        let stored = db.get_session_baseline("s1").unwrap().unwrap();
+        assert_eq!(stored.commit_sha, "abc1234");
+
+        db.delete_session("s1").unwrap();
+        assert!(db.get_session_baseline("s1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_worktree_is_deleted_with_session() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+        db.upsert_session_metadata(&SessionMetadata::new("s1", &project.id))
+            .unwrap();
+
+        db.insert_session_worktree(&SessionWorktree {
+            session_id: "s1".to_string(),
+            project_id: project.id.clone(),
+            path: "/tmp/.worktrees/codex-s1".to_string(),
+            branch: "codex/s1".to_string(),
+            base_branch: Some("main".to_string()),
+            base_commit: Some("abc1234".to_string()),
+            created_at: 1_000,
+        })
+        .unwrap();
+        assert_eq!(
+            db.get_session_worktree("s1").unwrap().unwrap().branch,
+            "codex/s1"
+        );
+        assert_eq!(db.list_session_worktrees(&project.id).unwrap().len(), 1);
+
+        db.delete_session("s1").unwrap();
+        assert!(db.get_session_worktree("s1").unwrap().is_none());
+        assert!(!db.delete_session_worktree("s1").unwrap());
+    }
+
+    #[test]
+    fn test_comparison_runs() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        let group = ComparisonGroup {
+            id: "g1".to_string(),
+            project_id: project.id.clone(),
+            prompt: "Fix the parser".to_string(),
+            base_branch: Some("main".to_string()),
+            base_commit: "abc1234".to_string(),
+            winner_run_id: None,
+            resolved_at: None,
+            created_at: 1_000,
+        };
+        let runs: Vec<_> = (0..2)
+            .map(|position| ComparisonRun {
+                id: format!("r{position}"),
+                group_id: "g1".to_string(),
+                position,
+                model: Some(format!("model-{position}")),
+                effort: None,
+                worktree_path: format!("/tmp/.worktrees/compare-{position}"),
+                branch: format!("codex/compare-{position}"),
+                thread_id: None,
+                status: ComparisonRunStatus::Running,
+                error: None,
+                duration_ms: 0,
+            })
+            .collect();
+        db.insert_comparison(&group, &runs).unwrap();
+
+        let mut finished = runs[1].clone();
+        finished.thread_id = Some("t1".to_string());
+        finished.status = ComparisonRunStatus::TimedOut;
+        finished.duration_ms = 42;
+        db.update_comparison_run(&finished).unwrap();
+        let stored = db.list_comparison_runs("g1").unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].status, ComparisonRunStatus::Running);
+        assert_eq!(stored[1].status, ComparisonRunStatus::TimedOut);
+        assert_eq!(stored[1].thread_id.as_deref(), Some("t1"));
+        assert_eq!(db.interrupt_stale_comparison_runs().unwrap(), 1);
+        assert_eq!(
+            db.list_comparison_runs("g1").unwrap()[0].status,
+            ComparisonRunStatus::Interrupted
+        );
+
+        assert!(db.resolve_comparison("g1", Some("r1"), 2_000).unwrap());
+        let resolved = db.get_comparison("g1").unwrap().unwrap();
+        assert_eq!(resolved.winner_run_id.as_deref(), Some("r1"));
+        assert_eq!(resolved.resolved_at, Some(2_000));
+        assert_eq!(db.list_comparisons(&project.id).unwrap().len(), 1);
+
+        db.delete_project(&project.id).unwrap();
+        assert!(db.get_comparison("g1").unwrap().is_none());
+        assert!(db.list_comparison_runs("g1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_turn_queue_order_and_dispatch() {
+        let (_temp_dir, db) = test_db();
+        let project = Project::new("/tmp/example");
+        db.insert_project(&project).unwrap();
+
+        let a = db.insert_queued_turn(&project.id, "t1", "do A", None, 1).unwrap();
+        let b = db.insert_queued_turn(&project.id, "t1", "do B", None, 2).unwrap();
+        let later = db
+            .insert_queued_turn(&project.id, "t2", "later", Some(1_000), 3)
+            .unwrap();
+        assert!(db
+            .reorder_turn_queue(&project.id, std::slice::from_ref(&b.id))
+            .is_err());
+        db.reorder_turn_queue(&project.id, &[later.id.clone(), b.id.clone(), a.id.clone()])
+            .unwrap();
+
+        // `later` is first but not due yet
+        assert_eq!(db.next_due_turn(&project.id, 10).unwrap().unwrap().id, b.id);
+        db.mark_queued_turn_running(&b.id, "turn-b", 10).unwrap();
+        assert!(db.projects_with_due_turns(10).unwrap().is_empty());
+        assert_eq!(db.running_queued_turn("t1").unwrap().unwrap().id, b.id);
+
+        db.finish_queued_turn(&b.id, "completed", None, 20).unwrap();
+        assert_eq!(db.projects_with_due_turns(20).unwrap(), vec![project.id.clone()]);
+        assert!(db.cancel_queued_turn(&a.id, 30).unwrap());
+        assert!(!db.cancel_queued_turn(&a.id, 31).unwrap());
+
+        let pending = db.list_turn_queue(Some(&project.id), false).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(db.list_turn_queue(None, true).unwrap().len(), 3);
+
+        db.delete_project(&project.id).unwrap();
+        assert!(db.list_turn_queue(None, true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_accounts_upsert_by_identity() {
+        let (_temp_dir, db) = test_db();
+
+        let work = db
+            .upsert_account("chatgpt:org-1", None, Some("me@work.com"), "chatgpt", Some("team"), "{}")
+            .unwrap();
+        assert_eq!(work.label, "me@work.com");
+        let renamed = db
+            .upsert_account("chatgpt:org-1", Some("Work"), None, "chatgpt", None, r#"{"v":2}"#)
+            .unwrap();
+        assert_eq!(renamed.id, work.id);
+        assert_eq!(renamed.label, "Work");
+        assert_eq!(renamed.email.as_deref(), Some("me@work.com"));
+        assert_eq!(db.get_account_auth(&work.id).unwrap().as_deref(), Some(r#"{"v":2}"#));
+
+        db.upsert_account("apikey:abc", None, None, "apiKey", None, "{}").unwrap();
+        assert_eq!(db.list_accounts().unwrap().len(), 2);
+        assert!(db.delete_account(&work.id).unwrap());
+        assert!(db.get_account_auth(&work.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_hook_bypasses_newest_first() {
+        let (_temp_dir, db) = test_db();
+        let bypass = |id: &str, operation: &str, created_at: i64| HookBypass {
+            id: id.to_string(),
+            repo_path: "/tmp/example".to_string(),
+            operation: operation.to_string(),
+            detail: "fix: typo".to_string(),
+            hooks: vec!["pre-commit".to_string()],
+            created_at,
+        };
+        db.insert_hook_bypass(&bypass("b1", "commit", 1_000)).unwrap();
+        db.insert_hook_bypass(&bypass("b2", "push", 2_000)).unwrap();
+
+        let stored = db.list_hook_bypasses("/tmp/example", 10).unwrap();
+        let ids: Vec<_> = stored.iter().map(|bypass| bypass.id.as_str()).collect();
+        assert_eq!(ids, vec!["b2", "b1"]);
+        assert_eq!(stored[1].hooks, vec!["pre-commit"]);
+        assert_eq!(db.list_hook_bypasses("/tmp/example", 1).unwrap().len(), 1);
+        assert!(db.list_hook_bypasses("/tmp/other", 10).unwrap().is_empty());
+    }
 }
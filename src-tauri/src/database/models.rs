@@ -1,5 +1,7 @@
 //! Database model definitions
 
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Project stored in local database
@@ -23,6 +25,15 @@ pub struct Project {
 
     /// JSON-encoded project settings
     pub settings_json: Option<String>,
+
+    /// Accent color as `#rrggbb` (defaults to a color derived from the path)
+    pub color: Option<String>,
+
+    /// Icon name shown next to the project
+    pub icon: Option<String>,
+
+    /// Emoji shown instead of the icon
+    pub emoji: Option<String>,
 }
 
 impl Project {
@@ -40,8 +51,34 @@ impl Project {
             created_at: chrono::Utc::now().timestamp(),
             last_opened_at: None,
             settings_json: None,
+            color: Some(default_project_color(path).to_string()),
+            icon: None,
+            emoji: None,
         }
     }
+
+    /// Parse project settings from JSON (defaults when missing or invalid)
+    pub fn settings(&self) -> ProjectSettings {
+        self.settings_json
+            .as_ref()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Accent colors assigned to projects by default
+pub const PROJECT_COLORS: &[&str] = &[
+    "#ef4444", "#f97316", "#f59e0b", "#84cc16", "#22c55e", "#14b8a6",
+    "#06b6d4", "#3b82f6", "#6366f1", "#8b5cf6", "#d946ef", "#ec4899",
+];
+
+/// Default accent color for a project, stable for a given path
+pub fn default_project_color(path: &str) -> &'static str {
+    // FNV-1a: stable across runs and platforms, unlike `DefaultHasher`
+    let hash = path.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    PROJECT_COLORS[(hash % PROJECT_COLORS.len() as u64) as usize]
 }
 
 /// Session status enum for agent state tracking
@@ -116,6 +153,26 @@ pub struct SessionMetadata {
 
     /// JSON array of tasks for progress tracking
     pub tasks_json: Option<String>,
+
+    /// Project-relative subdirectory used as the agent cwd, if any
+    #[serde(default)]
+    pub cwd_subpath: Option<String>,
+
+    /// Clipped text of the most recent user or agent message
+    #[serde(default)]
+    pub last_message_preview: Option<String>,
+
+    /// Number of turns started in this session
+    #[serde(default)]
+    pub turn_count: u32,
+
+    /// Number of file changes applied in this session
+    #[serde(default)]
+    pub files_changed_count: u32,
+
+    /// Model used for the most recent turn, if known
+    #[serde(default)]
+    pub last_model: Option<String>,
 }
 
 impl SessionMetadata {
@@ -133,6 +190,11 @@ impl SessionMetadata {
             status: SessionStatus::Idle,
             first_message: None,
             tasks_json: None,
+            cwd_subpath: None,
+            last_message_preview: None,
+            turn_count: 0,
+            files_changed_count: 0,
+            last_model: None,
         }
     }
 
@@ -180,6 +242,14 @@ impl SessionMetadata {
     }
 }
 
+/// Session list fields recomputed from a complete transcript
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionPreview {
+    pub last_message_preview: Option<String>,
+    pub turn_count: u32,
+    pub files_changed_count: u32,
+}
+
 /// Task item for progress tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -203,7 +273,7 @@ pub struct Snapshot {
     /// Unix timestamp when created
     pub created_at: i64,
 
-    /// Type: "git_ghost" or "file_backup"
+    /// Type: "git_ghost", "git_worktree" or "file_backup"
     pub snapshot_type: String,
 
     /// JSON-encoded snapshot metadata
@@ -226,6 +296,17 @@ impl Snapshot {
         }
     }
 
+    /// Create a snapshot of a git working tree captured as a detached commit
+    pub fn new_git_worktree(session_id: &str, metadata_json: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            session_id: session_id.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            snapshot_type: "git_worktree".to_string(),
+            metadata_json: Some(metadata_json.to_string()),
+        }
+    }
+
     /// Create a new file backup snapshot with full metadata
     pub fn new_file_backup(session_id: &str, metadata_json: &str) -> Self {
         Self {
@@ -249,6 +330,78 @@ impl Snapshot {
     }
 }
 
+/// A session matched by full-text search
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    #[serde(flatten)]
+    pub session: SessionMetadata,
+
+    /// BM25 rank (lower is more relevant); 0 for substring-only matches
+    pub rank: f64,
+
+    /// Final ranking score with title, recency and project boosts applied
+    /// (higher is more relevant); results are ordered by it. 0 for
+    /// substring-only matches, which come last
+    pub score: f64,
+
+    /// Excerpt around the best match, without markup
+    pub snippet: Option<String>,
+
+    /// `[start, end)` UTF-16 offsets of matched terms within `snippet`
+    pub highlights: Vec<(usize, usize)>,
+
+    /// `[start, end)` UTF-16 offsets of the matched word prefixes within `title`
+    pub title_highlights: Vec<(usize, usize)>,
+}
+
+/// Filters for listing a project's sessions
+#[derive(Debug, Clone, Default)]
+pub struct SessionFilter {
+    /// Only sessions carrying every one of these tags
+    pub tags: Vec<String>,
+    /// Only favorited sessions
+    pub favorites_only: bool,
+    /// Archived sessions instead of active ones
+    pub archived: bool,
+}
+
+/// A tag and the number of sessions carrying it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionTagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Number and approximate size of orphaned rows of one kind
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanGroup {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// Rows whose parent session or project no longer exists
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanCounts {
+    /// Session metadata for deleted projects
+    pub sessions: OrphanGroup,
+    /// Snapshots for missing or orphaned sessions
+    pub snapshots: OrphanGroup,
+    /// Allowlist entries for deleted projects
+    pub allowlist: OrphanGroup,
+    /// Search index entries for missing or orphaned sessions
+    pub search_index: OrphanGroup,
+}
+
+impl OrphanCounts {
+    pub fn total_bytes(&self) -> u64 {
+        self.sessions.bytes + self.snapshots.bytes + self.allowlist.bytes + self.search_index.bytes
+    }
+}
+
 /// Project settings stored as JSON
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -262,9 +415,595 @@ pub struct ProjectSettings {
     /// Default model to use
     pub model: Option<String>,
 
+    /// Default reasoning effort
+    pub reasoning_effort: Option<String>,
+
     /// Default sandbox mode
     pub sandbox_mode: Option<String>,
 
     /// Default approval policy
     pub ask_for_approval: Option<String>,
+
+    /// How symbolic links inside the project are treated by file commands
+    pub symlink_policy: Option<SymlinkPolicy>,
+
+    /// Extra .gitignore-style patterns for file listing and search;
+    /// `!pattern` re-includes hidden files and built-in ignored directories
+    pub ignore_rules: Option<Vec<String>>,
+
+    /// Saved account new threads in this project should run under
+    pub account_id: Option<String>,
+
+    /// Custom model provider new threads in this project use
+    pub model_provider: Option<String>,
+
+    /// Which command approvals are answered without asking
+    pub approval_preset: Option<ApprovalPreset>,
+
+    /// Restrictions on terminal and user shell commands run for the project
+    pub sandbox_profile: Option<crate::sandbox::SandboxProfile>,
+
+    /// Snapshot the project before every turn starts
+    pub auto_snapshot: Option<bool>,
+
+    /// Start every thread in a new git worktree and branch of its own
+    pub worktree_per_session: Option<bool>,
+
+    /// Branch name for session worktrees; `{project}`, `{date}` and `{id}`
+    /// are filled in
+    pub worktree_branch_template: Option<String>,
+
+    /// Fields this version does not know about, preserved on save
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProjectSettings {
+    /// Apply a JSON merge patch (RFC 7386): omitted keys are kept, `null` clears a key
+    pub fn merge_patch(&self, patch: &serde_json::Value) -> serde_json::Result<ProjectSettings> {
+        let mut merged = serde_json::to_value(self)?;
+        apply_merge_patch(&mut merged, patch);
+        serde_json::from_value(merged)
+    }
+}
+
+fn apply_merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let serde_json::Value::Object(target) = target else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            apply_merge_patch(target.entry(key.clone()).or_insert(serde_json::Value::Null), value);
+        }
+    }
+}
+
+/// Partial update applied by `Database::update_project`; `None` leaves a field unchanged
+#[derive(Debug, Clone, Default)]
+pub struct ProjectUpdate {
+    pub display_name: Option<String>,
+    /// JSON merge patch applied to the stored settings
+    pub settings_patch: Option<serde_json::Value>,
+    pub last_opened_at: Option<i64>,
+}
+
+/// Symbolic link handling for project file access
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum SymlinkPolicy {
+    /// Follow symlinks only when their target stays inside the project root
+    #[default]
+    FollowWithinRoot,
+    /// Follow symlinks anywhere (trusted projects only)
+    FollowAll,
+    /// Reject any path that traverses a symlink
+    Deny,
+}
+
+/// Which command approval requests are answered without asking the user
+///
+/// Commands matching a built-in deny rule and file-change approvals are
+/// always surfaced.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ApprovalPreset {
+    /// Approve read-only and allowlisted commands; ask for everything else.
+    /// Project tooling like `npm run` runs arbitrary code, so it is asked
+    /// for; the former `askForWrites`, which approved it, reads as this.
+    #[serde(alias = "askForWrites")]
+    AutoApproveRead,
+    /// Ask for every approval request
+    #[default]
+    AskForEverything,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_turn_outcome_classification() {
+        let classify = TurnOutcome::classify;
+        assert_eq!(classify("completed", None, None), TurnOutcome::Completed);
+        assert_eq!(classify("completed", Some("stream error"), None), TurnOutcome::Error);
+        assert_eq!(classify("failed", None, None), TurnOutcome::Error);
+        assert_eq!(classify("interrupted", None, Some(1_000)), TurnOutcome::Interrupted);
+        assert_eq!(
+            classify("interrupted", None, Some(APPROVAL_TIMEOUT_MS)),
+            TurnOutcome::NeedsApprovalTimeout
+        );
+        assert_eq!(
+            TurnOutcome::NeedsApprovalTimeout.session_status(),
+            SessionStatus::Interrupted
+        );
+        assert_eq!(TurnOutcome::Error.session_status(), SessionStatus::Failed);
+        for outcome in [TurnOutcome::Interrupted, TurnOutcome::NeedsApprovalTimeout] {
+            assert_eq!(TurnOutcome::from_str(outcome.as_str()), outcome);
+        }
+    }
+
+    #[test]
+    fn test_default_project_color_is_stable() {
+        let color = default_project_color("/Users/me/code/app");
+        assert_eq!(color, default_project_color("/Users/me/code/app"));
+        assert!(PROJECT_COLORS.contains(&color));
+        assert_eq!(Project::new("/Users/me/code/app").color.as_deref(), Some(color));
+    }
+
+    #[test]
+    fn test_settings_merge_patch_keeps_unchanged_fields() {
+        let stored: ProjectSettings = serde_json::from_value(json!({
+            "model": "gpt-5",
+            "sandboxMode": "workspace-write",
+            "envVars": { "A": "1", "B": "2" },
+            "futureFlag": true,
+        }))
+        .unwrap();
+
+        let merged = stored
+            .merge_patch(&json!({ "model": "o3", "sandboxMode": null, "envVars": { "B": null } }))
+            .unwrap();
+        assert_eq!(merged.model.as_deref(), Some("o3"));
+        assert!(merged.sandbox_mode.is_none());
+        assert_eq!(merged.env_vars.unwrap().len(), 1);
+        assert_eq!(merged.extra.get("futureFlag"), Some(&json!(true)));
+
+        let round_trip = serde_json::to_value(&stored).unwrap();
+        assert_eq!(round_trip["futureFlag"], json!(true));
+        assert!(stored.merge_patch(&json!({ "symlinkPolicy": "bogus" })).is_err());
+    }
+}
+
+/// Everything stored for one project, as written to a cold-storage archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectArchive {
+    /// Archive layout version, bumped on incompatible changes
+    pub format_version: u32,
+    /// Unix timestamp when the archive was written
+    pub exported_at: i64,
+    pub project: Project,
+    pub sessions: Vec<SessionMetadata>,
+    pub snapshots: Vec<Snapshot>,
+    pub allowlist: Vec<String>,
+    #[serde(default)]
+    pub denylist: Vec<String>,
+    /// Indexed conversation text by session ID
+    #[serde(default)]
+    pub session_content: BTreeMap<String, String>,
+    /// Stored transcripts of imported sessions by session ID
+    #[serde(default)]
+    pub imported_transcripts: BTreeMap<String, ImportedTranscript>,
+}
+
+/// A project moved out of the live database into an archive file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedProject {
+    pub id: String,
+    pub path: String,
+    pub display_name: Option<String>,
+    /// Absolute path of the archive file
+    pub archive_path: String,
+    /// Unix timestamp when the project was archived
+    pub archived_at: i64,
+    pub session_count: usize,
+}
+
+/// Thread items of a session imported from a transcript file
+///
+/// Imported sessions have no app-server thread, so their items live here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedTranscript {
+    /// "export" or "rollout"
+    pub source: String,
+    /// Working directory recorded in the file, if any
+    pub cwd: Option<String>,
+    pub items: Vec<serde_json::Value>,
+    /// Unix timestamp of the import
+    pub imported_at: i64,
+}
+
+/// Token counts reported by the app-server for a thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Add `other`'s counts to these
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_output_tokens += other.reasoning_output_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Usage metrics for one session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStats {
+    pub session_id: String,
+    pub project_id: String,
+    /// Display name (title or first message)
+    pub title: String,
+    /// Model used for the most recent turn, if known
+    pub model: Option<String>,
+    /// Unix timestamp when the session was created
+    pub created_at: i64,
+    /// Unix timestamp of the last turn or item, if any were recorded
+    pub last_activity_at: Option<i64>,
+    pub turn_count: u32,
+    pub token_usage: TokenUsage,
+    /// Completed tool items by item type (commandExecution, mcpToolCall, ...)
+    pub tool_calls: BTreeMap<String, u32>,
+    /// Distinct files changed in the session
+    pub files_touched: u32,
+    /// Seconds spent with a turn running
+    pub active_seconds: i64,
+}
+
+/// Where a turn's time went, in milliseconds
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnTimings {
+    pub turn_id: String,
+    pub session_id: String,
+    /// Final turn status; `None` while the turn is running
+    pub status: Option<String>,
+    /// Unix ms the `turn/start` request was sent, when the app sent it
+    pub requested_at: Option<i64>,
+    /// Unix ms the app-server started the turn
+    pub started_at: i64,
+    pub completed_at: Option<i64>,
+    /// Queued plus running time
+    pub elapsed_ms: i64,
+    /// Waiting between the request and the turn starting
+    pub queued_ms: i64,
+    /// Running time not covered by tools or patches (model sampling)
+    pub thinking_ms: i64,
+    pub tool_ms: i64,
+    pub patch_ms: i64,
+    pub tool_calls: u32,
+    pub patches: u32,
+}
+
+/// Approvals left unanswered this long turn an interrupted turn into an
+/// approval timeout
+pub const APPROVAL_TIMEOUT_MS: i64 = 5 * 60 * 1000;
+
+/// How a turn ended, as classified by the backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TurnOutcome {
+    Completed,
+    /// Stopped before finishing, by the user or a budget
+    Interrupted,
+    /// Failed, or reported an error
+    Error,
+    /// Stopped while an approval had been waiting for at least
+    /// `APPROVAL_TIMEOUT_MS`
+    NeedsApprovalTimeout,
+}
+
+impl TurnOutcome {
+    /// Classify a finished turn from its app-server status, the error it
+    /// reported and how long its oldest unanswered approval had been waiting
+    pub fn classify(status: &str, error: Option<&str>, approval_wait_ms: Option<i64>) -> Self {
+        match status {
+            "failed" => Self::Error,
+            "interrupted" if approval_wait_ms.is_some_and(|ms| ms >= APPROVAL_TIMEOUT_MS) => {
+                Self::NeedsApprovalTimeout
+            }
+            "interrupted" => Self::Interrupted,
+            _ if error.is_some() => Self::Error,
+            _ => Self::Completed,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurnOutcome::Completed => "completed",
+            TurnOutcome::Interrupted => "interrupted",
+            TurnOutcome::Error => "error",
+            TurnOutcome::NeedsApprovalTimeout => "needs-approval-timeout",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "interrupted" => TurnOutcome::Interrupted,
+            "error" => TurnOutcome::Error,
+            "needs-approval-timeout" => TurnOutcome::NeedsApprovalTimeout,
+            _ => TurnOutcome::Completed,
+        }
+    }
+
+    /// The status a session rolls up to after a turn with this outcome
+    pub fn session_status(&self) -> SessionStatus {
+        match self {
+            TurnOutcome::Completed => SessionStatus::Completed,
+            TurnOutcome::Interrupted | TurnOutcome::NeedsApprovalTimeout => {
+                SessionStatus::Interrupted
+            }
+            TurnOutcome::Error => SessionStatus::Failed,
+        }
+    }
+}
+
+/// A finished turn's classified outcome
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnOutcomeRecord {
+    pub turn_id: String,
+    pub session_id: String,
+    pub outcome: TurnOutcome,
+    /// The app-server's own turn status
+    pub status: String,
+    pub error: Option<String>,
+    /// Unix milliseconds
+    pub completed_at: i64,
+}
+
+/// The journaled part of a turn the app exited in the middle of, replayed
+/// at the next launch
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredTurn {
+    pub session_id: String,
+    pub turn_id: String,
+    /// In the order they started; unfinished ones carry `"partial": true`
+    pub items: Vec<serde_json::Value>,
+    /// Unix milliseconds
+    pub started_at: i64,
+    /// Unix milliseconds of the last journaled event
+    pub last_event_at: i64,
+    pub recovered_at: i64,
+}
+
+/// A commit or push made with `--no-verify`, skipping the repository's hooks
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookBypass {
+    pub id: String,
+    pub repo_path: String,
+    /// "commit" or "push"
+    pub operation: String,
+    /// Commit subject, or remote and branch pushed to
+    pub detail: String,
+    /// Hooks that would have run
+    pub hooks: Vec<String>,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+/// A stored project environment variable, still encrypted
+#[derive(Debug, Clone)]
+pub struct ProjectEnvRow {
+    pub name: String,
+    /// Sealed by `project_env`
+    pub value_encrypted: String,
+    /// Masked in logs, events and session text once loaded
+    pub is_secret: bool,
+    /// Unix seconds
+    pub updated_at: i64,
+}
+
+/// A Codex CLI rollout file in the session search index
+#[derive(Debug, Clone)]
+pub struct CodexIndexEntry {
+    pub file_path: String,
+    /// Unix ms of the file's last modification when it was indexed
+    pub modified_at: i64,
+    pub file_size: i64,
+    /// Session start, for ordering
+    pub timestamp: String,
+    /// The serialized `CodexSessionSummary`
+    pub summary_json: String,
+    pub project_name: String,
+    pub cwd: String,
+    pub git_branch: Option<String>,
+    pub first_message: Option<String>,
+    /// Message text searched besides the summary fields
+    pub content: String,
+}
+
+/// The commit a session's working tree started from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBaseline {
+    pub session_id: String,
+    /// Directory the session's agent runs in
+    pub cwd: String,
+    /// HEAD when the session started (the empty tree for a repository
+    /// without commits)
+    pub commit_sha: String,
+    /// Unix seconds
+    pub recorded_at: i64,
+}
+
+/// The git worktree and branch created for a session; see
+/// `session_worktrees`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWorktree {
+    pub session_id: String,
+    pub project_id: String,
+    /// Worktree directory the session's agent runs in
+    pub path: String,
+    pub branch: String,
+    /// Branch checked out in the project when the worktree was made
+    pub base_branch: Option<String>,
+    pub base_commit: Option<String>,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+/// One prompt run side by side in several worktrees; see `comparison`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonGroup {
+    pub id: String,
+    pub project_id: String,
+    pub prompt: String,
+    /// Branch checked out in the project when the runs were started
+    pub base_branch: Option<String>,
+    /// Commit every run's worktree started from
+    pub base_commit: String,
+    /// Run that was merged back, once the comparison is resolved
+    pub winner_run_id: Option<String>,
+    /// Unix seconds; set once the winner is merged and the worktrees removed
+    pub resolved_at: Option<i64>,
+    /// Unix seconds
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ComparisonRunStatus {
+    #[default]
+    Running,
+    Completed,
+    Failed,
+    Interrupted,
+    TimedOut,
+}
+
+impl ComparisonRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ComparisonRunStatus::Running => "running",
+            ComparisonRunStatus::Completed => "completed",
+            ComparisonRunStatus::Failed => "failed",
+            ComparisonRunStatus::Interrupted => "interrupted",
+            ComparisonRunStatus::TimedOut => "timedOut",
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Self {
+        match s {
+            "completed" => ComparisonRunStatus::Completed,
+            "failed" => ComparisonRunStatus::Failed,
+            "interrupted" => ComparisonRunStatus::Interrupted,
+            "timedOut" => ComparisonRunStatus::TimedOut,
+            _ => ComparisonRunStatus::Running,
+        }
+    }
+}
+
+/// One run of a comparison, in a worktree and branch of its own
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonRun {
+    pub id: String,
+    pub group_id: String,
+    /// 0-based order the runs were requested in
+    pub position: u32,
+    pub model: Option<String>,
+    pub effort: Option<String>,
+    pub worktree_path: String,
+    pub branch: String,
+    /// Thread the prompt ran in, once it has started
+    pub thread_id: Option<String>,
+    pub status: ComparisonRunStatus,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// A Codex login saved for switching between accounts
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedAccount {
+    pub id: String,
+    pub label: String,
+    pub email: Option<String>,
+    /// "chatgpt" or "apiKey"
+    pub auth_mode: String,
+    pub plan_type: Option<String>,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    /// Whether this login is the one currently in the Codex auth file
+    pub is_active: bool,
+    /// Stable key of the login (ChatGPT account id or API key hash)
+    #[serde(skip)]
+    pub identity: String,
+}
+
+/// Token counts and estimated cost of one turn
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsage {
+    pub session_id: String,
+    pub turn_id: String,
+    /// `None` for threads without a project (agent threads)
+    pub project_id: Option<String>,
+    pub model: Option<String>,
+    pub token_usage: TokenUsage,
+    /// Estimated USD cost; `None` when the model has no known pricing
+    pub cost_usd: Option<f64>,
+    /// Unix timestamp of the turn's first model call
+    pub recorded_at: i64,
+}
+
+/// A prompt lined up in a project's turn queue
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTurn {
+    pub id: String,
+    pub project_id: String,
+    /// Thread the turn runs on
+    pub thread_id: String,
+    pub prompt: String,
+    /// Order within the project's queue; lower runs first
+    pub position: i64,
+    /// "queued", "running", "completed", "failed" or "cancelled"
+    pub status: String,
+    /// Unix ms before which the turn is not started
+    pub run_after: Option<i64>,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// Set once the turn has been started
+    pub turn_id: Option<String>,
+    pub error: Option<String>,
 }
@@ -0,0 +1,362 @@
+//! Helpers for the `session_fts` full-text index
+//!
+//! FTS5 picks the candidates and scores them with BM25; `score` then reranks
+//! them the way a search palette should: sessions whose title starts with
+//! what was typed come first, recently used sessions beat stale ones, and
+//! sessions of the current project beat other projects'.
+//!
+//! FTS5 only matches whole tokens and token prefixes, so a session id or a
+//! fragment from inside a word would never be found; those are looked up by
+//! plain substring afterwards, with `substring_range` and `excerpt` marking
+//! where they matched.
+
+/// Marks the start of a matched term in `snippet()` output
+pub(super) const HIGHLIGHT_START: char = '\u{2}';
+
+/// Marks the end of a matched term in `snippet()` output
+pub(super) const HIGHLIGHT_END: char = '\u{3}';
+
+/// Candidates fetched from FTS5 per requested result, to rerank
+pub(super) const CANDIDATES_PER_RESULT: usize = 4;
+
+/// Fewest candidates fetched from FTS5, so small limits still rerank
+pub(super) const MIN_CANDIDATES: usize = 200;
+
+/// Boost when the title's leading words match the query terms in order
+const TITLE_PREFIX_BOOST: f64 = 2.0;
+
+/// Boost when every query term starts some word of the title; partial
+/// matches get a share of it
+const TITLE_WORD_BOOST: f64 = 0.75;
+
+/// Days after which a session's recency bonus has halved
+const RECENCY_HALF_LIFE_DAYS: f64 = 14.0;
+
+/// Share of the score that decays with age; stale sessions keep the rest
+const RECENCY_WEIGHT: f64 = 0.5;
+
+/// Multiplier for sessions of the project the search was made from
+const PROJECT_AFFINITY_BOOST: f64 = 1.5;
+
+/// Characters kept on each side of a substring match in an excerpt
+const EXCERPT_CONTEXT_CHARS: usize = 48;
+
+/// What a candidate is ranked on besides its BM25 rank
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RankInput<'a> {
+    /// BM25 rank from FTS5 (negative; lower is more relevant)
+    pub bm25: f64,
+    pub title: Option<&'a str>,
+    /// Last access, falling back to creation, in Unix seconds
+    pub last_used_at: i64,
+    pub in_current_project: bool,
+}
+
+/// Lowercased search terms, with quotes removed
+pub(super) fn query_terms(input: &str) -> Vec<String> {
+    input
+        .split_whitespace()
+        .map(|term| term.replace('"', "").to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Build an FTS5 query from user input
+///
+/// Every whitespace-separated term must match; each is quoted so FTS syntax
+/// characters are taken literally, and the last term matches as a prefix to
+/// support search-as-you-type. Returns `None` when there is nothing to search.
+pub(super) fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{term}\""))
+        .collect();
+    let last = terms.len().checked_sub(1)?;
+    Some(
+        terms
+            .iter()
+            .enumerate()
+            .map(|(idx, term)| if idx == last { format!("{term}*") } else { term.clone() })
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// The query as one substring to look for; `None` when blank
+pub(super) fn substring_needle(input: &str) -> Option<&str> {
+    Some(input.trim()).filter(|needle| !needle.is_empty())
+}
+
+/// `[start, end)` UTF-16 offsets of the first occurrence of `needle` in
+/// `text`, ignoring ASCII case as SQLite's `lower()` does
+pub(super) fn substring_range(text: &str, needle: &str) -> Option<(usize, usize)> {
+    let (start, end) = substring_chars(text, needle)?;
+    let utf16 = |chars: usize| text.chars().take(chars).map(char::len_utf16).sum::<usize>();
+    Some((utf16(start), utf16(end)))
+}
+
+/// Part of `text` around the first occurrence of `needle`, with the UTF-16
+/// range of the match within the excerpt
+pub(super) fn excerpt(text: &str, needle: &str) -> Option<(String, (usize, usize))> {
+    let (start, end) = substring_chars(text, needle)?;
+    let chars: Vec<char> = text.chars().collect();
+    let from = start.saturating_sub(EXCERPT_CONTEXT_CHARS);
+    let to = (end + EXCERPT_CONTEXT_CHARS).min(chars.len());
+
+    let mut excerpt = String::new();
+    if from > 0 {
+        excerpt.push('…');
+    }
+    let before: String = chars[from..start].iter().collect();
+    excerpt.push_str(&before);
+    let match_start = excerpt.encode_utf16().count();
+    excerpt.extend(&chars[start..end]);
+    let match_end = excerpt.encode_utf16().count();
+    excerpt.extend(&chars[end..to]);
+    if to < chars.len() {
+        excerpt.push('…');
+    }
+    Some((excerpt, (match_start, match_end)))
+}
+
+/// `[start, end)` char offsets of the first ASCII-case-insensitive
+/// occurrence of `needle` in `text`
+fn substring_chars(text: &str, needle: &str) -> Option<(usize, usize)> {
+    let text: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = needle.chars().collect();
+    if needle.is_empty() || needle.len() > text.len() {
+        return None;
+    }
+    let start = text.windows(needle.len()).position(|window| {
+        window
+            .iter()
+            .zip(&needle)
+            .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    })?;
+    Some((start, start + needle.len()))
+}
+
+/// Strip highlight markers from a snippet, returning the plain text and the
+/// `[start, end)` ranges of matched terms in UTF-16 code units
+pub(super) fn parse_snippet(raw: &str) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut highlights = Vec::new();
+    let mut offset = 0;
+    let mut start = None;
+
+    for ch in raw.chars() {
+        match ch {
+            HIGHLIGHT_START => start = Some(offset),
+            HIGHLIGHT_END => {
+                if let Some(start) = start.take() {
+                    highlights.push((start, offset));
+                }
+            }
+            _ => {
+                text.push(ch);
+                offset += ch.len_utf16();
+            }
+        }
+    }
+
+    (text, highlights)
+}
+
+/// Relevance of a candidate; higher is better
+pub(super) fn score(input: &RankInput<'_>, terms: &[String], now: i64) -> f64 {
+    let relevance = -input.bm25.min(0.0);
+
+    let prefix_boost = match input.title {
+        Some(title) => {
+            let words = title_words(title);
+            let leading = terms.len() <= words.len()
+                && terms
+                    .iter()
+                    .zip(&words)
+                    .all(|(term, (_, word))| prefix_len(word, term).is_some());
+            if leading && !terms.is_empty() {
+                TITLE_PREFIX_BOOST
+            } else {
+                let matched = terms
+                    .iter()
+                    .filter(|term| {
+                        words
+                            .iter()
+                            .any(|(_, word)| prefix_len(word, term).is_some())
+                    })
+                    .count();
+                TITLE_WORD_BOOST * matched as f64 / terms.len().max(1) as f64
+            }
+        }
+        None => 0.0,
+    };
+
+    let age_days = (now - input.last_used_at).max(0) as f64 / 86_400.0;
+    let recency =
+        (1.0 - RECENCY_WEIGHT) + RECENCY_WEIGHT * 0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS);
+
+    let affinity = if input.in_current_project {
+        PROJECT_AFFINITY_BOOST
+    } else {
+        1.0
+    };
+
+    relevance * (1.0 + prefix_boost) * recency * affinity
+}
+
+/// `[start, end)` UTF-16 offsets of the parts of `title` that match a term:
+/// the matched prefix of each word that starts with one
+pub(super) fn title_highlights(title: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    title_words(title)
+        .into_iter()
+        .filter_map(|(start, word)| {
+            let len = terms
+                .iter()
+                .filter_map(|term| prefix_len(word, term))
+                .max()?;
+            Some((start, start + len))
+        })
+        .collect()
+}
+
+/// Words of `title` as FTS5 tokenizes them, with their UTF-16 start offsets
+fn title_words(title: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut offset = 0;
+    let mut start = None;
+    for (idx, ch) in title.char_indices() {
+        if ch.is_alphanumeric() {
+            start.get_or_insert((idx, offset));
+        } else if let Some((byte_start, utf16_start)) = start.take() {
+            words.push((utf16_start, &title[byte_start..idx]));
+        }
+        offset += ch.len_utf16();
+    }
+    if let Some((byte_start, utf16_start)) = start {
+        words.push((utf16_start, &title[byte_start..]));
+    }
+    words
+}
+
+/// UTF-16 length of the start of `word` matching the lowercased `term`, if
+/// `word` starts with it
+fn prefix_len(word: &str, term: &str) -> Option<usize> {
+    let mut word_chars = word.chars();
+    let mut len = 0;
+    for term_char in term.chars() {
+        let word_char = word_chars.next()?;
+        if !word_char.to_lowercase().eq(term_char.to_lowercase()) {
+            return None;
+        }
+        len += word_char.len_utf16();
+    }
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_quotes_terms() {
+        assert_eq!(
+            fts_query("parse config").as_deref(),
+            Some("\"parse\" \"config\"*")
+        );
+        assert_eq!(fts_query("a\"b OR").as_deref(), Some("\"ab\" \"OR\"*"));
+        assert!(fts_query("  \" ").is_none());
+    }
+
+    #[test]
+    fn test_parse_snippet_offsets() {
+        let (text, highlights) = parse_snippet("fix \u{2}café\u{3} and \u{2}😀\u{3}");
+        assert_eq!(text, "fix café and 😀");
+        assert_eq!(highlights, vec![(4, 8), (13, 15)]);
+    }
+
+    #[test]
+    fn test_substring_matches() {
+        assert_eq!(substring_needle("  019a-f3 "), Some("019a-f3"));
+        assert!(substring_needle("   ").is_none());
+
+        assert_eq!(substring_range("café Refactor", "FACT"), Some((7, 11)));
+        assert!(substring_range("config", "configs").is_none());
+
+        let message = format!("{} needle {}", "a".repeat(60), "b".repeat(60));
+        let (text, range) = excerpt(&message, "Needle").unwrap();
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        let matched: String = text
+            .encode_utf16()
+            .skip(range.0)
+            .take(range.1 - range.0)
+            .map(|unit| unit as u8 as char)
+            .collect();
+        assert_eq!(matched, "needle");
+        assert_eq!(excerpt("see 😀 here", "here").unwrap().1, (7, 11));
+    }
+
+    #[test]
+    fn test_title_highlights() {
+        let terms = query_terms("Conf par");
+        assert_eq!(terms, vec!["conf", "par"]);
+        assert_eq!(
+            title_highlights("Refactor config-parser (café)", &terms),
+            vec![(9, 13), (16, 19)]
+        );
+        assert_eq!(
+            title_highlights("Ünïcode cafés", &query_terms("ünï CAF")),
+            vec![(0, 3), (8, 11)]
+        );
+        assert!(title_highlights("deconfigure", &terms).is_empty());
+    }
+
+    #[test]
+    fn test_score_boosts() {
+        let now = 1_700_000_000;
+        let terms = query_terms("config");
+        let base = RankInput {
+            bm25: -2.0,
+            title: Some("Fix the config loader"),
+            last_used_at: now,
+            in_current_project: false,
+        };
+        let word = score(&base, &terms, now);
+        let leading = score(
+            &RankInput {
+                title: Some("Config cleanup"),
+                ..base
+            },
+            &terms,
+            now,
+        );
+        let untitled = score(
+            &RankInput {
+                title: None,
+                ..base
+            },
+            &terms,
+            now,
+        );
+        assert!(leading > word && word > untitled);
+        assert_eq!(untitled, 2.0);
+
+        let stale = RankInput {
+            last_used_at: now - 14 * 86_400,
+            ..base
+        };
+        assert_eq!(score(&stale, &terms, now), word * 0.75);
+        let ancient = RankInput {
+            last_used_at: 0,
+            ..base
+        };
+        assert!(score(&ancient, &terms, now) >= word * 0.5);
+
+        let local = RankInput {
+            in_current_project: true,
+            ..stale
+        };
+        assert!(score(&local, &terms, now) > score(&stale, &terms, now));
+    }
+}
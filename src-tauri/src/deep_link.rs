@@ -0,0 +1,302 @@
+//! `codex-desktop://` links.
+//!
+//! A link opens a project or one of its sessions, or starts a thread:
+//!
+//! - `codex-desktop://project/<project id>`
+//! - `codex-desktop://project/<project id>/session/<session id>`
+//! - `codex-desktop://new?repo=<path>&prompt=<text>`, both parameters optional
+//!
+//! Links are checked against the database, the main window is brought to
+//! the front and the renderer gets the route through `deep-link`, buffered
+//! until it is ready, so a link that launched the app is not lost. A link
+//! never sends anything to the agent on its own: the prompt only fills the
+//! composer, and a repo that is not a project yet is offered, not added.
+//! macOS hands links to the running app; Windows and Linux start a second
+//! instance, which the single-instance plugin forwards here.
+//!
+//! Directories given on the command line, as in `codex-desktop ~/src/app`,
+//! are opened like a `new?repo=` link, whether they reach the first launch
+//! or are forwarded from a later one.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::quick_prompt::show_main_window;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+pub const SCHEME: &str = "codex-desktop";
+
+/// Where a link leads
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DeepLinkRoute {
+    #[serde(rename_all = "camelCase")]
+    Project { project_id: String },
+    #[serde(rename_all = "camelCase")]
+    Session {
+        project_id: String,
+        session_id: String,
+    },
+    /// Start a thread in `project_id`, or in the most recent project when
+    /// neither it nor `repo` is given
+    #[serde(rename_all = "camelCase")]
+    NewThread {
+        project_id: Option<String>,
+        /// Canonical path of a repo that is not a project yet
+        repo: Option<String>,
+        prompt: Option<String>,
+    },
+}
+
+/// Payload of `deep-link`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeepLinkEvent {
+    pub url: String,
+    pub route: Option<DeepLinkRoute>,
+    /// Why the link could not be opened
+    pub error: Option<Error>,
+}
+
+fn unsupported(url: &Url) -> Error {
+    Error::Other(format!("Unsupported link: {url}"))
+}
+
+/// Read the route of a link without checking that its targets exist
+pub fn parse(url: &Url) -> Result<DeepLinkRoute> {
+    if url.scheme() != SCHEME {
+        return Err(unsupported(url));
+    }
+    let segments: Vec<&str> = url
+        .path_segments()
+        .into_iter()
+        .flatten()
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let query = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    match (url.host_str(), segments.as_slice()) {
+        (Some("project"), [project_id]) => Ok(DeepLinkRoute::Project {
+            project_id: project_id.to_string(),
+        }),
+        (Some("project"), [project_id, "session", session_id]) => Ok(DeepLinkRoute::Session {
+            project_id: project_id.to_string(),
+            session_id: session_id.to_string(),
+        }),
+        (Some("new"), []) => Ok(DeepLinkRoute::NewThread {
+            project_id: None,
+            repo: query("repo"),
+            prompt: query("prompt"),
+        }),
+        _ => Err(unsupported(url)),
+    }
+}
+
+/// Check that the targets of `route` exist, and match a `repo` to its
+/// project
+async fn resolve(state: &AppState, route: DeepLinkRoute) -> Result<DeepLinkRoute> {
+    let database = state.database.clone();
+    crate::utils::spawn_blocking_io(move || match route {
+        DeepLinkRoute::Project { ref project_id } => {
+            database
+                .get_project(project_id)?
+                .ok_or_else(|| Error::ProjectNotFound(project_id.clone()))?;
+            Ok(route)
+        }
+        DeepLinkRoute::Session {
+            ref project_id,
+            ref session_id,
+        } => {
+            database
+                .get_session_by_id(session_id)?
+                .filter(|session| session.project_id == *project_id)
+                .ok_or_else(|| Error::SessionNotFound(session_id.clone()))?;
+            Ok(route)
+        }
+        DeepLinkRoute::NewThread { repo, prompt, .. } => {
+            let Some(repo) = repo else {
+                return Ok(DeepLinkRoute::NewThread {
+                    project_id: None,
+                    repo: None,
+                    prompt,
+                });
+            };
+            let path = crate::utils::validate_and_canonicalize_path(&repo)?;
+            let project = database.get_all_projects()?.into_iter().find(|project| {
+                std::path::Path::new(&project.path)
+                    .canonicalize()
+                    .is_ok_and(|root| root == path)
+            });
+            Ok(match project {
+                Some(project) => DeepLinkRoute::NewThread {
+                    project_id: Some(project.id),
+                    repo: None,
+                    prompt,
+                },
+                None => DeepLinkRoute::NewThread {
+                    project_id: None,
+                    repo: Some(path.to_string_lossy().to_string()),
+                    prompt,
+                },
+            })
+        }
+    })
+    .await
+}
+
+/// Route a link and tell the renderer
+pub async fn open(app: &AppHandle, url: Url) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let result = match parse(&url) {
+        Ok(route) => resolve(&state, route).await,
+        Err(err) => Err(err),
+    };
+    show_main_window(app);
+    // The query may hold a prompt, so it is left out of the log
+    let target = format!("{SCHEME}://{}{}", url.host_str().unwrap_or(""), url.path());
+    let (route, error) = match result {
+        Ok(route) => {
+            tracing::info!("Opening {}", target);
+            (Some(route), None)
+        }
+        Err(err) => {
+            tracing::warn!("Ignoring link {}: {}", target, err);
+            (None, Some(err))
+        }
+    };
+    let event = DeepLinkEvent {
+        url: url.to_string(),
+        route,
+        error,
+    };
+    state.events.emit("deep-link", event).await;
+}
+
+/// Route links the app receives from now on, and the one it was started with
+pub fn listen(app: &AppHandle) {
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        let app = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            for url in event.urls() {
+                open(&app, url).await;
+            }
+        });
+    });
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            for url in urls {
+                open(&app, url).await;
+            }
+        });
+    }
+}
+
+/// `new?repo=` links for the directories among `args`, which start with
+/// the program; relative paths are read from `cwd`
+pub fn links_for_args(args: &[String], cwd: &Path) -> Vec<Url> {
+    args.iter()
+        .skip(1)
+        // Flags, like macOS' -psn_..., and links handled by the plugin
+        .filter(|arg| !arg.starts_with('-') && !arg.starts_with(&format!("{SCHEME}:")))
+        .map(|arg| cwd.join(arg))
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let repo = path.to_string_lossy();
+            Url::parse_with_params(&format!("{SCHEME}://new"), [("repo", repo.as_ref())]).ok()
+        })
+        .collect()
+}
+
+/// Open the directories among the command-line `args`
+pub fn open_args(app: &AppHandle, args: &[String], cwd: &Path) {
+    let links = links_for_args(args, cwd);
+    if links.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for url in links {
+            open(&app, url).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(link: &str) -> Result<DeepLinkRoute> {
+        parse(&link.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            route("codex-desktop://project/p1").unwrap(),
+            DeepLinkRoute::Project {
+                project_id: "p1".to_string()
+            }
+        );
+        assert_eq!(
+            route("codex-desktop://project/p1/session/s1/").unwrap(),
+            DeepLinkRoute::Session {
+                project_id: "p1".to_string(),
+                session_id: "s1".to_string()
+            }
+        );
+        assert_eq!(
+            route("codex-desktop://new?repo=%2Ftmp%2Frepo&prompt=Fix+the+tests&prompt=x").unwrap(),
+            DeepLinkRoute::NewThread {
+                project_id: None,
+                repo: Some("/tmp/repo".to_string()),
+                prompt: Some("Fix the tests".to_string())
+            }
+        );
+        assert_eq!(
+            route("codex-desktop://new?prompt=").unwrap(),
+            DeepLinkRoute::NewThread {
+                project_id: None,
+                repo: None,
+                prompt: None
+            }
+        );
+        assert!(route("codex-desktop://project").is_err());
+        assert!(route("codex-desktop://settings/general").is_err());
+        assert!(route("https://project/p1").is_err());
+    }
+
+    #[test]
+    fn test_links_for_args() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(temp_dir.path().join("repo")).unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "").unwrap();
+        let args: Vec<String> = ["codex-desktop", "-psn_0_1", "repo", "notes.txt", "missing"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        let links = links_for_args(&args, temp_dir.path());
+        assert_eq!(links.len(), 1);
+        let repo = temp_dir.path().join("repo");
+        assert_eq!(
+            parse(&links[0]).unwrap(),
+            DeepLinkRoute::NewThread {
+                project_id: None,
+                repo: Some(repo.to_string_lossy().to_string()),
+                prompt: None
+            }
+        );
+    }
+}
@@ -0,0 +1,619 @@
+//! Checks of what the app depends on, for support.
+//!
+//! Each check reports a status, a one-line summary and, when something is
+//! off, a hint. The `text` of a `DiagnosticsReport` is the plain text users
+//! paste into bug reports, so reports stay in English like the logs. All
+//! checks but `app_server` and `permissions` run without app state or
+//! writing anything, which lets `codex-desktop doctor` use them headless.
+
+use std::path::Path;
+use std::process::Output;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::app_server::installations;
+use crate::commands::app_server::{server_state, ServerState};
+use crate::database::Database;
+use crate::global_state::AppServerLaunchSettings;
+use crate::i18n::{self, Locale};
+use crate::state::{AppState, DATABASE_FILE};
+
+/// An external tool taking longer than this counts as failed
+const TOOL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Integrity problems listed before the rest are counted
+const MAX_LISTED_PROBLEMS: usize = 5;
+
+/// Free space in the app data directory below which a warning is shown;
+/// snapshots and transcripts grow with use
+const LOW_DISK_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Free space below which writing the database or logs may fail
+const CRITICAL_DISK_SPACE_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    /// Stable name, e.g. "codexCli"
+    pub id: &'static str,
+    pub status: CheckStatus,
+    pub summary: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl DiagnosticCheck {
+    fn ok(id: &'static str, summary: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Ok,
+            summary: summary.into(),
+            hint: None,
+        }
+    }
+
+    fn warning(id: &'static str, summary: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Warning,
+            summary: summary.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn failed(id: &'static str, summary: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            id,
+            status: CheckStatus::Failed,
+            summary: summary.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    pub app_version: String,
+    /// e.g. "macos aarch64"
+    pub platform: String,
+    /// Unix timestamp
+    pub generated_at: i64,
+    pub checks: Vec<DiagnosticCheck>,
+    /// `to_text` with secrets masked and the home directory shown as `~`,
+    /// for the renderer to copy
+    pub text: String,
+}
+
+impl DiagnosticsReport {
+    pub fn new(checks: Vec<DiagnosticCheck>) -> Self {
+        let mut report = Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            generated_at: chrono::Utc::now().timestamp(),
+            checks,
+            text: String::new(),
+        };
+        let text = report.to_text();
+        let text = crate::secret_mask::mask(&text);
+        report.text = tilde_home(&text, dirs::home_dir().as_deref());
+        report
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == CheckStatus::Failed)
+    }
+
+    /// Plain text for bug reports, one check per line
+    fn to_text(&self) -> String {
+        let generated_at = chrono::DateTime::from_timestamp(self.generated_at, 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default();
+        let mut text = format!(
+            "Codex Desktop {} on {} ({})\n",
+            self.app_version, self.platform, generated_at
+        );
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Ok => "ok",
+                CheckStatus::Warning => "warning",
+                CheckStatus::Failed => "FAILED",
+            };
+            text.push_str(&format!("[{status}] {}: {}\n", check.id, check.summary));
+            if let Some(hint) = &check.hint {
+                text.push_str(&format!("    {hint}\n"));
+            }
+        }
+        text
+    }
+}
+
+/// `text` with every occurrence of `home` replaced by `~`
+fn tilde_home(text: &str, home: Option<&Path>) -> String {
+    let home = home.map(|home| home.to_string_lossy());
+    let home = home.as_deref().map(|home| home.trim_end_matches(['/', '\\']));
+    match home {
+        Some(home) if home.len() > 1 => text.replace(home, "~"),
+        _ => text.to_string(),
+    }
+}
+
+/// Output of a tool, or why it could not be run
+async fn run_tool(program: &str, args: &[&str]) -> std::result::Result<Output, String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .kill_on_drop(true)
+        .output();
+    match tokio::time::timeout(TOOL_TIMEOUT, output).await {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+            Err(format!("{program} was not found"))
+        }
+        Ok(Err(err)) => Err(format!("{program} could not be run: {err}")),
+        Err(_) => Err(format!(
+            "{program} did not answer within {} seconds",
+            TOOL_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// The codex binary the app-server would be spawned from, and its version
+pub async fn codex_cli(settings: &AppServerLaunchSettings) -> DiagnosticCheck {
+    const ID: &str = "codexCli";
+    let path = match installations::resolve_codex_binary(settings).await {
+        Ok(path) => path,
+        Err(err) => {
+            let hint = i18n::translate(
+                Locale::En,
+                "hint.cli_not_installed",
+                &[
+                    ("program", "codex"),
+                    ("install", "npm install -g @openai/codex"),
+                ],
+            );
+            return DiagnosticCheck::failed(ID, err.to_string(), hint);
+        }
+    };
+    match installations::version_output(&path).await {
+        Some(version) => DiagnosticCheck::ok(ID, format!("{version} at {}", path.display())),
+        None => DiagnosticCheck::warning(
+            ID,
+            format!("{} did not report a version", path.display()),
+            "Run `codex --version` in a terminal to see why it fails.",
+        ),
+    }
+}
+
+pub async fn git() -> DiagnosticCheck {
+    const ID: &str = "git";
+    match run_tool("git", &["--version"]).await {
+        Ok(output) if output.status.success() => {
+            DiagnosticCheck::ok(ID, String::from_utf8_lossy(&output.stdout).trim())
+        }
+        Ok(output) => DiagnosticCheck::failed(
+            ID,
+            format!("git --version exited with {}", output.status),
+            i18n::translate(Locale::En, "hint.git_not_found", &[]),
+        ),
+        Err(reason) => DiagnosticCheck::failed(
+            ID,
+            reason,
+            i18n::translate(Locale::En, "hint.git_not_found", &[]),
+        ),
+    }
+}
+
+/// Whether the app database opens and passes `PRAGMA integrity_check`.
+/// With `read_only` nothing is written to it, so pending migrations are
+/// listed without trying them. Blocking.
+pub fn database(path: &Path, read_only: bool) -> DiagnosticCheck {
+    const ID: &str = "database";
+    if !path.exists() {
+        return DiagnosticCheck::warning(
+            ID,
+            format!("No database at {}", path.display()),
+            "It is created the first time the app starts.",
+        );
+    }
+    let restore_hint = format!(
+        "Quit the app and move {} aside to start with an empty database; keep the copy for the bug report.",
+        path.display()
+    );
+    let opened = if read_only {
+        Database::open_read_only(path)
+    } else {
+        Database::open(path)
+    };
+    let database = match opened {
+        Ok(database) => database,
+        Err(err) => return DiagnosticCheck::failed(ID, err.to_string(), restore_hint),
+    };
+    let problems = match database.integrity_check() {
+        Ok(problems) => problems,
+        Err(err) => return DiagnosticCheck::failed(ID, err.to_string(), restore_hint),
+    };
+    if problems.is_empty() {
+        return schema(&database, path, read_only, restore_hint);
+    }
+    let mut summary = problems
+        .iter()
+        .take(MAX_LISTED_PROBLEMS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("; ");
+    if problems.len() > MAX_LISTED_PROBLEMS {
+        summary.push_str(&format!(
+            " and {} more",
+            problems.len() - MAX_LISTED_PROBLEMS
+        ));
+    }
+    DiagnosticCheck::failed(
+        ID,
+        format!("Integrity check failed: {summary}"),
+        restore_hint,
+    )
+}
+
+/// Schema version of a database that passed the integrity check; unless
+/// `read_only`, pending migrations are tried in a transaction that is
+/// rolled back
+fn schema(
+    database: &Database,
+    path: &Path,
+    read_only: bool,
+    restore_hint: String,
+) -> DiagnosticCheck {
+    const ID: &str = "database";
+    let status = match database.migration_status() {
+        Ok(status) => status,
+        Err(err) => return DiagnosticCheck::failed(ID, err.to_string(), restore_hint),
+    };
+    if status.current_version > status.latest_version {
+        return DiagnosticCheck::warning(
+            ID,
+            format!(
+                "Schema version {} is newer than this build supports ({})",
+                status.current_version, status.latest_version
+            ),
+            "Update Codex Desktop; an older build may not read everything a newer one stored.",
+        );
+    }
+    if !status.pending.is_empty() {
+        let tried = if read_only {
+            Ok(status.pending)
+        } else {
+            database.dry_run_migrations()
+        };
+        return match tried {
+            Ok(pending) => DiagnosticCheck::warning(
+                ID,
+                format!("Migrations not applied yet: {}", pending.join(", ")),
+                "They are applied the next time the app starts.",
+            ),
+            Err(err) => DiagnosticCheck::failed(
+                ID,
+                format!("A pending migration fails: {err}"),
+                restore_hint,
+            ),
+        };
+    }
+    DiagnosticCheck::ok(
+        ID,
+        format!(
+            "Integrity check passed for {} (schema version {})",
+            path.display(),
+            status.current_version
+        ),
+    )
+}
+
+/// Whether the GitHub CLI is installed and logged in. It is only needed
+/// for pull requests, so problems are warnings.
+pub async fn gh_auth() -> DiagnosticCheck {
+    const ID: &str = "ghAuth";
+    let output = match run_tool("gh", &["auth", "status"]).await {
+        Ok(output) => output,
+        Err(reason) => {
+            let hint = i18n::translate(
+                Locale::En,
+                "hint.cli_not_installed",
+                &[("program", "gh"), ("install", "https://cli.github.com")],
+            );
+            return DiagnosticCheck::warning(ID, reason, hint);
+        }
+    };
+    if !output.status.success() {
+        return DiagnosticCheck::warning(
+            ID,
+            "gh is not logged in",
+            "Run `gh auth login` in a terminal to open pull requests from the app.",
+        );
+    }
+    // Older versions print the status to stderr
+    let text = [output.stdout, output.stderr].concat();
+    let host = String::from_utf8_lossy(&text)
+        .lines()
+        .find_map(|line| gh_login_host(line).map(str::to_string));
+    DiagnosticCheck::ok(ID, host.unwrap_or_else(|| "Logged in".to_string()))
+}
+
+/// "Logged in to <host>" from a `gh auth status` line, leaving out the
+/// account name so it does not end up in bug reports
+fn gh_login_host(line: &str) -> Option<&str> {
+    let start = line.find("Logged in to ")?;
+    let rest = &line[start..];
+    let end = rest
+        .find(" account ")
+        .or_else(|| rest.find(" as "))
+        .unwrap_or(rest.len());
+    Some(rest[..end].trim_end())
+}
+
+/// Bytes available to the current user on the volume holding `path`
+#[cfg(unix)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read on success
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+    // The field types differ between platforms
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+}
+
+/// Bytes available to the current user on the volume holding `path`
+#[cfg(windows)]
+fn available_space(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and the null outputs are optional
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MIB: u64 = 1024 * 1024;
+    if bytes >= 1024 * MIB {
+        format!("{:.1} GiB", bytes as f64 / (1024 * MIB) as f64)
+    } else {
+        format!("{} MiB", bytes / MIB)
+    }
+}
+
+/// Free space on the volume holding the app data directory
+pub fn disk_space(app_data_dir: &Path) -> DiagnosticCheck {
+    const ID: &str = "diskSpace";
+    // The directory does not exist before the first start
+    let Some(dir) = app_data_dir.ancestors().find(|dir| dir.exists()) else {
+        return DiagnosticCheck::warning(
+            ID,
+            format!("{} does not exist", app_data_dir.display()),
+            "It is created the first time the app starts.",
+        );
+    };
+    let available = match available_space(dir) {
+        Ok(available) => available,
+        Err(err) => {
+            return DiagnosticCheck::warning(
+                ID,
+                format!("Could not read the free space of {}: {err}", dir.display()),
+                "Check that the disk is mounted and readable.",
+            )
+        }
+    };
+    let summary = format!("{} free for {}", format_bytes(available), dir.display());
+    let hint = "Free up space on this disk; the app keeps snapshots and transcripts there.";
+    if available < CRITICAL_DISK_SPACE_BYTES {
+        DiagnosticCheck::failed(ID, summary, hint)
+    } else if available < LOW_DISK_SPACE_BYTES {
+        DiagnosticCheck::warning(ID, summary, hint)
+    } else {
+        DiagnosticCheck::ok(ID, summary)
+    }
+}
+
+/// Whether a file can be created in `dir`
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".diagnostics-{}", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)?;
+    std::fs::remove_file(&probe)
+}
+
+/// Whether the app data directory, the folders the app keeps in it and the
+/// database are writable, and the Codex CLI home readable. Creates and
+/// removes a probe file in each folder. Blocking.
+pub fn permissions(app_data_dir: &Path) -> DiagnosticCheck {
+    const ID: &str = "permissions";
+    let mut problems = Vec::new();
+    let dirs = ["", "logs", "snapshots", "transcripts"]
+        .into_iter()
+        .map(|name| app_data_dir.join(name))
+        .filter(|dir| dir.exists());
+    for dir in dirs {
+        if let Err(err) = check_writable(&dir) {
+            problems.push(format!("{} is not writable: {err}", dir.display()));
+        }
+    }
+    let database = app_data_dir.join(DATABASE_FILE);
+    if let Ok(metadata) = std::fs::metadata(&database) {
+        if metadata.permissions().readonly() {
+            problems.push(format!("{} is read-only", database.display()));
+        }
+    }
+    let codex_dir = crate::codex_import::get_codex_dir();
+    if codex_dir.exists() {
+        if let Err(err) = std::fs::read_dir(&codex_dir) {
+            problems.push(format!("{} is not readable: {err}", codex_dir.display()));
+        }
+    }
+
+    if problems.is_empty() {
+        return DiagnosticCheck::ok(
+            ID,
+            format!("{} and its folders are writable", app_data_dir.display()),
+        );
+    }
+    DiagnosticCheck::failed(
+        ID,
+        problems.join("; "),
+        i18n::translate(Locale::En, "hint.permission_denied", &[]),
+    )
+}
+
+/// Whether the app-server answers a request. A stopped server is not
+/// started for the check.
+pub async fn app_server(state: &AppState) -> DiagnosticCheck {
+    const ID: &str = "appServer";
+    let (server, degraded) = server_state(state).await;
+    match server {
+        ServerState::Stopped | ServerState::Idle => {
+            return DiagnosticCheck::ok(ID, "Not running; it starts with the next thread")
+        }
+        ServerState::Degraded => {
+            let reason = degraded.map(|info| info.reason).unwrap_or_default();
+            return DiagnosticCheck::failed(
+                ID,
+                format!("The last start failed: {reason}"),
+                "Check the codex CLI above, then restart the app server.",
+            );
+        }
+        ServerState::Running => {}
+    }
+
+    let started = Instant::now();
+    let answer = {
+        let server = state.app_server.read().await;
+        match server.as_ref() {
+            Some(process) => {
+                let request = process
+                    .send_request::<_, serde_json::Value>("account/read", serde_json::json!({}));
+                tokio::time::timeout(TOOL_TIMEOUT, request).await
+            }
+            None => return DiagnosticCheck::ok(ID, "Not running; it starts with the next thread"),
+        }
+    };
+    let hint = "Restart the app server; the log tail shows what it printed.";
+    match answer {
+        Ok(Ok(_)) => DiagnosticCheck::ok(
+            ID,
+            format!("Answered in {} ms", started.elapsed().as_millis()),
+        ),
+        Ok(Err(err)) => DiagnosticCheck::failed(ID, format!("Request failed: {err}"), hint),
+        Err(_) => DiagnosticCheck::failed(
+            ID,
+            format!("No answer within {} seconds", TOOL_TIMEOUT.as_secs()),
+            hint,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let report = DiagnosticsReport::new(vec![
+            DiagnosticCheck::ok("git", "git version 2.44.0"),
+            DiagnosticCheck::failed("codexCli", "Codex CLI not found", "Install it"),
+        ]);
+        let text = &report.text;
+        assert!(text.starts_with("Codex Desktop "));
+        assert!(text.contains("[ok] git: git version 2.44.0\n"));
+        assert!(text.ends_with("[FAILED] codexCli: Codex CLI not found\n    Install it\n"));
+        assert!(report.has_failures());
+    }
+
+    #[test]
+    fn test_tilde_home() {
+        let home = Path::new("/home/alice/");
+        assert_eq!(
+            tilde_home("db at /home/alice/.config/codex.db", Some(home)),
+            "db at ~/.config/codex.db"
+        );
+        assert_eq!(tilde_home("/var/tmp", None), "/var/tmp");
+        assert_eq!(tilde_home("/var/tmp", Some(Path::new("/"))), "/var/tmp");
+    }
+
+    #[test]
+    fn test_gh_login_host() {
+        assert_eq!(
+            gh_login_host("  ✓ Logged in to github.com account octocat (keyring)"),
+            Some("Logged in to github.com")
+        );
+        assert_eq!(
+            gh_login_host("✓ Logged in to github.com as octocat (oauth_token)"),
+            Some("Logged in to github.com")
+        );
+        assert_eq!(gh_login_host("  - Active account: true"), None);
+    }
+
+    #[test]
+    fn test_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("test.db");
+        assert_eq!(database(&path, false).status, CheckStatus::Warning);
+        Database::new(&path).unwrap();
+        let check = database(&path, false);
+        assert_eq!(check.status, CheckStatus::Ok);
+        assert!(check.summary.contains("schema version"));
+        assert_eq!(database(&path, true).status, CheckStatus::Ok);
+
+        std::fs::write(&path, "not a database").unwrap();
+        assert_eq!(database(&path, false).status, CheckStatus::Failed);
+        assert_eq!(database(&path, true).status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_disk_space_and_permissions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app_data_dir = temp_dir.path().join("com.codex.desktop");
+        // Read from the nearest existing parent before the first start
+        assert!(disk_space(&app_data_dir).summary.contains("free for"));
+
+        std::fs::create_dir_all(app_data_dir.join("logs")).unwrap();
+        assert_eq!(permissions(&app_data_dir).status, CheckStatus::Ok);
+        assert_eq!(std::fs::read_dir(&app_data_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512 * 1024 * 1024), "512 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 / 2), "1.5 GiB");
+    }
+}
@@ -3,6 +3,10 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::commit_signing::SigningFailure;
+use crate::i18n::{self, Locale};
+use crate::patch_preflight::PatchPreflight;
+
 /// Codex error type classification (matches Codex CLI error types)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -109,12 +113,54 @@ pub enum Error {
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
+    #[error("File changed on disk: {0}")]
+    FileConflict(String),
+
     #[error("Git error: {0}")]
     Git(String),
 
+    #[error("Not a git repository")]
+    NotARepo,
+
+    /// HEAD points at a commit rather than a branch
+    #[error("HEAD is detached at {sha}")]
+    DetachedHead { sha: String },
+
+    /// HEAD names a branch that has no commits yet
+    #[error("Branch '{0}' has no commits yet")]
+    UnbornBranch(String),
+
+    #[error("git is not installed or not on PATH")]
+    GitNotFound,
+
+    /// A CLI the operation shells out to is missing
+    #[error("{program} is not installed. Install it with: {install}")]
+    CliNotInstalled {
+        program: &'static str,
+        install: &'static str,
+    },
+
+    #[error("The app server exited: {0}")]
+    AppServerCrashed(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Commit signing failed: {}", .0.message)]
+    Signing(SigningFailure),
+
+    #[error("Patch does not apply:\n{}", .0.summary())]
+    PatchRejected(Box<PatchPreflight>),
+
     #[error("Tauri error: {0}")]
     Tauri(String),
 
+    #[error("Codex Desktop is locked")]
+    Locked,
+
     #[error("{0}")]
     Other(String),
 }
@@ -137,6 +183,140 @@ impl Error {
     }
 }
 
+/// Machine-readable kind of an `Error`, so the renderer can tell failures
+/// apart without matching on messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Database,
+    Io,
+    Json,
+    AppServer,
+    AppServerCrashed,
+    Codex,
+    NotFound,
+    InvalidPath,
+    FileConflict,
+    Git,
+    NotARepo,
+    DetachedHead,
+    UnbornBranch,
+    GitNotFound,
+    CliNotInstalled,
+    SigningFailed,
+    PatchRejected,
+    Timeout,
+    PermissionDenied,
+    Tauri,
+    Locked,
+    Other,
+}
+
+impl Error {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::Database(_) => ErrorCode::Database,
+            Error::Io(err) => match err.kind() {
+                std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+                std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+                std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+                _ => ErrorCode::Io,
+            },
+            Error::Json(_) => ErrorCode::Json,
+            Error::AppServer(_) => ErrorCode::AppServer,
+            Error::AppServerCrashed(_) => ErrorCode::AppServerCrashed,
+            Error::Codex { .. } => ErrorCode::Codex,
+            Error::ProjectNotFound(_) | Error::SessionNotFound(_) | Error::SnapshotNotFound(_) => {
+                ErrorCode::NotFound
+            }
+            Error::InvalidPath(_) => ErrorCode::InvalidPath,
+            Error::FileConflict(_) => ErrorCode::FileConflict,
+            Error::Git(_) => ErrorCode::Git,
+            Error::NotARepo => ErrorCode::NotARepo,
+            Error::DetachedHead { .. } => ErrorCode::DetachedHead,
+            Error::UnbornBranch(_) => ErrorCode::UnbornBranch,
+            Error::GitNotFound => ErrorCode::GitNotFound,
+            Error::CliNotInstalled { .. } => ErrorCode::CliNotInstalled,
+            Error::Signing(_) => ErrorCode::SigningFailed,
+            Error::PatchRejected(_) => ErrorCode::PatchRejected,
+            Error::Timeout(_) => ErrorCode::Timeout,
+            Error::PermissionDenied(_) => ErrorCode::PermissionDenied,
+            Error::Tauri(_) => ErrorCode::Tauri,
+            Error::Locked => ErrorCode::Locked,
+            Error::Other(_) => ErrorCode::Other,
+        }
+    }
+
+    /// The message shown to the user, in `locale`. Details quoted from git,
+    /// the OS or the app server stay as reported.
+    pub fn message_in(&self, locale: Locale) -> String {
+        let with_detail =
+            |key: &str, detail: &str| i18n::translate(locale, key, &[("detail", detail)]);
+        match self {
+            Error::Database(err) => with_detail("error.database", &err.to_string()),
+            Error::Io(err) => with_detail("error.io", &err.to_string()),
+            Error::Json(err) => with_detail("error.json", &err.to_string()),
+            Error::AppServer(detail) => with_detail("error.app_server", detail),
+            Error::ProjectNotFound(id) => with_detail("error.project_not_found", id),
+            Error::SessionNotFound(id) => with_detail("error.session_not_found", id),
+            Error::SnapshotNotFound(id) => with_detail("error.snapshot_not_found", id),
+            Error::InvalidPath(detail) => with_detail("error.invalid_path", detail),
+            Error::FileConflict(path) => with_detail("error.file_conflict", path),
+            Error::Git(detail) => with_detail("error.git", detail),
+            Error::NotARepo => i18n::translate(locale, "error.not_a_repo", &[]),
+            Error::DetachedHead { sha } => {
+                i18n::translate(locale, "error.detached_head", &[("sha", sha)])
+            }
+            Error::UnbornBranch(branch) => {
+                i18n::translate(locale, "error.unborn_branch", &[("branch", branch)])
+            }
+            Error::GitNotFound => i18n::translate(locale, "error.git_not_found", &[]),
+            Error::CliNotInstalled { program, install } => i18n::translate(
+                locale,
+                "error.cli_not_installed",
+                &[("program", program), ("install", install)],
+            ),
+            Error::AppServerCrashed(detail) => with_detail("error.app_server_crashed", detail),
+            Error::Timeout(detail) => with_detail("error.timeout", detail),
+            Error::PermissionDenied(detail) => with_detail("error.permission_denied", detail),
+            Error::Signing(failure) => with_detail("error.signing_failed", &failure.message),
+            Error::PatchRejected(preflight) => {
+                with_detail("error.patch_rejected", &preflight.summary())
+            }
+            Error::Tauri(detail) => with_detail("error.tauri", detail),
+            Error::Locked => i18n::translate(locale, "error.locked", &[]),
+            Error::Codex { message, .. } | Error::Other(message) => message.clone(),
+        }
+    }
+
+    /// What the user can do about it, in `locale`, when there is something
+    pub fn hint_in(&self, locale: Locale) -> Option<String> {
+        let key = match self.code() {
+            ErrorCode::NotARepo => "hint.not_a_repo",
+            ErrorCode::DetachedHead => "hint.detached_head",
+            ErrorCode::UnbornBranch => "hint.unborn_branch",
+            ErrorCode::GitNotFound => "hint.git_not_found",
+            ErrorCode::CliNotInstalled => {
+                let Error::CliNotInstalled { program, install } = self else {
+                    return None;
+                };
+                return Some(i18n::translate(
+                    locale,
+                    "hint.cli_not_installed",
+                    &[("program", program), ("install", install)],
+                ));
+            }
+            ErrorCode::AppServerCrashed => "hint.app_server_crashed",
+            ErrorCode::Timeout => "hint.timeout",
+            ErrorCode::PermissionDenied => "hint.permission_denied",
+            ErrorCode::Locked => "hint.locked",
+            ErrorCode::FileConflict => "hint.file_conflict",
+            _ => return None,
+        };
+        Some(i18n::translate(locale, key, &[]))
+    }
+}
+
 /// Result type alias for this crate
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -145,8 +325,15 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[serde(rename_all = "camelCase")]
 struct ErrorResponse {
     message: String,
+    code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error_info: Option<CodexErrorInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signing_error: Option<SigningFailure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    patch_preflight: Option<PatchPreflight>,
 }
 
 // Implement conversion to Tauri's invoke error with structured info
@@ -155,21 +342,80 @@ impl Serialize for Error {
     where
         S: serde::Serializer,
     {
+        let locale = i18n::current();
+        let mut response = ErrorResponse {
+            message: self.message_in(locale),
+            code: self.code(),
+            hint: self.hint_in(locale),
+            error_info: None,
+            signing_error: None,
+            patch_preflight: None,
+        };
         match self {
             Error::Codex { message, info } => {
-                let response = ErrorResponse {
-                    message: message.clone(),
-                    error_info: info.clone(),
-                };
-                response.serialize(serializer)
+                response.message = message.clone();
+                response.error_info = info.clone();
             }
-            _ => {
-                let response = ErrorResponse {
-                    message: self.to_string(),
-                    error_info: None,
-                };
-                response.serialize(serializer)
+            Error::Signing(failure) => response.signing_error = Some(failure.clone()),
+            Error::PatchRejected(preflight) => {
+                response.patch_preflight = Some((**preflight).clone());
             }
+            _ => {}
+        }
+        response.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_code_and_hint() {
+        let value = serde_json::to_value(Error::NotARepo).unwrap();
+        assert_eq!(value["code"], "not_a_repo");
+        assert_eq!(value["message"], "Not a git repository");
+        assert!(value["hint"].is_string());
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        let value = serde_json::to_value(Error::Io(denied)).unwrap();
+        assert_eq!(value["code"], "permission_denied");
+
+        let value = serde_json::to_value(Error::Other("boom".to_string())).unwrap();
+        assert_eq!(value["code"], "other");
+        assert!(value.get("hint").is_none());
+
+        let detached = Error::DetachedHead {
+            sha: "1a2b3c4".to_string(),
+        };
+        let value = serde_json::to_value(detached).unwrap();
+        assert_eq!(value["code"], "detached_head");
+        assert!(value["hint"].is_string());
+    }
+
+    #[test]
+    fn test_english_messages_match_display() {
+        let errors = [
+            Error::NotARepo,
+            Error::DetachedHead {
+                sha: "1a2b3c4".to_string(),
+            },
+            Error::UnbornBranch("main".to_string()),
+            Error::GitNotFound,
+            Error::CliNotInstalled {
+                program: "gh",
+                install: "brew install gh",
+            },
+            Error::Timeout("git push".to_string()),
+            Error::ProjectNotFound("p1".to_string()),
+            Error::Io(std::io::Error::other("disk full")),
+            Error::Locked,
+            Error::Other("boom".to_string()),
+        ];
+        for error in errors {
+            assert_eq!(error.message_in(Locale::En), error.to_string());
         }
+        assert_eq!(Error::NotARepo.message_in(Locale::ZhCn), "不是 git 仓库");
+        assert!(Error::Locked.hint_in(Locale::ZhCn).is_some());
     }
 }
@@ -1,4 +1,4 @@
-//! Buffered event emitter for renderer readiness.
+//! Buffered event emitter for renderer readiness and the app lock.
 
 use serde::Serialize;
 use serde_json::Value as JsonValue;
@@ -9,17 +9,22 @@ use tokio::sync::Mutex;
 
 const MAX_PENDING_EVENTS: usize = 1000;
 
+/// Events still sent while the app lock holds the rest back
+const UNHELD_EVENTS: &[&str] = &["app-lock-changed", "startup-progress"];
+
 #[derive(Debug)]
 struct BufferedEvent {
     name: String,
     payload: JsonValue,
 }
 
-/// Emits events to the renderer, buffering until it signals readiness.
+/// Emits events to the renderer, buffering until it signals readiness and
+/// while the app is locked.
 #[derive(Clone)]
 pub struct AppEventEmitter {
     app_handle: AppHandle,
     ready: Arc<AtomicBool>,
+    held: Arc<AtomicBool>,
     pending: Arc<Mutex<Vec<BufferedEvent>>>,
 }
 
@@ -28,6 +33,7 @@ impl AppEventEmitter {
         Self {
             app_handle,
             ready: Arc::new(AtomicBool::new(false)),
+            held: Arc::new(AtomicBool::new(false)),
             pending: Arc::new(Mutex::new(Vec::with_capacity(64))),
         }
     }
@@ -36,12 +42,39 @@ impl AppEventEmitter {
         self.ready.load(Ordering::SeqCst)
     }
 
+    /// Number of events buffered while waiting for the renderer
+    pub async fn pending_len(&self) -> usize {
+        self.pending.lock().await.len()
+    }
+
     pub async fn set_ready(&self) {
         if self.ready.swap(true, Ordering::SeqCst) {
             return;
         }
+        self.flush().await;
+    }
 
+    /// Hold back events, other than `UNHELD_EVENTS`, until `release`
+    pub fn hold(&self) {
+        self.held.store(true, Ordering::SeqCst);
+    }
+
+    /// Send the events held back since `hold`
+    pub async fn release(&self) {
+        if self.held.swap(false, Ordering::SeqCst) {
+            self.flush().await;
+        }
+    }
+
+    fn buffers(&self, event: &str) -> bool {
+        !self.is_ready() || (self.held.load(Ordering::SeqCst) && !UNHELD_EVENTS.contains(&event))
+    }
+
+    async fn flush(&self) {
         let mut pending = self.pending.lock().await;
+        if !self.is_ready() || self.held.load(Ordering::SeqCst) {
+            return;
+        }
         if !pending.is_empty() {
             tracing::info!("Flushing {} pending events", pending.len());
         }
@@ -60,9 +93,17 @@ impl AppEventEmitter {
         }
     }
 
-    pub async fn emit_json(&self, event: &str, payload: JsonValue) {
-        if !self.is_ready() {
+    /// Secrets in `payload` are masked before it is buffered or sent
+    pub async fn emit_json(&self, event: &str, mut payload: JsonValue) {
+        crate::secret_mask::mask_json(&mut payload);
+        if self.buffers(event) {
             let mut pending = self.pending.lock().await;
+            // Checked again under the lock so a concurrent flush cannot strand it
+            if !self.buffers(event) {
+                drop(pending);
+                self.send(event, payload);
+                return;
+            }
             if pending.len() >= MAX_PENDING_EVENTS {
                 let overflow = pending.len() + 1 - MAX_PENDING_EVENTS;
                 pending.drain(0..overflow);
@@ -74,7 +115,10 @@ impl AppEventEmitter {
             });
             return;
         }
+        self.send(event, payload);
+    }
 
+    fn send(&self, event: &str, payload: JsonValue) {
         if let Err(e) = self.app_handle.emit(event, payload) {
             tracing::warn!("Failed to emit event {}: {}", event, e);
         }
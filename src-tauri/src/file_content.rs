@@ -0,0 +1,317 @@
+//! Content sniffing for project files shown in the file and diff viewers.
+//!
+//! Decides whether bytes are text or binary, decodes text (UTF-8, UTF-16 or
+//! Latin-1), and derives a mime type and syntax language hint.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Number of leading bytes inspected when classifying content
+const SNIFF_LEN: usize = 8192;
+
+/// Structured view of a file's contents
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    pub mime_type: String,
+    pub is_binary: bool,
+    /// Detected text encoding ("utf-8", "utf-16le", "utf-16be", "latin-1"); `None` for binary
+    pub encoding: Option<String>,
+    /// Decoded text; `None` for binary content
+    pub text: Option<String>,
+    pub line_count: Option<usize>,
+    /// Syntax highlighting hint derived from the file name
+    pub language: Option<String>,
+    pub size: u64,
+    /// SHA-256 of the raw bytes, usable as `expected_hash` when saving
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+impl Encoding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+        }
+    }
+}
+
+/// Classify and decode `bytes` read from `path`
+pub fn analyze(path: &Path, bytes: &[u8]) -> FileContent {
+    let language = language_hint(path).map(str::to_string);
+    let hash = crate::utils::sha256_hex(bytes);
+    let size = bytes.len() as u64;
+
+    let decoded = if magic_mime(bytes).is_some() {
+        None
+    } else {
+        detect_encoding(bytes).and_then(|encoding| decode(bytes, encoding).map(|text| (encoding, text)))
+    };
+
+    match decoded {
+        Some((encoding, text)) => FileContent {
+            mime_type: extension_mime(path).unwrap_or("text/plain").to_string(),
+            is_binary: false,
+            encoding: Some(encoding.as_str().to_string()),
+            line_count: Some(count_lines(&text)),
+            text: Some(text),
+            language,
+            size,
+            hash,
+        },
+        None => FileContent {
+            mime_type: magic_mime(bytes)
+                .or_else(|| extension_mime(path))
+                .unwrap_or("application/octet-stream")
+                .to_string(),
+            is_binary: true,
+            encoding: None,
+            text: None,
+            line_count: None,
+            language: None,
+            size,
+            hash,
+        },
+    }
+}
+
+/// Pick a text encoding, or `None` if the content looks binary
+fn detect_encoding(bytes: &[u8]) -> Option<Encoding> {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        return Some(Encoding::Utf8);
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Some(Encoding::Utf16Le);
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Some(Encoding::Utf16Be);
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if let Some(encoding) = bomless_utf16(sample) {
+        return Some(encoding);
+    }
+    if sample.contains(&0) {
+        return None;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Some(Encoding::Utf8);
+    }
+
+    // Latin-1 decodes anything; only accept it when control characters are rare
+    let control = sample
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0C | 0x1B))
+        .count();
+    (control * 100 <= sample.len() * 5).then_some(Encoding::Latin1)
+}
+
+/// Detect BOM-less UTF-16 from NUL bytes concentrated at odd or even offsets
+fn bomless_utf16(sample: &[u8]) -> Option<Encoding> {
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    if odd_nuls * 10 >= pairs * 7 && even_nuls * 10 <= pairs {
+        Some(Encoding::Utf16Le)
+    } else if even_nuls * 10 >= pairs * 7 && odd_nuls * 10 <= pairs {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+fn decode(bytes: &[u8], encoding: Encoding) -> Option<String> {
+    match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            String::from_utf8(bytes.to_vec()).ok()
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let bom: &[u8] = if encoding == Encoding::Utf16Le { &[0xFF, 0xFE] } else { &[0xFE, 0xFF] };
+            let bytes = bytes.strip_prefix(bom).unwrap_or(bytes);
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| match encoding {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                })
+                .collect();
+            String::from_utf16(&units).ok()
+        }
+        Encoding::Latin1 => Some(bytes.iter().map(|&b| b as char).collect()),
+    }
+}
+
+fn count_lines(text: &str) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    text.lines().count()
+}
+
+/// Mime type from well-known binary signatures
+fn magic_mime(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1F\x8B", "application/gzip"),
+        (b"\x7FELF", "application/x-elf"),
+        (b"\0asm", "application/wasm"),
+    ];
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+        .map(|(_, mime)| *mime)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+}
+
+/// Mime type implied by the file extension
+fn extension_mime(path: &Path) -> Option<&'static str> {
+    let mime = match extension(path)?.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" | "cjs" => "text/javascript",
+        "ts" | "tsx" => "text/typescript",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "yaml" | "yml" => "application/yaml",
+        "toml" => "application/toml",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Syntax language hint for viewers, keyed on file name then extension
+pub fn language_hint(path: &Path) -> Option<&'static str> {
+    let file_name = path.file_name()?.to_str()?;
+    match file_name {
+        "Dockerfile" => return Some("dockerfile"),
+        "Makefile" | "GNUmakefile" => return Some("makefile"),
+        "CMakeLists.txt" => return Some("cmake"),
+        "Cargo.lock" => return Some("toml"),
+        _ => {}
+    }
+
+    let language = match extension(path)?.as_str() {
+        "rs" => "rust",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "cpp",
+        "cs" => "csharp",
+        "rb" => "ruby",
+        "php" => "php",
+        "sh" | "bash" | "zsh" => "bash",
+        "ps1" => "powershell",
+        "sql" => "sql",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "md" | "markdown" => "markdown",
+        "vue" => "vue",
+        "svelte" => "svelte",
+        "lua" => "lua",
+        "dart" => "dart",
+        "scala" => "scala",
+        "ex" | "exs" => "elixir",
+        "hs" => "haskell",
+        "zig" => "zig",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_utf8_text() {
+        let content = analyze(Path::new("src/main.rs"), "fn main() {}\n// héllo\n".as_bytes());
+        assert!(!content.is_binary);
+        assert_eq!(content.encoding.as_deref(), Some("utf-8"));
+        assert_eq!(content.line_count, Some(2));
+        assert_eq!(content.language.as_deref(), Some("rust"));
+        assert_eq!(content.mime_type, "text/plain");
+    }
+
+    #[test]
+    fn test_analyze_utf16_with_and_without_bom() {
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend("hi\nthere".encode_utf16().flat_map(|u| u.to_le_bytes()));
+        let content = analyze(Path::new("notes.txt"), &with_bom);
+        assert_eq!(content.encoding.as_deref(), Some("utf-16le"));
+        assert_eq!(content.text.as_deref(), Some("hi\nthere"));
+
+        let bomless: Vec<u8> = "plain ascii".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        let content = analyze(Path::new("notes.txt"), &bomless);
+        assert_eq!(content.encoding.as_deref(), Some("utf-16be"));
+        assert_eq!(content.text.as_deref(), Some("plain ascii"));
+    }
+
+    #[test]
+    fn test_analyze_latin1_and_binary() {
+        let content = analyze(Path::new("legacy.txt"), b"caf\xE9 au lait");
+        assert_eq!(content.encoding.as_deref(), Some("latin-1"));
+        assert_eq!(content.text.as_deref(), Some("café au lait"));
+
+        let content = analyze(Path::new("logo.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        assert!(content.is_binary);
+        assert_eq!(content.mime_type, "image/png");
+        assert!(content.text.is_none());
+
+        let content = analyze(Path::new("blob.bin"), b"\x01\x02\x00\x03\x04\x05\x00\x00\x10");
+        assert!(content.is_binary);
+        assert_eq!(content.mime_type, "application/octet-stream");
+    }
+}
@@ -0,0 +1,412 @@
+//! In-memory file index per project for fast @-mention search
+//!
+//! Each project root gets an index that is built on first access. The
+//! project's watcher (see `watcher`) only marks the index stale; the next
+//! search rebuilds it, so bursts of changes cost a single walk. Without a
+//! watcher an index is rebuilt once it is `UNWATCHED_MAX_AGE` old. Only the
+//! `MAX_INDEXES` most recently searched projects keep an index.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+
+mod rules;
+
+pub use rules::{explain, IgnoreExplanation, IgnoreRules, IgnoreSource};
+
+/// Directories that are never listed, even without a .gitignore
+pub(crate) const ALWAYS_IGNORED_DIRS: &[&str] = &[".git", ".svn", ".hg", "node_modules"];
+
+/// Build output and environment directories that are not listed, even
+/// without a .gitignore, unless a project `!rule` re-includes them
+pub(crate) const DEFAULT_EXCLUDED_DIRS: &[&str] = &[
+    "target",
+    "dist",
+    "build",
+    "coverage",
+    "vendor",
+    "__pycache__",
+    "venv",
+    "env",
+    ".env",
+];
+
+/// Hidden files that are still useful to mention
+pub(crate) const ALLOWED_HIDDEN_FILES: &[&str] = &[".env", ".gitignore", ".eslintrc", ".prettierrc"];
+
+/// Maximum traversal depth when building an index
+const INDEX_MAX_DEPTH: usize = 128;
+
+/// Upper bound on indexed entries per project to cap memory usage
+const MAX_INDEXED_ENTRIES: usize = 200_000;
+
+/// Indexes kept at once; the least recently searched one goes first
+const MAX_INDEXES: usize = 8;
+
+/// Age at which the index of a project nothing watches is rebuilt
+const UNWATCHED_MAX_AGE: Duration = Duration::from_secs(10);
+
+/// Build a gitignore-aware walker rooted at the project directory
+///
+/// `DEFAULT_EXCLUDED_DIRS` are skipped through overrides. Project `rules`
+/// are applied on top of .gitignore and may re-include hidden files and the
+/// always-ignored directories.
+pub(crate) fn project_walker(
+    root: &Path,
+    max_depth: usize,
+    rules: &IgnoreRules,
+) -> ignore::Walk {
+    let rules = rules.clone();
+    ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .require_git(false)
+        .max_depth(Some(max_depth))
+        .overrides(rules.overrides(root))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            entry.depth() == 0 || rules.allows(entry.path(), is_dir)
+        })
+        .build()
+}
+
+/// Ranked search result
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMatch {
+    /// Relative path from project root
+    pub path: String,
+    /// File name only
+    pub name: String,
+    /// Whether it's a directory
+    pub is_dir: bool,
+    /// Relevance score (higher is better)
+    pub score: i32,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedEntry {
+    path: String,
+    name: String,
+    is_dir: bool,
+    path_lower: String,
+    name_lower: String,
+}
+
+/// Index for a single project root
+struct ProjectIndex {
+    entries: RwLock<Vec<IndexedEntry>>,
+    rules: Mutex<IgnoreRules>,
+    stale: AtomicBool,
+    built_at: Mutex<Option<Instant>>,
+    last_used: Mutex<Instant>,
+}
+
+impl ProjectIndex {
+    fn new() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+            rules: Mutex::new(IgnoreRules::default()),
+            stale: AtomicBool::new(true),
+            built_at: Mutex::new(None),
+            last_used: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Rebuild the entry list if the watcher flagged changes, the rules
+    /// changed or, with no watcher, the entries are too old
+    fn refresh_if_stale(&self, root: &Path, rules: &IgnoreRules, watched: bool) {
+        {
+            let mut current = self.rules.lock();
+            if *current != *rules {
+                *current = rules.clone();
+                self.stale.store(true, Ordering::SeqCst);
+            }
+        }
+        let expired = !watched
+            && self
+                .built_at
+                .lock()
+                .is_none_or(|built_at| built_at.elapsed() >= UNWATCHED_MAX_AGE);
+        if !self.stale.swap(false, Ordering::SeqCst) && !expired {
+            return;
+        }
+        let entries = build_entries(root, rules);
+        tracing::debug!("Indexed {} entries under {}", entries.len(), root.display());
+        *self.entries.write() = entries;
+        *self.built_at.lock() = Some(Instant::now());
+    }
+}
+
+/// Whether a changed path can be skipped without invalidating the index
+pub(crate) fn is_ignored_change(root: &Path, gitignore: &ignore::gitignore::Gitignore, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    if relative
+        .components()
+        .any(|c| ALWAYS_IGNORED_DIRS.contains(&&*c.as_os_str().to_string_lossy()))
+    {
+        return true;
+    }
+    gitignore
+        .matched_path_or_any_parents(relative, path.is_dir())
+        .is_ignore()
+}
+
+fn build_entries(root: &Path, rules: &IgnoreRules) -> Vec<IndexedEntry> {
+    let mut entries = Vec::new();
+
+    for entry in project_walker(root, INDEX_MAX_DEPTH, rules).filter_map(|e| e.ok()) {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entries.len() >= MAX_INDEXED_ENTRIES {
+            tracing::warn!(
+                "File index for {} truncated at {} entries",
+                root.display(),
+                MAX_INDEXED_ENTRIES
+            );
+            break;
+        }
+
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+
+        entries.push(IndexedEntry {
+            path_lower: relative.to_lowercase(),
+            name_lower: name.to_lowercase(),
+            path: relative,
+            name: name.to_string(),
+            is_dir: entry.file_type().is_some_and(|t| t.is_dir()),
+        });
+    }
+
+    entries
+}
+
+/// Score how well `query` (lowercase) matches an entry; `None` means no match.
+///
+/// Name matches outrank path matches, and fuzzy subsequence matches are
+/// rewarded for consecutive characters and for hitting word boundaries.
+fn score_entry(name_lower: &str, path_lower: &str, query: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let base = if name_lower == query {
+        1000
+    } else if name_lower.starts_with(query) {
+        800
+    } else if name_lower.contains(query) {
+        600
+    } else if path_lower.contains(query) {
+        400
+    } else if let Some(fuzzy) = fuzzy_score(name_lower, query) {
+        200 + fuzzy
+    } else {
+        fuzzy_score(path_lower, query)?
+    };
+
+    // Prefer shallower, shorter paths among equal matches
+    let depth_penalty = path_lower.matches('/').count() as i32 * 5;
+    let length_penalty = (path_lower.len() as i32).min(100);
+    Some(base - depth_penalty - length_penalty / 10)
+}
+
+/// Subsequence match score: consecutive and boundary hits score higher
+fn fuzzy_score(target: &str, query: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut prev_match_end: Option<usize> = None;
+    let mut target_iter = target.char_indices();
+    let mut prev_char: Option<char> = None;
+
+    for query_char in query.chars() {
+        loop {
+            let (idx, c) = target_iter.next()?;
+            let boundary = matches!(prev_char, None | Some('/' | '_' | '-' | '.' | ' '));
+            prev_char = Some(c);
+            if c != query_char {
+                continue;
+            }
+            score += 1;
+            if boundary {
+                score += 8;
+            }
+            if prev_match_end == Some(idx) {
+                score += 5;
+            }
+            prev_match_end = Some(idx + c.len_utf8());
+            break;
+        }
+    }
+
+    Some(score)
+}
+
+/// Per-project file indexes keyed by canonical project root
+pub struct FileIndexManager {
+    indexes: Mutex<HashMap<PathBuf, Arc<ProjectIndex>>>,
+}
+
+impl Default for FileIndexManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileIndexManager {
+    pub fn new() -> Self {
+        Self {
+            indexes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The index for `root`, evicting the least recently used one when a
+    /// new index would exceed `MAX_INDEXES`
+    fn index_for(&self, root: &Path) -> Arc<ProjectIndex> {
+        let mut indexes = self.indexes.lock();
+        if !indexes.contains_key(root) && indexes.len() >= MAX_INDEXES {
+            let oldest = indexes
+                .iter()
+                .min_by_key(|(_, index)| *index.last_used.lock())
+                .map(|(root, _)| root.clone());
+            if let Some(oldest) = oldest {
+                indexes.remove(&oldest);
+            }
+        }
+        let index = indexes
+            .entry(root.to_path_buf())
+            .or_insert_with(|| Arc::new(ProjectIndex::new()))
+            .clone();
+        *index.last_used.lock() = Instant::now();
+        index
+    }
+
+    /// Ranked fuzzy search over the index for `root` (blocking on first build)
+    ///
+    /// `watched` says the project's watcher invalidates the index on change.
+    pub fn search(
+        &self,
+        root: &Path,
+        rules: &IgnoreRules,
+        watched: bool,
+        query: &str,
+        limit: usize,
+    ) -> Vec<FileMatch> {
+        let index = self.index_for(root);
+        index.refresh_if_stale(root, rules, watched);
+
+        let query = query.trim().to_lowercase();
+        let entries = index.entries.read();
+        let mut matches: Vec<FileMatch> = entries
+            .iter()
+            .filter_map(|entry| {
+                score_entry(&entry.name_lower, &entry.path_lower, &query).map(|score| FileMatch {
+                    path: entry.path.clone(),
+                    name: entry.name.clone(),
+                    is_dir: entry.is_dir,
+                    score,
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Mark an index stale so the next search rebuilds it
+    pub fn invalidate(&self, root: &Path) {
+        if let Some(index) = self.indexes.lock().get(root) {
+            index.stale.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Drop the index for a project root
+    pub fn remove(&self, root: &Path) {
+        self.indexes.lock().remove(root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_entry_ranking() {
+        let exact = score_entry("main.rs", "src/main.rs", "main.rs").unwrap();
+        let prefix = score_entry("main_window.rs", "src/main_window.rs", "main").unwrap();
+        let contains = score_entry("domain.rs", "src/domain.rs", "main").unwrap();
+        let path_only = score_entry("mod.rs", "src/main/mod.rs", "main").unwrap();
+        let fuzzy = score_entry("my_app_index.rs", "src/my_app_index.rs", "mai").unwrap();
+
+        assert!(exact > prefix);
+        assert!(prefix > contains);
+        assert!(contains > path_only);
+        assert!(path_only > fuzzy);
+        assert!(score_entry("lib.rs", "src/lib.rs", "xyz").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_boundaries() {
+        let boundary = fuzzy_score("file_index_manager", "fim").unwrap();
+        let scattered = fuzzy_score("afxixm", "fim").unwrap();
+        assert!(boundary > scattered);
+        assert!(fuzzy_score("abc", "abd").is_none());
+    }
+
+    #[test]
+    fn test_manager_search_builds_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        std::fs::create_dir_all(root.join("src/components")).unwrap();
+        std::fs::write(root.join("src/components/Button.tsx"), "").unwrap();
+        std::fs::write(root.join("src/main.ts"), "").unwrap();
+
+        let manager = FileIndexManager::new();
+        let rules = IgnoreRules::default();
+        let results = manager.search(&root, &rules, true, "button", 10);
+        assert_eq!(results.first().map(|m| m.path.as_str()), Some("src/components/Button.tsx"));
+
+        std::fs::write(root.join("src/buttons.css"), "").unwrap();
+        let results = manager.search(&root, &rules, true, "button", 10);
+        assert!(!results.iter().any(|m| m.path == "src/buttons.css"));
+        manager.invalidate(&root);
+        let results = manager.search(&root, &rules, true, "button", 10);
+        assert!(results.iter().any(|m| m.path == "src/buttons.css"));
+    }
+
+    #[test]
+    fn test_manager_evicts_least_recently_used() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = FileIndexManager::new();
+        let roots: Vec<PathBuf> = (0..=MAX_INDEXES)
+            .map(|i| temp_dir.path().join(format!("project{i}")))
+            .collect();
+        for root in &roots {
+            std::fs::create_dir_all(root).unwrap();
+            manager.search(root, &IgnoreRules::default(), true, "", 1);
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        let indexes = manager.indexes.lock();
+        assert_eq!(indexes.len(), MAX_INDEXES);
+        assert!(!indexes.contains_key(&roots[0]));
+        assert!(indexes.contains_key(&roots[MAX_INDEXES]));
+    }
+}
@@ -0,0 +1,335 @@
+//! Per-project ignore rules for file listing, search and indexing
+//!
+//! Rules use .gitignore syntax and are checked before the built-in filters:
+//! a matching pattern hides the entry, a matching `!pattern` shows it even if
+//! it is hidden (dot-prefixed) or one of `ALWAYS_IGNORED_DIRS`. Entries
+//! ignored by the project's .gitignore files stay ignored, since the walker
+//! applies those first. `DEFAULT_EXCLUDED_DIRS` are walker overrides, so
+//! only a `!rule` naming one at the project root brings it back.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::Match;
+use serde::Serialize;
+
+use super::{ALLOWED_HIDDEN_FILES, ALWAYS_IGNORED_DIRS, DEFAULT_EXCLUDED_DIRS};
+use crate::{Error, Result};
+
+/// Maximum number of patterns per project
+pub const MAX_IGNORE_RULES: usize = 200;
+
+/// Compiled ignore rules for one project root
+#[derive(Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<String>,
+    matcher: Option<Arc<Gitignore>>,
+}
+
+impl PartialEq for IgnoreRules {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
+    }
+}
+
+impl IgnoreRules {
+    /// Compile `patterns` relative to `root`; fails on an invalid pattern
+    pub fn new(root: &Path, patterns: &[String]) -> Result<Self> {
+        if patterns.len() > MAX_IGNORE_RULES {
+            return Err(Error::Other(format!(
+                "Too many ignore rules: {} (max {MAX_IGNORE_RULES})",
+                patterns.len()
+            )));
+        }
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|pattern| pattern.trim().to_string())
+            .filter(|pattern| !pattern.is_empty() && !pattern.starts_with('#'))
+            .collect();
+        if patterns.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &patterns {
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| Error::Other(format!("Invalid ignore rule '{pattern}': {e}")))?;
+        }
+        let matcher = builder
+            .build()
+            .map_err(|e| Error::Other(format!("Invalid ignore rules: {e}")))?;
+        Ok(Self {
+            patterns,
+            matcher: Some(Arc::new(matcher)),
+        })
+    }
+
+    /// The project rule deciding `path`, if any, as (ignored, pattern)
+    fn rule_for(&self, path: &Path, is_dir: bool) -> Option<(bool, String)> {
+        match self.matcher.as_ref()?.matched(path, is_dir) {
+            Match::Ignore(glob) => Some((true, glob.original().to_string())),
+            Match::Whitelist(glob) => Some((false, glob.original().to_string())),
+            Match::None => None,
+        }
+    }
+
+    /// Whether the walker should descend into / list `path`
+    pub(crate) fn allows(&self, path: &Path, is_dir: bool) -> bool {
+        match self.rule_for(path, is_dir) {
+            Some((ignored, _)) => !ignored,
+            None => builtin_decision(path, is_dir).is_none(),
+        }
+    }
+
+    /// `DEFAULT_EXCLUDED_DIRS` that no `!rule` re-includes at `root`
+    fn default_excludes(&self, root: &Path) -> Vec<&'static str> {
+        DEFAULT_EXCLUDED_DIRS
+            .iter()
+            .copied()
+            .filter(|name| !matches!(self.rule_for(&root.join(name), true), Some((false, _))))
+            .collect()
+    }
+
+    /// Walker overrides hiding the default-excluded directories under `root`
+    pub(crate) fn overrides(&self, root: &Path) -> Override {
+        let mut builder = OverrideBuilder::new(root);
+        for name in self.default_excludes(root) {
+            if let Err(err) = builder.add(&format!("!{name}/")) {
+                tracing::warn!("Invalid default exclude '{}': {}", name, err);
+            }
+        }
+        builder.build().unwrap_or_else(|err| {
+            tracing::warn!("Failed to build default excludes: {}", err);
+            Override::empty()
+        })
+    }
+}
+
+/// Why the built-in filters hide `path`, if they do
+fn builtin_decision(path: &Path, is_dir: bool) -> Option<IgnoreSource> {
+    let name = path.file_name()?.to_string_lossy();
+    if is_dir && ALWAYS_IGNORED_DIRS.contains(&&*name) {
+        return Some(IgnoreSource::BuiltinDirectory);
+    }
+    if name.starts_with('.') && !ALLOWED_HIDDEN_FILES.contains(&&*name) {
+        return Some(IgnoreSource::Hidden);
+    }
+    None
+}
+
+/// What decided whether a path is listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IgnoreSource {
+    /// A project ignore rule (or `!rule` re-including the path)
+    ProjectRule,
+    /// A .gitignore file or .git/info/exclude
+    Gitignore,
+    /// A build output or environment directory (target, dist, venv, ...)
+    DefaultExclude,
+    /// One of the always-ignored directories (.git, node_modules, ...)
+    BuiltinDirectory,
+    /// A dot-prefixed name that is not on the allowed list
+    Hidden,
+}
+
+/// Result of `preview_ignore_effect`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreExplanation {
+    /// Path relative to the project root
+    pub path: String,
+    pub ignored: bool,
+    /// `None` when no rule applies and the path is listed
+    pub source: Option<IgnoreSource>,
+    /// The deciding pattern, for rule and .gitignore decisions
+    pub pattern: Option<String>,
+    /// The ancestor directory whose rules hide this path, if not the path itself
+    pub via: Option<String>,
+}
+
+/// Explain whether `path` (inside `root`) is listed and which rule decides
+///
+/// Ancestors are checked from the root down, since an ignored directory
+/// hides everything beneath it.
+pub fn explain(root: &Path, rules: &IgnoreRules, path: &Path) -> IgnoreExplanation {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let display = relative.to_string_lossy().replace('\\', "/");
+
+    let components: Vec<_> = relative.components().collect();
+    let mut current = root.to_path_buf();
+    for (idx, component) in components.iter().enumerate() {
+        current.push(component);
+        let is_last = idx + 1 == components.len();
+        let is_dir = !is_last || path.is_dir();
+        let Some((source, ignored, pattern)) = decide(root, rules, &current, is_dir) else {
+            continue;
+        };
+        // A re-included ancestor only matters once its children are checked
+        if !ignored && !is_last {
+            continue;
+        }
+        let via = (!is_last).then(|| {
+            current
+                .strip_prefix(root)
+                .unwrap_or(&current)
+                .to_string_lossy()
+                .replace('\\', "/")
+        });
+        return IgnoreExplanation {
+            path: display,
+            ignored,
+            source: Some(source),
+            pattern,
+            via,
+        };
+    }
+
+    IgnoreExplanation {
+        path: display,
+        ignored: false,
+        source: None,
+        pattern: None,
+        via: None,
+    }
+}
+
+/// Decide one path in walker order: .gitignore, project rules hiding it,
+/// default excludes, project rules showing it, built-ins
+fn decide(
+    root: &Path,
+    rules: &IgnoreRules,
+    path: &Path,
+    is_dir: bool,
+) -> Option<(IgnoreSource, bool, Option<String>)> {
+    if let Some(pattern) = gitignore_match(root, path, is_dir) {
+        return Some((IgnoreSource::Gitignore, true, Some(pattern)));
+    }
+    let rule = rules.rule_for(path, is_dir);
+    if let Some((true, pattern)) = rule {
+        return Some((IgnoreSource::ProjectRule, true, Some(pattern)));
+    }
+    let name = path.file_name().map(|name| name.to_string_lossy());
+    if is_dir && name.is_some_and(|name| rules.default_excludes(root).contains(&&*name)) {
+        return Some((IgnoreSource::DefaultExclude, true, None));
+    }
+    if let Some((false, pattern)) = rule {
+        return Some((IgnoreSource::ProjectRule, false, Some(pattern)));
+    }
+    builtin_decision(path, is_dir).map(|source| (source, true, None))
+}
+
+/// Pattern of the deepest .gitignore (or .git/info/exclude) ignoring `path`
+fn gitignore_match(root: &Path, path: &Path, is_dir: bool) -> Option<String> {
+    let mut files: Vec<PathBuf> = vec![root.join(".git/info/exclude")];
+    let mut dir = path.parent();
+    let mut nested = Vec::new();
+    while let Some(current) = dir {
+        if !current.starts_with(root) {
+            break;
+        }
+        nested.push(current.join(".gitignore"));
+        dir = current.parent();
+    }
+    files.extend(nested.into_iter().rev());
+
+    let mut decision = None;
+    for file in files.iter().filter(|file| file.is_file()) {
+        let base = if file.ends_with(".git/info/exclude") {
+            root
+        } else {
+            file.parent().unwrap_or(root)
+        };
+        let mut builder = GitignoreBuilder::new(base);
+        if builder.add(file).is_some() {
+            continue;
+        }
+        let Ok(gitignore) = builder.build() else {
+            continue;
+        };
+        match gitignore.matched(path, is_dir) {
+            Match::Ignore(glob) => decision = Some(glob.original().to_string()),
+            Match::Whitelist(_) => decision = None,
+            Match::None => {}
+        }
+    }
+    decision
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_override_builtins() {
+        let root = Path::new("/project");
+        let rules = IgnoreRules::new(
+            root,
+            &["dist/".to_string(), "!.github/".to_string(), "# comment".to_string()],
+        )
+        .unwrap();
+
+        assert!(!rules.allows(&root.join("dist"), true));
+        assert!(rules.allows(&root.join(".github"), true));
+        assert!(!rules.allows(&root.join(".cache"), true));
+        assert!(!rules.allows(&root.join("node_modules"), true));
+        assert!(rules.allows(&root.join("src"), true));
+        assert!(rules.allows(&root.join(".env"), false));
+    }
+
+    #[test]
+    fn test_walker_skips_default_excludes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        for dir in ["src", "target/debug", "web/dist", "venv/lib"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        let listed = |rules: &IgnoreRules| -> Vec<String> {
+            crate::file_index::project_walker(root, 8, rules)
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.depth() > 0)
+                .map(|entry| {
+                    let relative = entry.path().strip_prefix(root).unwrap();
+                    relative.to_string_lossy().replace('\\', "/")
+                })
+                .collect()
+        };
+
+        assert_eq!(listed(&IgnoreRules::default()), vec!["src", "web"]);
+        let rules = IgnoreRules::new(root, &["!target/".to_string()]).unwrap();
+        assert_eq!(listed(&rules), vec!["src", "target", "target/debug", "web"]);
+    }
+
+    #[test]
+    fn test_explain_reports_deciding_rule() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::create_dir_all(root.join("dist/assets")).unwrap();
+        let rules = IgnoreRules::new(root, &["dist/".to_string()]).unwrap();
+
+        let log = explain(root, &rules, &root.join("debug.log"));
+        assert!(log.ignored);
+        assert_eq!(log.source, Some(IgnoreSource::Gitignore));
+        assert_eq!(log.pattern.as_deref(), Some("*.log"));
+
+        let asset = explain(root, &rules, &root.join("dist/assets"));
+        assert!(asset.ignored);
+        assert_eq!(asset.source, Some(IgnoreSource::ProjectRule));
+        assert_eq!(asset.via.as_deref(), Some("dist"));
+
+        let source = explain(root, &rules, &root.join("src/main.rs"));
+        assert!(!source.ignored);
+        assert!(source.source.is_none());
+
+        std::fs::create_dir_all(root.join("crates/core/target")).unwrap();
+        let build = explain(root, &rules, &root.join("crates/core/target"));
+        assert_eq!(build.source, Some(IgnoreSource::DefaultExclude));
+
+        assert!(IgnoreRules::new(root, &["a[z-a]".to_string()]).is_err());
+    }
+}
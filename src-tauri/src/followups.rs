@@ -0,0 +1,164 @@
+//! Per-session queues of follow-up prompts.
+//!
+//! When a turn completes successfully, the supervisor pops the next queued
+//! prompt for that session and starts a new turn with it, so "do A, then B,
+//! then C" pipelines run without the user waiting on each step.
+
+use std::collections::{HashMap, VecDeque};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::global_state::unix_timestamp_millis;
+use crate::{Error, Result};
+
+/// Maximum number of prompts queued for a single session
+const MAX_QUEUED_PER_SESSION: usize = 50;
+
+/// A prompt waiting to be sent once the current turn completes
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedFollowup {
+    pub id: String,
+    pub prompt: String,
+    pub created_at: i64,
+}
+
+/// Follow-up queues keyed by session (thread) id
+#[derive(Debug, Default)]
+pub struct FollowupQueues {
+    queues: Mutex<HashMap<String, VecDeque<QueuedFollowup>>>,
+}
+
+impl FollowupQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a prompt to the end of a session's queue
+    pub fn enqueue(&self, session_id: &str, prompt: String) -> Result<QueuedFollowup> {
+        if prompt.trim().is_empty() {
+            return Err(Error::Other("Follow-up prompt cannot be empty".to_string()));
+        }
+
+        let mut queues = self.queues.lock();
+        let queue = queues.entry(session_id.to_string()).or_default();
+        if queue.len() >= MAX_QUEUED_PER_SESSION {
+            return Err(Error::Other(format!(
+                "Follow-up queue is full (max {MAX_QUEUED_PER_SESSION} prompts)"
+            )));
+        }
+
+        let followup = QueuedFollowup {
+            id: uuid::Uuid::new_v4().to_string(),
+            prompt,
+            created_at: unix_timestamp_millis(),
+        };
+        queue.push_back(followup.clone());
+        Ok(followup)
+    }
+
+    /// Take the next prompt for a session, if any
+    pub fn pop(&self, session_id: &str) -> Option<QueuedFollowup> {
+        let mut queues = self.queues.lock();
+        let queue = queues.get_mut(session_id)?;
+        let next = queue.pop_front();
+        if queue.is_empty() {
+            queues.remove(session_id);
+        }
+        next
+    }
+
+    /// Put a prompt back at the head of the queue (e.g. after a failed send)
+    pub fn requeue_front(&self, session_id: &str, followup: QueuedFollowup) {
+        self.queues
+            .lock()
+            .entry(session_id.to_string())
+            .or_default()
+            .push_front(followup);
+    }
+
+    /// Snapshot of a session's queue in send order
+    pub fn list(&self, session_id: &str) -> Vec<QueuedFollowup> {
+        self.queues
+            .lock()
+            .get(session_id)
+            .map(|queue| queue.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Remove a single queued prompt; returns whether it was found
+    pub fn remove(&self, session_id: &str, followup_id: &str) -> bool {
+        let mut queues = self.queues.lock();
+        let Some(queue) = queues.get_mut(session_id) else {
+            return false;
+        };
+        let before = queue.len();
+        queue.retain(|followup| followup.id != followup_id);
+        let removed = queue.len() != before;
+        if queue.is_empty() {
+            queues.remove(session_id);
+        }
+        removed
+    }
+
+    /// Total prompts queued across all sessions
+    pub fn total_len(&self) -> usize {
+        self.queues.lock().values().map(VecDeque::len).sum()
+    }
+
+    /// Sessions with queued prompts and how many each has
+    pub fn counts(&self) -> Vec<(String, usize)> {
+        self.queues
+            .lock()
+            .iter()
+            .map(|(session_id, queue)| (session_id.clone(), queue.len()))
+            .collect()
+    }
+
+    /// Drop every queued prompt for a session; returns how many were dropped
+    pub fn clear(&self, session_id: &str) -> usize {
+        self.queues
+            .lock()
+            .remove(session_id)
+            .map(|queue| queue.len())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_order_and_removal() {
+        let queues = FollowupQueues::new();
+        let a = queues.enqueue("s1", "do A".to_string()).unwrap();
+        let b = queues.enqueue("s1", "do B".to_string()).unwrap();
+        queues.enqueue("s1", "do C".to_string()).unwrap();
+        assert!(queues.enqueue("s1", "   ".to_string()).is_err());
+
+        assert!(queues.remove("s1", &b.id));
+        assert!(!queues.remove("s1", &b.id));
+
+        let first = queues.pop("s1").unwrap();
+        assert_eq!(first.id, a.id);
+        queues.requeue_front("s1", first);
+        let prompts: Vec<_> = queues.list("s1").into_iter().map(|f| f.prompt).collect();
+        assert_eq!(prompts, vec!["do A", "do C"]);
+
+        assert_eq!(queues.clear("s1"), 2);
+        assert!(queues.pop("s1").is_none());
+        assert!(queues.list("s2").is_empty());
+    }
+
+    #[test]
+    fn test_queue_limit() {
+        let queues = FollowupQueues::new();
+        for i in 0..MAX_QUEUED_PER_SESSION {
+            queues.enqueue("s1", format!("step {i}")).unwrap();
+        }
+        assert!(queues.enqueue("s1", "one more".to_string()).is_err());
+        assert!(queues.enqueue("s2", "other session".to_string()).is_ok());
+    }
+}
@@ -0,0 +1,558 @@
+//! Code-hosting providers ("forges") behind a project's git remote.
+//!
+//! Pull request creation, auth checks and web links used to assume GitHub.
+//! The provider is now detected from the remote's host: GitHub goes through
+//! `gh`, GitLab (gitlab.com or any host named like it) through `glab`, and
+//! Bitbucket Cloud through its REST API with an app password kept in the
+//! keychain, since it has no official CLI.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+use crate::{Error, Result};
+
+const KEYCHAIN_SERVICE: &str = "codex-desktop.bitbucket";
+const KEYCHAIN_ACCOUNT: &str = "app-password";
+
+const BITBUCKET_API: &str = "https://api.bitbucket.org/2.0";
+const BITBUCKET_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForgeProvider {
+    Github,
+    Gitlab,
+    Bitbucket,
+}
+
+impl ForgeProvider {
+    /// Provider of a remote host; `None` for hosts it cannot be told from
+    pub fn from_host(host: &str) -> Option<Self> {
+        let host = host.to_ascii_lowercase();
+        if host == "github.com" || host.starts_with("github.") || host.ends_with(".ghe.com") {
+            Some(Self::Github)
+        } else if host.split('.').any(|label| label == "gitlab") {
+            Some(Self::Gitlab)
+        } else if host == "bitbucket.org" {
+            Some(Self::Bitbucket)
+        } else {
+            None
+        }
+    }
+
+    /// What the user has to have set up, as shown in the UI
+    pub fn tool_name(self) -> &'static str {
+        match self {
+            Self::Github => "GitHub CLI (gh)",
+            Self::Gitlab => "GitLab CLI (glab)",
+            Self::Bitbucket => "Bitbucket app password",
+        }
+    }
+
+    fn setup_hint(self, status: ForgeAuthStatus) -> Option<&'static str> {
+        match (self, status) {
+            (_, ForgeAuthStatus::Ready) => None,
+            (Self::Github, ForgeAuthStatus::NotInstalled) => {
+                Some("Install GitHub CLI, then run: gh auth login")
+            }
+            (Self::Github, ForgeAuthStatus::NotAuthenticated) => Some("Run: gh auth login"),
+            (Self::Gitlab, ForgeAuthStatus::NotInstalled) => {
+                Some("Install GitLab CLI, then run: glab auth login")
+            }
+            (Self::Gitlab, ForgeAuthStatus::NotAuthenticated) => Some("Run: glab auth login"),
+            (Self::Bitbucket, _) => {
+                Some("Add your Bitbucket username and an app password with pull request access")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForgeAuthStatus {
+    Ready,
+    NotInstalled,
+    NotAuthenticated,
+}
+
+/// The repository a git remote points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeRemote {
+    pub provider: ForgeProvider,
+    pub host: String,
+    /// Owner, group path or workspace, e.g. `group/subgroup` on GitLab
+    pub namespace: String,
+    pub repo: String,
+}
+
+/// Result of `check_forge_cli`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForgeStatus {
+    pub provider: ForgeProvider,
+    pub host: String,
+    pub tool_name: &'static str,
+    pub status: ForgeAuthStatus,
+    pub setup_hint: Option<&'static str>,
+    pub web_url: String,
+}
+
+/// Result of `create_pull_request`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedPullRequest {
+    pub provider: ForgeProvider,
+    pub url: String,
+}
+
+/// What `create` opens; fields are validated by the caller
+#[derive(Debug, Clone)]
+pub struct NewPullRequest {
+    pub title: String,
+    pub body: String,
+    pub base_branch: String,
+    pub head_branch: String,
+    pub draft: bool,
+}
+
+impl ForgeRemote {
+    /// Split a remote URL into host, namespace and repository:
+    /// `git@host:ns/repo.git`, `ssh://git@host:22/ns/repo` or
+    /// `https://host/ns/repo.git`
+    fn parse_url(url: &str) -> Option<(String, String, String)> {
+        let url = url.trim();
+        let (host, path) = match url.split_once("://") {
+            Some((_, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                let host = authority.rsplit('@').next()?;
+                let host = host.split(':').next()?;
+                (host, path)
+            }
+            None => {
+                let (authority, path) = url.split_once(':')?;
+                (authority.rsplit('@').next()?, path)
+            }
+        };
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let (namespace, repo) = path.rsplit_once('/')?;
+        if host.is_empty() || namespace.is_empty() || repo.is_empty() {
+            return None;
+        }
+        Some((host.to_string(), namespace.to_string(), repo.to_string()))
+    }
+
+    fn from_url(url: &str) -> Result<Self> {
+        let (host, namespace, repo) = Self::parse_url(url)
+            .ok_or_else(|| Error::Other(format!("Unrecognized git remote URL: {url}")))?;
+        let provider = ForgeProvider::from_host(&host).ok_or_else(|| {
+            Error::Other(format!(
+                "Unsupported git host '{host}'; pull requests work with GitHub, GitLab and Bitbucket"
+            ))
+        })?;
+        Ok(Self {
+            provider,
+            host,
+            namespace,
+            repo,
+        })
+    }
+
+    /// Web page of the repository
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.namespace, self.repo)
+    }
+
+    /// Web page of `branch`
+    pub fn branch_url(&self, branch: &str) -> String {
+        let branch = encode_path(branch);
+        match self.provider {
+            ForgeProvider::Github => format!("{}/tree/{branch}", self.web_url()),
+            ForgeProvider::Gitlab => format!("{}/-/tree/{branch}", self.web_url()),
+            ForgeProvider::Bitbucket => format!("{}/branch/{branch}", self.web_url()),
+        }
+    }
+
+    /// Web page that starts a pull request from `head` into `base`
+    pub fn new_pull_request_url(&self, base: &str, head: &str) -> String {
+        match self.provider {
+            ForgeProvider::Github => format!(
+                "{}/compare/{}...{}?expand=1",
+                self.web_url(),
+                encode_path(base),
+                encode_path(head)
+            ),
+            ForgeProvider::Gitlab => format!(
+                "{}/-/merge_requests/new?merge_request%5Bsource_branch%5D={}\
+                 &merge_request%5Btarget_branch%5D={}",
+                self.web_url(),
+                encode_component(head),
+                encode_component(base)
+            ),
+            ForgeProvider::Bitbucket => format!(
+                "{}/pull-requests/new?source={}&dest={}",
+                self.web_url(),
+                encode_component(head),
+                encode_component(base)
+            ),
+        }
+    }
+}
+
+/// Percent-encode everything but unreserved characters
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Like `encode_component`, keeping `/` so branch paths stay readable
+fn encode_path(value: &str) -> String {
+    value
+        .split('/')
+        .map(encode_component)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The remote pull requests for `head_branch` go to: the branch's own
+/// remote, else `origin`, else the only remote. Blocking.
+pub fn detect(dir: &Path, head_branch: Option<&str>) -> Result<ForgeRemote> {
+    let configured = head_branch
+        .and_then(|branch| git::value(dir, &["config", &format!("branch.{branch}.remote")]).ok())
+        .filter(|remote| !remote.is_empty() && remote != ".");
+    let remote = match configured {
+        Some(remote) => remote,
+        None => {
+            let remotes = git::run(dir, &["remote"])?;
+            let remotes: Vec<&str> = remotes.lines().collect();
+            match remotes.as_slice() {
+                [] => return Err(Error::Other("The repository has no git remote".to_string())),
+                [only] => only.to_string(),
+                _ if remotes.contains(&"origin") => "origin".to_string(),
+                _ => {
+                    return Err(Error::Other(
+                        "The repository has several remotes and none is named origin".to_string(),
+                    ))
+                }
+            }
+        }
+    };
+    ForgeRemote::from_url(&git::value(dir, &["remote", "get-url", &remote])?)
+}
+
+/// Whether the provider's CLI or credentials are set up for `remote`.
+/// Blocking.
+pub fn auth_status(dir: &Path, remote: &ForgeRemote) -> Result<ForgeStatus> {
+    let status = match remote.provider {
+        ForgeProvider::Github => cli_auth_status(dir, "gh", &["auth", "status"])?,
+        ForgeProvider::Gitlab => {
+            cli_auth_status(dir, "glab", &["auth", "status", "--hostname", &remote.host])?
+        }
+        ForgeProvider::Bitbucket => match bitbucket_credentials()? {
+            Some(_) => ForgeAuthStatus::Ready,
+            None => ForgeAuthStatus::NotAuthenticated,
+        },
+    };
+    Ok(ForgeStatus {
+        provider: remote.provider,
+        host: remote.host.clone(),
+        tool_name: remote.provider.tool_name(),
+        status,
+        setup_hint: remote.provider.setup_hint(status),
+        web_url: remote.web_url(),
+    })
+}
+
+fn cli_auth_status(dir: &Path, program: &str, auth_args: &[&str]) -> Result<ForgeAuthStatus> {
+    match Command::new(program)
+        .arg("--version")
+        .current_dir(dir)
+        .output()
+    {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(ForgeAuthStatus::NotInstalled)
+        }
+        Err(err) => return Err(Error::Other(format!("Failed to check {program}: {err}"))),
+    }
+
+    let output = Command::new(program)
+        .args(auth_args)
+        .current_dir(dir)
+        .output()
+        .map_err(|err| Error::Other(format!("Failed to check {program}: {err}")))?;
+    Ok(if output.status.success() {
+        ForgeAuthStatus::Ready
+    } else {
+        ForgeAuthStatus::NotAuthenticated
+    })
+}
+
+/// Open a pull request (a merge request on GitLab) and return its URL.
+/// Blocking for GitHub and GitLab; Bitbucket goes through the HTTP client.
+pub async fn create(
+    dir: &Path,
+    remote: &ForgeRemote,
+    request: NewPullRequest,
+) -> Result<CreatedPullRequest> {
+    let url = match remote.provider {
+        ForgeProvider::Github => {
+            let dir = dir.to_path_buf();
+            crate::utils::spawn_blocking_io(move || create_github(&dir, &request)).await?
+        }
+        ForgeProvider::Gitlab => {
+            let dir = dir.to_path_buf();
+            crate::utils::spawn_blocking_io(move || create_gitlab(&dir, &request)).await?
+        }
+        ForgeProvider::Bitbucket => create_bitbucket(remote, &request).await?,
+    };
+    Ok(CreatedPullRequest {
+        provider: remote.provider,
+        url,
+    })
+}
+
+fn create_github(dir: &Path, request: &NewPullRequest) -> Result<String> {
+    // Title goes as a direct argument (no shell); the body via stdin
+    let mut args = vec![
+        "pr",
+        "create",
+        "--title",
+        &request.title,
+        "--base",
+        &request.base_branch,
+        "--head",
+        &request.head_branch,
+        "--body-file",
+        "-",
+    ];
+    if request.draft {
+        args.push("--draft");
+    }
+    let stdout = crate::pull_requests::gh_with_input(dir, &args, Some(&request.body))?;
+    // gh pr create prints the PR URL on stdout
+    Ok(stdout.trim().to_string())
+}
+
+fn create_gitlab(dir: &Path, request: &NewPullRequest) -> Result<String> {
+    let mut args = vec![
+        "mr",
+        "create",
+        "--title",
+        &request.title,
+        "--description",
+        &request.body,
+        "--target-branch",
+        &request.base_branch,
+        "--source-branch",
+        &request.head_branch,
+        "--yes",
+    ];
+    if request.draft {
+        args.push("--draft");
+    }
+    let output = Command::new("glab")
+        .args(&args)
+        .current_dir(dir)
+        .env("NO_PROMPT", "1")
+        .env("NO_COLOR", "1")
+        .output()
+        .map_err(|err| {
+            if err.kind() == std::io::ErrorKind::NotFound {
+                Error::CliNotInstalled {
+                    program: "GitLab CLI (glab)",
+                    install: "brew install glab",
+                }
+            } else {
+                Error::Other(format!("Failed to run glab: {err}"))
+            }
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Other(format!(
+            "glab mr create failed: {}",
+            stderr.trim()
+        )));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    merge_request_url(&stdout)
+        .ok_or_else(|| Error::Other(format!("Unexpected output from glab: {}", stdout.trim())))
+}
+
+/// The merge request URL in `glab mr create` output, which ends with it
+fn merge_request_url(stdout: &str) -> Option<String> {
+    stdout
+        .split_whitespace()
+        .rev()
+        .find(|word| word.starts_with("https://") || word.starts_with("http://"))
+        .map(str::to_string)
+}
+
+#[derive(Deserialize)]
+struct BitbucketPullRequest {
+    links: BitbucketLinks,
+}
+
+#[derive(Deserialize)]
+struct BitbucketLinks {
+    html: BitbucketLink,
+}
+
+#[derive(Deserialize)]
+struct BitbucketLink {
+    href: String,
+}
+
+async fn create_bitbucket(remote: &ForgeRemote, request: &NewPullRequest) -> Result<String> {
+    let (username, password) = bitbucket_credentials()?.ok_or_else(|| {
+        Error::Other("Add a Bitbucket app password before creating pull requests".to_string())
+    })?;
+    let client = reqwest::Client::builder()
+        .timeout(BITBUCKET_TIMEOUT)
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to create HTTP client: {e}")))?;
+    let response = client
+        .post(format!(
+            "{BITBUCKET_API}/repositories/{}/{}/pullrequests",
+            remote.namespace, remote.repo
+        ))
+        .basic_auth(username, Some(password))
+        .json(&serde_json::json!({
+            "title": request.title,
+            "description": request.body,
+            "source": { "branch": { "name": request.head_branch } },
+            "destination": { "branch": { "name": request.base_branch } },
+            "draft": request.draft,
+        }))
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Could not reach Bitbucket: {e}")))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(match status.as_u16() {
+            401 | 403 => format!("Bitbucket rejected the credentials ({status})"),
+            _ => format!("Bitbucket returned {status}: {}", body.trim()),
+        }));
+    }
+    let created: BitbucketPullRequest = response
+        .json()
+        .await
+        .map_err(|e| Error::Other(format!("Unexpected response from Bitbucket: {e}")))?;
+    Ok(created.links.html.href)
+}
+
+fn keychain_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| Error::Other(format!("Keychain unavailable: {e}")))
+}
+
+/// Stored Bitbucket username and app password
+fn bitbucket_credentials() -> Result<Option<(String, String)>> {
+    let stored = match keychain_entry()?.get_password() {
+        Ok(stored) => stored,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => {
+            return Err(Error::Other(format!(
+                "Failed to read Bitbucket credentials: {e}"
+            )))
+        }
+    };
+    Ok(stored
+        .split_once(':')
+        .map(|(username, password)| (username.to_string(), password.to_string())))
+}
+
+/// Store (or with `None`, remove) the Bitbucket username and app password
+pub fn set_bitbucket_credentials(credentials: Option<(&str, &str)>) -> Result<()> {
+    let entry = keychain_entry()?;
+    let result = match credentials {
+        Some((username, password)) => entry.set_password(&format!("{username}:{password}")),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            result => result,
+        },
+    };
+    result.map_err(|e| Error::Other(format!("Failed to store Bitbucket credentials: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(url: &str) -> ForgeRemote {
+        ForgeRemote::from_url(url).unwrap()
+    }
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let github = remote("git@github.com:octo/app.git");
+        assert_eq!(github.provider, ForgeProvider::Github);
+        assert_eq!(
+            (github.namespace.as_str(), github.repo.as_str()),
+            ("octo", "app")
+        );
+
+        let gitlab = remote("ssh://git@gitlab.example.com:2222/group/sub/app.git");
+        assert_eq!(gitlab.provider, ForgeProvider::Gitlab);
+        assert_eq!(gitlab.host, "gitlab.example.com");
+        assert_eq!(gitlab.namespace, "group/sub");
+
+        let bitbucket = remote("https://jo@bitbucket.org/team/app");
+        assert_eq!(bitbucket.provider, ForgeProvider::Bitbucket);
+        assert_eq!(bitbucket.web_url(), "https://bitbucket.org/team/app");
+
+        assert!(ForgeRemote::from_url("https://git.example.com/team/app.git").is_err());
+        assert!(ForgeRemote::from_url("/srv/git/app.git").is_err());
+    }
+
+    #[test]
+    fn test_web_urls() {
+        let github = remote("https://github.com/octo/app.git");
+        assert_eq!(
+            github.new_pull_request_url("main", "feat/x"),
+            "https://github.com/octo/app/compare/main...feat/x?expand=1"
+        );
+        assert_eq!(
+            github.branch_url("feat/a#b"),
+            "https://github.com/octo/app/tree/feat/a%23b"
+        );
+
+        let gitlab = remote("git@gitlab.com:group/app.git");
+        assert_eq!(
+            gitlab.new_pull_request_url("main", "feat/x"),
+            "https://gitlab.com/group/app/-/merge_requests/new\
+             ?merge_request%5Bsource_branch%5D=feat%2Fx&merge_request%5Btarget_branch%5D=main"
+        );
+
+        let bitbucket = remote("git@bitbucket.org:team/app.git");
+        assert_eq!(
+            bitbucket.new_pull_request_url("main", "fix"),
+            "https://bitbucket.org/team/app/pull-requests/new?source=fix&dest=main"
+        );
+    }
+
+    #[test]
+    fn test_merge_request_url() {
+        let stdout = "\nCreating merge request for fix into main in group/app\n\n\
+                      !12 Fix it (fix)\n https://gitlab.com/group/app/-/merge_requests/12\n";
+        assert_eq!(
+            merge_request_url(stdout).as_deref(),
+            Some("https://gitlab.com/group/app/-/merge_requests/12")
+        );
+        assert!(merge_request_url("nothing").is_none());
+    }
+}
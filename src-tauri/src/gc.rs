@@ -0,0 +1,109 @@
+//! Garbage collection for data left behind by deleted sessions and projects.
+//!
+//! Covers orphaned database rows (snapshots, session metadata, allowlist
+//! entries, search index rows) and image attachments written to the temp
+//! directory by earlier app runs.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::database::{Database, OrphanCounts, OrphanGroup};
+use crate::Result;
+
+/// Prefix of image attachments written by `save_temp_image`
+const TEMP_IMAGE_PREFIX: &str = "codex_image_";
+
+/// Attachments younger than this may still be in use by another instance
+const TEMP_IMAGE_MIN_AGE: Duration = Duration::from_secs(3600);
+
+/// What a GC run would remove (preview) or did remove (run)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub database: OrphanCounts,
+    pub attachments: OrphanGroup,
+    /// Approximate reclaimable bytes across all categories
+    pub total_bytes: u64,
+}
+
+impl GcReport {
+    fn new(database: OrphanCounts, attachments: OrphanGroup) -> Self {
+        let total_bytes = database.total_bytes() + attachments.bytes;
+        Self {
+            database,
+            attachments,
+            total_bytes,
+        }
+    }
+}
+
+/// Report reclaimable data without deleting anything
+pub fn preview(db: &Database) -> Result<GcReport> {
+    let attachments = orphaned_temp_images();
+    Ok(GcReport::new(db.orphan_counts()?, group(&attachments)))
+}
+
+/// Delete orphaned rows and stale attachments; returns what was removed
+pub fn run(db: &Database) -> Result<GcReport> {
+    let database = db.delete_orphans()?;
+
+    let mut attachments = OrphanGroup::default();
+    for (path, size) in orphaned_temp_images() {
+        if std::fs::remove_file(&path).is_ok() {
+            attachments.count += 1;
+            attachments.bytes += size;
+        }
+    }
+
+    let report = GcReport::new(database, attachments);
+    if report.total_bytes > 0 {
+        tracing::info!(
+            "GC removed {} snapshots, {} sessions, {} allowlist entries, {} index rows and {} attachments ({} bytes)",
+            report.database.snapshots.count,
+            report.database.sessions.count,
+            report.database.allowlist.count,
+            report.database.search_index.count,
+            report.attachments.count,
+            report.total_bytes
+        );
+    }
+    Ok(report)
+}
+
+fn group(files: &[(PathBuf, u64)]) -> OrphanGroup {
+    OrphanGroup {
+        count: files.len(),
+        bytes: files.iter().map(|(_, size)| size).sum(),
+    }
+}
+
+/// Image attachments from other processes older than an hour, with their sizes
+///
+/// Files are named `codex_image_<pid>_<timestamp>.<ext>`; files from the
+/// current process are never returned.
+pub fn orphaned_temp_images() -> Vec<(PathBuf, u64)> {
+    let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) else {
+        return Vec::new();
+    };
+    let current_pid = std::process::id();
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?;
+            let pid: u32 = name.strip_prefix(TEMP_IMAGE_PREFIX)?.split('_').next()?.parse().ok()?;
+            if pid == current_pid {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let age = metadata.modified().ok()?.elapsed().ok()?;
+            (age > TEMP_IMAGE_MIN_AGE).then(|| (path, metadata.len()))
+        })
+        .collect()
+}
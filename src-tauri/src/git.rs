@@ -0,0 +1,101 @@
+//! Running the git CLI and capturing what it prints.
+//!
+//! Every helper here is blocking; async callers go through
+//! `spawn_blocking_io`.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+use crate::{Error, Result};
+
+/// Run `git args` in `dir` and return its stdout; a non-zero exit is an
+/// error carrying git's stderr
+pub fn run(dir: &Path, args: &[&str]) -> Result<String> {
+    run_bytes(dir, args).map(|stdout| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// `run` with the output trimmed, for commands that print a single value
+pub fn value(dir: &Path, args: &[&str]) -> Result<String> {
+    run(dir, args).map(|stdout| stdout.trim().to_string())
+}
+
+/// `run` returning stdout as is, for file contents
+pub fn run_bytes(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    checked(output(dir, args)?, args)
+}
+
+/// `run` with git reading and writing `index` instead of the repository's
+/// own index file
+pub fn run_with_index(dir: &Path, index: &Path, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_INDEX_FILE", index);
+    let stdout = checked(spawn(command, dir, args)?, args)?;
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Run `git args` in `dir` whatever its exit status, for commands that
+/// answer through it like `check-ignore` or `diff --no-index`
+pub fn output(dir: &Path, args: &[&str]) -> Result<Output> {
+    let mut command = Command::new("git");
+    command.args(args).current_dir(dir);
+    spawn(command, dir, args)
+}
+
+/// A config value as git resolves it for `dir`; `None` when unset or empty
+pub fn config_value(dir: &Path, key: &str) -> Option<String> {
+    value(dir, &["config", "--get", key])
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+fn spawn(mut command: Command, dir: &Path, args: &[&str]) -> Result<Output> {
+    command.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound if dir.is_dir() => Error::GitNotFound,
+        _ => Error::Git(format!("Failed to run git {}: {e}", subcommand(args))),
+    })
+}
+
+fn checked(output: Output, args: &[&str]) -> Result<Vec<u8>> {
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Git(format!(
+            "git {} failed: {}",
+            subcommand(args),
+            stderr.trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+fn subcommand<'a>(args: &[&'a str]) -> &'a str {
+    args.first().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_reports_stderr_and_trims_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        run(dir, &["init", "-q", "-b", "main"]).unwrap();
+
+        assert_eq!(
+            value(dir, &["symbolic-ref", "--short", "HEAD"]).unwrap(),
+            "main"
+        );
+        let err = run(dir, &["rev-parse", "--verify", "HEAD"]).unwrap_err();
+        assert!(matches!(&err, Error::Git(message) if message.starts_with("git rev-parse failed")));
+
+        // `output` leaves the exit status to the caller
+        let ignored = output(dir, &["check-ignore", "-q", "--", "a.txt"]).unwrap();
+        assert_eq!(ignored.status.code(), Some(1));
+        run(dir, &["config", "core.eol", "lf"]).unwrap();
+        assert_eq!(config_value(dir, "core.eol").as_deref(), Some("lf"));
+        assert_eq!(config_value(dir, "core.autocrlf"), None);
+    }
+}
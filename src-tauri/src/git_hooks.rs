@@ -0,0 +1,283 @@
+//! Git hooks around commits and pushes made from the app.
+//!
+//! `list` finds the hooks a repository has installed, honoring
+//! `core.hooksPath`. `run_git` runs `git commit` or `git push` with stdin
+//! closed, so a hook that asks for input gets end-of-file instead of
+//! hanging, and emits everything the command and its hooks print as
+//! `git-hook-output` line by line. Skipping hooks with `--no-verify` is
+//! opt-in per call and recorded in the database as a `HookBypass`.
+
+use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::events::AppEventEmitter;
+use crate::git;
+use crate::terminal_output::TerminalStream;
+use crate::{Error, Result};
+
+/// A commit or push is killed after this long, hooks included
+const GIT_HOOK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// What a hook runs around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookOperation {
+    Commit,
+    Push,
+}
+
+impl HookOperation {
+    /// Hooks git runs for this operation
+    pub fn hook_names(self) -> &'static [&'static str] {
+        match self {
+            Self::Commit => &["pre-commit", "prepare-commit-msg", "commit-msg", "post-commit"],
+            Self::Push => &["pre-push"],
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Commit => "commit",
+            Self::Push => "push",
+        }
+    }
+
+    fn of_hook(name: &str) -> Option<Self> {
+        [Self::Commit, Self::Push]
+            .into_iter()
+            .find(|operation| operation.hook_names().contains(&name))
+    }
+}
+
+/// An installed hook
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHook {
+    /// e.g. "pre-commit"
+    pub name: String,
+    pub path: String,
+    /// Git skips hooks without the executable bit
+    pub executable: bool,
+    /// Commits or pushes this hook runs for, if either
+    pub operation: Option<HookOperation>,
+    /// From the `#!` line
+    pub interpreter: Option<String>,
+    /// The script reads from `/dev/tty`, so it may wait for an answer
+    pub reads_terminal: bool,
+}
+
+/// Hooks of a repository
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHooks {
+    pub hooks_dir: String,
+    /// `core.hooksPath` points somewhere else than `.git/hooks`
+    pub custom_hooks_path: bool,
+    /// Sorted by name; `.sample` files are left out
+    pub hooks: Vec<GitHook>,
+}
+
+impl GitHooks {
+    /// Names of the hooks that will run for `operation`
+    pub fn active_for(&self, operation: HookOperation) -> Vec<String> {
+        self.hooks
+            .iter()
+            .filter(|hook| hook.executable && hook.operation == Some(operation))
+            .map(|hook| hook.name.clone())
+            .collect()
+    }
+}
+
+/// Hooks installed in the repository at `dir`. Blocking.
+pub fn list(dir: &Path) -> Result<GitHooks> {
+    let hooks_dir = git::run(dir, &["rev-parse", "--git-path", "hooks"])?;
+    let hooks_dir = dir.join(hooks_dir.trim());
+    let custom_hooks_path = git::run(dir, &["config", "--get", "core.hooksPath"])
+        .is_ok_and(|value| !value.trim().is_empty());
+
+    let mut hooks = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&hooks_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if name.ends_with(".sample") || name.starts_with('.') || !path.is_file() {
+                continue;
+            }
+            hooks.push(describe(name, &path));
+        }
+    }
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(GitHooks {
+        hooks_dir: hooks_dir.to_string_lossy().into_owned(),
+        custom_hooks_path,
+        hooks,
+    })
+}
+
+fn describe(name: &str, path: &Path) -> GitHook {
+    // Only the start matters; hooks are small scripts or binaries
+    let head = std::fs::read(path)
+        .map(|bytes| String::from_utf8_lossy(&bytes[..bytes.len().min(64 * 1024)]).into_owned())
+        .unwrap_or_default();
+    let interpreter = head
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("#!"))
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty());
+    GitHook {
+        name: name.to_string(),
+        path: path.to_string_lossy().into_owned(),
+        executable: is_executable(path),
+        operation: HookOperation::of_hook(name),
+        interpreter,
+        reads_terminal: head.contains("/dev/tty"),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+/// What a streamed git command printed
+#[derive(Debug)]
+pub struct GitRun {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Run git in `dir` with stdin closed, emitting each output line as
+/// `git-hook-output` as it arrives
+pub async fn run_git(
+    dir: &Path,
+    args: &[&str],
+    operation: HookOperation,
+    events: &AppEventEmitter,
+) -> Result<GitRun> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| Error::Git(format!("Failed to run git {}: {err}", args[0])))?;
+
+    let path = dir.to_string_lossy().into_owned();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let result = tokio::time::timeout(GIT_HOOK_TIMEOUT, async {
+        let (stdout, stderr) = tokio::join!(
+            forward(stdout, TerminalStream::Stdout, &path, operation, events),
+            forward(stderr, TerminalStream::Stderr, &path, operation, events),
+        );
+        child.wait().await.map(|status| GitRun {
+            status,
+            stdout,
+            stderr,
+        })
+    })
+    .await;
+
+    match result {
+        Ok(Ok(run)) => Ok(run),
+        Ok(Err(err)) => Err(Error::Git(format!("Failed to wait for git {}: {err}", args[0]))),
+        Err(_) => {
+            let _ = child.kill().await;
+            Err(Error::Timeout(format!(
+                "git {} did not finish within {} minutes; a hook may be waiting for input. \
+                 Skip the hooks to {} without them.",
+                args[0],
+                GIT_HOOK_TIMEOUT.as_secs() / 60,
+                operation.as_str()
+            )))
+        }
+    }
+}
+
+/// Emit each line of `reader` and return all of it
+async fn forward<R: AsyncRead + Unpin>(
+    reader: Option<R>,
+    stream: TerminalStream,
+    path: &str,
+    operation: HookOperation,
+    events: &AppEventEmitter,
+) -> String {
+    let Some(reader) = reader else {
+        return String::new();
+    };
+    let mut output = String::new();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        events
+            .emit(
+                "git-hook-output",
+                json!({ "path": path, "operation": operation, "stream": stream, "line": line }),
+            )
+            .await;
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_list_hooks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        git::run(temp_dir.path(), &["init", "-q"]).unwrap();
+        let hooks_dir = PathBuf::from(list(temp_dir.path()).unwrap().hooks_dir);
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        std::fs::write(
+            hooks_dir.join("pre-commit"),
+            "#!/bin/sh\nexec < /dev/tty\nread answer\n",
+        )
+        .unwrap();
+        std::fs::write(hooks_dir.join("pre-push"), "#!/usr/bin/env bash\nexit 0\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let executable = std::fs::Permissions::from_mode(0o755);
+            std::fs::set_permissions(hooks_dir.join("pre-commit"), executable).unwrap();
+        }
+
+        let hooks = list(temp_dir.path()).unwrap();
+        assert!(!hooks.custom_hooks_path);
+        let names: Vec<_> = hooks.hooks.iter().map(|hook| hook.name.as_str()).collect();
+        assert_eq!(names, vec!["pre-commit", "pre-push"]);
+        let pre_commit = &hooks.hooks[0];
+        assert_eq!(pre_commit.operation, Some(HookOperation::Commit));
+        assert_eq!(pre_commit.interpreter.as_deref(), Some("/bin/sh"));
+        assert!(pre_commit.reads_terminal);
+        assert_eq!(hooks.hooks[1].operation, Some(HookOperation::Push));
+        assert!(!hooks.hooks[1].reads_terminal);
+
+        if cfg!(unix) {
+            assert_eq!(hooks.active_for(HookOperation::Commit), vec!["pre-commit"]);
+            assert!(hooks.active_for(HookOperation::Push).is_empty());
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! System-wide keyboard shortcuts.
+//!
+//! One plugin serves every shortcut the app holds: the approval shortcuts
+//! (see `approval_shortcuts`) and the summon shortcuts that bring the main
+//! window to the front or open the quick prompt palette (see
+//! `quick_prompt`). The plugin can only release everything at once, so
+//! `register` always replaces the whole set from both settings sections,
+//! and `validate` checks them together so no accelerator is bound twice.
+
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcut, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::approval_shortcuts::{self, ShortcutDecision, ShortcutOutcome};
+use crate::global_state::{ApprovalShortcutSettings, SummonShortcutSettings};
+use crate::quick_prompt;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// What a shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShortcutAction {
+    Answer(ShortcutDecision),
+    ShowWindow,
+    QuickPrompt,
+}
+
+/// The enabled accelerators of both sections with their actions
+fn bindings<'a>(
+    approvals: &'a ApprovalShortcutSettings,
+    summon: &'a SummonShortcutSettings,
+) -> Vec<(&'a str, ShortcutAction)> {
+    use ShortcutAction::*;
+
+    let mut configured = Vec::new();
+    if approvals.enabled {
+        configured.push((&approvals.approve, Answer(ShortcutDecision::Accept)));
+        configured.push((&approvals.deny, Answer(ShortcutDecision::Decline)));
+    }
+    if summon.enabled {
+        configured.push((&summon.show_window, ShowWindow));
+        configured.push((&summon.quick_prompt, QuickPrompt));
+    }
+    configured
+        .into_iter()
+        .filter_map(|(accelerator, action)| Some((accelerator.as_deref()?, action)))
+        .collect()
+}
+
+/// The global shortcut plugin, with presses routed to `on_shortcut`
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(on_shortcut)
+        .build()
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut> {
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| Error::Other(format!("Invalid shortcut \"{accelerator}\": {e}")))
+}
+
+/// Check that every configured shortcut parses and that none is used twice,
+/// disabled sections included, so enabling one later cannot clash
+pub fn validate(
+    approvals: &ApprovalShortcutSettings,
+    summon: &SummonShortcutSettings,
+) -> Result<()> {
+    let approvals = ApprovalShortcutSettings {
+        enabled: true,
+        ..approvals.clone()
+    };
+    let summon = SummonShortcutSettings {
+        enabled: true,
+        ..summon.clone()
+    };
+    let mut seen: Vec<(u32, &str)> = Vec::new();
+    for (accelerator, _) in bindings(&approvals, &summon) {
+        let id = parse(accelerator)?.id();
+        if let Some((_, other)) = seen.iter().find(|(seen_id, _)| *seen_id == id) {
+            return Err(Error::Other(format!(
+                "The shortcuts \"{other}\" and \"{accelerator}\" are the same"
+            )));
+        }
+        seen.push((id, accelerator));
+    }
+    Ok(())
+}
+
+/// Replace the registered shortcuts with those the settings enable
+pub fn register(
+    app: &AppHandle,
+    approvals: &ApprovalShortcutSettings,
+    summon: &SummonShortcutSettings,
+) -> Result<()> {
+    // The plugin is left out in safe mode
+    let Some(shortcuts) = app.try_state::<GlobalShortcut<Wry>>() else {
+        return Err(Error::Other(
+            "Global shortcuts are unavailable in safe mode".to_string(),
+        ));
+    };
+    shortcuts
+        .unregister_all()
+        .map_err(|e| Error::Other(format!("Failed to release shortcuts: {e}")))?;
+    let bindings = bindings(approvals, summon);
+    for (accelerator, _) in &bindings {
+        shortcuts.register(parse(accelerator)?).map_err(|e| {
+            Error::Other(format!(
+                "Failed to register shortcut \"{accelerator}\" (another app may use it): {e}"
+            ))
+        })?;
+    }
+    tracing::info!("Registered {} global shortcuts", bindings.len());
+    Ok(())
+}
+
+/// `register` with the saved settings
+pub fn register_saved(app: &AppHandle) -> Result<()> {
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(());
+    };
+    let (approvals, summon) = state.global_state.read(|global| {
+        (
+            global.approval_shortcuts.clone(),
+            global.summon_shortcuts.clone(),
+        )
+    });
+    register(app, &approvals, &summon)
+}
+
+fn on_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let (approvals, summon) = state.global_state.read(|global| {
+        (
+            global.approval_shortcuts.clone(),
+            global.summon_shortcuts.clone(),
+        )
+    });
+    let Some(action) = bindings(&approvals, &summon)
+        .into_iter()
+        .find(|(accelerator, _)| {
+            accelerator
+                .parse::<Shortcut>()
+                .is_ok_and(|configured| configured.id() == shortcut.id())
+        })
+        .map(|(_, action)| action)
+    else {
+        return;
+    };
+
+    if action == ShortcutAction::ShowWindow {
+        quick_prompt::show_main_window(app);
+        return;
+    }
+    let app = app.clone();
+    let confirm = approvals.confirm_notification;
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let result = match action {
+            ShortcutAction::Answer(decision) => {
+                approval_shortcuts::answer_latest(&state, decision, confirm)
+                    .await
+                    .map(|outcome| match outcome {
+                        ShortcutOutcome::NothingPending => {
+                            tracing::debug!("Approval shortcut pressed with nothing pending");
+                        }
+                        ShortcutOutcome::NeedsReview(approval) => {
+                            tracing::info!(
+                                "Not accepting approval request {} by shortcut: it needs review",
+                                approval.request_id
+                            );
+                            quick_prompt::show_main_window(&app);
+                        }
+                        ShortcutOutcome::Answered(_) => {}
+                    })
+            }
+            _ => quick_prompt::open(&app).await,
+        };
+        if let Err(err) = result {
+            tracing::warn!("Global shortcut {:?} failed: {}", action, err);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let approvals = ApprovalShortcutSettings::default();
+        let mut summon = SummonShortcutSettings::default();
+        assert!(validate(&approvals, &summon).is_ok());
+
+        // Disabled sections still count
+        summon.quick_prompt = approvals.approve.clone();
+        assert!(validate(&approvals, &summon).is_err());
+
+        summon.quick_prompt = Some("Not+A+Key".to_string());
+        assert!(validate(&approvals, &summon).is_err());
+    }
+
+    #[test]
+    fn test_bindings_skip_disabled_sections() {
+        let approvals = ApprovalShortcutSettings::default();
+        let summon = SummonShortcutSettings {
+            enabled: true,
+            show_window: None,
+            ..SummonShortcutSettings::default()
+        };
+        assert_eq!(
+            bindings(&approvals, &summon),
+            vec![("CommandOrControl+Alt+Space", ShortcutAction::QuickPrompt)]
+        );
+    }
+}
@@ -1,15 +1,54 @@
 //! Global persistent state store (JSON with atomic writes).
+//!
+//! Every subsystem keeps its settings in a typed section of one file rather
+//! than a JSON file of its own. Sections carry their own version so they can
+//! be migrated independently, and fields this build does not know (written
+//! by a newer build) are kept through a load/save round trip.
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 
+use crate::i18n::Locale;
 use crate::Result;
 
-const STATE_VERSION: u32 = 2;
+const STATE_VERSION: u32 = 3;
+
+/// Sections holding user settings that make sense on another machine, keyed
+/// as in the state file. Runtime state, window placement and the app-server
+/// launch settings (local paths, possibly secrets in `env`) stay behind.
+pub const PORTABLE_SECTIONS: &[&str] = &[
+    "backendHeartbeat",
+    "rateLimits",
+    "rpcTrace",
+    "snapshots",
+    "turnConcurrency",
+    "turnBudget",
+    "modelProviders",
+    "attachmentBudget",
+    "redaction",
+    "secretMasking",
+    "terminalOutput",
+    "tray",
+    "notifications",
+    "approvalShortcuts",
+    "summonShortcuts",
+    "locale",
+];
+
+const WINDOW_LAYOUT_VERSION: u32 = 1;
+const TRAY_VERSION: u32 = 1;
+const SCHEDULER_VERSION: u32 = 1;
+const NOTIFICATIONS_VERSION: u32 = 1;
+
+/// Fields not known to this build, kept so saving does not drop them
+pub type UnknownFields = BTreeMap<String, JsonValue>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
@@ -17,6 +56,10 @@ pub struct AppServerState {
     pub restart_count: u64,
     pub last_restart_at: Option<i64>,
     pub last_disconnect_reason: Option<String>,
+    /// Stops while idle, not counted as restarts
+    #[serde(default)]
+    pub idle_stop_count: u64,
+    pub last_idle_stop_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -33,6 +76,529 @@ pub struct RendererState {
 pub struct StartupState {
     pub app_started_at_ms: Option<i64>,
     pub renderer_ready_latency_ms: Option<u64>,
+    /// Set at launch and cleared once the app has stayed up past the crash window
+    pub unstable_since_ms: Option<i64>,
+    /// Launches in a row that ended within the crash window
+    pub consecutive_failed_startups: u32,
+}
+
+/// Settings for the periodic `backend-heartbeat` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackendHeartbeatSettings {
+    pub enabled: bool,
+    pub interval_secs: u64,
+}
+
+impl Default for BackendHeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 10,
+        }
+    }
+}
+
+/// Thresholds for rate-limit warnings and holding back new turns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RateLimitSettings {
+    /// Queue `send_message` turns while a window is above `queue_percent`
+    pub queue_when_limited: bool,
+    pub warning_percent: f64,
+    pub queue_percent: f64,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            queue_when_limited: false,
+            warning_percent: 80.0,
+            queue_percent: 95.0,
+        }
+    }
+}
+
+/// How the `codex app-server` process is launched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppServerLaunchSettings {
+    /// Absolute path of the codex binary; `None` searches PATH
+    pub codex_path: Option<String>,
+    /// Required version ("0.46" or "0.46.1"); spawning another version fails
+    pub pinned_version: Option<String>,
+    /// Appended after `app-server`
+    pub extra_args: Vec<String>,
+    /// Set in addition to the app's own environment
+    pub env: BTreeMap<String, String>,
+    /// Stop the app-server after this many minutes without turns, approvals
+    /// or requests; it starts again on the next thread command. `None` or 0
+    /// keeps it running.
+    pub idle_shutdown_mins: Option<u32>,
+}
+
+/// Recording of app-server JSON-RPC traffic for the protocol inspector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RpcTraceSettings {
+    pub enabled: bool,
+    /// Messages kept in memory
+    pub capacity: usize,
+    /// Also append messages to `logs/rpc-trace.jsonl`
+    pub log_to_file: bool,
+}
+
+impl Default for RpcTraceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: 2000,
+            log_to_file: false,
+        }
+    }
+}
+
+/// Limits on turns running at the same time across threads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TurnConcurrencySettings {
+    /// Turns running at once across all threads; 0 means unlimited
+    pub max_concurrent_turns: u32,
+    /// Turns running at once within one project; 0 means unlimited
+    pub max_turns_per_project: u32,
+}
+
+impl Default for TurnConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_turns: 4,
+            max_turns_per_project: 0,
+        }
+    }
+}
+
+/// Ceiling on what one turn may spend before it is interrupted
+///
+/// As a settings section it is the default for every turn; `send_message`
+/// can pass one for a single turn instead. Unset limits do not apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TurnBudget {
+    /// Tokens (input plus output) across the turn's model calls
+    pub max_tokens: Option<u64>,
+    /// Estimated USD cost; only enforced for models with known pricing
+    pub max_cost_usd: Option<f64>,
+}
+
+impl TurnBudget {
+    pub fn is_limited(&self) -> bool {
+        self.max_tokens.is_some() || self.max_cost_usd.is_some()
+    }
+}
+
+/// An OpenAI-compatible endpoint configured as a Codex model provider
+///
+/// Written to the Codex config as `model_providers.<id>`; the API key lives
+/// in the OS keychain and reaches the app-server through `env_key`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CustomModelProvider {
+    /// Key under `model_providers`; lowercase letters, digits, `-` and `_`
+    pub id: String,
+    pub name: String,
+    /// Endpoint root, e.g. "http://localhost:11434/v1"
+    pub base_url: String,
+    /// "chat" (Chat Completions) or "responses"
+    pub wire_api: String,
+    /// Models offered for this provider; the first is the default
+    pub models: Vec<String>,
+    /// Whether threads may only start once an API key is stored
+    pub requires_api_key: bool,
+}
+
+impl Default for CustomModelProvider {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            base_url: String::new(),
+            wire_api: "chat".to_string(),
+            models: Vec::new(),
+            requires_api_key: true,
+        }
+    }
+}
+
+/// Custom model providers, in display order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelProviderSettings {
+    pub providers: Vec<CustomModelProvider>,
+}
+
+/// Safety nets around snapshot reverts, and how long snapshots are kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapshotSettings {
+    /// Snapshot the current state before every revert so it can be undone
+    pub backup_before_revert: bool,
+    /// Most recent snapshots kept per session; 0 keeps all
+    pub max_per_session: usize,
+    /// Snapshots older than this are deleted
+    pub max_age_days: Option<u32>,
+    /// Oldest snapshots are deleted while all of them together take more
+    pub max_disk_bytes: Option<u64>,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            backup_before_revert: true,
+            max_per_session: 10,
+            max_age_days: None,
+            max_disk_bytes: None,
+        }
+    }
+}
+
+/// What to do with an attachment larger than the per-file budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum OversizePolicy {
+    /// Keep the start and end of the file
+    #[default]
+    Truncate,
+    /// Keep an outline of the file's declarations
+    Summarize,
+    /// Leave the file out
+    Omit,
+}
+
+/// Token budget for files attached to a prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AttachmentBudgetSettings {
+    /// Estimated tokens allowed across all attachments of one prompt
+    pub max_total_tokens: u32,
+    /// Estimated tokens allowed for a single attachment
+    pub max_file_tokens: u32,
+    pub oversize_policy: OversizePolicy,
+}
+
+impl Default for AttachmentBudgetSettings {
+    fn default() -> Self {
+        Self {
+            max_total_tokens: 32_000,
+            max_file_tokens: 8_000,
+            oversize_policy: OversizePolicy::Truncate,
+        }
+    }
+}
+
+/// What is masked in session exports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RedactionSettings {
+    /// Redact exports unless the export asks otherwise
+    pub enabled: bool,
+    /// Mask API keys, tokens, passwords and private keys
+    pub mask_secrets: bool,
+    /// Replace the project directory with `<project>` and the home
+    /// directory with `~`
+    pub mask_paths: bool,
+    /// Replace the local user name and `usernames` with `[user]`
+    pub mask_usernames: bool,
+    pub usernames: Vec<String>,
+    /// Regular expressions whose matches are replaced with `[redacted]`
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mask_secrets: true,
+            mask_paths: true,
+            mask_usernames: true,
+            usernames: Vec::new(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+/// Masking of secrets in logs, renderer events and stored session text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SecretMaskingSettings {
+    pub enabled: bool,
+    /// Regular expressions masked besides the built-in credential patterns
+    pub custom_patterns: Vec<String>,
+}
+
+impl Default for SecretMaskingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            custom_patterns: Vec::new(),
+        }
+    }
+}
+
+/// What terminal command output looks like by the time it reaches the
+/// renderer; the scrollback keeps whole lines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalOutputSettings {
+    /// Longer lines are cut to this many characters in terminal events
+    pub max_line_chars: u32,
+    /// Pass output through secret masking, scrollback included
+    pub redact_secrets: bool,
+}
+
+impl Default for TerminalOutputSettings {
+    fn default() -> Self {
+        Self {
+            max_line_chars: 4_000,
+            redact_secrets: true,
+        }
+    }
+}
+
+/// Position and size of a window, in logical pixels
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Name of the monitor the window was on
+    pub monitor: Option<String>,
+}
+
+/// Window placement restored on the next launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WindowLayoutState {
+    pub version: u32,
+    /// Keyed by window label
+    pub windows: BTreeMap<String, WindowGeometry>,
+    pub sidebar_width: Option<u32>,
+    pub sidebar_collapsed: bool,
+    #[serde(flatten)]
+    pub unknown: UnknownFields,
+}
+
+impl Default for WindowLayoutState {
+    fn default() -> Self {
+        Self {
+            version: WINDOW_LAYOUT_VERSION,
+            windows: BTreeMap::new(),
+            sidebar_width: None,
+            sidebar_collapsed: false,
+            unknown: UnknownFields::new(),
+        }
+    }
+}
+
+/// System tray / menu bar presence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TraySettings {
+    pub version: u32,
+    pub enabled: bool,
+    /// Closing the main window hides it to the tray instead of quitting
+    pub close_to_tray: bool,
+    /// Show the number of pending approvals on the tray icon
+    pub show_pending_count: bool,
+    #[serde(flatten)]
+    pub unknown: UnknownFields,
+}
+
+impl Default for TraySettings {
+    fn default() -> Self {
+        Self {
+            version: TRAY_VERSION,
+            enabled: true,
+            close_to_tray: false,
+            show_pending_count: true,
+            unknown: UnknownFields::new(),
+        }
+    }
+}
+
+/// Persistent state of the task scheduler
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SchedulerState {
+    pub version: u32,
+    /// Scheduled work is held back while paused
+    pub paused: bool,
+    /// Unix seconds of each job's last run, keyed by job id
+    pub last_runs: BTreeMap<String, i64>,
+    /// Projects whose turn queue is held back, e.g. after a failed entry
+    pub paused_projects: BTreeSet<String>,
+    #[serde(flatten)]
+    pub unknown: UnknownFields,
+}
+
+impl Default for SchedulerState {
+    fn default() -> Self {
+        Self {
+            version: SCHEDULER_VERSION,
+            paused: false,
+            last_runs: BTreeMap::new(),
+            paused_projects: BTreeSet::new(),
+            unknown: UnknownFields::new(),
+        }
+    }
+}
+
+/// Which native notifications to show; see `notifications`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub version: u32,
+    pub enabled: bool,
+    pub turn_completed: bool,
+    pub approval_requested: bool,
+    /// Turns that failed
+    pub errors: bool,
+    /// The app server exited unexpectedly
+    pub app_server_crashed: bool,
+    /// Suppress notifications while the app window is focused
+    pub only_when_unfocused: bool,
+    pub sound: bool,
+    /// Nothing is shown between these times
+    pub do_not_disturb: Option<QuietHours>,
+    #[serde(flatten)]
+    pub unknown: UnknownFields,
+}
+
+/// Local times as "HH:MM"; a range with `end` before `start` spans
+/// midnight
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            version: NOTIFICATIONS_VERSION,
+            enabled: true,
+            turn_completed: true,
+            approval_requested: true,
+            errors: true,
+            app_server_crashed: true,
+            only_when_unfocused: true,
+            sound: false,
+            do_not_disturb: None,
+            unknown: UnknownFields::new(),
+        }
+    }
+}
+
+/// Limits on background workloads; see `background_tasks`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackgroundTaskSettings {
+    /// Hold every background task until resumed
+    pub paused: bool,
+    /// One task at a time, with loop intervals stretched
+    pub cpu_friendly: bool,
+    /// Tasks doing work at once; 0 means unlimited
+    pub max_concurrent: usize,
+    /// Ids of tasks turned off
+    pub disabled: Vec<String>,
+}
+
+impl Default for BackgroundTaskSettings {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            cpu_friendly: false,
+            max_concurrent: 2,
+            disabled: Vec::new(),
+        }
+    }
+}
+
+/// Passphrase lock; see `app_lock`. The passphrase hash itself is kept in
+/// the keychain, so the section stays on this machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppLockSettings {
+    /// A passphrase is set; changed only with the passphrase
+    pub enabled: bool,
+    pub lock_on_launch: bool,
+    /// Lock after this many minutes without user activity; `None` never
+    pub idle_timeout_mins: Option<u32>,
+}
+
+impl Default for AppLockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lock_on_launch: true,
+            idle_timeout_mins: Some(15),
+        }
+    }
+}
+
+/// System-wide shortcuts that answer the newest pending approval; see
+/// `approval_shortcuts`. Accelerators read like "CommandOrControl+Alt+Shift+Y".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApprovalShortcutSettings {
+    pub enabled: bool,
+    pub approve: Option<String>,
+    pub deny: Option<String>,
+    /// Show a notification saying what a shortcut answered
+    pub confirm_notification: bool,
+}
+
+impl Default for ApprovalShortcutSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            approve: Some("CommandOrControl+Alt+Shift+Y".to_string()),
+            deny: Some("CommandOrControl+Alt+Shift+N".to_string()),
+            confirm_notification: true,
+        }
+    }
+}
+
+/// System-wide shortcuts that bring the main window to the front or open
+/// the quick prompt palette; see `quick_prompt`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SummonShortcutSettings {
+    pub enabled: bool,
+    pub show_window: Option<String>,
+    /// Also shows the window, then asks the renderer for the palette
+    pub quick_prompt: Option<String>,
+}
+
+impl Default for SummonShortcutSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            show_window: Some("CommandOrControl+Shift+Space".to_string()),
+            quick_prompt: Some("CommandOrControl+Alt+Space".to_string()),
+        }
+    }
+}
+
+/// Language of backend text the renderer shows; see `i18n`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocaleSettings {
+    pub locale: Locale,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +608,29 @@ pub struct GlobalStateFile {
     pub app_server: AppServerState,
     pub renderer: RendererState,
     pub startup: StartupState,
+    pub backend_heartbeat: BackendHeartbeatSettings,
+    pub rate_limits: RateLimitSettings,
+    pub rpc_trace: RpcTraceSettings,
+    pub app_server_launch: AppServerLaunchSettings,
+    pub snapshots: SnapshotSettings,
+    pub turn_concurrency: TurnConcurrencySettings,
+    pub turn_budget: TurnBudget,
+    pub model_providers: ModelProviderSettings,
+    pub attachment_budget: AttachmentBudgetSettings,
+    pub redaction: RedactionSettings,
+    pub secret_masking: SecretMaskingSettings,
+    pub terminal_output: TerminalOutputSettings,
+    pub window_layout: WindowLayoutState,
+    pub tray: TraySettings,
+    pub scheduler: SchedulerState,
+    pub notifications: NotificationSettings,
+    pub background_tasks: BackgroundTaskSettings,
+    pub app_lock: AppLockSettings,
+    pub approval_shortcuts: ApprovalShortcutSettings,
+    pub summon_shortcuts: SummonShortcutSettings,
+    pub locale: LocaleSettings,
+    #[serde(flatten)]
+    pub unknown: UnknownFields,
 }
 
 impl Default for GlobalStateFile {
@@ -51,10 +640,76 @@ impl Default for GlobalStateFile {
             app_server: AppServerState::default(),
             renderer: RendererState::default(),
             startup: StartupState::default(),
+            backend_heartbeat: BackendHeartbeatSettings::default(),
+            rate_limits: RateLimitSettings::default(),
+            rpc_trace: RpcTraceSettings::default(),
+            app_server_launch: AppServerLaunchSettings::default(),
+            snapshots: SnapshotSettings::default(),
+            turn_concurrency: TurnConcurrencySettings::default(),
+            turn_budget: TurnBudget::default(),
+            model_providers: ModelProviderSettings::default(),
+            attachment_budget: AttachmentBudgetSettings::default(),
+            redaction: RedactionSettings::default(),
+            secret_masking: SecretMaskingSettings::default(),
+            terminal_output: TerminalOutputSettings::default(),
+            window_layout: WindowLayoutState::default(),
+            tray: TraySettings::default(),
+            scheduler: SchedulerState::default(),
+            notifications: NotificationSettings::default(),
+            background_tasks: BackgroundTaskSettings::default(),
+            app_lock: AppLockSettings::default(),
+            approval_shortcuts: ApprovalShortcutSettings::default(),
+            summon_shortcuts: SummonShortcutSettings::default(),
+            locale: LocaleSettings::default(),
+            unknown: UnknownFields::new(),
         }
     }
 }
 
+impl GlobalStateFile {
+    /// The `PORTABLE_SECTIONS` of this state
+    pub fn portable_sections(&self) -> Result<Map<String, JsonValue>> {
+        let JsonValue::Object(mut object) = serde_json::to_value(self)? else {
+            return Ok(Map::new());
+        };
+        object.retain(|key, _| PORTABLE_SECTIONS.contains(&key.as_str()));
+        Ok(object)
+    }
+
+    /// Replace portable sections with those in `sections`; returns the keys
+    /// applied
+    ///
+    /// Other keys are ignored. Nothing is applied if any section is
+    /// unreadable.
+    pub fn apply_portable_sections(
+        &mut self,
+        sections: &Map<String, JsonValue>,
+    ) -> Result<Vec<String>> {
+        let incoming: Map<String, JsonValue> = sections
+            .iter()
+            .filter(|(key, _)| PORTABLE_SECTIONS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let invalid = invalid_sections(&incoming);
+        if !invalid.is_empty() {
+            return Err(crate::Error::Other(format!(
+                "Invalid settings sections: {}",
+                invalid.join(", ")
+            )));
+        }
+
+        let applied = incoming.keys().cloned().collect();
+        let mut raw = serde_json::to_value(&*self)?;
+        if let Some(object) = raw.as_object_mut() {
+            object.extend(incoming);
+        }
+        // Imported sections may carry older section versions
+        let (state, _) = GlobalStateStore::migrate(raw)?;
+        *self = state;
+        Ok(applied)
+    }
+}
+
 pub struct GlobalStateStore {
     path: PathBuf,
     state: Mutex<GlobalStateFile>,
@@ -65,11 +720,11 @@ impl GlobalStateStore {
     pub fn load(path: PathBuf) -> Result<Self> {
         let (state, migrated) = if path.exists() {
             match std::fs::read_to_string(&path) {
-                Ok(contents) => match serde_json::from_str::<GlobalStateFile>(&contents) {
-                    Ok(parsed) => {
-                        let (migrated_state, changed) = Self::migrate(parsed);
-                        (migrated_state, changed)
-                    }
+                Ok(contents) => match serde_json::from_str::<JsonValue>(&contents)
+                    .map_err(Into::into)
+                    .and_then(Self::migrate)
+                {
+                    Ok((migrated_state, changed)) => (migrated_state, changed),
                     Err(err) => {
                         let corrupt = path.with_extension("corrupt");
                         let _ = std::fs::rename(&path, &corrupt);
@@ -97,13 +752,48 @@ impl GlobalStateStore {
         })
     }
 
-    fn migrate(mut state: GlobalStateFile) -> (GlobalStateFile, bool) {
+    /// Read a state file without repairing it: a missing file gives the
+    /// defaults, and one that cannot be parsed is an error and left in place
+    pub fn read_file(path: &Path) -> Result<GlobalStateFile> {
+        if !path.exists() {
+            return Ok(GlobalStateFile::default());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        let (state, _) = Self::migrate(serde_json::from_str(&contents)?)?;
+        Ok(state)
+    }
+
+    /// Parse a state file and bring it up to the current version
+    ///
+    /// A section this build cannot read (e.g. one a newer build changed the
+    /// shape of) is reset to its defaults instead of discarding the file.
+    /// Versions are never lowered, so a file from a newer build keeps
+    /// telling that build which migrations it already ran.
+    fn migrate(mut raw: JsonValue) -> Result<(GlobalStateFile, bool)> {
         let mut changed = false;
-        if state.version < STATE_VERSION {
-            state.version = STATE_VERSION;
-            changed = true;
+        if let Some(object) = raw.as_object_mut() {
+            for section in invalid_sections(object) {
+                tracing::warn!("Resetting unreadable global state section: {}", section);
+                object.remove(&section);
+                changed = true;
+            }
         }
-        (state, changed)
+
+        let mut state: GlobalStateFile = serde_json::from_value(raw)?;
+        changed |= raise_version(&mut state.version, STATE_VERSION);
+        changed |= raise_version(&mut state.window_layout.version, WINDOW_LAYOUT_VERSION);
+        changed |= raise_version(&mut state.tray.version, TRAY_VERSION);
+        changed |= raise_version(&mut state.scheduler.version, SCHEDULER_VERSION);
+        changed |= raise_version(&mut state.notifications.version, NOTIFICATIONS_VERSION);
+        Ok((state, changed))
+    }
+
+    /// Read a value from the current state without marking it dirty
+    pub fn read<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&GlobalStateFile) -> T,
+    {
+        f(&self.state.lock().unwrap())
     }
 
     pub fn update<F>(&self, f: F)
@@ -147,6 +837,57 @@ impl Drop for GlobalStateStore {
     }
 }
 
+fn raise_version(version: &mut u32, current: u32) -> bool {
+    if *version < current {
+        *version = current;
+        true
+    } else {
+        false
+    }
+}
+
+/// Keys of sections in `object` that do not deserialize as their type
+fn invalid_sections(object: &Map<String, JsonValue>) -> Vec<String> {
+    fn check<T: DeserializeOwned>(
+        object: &Map<String, JsonValue>,
+        key: &str,
+        invalid: &mut Vec<String>,
+    ) {
+        if let Some(value) = object.get(key) {
+            if serde_json::from_value::<T>(value.clone()).is_err() {
+                invalid.push(key.to_string());
+            }
+        }
+    }
+
+    let mut invalid = Vec::new();
+    check::<AppServerState>(object, "appServer", &mut invalid);
+    check::<RendererState>(object, "renderer", &mut invalid);
+    check::<StartupState>(object, "startup", &mut invalid);
+    check::<BackendHeartbeatSettings>(object, "backendHeartbeat", &mut invalid);
+    check::<RateLimitSettings>(object, "rateLimits", &mut invalid);
+    check::<RpcTraceSettings>(object, "rpcTrace", &mut invalid);
+    check::<AppServerLaunchSettings>(object, "appServerLaunch", &mut invalid);
+    check::<SnapshotSettings>(object, "snapshots", &mut invalid);
+    check::<TurnConcurrencySettings>(object, "turnConcurrency", &mut invalid);
+    check::<TurnBudget>(object, "turnBudget", &mut invalid);
+    check::<ModelProviderSettings>(object, "modelProviders", &mut invalid);
+    check::<AttachmentBudgetSettings>(object, "attachmentBudget", &mut invalid);
+    check::<RedactionSettings>(object, "redaction", &mut invalid);
+    check::<SecretMaskingSettings>(object, "secretMasking", &mut invalid);
+    check::<TerminalOutputSettings>(object, "terminalOutput", &mut invalid);
+    check::<WindowLayoutState>(object, "windowLayout", &mut invalid);
+    check::<TraySettings>(object, "tray", &mut invalid);
+    check::<SchedulerState>(object, "scheduler", &mut invalid);
+    check::<NotificationSettings>(object, "notifications", &mut invalid);
+    check::<BackgroundTaskSettings>(object, "backgroundTasks", &mut invalid);
+    check::<AppLockSettings>(object, "appLock", &mut invalid);
+    check::<ApprovalShortcutSettings>(object, "approvalShortcuts", &mut invalid);
+    check::<SummonShortcutSettings>(object, "summonShortcuts", &mut invalid);
+    check::<LocaleSettings>(object, "locale", &mut invalid);
+    invalid
+}
+
 pub fn unix_timestamp_secs() -> i64 {
     match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
         Ok(duration) => duration.as_secs() as i64,
@@ -160,3 +901,81 @@ pub fn unix_timestamp_millis() -> i64 {
         Err(_) => 0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_preserves_unknown_fields() {
+        let raw = json!({
+            "version": 2,
+            "tray": { "enabled": false, "iconStyle": "mono" },
+            "futureSection": { "a": 1 }
+        });
+        let (state, changed) = GlobalStateStore::migrate(raw).unwrap();
+        assert!(changed);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(!state.tray.enabled);
+
+        let saved = serde_json::to_value(&state).unwrap();
+        assert_eq!(saved["futureSection"], json!({ "a": 1 }));
+        assert_eq!(saved["tray"]["iconStyle"], json!("mono"));
+        assert_eq!(
+            saved["notifications"]["version"],
+            json!(NOTIFICATIONS_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_migrate_resets_unreadable_section() {
+        let raw = json!({
+            "version": STATE_VERSION + 1,
+            "rateLimits": { "queueWhenLimited": true },
+            "scheduler": { "lastRuns": "not a map" }
+        });
+        let (state, changed) = GlobalStateStore::migrate(raw).unwrap();
+        assert!(changed);
+        // Files from newer builds keep their version
+        assert_eq!(state.version, STATE_VERSION + 1);
+        assert!(state.rate_limits.queue_when_limited);
+        assert!(state.scheduler.last_runs.is_empty());
+    }
+
+    #[test]
+    fn test_read_file_leaves_corrupt_files_alone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("global-state.json");
+        assert!(GlobalStateStore::read_file(&path).is_ok());
+
+        std::fs::write(&path, r#"{"appLock": {"enabled": true}}"#).unwrap();
+        assert!(GlobalStateStore::read_file(&path).unwrap().app_lock.enabled);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(GlobalStateStore::read_file(&path).is_err());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_portable_sections_round_trip() {
+        let mut source = GlobalStateFile::default();
+        source.turn_concurrency.max_concurrent_turns = 9;
+        source.app_server_launch.env.insert("TOKEN".into(), "secret".into());
+        let sections = source.portable_sections().unwrap();
+        assert!(sections.contains_key("turnConcurrency"));
+        assert!(!sections.contains_key("appServerLaunch"));
+        assert!(!sections.contains_key("windowLayout"));
+
+        let mut target = GlobalStateFile::default();
+        let applied = target.apply_portable_sections(&sections).unwrap();
+        assert_eq!(applied.len(), PORTABLE_SECTIONS.len());
+        assert_eq!(target.turn_concurrency.max_concurrent_turns, 9);
+        assert!(target.app_server_launch.env.is_empty());
+
+        let mut bad = Map::new();
+        bad.insert("rateLimits".into(), json!({ "warningPercent": "high" }));
+        assert!(target.apply_portable_sections(&bad).is_err());
+        assert_eq!(target.turn_concurrency.max_concurrent_turns, 9);
+    }
+}
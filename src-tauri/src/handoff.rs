@@ -0,0 +1,166 @@
+//! Inter-session handoff helpers.
+//!
+//! Renders a source thread's items into a compact transcript, asks a
+//! throwaway summarizer thread to condense it, and seeds a new thread in the
+//! target project with the result.
+
+use serde_json::Value as JsonValue;
+
+/// Maximum transcript bytes sent to the summarizer (oldest items are dropped first)
+const MAX_TRANSCRIPT_BYTES: usize = 60 * 1024;
+
+/// Maximum characters kept from a single command or message in the transcript
+const MAX_ITEM_CHARS: usize = 2000;
+
+/// Instructions for the summarizer turn
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the coding session transcript below so another \
+agent can continue the work in a different repository. Do not run any commands or edit files. \
+Respond with Markdown using exactly these sections:\n\
+## Goal\n## Key decisions\n## Remaining tasks\n## Relevant files\n\
+Be specific: name files, APIs and commands. Omit anything already resolved that does not \
+affect the remaining work.";
+
+/// Render raw thread items into a plain-text transcript, newest content kept
+pub fn render_transcript(items: &[JsonValue]) -> String {
+    let entries: Vec<String> = items.iter().filter_map(render_item).collect();
+
+    let mut kept = Vec::new();
+    let mut total = 0;
+    for entry in entries.iter().rev() {
+        // Count the blank-line separator added by the join
+        let cost = entry.len() + 2;
+        if total + cost > MAX_TRANSCRIPT_BYTES {
+            kept.push("[... earlier items omitted ...]".to_string());
+            break;
+        }
+        total += cost;
+        kept.push(entry.clone());
+    }
+    kept.reverse();
+    kept.join("\n\n")
+}
+
+fn render_item(item: &JsonValue) -> Option<String> {
+    let text = match item.get("type")?.as_str()? {
+        "userMessage" => {
+            let text: Vec<&str> = item
+                .get("content")?
+                .as_array()?
+                .iter()
+                .filter_map(|entry| entry.get("text").and_then(JsonValue::as_str))
+                .collect();
+            format!("USER: {}", clip(&text.join("\n")))
+        }
+        "agentMessage" => format!("AGENT: {}", clip(item.get("text")?.as_str()?)),
+        "commandExecution" => {
+            let command = match item.get("command")? {
+                JsonValue::Array(parts) => parts
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                other => other.as_str()?.to_string(),
+            };
+            let exit = item
+                .get("exitCode")
+                .and_then(JsonValue::as_i64)
+                .map(|code| format!(" (exit {code})"))
+                .unwrap_or_default();
+            format!("COMMAND: {}{exit}", clip(&command))
+        }
+        "fileChange" => {
+            let paths: Vec<&str> = item
+                .get("changes")?
+                .as_array()?
+                .iter()
+                .filter_map(|change| change.get("path").and_then(JsonValue::as_str))
+                .collect();
+            if paths.is_empty() {
+                return None;
+            }
+            format!("FILES CHANGED: {}", paths.join(", "))
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+fn clip(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_ITEM_CHARS) {
+        Some((idx, _)) => format!("{} [...]", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Prompt for the summarizer thread
+pub fn summary_prompt(transcript: &str) -> String {
+    format!("{SUMMARY_INSTRUCTIONS}\n\n<transcript>\n{transcript}\n</transcript>")
+}
+
+/// Text of the last agent message among `items`, if any
+pub fn last_agent_message(items: &[JsonValue]) -> Option<String> {
+    items
+        .iter()
+        .rev()
+        .filter(|item| item.get("type").and_then(JsonValue::as_str) == Some("agentMessage"))
+        .filter_map(|item| item.get("text").and_then(JsonValue::as_str))
+        .map(str::trim)
+        .find(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+/// Opening message for the new thread in the target project
+pub fn seed_prompt(source_project: &str, summary: &str, instructions: Option<&str>) -> String {
+    let mut prompt = format!(
+        "This session continues work started in another project ({source_project}). \
+         Here is the handoff summary from that session:\n\n{summary}\n\n"
+    );
+    match instructions.map(str::trim).filter(|text| !text.is_empty()) {
+        Some(instructions) => prompt.push_str(instructions),
+        None => prompt.push_str(
+            "Review the remaining tasks that apply to this repository and continue the work.",
+        ),
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_transcript_and_last_message() {
+        let items = vec![
+            json!({ "type": "userMessage", "content": [{ "type": "text", "text": "Add an API client" }] }),
+            json!({ "type": "reasoning", "summary": ["thinking"] }),
+            json!({ "type": "commandExecution", "command": "cargo test", "exitCode": 1 }),
+            json!({ "type": "fileChange", "changes": [{ "path": "src/client.rs", "kind": "add" }] }),
+            json!({ "type": "agentMessage", "text": "Client added; server side still pending." }),
+        ];
+
+        let transcript = render_transcript(&items);
+        assert_eq!(
+            transcript,
+            "USER: Add an API client\n\nCOMMAND: cargo test (exit 1)\n\n\
+             FILES CHANGED: src/client.rs\n\nAGENT: Client added; server side still pending."
+        );
+        assert_eq!(
+            last_agent_message(&items).as_deref(),
+            Some("Client added; server side still pending.")
+        );
+        assert!(last_agent_message(&items[..3]).is_none());
+    }
+
+    #[test]
+    fn test_render_transcript_drops_oldest_items() {
+        let long = "x".repeat(MAX_ITEM_CHARS);
+        let items: Vec<JsonValue> = (0..100)
+            .map(|_| json!({ "type": "agentMessage", "text": long }))
+            .collect();
+        let transcript = render_transcript(&items);
+        assert!(transcript.starts_with("[... earlier items omitted ...]"));
+        assert!(transcript.len() <= MAX_TRANSCRIPT_BYTES + 64);
+    }
+}
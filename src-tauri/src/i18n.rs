@@ -0,0 +1,140 @@
+//! Translations of text the backend shows to the user.
+//!
+//! Error messages and hints, and notification text raised from Rust, are
+//! looked up by key in tables compiled in from `locales/<tag>.json`, with
+//! `{name}` placeholders filled in by `translate`. The locale is
+//! process-wide, like secret masking, so `Error`'s `Serialize` can use it
+//! without app state; it is set at startup from the `locale` settings
+//! section and by `set_locale`. Logs stay in English, and so do details
+//! quoted from git, the OS or the app server.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+type Table = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    En,
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::ZhCn];
+
+    /// BCP 47 tag, as in the table file names
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::ZhCn => "zh-CN",
+        }
+    }
+
+    /// Supported locale closest to a tag like "zh-Hans-CN", "en_US.UTF-8"
+    /// or "zh"; `None` for languages without a table
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Self::En),
+            "zh" => Some(Self::ZhCn),
+            _ => None,
+        }
+    }
+
+    fn table(self) -> &'static Table {
+        match self {
+            Self::En => &EN,
+            Self::ZhCn => &ZH_CN,
+        }
+    }
+}
+
+static EN: LazyLock<Table> = LazyLock::new(|| parse(include_str!("../locales/en.json")));
+static ZH_CN: LazyLock<Table> = LazyLock::new(|| parse(include_str!("../locales/zh-CN.json")));
+
+fn parse(json: &str) -> Table {
+    serde_json::from_str(json).expect("bundled locale table is valid JSON")
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Locale of text handed to the renderer from now on
+pub fn current() -> Locale {
+    Locale::ALL
+        .get(usize::from(CURRENT.load(Ordering::Relaxed)))
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn set(locale: Locale) {
+    let index = Locale::ALL.iter().position(|l| *l == locale).unwrap_or(0);
+    CURRENT.store(index as u8, Ordering::Relaxed);
+}
+
+/// Text of `key` in `locale` with each `{name}` replaced by its value.
+/// Keys missing from the table fall back to English, then to the key.
+pub fn translate(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    let template = locale
+        .table()
+        .get(key)
+        .or_else(|| EN.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), value);
+    }
+    text
+}
+
+/// `translate` in the current locale
+pub fn t(key: &str, args: &[(&str, &str)]) -> String {
+    translate(current(), key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_tables_have_the_same_keys() {
+        let english: BTreeSet<_> = EN.keys().collect();
+        for locale in Locale::ALL {
+            let keys: BTreeSet<_> = locale.table().keys().collect();
+            assert_eq!(keys, english, "{}", locale.tag());
+        }
+    }
+
+    #[test]
+    fn test_translate() {
+        let args = [("program", "gh"), ("install", "brew install gh")];
+        assert_eq!(
+            translate(Locale::En, "error.cli_not_installed", &args),
+            "gh is not installed. Install it with: brew install gh"
+        );
+        assert_eq!(
+            translate(Locale::ZhCn, "error.cli_not_installed", &args),
+            "未安装 gh。安装命令：brew install gh"
+        );
+        assert_eq!(translate(Locale::ZhCn, "no.such.key", &[]), "no.such.key");
+    }
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("zh-Hans-CN"), Some(Locale::ZhCn));
+        assert_eq!(Locale::from_tag("en_US.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("fr-FR"), None);
+    }
+}
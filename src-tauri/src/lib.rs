@@ -5,22 +5,81 @@
 //! and command execution with safety controls.
 
 pub mod app_server;
+pub mod cli;
 pub mod codex_import;
 pub mod commands;
 pub mod database;
 pub mod snapshots;
 
+mod accounts;
+mod actions;
+mod allowlist;
+mod app_lock;
+mod approval_shortcuts;
+mod approvals;
+mod attachments;
+mod background_tasks;
+mod backend_log;
+mod batch;
+mod change_report;
+mod ci_status;
+mod commit_message;
+mod commit_signing;
+mod comparison;
+mod conflict_markers;
+mod content_search;
+mod deep_link;
+mod diagnostics;
 mod events;
+mod file_content;
+mod file_index;
+mod followups;
+mod forge;
+mod gc;
+mod git;
+mod git_hooks;
+mod global_shortcuts;
 mod global_state;
+mod handoff;
 mod health;
+mod i18n;
+mod live_diff;
+mod model_providers;
+mod notifications;
+mod patch_preflight;
+mod pending_approvals;
+mod pending_work;
+mod project_archive;
+mod project_env;
+mod pull_requests;
+mod quick_prompt;
+mod redaction;
+mod safe_mode;
+mod sandbox;
+mod secret_mask;
+mod session_preview;
+mod session_stats;
+mod session_worktrees;
+mod settings_transfer;
 mod error;
+mod startup_progress;
 mod state;
+mod terminal_output;
+mod transcript_export;
+mod transcript_import;
+mod tray;
+mod turn_queue;
+mod turn_timings;
+mod usage;
 mod utils;
+mod watcher;
+mod workflows;
 
 pub use error::{CodexErrorInfo, CodexErrorType, Error, Result};
 pub use state::AppState;
 
 use std::io;
+use std::path::Path;
 use tauri::{Manager, WindowEvent};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
@@ -77,58 +136,30 @@ fn cleanup_old_logs(app_handle: &tauri::AppHandle) {
 
 /// Clean up old temp image files from previous sessions
 fn cleanup_temp_images() {
-    let temp_dir = std::env::temp_dir();
+    let cleaned = gc::orphaned_temp_images()
+        .into_iter()
+        .filter(|(path, _)| std::fs::remove_file(path).is_ok())
+        .count();
 
-    if let Ok(entries) = std::fs::read_dir(&temp_dir) {
-        let current_pid = std::process::id();
-        let mut cleaned = 0;
-
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                // Match codex_image_<pid>_<timestamp>.<ext> pattern
-                if name.starts_with("codex_image_") {
-                    // Extract PID from filename
-                    let parts: Vec<&str> = name.split('_').collect();
-                    if parts.len() >= 3 {
-                        if let Ok(pid) = parts[2].parse::<u32>() {
-                            // Only clean files from other PIDs (not current session)
-                            if pid != current_pid {
-                                if let Ok(metadata) = std::fs::metadata(&path) {
-                                    // Only delete files older than 1 hour
-                                    if let Ok(modified) = metadata.modified() {
-                                        if let Ok(age) = std::time::SystemTime::now().duration_since(modified) {
-                                            if age.as_secs() > 3600
-                                                && std::fs::remove_file(&path).is_ok()
-                                            {
-                                                cleaned += 1;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        if cleaned > 0 {
-            tracing::info!("Cleaned up {} old temp image files", cleaned);
-        }
+    if cleaned > 0 {
+        tracing::info!("Cleaned up {} old temp image files", cleaned);
     }
 }
 
 /// Initialize and run the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Clean up old temp images from previous sessions
-    cleanup_temp_images();
-
     tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
+        // Must come first: a second launch hands its arguments to this
+        // instance and exits before it starts an app server or opens the
+        // database. Links among them go on to the deep-link plugin.
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            tracing::info!("Another launch was redirected to this instance");
+            quick_prompt::show_main_window(app);
+            deep_link::open_args(app, &args, Path::new(&cwd));
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_fs::init())
         .setup(|app| {
             let app_handle = app.handle().clone();
 
@@ -146,25 +177,81 @@ pub fn run() {
             }
 
             // Initialize logging (file + stdout)
-            let log_guard = init_tracing(&app_handle);
+            let (log_guard, backend_log_rx) = init_tracing(&app_handle);
 
             tracing::info!("Starting Codex Desktop");
 
             // Clean up old log files (>30 days)
             cleanup_old_logs(&app_handle);
 
+            // Clean up old temp images from previous sessions; only here, once
+            // this is known to be the only instance
+            cleanup_temp_images();
+
             // Initialize application state
             let state = AppState::new(&app_handle, log_guard)?;
+            let masking = state.global_state.read(|global| global.secret_masking.clone());
+            if let Err(err) = secret_mask::configure(&masking) {
+                tracing::warn!("Ignoring custom secret masking patterns: {}", err);
+            }
+            i18n::set(state.global_state.read(|global| global.locale.locale));
+            backend_log::spawn_forwarder(backend_log_rx, state.events.clone());
+            // Optional plugins are left out in safe mode; dialogs stay for recovery prompts
+            if state.safe_mode.is_none() {
+                app_handle.plugin(tauri_plugin_shell::init())?;
+                app_handle.plugin(tauri_plugin_fs::init())?;
+                app_handle.plugin(global_shortcuts::plugin())?;
+                app_handle.plugin(tauri_plugin_notification::init())?;
+                let (approvals, summon) = state.global_state.read(|global| {
+                    (
+                        global.approval_shortcuts.clone(),
+                        global.summon_shortcuts.clone(),
+                    )
+                });
+                if let Err(err) = global_shortcuts::register(&app_handle, &approvals, &summon) {
+                    tracing::warn!("Global shortcuts unavailable: {}", err);
+                }
+            }
             app.manage(state);
             app.manage(commands::system::CaffeinateState(std::sync::Mutex::new(None)));
             app.state::<AppState>().start_background_tasks();
+            deep_link::listen(&app_handle);
+            if let Ok(cwd) = std::env::current_dir() {
+                let args: Vec<String> = std::env::args().collect();
+                deep_link::open_args(&app_handle, &args, &cwd);
+            }
+            let state = app.state::<AppState>();
+            if state.safe_mode.is_none() {
+                let tray_enabled = state.global_state.read(|global| global.tray.enabled);
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if tray_enabled {
+                        if let Err(err) = tray::create(&app_handle).await {
+                            tracing::warn!("Tray icon unavailable: {}", err);
+                        }
+                    }
+                    tray::run_status_updates(app_handle).await;
+                });
+            }
 
             tracing::info!("Application state initialized");
             Ok(())
         })
         .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if tray::hide_to_tray(window) {
+                    api.prevent_close();
+                    return;
+                }
+            }
             if matches!(event, WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed) {
                 if let Some(state) = window.app_handle().try_state::<AppState>() {
+                    // A clean close is not a crash, even inside the crash window
+                    if state.safe_mode.is_none() {
+                        state
+                            .global_state
+                            .update(|global| safe_mode::mark_stable(&mut global.startup));
+                    }
                     if let Err(err) = state.global_state.flush() {
                         tracing::warn!("Failed to flush global state on close: {}", err);
                     }
@@ -181,32 +268,72 @@ pub fn run() {
                 }
             }
         })
-        .invoke_handler(tauri::generate_handler![
+        .invoke_handler(app_lock::gate(tauri::generate_handler![
             // Project commands
             commands::projects::list_projects,
             commands::projects::add_project,
             commands::projects::remove_project,
+            commands::projects::archive_project,
+            commands::projects::unarchive_project,
+            commands::projects::list_archived_projects,
             commands::projects::update_project,
+            commands::projects::set_project_appearance,
+            commands::projects::set_project_env,
+            commands::projects::list_project_env,
+            commands::projects::delete_project_env,
             commands::projects::get_project_git_info,
             commands::projects::get_project_git_diff,
             commands::projects::git_diff_staged,
             commands::projects::git_diff_branch,
             commands::projects::list_project_files,
+            commands::projects::get_directory_children,
+            commands::projects::get_recently_modified_files,
+            commands::projects::search_project_files,
+            commands::projects::search_project_content,
+            commands::projects::cancel_project_content_search,
+            commands::projects::preview_ignore_effect,
             commands::projects::validate_project_directory,
             commands::projects::read_project_file,
+            commands::projects::read_project_file_content,
+            commands::projects::write_project_file,
+            commands::projects::create_project_file,
+            commands::projects::rename_project_path,
+            commands::projects::move_project_path,
+            commands::projects::duplicate_project_path,
+            commands::projects::create_directory,
             commands::projects::get_git_branches,
             commands::projects::get_git_commits,
+            commands::projects::generate_change_report,
             commands::projects::git_status,
             commands::projects::git_stage_files,
             commands::projects::git_unstage_files,
             commands::projects::git_commit,
+            commands::projects::get_signing_config,
+            commands::projects::list_git_hooks,
+            commands::projects::list_hook_bypasses,
+            commands::projects::suggest_commit_message,
             commands::projects::git_push,
             commands::projects::git_remote_info,
             commands::projects::git_apply_patch,
+            commands::projects::preflight_patch,
             // PR commands
-            commands::projects::check_gh_cli,
+            commands::projects::check_forge_cli,
+            commands::projects::get_forge_links,
+            commands::projects::set_bitbucket_credentials,
             commands::projects::get_current_branch,
+            commands::projects::get_head_state,
+            commands::projects::unshallow_repo,
+            commands::projects::create_branch_from_detached_head,
             commands::projects::create_pull_request,
+            commands::pull_requests::list_pull_requests,
+            commands::pull_requests::get_pull_request,
+            commands::pull_requests::checkout_pull_request,
+            commands::pull_requests::list_pr_review_comments,
+            commands::pull_requests::reply_to_pr_comment,
+            commands::pull_requests::start_pr_review_thread,
+            commands::pull_requests::get_ci_status,
+            commands::pull_requests::watch_ci_status,
+            commands::pull_requests::unwatch_ci_status,
             // Worktree commands
             commands::projects::create_worktree,
             commands::projects::remove_worktree,
@@ -214,34 +341,121 @@ pub fn run() {
             // Swarm git commands
             commands::projects::git_checkout_branch,
             commands::projects::git_merge_no_ff,
+            // Filesystem watcher commands
+            commands::watcher::watch_project,
+            commands::watcher::unwatch_project,
             // Session commands
             commands::sessions::list_sessions,
             commands::sessions::get_session,
             commands::sessions::update_session_metadata,
             commands::sessions::delete_session,
+            commands::sessions::delete_sessions,
+            commands::sessions::archive_sessions,
+            commands::sessions::move_sessions_to_project,
+            commands::sessions::adopt_session,
+            commands::sessions::add_session_tag,
+            commands::sessions::remove_session_tag,
+            commands::sessions::list_session_tags,
+            commands::sessions::set_session_favorite,
             commands::sessions::search_sessions,
+            commands::sessions::export_session,
+            commands::sessions::preview_session_redaction,
+            commands::sessions::get_redaction_settings,
+            commands::sessions::set_redaction_settings,
+            commands::sessions::get_secret_masking_settings,
+            commands::sessions::set_secret_masking_settings,
+            commands::sessions::import_session,
+            commands::sessions::get_imported_session_items,
+            commands::sessions::get_session_stats,
+            commands::sessions::get_turn_timings,
+            commands::sessions::list_turn_outcomes,
+            commands::sessions::list_recovered_turns,
+            commands::sessions::get_session_live_diff,
+            commands::sessions::clear_session_live_diff,
+            commands::sessions::get_session_worktree,
+            commands::sessions::cleanup_session_worktree,
+            commands::sessions::get_session_baseline_diff,
             commands::sessions::update_session_status,
             commands::sessions::set_session_first_message,
             commands::sessions::update_session_tasks,
             // Thread commands (proxy to app-server)
             commands::thread::start_thread,
+            commands::thread::get_effective_thread_options,
             commands::thread::resume_thread,
             commands::thread::send_message,
             commands::thread::interrupt_turn,
+            commands::thread::list_active_turns,
+            commands::thread::interrupt_all_turns,
+            commands::thread::get_turn_concurrency_settings,
+            commands::thread::set_turn_concurrency_settings,
+            commands::thread::get_turn_budget_settings,
+            commands::thread::set_turn_budget_settings,
             commands::thread::respond_to_approval,
             commands::thread::list_threads,
+            // Follow-up queue commands
+            commands::followups::queue_followup,
+            commands::followups::list_followups,
+            commands::followups::remove_followup,
+            commands::followups::clear_followups,
+            commands::turn_queue::enqueue_turn,
+            commands::turn_queue::list_queue,
+            commands::turn_queue::reorder_queue,
+            commands::turn_queue::cancel_queued_turn,
+            commands::turn_queue::get_queue_status,
+            commands::turn_queue::set_queue_paused,
+            // Handoff commands
+            commands::handoff::handoff_session,
+            // Batch commands
+            commands::batch::run_batch,
+            commands::comparison::run_comparison,
+            commands::comparison::list_comparisons,
+            commands::comparison::get_comparison_results,
+            commands::comparison::resolve_comparison,
+            // Attachment budget commands
+            commands::attachments::prepare_attachments,
+            commands::attachments::get_attachment_budget_settings,
+            commands::attachments::set_attachment_budget_settings,
+            // Workflow commands
+            commands::workflows::list_workflows,
+            commands::workflows::start_workflow,
             // Snapshot commands
             commands::snapshots::create_snapshot,
             commands::snapshots::revert_to_snapshot,
+            commands::snapshots::restore_files_from_snapshot,
+            commands::snapshots::undo_last_revert,
+            commands::snapshots::diff_snapshots,
+            commands::snapshots::diff_snapshot_to_worktree,
+            commands::snapshots::export_snapshot_patch,
+            commands::snapshots::apply_snapshot_to_path,
             commands::snapshots::list_snapshots,
             commands::snapshots::cleanup_old_snapshots_by_age,
+            commands::snapshots::get_snapshot_storage_usage,
+            commands::snapshots::get_snapshot_sizes,
+            commands::snapshots::compact_snapshot_storage,
             commands::snapshots::cleanup_session_snapshots,
+            commands::snapshots::get_snapshot_settings,
+            commands::snapshots::set_snapshot_settings,
             // App server commands
             commands::app_server::get_server_status,
             commands::app_server::restart_server,
+            commands::app_server::get_app_server_launch_settings,
+            commands::app_server::set_app_server_launch_settings,
+            commands::app_server::detect_codex_installations,
+            commands::app_server::check_codex_version,
+            commands::app_server::upgrade_codex,
             commands::app_server::get_account_info,
             commands::app_server::start_login,
+            commands::app_server::start_reauth,
             commands::app_server::logout,
+            commands::app_server::list_accounts,
+            commands::app_server::save_current_account,
+            commands::app_server::switch_account,
+            commands::app_server::remove_account,
+            commands::app_server::list_model_providers,
+            commands::app_server::save_model_provider,
+            commands::app_server::remove_model_provider,
+            commands::app_server::set_model_provider_api_key,
+            commands::app_server::test_provider_connection,
             commands::app_server::get_models,
             commands::app_server::list_skills,
             commands::app_server::list_mcp_servers,
@@ -252,10 +466,26 @@ pub fn run() {
             commands::app_server::write_config,
             // Account rate limits
             commands::app_server::get_account_rate_limits,
+            commands::app_server::get_rate_limit_forecast,
+            commands::app_server::set_rate_limit_settings,
+            commands::app_server::cancel_rate_limited_message,
+            commands::app_server::get_rpc_trace,
+            commands::app_server::clear_rpc_trace,
+            commands::app_server::set_rpc_trace_config,
             // Allowlist commands
             commands::allowlist::get_allowlist,
             commands::allowlist::add_to_allowlist,
             commands::allowlist::remove_from_allowlist,
+            commands::allowlist::check_allowlist_match,
+            commands::allowlist::get_denylist,
+            commands::allowlist::add_to_denylist,
+            commands::allowlist::remove_from_denylist,
+            commands::allowlist::classify_command,
+            commands::allowlist::get_approval_policy,
+            commands::allowlist::set_approval_policy,
+            // Settings transfer commands
+            commands::settings_transfer::export_settings,
+            commands::settings_transfer::import_settings,
             // Codex CLI import commands
             commands::codex_import::get_codex_config,
             commands::codex_import::list_codex_sessions,
@@ -265,9 +495,26 @@ pub fn run() {
             commands::codex_import::get_codex_dir,
             // Terminal commands
             commands::terminal::execute_terminal_command,
+            commands::terminal::get_sandbox_support,
+            commands::terminal::get_terminal_scrollback,
+            commands::terminal::get_terminal_output_settings,
+            commands::terminal::set_terminal_output_settings,
             // Renderer lifecycle
             commands::lifecycle::renderer_ready,
             commands::lifecycle::renderer_heartbeat,
+            commands::lifecycle::get_backend_heartbeat_config,
+            commands::lifecycle::set_backend_heartbeat_config,
+            commands::lifecycle::get_background_tasks,
+            commands::lifecycle::set_background_task_settings,
+            commands::lifecycle::get_safe_mode_reason,
+            commands::lifecycle::exit_safe_mode,
+            commands::pending_work::get_pending_work_summary,
+            // Garbage collection
+            commands::gc::get_gc_preview,
+            commands::gc::run_gc,
+            // Usage
+            commands::usage::get_usage_summary,
+            commands::usage::export_usage_csv,
             // System commands (keep awake)
             commands::system::start_keep_awake,
             commands::system::stop_keep_awake,
@@ -275,16 +522,45 @@ pub fn run() {
             // Diagnostics
             commands::system::get_app_paths,
             commands::system::get_log_tail,
-        ])
+            commands::system::list_actions,
+            commands::diagnostics::run_diagnostics,
+            // App lock
+            commands::app_lock::get_app_lock_status,
+            commands::app_lock::lock_app,
+            commands::app_lock::unlock_app,
+            commands::app_lock::set_app_lock_passphrase,
+            commands::app_lock::set_app_lock_settings,
+            commands::app_lock::report_user_activity,
+            // Approval shortcut commands
+            commands::approval_shortcuts::list_pending_approvals,
+            commands::approval_shortcuts::get_approval_shortcut_settings,
+            commands::approval_shortcuts::set_approval_shortcut_settings,
+            commands::quick_prompt::get_summon_shortcut_settings,
+            commands::quick_prompt::set_summon_shortcut_settings,
+            commands::quick_prompt::get_quick_prompt_target,
+            commands::locale::get_locale,
+            commands::locale::set_locale,
+            commands::notifications::get_notification_preferences,
+            commands::notifications::set_notification_preferences,
+            commands::tray::get_tray_settings,
+            commands::tray::set_tray_settings,
+        ]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
 
-fn init_tracing(app_handle: &tauri::AppHandle) -> Option<tracing_appender::non_blocking::WorkerGuard> {
+fn init_tracing(
+    app_handle: &tauri::AppHandle,
+) -> (
+    Option<tracing_appender::non_blocking::WorkerGuard>,
+    tokio::sync::mpsc::Receiver<backend_log::BackendLogRecord>,
+) {
     let env_filter = tracing_subscriber::EnvFilter::from_default_env()
         .add_directive("codex_desktop=debug".parse().unwrap());
 
-    let stdout_layer = tracing_subscriber::fmt::layer().with_writer(io::stdout);
+    let stdout_layer = tracing_subscriber::fmt::layer()
+        .with_writer(secret_mask::MaskingMakeWriter(io::stdout));
+    let (backend_log_layer, backend_log_rx) = backend_log::BackendLogLayer::new();
     let mut guard = None;
     let mut file_layer = None;
     if let Ok(app_data_dir) = app_handle.path().app_data_dir() {
@@ -293,7 +569,7 @@ fn init_tracing(app_handle: &tauri::AppHandle) -> Option<tracing_appender::non_b
             let file_appender = tracing_appender::rolling::daily(&log_dir, "codex-desktop.log");
             let (non_blocking, file_guard) = tracing_appender::non_blocking(file_appender);
             file_layer = Some(tracing_subscriber::fmt::layer()
-                .with_writer(non_blocking)
+                .with_writer(secret_mask::MaskingMakeWriter(non_blocking))
                 .with_ansi(false));
             guard = Some(file_guard);
         }
@@ -303,6 +579,7 @@ fn init_tracing(app_handle: &tauri::AppHandle) -> Option<tracing_appender::non_b
         .with(env_filter)
         .with(stdout_layer)
         .with(file_layer)
+        .with(backend_log_layer)
         .init();
-    guard
+    (guard, backend_log_rx)
 }
@@ -0,0 +1,467 @@
+//! Per-session diff accumulated while the agent works.
+//!
+//! Completed `fileChange` items append their patches to the session's
+//! entry for each file. Commands that write files (redirects, `sed -i`,
+//! `mv`, ...) are diffed against `HEAD` for just the paths they name, since
+//! their output carries no patch. The diff panel reads the accumulated
+//! state once and then follows `live-diff-updated` events, instead of
+//! re-diffing the whole repository after every turn. Diffs live in memory
+//! for the lifetime of the app.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::git;
+
+/// Sessions tracked at once; the least recently updated is dropped first
+const MAX_SESSIONS: usize = 50;
+
+/// Diff text kept per file; later patches still update the line counts
+const MAX_FILE_DIFF_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffSource {
+    /// Patches applied by the agent
+    Patch,
+    /// Written by a command; the diff is the file against `HEAD`
+    Command,
+}
+
+/// Accumulated changes to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveFileDiff {
+    pub path: String,
+    /// "add", "delete" or "update"
+    pub kind: String,
+    pub source: DiffSource,
+    pub diff: String,
+    pub insertions: u32,
+    pub deletions: u32,
+    /// `diff` stopped growing at the size limit
+    pub truncated: bool,
+    /// Unix milliseconds
+    pub updated_at: i64,
+}
+
+/// A session's accumulated diff
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveDiff {
+    pub session_id: String,
+    /// Increases with every update, so stale events can be ignored
+    pub version: u64,
+    /// Sorted by path
+    pub files: Vec<LiveFileDiff>,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Payload of `live-diff-updated`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveDiffUpdate {
+    pub session_id: String,
+    pub version: u64,
+    /// Files whose entry changed, in full
+    pub files: Vec<LiveFileDiff>,
+    /// Files no longer in the diff (added and then deleted again)
+    pub removed: Vec<String>,
+    /// Session totals after the update
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+#[derive(Debug, Default)]
+struct SessionDiff {
+    files: BTreeMap<String, LiveFileDiff>,
+    version: u64,
+    updated_at: i64,
+}
+
+impl SessionDiff {
+    fn totals(&self) -> (u32, u32) {
+        self.files.values().fold((0, 0), |(ins, del), file| {
+            (ins + file.insertions, del + file.deletions)
+        })
+    }
+}
+
+/// Accumulated diffs, keyed by session (thread) id
+#[derive(Debug, Default)]
+pub struct LiveDiffTracker {
+    sessions: Mutex<HashMap<String, SessionDiff>>,
+}
+
+impl LiveDiffTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a completed `fileChange` item's patches to its session
+    ///
+    /// Returns the update to emit, or `None` when the item changed nothing.
+    pub fn apply_file_change(
+        &self,
+        session_id: &str,
+        item: &JsonValue,
+        at_ms: i64,
+    ) -> Option<LiveDiffUpdate> {
+        if item.get("type").and_then(JsonValue::as_str) != Some("fileChange") {
+            return None;
+        }
+        let status = item.get("status").and_then(JsonValue::as_str);
+        if status.is_some_and(|status| status != "completed") {
+            return None;
+        }
+        let changes = item.get("changes")?.as_array()?;
+
+        let mut sessions = self.sessions.lock();
+        let session = session_entry(&mut sessions, session_id);
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+        for change in changes {
+            let Some(path) = change.get("path").and_then(JsonValue::as_str) else {
+                continue;
+            };
+            let kind = change_kind(change);
+            let diff = change
+                .get("diff")
+                .and_then(JsonValue::as_str)
+                .unwrap_or_default();
+            let (insertions, deletions) = count_lines(&kind, diff);
+
+            let added_earlier = session
+                .files
+                .get(path)
+                .is_some_and(|file| file.kind == "add" && file.source == DiffSource::Patch);
+            if kind == "delete" && added_earlier {
+                // Created and removed within the session: nothing left to show
+                session.files.remove(path);
+                removed.push(path.to_string());
+                continue;
+            }
+
+            let file = session
+                .files
+                .entry(path.to_string())
+                .or_insert_with(|| LiveFileDiff {
+                    path: path.to_string(),
+                    kind: kind.clone(),
+                    source: DiffSource::Patch,
+                    diff: String::new(),
+                    insertions: 0,
+                    deletions: 0,
+                    truncated: false,
+                    updated_at: at_ms,
+                });
+            if !(file.kind == "add" && kind == "update") {
+                file.kind = kind;
+            }
+            file.source = DiffSource::Patch;
+            file.insertions += insertions;
+            file.deletions += deletions;
+            file.updated_at = at_ms;
+            append_diff(file, diff);
+            changed.push(path.to_string());
+        }
+
+        finish_update(session_id, session, changed, removed, at_ms)
+    }
+
+    /// Replace the entries of files a command wrote with their diffs
+    /// against `HEAD`; files without changes are dropped from the diff
+    pub fn record_command_writes(
+        &self,
+        session_id: &str,
+        diffs: Vec<(String, String)>,
+        at_ms: i64,
+    ) -> Option<LiveDiffUpdate> {
+        if diffs.is_empty() {
+            return None;
+        }
+        let mut sessions = self.sessions.lock();
+        let session = session_entry(&mut sessions, session_id);
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+        for (path, diff) in diffs {
+            if diff.trim().is_empty() {
+                if session.files.remove(&path).is_some() {
+                    removed.push(path);
+                }
+                continue;
+            }
+            let kind = if diff.contains("\nnew file mode") {
+                "add"
+            } else if diff.contains("\ndeleted file mode") {
+                "delete"
+            } else {
+                "update"
+            };
+            let (insertions, deletions) = count_lines("update", &diff);
+            let mut file = LiveFileDiff {
+                path: path.clone(),
+                kind: kind.to_string(),
+                source: DiffSource::Command,
+                diff: String::new(),
+                insertions,
+                deletions,
+                truncated: false,
+                updated_at: at_ms,
+            };
+            append_diff(&mut file, &diff);
+            session.files.insert(path.clone(), file);
+            changed.push(path);
+        }
+
+        finish_update(session_id, session, changed, removed, at_ms)
+    }
+
+    /// The session's accumulated diff (empty when nothing was tracked)
+    pub fn get(&self, session_id: &str) -> LiveDiff {
+        let sessions = self.sessions.lock();
+        let Some(session) = sessions.get(session_id) else {
+            return LiveDiff {
+                session_id: session_id.to_string(),
+                ..LiveDiff::default()
+            };
+        };
+        let (insertions, deletions) = session.totals();
+        LiveDiff {
+            session_id: session_id.to_string(),
+            version: session.version,
+            files: session.files.values().cloned().collect(),
+            insertions,
+            deletions,
+        }
+    }
+
+    /// Forget a session's diff; returns whether there was one
+    pub fn clear(&self, session_id: &str) -> bool {
+        self.sessions.lock().remove(session_id).is_some()
+    }
+}
+
+/// Diffs of `paths` (as a command in `cwd` named them) against `HEAD`
+///
+/// Untracked files are diffed against an empty file. Paths outside a git
+/// repository, or that git cannot diff, are skipped. Blocking.
+pub fn command_file_diffs(cwd: &Path, paths: &[String]) -> Vec<(String, String)> {
+    let mut diffs = Vec::new();
+    for path in paths {
+        if path.starts_with('-') || path.starts_with('~') || path.starts_with('$') {
+            continue;
+        }
+        let full_path = cwd.join(path);
+        let Some(diff) = git_file_diff(cwd, path, &full_path) else {
+            continue;
+        };
+        diffs.push((full_path.to_string_lossy().to_string(), diff));
+    }
+    diffs
+}
+
+fn git_file_diff(cwd: &Path, path: &str, full_path: &Path) -> Option<String> {
+    let diff = git::run(cwd, &["diff", "--no-color", "HEAD", "--", path]).ok()?;
+    if !diff.is_empty() || !full_path.is_file() {
+        return Some(diff);
+    }
+
+    // Nothing against HEAD: either unchanged or not tracked yet
+    let tracked = git::run(cwd, &["ls-files", "--error-unmatch", "--", path]).is_ok();
+    if tracked {
+        return Some(diff);
+    }
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let output = git::output(
+        cwd,
+        &["diff", "--no-color", "--no-index", "--", null_path, path],
+    )
+    .ok()?;
+    // --no-index exits with 1 when the files differ
+    matches!(output.status.code(), Some(0 | 1))
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn session_entry<'a>(
+    sessions: &'a mut HashMap<String, SessionDiff>,
+    session_id: &str,
+) -> &'a mut SessionDiff {
+    if !sessions.contains_key(session_id) && sessions.len() >= MAX_SESSIONS {
+        let oldest = sessions
+            .iter()
+            .min_by_key(|(_, session)| session.updated_at)
+            .map(|(id, _)| id.clone());
+        if let Some(oldest) = oldest {
+            sessions.remove(&oldest);
+        }
+    }
+    sessions.entry(session_id.to_string()).or_default()
+}
+
+fn finish_update(
+    session_id: &str,
+    session: &mut SessionDiff,
+    changed: Vec<String>,
+    removed: Vec<String>,
+    at_ms: i64,
+) -> Option<LiveDiffUpdate> {
+    if changed.is_empty() && removed.is_empty() {
+        return None;
+    }
+    session.version += 1;
+    session.updated_at = at_ms;
+    let (insertions, deletions) = session.totals();
+    Some(LiveDiffUpdate {
+        session_id: session_id.to_string(),
+        version: session.version,
+        files: changed
+            .iter()
+            .filter_map(|path| session.files.get(path).cloned())
+            .collect(),
+        removed,
+        insertions,
+        deletions,
+    })
+}
+
+/// "add", "delete" or "update" from a change's `kind` (a string or
+/// `{ "type": ... }`)
+fn change_kind(change: &JsonValue) -> String {
+    let kind = match change.get("kind") {
+        Some(JsonValue::String(kind)) => kind.as_str(),
+        Some(kind) => kind
+            .get("type")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("update"),
+        None => "update",
+    };
+    kind.to_lowercase()
+}
+
+/// Lines added and removed by a change; added and deleted files carry the
+/// whole content rather than a unified diff
+fn count_lines(kind: &str, diff: &str) -> (u32, u32) {
+    match kind {
+        "add" if !diff.starts_with("diff ") && !diff.starts_with("@@") => {
+            (diff.lines().count() as u32, 0)
+        }
+        "delete" if !diff.starts_with("diff ") && !diff.starts_with("@@") => {
+            (0, diff.lines().count() as u32)
+        }
+        _ => diff.lines().fold((0, 0), |(ins, del), line| {
+            if line.starts_with("+++") || line.starts_with("---") {
+                (ins, del)
+            } else if line.starts_with('+') {
+                (ins + 1, del)
+            } else if line.starts_with('-') {
+                (ins, del + 1)
+            } else {
+                (ins, del)
+            }
+        }),
+    }
+}
+
+fn append_diff(file: &mut LiveFileDiff, diff: &str) {
+    if file.truncated || diff.is_empty() {
+        return;
+    }
+    if file.diff.len() + diff.len() + 1 > MAX_FILE_DIFF_BYTES {
+        file.truncated = true;
+        return;
+    }
+    if !file.diff.is_empty() && !file.diff.ends_with('\n') {
+        file.diff.push('\n');
+    }
+    file.diff.push_str(diff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn file_change(changes: JsonValue) -> JsonValue {
+        json!({ "type": "fileChange", "status": "completed", "changes": changes })
+    }
+
+    #[test]
+    fn test_accumulates_patches() {
+        let tracker = LiveDiffTracker::new();
+        let update = tracker
+            .apply_file_change(
+                "t1",
+                &file_change(json!([
+                    { "path": "/w/src/lib.rs", "kind": { "type": "update" }, "diff": "@@ -1 +1 @@\n-a\n+b\n" },
+                    { "path": "/w/notes.md", "kind": "add", "diff": "one\ntwo\n" },
+                ])),
+                1,
+            )
+            .unwrap();
+        assert_eq!(update.version, 1);
+        assert_eq!((update.insertions, update.deletions), (3, 1));
+
+        let update = tracker
+            .apply_file_change(
+                "t1",
+                &file_change(json!([
+                    { "path": "/w/src/lib.rs", "kind": { "type": "update" }, "diff": "@@ -5 +5,2 @@\n+c\n" },
+                ])),
+                2,
+            )
+            .unwrap();
+        assert_eq!(update.files.len(), 1);
+        assert_eq!(update.files[0].insertions, 2);
+        assert!(update.files[0].diff.contains("-a\n+b\n@@ -5"));
+
+        let update = tracker
+            .apply_file_change(
+                "t1",
+                &file_change(json!([{ "path": "/w/notes.md", "kind": "delete", "diff": "" }])),
+                3,
+            )
+            .unwrap();
+        assert_eq!(update.removed, vec!["/w/notes.md"]);
+
+        let diff = tracker.get("t1");
+        assert_eq!(diff.version, 3);
+        assert_eq!(diff.files.len(), 1);
+        assert_eq!((diff.insertions, diff.deletions), (2, 1));
+        assert_eq!(tracker.get("other").files.len(), 0);
+    }
+
+    #[test]
+    fn test_ignores_unapplied_changes_and_records_commands() {
+        let tracker = LiveDiffTracker::new();
+        let declined = json!({
+            "type": "fileChange",
+            "status": "declined",
+            "changes": [{ "path": "a", "kind": "add", "diff": "x" }],
+        });
+        assert!(tracker.apply_file_change("t1", &declined, 1).is_none());
+
+        let diff = "diff --git a/out.txt b/out.txt\nnew file mode 100644\n--- /dev/null\n+++ b/out.txt\n@@ -0,0 +1 @@\n+hi\n";
+        let update = tracker
+            .record_command_writes(
+                "t1",
+                vec![
+                    ("/w/out.txt".to_string(), diff.to_string()),
+                    ("/w/same.txt".to_string(), String::new()),
+                ],
+                2,
+            )
+            .unwrap();
+        assert_eq!(update.files[0].kind, "add");
+        assert_eq!(update.files[0].source, DiffSource::Command);
+        assert_eq!(update.insertions, 1);
+        assert!(update.removed.is_empty());
+        assert!(tracker.clear("t1"));
+        assert!(!tracker.clear("t1"));
+    }
+}
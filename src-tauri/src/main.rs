@@ -2,5 +2,12 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args_os()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    // Headless commands like `codex-desktop doctor` exit before the app starts
+    if let Some(code) = codex_desktop_lib::cli::run(&args) {
+        std::process::exit(code);
+    }
     codex_desktop_lib::run()
 }
@@ -0,0 +1,478 @@
+//! Custom model providers for self-hosted or alternative endpoints.
+//!
+//! Providers are kept in the global state and written to the Codex config
+//! as `model_providers.<id>`, so the CLI can use them like its built-in
+//! ones. API keys never touch the config: they are stored in the OS
+//! keychain and handed to the app-server as environment variables (named by
+//! each provider's `env_key`) when it is spawned, so changing a key
+//! restarts the server.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::app_server::ipc_bridge::ThreadStartParams;
+use crate::global_state::CustomModelProvider;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+/// Keychain service the API keys are stored under, one entry per provider id
+const KEYCHAIN_SERVICE: &str = "codex-desktop.model-provider";
+
+/// Ids Codex defines itself; a custom provider cannot shadow them
+const BUILT_IN_PROVIDERS: &[&str] = &["openai", "oss", "ollama", "lmstudio"];
+
+/// `test_provider_connection` gives up after this long
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A provider as shown in the settings UI
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelProviderInfo {
+    #[serde(flatten)]
+    pub provider: CustomModelProvider,
+    /// Environment variable the app-server reads the API key from
+    pub env_key: String,
+    pub has_api_key: bool,
+}
+
+/// Result of `test_provider_connection`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConnectionTest {
+    pub ok: bool,
+    /// HTTP status of `GET <base_url>/models`; `None` when no response came
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    /// Model ids the endpoint reported
+    pub models: Vec<String>,
+    /// Configured models the endpoint did not report
+    pub missing_models: Vec<String>,
+    pub message: String,
+}
+
+/// Environment variable holding a provider's API key
+///
+/// `-` and `_` both become `_`, so `check_unique` keeps ids that differ
+/// only there from sharing a variable.
+pub fn env_key(provider_id: &str) -> String {
+    format!(
+        "CODEX_DESKTOP_{}_API_KEY",
+        provider_id.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+/// Reject `provider` when another of `providers` would read its API key
+/// from the same environment variable
+pub(crate) fn check_unique(
+    providers: &[CustomModelProvider],
+    provider: &CustomModelProvider,
+) -> Result<()> {
+    let key = env_key(&provider.id);
+    match providers
+        .iter()
+        .find(|other| other.id != provider.id && env_key(&other.id) == key)
+    {
+        Some(other) => Err(Error::Other(format!(
+            "Provider id '{}' clashes with '{}'; ids must differ in more than '-' and '_'",
+            provider.id, other.id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Trim a provider and reject one Codex could not use
+pub(crate) fn normalize(provider: CustomModelProvider) -> Result<CustomModelProvider> {
+    let mut provider = provider;
+    provider.id = provider.id.trim().to_string();
+    provider.name = provider.name.trim().to_string();
+    provider.base_url = provider.base_url.trim().trim_end_matches('/').to_string();
+    provider.wire_api = provider.wire_api.trim().to_string();
+    let mut models: Vec<String> = Vec::new();
+    for model in provider.models.iter().map(|model| model.trim()) {
+        if !model.is_empty() && !models.iter().any(|m| m == model) {
+            models.push(model.to_string());
+        }
+    }
+    provider.models = models;
+
+    if provider.id.is_empty()
+        || !provider
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'))
+    {
+        return Err(Error::Other(format!(
+            "Invalid provider id '{}': use lowercase letters, digits, '-' and '_'",
+            provider.id
+        )));
+    }
+    if BUILT_IN_PROVIDERS.contains(&provider.id.as_str()) {
+        return Err(Error::Other(format!(
+            "'{}' is a built-in Codex provider",
+            provider.id
+        )));
+    }
+    if provider.name.is_empty() {
+        provider.name = provider.id.clone();
+    }
+    if !(provider.base_url.starts_with("http://") || provider.base_url.starts_with("https://"))
+        || provider.base_url.contains(char::is_whitespace)
+    {
+        return Err(Error::Other(format!(
+            "Base URL must be an http(s) URL: {}",
+            provider.base_url
+        )));
+    }
+    if !matches!(provider.wire_api.as_str(), "chat" | "responses") {
+        return Err(Error::Other(format!(
+            "Wire API must be 'chat' or 'responses', not '{}'",
+            provider.wire_api
+        )));
+    }
+    Ok(provider)
+}
+
+/// The `model_providers.<id>` table written to the Codex config
+pub(crate) fn config_value(provider: &CustomModelProvider) -> JsonValue {
+    serde_json::json!({
+        "name": provider.name,
+        "base_url": provider.base_url,
+        "env_key": env_key(&provider.id),
+        "wire_api": provider.wire_api,
+    })
+}
+
+fn keychain_entry(provider_id: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, provider_id)
+        .map_err(|e| Error::Other(format!("Keychain unavailable: {e}")))
+}
+
+/// A provider's API key, or `None` when none is stored
+pub fn api_key(provider_id: &str) -> Result<Option<String>> {
+    match keychain_entry(provider_id)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(Error::Other(format!("Failed to read API key: {e}"))),
+    }
+}
+
+/// Store (or with `None`, remove) a provider's API key
+pub fn set_api_key(provider_id: &str, key: Option<&str>) -> Result<()> {
+    let entry = keychain_entry(provider_id)?;
+    let result = match key.map(str::trim).filter(|key| !key.is_empty()) {
+        Some(key) => entry.set_password(key),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            result => result,
+        },
+    };
+    result.map_err(|e| Error::Other(format!("Failed to store API key: {e}")))
+}
+
+/// Change a provider's stored API key and restart a running app-server so
+/// it picks the key up
+///
+/// The restart would interrupt running turns, so the change is refused
+/// while any are in flight.
+pub async fn update_api_key(
+    state: &AppState,
+    provider_id: &str,
+    key: Option<String>,
+) -> Result<()> {
+    let is_running = {
+        let server = state.app_server.read().await;
+        server.as_ref().is_some_and(|s| s.is_running())
+    };
+    if is_running && !state.active_turns.list().is_empty() {
+        return Err(Error::Other(
+            "Changing an API key restarts the app server; wait for running turns to finish"
+                .to_string(),
+        ));
+    }
+
+    let id = provider_id.to_string();
+    crate::utils::spawn_blocking_io(move || set_api_key(&id, key.as_deref())).await?;
+    if is_running {
+        state.restart_app_server().await?;
+    }
+    Ok(())
+}
+
+/// Providers with their key status
+pub fn list(providers: &[CustomModelProvider]) -> Vec<ModelProviderInfo> {
+    providers
+        .iter()
+        .map(|provider| ModelProviderInfo {
+            provider: provider.clone(),
+            env_key: env_key(&provider.id),
+            has_api_key: api_key(&provider.id).ok().flatten().is_some(),
+        })
+        .collect()
+}
+
+/// Environment variables carrying the stored API keys, for spawning the
+/// app-server
+///
+/// Keys that cannot be read are left out (and logged); threads on those
+/// providers then fail the check in `apply_to_thread_start`.
+pub fn provider_env(providers: &[CustomModelProvider]) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    for provider in providers {
+        match api_key(&provider.id) {
+            Ok(Some(key)) => {
+                env.insert(env_key(&provider.id), key);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("No API key for provider {}: {}", provider.id, e),
+        }
+    }
+    env
+}
+
+/// Write a provider to the Codex config, or with `None` remove it
+pub async fn write_to_config(
+    state: &AppState,
+    provider_id: &str,
+    provider: Option<&CustomModelProvider>,
+) -> Result<()> {
+    state.start_app_server().await?;
+    let mut server = state.app_server.write().await;
+    let server = server
+        .as_mut()
+        .ok_or_else(|| Error::AppServer("App server not running".to_string()))?;
+    let params = serde_json::json!({
+        "key": format!("model_providers.{provider_id}"),
+        "value": provider.map(config_value).unwrap_or(JsonValue::Null),
+    });
+    let _: JsonValue = server.send_request("config/write", params).await?;
+    Ok(())
+}
+
+/// Model ids in an OpenAI-style `/models` response
+fn parse_model_ids(body: &JsonValue) -> Vec<String> {
+    body.get("data")
+        .or_else(|| body.get("models"))
+        .and_then(JsonValue::as_array)
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|model| {
+                    model
+                        .get("id")
+                        .or_else(|| model.get("name"))
+                        .and_then(JsonValue::as_str)
+                        .map(str::to_string)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Call `GET <base_url>/models` with the provider's key
+///
+/// Failures are reported in the result rather than as an error so the UI
+/// can show them next to the form.
+pub async fn test_connection(
+    provider: &CustomModelProvider,
+    key: Option<&str>,
+) -> ProviderConnectionTest {
+    let mut test = ProviderConnectionTest {
+        ok: false,
+        status_code: None,
+        latency_ms: 0,
+        models: Vec::new(),
+        missing_models: Vec::new(),
+        message: String::new(),
+    };
+    let client = match reqwest::Client::builder()
+        .timeout(CONNECTION_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            test.message = format!("Failed to create HTTP client: {e}");
+            return test;
+        }
+    };
+    let mut request = client.get(format!("{}/models", provider.base_url));
+    if let Some(key) = key {
+        request = request.bearer_auth(key);
+    }
+
+    let started = Instant::now();
+    let response = request.send().await;
+    test.latency_ms = started.elapsed().as_millis() as u64;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            test.message = if e.is_timeout() {
+                format!("No response within {}s", CONNECTION_TIMEOUT.as_secs())
+            } else {
+                format!("Could not connect: {e}")
+            };
+            return test;
+        }
+    };
+
+    let status = response.status();
+    test.status_code = Some(status.as_u16());
+    if !status.is_success() {
+        test.message = match status.as_u16() {
+            401 | 403 => format!("Authentication failed ({status}); check the API key"),
+            404 => format!("{}/models not found; check the base URL", provider.base_url),
+            _ => format!("Endpoint returned {status}"),
+        };
+        return test;
+    }
+
+    test.models = match response.json::<JsonValue>().await {
+        Ok(body) => parse_model_ids(&body),
+        Err(_) => Vec::new(),
+    };
+    test.missing_models = provider
+        .models
+        .iter()
+        .filter(|model| !test.models.is_empty() && !test.models.contains(model))
+        .cloned()
+        .collect();
+    test.ok = true;
+    test.message = if test.missing_models.is_empty() {
+        format!("Connected ({} models available)", test.models.len())
+    } else {
+        format!(
+            "Connected, but the endpoint does not list: {}",
+            test.missing_models.join(", ")
+        )
+    };
+    test
+}
+
+/// Check a provider can serve `model` and pick the model to use
+fn resolve_model(provider: &CustomModelProvider, model: Option<String>) -> Result<Option<String>> {
+    match model {
+        Some(model) if !provider.models.is_empty() && !provider.models.contains(&model) => {
+            Err(Error::Other(format!(
+                "Model '{model}' is not configured for provider '{}'",
+                provider.name
+            )))
+        }
+        Some(model) => Ok(Some(model)),
+        None => Ok(provider.models.first().cloned()),
+    }
+}
+
+/// Route a new thread to the project's (or an explicitly requested) custom
+/// provider
+///
+/// Fails when the provider was removed, has no API key although it needs
+/// one, or does not offer the requested model, so the thread does not start
+/// against the wrong endpoint.
+pub fn apply_to_thread_start(
+    state: &AppState,
+    project_id: &str,
+    params: &mut ThreadStartParams,
+) -> Result<()> {
+    let provider_id = match params.model_provider.clone() {
+        Some(id) => Some(id),
+        None if project_id.is_empty() => None,
+        None => state
+            .database
+            .get_project(project_id)?
+            .and_then(|project| project.settings().model_provider),
+    };
+    let Some(provider_id) = provider_id.filter(|id| !id.is_empty()) else {
+        return Ok(());
+    };
+    if BUILT_IN_PROVIDERS.contains(&provider_id.as_str()) {
+        params.model_provider = Some(provider_id);
+        return Ok(());
+    }
+
+    let provider = state
+        .global_state
+        .read(|global| {
+            global
+                .model_providers
+                .providers
+                .iter()
+                .find(|provider| provider.id == provider_id)
+                .cloned()
+        })
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "Model provider '{provider_id}' is not configured; add it again or pick another provider for this project"
+            ))
+        })?;
+    if provider.requires_api_key && api_key(&provider.id)?.is_none() {
+        return Err(Error::Other(format!(
+            "No API key stored for model provider '{}'",
+            provider.name
+        )));
+    }
+    params.model = resolve_model(&provider, params.model.take())?;
+    params.model_provider = Some(provider.id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(id: &str) -> CustomModelProvider {
+        CustomModelProvider {
+            id: id.to_string(),
+            base_url: "http://localhost:11434/v1/".to_string(),
+            models: vec![
+                " qwen3 ".to_string(),
+                "llama4".to_string(),
+                "qwen3".to_string(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_provider() {
+        let normalized = normalize(provider("local-llm")).unwrap();
+        assert_eq!(normalized.name, "local-llm");
+        assert_eq!(normalized.base_url, "http://localhost:11434/v1");
+        assert_eq!(normalized.models, vec!["qwen3", "llama4"]);
+        assert_eq!(env_key("local-llm"), "CODEX_DESKTOP_LOCAL_LLM_API_KEY");
+
+        assert!(normalize(provider("Local")).is_err());
+        assert!(normalize(provider("openai")).is_err());
+        let mut bad_url = provider("x");
+        bad_url.base_url = "localhost:8080".to_string();
+        assert!(normalize(bad_url).is_err());
+        let mut bad_wire = provider("x");
+        bad_wire.wire_api = "grpc".to_string();
+        assert!(normalize(bad_wire).is_err());
+    }
+
+    #[test]
+    fn test_env_keys_stay_unique() {
+        let saved = vec![provider("my-x"), provider("other")];
+        assert_eq!(env_key("my-x"), env_key("my_x"));
+        assert!(check_unique(&saved, &provider("my_x")).is_err());
+        assert!(check_unique(&saved, &provider("my-x")).is_ok());
+        assert!(check_unique(&saved, &provider("my-y")).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_model_and_parse_ids() {
+        let provider = normalize(provider("local")).unwrap();
+        assert_eq!(
+            resolve_model(&provider, None).unwrap().as_deref(),
+            Some("qwen3")
+        );
+        assert!(resolve_model(&provider, Some("gpt-5".to_string())).is_err());
+
+        let body = serde_json::json!({ "data": [{ "id": "qwen3" }, { "id": "llama4" }] });
+        assert_eq!(parse_model_ids(&body), vec!["qwen3", "llama4"]);
+        let ollama = serde_json::json!({ "models": [{ "name": "qwen3" }] });
+        assert_eq!(parse_model_ids(&ollama), vec!["qwen3"]);
+    }
+}
@@ -0,0 +1,179 @@
+//! Native OS notifications raised by the backend.
+//!
+//! Finished turns, approval requests, app-server crashes and answers given
+//! with an approval shortcut are shown through the notification plugin, so
+//! they appear while the window is hidden or the renderer is reloading.
+//! `NotificationSettings` decides which kinds are shown, whether to hold
+//! them while the window has focus, and the do-not-disturb hours during
+//! which nothing is shown. The plugin is left out in safe mode.
+
+use std::sync::Arc;
+
+use chrono::Timelike;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::Notification;
+
+use crate::global_state::{GlobalStateStore, NotificationSettings, QuietHours};
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    TurnCompleted,
+    TurnFailed,
+    ApprovalRequested,
+    AppServerCrashed,
+    /// Confirms an approval shortcut; asked for by `confirm_notification`
+    /// of the shortcut settings instead
+    ApprovalAnswered,
+}
+
+impl NotificationKind {
+    fn enabled_in(self, settings: &NotificationSettings) -> bool {
+        match self {
+            Self::TurnCompleted => settings.turn_completed,
+            Self::TurnFailed => settings.errors,
+            Self::ApprovalRequested => settings.approval_requested,
+            Self::AppServerCrashed => settings.app_server_crashed,
+            Self::ApprovalAnswered => true,
+        }
+    }
+}
+
+/// Minutes past midnight of a "HH:MM" time
+fn minute_of_day(time: &str) -> Option<u16> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+fn in_quiet_hours(quiet: &QuietHours, minute: u16) -> bool {
+    let (Some(start), Some(end)) = (minute_of_day(&quiet.start), minute_of_day(&quiet.end)) else {
+        return false;
+    };
+    if start <= end {
+        (start..end).contains(&minute)
+    } else {
+        minute >= start || minute < end
+    }
+}
+
+/// Check that the do-not-disturb times read as "HH:MM"
+pub fn validate(settings: &NotificationSettings) -> Result<()> {
+    let Some(quiet) = &settings.do_not_disturb else {
+        return Ok(());
+    };
+    for time in [&quiet.start, &quiet.end] {
+        if minute_of_day(time).is_none() {
+            return Err(Error::Other(format!(
+                "Invalid do-not-disturb time \"{time}\"; use HH:MM"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `kind` is shown with the window `focused` at `minute` past
+/// local midnight
+pub fn should_show(
+    settings: &NotificationSettings,
+    kind: NotificationKind,
+    focused: bool,
+    minute: u16,
+) -> bool {
+    settings.enabled
+        && kind.enabled_in(settings)
+        && !(settings.only_when_unfocused && focused)
+        && !settings
+            .do_not_disturb
+            .as_ref()
+            .is_some_and(|quiet| in_quiet_hours(quiet, minute))
+}
+
+/// Shows notifications the settings allow
+pub struct Notifier {
+    app_handle: AppHandle,
+    global_state: Arc<GlobalStateStore>,
+}
+
+impl Notifier {
+    pub fn new(app_handle: AppHandle, global_state: Arc<GlobalStateStore>) -> Self {
+        Self {
+            app_handle,
+            global_state,
+        }
+    }
+
+    pub fn notify(&self, kind: NotificationKind, title: &str, body: &str) {
+        let settings = self
+            .global_state
+            .read(|global| global.notifications.clone());
+        let focused = self
+            .app_handle
+            .get_webview_window("main")
+            .and_then(|window| window.is_focused().ok())
+            .unwrap_or(false);
+        let now = chrono::Local::now();
+        let minute = (now.hour() * 60 + now.minute()) as u16;
+        if !should_show(&settings, kind, focused, minute) {
+            return;
+        }
+        let Some(notification) = self.app_handle.try_state::<Notification<tauri::Wry>>() else {
+            return;
+        };
+        let mut builder = notification.builder().title(title).body(body);
+        if settings.sound {
+            builder = builder.sound("default");
+        }
+        if let Err(err) = builder.show() {
+            tracing::warn!("Failed to show {:?} notification: {}", kind, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet(start: &str, end: &str) -> Option<QuietHours> {
+        Some(QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_should_show() {
+        use NotificationKind::*;
+
+        let mut settings = NotificationSettings::default();
+        let noon = 12 * 60;
+        assert!(should_show(&settings, TurnCompleted, false, noon));
+        assert!(!should_show(&settings, TurnCompleted, true, noon));
+
+        settings.errors = false;
+        assert!(!should_show(&settings, TurnFailed, false, noon));
+
+        settings.do_not_disturb = quiet("22:00", "07:30");
+        assert!(!should_show(&settings, ApprovalRequested, false, 23 * 60));
+        assert!(!should_show(&settings, ApprovalRequested, false, 7 * 60));
+        let morning = 7 * 60 + 30;
+        assert!(should_show(&settings, ApprovalRequested, false, morning));
+
+        settings.do_not_disturb = quiet("13:00", "14:00");
+        assert!(should_show(&settings, AppServerCrashed, false, noon));
+
+        settings.enabled = false;
+        assert!(!should_show(&settings, ApprovalAnswered, false, noon));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut settings = NotificationSettings::default();
+        assert!(validate(&settings).is_ok());
+        settings.do_not_disturb = quiet("22:00", "7:05");
+        assert!(validate(&settings).is_ok());
+        settings.do_not_disturb = quiet("24:00", "07:00");
+        assert!(validate(&settings).is_err());
+    }
+}
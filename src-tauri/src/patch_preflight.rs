@@ -0,0 +1,736 @@
+//! Checks a patch against the repository before `git apply` runs it.
+//!
+//! `check` parses the files the patch touches, rejects paths that would
+//! land outside the project, compares the patch's line endings with the
+//! files it changes (taking `core.autocrlf`, `core.eol` and the `text`/`eol`
+//! attributes into account) and runs `git apply --check` to find hunks
+//! whose context no longer matches, or with `--3way`, which of them will
+//! be merged with conflict markers. Everything found is returned as a
+//! `PatchPreflight` rather than git's bare stderr.
+
+use std::io::Write;
+use std::path::{Component, Path};
+use std::process::{Command, Output, Stdio};
+
+use serde::Serialize;
+
+use crate::git;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchIssueKind {
+    /// The path is absolute, climbs out with `..` or goes through a symlink
+    /// that points outside the project
+    OutsideProject,
+    /// A hunk's context lines no longer match the file
+    ContextMismatch,
+    /// The patch and the file disagree on CRLF and LF
+    LineEndings,
+    /// Trailing whitespace and the like; git applies it anyway
+    Whitespace,
+    /// A file the patch creates is already there
+    AlreadyExists,
+    /// A file the patch changes or deletes is not there
+    Missing,
+    /// A three-way apply will leave conflict markers in the file
+    Conflict,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    /// The patch will not apply
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchIssue {
+    pub kind: PatchIssueKind,
+    pub severity: IssueSeverity,
+    pub path: Option<String>,
+    /// Line in the file for context mismatches, in the patch for whitespace
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+/// How `git apply` is asked to apply the patch
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApplyOptions {
+    /// Apply to the index only
+    pub cached: bool,
+    pub reverse: bool,
+    /// Fall back to a three-way merge where the context drifted
+    pub three_way: bool,
+}
+
+impl ApplyOptions {
+    /// `git apply` arguments for these options
+    pub fn args(self) -> Vec<&'static str> {
+        let mut args = Vec::new();
+        if self.cached {
+            args.push("--cached");
+        }
+        if self.reverse {
+            args.push("--reverse");
+        }
+        if self.three_way {
+            args.push("--3way");
+        }
+        args
+    }
+}
+
+/// A file touched by the patch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFile {
+    /// Path after the patch, relative to the project
+    pub path: String,
+    /// Path before the patch, when it is renamed
+    pub old_path: Option<String>,
+    pub created: bool,
+    pub deleted: bool,
+    /// Hunk lines ending in CRLF
+    pub crlf_lines: u32,
+    /// Hunk lines ending in a bare LF
+    pub lf_lines: u32,
+    /// Context and removed lines that end in CRLF, i.e. what the patch
+    /// expects the file to contain
+    #[serde(skip)]
+    expected_crlf: u32,
+    #[serde(skip)]
+    expected_lf: u32,
+}
+
+/// What `check` found
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchPreflight {
+    /// No issue is an error
+    pub applies: bool,
+    pub files: Vec<PatchFile>,
+    pub issues: Vec<PatchIssue>,
+    /// `core.autocrlf`, when set
+    pub autocrlf: Option<String>,
+    /// `core.eol`, when set
+    pub core_eol: Option<String>,
+}
+
+impl PatchPreflight {
+    /// The errors, one per line
+    pub fn summary(&self) -> String {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .map(|issue| issue.message.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn issue(
+    kind: PatchIssueKind,
+    severity: IssueSeverity,
+    path: Option<&str>,
+    line: Option<u32>,
+    message: String,
+) -> PatchIssue {
+    PatchIssue {
+        kind,
+        severity,
+        path: path.map(str::to_string),
+        line,
+        message,
+    }
+}
+
+/// Files in a unified or git diff, in patch order
+pub fn parse_files(patch: &str, reverse: bool) -> Vec<PatchFile> {
+    let mut files: Vec<PatchFile> = Vec::new();
+    // Old and new lines left in the current hunk
+    let mut remaining = (0u32, 0u32);
+    // A git header opens a file; `---` only opens one in plain diffs
+    let mut git_header = false;
+
+    for line in patch.split_inclusive('\n') {
+        let crlf = line.ends_with("\r\n");
+        let text = line.trim_end_matches(['\n', '\r']);
+
+        if remaining != (0, 0) {
+            let (expected, old, new) = match text.chars().next() {
+                Some(' ') | None => (true, 1, 1),
+                Some('-') => (!reverse, 1, 0),
+                Some('+') => (reverse, 0, 1),
+                // "\ No newline at end of file"
+                _ => continue,
+            };
+            remaining = (remaining.0.saturating_sub(old), remaining.1.saturating_sub(new));
+            let Some(file) = files.last_mut() else {
+                continue;
+            };
+            match (crlf, expected) {
+                (true, true) => {
+                    file.crlf_lines += 1;
+                    file.expected_crlf += 1;
+                }
+                (true, false) => file.crlf_lines += 1,
+                (false, true) => {
+                    file.lf_lines += 1;
+                    file.expected_lf += 1;
+                }
+                (false, false) => file.lf_lines += 1,
+            }
+            continue;
+        }
+
+        if let Some(rest) = text.strip_prefix("diff --git ") {
+            git_header = true;
+            files.push(PatchFile {
+                path: git_header_path(rest).unwrap_or_default(),
+                ..PatchFile::default()
+            });
+            continue;
+        }
+        if let Some(header) = text.strip_prefix("@@ ") {
+            remaining = hunk_lengths(header).unwrap_or_default();
+            continue;
+        }
+
+        if let Some(old) = text.strip_prefix("--- ") {
+            if !git_header {
+                files.push(PatchFile::default());
+            }
+            git_header = false;
+            if let Some(file) = files.last_mut() {
+                match diff_path(old) {
+                    Some(old) if file.path.is_empty() => file.path = old,
+                    Some(_) => {}
+                    None => file.created = true,
+                }
+            }
+        } else if let Some(new) = text.strip_prefix("+++ ") {
+            if let Some(file) = files.last_mut() {
+                match diff_path(new) {
+                    Some(new) => file.path = new,
+                    None => file.deleted = true,
+                }
+            }
+        } else if let Some(file) = files.last_mut() {
+            if text.starts_with("new file mode") {
+                file.created = true;
+            } else if text.starts_with("deleted file mode") {
+                file.deleted = true;
+            } else if let Some(from) = text.strip_prefix("rename from ") {
+                file.old_path = Some(unquote(from));
+            } else if let Some(to) = text.strip_prefix("rename to ") {
+                file.path = unquote(to);
+            }
+        }
+    }
+
+    if reverse {
+        for file in &mut files {
+            std::mem::swap(&mut file.created, &mut file.deleted);
+        }
+    }
+    files.retain(|file| !file.path.is_empty());
+    files
+}
+
+/// Old and new line counts of a `@@ -1,2 +1,3 @@` header (after `@@ `)
+fn hunk_lengths(header: &str) -> Option<(u32, u32)> {
+    let mut ranges = header.split_whitespace();
+    let length = |range: &str| -> Option<u32> {
+        match range.split_once(',') {
+            Some((_, length)) => length.parse().ok(),
+            None => Some(1),
+        }
+    };
+    let old = length(ranges.next()?.strip_prefix('-')?)?;
+    let new = length(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// `b/<path>` of `diff --git a/<path> b/<path>` when both sides agree
+fn git_header_path(rest: &str) -> Option<String> {
+    let rest = rest.strip_prefix("a/")?;
+    let half = rest.len().checked_sub(3)? / 2;
+    let (old, new) = (rest.get(..half)?, rest.get(half..)?);
+    (new.strip_prefix(" b/")? == old).then(|| old.to_string())
+}
+
+/// Path of a `---`/`+++` line; `None` for /dev/null
+fn diff_path(value: &str) -> Option<String> {
+    // Plain diffs put a tab and a timestamp after the name
+    let value = value.split('\t').next().unwrap_or(value);
+    let value = unquote(value);
+    if value == "/dev/null" {
+        return None;
+    }
+    Some(
+        value
+            .strip_prefix("a/")
+            .or_else(|| value.strip_prefix("b/"))
+            .unwrap_or(&value)
+            .to_string(),
+    )
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// Whether `path` from the patch would be written outside `root`, which
+/// must be canonical
+pub fn escapes_root(root: &Path, path: &str) -> bool {
+    let relative = Path::new(path);
+    let mut depth = 0usize;
+    for component in relative.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if depth == 0 {
+                    return true;
+                }
+                depth -= 1;
+            }
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    // A symlinked directory on the way may point elsewhere
+    let mut existing = root.join(relative);
+    while !existing.exists() {
+        if !existing.pop() {
+            return false;
+        }
+    }
+    existing
+        .canonicalize()
+        .is_ok_and(|resolved| !resolved.starts_with(root))
+}
+
+/// Run `git apply` with `args`, feeding it the patch
+pub fn git_apply(root: &Path, patch: &str, args: &[&str]) -> Result<Output> {
+    let mut child = Command::new("git")
+        .arg("apply")
+        .args(args)
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::Other(format!("Failed to spawn git apply: {err}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(patch.as_bytes())
+            .map_err(|err| Error::Other(format!("Failed to write patch to stdin: {err}")))?;
+    }
+
+    child
+        .wait_with_output()
+        .map_err(|err| Error::Other(format!("Failed to wait for git apply: {err}")))
+}
+
+/// Issues in `git apply --check` output
+pub fn parse_check_output(stderr: &str) -> Vec<PatchIssue> {
+    let mut issues: Vec<PatchIssue> = Vec::new();
+    for line in stderr.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("error: patch failed: ") {
+            let (path, number) = match rest.rsplit_once(':') {
+                Some((path, number)) => (path, number.parse().ok()),
+                None => (rest, None),
+            };
+            issues.push(issue(
+                PatchIssueKind::ContextMismatch,
+                IssueSeverity::Error,
+                Some(path),
+                number,
+                match number {
+                    Some(number) => format!("{path}: context at line {number} does not match"),
+                    None => format!("{path}: context does not match"),
+                },
+            ));
+        } else if let Some(rest) = line.strip_prefix("error: ") {
+            let Some((path, reason)) = rest.split_once(": ") else {
+                issues.push(issue(
+                    PatchIssueKind::Other,
+                    IssueSeverity::Error,
+                    None,
+                    None,
+                    rest.to_string(),
+                ));
+                continue;
+            };
+            let kind = if reason == "patch does not apply" {
+                // Reported with its line by the `patch failed` line before
+                if issues.iter().any(|issue| issue.path.as_deref() == Some(path)) {
+                    continue;
+                }
+                PatchIssueKind::ContextMismatch
+            } else if reason.starts_with("already exists") {
+                PatchIssueKind::AlreadyExists
+            } else if reason.starts_with("No such file")
+                || reason.starts_with("does not exist")
+            {
+                PatchIssueKind::Missing
+            } else {
+                PatchIssueKind::Other
+            };
+            issues.push(issue(
+                kind,
+                IssueSeverity::Error,
+                Some(path),
+                None,
+                format!("{path}: {reason}"),
+            ));
+        } else if let Some((location, problem)) = whitespace_warning(line) {
+            issues.push(issue(
+                PatchIssueKind::Whitespace,
+                IssueSeverity::Warning,
+                None,
+                location,
+                format!("Patch line {}: {problem}", location.unwrap_or_default()),
+            ));
+        }
+    }
+    issues
+}
+
+/// `<stdin>:12: trailing whitespace.` as (12, "trailing whitespace")
+fn whitespace_warning(line: &str) -> Option<(Option<u32>, String)> {
+    let rest = line.strip_prefix("<stdin>:")?;
+    let (number, problem) = rest.split_once(": ")?;
+    let problem = problem.trim_end_matches('.');
+    let known = ["trailing whitespace", "space before tab", "new blank line", "indent with"];
+    known
+        .iter()
+        .any(|known| problem.starts_with(known))
+        .then(|| (number.parse().ok(), problem.to_string()))
+}
+
+/// `text` and `eol` attributes of `path`
+fn line_ending_attributes(root: &Path, path: &str) -> (Option<String>, Option<String>) {
+    let Ok(output) = git::run(root, &["check-attr", "text", "eol", "--", path]) else {
+        return (None, None);
+    };
+    let mut text = None;
+    let mut eol = None;
+    for line in output.lines() {
+        // "<path>: <attribute>: <value>"
+        let mut parts = line.rsplitn(3, ": ");
+        let (Some(value), Some(attribute)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if value == "unspecified" {
+            continue;
+        }
+        match attribute {
+            "text" => text = Some(value.to_string()),
+            "eol" => eol = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    (text, eol)
+}
+
+/// Whether the file the patch will be applied to uses CRLF: the index copy
+/// with `cached`, the working tree copy otherwise. `None` when it has no
+/// line endings to go by.
+fn target_uses_crlf(root: &Path, path: &str, cached: bool) -> Option<bool> {
+    let content = if cached {
+        git::run_bytes(root, &["show", &format!(":{path}")]).ok()?
+    } else {
+        std::fs::read(root.join(path)).ok()?
+    };
+    let newlines = content.iter().filter(|&&byte| byte == b'\n').count();
+    if newlines == 0 {
+        return None;
+    }
+    let crlf = content.windows(2).filter(|pair| pair == b"\r\n").count();
+    Some(crlf * 2 > newlines)
+}
+
+/// Check `patch` against the repository at `root` (canonical) the way
+/// `git apply` with `options` would apply it. Blocking.
+pub fn check(root: &Path, patch: &str, options: ApplyOptions) -> Result<PatchPreflight> {
+    let ApplyOptions { cached, three_way, .. } = options;
+    let files = parse_files(patch, options.reverse);
+    let autocrlf = git::config_value(root, "core.autocrlf");
+    let core_eol = git::config_value(root, "core.eol");
+    let mut issues = Vec::new();
+
+    if files.is_empty() {
+        issues.push(issue(
+            PatchIssueKind::Other,
+            IssueSeverity::Error,
+            None,
+            None,
+            "The patch changes no files".to_string(),
+        ));
+    }
+
+    for file in &files {
+        for path in std::iter::once(&file.path).chain(&file.old_path) {
+            if escapes_root(root, path) {
+                issues.push(issue(
+                    PatchIssueKind::OutsideProject,
+                    IssueSeverity::Error,
+                    Some(path),
+                    None,
+                    format!("{path} is outside the project"),
+                ));
+            }
+        }
+    }
+    // Never hand git a patch that writes outside the project
+    if issues.iter().any(|issue| issue.kind == PatchIssueKind::OutsideProject) {
+        return Ok(PatchPreflight {
+            applies: false,
+            files,
+            issues,
+            autocrlf,
+            core_eol,
+        });
+    }
+
+    for file in &files {
+        if file.crlf_lines > 0 && file.lf_lines > 0 {
+            issues.push(issue(
+                PatchIssueKind::LineEndings,
+                IssueSeverity::Warning,
+                Some(&file.path),
+                None,
+                format!(
+                    "{}: the patch mixes CRLF and LF lines ({} and {})",
+                    file.path, file.crlf_lines, file.lf_lines
+                ),
+            ));
+        }
+        if file.created || file.expected_crlf + file.expected_lf == 0 {
+            continue;
+        }
+        let Some(target_crlf) = target_uses_crlf(root, &file.path, cached) else {
+            continue;
+        };
+        let patch_crlf = file.expected_crlf > file.expected_lf;
+        if patch_crlf == target_crlf {
+            continue;
+        }
+        let (text, eol) = line_ending_attributes(root, &file.path);
+        let (file_endings, patch_endings) = if target_crlf {
+            ("CRLF", "LF")
+        } else {
+            ("LF", "CRLF")
+        };
+        let cause = if target_crlf && !cached && autocrlf.as_deref() == Some("true") {
+            " core.autocrlf=true checks text files out with CRLF; a patch taken from the \
+             index or another checkout has LF."
+                .to_string()
+        } else if let Some(eol) = eol {
+            format!(" .gitattributes sets eol={eol} for it.")
+        } else if text.as_deref() == Some("unset") {
+            " .gitattributes marks it as binary (-text), so its line endings are kept as is."
+                .to_string()
+        } else {
+            String::new()
+        };
+        // A three-way merge still goes through, with the region in conflict
+        let severity = if three_way {
+            IssueSeverity::Warning
+        } else {
+            IssueSeverity::Error
+        };
+        issues.push(issue(
+            PatchIssueKind::LineEndings,
+            severity,
+            Some(&file.path),
+            None,
+            format!(
+                "{}: the file uses {file_endings} line endings but the patch has \
+                 {patch_endings}.{cause}",
+                file.path
+            ),
+        ));
+    }
+
+    let mut args = vec!["--check", "--whitespace=warn"];
+    args.extend(options.args());
+    let output = git_apply(root, patch, &args)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // `--check --3way` reports conflicts but still succeeds
+    for path in crate::conflict_markers::conflicted_paths(&stderr) {
+        issues.push(issue(
+            PatchIssueKind::Conflict,
+            IssueSeverity::Warning,
+            Some(&path),
+            None,
+            format!("{path}: will be merged with conflict markers"),
+        ));
+    }
+    for found in parse_check_output(&stderr) {
+        // A line ending mismatch already explains the failed context
+        let explained = found.kind == PatchIssueKind::ContextMismatch
+            && issues.iter().any(|known| {
+                known.kind == PatchIssueKind::LineEndings
+                    && known.severity == IssueSeverity::Error
+                    && known.path == found.path
+            });
+        if !explained {
+            issues.push(found);
+        }
+    }
+    if !output.status.success()
+        && !issues.iter().any(|issue| issue.severity == IssueSeverity::Error)
+    {
+        issues.push(issue(
+            PatchIssueKind::Other,
+            IssueSeverity::Error,
+            None,
+            None,
+            format!("git apply --check failed: {}", stderr.trim()),
+        ));
+    }
+
+    Ok(PatchPreflight {
+        applies: !issues.iter().any(|issue| issue.severity == IssueSeverity::Error),
+        files,
+        issues,
+        autocrlf,
+        core_eol,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = concat!(
+        "diff --git a/src/lib.rs b/src/lib.rs\n",
+        "index 1111111..2222222 100644\n",
+        "--- a/src/lib.rs\n",
+        "+++ b/src/lib.rs\n",
+        "@@ -1,2 +1,2 @@\n",
+        " fn main() {\n",
+        "-}\n",
+        "+    run();\n",
+        "diff --git a/notes.txt b/notes.txt\n",
+        "new file mode 100644\n",
+        "--- /dev/null\n",
+        "+++ b/notes.txt\n",
+        "@@ -0,0 +1 @@\n",
+        "+hello\r\n",
+    );
+
+    #[test]
+    fn test_parse_files() {
+        let files = parse_files(PATCH, false);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert!(!files[0].created);
+        assert_eq!((files[0].crlf_lines, files[0].lf_lines), (0, 3));
+        assert_eq!(files[1].path, "notes.txt");
+        assert!(files[1].created);
+        assert_eq!(files[1].crlf_lines, 1);
+
+        let reversed = parse_files(PATCH, true);
+        assert!(reversed[1].deleted && !reversed[1].created);
+    }
+
+    #[test]
+    fn test_escapes_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        assert!(!escapes_root(&root, "src/new/file.rs"));
+        assert!(!escapes_root(&root, "src/../README.md"));
+        assert!(escapes_root(&root, "../outside.txt"));
+        assert!(escapes_root(&root, "/etc/passwd"));
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            std::os::unix::fs::symlink(outside.path(), root.join("link")).unwrap();
+            assert!(escapes_root(&root, "link/file.txt"));
+        }
+    }
+
+    #[test]
+    fn test_parse_check_output() {
+        let stderr = "<stdin>:9: trailing whitespace.\n\
+            error: patch failed: src/lib.rs:1\n\
+            error: src/lib.rs: patch does not apply\n\
+            error: notes.txt: already exists in working directory\n";
+        let issues = parse_check_output(stderr);
+        let kinds: Vec<_> = issues.iter().map(|issue| issue.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PatchIssueKind::Whitespace,
+                PatchIssueKind::ContextMismatch,
+                PatchIssueKind::AlreadyExists,
+            ]
+        );
+        assert_eq!(issues[0].severity, IssueSeverity::Warning);
+        assert_eq!(issues[1].line, Some(1));
+    }
+
+    #[test]
+    fn test_check_reports_crlf_mismatch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        git::run(&root, &["init", "-q"]).unwrap();
+        std::fs::write(root.join("a.txt"), "one\r\ntwo\r\n").unwrap();
+
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n";
+        let preflight = check(&root, patch, ApplyOptions::default()).unwrap();
+        assert!(!preflight.applies);
+        assert_eq!(preflight.issues.len(), 1, "{:?}", preflight.issues);
+        assert_eq!(preflight.issues[0].kind, PatchIssueKind::LineEndings);
+
+        let outside = "--- /dev/null\n+++ b/../x.txt\n@@ -0,0 +1 @@\n+x\n";
+        let preflight = check(&root, outside, ApplyOptions::default()).unwrap();
+        assert_eq!(preflight.issues[0].kind, PatchIssueKind::OutsideProject);
+        assert!(!root.parent().unwrap().join("x.txt").exists());
+    }
+
+    #[test]
+    fn test_three_way_check_reports_conflicts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let git = |args: &[&str]| git::run(&root, args).unwrap();
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "user.name", "Test"]);
+        std::fs::write(root.join("f.txt"), "a\nb\nc\n").unwrap();
+        git(&["add", "f.txt"]);
+        git(&["commit", "-q", "-m", "base"]);
+        std::fs::write(root.join("f.txt"), "a\nB\nc\n").unwrap();
+        let patch = git(&["diff"]);
+        std::fs::write(root.join("f.txt"), "a\nX\nc\n").unwrap();
+        git(&["commit", "-q", "-a", "-m", "drift"]);
+
+        let plain = check(&root, &patch, ApplyOptions::default()).unwrap();
+        assert!(!plain.applies);
+        assert_eq!(plain.issues[0].kind, PatchIssueKind::ContextMismatch);
+
+        let options = ApplyOptions {
+            three_way: true,
+            ..ApplyOptions::default()
+        };
+        let three_way = check(&root, &patch, options).unwrap();
+        assert!(three_way.applies, "{:?}", three_way.issues);
+        assert_eq!(three_way.issues[0].kind, PatchIssueKind::Conflict);
+        assert_eq!(three_way.issues[0].path.as_deref(), Some("f.txt"));
+    }
+}
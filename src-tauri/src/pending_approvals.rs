@@ -0,0 +1,229 @@
+//! Approval requests the app-server is waiting on an answer for.
+//!
+//! A request is recorded when it is handed to the renderer and dropped once
+//! it is answered, its item or turn completes, or the app-server goes away,
+//! so the backend can answer the newest one itself (see
+//! `approval_shortcuts`).
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::approvals::{command_text, RiskLevel};
+
+/// Oldest requests are dropped beyond this many
+const MAX_PENDING: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingApproval {
+    /// JSON-RPC request ID to answer
+    pub request_id: u64,
+    /// e.g. `item/commandExecution/requestApproval`
+    pub method: String,
+    pub thread_id: Option<String>,
+    pub item_id: Option<String>,
+    /// The command to run, or the reason given for a file change
+    pub summary: Option<String>,
+    /// Risk of a command from its `_commandContext`; `None` for file changes
+    pub risk: Option<RiskLevel>,
+    /// The command matches a built-in deny rule or the project denylist
+    pub deny_matched: bool,
+    /// Unix milliseconds
+    pub received_at: i64,
+}
+
+impl PendingApproval {
+    pub fn from_request(request_id: u64, method: &str, params: &JsonValue) -> Self {
+        let text = |key: &str| {
+            params
+                .get(key)
+                .and_then(JsonValue::as_str)
+                .map(str::to_string)
+        };
+        let summary = params
+            .pointer("/_commandContext/command")
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+            .or_else(|| command_text(params.get("command")))
+            .or_else(|| text("reason").filter(|reason| !reason.trim().is_empty()));
+        let risk = params
+            .pointer("/_commandContext/risk")
+            .and_then(|risk| serde_json::from_value(risk.clone()).ok());
+        let deny_matched = params
+            .pointer("/_commandContext/denyMatches")
+            .and_then(JsonValue::as_array)
+            .is_some_and(|matches| !matches.is_empty());
+        Self {
+            request_id,
+            method: method.to_string(),
+            thread_id: text("threadId"),
+            item_id: text("itemId"),
+            summary,
+            risk,
+            deny_matched,
+            received_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    /// Whether the request should be looked at before it is approved
+    pub fn needs_review(&self) -> bool {
+        self.deny_matched || self.risk == Some(RiskLevel::High)
+    }
+}
+
+/// Whether a server-initiated request asks for an approval
+pub fn is_approval_request(method: &str) -> bool {
+    method.ends_with("/requestApproval")
+}
+
+/// Unanswered approval requests, oldest first
+#[derive(Default)]
+pub struct PendingApprovals {
+    entries: Mutex<Vec<PendingApproval>>,
+}
+
+impl PendingApprovals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, approval: PendingApproval) {
+        let mut entries = self.entries.lock();
+        entries.retain(|entry| entry.request_id != approval.request_id);
+        if entries.len() >= MAX_PENDING {
+            entries.remove(0);
+        }
+        // Kept in order of arrival, also for a request put back by `restore`
+        let index = entries.partition_point(|entry| entry.received_at <= approval.received_at);
+        entries.insert(index, approval);
+    }
+
+    /// Drop an answered request, returning it if it was pending
+    pub fn remove(&self, request_id: u64) -> Option<PendingApproval> {
+        let mut entries = self.entries.lock();
+        let index = entries
+            .iter()
+            .position(|entry| entry.request_id == request_id)?;
+        Some(entries.remove(index))
+    }
+
+    /// Drop requests for an item that has completed
+    pub fn remove_item(&self, item_id: &str) {
+        self.entries
+            .lock()
+            .retain(|entry| entry.item_id.as_deref() != Some(item_id));
+    }
+
+    /// Drop requests for a thread whose turn has completed, returning when
+    /// the oldest of them was received
+    pub fn remove_thread(&self, thread_id: &str) -> Option<i64> {
+        let mut oldest = None;
+        self.entries.lock().retain(|entry| {
+            if entry.thread_id.as_deref() != Some(thread_id) {
+                return true;
+            }
+            oldest = Some(oldest.map_or(entry.received_at, |at: i64| at.min(entry.received_at)));
+            false
+        });
+        oldest
+    }
+
+    /// Drop everything, when the app-server that asked is gone
+    pub fn clear(&self) {
+        self.entries.lock().clear();
+    }
+
+    /// The most recently received request
+    pub fn latest(&self) -> Option<PendingApproval> {
+        self.entries.lock().last().cloned()
+    }
+
+    pub fn list(&self) -> Vec<PendingApproval> {
+        self.entries.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pending_approvals() {
+        let pending = PendingApprovals::new();
+        assert!(pending.latest().is_none());
+
+        let command = PendingApproval::from_request(
+            1,
+            "item/commandExecution/requestApproval",
+            &json!({"threadId": "t1", "itemId": "i1", "command": ["git", "push"]}),
+        );
+        assert_eq!(command.summary.as_deref(), Some("git push"));
+        pending.record(command);
+        pending.record(PendingApproval::from_request(
+            2,
+            "item/fileChange/requestApproval",
+            &json!({"threadId": "t2", "itemId": "i2", "reason": "Write outside the project"}),
+        ));
+        pending.record(PendingApproval::from_request(
+            3,
+            "item/commandExecution/requestApproval",
+            &json!({"threadId": "t2", "itemId": "i3",
+                    "_commandContext": {"command": "rm -rf build"}}),
+        ));
+
+        assert_eq!(pending.latest().unwrap().request_id, 3);
+        assert_eq!(
+            pending.latest().unwrap().summary.as_deref(),
+            Some("rm -rf build")
+        );
+        assert_eq!(pending.remove(3).unwrap().item_id.as_deref(), Some("i3"));
+        assert!(pending.remove(3).is_none());
+        assert_eq!(
+            pending.latest().unwrap().summary.as_deref(),
+            Some("Write outside the project")
+        );
+
+        pending.remove_item("i2");
+        assert_eq!(pending.latest().unwrap().request_id, 1);
+        pending.record(PendingApproval::from_request(
+            4,
+            "x/requestApproval",
+            &json!({"threadId": "t1"}),
+        ));
+        assert!(pending.remove_thread("t1").is_some());
+        assert!(pending.list().is_empty());
+        assert_eq!(pending.remove_thread("t1"), None);
+
+        let risky = PendingApproval::from_request(
+            5,
+            "item/commandExecution/requestApproval",
+            &json!({"_commandContext": {"command": "rm -rf /", "risk": "high", "denyMatches": []}}),
+        );
+        assert_eq!(risky.risk, Some(RiskLevel::High));
+        assert!(risky.needs_review());
+        let denied = PendingApproval::from_request(
+            6,
+            "item/commandExecution/requestApproval",
+            &json!({"_commandContext": {"risk": "medium", "denyMatches": ["Denylisted (npm *)"]}}),
+        );
+        assert!(denied.deny_matched && denied.needs_review());
+        let change =
+            PendingApproval::from_request(7, "item/fileChange/requestApproval", &json!({}));
+        assert_eq!(change.risk, None);
+        assert!(!change.needs_review());
+
+        // A request put back after a failed answer keeps its place
+        let mut older = change.clone();
+        older.request_id = 8;
+        older.received_at -= 1_000;
+        pending.record(change);
+        pending.record(older.clone());
+        assert_eq!(pending.latest().unwrap().request_id, 7);
+        assert_eq!(pending.list()[0], older);
+
+        assert!(is_approval_request("item/fileChange/requestApproval"));
+        assert!(!is_approval_request("item/tool/requestUserInput"));
+    }
+}
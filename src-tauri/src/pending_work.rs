@@ -0,0 +1,218 @@
+//! What needs the user across all projects, for the digest shown when the
+//! window regains focus.
+//!
+//! Everything but unpushed commits comes from in-memory trackers or a
+//! single turn-queue query. Commits on session worktree branches need git,
+//! so they are counted at most once per `UNPUSHED_REFRESH_INTERVAL` and
+//! served from `PendingWorkCache` in between.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::app_server::ActiveTurn;
+use crate::database::{Database, QueuedTurn};
+use crate::pending_approvals::PendingApproval;
+use crate::Result;
+
+/// How long counted worktree commits are reused
+const UNPUSHED_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Prompts waiting to be sent on one session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedMessages {
+    pub session_id: String,
+    pub project_id: Option<String>,
+    /// Follow-ups sent once the current turn completes
+    pub followups: usize,
+    /// Waiting entries of the project's turn queue
+    pub queued_turns: usize,
+}
+
+/// A session worktree branch with commits its base branch lacks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnpushedBranch {
+    pub session_id: String,
+    pub project_id: String,
+    pub branch: String,
+    pub commits: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingWorkSummary {
+    /// Turns still running
+    pub unfinished_turns: Vec<ActiveTurn>,
+    pub queued_messages: Vec<QueuedMessages>,
+    /// Oldest first
+    pub pending_approvals: Vec<PendingApproval>,
+    /// The failed entry holding back each paused project queue
+    pub failed_tasks: Vec<QueuedTurn>,
+    pub unpushed_commits: Vec<UnpushedBranch>,
+    /// Number of entries above
+    pub total: usize,
+    /// Unix milliseconds
+    pub computed_at: i64,
+}
+
+impl PendingWorkSummary {
+    pub fn new(
+        unfinished_turns: Vec<ActiveTurn>,
+        queued_messages: Vec<QueuedMessages>,
+        pending_approvals: Vec<PendingApproval>,
+        failed_tasks: Vec<QueuedTurn>,
+        unpushed_commits: Vec<UnpushedBranch>,
+        computed_at: i64,
+    ) -> Self {
+        let total = unfinished_turns.len()
+            + queued_messages.len()
+            + pending_approvals.len()
+            + failed_tasks.len()
+            + unpushed_commits.len();
+        Self {
+            unfinished_turns,
+            queued_messages,
+            pending_approvals,
+            failed_tasks,
+            unpushed_commits,
+            total,
+            computed_at,
+        }
+    }
+}
+
+/// Follow-ups (session id, project id, count) and waiting queue entries
+/// grouped per session, in session id order
+pub fn group_queued_messages(
+    followups: Vec<(String, Option<String>, usize)>,
+    queue: &[QueuedTurn],
+) -> Vec<QueuedMessages> {
+    let mut sessions: BTreeMap<String, QueuedMessages> = BTreeMap::new();
+    for (session_id, project_id, count) in followups {
+        sessions.insert(
+            session_id.clone(),
+            QueuedMessages {
+                session_id,
+                project_id,
+                followups: count,
+                queued_turns: 0,
+            },
+        );
+    }
+    // Running entries show up as unfinished turns
+    for entry in queue.iter().filter(|entry| entry.status == "queued") {
+        let messages = sessions
+            .entry(entry.thread_id.clone())
+            .or_insert_with(|| QueuedMessages {
+                session_id: entry.thread_id.clone(),
+                project_id: None,
+                followups: 0,
+                queued_turns: 0,
+            });
+        messages.project_id = Some(entry.project_id.clone());
+        messages.queued_turns += 1;
+    }
+    sessions.into_values().collect()
+}
+
+/// Worktree commit counts from the last refresh
+#[derive(Default)]
+pub struct PendingWorkCache {
+    unpushed: Mutex<Option<(Instant, Vec<UnpushedBranch>)>>,
+}
+
+impl PendingWorkCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Session worktree branches ahead of their base, counted again once
+    /// the cached counts are older than `UNPUSHED_REFRESH_INTERVAL`. Blocking.
+    pub fn unpushed_commits(&self, database: &Database) -> Result<Vec<UnpushedBranch>> {
+        if let Some((counted_at, branches)) = &*self.unpushed.lock() {
+            if counted_at.elapsed() < UNPUSHED_REFRESH_INTERVAL {
+                return Ok(branches.clone());
+            }
+        }
+        let branches: Vec<UnpushedBranch> = database
+            .list_all_session_worktrees()?
+            .into_iter()
+            .filter(|worktree| Path::new(&worktree.path).is_dir())
+            .map(crate::session_worktrees::status)
+            .filter(|status| status.commits_ahead > 0)
+            .map(|status| UnpushedBranch {
+                session_id: status.worktree.session_id,
+                project_id: status.worktree.project_id,
+                branch: status.worktree.branch,
+                commits: status.commits_ahead,
+            })
+            .collect();
+        *self.unpushed.lock() = Some((Instant::now(), branches.clone()));
+        Ok(branches)
+    }
+
+    /// Count again on the next summary, e.g. after a worktree was merged
+    pub fn invalidate(&self) {
+        *self.unpushed.lock() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(thread_id: &str, status: &str) -> QueuedTurn {
+        QueuedTurn {
+            id: format!("{thread_id}-{status}"),
+            project_id: "p1".to_string(),
+            thread_id: thread_id.to_string(),
+            prompt: "Next step".to_string(),
+            position: 0,
+            status: status.to_string(),
+            run_after: None,
+            created_at: 0,
+            started_at: None,
+            finished_at: None,
+            turn_id: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_group_queued_messages() {
+        let followups = vec![("s2".to_string(), None, 2)];
+        let queue = [
+            entry("s1", "running"),
+            entry("s1", "queued"),
+            entry("s2", "queued"),
+            entry("s2", "queued"),
+        ];
+        let grouped = group_queued_messages(followups, &queue);
+        assert_eq!(
+            grouped,
+            vec![
+                QueuedMessages {
+                    session_id: "s1".to_string(),
+                    project_id: Some("p1".to_string()),
+                    followups: 0,
+                    queued_turns: 1,
+                },
+                QueuedMessages {
+                    session_id: "s2".to_string(),
+                    project_id: Some("p1".to_string()),
+                    followups: 2,
+                    queued_turns: 2,
+                },
+            ]
+        );
+
+        let summary =
+            PendingWorkSummary::new(Vec::new(), grouped, Vec::new(), Vec::new(), Vec::new(), 0);
+        assert_eq!(summary.total, 2);
+    }
+}
@@ -0,0 +1,103 @@
+//! Cold-storage archives for finished projects.
+//!
+//! An archive is the project's rows (`ProjectArchive`) as gzip-compressed
+//! JSON. Archiving writes the file first and only then removes the rows from
+//! the live database, so a failed write never loses data.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::database::{Project, ProjectArchive};
+use crate::Result;
+
+/// File extension of project archives
+pub const ARCHIVE_EXTENSION: &str = "codexarchive.gz";
+
+/// Largest decompressed archive accepted when restoring
+const MAX_ARCHIVE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Where to write `project`'s archive
+///
+/// An existing directory gets a generated file name; anything else is used
+/// as the file path as given.
+pub fn archive_path(destination: &Path, project: &Project) -> PathBuf {
+    if !destination.is_dir() {
+        return destination.to_path_buf();
+    }
+    let name: String = project
+        .display_name
+        .as_deref()
+        .unwrap_or("project")
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let date = chrono::Utc::now().format("%Y%m%d");
+    let short_id: String = project.id.chars().take(8).collect();
+    destination.join(format!("{name}-{date}-{short_id}.{ARCHIVE_EXTENSION}"))
+}
+
+/// Write `archive` to `path` atomically
+pub fn write(path: &Path, archive: &ProjectArchive) -> Result<()> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, archive)?;
+    crate::utils::atomic_write(path, &encoder.finish()?)
+}
+
+/// Read an archive written by [`write`]
+pub fn read(path: &Path) -> Result<ProjectArchive> {
+    let file = std::fs::File::open(path)?;
+    let mut json = Vec::new();
+    GzDecoder::new(file)
+        .take(MAX_ARCHIVE_BYTES + 1)
+        .read_to_end(&mut json)?;
+    if json.len() as u64 > MAX_ARCHIVE_BYTES {
+        return Err(crate::Error::Other(format!(
+            "Archive is too large to restore: {}",
+            path.display()
+        )));
+    }
+    Ok(serde_json::from_slice(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, SessionMetadata, Snapshot};
+
+    #[test]
+    fn test_archive_round_trip() {
+        let (temp_dir, db) = crate::database::test_db();
+
+        let project = Project::new("/tmp/finished");
+        db.insert_project(&project).unwrap();
+        let mut session = SessionMetadata::new("s1", &project.id);
+        session.set_tags(vec!["release".to_string()]);
+        db.upsert_session_metadata(&session).unwrap();
+        db.insert_snapshot(&Snapshot::new_git_ghost("s1", "abc123")).unwrap();
+        db.add_to_allowlist(&project.id, "cargo test").unwrap();
+        db.index_session_content("s1", "USER: ship it").unwrap();
+
+        let archive = db.export_project(&project.id).unwrap().unwrap();
+        let path = archive_path(temp_dir.path(), &project);
+        write(&path, &archive).unwrap();
+        db.mark_project_archived(&archive, &path.to_string_lossy())
+            .unwrap();
+        assert!(db.get_project(&project.id).unwrap().is_none());
+        assert!(db.get_session_by_id("s1").unwrap().is_none());
+        assert_eq!(db.list_archived_projects().unwrap().len(), 1);
+
+        db.restore_project(&read(&path).unwrap()).unwrap();
+        assert!(db.list_archived_projects().unwrap().is_empty());
+        let restored = db.get_session_by_id("s1").unwrap().unwrap();
+        assert_eq!(restored.get_tags(), vec!["release"]);
+        assert_eq!(db.get_snapshots_for_session("s1").unwrap().len(), 1);
+        assert_eq!(db.get_allowlist(&project.id).unwrap(), vec!["cargo test"]);
+        assert_eq!(db.search_sessions("ship", &[], false, None, 10).unwrap().len(), 1);
+
+        assert!(db.restore_project(&archive).is_err());
+    }
+}
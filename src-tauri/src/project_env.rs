@@ -0,0 +1,234 @@
+//! Per-project environment variables.
+//!
+//! Values are encrypted with AES-256-GCM before they reach the database;
+//! the key lives in the OS keychain, so a copied database (or a project
+//! archive, which leaves the variables out) reveals nothing. Variables are
+//! set on terminal commands run for the project and, through the Codex
+//! `shell_environment_policy`, on the commands of threads started in it.
+//! Values of variables flagged secret are handed to `secret_mask` when
+//! loaded, so command output echoing them is masked.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::database::Database;
+use crate::{Error, Result};
+
+const KEYCHAIN_SERVICE: &str = "codex-desktop.project-env";
+const KEYCHAIN_ACCOUNT: &str = "encryption-key";
+
+/// Codex config key whose table is set on every command a thread runs
+pub const SHELL_ENV_CONFIG_KEY: &str = "shell_environment_policy.set";
+
+const MAX_NAME_LEN: usize = 128;
+const MAX_VALUE_BYTES: usize = 32 * 1024;
+
+/// Bytes of the random nonce prefixed to each sealed value
+const NONCE_LEN: usize = 12;
+
+/// A project variable; `value` is only filled in when asked for
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectEnvVar {
+    pub name: String,
+    pub value: Option<String>,
+    pub secret: bool,
+    pub updated_at: i64,
+}
+
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Other(format!(
+            "Invalid environment variable name '{name}': use letters, digits and underscores, not starting with a digit"
+        )))
+    }
+}
+
+pub fn validate_value(value: &str) -> Result<()> {
+    if value.len() > MAX_VALUE_BYTES {
+        return Err(Error::Other(format!(
+            "Environment variable values are limited to {} KiB",
+            MAX_VALUE_BYTES / 1024
+        )));
+    }
+    if value.contains('\0') {
+        return Err(Error::Other(
+            "Environment variable values cannot contain null bytes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Encrypt and store a variable
+pub fn set(db: &Database, project_id: &str, name: &str, value: &str, secret: bool) -> Result<()> {
+    validate_name(name)?;
+    validate_value(value)?;
+    let sealed = seal(&encryption_key()?, value)?;
+    db.set_project_env(project_id, name, &sealed, secret)
+}
+
+/// The project's variables, sorted by name; values are decrypted only
+/// when `reveal` is set
+pub fn list(db: &Database, project_id: &str, reveal: bool) -> Result<Vec<ProjectEnvVar>> {
+    let rows = db.get_project_env(project_id)?;
+    let key = if reveal && !rows.is_empty() {
+        Some(encryption_key()?)
+    } else {
+        None
+    };
+    rows.into_iter()
+        .map(|row| {
+            let value = key
+                .as_ref()
+                .map(|key| open(key, &row.value_encrypted))
+                .transpose()?;
+            Ok(ProjectEnvVar {
+                name: row.name,
+                value,
+                secret: row.is_secret,
+                updated_at: row.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// The project's variables as name/value pairs; secret values are masked
+/// from here on
+pub fn load(db: &Database, project_id: &str) -> Result<Vec<(String, String)>> {
+    let vars = list(db, project_id, true)?;
+    crate::secret_mask::register_values(
+        vars.iter()
+            .filter(|var| var.secret)
+            .filter_map(|var| var.value.clone()),
+    );
+    Ok(vars
+        .into_iter()
+        .filter_map(|var| Some((var.name, var.value?)))
+        .collect())
+}
+
+/// Add the project's variables to a thread's config overrides, keeping
+/// any the caller set explicitly
+pub fn apply_to_thread_config(config: &mut Option<JsonValue>, vars: Vec<(String, String)>) {
+    if vars.is_empty() {
+        return;
+    }
+    let config = config.get_or_insert_with(|| JsonValue::Object(Default::default()));
+    let Some(config) = config.as_object_mut() else {
+        return;
+    };
+    let set = config
+        .entry(SHELL_ENV_CONFIG_KEY)
+        .or_insert_with(|| JsonValue::Object(Default::default()));
+    let Some(set) = set.as_object_mut() else {
+        return;
+    };
+    for (name, value) in vars {
+        set.entry(name).or_insert(JsonValue::String(value));
+    }
+}
+
+/// The keychain-held key, created on first use
+fn encryption_key() -> Result<Key<Aes256Gcm>> {
+    let entry = keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT)
+        .map_err(|e| Error::Other(format!("Keychain unavailable: {e}")))?;
+    match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = STANDARD
+                .decode(encoded.trim())
+                .ok()
+                .filter(|bytes| bytes.len() == 32)
+                .ok_or_else(|| {
+                    Error::Other("The project environment key in the keychain is corrupt".to_string())
+                })?;
+            Ok(*Key::<Aes256Gcm>::from_slice(&bytes))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let key = Aes256Gcm::generate_key(OsRng);
+            entry
+                .set_password(&STANDARD.encode(key))
+                .map_err(|e| Error::Other(format!("Failed to store encryption key: {e}")))?;
+            Ok(key)
+        }
+        Err(e) => Err(Error::Other(format!("Failed to read encryption key: {e}"))),
+    }
+}
+
+/// Base64 of a random nonce followed by the ciphertext
+fn seal(key: &Key<Aes256Gcm>, plaintext: &str) -> Result<String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(key)
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| Error::Other("Failed to encrypt environment variable".to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+fn open(key: &Key<Aes256Gcm>, sealed: &str) -> Result<String> {
+    let undecryptable = || {
+        Error::Other(
+            "An environment variable cannot be decrypted; the keychain key may have changed"
+                .to_string(),
+        )
+    };
+    let bytes = STANDARD.decode(sealed).map_err(|_| undecryptable())?;
+    if bytes.len() < NONCE_LEN {
+        return Err(undecryptable());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = Aes256Gcm::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| undecryptable())?;
+    String::from_utf8(plaintext).map_err(|_| undecryptable())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_round_trip() {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let sealed = seal(&key, "sk-secret").unwrap();
+        assert!(!sealed.contains("sk-secret"));
+        assert_ne!(sealed, seal(&key, "sk-secret").unwrap());
+        assert_eq!(open(&key, &sealed).unwrap(), "sk-secret");
+
+        let other = Aes256Gcm::generate_key(OsRng);
+        assert!(open(&other, &sealed).is_err());
+        assert!(open(&key, "AAAA").is_err());
+    }
+
+    #[test]
+    fn test_names_and_thread_config() {
+        assert!(validate_name("OPENAI_API_KEY").is_ok());
+        for name in ["", "1KEY", "MY-KEY", "A B"] {
+            assert!(validate_name(name).is_err(), "{name}");
+        }
+
+        let mut config = Some(serde_json::json!({
+            SHELL_ENV_CONFIG_KEY: { "TOKEN": "explicit" },
+        }));
+        apply_to_thread_config(
+            &mut config,
+            vec![
+                ("TOKEN".to_string(), "project".to_string()),
+                ("REGION".to_string(), "eu".to_string()),
+            ],
+        );
+        assert_eq!(
+            config.unwrap()[SHELL_ENV_CONFIG_KEY],
+            serde_json::json!({ "TOKEN": "explicit", "REGION": "eu" })
+        );
+    }
+}
@@ -0,0 +1,651 @@
+//! Pull requests of a project's GitHub repository, through the `gh` CLI.
+//!
+//! Lists pull requests, loads one with its diff, comments and checks,
+//! checks one out and replies to its comments, so a pull request can be
+//! brought into the GUI and its review comments handed to Codex. `gh`
+//! resolves the repository from the project's remotes and uses its own
+//! login; nothing here talks to GitHub directly.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+use crate::{Error, Result};
+
+/// Pull requests listed when no limit is given
+pub const DEFAULT_LIST_LIMIT: u32 = 30;
+pub const MAX_LIST_LIMIT: u32 = 200;
+
+/// Fields requested from `gh pr list` and `gh pr view`
+const SUMMARY_FIELDS: &str =
+    "number,title,author,headRefName,baseRefName,isDraft,state,url,updatedAt,reviewDecision";
+
+/// Which pull requests `list_pull_requests` returns
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullRequestFilter {
+    #[default]
+    Open,
+    Closed,
+    Merged,
+    All,
+}
+
+impl PullRequestFilter {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+            Self::Merged => "merged",
+            Self::All => "all",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestSummary {
+    pub number: u64,
+    pub title: String,
+    pub author: Option<String>,
+    pub head_branch: String,
+    pub base_branch: String,
+    pub is_draft: bool,
+    /// "OPEN", "CLOSED" or "MERGED"
+    pub state: String,
+    pub url: String,
+    pub updated_at: Option<String>,
+    /// "APPROVED", "CHANGES_REQUESTED" or "REVIEW_REQUIRED"; `None` when
+    /// the repository requires no review
+    pub review_decision: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PullRequestCommentKind {
+    /// On the pull request's conversation
+    Conversation,
+    /// The summary of a submitted review
+    Review,
+    /// On a line of the diff
+    Inline,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestComment {
+    /// REST id of an inline comment, which replies go to; `None` for the
+    /// other kinds
+    pub id: Option<u64>,
+    /// The inline comment this one replies to, i.e. its thread
+    pub in_reply_to_id: Option<u64>,
+    pub kind: PullRequestCommentKind,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: Option<String>,
+    pub url: Option<String>,
+    /// File and line of an inline comment; `line` is `None` once the line
+    /// is no longer part of the diff
+    pub path: Option<String>,
+    pub line: Option<u32>,
+    /// "APPROVED", "CHANGES_REQUESTED", "COMMENTED" or "DISMISSED" for reviews
+    pub review_state: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestCheck {
+    pub name: String,
+    pub workflow: Option<String>,
+    pub state: String,
+    /// "pass", "fail", "pending", "skipping" or "cancel"
+    pub bucket: Option<String>,
+    pub link: Option<String>,
+}
+
+/// A pull request with everything needed to review or address it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestDetail {
+    #[serde(flatten)]
+    pub summary: PullRequestSummary,
+    pub body: String,
+    pub diff: String,
+    /// `diff` was cut off at `comparison::MAX_DIFF_BYTES`
+    pub diff_truncated: bool,
+    /// Oldest first
+    pub comments: Vec<PullRequestComment>,
+    /// Empty when the pull request has no checks or `gh` is too old to
+    /// report them
+    pub checks: Vec<PullRequestCheck>,
+    /// Prompt asking Codex to address the review comments
+    pub address_comments_prompt: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhPullRequest {
+    number: u64,
+    title: String,
+    author: Option<GhUser>,
+    head_ref_name: String,
+    base_ref_name: String,
+    #[serde(default)]
+    is_draft: bool,
+    state: String,
+    url: String,
+    updated_at: Option<String>,
+    review_decision: Option<String>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    comments: Vec<GhComment>,
+    #[serde(default)]
+    reviews: Vec<GhReview>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhComment {
+    author: Option<GhUser>,
+    body: String,
+    created_at: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhReview {
+    author: Option<GhUser>,
+    body: String,
+    submitted_at: Option<String>,
+    state: String,
+}
+
+/// An entry of the REST `pulls/{number}/comments` list, or a comment
+/// created through the REST API
+#[derive(Debug, Deserialize)]
+struct GhReviewComment {
+    id: u64,
+    in_reply_to_id: Option<u64>,
+    user: Option<GhUser>,
+    body: String,
+    path: Option<String>,
+    line: Option<u32>,
+    created_at: Option<String>,
+    html_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCheck {
+    name: String,
+    workflow: Option<String>,
+    state: String,
+    bucket: Option<String>,
+    link: Option<String>,
+}
+
+impl GhPullRequest {
+    fn summary(&self) -> PullRequestSummary {
+        PullRequestSummary {
+            number: self.number,
+            title: self.title.clone(),
+            author: self.author.as_ref().map(|user| user.login.clone()),
+            head_branch: self.head_ref_name.clone(),
+            base_branch: self.base_ref_name.clone(),
+            is_draft: self.is_draft,
+            state: self.state.clone(),
+            url: self.url.clone(),
+            updated_at: self.updated_at.clone(),
+            review_decision: self
+                .review_decision
+                .clone()
+                .filter(|value| !value.is_empty()),
+        }
+    }
+}
+
+impl GhCheck {
+    fn into_check(self) -> PullRequestCheck {
+        PullRequestCheck {
+            name: self.name,
+            workflow: self.workflow.filter(|workflow| !workflow.is_empty()),
+            state: self.state,
+            bucket: self.bucket,
+            link: self.link.filter(|link| !link.is_empty()),
+        }
+    }
+}
+
+impl GhReviewComment {
+    fn into_comment(self, kind: PullRequestCommentKind) -> PullRequestComment {
+        PullRequestComment {
+            id: Some(self.id),
+            in_reply_to_id: self.in_reply_to_id,
+            kind,
+            author: self.user.map(|user| user.login),
+            body: self.body,
+            created_at: self.created_at,
+            url: self.html_url,
+            path: self.path,
+            line: self.line,
+            review_state: None,
+        }
+    }
+}
+
+fn gh_command(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("gh");
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("GH_PROMPT_DISABLED", "1")
+        .env("NO_COLOR", "1");
+    command
+}
+
+fn run_gh(dir: &Path, args: &[&str], input: Option<&str>) -> Result<Output> {
+    let mut command = gh_command(dir, args);
+    let output = match input {
+        None => command.output(),
+        Some(input) => command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(input.as_bytes())?;
+                }
+                child.wait_with_output()
+            }),
+    };
+    output.map_err(|err| {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            Error::CliNotInstalled {
+                program: "GitHub CLI (gh)",
+                install: "brew install gh",
+            }
+        } else {
+            Error::Other(format!("Failed to run gh: {err}"))
+        }
+    })
+}
+
+/// Run `gh` in `dir` and return its stdout. Blocking.
+pub(crate) fn gh(dir: &Path, args: &[&str]) -> Result<String> {
+    gh_with_input(dir, args, None)
+}
+
+pub(crate) fn gh_with_input(dir: &Path, args: &[&str], input: Option<&str>) -> Result<String> {
+    let output = run_gh(dir, args, input)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Other(format!(
+            "gh {} failed: {}",
+            args[..2.min(args.len())].join(" "),
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+pub(crate) fn parse<T: for<'de> Deserialize<'de>>(what: &str, json: &str) -> Result<T> {
+    serde_json::from_str(json)
+        .map_err(|err| Error::Other(format!("Unexpected output from gh {what}: {err}")))
+}
+
+/// Pull requests of the repository `dir` belongs to, newest first.
+/// Blocking.
+pub fn list(dir: &Path, filter: PullRequestFilter, limit: u32) -> Result<Vec<PullRequestSummary>> {
+    let limit = limit.to_string();
+    let json = gh(
+        dir,
+        &[
+            "pr",
+            "list",
+            "--state",
+            filter.as_str(),
+            "--limit",
+            &limit,
+            "--json",
+            SUMMARY_FIELDS,
+        ],
+    )?;
+    let pull_requests: Vec<GhPullRequest> = parse("pr list", &json)?;
+    Ok(pull_requests.iter().map(GhPullRequest::summary).collect())
+}
+
+fn view(dir: &Path, number: u64, fields: &str) -> Result<GhPullRequest> {
+    let json = gh(dir, &["pr", "view", &number.to_string(), "--json", fields])?;
+    parse("pr view", &json)
+}
+
+fn fetch_review_comments(dir: &Path, number: u64) -> Result<Vec<GhReviewComment>> {
+    let endpoint = format!("repos/{{owner}}/{{repo}}/pulls/{number}/comments?per_page=100");
+    parse("api", &gh(dir, &["api", &endpoint])?)
+}
+
+/// A pull request without its comments, diff and checks. Blocking.
+pub fn summary(dir: &Path, number: u64) -> Result<PullRequestSummary> {
+    Ok(view(dir, number, SUMMARY_FIELDS)?.summary())
+}
+
+/// A pull request with its diff, comments and checks. Blocking.
+pub fn get(dir: &Path, number: u64) -> Result<PullRequestDetail> {
+    let number_arg = number.to_string();
+    let pull_request = view(
+        dir,
+        number,
+        &format!("{SUMMARY_FIELDS},body,comments,reviews"),
+    )?;
+    let inline = fetch_review_comments(dir, number)?;
+
+    let (diff, diff_truncated) =
+        crate::comparison::truncate_diff(gh(dir, &["pr", "diff", &number_arg])?);
+
+    let checks = fetch_checks(dir, &number_arg);
+    Ok(detail(pull_request, inline, checks, diff, diff_truncated))
+}
+
+fn fetch_checks(dir: &Path, pull_request: &str) -> Vec<GhCheck> {
+    // `gh pr checks` exits non-zero while checks fail or are pending, and
+    // when there are none; its JSON is used whenever it printed any
+    run_gh(
+        dir,
+        &[
+            "pr",
+            "checks",
+            pull_request,
+            "--json",
+            "name,workflow,state,bucket,link",
+        ],
+        None,
+    )
+    .ok()
+    .and_then(|output| serde_json::from_slice::<Vec<GhCheck>>(&output.stdout).ok())
+    .unwrap_or_default()
+}
+
+/// Checks of the pull request `pull_request` names (a number or a head
+/// branch); empty when there is none or it has no checks. Blocking.
+pub fn checks(dir: &Path, pull_request: &str) -> Vec<PullRequestCheck> {
+    fetch_checks(dir, pull_request)
+        .into_iter()
+        .map(GhCheck::into_check)
+        .collect()
+}
+
+fn detail(
+    pull_request: GhPullRequest,
+    inline: Vec<GhReviewComment>,
+    checks: Vec<GhCheck>,
+    diff: String,
+    diff_truncated: bool,
+) -> PullRequestDetail {
+    let login = |user: Option<GhUser>| user.map(|user| user.login);
+    let summary = pull_request.summary();
+    let mut comments: Vec<PullRequestComment> = pull_request
+        .comments
+        .into_iter()
+        .map(|comment| PullRequestComment {
+            id: None,
+            in_reply_to_id: None,
+            kind: PullRequestCommentKind::Conversation,
+            author: login(comment.author),
+            body: comment.body,
+            created_at: comment.created_at,
+            url: comment.url,
+            path: None,
+            line: None,
+            review_state: None,
+        })
+        .collect();
+    comments.extend(
+        pull_request
+            .reviews
+            .into_iter()
+            .filter(|review| !review.body.trim().is_empty())
+            .map(|review| PullRequestComment {
+                id: None,
+                in_reply_to_id: None,
+                kind: PullRequestCommentKind::Review,
+                author: login(review.author),
+                body: review.body,
+                created_at: review.submitted_at,
+                url: None,
+                path: None,
+                line: None,
+                review_state: Some(review.state),
+            }),
+    );
+    comments.extend(
+        inline
+            .into_iter()
+            .map(|comment| comment.into_comment(PullRequestCommentKind::Inline)),
+    );
+    // ISO 8601 timestamps sort as text; undated comments go first
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    let checks = checks.into_iter().map(GhCheck::into_check).collect();
+    let address_comments_prompt = address_comments_prompt(&summary, &comments);
+    PullRequestDetail {
+        summary,
+        body: pull_request.body,
+        diff,
+        diff_truncated,
+        comments,
+        checks,
+        address_comments_prompt,
+    }
+}
+
+/// Inline review comments of a pull request, replies included, oldest
+/// first. Blocking.
+pub fn review_comments(dir: &Path, number: u64) -> Result<Vec<PullRequestComment>> {
+    let mut comments: Vec<PullRequestComment> = fetch_review_comments(dir, number)?
+        .into_iter()
+        .map(|comment| comment.into_comment(PullRequestCommentKind::Inline))
+        .collect();
+    comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(comments)
+}
+
+/// Reply in the thread of the inline comment `comment_id`, or comment on
+/// the pull request's conversation when `None`. Blocking.
+pub fn reply(
+    dir: &Path,
+    number: u64,
+    comment_id: Option<u64>,
+    body: &str,
+) -> Result<PullRequestComment> {
+    let (endpoint, kind) = match comment_id {
+        Some(id) => (
+            format!("repos/{{owner}}/{{repo}}/pulls/{number}/comments/{id}/replies"),
+            PullRequestCommentKind::Inline,
+        ),
+        None => (
+            format!("repos/{{owner}}/{{repo}}/issues/{number}/comments"),
+            PullRequestCommentKind::Conversation,
+        ),
+    };
+    // The body goes through stdin so it is never parsed as a `gh` argument
+    let input = serde_json::json!({ "body": body }).to_string();
+    let json = gh_with_input(
+        dir,
+        &["api", "--method", "POST", &endpoint, "--input", "-"],
+        Some(&input),
+    )?;
+    let created: GhReviewComment = parse("api", &json)?;
+    let mut comment = created.into_comment(kind);
+    if kind == PullRequestCommentKind::Conversation {
+        // Issue comment ids cannot be replied to as threads
+        comment.id = None;
+    }
+    Ok(comment)
+}
+
+/// Ask Codex to work through the review feedback, one numbered item per
+/// comment
+pub fn address_comments_prompt(
+    summary: &PullRequestSummary,
+    comments: &[PullRequestComment],
+) -> String {
+    let mut prompt = format!(
+        "Address the review comments on pull request #{} \"{}\" ({} into {}). \
+         Make the requested changes; where a comment is unclear or you disagree, \
+         explain why instead.\n",
+        summary.number, summary.title, summary.head_branch, summary.base_branch
+    );
+    let feedback = comments.iter().filter(|comment| {
+        !comment.body.trim().is_empty() && comment.review_state.as_deref() != Some("APPROVED")
+    });
+    for (index, comment) in feedback.enumerate() {
+        let author = comment.author.as_deref().unwrap_or("unknown");
+        let source = match (&comment.path, comment.line) {
+            (Some(path), Some(line)) => format!("{path}:{line} ({author})"),
+            (Some(path), None) => format!("{path} ({author}, outdated)"),
+            _ if comment.kind == PullRequestCommentKind::Review => format!("Review by {author}"),
+            _ => author.to_string(),
+        };
+        prompt.push_str(&format!(
+            "\n{}. {source}: {}\n",
+            index + 1,
+            comment.body.trim()
+        ));
+    }
+    prompt
+}
+
+/// Check out the pull request's branch in `dir`, under `branch` if given.
+/// Blocking.
+pub fn checkout(dir: &Path, number: u64, branch: Option<&str>) -> Result<()> {
+    let number = number.to_string();
+    let mut args = vec!["pr", "checkout", number.as_str()];
+    if let Some(branch) = branch {
+        args.extend(["--branch", branch]);
+    }
+    gh(dir, &args).map(|_| ())
+}
+
+/// Worktree with the pull request's head branch checked out, made next to
+/// the project under `.worktrees/pr-<number>` unless the branch is checked
+/// out somewhere already. Blocking.
+pub fn review_worktree(repo: &Path, summary: &PullRequestSummary) -> Result<PathBuf> {
+    let list = git::run(repo, &["worktree", "list", "--porcelain"])?;
+    if let Some(path) = worktree_for_branch(&list, &summary.head_branch) {
+        return Ok(path);
+    }
+
+    let parent = repo
+        .parent()
+        .ok_or_else(|| Error::Other("Cannot determine parent directory".to_string()))?;
+    let path = parent
+        .join(".worktrees")
+        .join(format!("pr-{}", summary.number));
+    if !path.exists() {
+        std::fs::create_dir_all(parent.join(".worktrees"))?;
+        let path_arg = path.to_string_lossy();
+        git::run(repo, &["worktree", "add", "--detach", "--", &path_arg])?;
+    }
+    // Fetches the branch, from a fork if need be, and sets up tracking
+    checkout(&path, summary.number, None)?;
+    Ok(path)
+}
+
+/// Path of the worktree on `branch` in `git worktree list --porcelain`
+/// output
+fn worktree_for_branch(porcelain: &str, branch: &str) -> Option<PathBuf> {
+    let wanted = format!("refs/heads/{branch}");
+    let mut path = None;
+    for line in porcelain.lines() {
+        if let Some(worktree) = line.strip_prefix("worktree ") {
+            path = Some(PathBuf::from(worktree));
+        } else if line.strip_prefix("branch ") == Some(wanted.as_str()) {
+            return path;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detail_from_gh_output() {
+        let pull_request: GhPullRequest = serde_json::from_str(
+            r#"{
+                "number": 12, "title": "Add cache", "author": {"login": "alice"},
+                "headRefName": "feature/cache", "baseRefName": "main", "isDraft": false,
+                "state": "OPEN", "url": "https://github.com/o/r/pull/12",
+                "updatedAt": "2026-01-03T00:00:00Z", "reviewDecision": "",
+                "body": "Adds a cache",
+                "comments": [{"author": {"login": "carol"}, "body": "Needs docs",
+                    "createdAt": "2026-01-02T00:00:00Z", "url": "u1"}],
+                "reviews": [
+                    {"author": {"login": "bob"}, "body": "Please rename",
+                        "submittedAt": "2026-01-01T00:00:00Z", "state": "CHANGES_REQUESTED"},
+                    {"author": {"login": "dan"}, "body": "",
+                        "submittedAt": "2026-01-01T01:00:00Z", "state": "APPROVED"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let inline: Vec<GhReviewComment> = serde_json::from_str(
+            r#"[{"id": 7, "user": {"login": "bob"}, "body": "Off by one", "path": "src/cache.rs",
+                "line": 42, "created_at": "2026-01-01T00:30:00Z", "html_url": "u2"}]"#,
+        )
+        .unwrap();
+        let checks: Vec<GhCheck> = serde_json::from_str(
+            r#"[{"name": "test", "workflow": "", "state": "FAILURE", "bucket": "fail", "link": "l"}]"#,
+        )
+        .unwrap();
+
+        let detail = detail(pull_request, inline, checks, String::new(), false);
+        assert_eq!(detail.summary.review_decision, None);
+        assert_eq!(detail.summary.author.as_deref(), Some("alice"));
+        let kinds: Vec<_> = detail.comments.iter().map(|comment| comment.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                PullRequestCommentKind::Review,
+                PullRequestCommentKind::Inline,
+                PullRequestCommentKind::Conversation
+            ]
+        );
+        assert_eq!(detail.checks[0].workflow, None);
+        assert_eq!(detail.comments[1].id, Some(7));
+        assert_eq!(detail.comments[2].id, None);
+
+        let prompt = &detail.address_comments_prompt;
+        assert!(prompt.contains("#12 \"Add cache\" (feature/cache into main)"));
+        assert!(prompt.contains("1. Review by bob: Please rename"));
+        assert!(prompt.contains("2. src/cache.rs:42 (bob): Off by one"));
+        assert!(prompt.contains("3. carol: Needs docs"));
+    }
+
+    #[test]
+    fn test_worktree_for_branch() {
+        let porcelain = "worktree /repo\nHEAD abc\nbranch refs/heads/main\n\n\
+                         worktree /wt/pr-12\nHEAD def\nbranch refs/heads/feature/cache\n\n\
+                         worktree /wt/detached\nHEAD 123\ndetached\n";
+        assert_eq!(
+            worktree_for_branch(porcelain, "feature/cache"),
+            Some(PathBuf::from("/wt/pr-12"))
+        );
+        assert_eq!(
+            worktree_for_branch(porcelain, "main"),
+            Some(PathBuf::from("/repo"))
+        );
+        assert_eq!(worktree_for_branch(porcelain, "feature"), None);
+    }
+}
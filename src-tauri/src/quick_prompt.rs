@@ -0,0 +1,66 @@
+//! Summoning the app from anywhere, and the quick prompt palette.
+//!
+//! The summon shortcuts (registered by `global_shortcuts`) bring the main
+//! window to the front; the quick prompt one then emits
+//! `quick-prompt-requested` with the most recent project and thread, so the
+//! renderer opens its palette on them without another round trip. The turn
+//! itself is started from the palette through the usual thread commands.
+//! While the app is locked the window comes up on the lock screen and no
+//! palette is requested.
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+
+use crate::state::AppState;
+use crate::Result;
+
+/// Where a quick prompt goes by default
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickPromptTarget {
+    pub project_id: String,
+    pub project_path: String,
+    pub project_name: Option<String>,
+    /// Most recently used unarchived thread; `None` starts a new one
+    pub thread_id: Option<String>,
+    pub thread_title: Option<String>,
+}
+
+/// The most recently opened project and its latest thread; `None` before
+/// any project was added
+pub async fn resolve_target(state: &AppState) -> Result<Option<QuickPromptTarget>> {
+    let database = state.database.clone();
+    let recent =
+        crate::utils::spawn_blocking_io(move || database.get_most_recent_session()).await?;
+    Ok(recent.map(|(project, session)| QuickPromptTarget {
+        project_id: project.id,
+        project_path: project.path,
+        project_name: project.display_name,
+        thread_id: session.as_ref().map(|s| s.session_id.clone()),
+        thread_title: session.and_then(|s| s.title),
+    }))
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Show the window and ask the renderer for the palette
+pub async fn open(app: &AppHandle) -> Result<()> {
+    show_main_window(app);
+    let state = app.state::<AppState>();
+    if state.app_lock.is_locked() {
+        return Ok(());
+    }
+    let target = resolve_target(&state).await?;
+    state
+        .events
+        .emit("quick-prompt-requested", json!({ "target": target }))
+        .await;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Crash-loop detection and safe mode.
+//!
+//! Every launch marks the startup as unstable in global state; the mark is
+//! cleared once the app has stayed up for `CRASH_WINDOW_SECS` or closes its
+//! window cleanly. A launch that finds the mark still set means the previous
+//! run died early. After `CRASH_LOOP_THRESHOLD` such launches in a row the app
+//! boots in safe mode: no background tasks, no app-server autostart and no
+//! optional plugins, until the user exits safe mode.
+
+use serde::Serialize;
+
+use crate::global_state::StartupState;
+
+/// A run that ends within this many seconds of launch counts as a failed startup
+pub const CRASH_WINDOW_SECS: u64 = 30;
+
+/// Consecutive failed startups that trigger safe mode
+pub const CRASH_LOOP_THRESHOLD: u32 = 3;
+
+/// Why the app booted in safe mode
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModeReason {
+    pub failed_startups: u32,
+    pub crash_window_secs: u64,
+    /// Launch time (ms) of the most recent failed startup
+    pub last_failed_start_ms: Option<i64>,
+    pub message: String,
+}
+
+/// Record a launch at `now_ms`; returns the safe mode reason if the app is crash-looping
+pub fn record_startup(startup: &mut StartupState, now_ms: i64) -> Option<SafeModeReason> {
+    let last_failed_start_ms = startup.unstable_since_ms;
+    if last_failed_start_ms.is_some() {
+        startup.consecutive_failed_startups = startup.consecutive_failed_startups.saturating_add(1);
+    } else {
+        startup.consecutive_failed_startups = 0;
+    }
+    startup.unstable_since_ms = Some(now_ms);
+
+    let failed_startups = startup.consecutive_failed_startups;
+    (failed_startups >= CRASH_LOOP_THRESHOLD).then(|| SafeModeReason {
+        failed_startups,
+        crash_window_secs: CRASH_WINDOW_SECS,
+        last_failed_start_ms,
+        message: format!(
+            "Codex Desktop exited within {CRASH_WINDOW_SECS} seconds of launch \
+             {failed_startups} times in a row, so background tasks, app-server \
+             autostart and plugins were disabled."
+        ),
+    })
+}
+
+/// Mark the current launch as healthy and reset the failure streak
+pub fn mark_stable(startup: &mut StartupState) {
+    startup.unstable_since_ms = None;
+    startup.consecutive_failed_startups = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_loop_enters_safe_mode() {
+        let mut startup = StartupState::default();
+        assert!(record_startup(&mut startup, 1_000).is_none());
+        assert!(record_startup(&mut startup, 2_000).is_none());
+        assert!(record_startup(&mut startup, 3_000).is_none());
+
+        let reason = record_startup(&mut startup, 4_000).unwrap();
+        assert_eq!(reason.failed_startups, CRASH_LOOP_THRESHOLD);
+        assert_eq!(reason.last_failed_start_ms, Some(3_000));
+        assert_eq!(startup.unstable_since_ms, Some(4_000));
+    }
+
+    #[test]
+    fn test_stable_run_resets_streak() {
+        let mut startup = StartupState::default();
+        record_startup(&mut startup, 1_000);
+        record_startup(&mut startup, 2_000);
+        mark_stable(&mut startup);
+
+        assert!(record_startup(&mut startup, 3_000).is_none());
+        assert_eq!(startup.consecutive_failed_startups, 0);
+    }
+}
@@ -0,0 +1,384 @@
+//! Sandboxing for commands the app runs on the user's behalf.
+//!
+//! A project can restrict what terminal and user shell commands may touch:
+//! the filesystem (everything, only the workspace, or nothing but temp
+//! directories) and the network. The restriction is applied by wrapping the
+//! command in a platform sandbox:
+//!
+//! - macOS: `sandbox-exec` with a generated SBPL profile
+//! - Linux: `bwrap` (bubblewrap), or `unshare` into a new network namespace
+//!   when bubblewrap is missing and only the network is restricted
+//!
+//! Commands are refused, not run unrestricted, when the profile asks for
+//! more than the available sandbox can enforce.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Project;
+use crate::{Error, Result};
+
+const SANDBOX_EXEC_PATH: &str = "/usr/bin/sandbox-exec";
+
+/// Most extra writable directories a profile may list
+const MAX_WRITABLE_ROOTS: usize = 32;
+
+/// What a sandboxed command may write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilesystemAccess {
+    /// No filesystem restriction
+    #[default]
+    Full,
+    /// The project directory, extra writable roots and temp directories
+    Workspace,
+    /// Only temp directories
+    ReadOnly,
+}
+
+/// A project's sandbox for the commands the app runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxProfile {
+    #[serde(default)]
+    pub filesystem: FilesystemAccess,
+    #[serde(default)]
+    pub block_network: bool,
+    /// Absolute directories also writable under `workspace` access
+    #[serde(default)]
+    pub writable_roots: Vec<String>,
+}
+
+impl SandboxProfile {
+    /// Whether the profile restricts anything at all
+    pub fn is_restricted(&self) -> bool {
+        self.filesystem != FilesystemAccess::Full || self.block_network
+    }
+
+    /// The Codex sandbox mode matching the profile's filesystem access
+    pub fn codex_sandbox_mode(&self) -> Option<&'static str> {
+        match self.filesystem {
+            FilesystemAccess::Full => None,
+            FilesystemAccess::Workspace => Some("workspace-write"),
+            FilesystemAccess::ReadOnly => Some("read-only"),
+        }
+    }
+}
+
+/// Platform mechanism used to sandbox commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SandboxBackend {
+    SandboxExec,
+    Bubblewrap,
+    /// Network namespace only; cannot restrict the filesystem
+    Unshare,
+}
+
+impl SandboxBackend {
+    fn can_enforce(self, profile: &SandboxProfile) -> bool {
+        match self {
+            SandboxBackend::SandboxExec | SandboxBackend::Bubblewrap => true,
+            SandboxBackend::Unshare => profile.filesystem == FilesystemAccess::Full,
+        }
+    }
+}
+
+/// What this machine can sandbox
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxSupport {
+    /// `None` when commands cannot be sandboxed at all
+    pub backend: Option<SandboxBackend>,
+    /// Whether filesystem restrictions can be enforced
+    pub filesystem: bool,
+    /// Whether network restrictions can be enforced
+    pub network: bool,
+}
+
+/// Detect the sandbox backend once; probing runs a throwaway command
+pub fn backend() -> Option<SandboxBackend> {
+    static BACKEND: OnceLock<Option<SandboxBackend>> = OnceLock::new();
+    *BACKEND.get_or_init(detect_backend)
+}
+
+pub fn support() -> SandboxSupport {
+    let backend = backend();
+    SandboxSupport {
+        backend,
+        filesystem: matches!(
+            backend,
+            Some(SandboxBackend::SandboxExec | SandboxBackend::Bubblewrap)
+        ),
+        network: backend.is_some(),
+    }
+}
+
+fn detect_backend() -> Option<SandboxBackend> {
+    if cfg!(target_os = "macos") {
+        return Path::new(SANDBOX_EXEC_PATH)
+            .is_file()
+            .then_some(SandboxBackend::SandboxExec);
+    }
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    // User namespaces can be disabled, so try each tool rather than just finding it
+    let probes: [(SandboxBackend, &str, &[&str]); 2] = [
+        (
+            SandboxBackend::Bubblewrap,
+            "bwrap",
+            &["--ro-bind", "/", "/", "--unshare-net", "true"],
+        ),
+        (
+            SandboxBackend::Unshare,
+            "unshare",
+            &["--user", "--map-root-user", "--net", "true"],
+        ),
+    ];
+    probes.into_iter().find_map(|(backend, program, args)| {
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .ok()
+            .filter(|status| status.success())
+            .map(|_| backend)
+    })
+}
+
+/// Reject profiles with unusable writable roots
+pub fn validate_profile(profile: &SandboxProfile) -> Result<()> {
+    if profile.writable_roots.len() > MAX_WRITABLE_ROOTS {
+        return Err(Error::Other(format!(
+            "A sandbox profile can list at most {MAX_WRITABLE_ROOTS} writable roots"
+        )));
+    }
+    for root in &profile.writable_roots {
+        if !Path::new(root).is_absolute() || root.contains('\0') {
+            return Err(Error::InvalidPath(format!(
+                "Sandbox writable root must be an absolute path: {root}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `argv` wrapped in the project's sandbox; unchanged when the project
+/// sets no restrictions
+///
+/// Fails when the profile cannot be enforced on this machine.
+pub fn wrap_for_project(project: &Project, argv: Vec<String>) -> Result<Vec<String>> {
+    let Some(profile) = project.settings().sandbox_profile else {
+        return Ok(argv);
+    };
+    if !profile.is_restricted() {
+        return Ok(argv);
+    }
+    let backend = backend()
+        .filter(|backend| backend.can_enforce(&profile))
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "Project '{}' runs commands in a sandbox, but this system cannot enforce it{}",
+                project.display_name.as_deref().unwrap_or(&project.path),
+                if cfg!(target_os = "linux") {
+                    "; install bubblewrap (bwrap)"
+                } else {
+                    ""
+                }
+            ))
+        })?;
+    Ok(wrap(
+        backend,
+        &profile,
+        &writable_dirs(&profile, Path::new(&project.path)),
+        argv,
+    ))
+}
+
+/// Quote `argv` for a POSIX shell
+pub fn shell_join(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Directories a `workspace` profile may write, resolved so macOS profile
+/// paths match (`/var` is `/private/var` there); missing ones are skipped
+fn writable_dirs(profile: &SandboxProfile, workspace: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if profile.filesystem == FilesystemAccess::Workspace {
+        let roots = std::iter::once(workspace.to_path_buf())
+            .chain(profile.writable_roots.iter().map(PathBuf::from));
+        for root in roots {
+            if let Ok(root) = root.canonicalize() {
+                if !dirs.contains(&root) {
+                    dirs.push(root);
+                }
+            }
+        }
+    }
+    dirs
+}
+
+fn wrap(
+    backend: SandboxBackend,
+    profile: &SandboxProfile,
+    writable: &[PathBuf],
+    argv: Vec<String>,
+) -> Vec<String> {
+    let mut wrapped: Vec<String> = Vec::new();
+    match backend {
+        SandboxBackend::SandboxExec => {
+            wrapped.push(SANDBOX_EXEC_PATH.to_string());
+            wrapped.push("-p".to_string());
+            wrapped.push(sbpl_profile(profile, writable.len()));
+            let temp = std::env::temp_dir()
+                .canonicalize()
+                .unwrap_or_else(|_| std::env::temp_dir());
+            wrapped.push("-D".to_string());
+            wrapped.push(format!("TMPDIR={}", temp.display()));
+            for (index, dir) in writable.iter().enumerate() {
+                wrapped.push("-D".to_string());
+                wrapped.push(format!("WRITABLE_{index}={}", dir.display()));
+            }
+        }
+        SandboxBackend::Bubblewrap => {
+            wrapped.push("bwrap".to_string());
+            let root_bind = match profile.filesystem {
+                FilesystemAccess::Full => "--bind",
+                FilesystemAccess::Workspace | FilesystemAccess::ReadOnly => "--ro-bind",
+            };
+            for arg in [root_bind, "/", "/", "--dev", "/dev", "--proc", "/proc"] {
+                wrapped.push(arg.to_string());
+            }
+            if profile.filesystem != FilesystemAccess::Full {
+                wrapped.push("--tmpfs".to_string());
+                wrapped.push("/tmp".to_string());
+            }
+            for dir in writable {
+                let dir = dir.to_string_lossy().to_string();
+                wrapped.extend(["--bind".to_string(), dir.clone(), dir]);
+            }
+            if profile.block_network {
+                wrapped.push("--unshare-net".to_string());
+            }
+            wrapped.push("--die-with-parent".to_string());
+        }
+        SandboxBackend::Unshare => {
+            for arg in ["unshare", "--user", "--map-root-user", "--net"] {
+                wrapped.push(arg.to_string());
+            }
+        }
+    }
+    wrapped.extend(argv);
+    wrapped
+}
+
+/// SBPL for `sandbox-exec`; paths come in as `-D` parameters so they need
+/// no escaping
+fn sbpl_profile(profile: &SandboxProfile, writable_count: usize) -> String {
+    let mut sbpl = String::from("(version 1)\n(allow default)\n");
+    if profile.filesystem != FilesystemAccess::Full {
+        sbpl.push_str("(deny file-write*)\n(allow file-write*\n");
+        sbpl.push_str("  (literal \"/dev/null\") (literal \"/dev/tty\") (regex #\"^/dev/fd/\")\n");
+        sbpl.push_str("  (subpath \"/private/tmp\") (subpath (param \"TMPDIR\"))");
+        for index in 0..writable_count {
+            sbpl.push_str(&format!("\n  (subpath (param \"WRITABLE_{index}\"))"));
+        }
+        sbpl.push_str(")\n");
+    }
+    if profile.block_network {
+        sbpl.push_str("(deny network*)\n(allow network* (local unix-socket))\n");
+    }
+    sbpl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(filesystem: FilesystemAccess, block_network: bool) -> SandboxProfile {
+        SandboxProfile {
+            filesystem,
+            block_network,
+            writable_roots: Vec::new(),
+        }
+    }
+
+    fn argv() -> Vec<String> {
+        vec!["/bin/sh".to_string(), "-c".to_string(), "make test".to_string()]
+    }
+
+    #[test]
+    fn test_bubblewrap_and_unshare_arguments() {
+        let writable = vec![PathBuf::from("/work/app")];
+        let wrapped = wrap(
+            SandboxBackend::Bubblewrap,
+            &profile(FilesystemAccess::Workspace, true),
+            &writable,
+            argv(),
+        );
+        assert_eq!(
+            wrapped.join(" "),
+            "bwrap --ro-bind / / --dev /dev --proc /proc --tmpfs /tmp \
+             --bind /work/app /work/app --unshare-net --die-with-parent /bin/sh -c make test"
+        );
+
+        let wrapped = wrap(
+            SandboxBackend::Unshare,
+            &profile(FilesystemAccess::Full, true),
+            &[],
+            argv(),
+        );
+        assert_eq!(wrapped[..4], ["unshare", "--user", "--map-root-user", "--net"]);
+        assert!(!SandboxBackend::Unshare.can_enforce(&profile(FilesystemAccess::ReadOnly, false)));
+    }
+
+    #[test]
+    fn test_sandbox_exec_profile() {
+        let sbpl = sbpl_profile(&profile(FilesystemAccess::Workspace, false), 2);
+        assert!(sbpl.contains("(deny file-write*)"));
+        assert!(sbpl.contains("(subpath (param \"WRITABLE_1\"))"));
+        assert!(!sbpl.contains("network"));
+
+        let sbpl = sbpl_profile(&profile(FilesystemAccess::Full, true), 0);
+        assert!(!sbpl.contains("file-write"));
+        assert!(sbpl.contains("(deny network*)"));
+    }
+
+    #[test]
+    fn test_shell_join_and_validation() {
+        let argv = vec![
+            "bwrap".to_string(),
+            "/bin/sh".to_string(),
+            "-c".to_string(),
+            "echo 'hi' > out.txt".to_string(),
+        ];
+        assert_eq!(
+            shell_join(&argv),
+            r#"bwrap /bin/sh -c 'echo '\''hi'\'' > out.txt'"#
+        );
+
+        let mut relative = profile(FilesystemAccess::Workspace, false);
+        relative.writable_roots = vec!["build".to_string()];
+        assert!(validate_profile(&relative).is_err());
+        assert!(!SandboxProfile::default().is_restricted());
+    }
+}
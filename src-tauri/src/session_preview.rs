@@ -0,0 +1,126 @@
+//! Denormalized session list data derived from thread items.
+//!
+//! `session_metadata` keeps a last-message preview, turn count, files-changed
+//! count and last model so the session list can render without loading
+//! transcripts. They are updated incrementally as turns start and items
+//! complete, and recomputed from the full item list when a thread is resumed.
+
+use serde_json::Value as JsonValue;
+
+use crate::app_server::ipc_bridge::{TurnStartParams, UserInput};
+use crate::database::{Database, SessionPreview};
+
+/// Maximum characters kept in a message preview
+const MAX_PREVIEW_CHARS: usize = 200;
+
+/// Mask secrets, collapse whitespace and clip `text` for display; `None`
+/// when blank
+pub fn preview_text(text: &str) -> Option<String> {
+    let text = crate::secret_mask::mask(text);
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+        return None;
+    }
+    Some(match collapsed.char_indices().nth(MAX_PREVIEW_CHARS) {
+        Some((idx, _)) => format!("{}…", &collapsed[..idx]),
+        None => collapsed,
+    })
+}
+
+/// Text of a `userMessage` or `agentMessage` item
+pub fn message_text(item: &JsonValue) -> Option<String> {
+    match item.get("type")?.as_str()? {
+        "userMessage" => {
+            let text: Vec<&str> = item
+                .get("content")?
+                .as_array()?
+                .iter()
+                .filter_map(|entry| entry.get("text").and_then(JsonValue::as_str))
+                .collect();
+            Some(text.join("\n"))
+        }
+        "agentMessage" => item.get("text")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Number of file entries in a `fileChange` item (0 for other items)
+pub fn files_changed(item: &JsonValue) -> u32 {
+    if item.get("type").and_then(JsonValue::as_str) != Some("fileChange") {
+        return 0;
+    }
+    item.get("changes")
+        .and_then(JsonValue::as_array)
+        .map_or(0, |changes| changes.len() as u32)
+}
+
+/// Count a turn that was just started and record its prompt and model
+///
+/// Failures are logged; preview data never blocks a turn.
+pub fn record_turn_started(database: &Database, params: &TurnStartParams) {
+    let text: Vec<&str> = params
+        .input
+        .iter()
+        .filter_map(|input| match input {
+            UserInput::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let preview = preview_text(&text.join("\n"));
+    if let Err(err) =
+        database.record_turn_started(&params.thread_id, preview.as_deref(), params.model.as_deref())
+    {
+        tracing::warn!("Failed to update session preview: {}", err);
+    }
+}
+
+/// Recompute preview fields from a thread's complete item list
+///
+/// Each user message starts a turn, so turns are counted from those.
+pub fn summarize(items: &[JsonValue]) -> SessionPreview {
+    let turn_count = items
+        .iter()
+        .filter(|item| item.get("type").and_then(JsonValue::as_str) == Some("userMessage"))
+        .count() as u32;
+    let last_message_preview = items
+        .iter()
+        .rev()
+        .filter_map(message_text)
+        .find_map(|text| preview_text(&text));
+
+    SessionPreview {
+        last_message_preview,
+        turn_count,
+        files_changed_count: items.iter().map(files_changed).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_summarize_items() {
+        let items = vec![
+            json!({ "type": "userMessage", "content": [{ "type": "text", "text": "Fix the parser" }] }),
+            json!({ "type": "fileChange", "changes": [{ "path": "a.rs" }, { "path": "b.rs" }] }),
+            json!({ "type": "agentMessage", "text": "Done:\n  fixed   both files" }),
+            json!({ "type": "userMessage", "content": [{ "type": "text", "text": "Thanks" }] }),
+            json!({ "type": "agentMessage", "text": "   " }),
+        ];
+
+        let preview = summarize(&items);
+        assert_eq!(preview.turn_count, 2);
+        assert_eq!(preview.files_changed_count, 2);
+        assert_eq!(preview.last_message_preview.as_deref(), Some("Thanks"));
+    }
+
+    #[test]
+    fn test_preview_text_clips() {
+        let long = "x".repeat(MAX_PREVIEW_CHARS + 10);
+        let preview = preview_text(&long).unwrap();
+        assert_eq!(preview.chars().count(), MAX_PREVIEW_CHARS + 1);
+        assert!(preview_text(" \n ").is_none());
+    }
+}
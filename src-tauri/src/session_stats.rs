@@ -0,0 +1,189 @@
+//! Session usage analytics.
+//!
+//! Token usage, tool calls, touched files and active time are recorded per
+//! session as app-server notifications arrive; `aggregate` rolls the
+//! per-session rows up by day and project for `get_session_stats`.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::database::{Database, SessionStats, TokenUsage};
+
+/// Completed item types counted as tool calls
+pub const TOOL_ITEM_TYPES: &[&str] = &["commandExecution", "fileChange", "mcpToolCall", "webSearch"];
+
+/// Count a completed tool item and the files it changed
+///
+/// Failures are logged; analytics never interrupt a session.
+pub fn record_item(database: &Database, thread_id: &str, item: &JsonValue) {
+    let Some(kind) = item
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .filter(|kind| TOOL_ITEM_TYPES.contains(kind))
+    else {
+        return;
+    };
+    let paths: Vec<String> = item
+        .get("changes")
+        .and_then(JsonValue::as_array)
+        .map(|changes| {
+            changes
+                .iter()
+                .filter_map(|change| change.get("path").and_then(JsonValue::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Err(err) = database.record_tool_call(thread_id, kind, &paths) {
+        tracing::warn!("Failed to record tool call: {}", err);
+    }
+}
+
+/// Store the running total of a `thread/tokenUsage/updated` notification
+pub fn record_token_usage(database: &Database, thread_id: &str, usage: &JsonValue) {
+    let Some(total) = usage.get("total") else {
+        return;
+    };
+    let usage: TokenUsage = match serde_json::from_value(total.clone()) {
+        Ok(usage) => usage,
+        Err(err) => {
+            tracing::warn!("Ignoring malformed token usage: {}", err);
+            return;
+        }
+    };
+    if let Err(err) = database.record_token_usage(thread_id, &usage) {
+        tracing::warn!("Failed to record token usage: {}", err);
+    }
+}
+
+/// Metrics summed over a group of sessions
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsBucket {
+    /// Day (YYYY-MM-DD, UTC), project ID, or "all" for the totals
+    pub key: String,
+    pub sessions: u32,
+    pub turns: u32,
+    pub token_usage: TokenUsage,
+    pub tool_calls: BTreeMap<String, u32>,
+    pub files_touched: u32,
+    pub active_seconds: i64,
+    /// Turns per model
+    pub models: BTreeMap<String, u32>,
+}
+
+impl StatsBucket {
+    fn add(&mut self, stats: &SessionStats) {
+        self.sessions += 1;
+        self.turns += stats.turn_count;
+        self.token_usage.add(&stats.token_usage);
+        for (kind, count) in &stats.tool_calls {
+            *self.tool_calls.entry(kind.clone()).or_default() += count;
+        }
+        self.files_touched += stats.files_touched;
+        self.active_seconds += stats.active_seconds;
+        if let Some(model) = &stats.model {
+            *self.models.entry(model.clone()).or_default() += stats.turn_count;
+        }
+    }
+}
+
+/// Result of `get_session_stats`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatsReport {
+    pub sessions: Vec<SessionStats>,
+    /// By the UTC day each session was created, oldest first
+    pub by_day: Vec<StatsBucket>,
+    pub by_project: Vec<StatsBucket>,
+    pub totals: StatsBucket,
+}
+
+/// Roll per-session metrics up by day and project
+///
+/// A session counts toward the day it was created, so a long session's
+/// usage is not split across days.
+pub fn aggregate(sessions: Vec<SessionStats>) -> SessionStatsReport {
+    let mut by_day: BTreeMap<String, StatsBucket> = BTreeMap::new();
+    let mut by_project: BTreeMap<String, StatsBucket> = BTreeMap::new();
+    let mut totals = StatsBucket {
+        key: "all".to_string(),
+        ..Default::default()
+    };
+
+    for stats in &sessions {
+        let day = chrono::DateTime::from_timestamp(stats.created_at, 0)
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        by_day
+            .entry(day.clone())
+            .or_insert_with(|| StatsBucket {
+                key: day,
+                ..Default::default()
+            })
+            .add(stats);
+        by_project
+            .entry(stats.project_id.clone())
+            .or_insert_with(|| StatsBucket {
+                key: stats.project_id.clone(),
+                ..Default::default()
+            })
+            .add(stats);
+        totals.add(stats);
+    }
+
+    SessionStatsReport {
+        sessions,
+        by_day: by_day.into_values().collect(),
+        by_project: by_project.into_values().collect(),
+        totals,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(session_id: &str, project_id: &str, created_at: i64, tokens: u64) -> SessionStats {
+        SessionStats {
+            session_id: session_id.to_string(),
+            project_id: project_id.to_string(),
+            title: session_id.to_string(),
+            model: Some("gpt-5".to_string()),
+            created_at,
+            last_activity_at: None,
+            turn_count: 2,
+            token_usage: TokenUsage {
+                total_tokens: tokens,
+                ..Default::default()
+            },
+            tool_calls: BTreeMap::from([("commandExecution".to_string(), 3)]),
+            files_touched: 1,
+            active_seconds: 60,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_by_day_and_project() {
+        let day = 86_400;
+        let report = aggregate(vec![
+            stats("s3", "p2", day + 10, 500),
+            stats("s2", "p1", 20, 200),
+            stats("s1", "p1", 10, 100),
+        ]);
+
+        assert_eq!(report.totals.sessions, 3);
+        assert_eq!(report.totals.token_usage.total_tokens, 800);
+        assert_eq!(report.totals.tool_calls["commandExecution"], 9);
+        assert_eq!(report.totals.models["gpt-5"], 6);
+
+        let days: Vec<(&str, u32)> =
+            report.by_day.iter().map(|b| (b.key.as_str(), b.sessions)).collect();
+        assert_eq!(days, vec![("1970-01-01", 2), ("1970-01-02", 1)]);
+        assert_eq!(report.by_project[0].key, "p1");
+        assert_eq!(report.by_project[0].token_usage.total_tokens, 300);
+        assert_eq!(report.by_project[0].active_seconds, 120);
+    }
+}
@@ -0,0 +1,250 @@
+//! A git worktree and branch of its own for each session.
+//!
+//! With a project's `worktreePerSession` on, `start_thread` creates a branch
+//! named from `worktreeBranchTemplate` and a worktree for it next to the
+//! project, and runs the agent there, so parallel sessions do not step on
+//! each other's files. When such a session is archived the renderer is
+//! offered to merge the branch back into the project's checkout or to
+//! delete it along with the worktree.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::SessionWorktree;
+use crate::git;
+use crate::Result;
+
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "codex/{date}-{id}";
+
+/// What to do with a session's worktree and branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorktreeCleanup {
+    /// Commit what is left in the worktree, merge the branch into the
+    /// project's current branch, then remove both
+    Merge,
+    /// Remove the worktree and branch, discarding their changes
+    Delete,
+}
+
+/// A session worktree with what cleaning it up would involve
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWorktreeStatus {
+    #[serde(flatten)]
+    pub worktree: SessionWorktree,
+    /// The worktree directory is still there
+    pub exists: bool,
+    /// The worktree has uncommitted changes
+    pub dirty: bool,
+    /// Commits on the branch that its base branch lacks
+    pub commits_ahead: u32,
+}
+
+/// Branch name for a new session worktree; `{project}`, `{date}` and `{id}`
+/// are filled in, and `id` is appended when the template lacks it so names
+/// stay unique
+pub fn branch_name(template: Option<&str>, project_name: &str, id: &str, date: &str) -> String {
+    let template = template
+        .map(str::trim)
+        .filter(|template| !template.is_empty())
+        .unwrap_or(DEFAULT_BRANCH_TEMPLATE);
+    let mut name = template
+        .replace("{project}", &slug(project_name))
+        .replace("{date}", date)
+        .replace("{id}", id);
+    if !template.contains("{id}") {
+        name = format!("{}-{id}", name.trim_end_matches(['/', '-']));
+    }
+
+    let mut clean = String::with_capacity(name.len());
+    for c in name.chars() {
+        let c = if c.is_ascii_alphanumeric() || "/._-".contains(c) {
+            c
+        } else {
+            '-'
+        };
+        // No empty path components, `..` or `-` runs
+        if (c == '/' || c == '.' || c == '-') && clean.ends_with(c) {
+            continue;
+        }
+        clean.push(c);
+    }
+    let clean = clean
+        .split('/')
+        .map(|part| part.trim_matches(['.', '-']))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    let clean = clean.strip_suffix(".lock").unwrap_or(&clean);
+    if clean.is_empty() {
+        format!("codex/{id}")
+    } else {
+        clean.to_string()
+    }
+}
+
+fn slug(name: &str) -> String {
+    let slug: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Branch checked out in `repo`; `None` when detached. Blocking.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    git::value(repo, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .ok()
+        .filter(|branch| !branch.is_empty())
+}
+
+/// Full hash of the commit checked out in `repo`. Blocking.
+pub fn head_commit(repo: &Path) -> Result<String> {
+    git::value(repo, &["rev-parse", "--verify", "HEAD"])
+}
+
+/// Everything that changed in the worktree since `base`, committed or not,
+/// untracked files included. A scratch index is used so the worktree's own
+/// index is left alone. Blocking.
+pub fn diff_since(worktree: &Path, base: &str) -> Result<String> {
+    let index = git::value(worktree, &["rev-parse", "--git-path", "codex-diff-index"])?;
+    let index = worktree.join(index);
+    let run = |args: &[&str]| git::run_with_index(worktree, &index, args);
+    let diff = run(&["read-tree", "HEAD"])
+        .and_then(|_| run(&["add", "-A"]))
+        .and_then(|_| run(&["diff", "--cached", "--no-color", base]));
+    let _ = std::fs::remove_file(&index);
+    diff
+}
+
+/// Whether the worktree exists, has uncommitted changes and is ahead of its
+/// base. Blocking.
+pub fn status(worktree: SessionWorktree) -> SessionWorktreeStatus {
+    let path = Path::new(&worktree.path);
+    let exists = path.is_dir();
+    let dirty = exists
+        && git::run(path, &["status", "--porcelain"]).is_ok_and(|status| !status.is_empty());
+    let base = worktree.base_branch.as_deref().or(worktree.base_commit.as_deref());
+    let commits_ahead = match base {
+        Some(base) if exists => {
+            git::value(path, &["rev-list", "--count", &format!("{base}..HEAD")])
+                .ok()
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0)
+        }
+        _ => 0,
+    };
+    SessionWorktreeStatus {
+        worktree,
+        exists,
+        dirty,
+        commits_ahead,
+    }
+}
+
+/// Commit everything left in the worktree; returns whether there was
+/// anything to commit. Blocking.
+pub fn commit_all(worktree: &Path, message: &str) -> Result<bool> {
+    git::run(worktree, &["add", "-A"])?;
+    if git::run(worktree, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(false);
+    }
+    git::run(worktree, &["commit", "--no-verify", "-q", "-m", message])?;
+    Ok(true)
+}
+
+/// Delete a branch; `force` also deletes one that is not merged. Blocking.
+pub fn delete_branch(repo: &Path, branch: &str, force: bool) -> Result<()> {
+    let flag = if force { "-D" } else { "-d" };
+    git::run(repo, &["branch", flag, "--", branch]).map(|_| ())
+}
+
+/// Forget worktrees whose directories are gone. Blocking.
+pub fn prune(repo: &Path) -> Result<()> {
+    git::run(repo, &["worktree", "prune"]).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(
+            branch_name(None, "My App", "1a2b3c4d", "20260101"),
+            "codex/20260101-1a2b3c4d"
+        );
+        assert_eq!(
+            branch_name(Some("agents/{project}/{id}"), "My App!", "1a2b", "20260101"),
+            "agents/my-app/1a2b"
+        );
+        // The id is added when the template leaves it out
+        assert_eq!(
+            branch_name(Some("wip/{project}-"), "api", "1a2b", "20260101"),
+            "wip/api-1a2b"
+        );
+        assert_eq!(
+            branch_name(Some("../x y//..z.lock"), "p", "1a2b", "d"),
+            "x-y/z.lock-1a2b"
+        );
+        assert_eq!(branch_name(Some("{id}.lock"), "p", "1a2b", "d"), "1a2b");
+    }
+
+    #[test]
+    fn test_commit_and_status() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path();
+        git::run(repo, &["init", "-q", "-b", "main"]).unwrap();
+        git::run(repo, &["config", "user.email", "test@example.com"]).unwrap();
+        git::run(repo, &["config", "user.name", "Test"]).unwrap();
+        fs::write(repo.join("a.txt"), "one\n").unwrap();
+        commit_all(repo, "init").unwrap();
+        assert_eq!(current_branch(repo).as_deref(), Some("main"));
+
+        git::run(repo, &["checkout", "-q", "-b", "codex/s1"]).unwrap();
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+        let worktree = SessionWorktree {
+            session_id: "s1".to_string(),
+            project_id: "p1".to_string(),
+            path: repo.to_string_lossy().to_string(),
+            branch: "codex/s1".to_string(),
+            base_branch: Some("main".to_string()),
+            base_commit: None,
+            created_at: 0,
+        };
+        let before = status(worktree.clone());
+        assert!(before.exists && before.dirty);
+        assert_eq!(before.commits_ahead, 0);
+
+        assert!(commit_all(repo, "session").unwrap());
+        assert!(!commit_all(repo, "again").unwrap());
+        let after = status(worktree);
+        assert!(!after.dirty);
+        assert_eq!(after.commits_ahead, 1);
+
+        let base = git::value(repo, &["rev-parse", "main"]).unwrap();
+        fs::write(repo.join("new.txt"), "three\n").unwrap();
+        let diff = diff_since(repo, &base).unwrap();
+        assert!(diff.contains("+two") && diff.contains("+three"));
+        // The real index still lacks the untracked file
+        assert!(git::run(repo, &["status", "--porcelain"]).unwrap().contains("?? new.txt"));
+        assert_eq!(head_commit(repo).unwrap().len(), 40);
+        fs::remove_file(repo.join("new.txt")).unwrap();
+
+        git::run(repo, &["checkout", "-q", "main"]).unwrap();
+        assert!(delete_branch(repo, "codex/s1", false).is_err());
+        delete_branch(repo, "codex/s1", true).unwrap();
+    }
+}
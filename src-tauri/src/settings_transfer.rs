@@ -0,0 +1,332 @@
+//! Export and import of workspace-wide settings.
+//!
+//! A settings file carries the portable global settings sections, every
+//! project's command allowlist (keyed by project path, since project ids
+//! differ between machines), optionally the projects themselves, and an
+//! opaque blob of renderer-owned settings such as the prompt library and
+//! keybindings. Importing merges into what is already there: nothing is
+//! removed, and allowlists for paths that are not projects here are skipped.
+
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::allowlist::AllowlistPattern;
+use crate::database::{Database, Project, ProjectSettings, ProjectUpdate};
+use crate::global_state::unix_timestamp_millis;
+use crate::{Error, Result};
+
+/// Value of the `format` field of settings files
+pub const FORMAT: &str = "codex-desktop-settings";
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Largest settings file accepted when importing
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Project settings that only mean something on the machine they were set on
+const LOCAL_PROJECT_SETTINGS: &[&str] = &["accountId"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    pub format: String,
+    pub version: u32,
+    /// Unix milliseconds
+    pub exported_at: i64,
+    /// Portable global state sections, keyed as in the state file
+    #[serde(default)]
+    pub global: Map<String, JsonValue>,
+    #[serde(default)]
+    pub allowlists: Vec<AllowlistExport>,
+    /// Present only when projects were included in the export
+    #[serde(default)]
+    pub projects: Option<Vec<ProjectExport>>,
+    /// Renderer-owned settings (prompt library, keybindings), stored as given
+    #[serde(default)]
+    pub renderer: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AllowlistExport {
+    pub project_path: String,
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectExport {
+    pub path: String,
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub emoji: Option<String>,
+    /// Project settings without machine-local fields
+    #[serde(default)]
+    pub settings: Map<String, JsonValue>,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// Global settings sections replaced
+    pub global_sections: Vec<String>,
+    /// Allowlist patterns added
+    pub allowlist_patterns: usize,
+    pub projects_added: usize,
+    pub projects_updated: usize,
+    /// Entries left out, with the reason
+    pub skipped: Vec<String>,
+    /// Renderer-owned settings from the file, for the renderer to restore
+    pub renderer: Option<JsonValue>,
+}
+
+/// Gather allowlists (and projects, if asked) into a bundle
+pub fn export(
+    database: &Database,
+    global: Map<String, JsonValue>,
+    include_projects: bool,
+    renderer: Option<JsonValue>,
+) -> Result<SettingsBundle> {
+    let projects = database.get_all_projects()?;
+
+    let mut allowlists = Vec::new();
+    for project in &projects {
+        let patterns = database.get_allowlist(&project.id)?;
+        if !patterns.is_empty() {
+            allowlists.push(AllowlistExport {
+                project_path: project.path.clone(),
+                patterns,
+            });
+        }
+    }
+
+    let projects = include_projects.then(|| {
+        projects
+            .iter()
+            .map(|project| ProjectExport {
+                path: project.path.clone(),
+                display_name: project.display_name.clone(),
+                color: project.color.clone(),
+                icon: project.icon.clone(),
+                emoji: project.emoji.clone(),
+                settings: portable_project_settings(project.settings_json.as_deref()),
+            })
+            .collect()
+    });
+
+    Ok(SettingsBundle {
+        format: FORMAT.to_string(),
+        version: FORMAT_VERSION,
+        exported_at: unix_timestamp_millis(),
+        global,
+        allowlists,
+        projects,
+        renderer,
+    })
+}
+
+/// Add the bundle's projects and allowlists to the database
+///
+/// Projects already present (by path) get their settings merged; new ones
+/// are added only if their directory exists here. `global_sections` is
+/// left for the caller, which owns the global state.
+pub fn import(database: &Database, bundle: SettingsBundle) -> Result<ImportSummary> {
+    let mut summary = ImportSummary {
+        renderer: bundle.renderer,
+        ..ImportSummary::default()
+    };
+
+    for export in bundle.projects.unwrap_or_default() {
+        let mut settings = export.settings;
+        settings.retain(|key, _| !LOCAL_PROJECT_SETTINGS.contains(&key.as_str()));
+        if let Err(err) =
+            serde_json::from_value::<ProjectSettings>(JsonValue::Object(settings.clone()))
+        {
+            summary
+                .skipped
+                .push(format!("Project {}: invalid settings ({err})", export.path));
+            continue;
+        }
+
+        let existing = find_project(database, &export.path)?;
+        if let Some(project) = existing {
+            let update = ProjectUpdate {
+                display_name: export.display_name,
+                settings_patch: Some(JsonValue::Object(settings)),
+                last_opened_at: None,
+            };
+            database.update_project(&project.id, &update)?;
+            summary.projects_updated += 1;
+        } else if Path::new(&export.path).is_dir() {
+            let mut project = Project::new(&export.path);
+            if export.display_name.is_some() {
+                project.display_name = export.display_name;
+            }
+            if export.color.is_some() {
+                project.color = export.color;
+            }
+            project.icon = export.icon;
+            project.emoji = export.emoji;
+            project.settings_json = Some(serde_json::to_string(&settings)?);
+            database.insert_project(&project)?;
+            summary.projects_added += 1;
+        } else {
+            summary
+                .skipped
+                .push(format!("Project {}: directory does not exist", export.path));
+        }
+    }
+
+    for allowlist in bundle.allowlists {
+        let Some(project) = find_project(database, &allowlist.project_path)? else {
+            summary.skipped.push(format!(
+                "Allowlist for {}: not a project here",
+                allowlist.project_path
+            ));
+            continue;
+        };
+        let existing = database.get_allowlist(&project.id)?;
+        for pattern in allowlist.patterns {
+            let pattern = match AllowlistPattern::parse(&pattern) {
+                Ok(pattern) => pattern,
+                Err(err) => {
+                    summary.skipped.push(err.to_string());
+                    continue;
+                }
+            };
+            if !existing.iter().any(|stored| stored == pattern.as_str()) {
+                database.add_to_allowlist(&project.id, pattern.as_str())?;
+                summary.allowlist_patterns += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Write `bundle` to `path` as pretty-printed JSON, atomically
+pub fn write(path: &Path, bundle: &SettingsBundle) -> Result<()> {
+    crate::utils::atomic_write(path, &serde_json::to_vec_pretty(bundle)?)
+}
+
+/// Read a settings file written by [`write`]
+pub fn read(path: &Path) -> Result<SettingsBundle> {
+    let mut json = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_FILE_BYTES + 1)
+        .read_to_end(&mut json)?;
+    if json.len() as u64 > MAX_FILE_BYTES {
+        return Err(Error::Other(format!(
+            "Settings file is too large: {}",
+            path.display()
+        )));
+    }
+
+    let bundle: SettingsBundle = serde_json::from_slice(&json)?;
+    if bundle.format != FORMAT {
+        return Err(Error::Other(format!(
+            "Not a settings file: {}",
+            path.display()
+        )));
+    }
+    if bundle.version > FORMAT_VERSION {
+        return Err(Error::Other(format!(
+            "Settings file version {} is newer than this app supports",
+            bundle.version
+        )));
+    }
+    Ok(bundle)
+}
+
+fn portable_project_settings(settings_json: Option<&str>) -> Map<String, JsonValue> {
+    let mut settings = settings_json
+        .and_then(|json| serde_json::from_str::<Map<String, JsonValue>>(json).ok())
+        .unwrap_or_default();
+    settings
+        .retain(|key, value| !value.is_null() && !LOCAL_PROJECT_SETTINGS.contains(&key.as_str()));
+    settings
+}
+
+fn find_project(database: &Database, path: &str) -> Result<Option<Project>> {
+    Ok(database
+        .get_all_projects()?
+        .into_iter()
+        .find(|project| project.path == path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = Database::new(&temp_dir.path().join("source.db")).unwrap();
+        let project_dir = temp_dir.path().join("app");
+        std::fs::create_dir(&project_dir).unwrap();
+        let project_path = project_dir.to_string_lossy().to_string();
+
+        let mut project = Project::new(&project_path);
+        project.settings_json =
+            Some(r#"{"model":"o3","accountId":"acct-1","approvalPreset":"autoApproveRead"}"#.into());
+        source.insert_project(&project).unwrap();
+        source.add_to_allowlist(&project.id, "npm test").unwrap();
+        let missing = Project::new("/does/not/exist");
+        source.insert_project(&missing).unwrap();
+        source.add_to_allowlist(&missing.id, "ls").unwrap();
+
+        let bundle = export(
+            &source,
+            Map::new(),
+            true,
+            Some(serde_json::json!({ "k": 1 })),
+        )
+        .unwrap();
+        let projects = bundle.projects.as_ref().unwrap();
+        assert_eq!(projects.len(), 2);
+        assert!(projects
+            .iter()
+            .all(|p| !p.settings.contains_key("accountId")));
+
+        let path = temp_dir.path().join("settings.json");
+        write(&path, &bundle).unwrap();
+        let bundle = read(&path).unwrap();
+
+        let target = Database::new(&temp_dir.path().join("target.db")).unwrap();
+        let summary = import(&target, bundle.clone()).unwrap();
+        assert_eq!(summary.projects_added, 1);
+        assert_eq!(summary.allowlist_patterns, 1);
+        assert_eq!(summary.skipped.len(), 2);
+        assert_eq!(summary.renderer, Some(serde_json::json!({ "k": 1 })));
+
+        let imported = find_project(&target, &project_path).unwrap().unwrap();
+        assert_eq!(imported.settings().model.as_deref(), Some("o3"));
+        assert_eq!(imported.settings().account_id, None);
+        assert_eq!(
+            target.get_allowlist(&imported.id).unwrap(),
+            vec!["npm test"]
+        );
+
+        // Importing again changes nothing new
+        let summary = import(&target, bundle).unwrap();
+        assert_eq!(summary.projects_updated, 1);
+        assert_eq!(summary.allowlist_patterns, 0);
+    }
+
+    #[test]
+    fn test_read_rejects_other_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("other.json");
+        std::fs::write(
+            &path,
+            r#"{"format":"something-else","version":1,"exportedAt":0}"#,
+        )
+        .unwrap();
+        assert!(read(&path).is_err());
+    }
+}
@@ -0,0 +1,586 @@
+//! Per-file diffs between two snapshots, or a snapshot and the working tree
+//!
+//! Each side is listed as relative path -> where its contents live (a git
+//! blob, a backup file or inline backup, a working-tree file). Files whose
+//! contents match are skipped; the rest are diffed with `git diff
+//! --no-index` through temporary files, so every snapshot type compares the
+//! same way and a revert can be previewed before it is applied.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Serialize;
+
+use super::store::{object_path, read_object};
+use super::{
+    collect_project_files, is_git_repo, validate_commit_sha, FileBackupMetadata, FILE_REF_PREFIX,
+    MAX_BACKUP_FILE_BYTES,
+};
+use crate::database::{Database, Snapshot};
+use crate::git;
+use crate::{Error, Result};
+
+/// Changed files diffed in full; later ones are listed without a diff
+const MAX_DIFFED_FILES: usize = 500;
+
+/// Diff text kept per file; the line counts cover the whole diff
+const MAX_FILE_DIFF_BYTES: usize = 256 * 1024;
+
+/// Leading bytes searched for NUL to tell binary files apart, as git does
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeStatus {
+    Added,
+    Deleted,
+    Modified,
+}
+
+/// Changes to one file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotFileDiff {
+    /// Relative to the project root
+    pub path: String,
+    pub status: FileChangeStatus,
+    /// Unified diff hunks without file headers; empty for binary files
+    pub diff: String,
+    pub insertions: u32,
+    pub deletions: u32,
+    pub binary: bool,
+    /// `diff` was cut at the size limit, or left out past the file limit
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub from_snapshot_id: String,
+    /// `None` when compared with the working tree
+    pub to_snapshot_id: Option<String>,
+    /// Sorted by path
+    pub files: Vec<SnapshotFileDiff>,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Where a listed file's contents live
+pub(super) enum Source {
+    Inline(Vec<u8>),
+    File(PathBuf),
+    /// Compressed object in the snapshot store
+    Object(PathBuf),
+    /// Blob in the project's repository
+    Git(String),
+}
+
+pub(super) struct Entry {
+    /// Git blob id, when known without reading the contents
+    pub(super) blob: Option<String>,
+    source: Source,
+}
+
+pub(super) type Listing = BTreeMap<String, Entry>;
+
+/// Diff snapshot `from_id` to snapshot `to_id` of the same project. Blocking.
+pub fn diff_snapshots(
+    db: &Database,
+    from_id: &str,
+    to_id: &str,
+    project_path: &Path,
+) -> Result<SnapshotDiff> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+    let from = load_snapshot(db, from_id)?;
+    let to = load_snapshot(db, to_id)?;
+    let files = diff_listings(
+        &canonical_path,
+        &list_snapshot(&from, &canonical_path)?,
+        &list_snapshot(&to, &canonical_path)?,
+    )?;
+    Ok(summarize(from.id, Some(to.id), files))
+}
+
+/// Diff a snapshot to the current working tree, i.e. the changes a revert
+/// to it would undo. Blocking.
+pub fn diff_snapshot_to_worktree(
+    db: &Database,
+    snapshot_id: &str,
+    project_path: &Path,
+) -> Result<SnapshotDiff> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+    let snapshot = load_snapshot(db, snapshot_id)?;
+    let files = diff_listings(
+        &canonical_path,
+        &list_snapshot(&snapshot, &canonical_path)?,
+        &list_worktree(&canonical_path)?,
+    )?;
+    Ok(summarize(snapshot.id, None, files))
+}
+
+pub(super) fn load_snapshot(db: &Database, snapshot_id: &str) -> Result<Snapshot> {
+    db.get_snapshot(snapshot_id)?
+        .ok_or_else(|| Error::SnapshotNotFound(snapshot_id.to_string()))
+}
+
+fn summarize(
+    from_snapshot_id: String,
+    to_snapshot_id: Option<String>,
+    files: Vec<SnapshotFileDiff>,
+) -> SnapshotDiff {
+    SnapshotDiff {
+        from_snapshot_id,
+        to_snapshot_id,
+        insertions: files.iter().map(|file| file.insertions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+    }
+}
+
+pub(super) fn list_snapshot(snapshot: &Snapshot, project_path: &Path) -> Result<Listing> {
+    match snapshot.snapshot_type.as_str() {
+        "git_ghost" | "git_worktree" => list_git_snapshot(snapshot, project_path),
+        "file_backup" => list_file_backup(snapshot),
+        _ => Err(Error::Other(format!(
+            "Unknown snapshot type: {}",
+            snapshot.snapshot_type
+        ))),
+    }
+}
+
+fn list_git_snapshot(snapshot: &Snapshot, project_path: &Path) -> Result<Listing> {
+    let metadata: serde_json::Value = snapshot
+        .metadata_json
+        .as_ref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or_else(|| Error::Other("Invalid snapshot metadata".to_string()))?;
+    let commit_sha = metadata["commit_sha"]
+        .as_str()
+        .ok_or_else(|| Error::Other("Missing commit_sha in snapshot".to_string()))?;
+
+    if !commit_sha.starts_with("stash@") {
+        validate_commit_sha(commit_sha)?;
+        return list_git_tree(project_path, commit_sha);
+    }
+    if !commit_sha.starts_with("stash@{") || !commit_sha.ends_with('}') {
+        return Err(Error::Other("Invalid stash reference format".to_string()));
+    }
+    // A stash keeps untracked files in its third parent
+    let mut listing = list_git_tree(project_path, commit_sha)?;
+    let untracked = format!("{commit_sha}^3");
+    if git::run(project_path, &["rev-parse", "--verify", "-q", &untracked]).is_ok() {
+        listing.extend(list_git_tree(project_path, &untracked)?);
+    }
+    Ok(listing)
+}
+
+pub(super) fn list_git_tree(project_path: &Path, rev: &str) -> Result<Listing> {
+    let output = git::run_bytes(project_path, &["ls-tree", "-r", "-z", "--full-tree", rev])?;
+    let mut listing = Listing::new();
+    for record in output
+        .split(|byte| *byte == 0)
+        .filter(|record| !record.is_empty())
+    {
+        let record = String::from_utf8_lossy(record);
+        // "<mode> <type> <object>\t<path>"; submodules are "commit" entries
+        let Some((info, path)) = record.split_once('\t') else {
+            continue;
+        };
+        let mut fields = info.split(' ').skip(1);
+        let (Some("blob"), Some(object)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        listing.insert(
+            path.to_string(),
+            Entry {
+                blob: Some(object.to_string()),
+                source: Source::Git(object.to_string()),
+            },
+        );
+    }
+    Ok(listing)
+}
+
+fn list_file_backup(snapshot: &Snapshot) -> Result<Listing> {
+    let metadata_str = snapshot
+        .metadata_json
+        .as_ref()
+        .ok_or_else(|| Error::Other("Missing metadata in file backup snapshot".to_string()))?;
+    let metadata: FileBackupMetadata = serde_json::from_str(metadata_str)
+        .map_err(|e| Error::Other(format!("Failed to parse file backup metadata: {e}")))?;
+
+    metadata
+        .files
+        .into_iter()
+        .map(|(path, content_or_ref)| {
+            let source = if let Some(object) = object_path(&content_or_ref) {
+                Source::Object(object)
+            } else if let Some(file_path) = content_or_ref.strip_prefix(FILE_REF_PREFIX) {
+                Source::File(PathBuf::from(file_path))
+            } else {
+                Source::Inline(BASE64.decode(&content_or_ref).map_err(|e| {
+                    Error::Other(format!("Failed to decode file content for '{path}': {e}"))
+                })?)
+            };
+            Ok((path, Entry { blob: None, source }))
+        })
+        .collect()
+}
+
+/// The files a snapshot of the project would capture now
+fn list_worktree(project_path: &Path) -> Result<Listing> {
+    if !is_git_repo(project_path) {
+        let mut listing = Listing::new();
+        for file_path in collect_project_files(project_path)? {
+            if fs::metadata(&file_path).is_ok_and(|m| m.len() > MAX_BACKUP_FILE_BYTES) {
+                continue;
+            }
+            let Ok(relative_path) = file_path.strip_prefix(project_path) else {
+                continue;
+            };
+            listing.insert(
+                relative_path.to_string_lossy().to_string(),
+                Entry {
+                    blob: None,
+                    source: Source::File(file_path.clone()),
+                },
+            );
+        }
+        return Ok(listing);
+    }
+
+    // Tracked and untracked, non-ignored files; deleted tracked files are
+    // still listed by `--cached`
+    let output = git::run_bytes(
+        project_path,
+        &[
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ],
+    )?;
+    let paths: BTreeSet<String> = output
+        .split(|byte| *byte == 0)
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        // `hash-object --stdin-paths` reads one path per line
+        .filter(|path| !path.is_empty() && !path.contains('\n'))
+        .filter(|path| project_path.join(path).is_file())
+        .collect();
+    let paths: Vec<String> = paths.into_iter().collect();
+    let blobs = hash_objects(project_path, &paths)?;
+
+    Ok(paths
+        .into_iter()
+        .zip(blobs)
+        .map(|(path, blob)| {
+            let source = Source::File(project_path.join(&path));
+            (
+                path,
+                Entry {
+                    blob: Some(blob),
+                    source,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Blob ids of working-tree files, as `git add` would store them
+fn hash_objects(project_path: &Path, paths: &[String]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = Command::new("git")
+        .args(["hash-object", "--stdin-paths"])
+        .current_dir(project_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::Git(format!("Failed to run git hash-object: {e}")))?;
+
+    // Written from another thread so a full stdout pipe cannot deadlock
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = paths.join("\n") + "\n";
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::Git(format!("Failed to run git hash-object: {e}")))?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Git(format!(
+            "git hash-object failed: {}",
+            stderr.trim()
+        )));
+    }
+    let blobs: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    if blobs.len() != paths.len() {
+        return Err(Error::Git(
+            "git hash-object returned too few ids".to_string(),
+        ));
+    }
+    Ok(blobs)
+}
+
+pub(super) fn read_source<'a>(entry: &'a Entry, project_path: &Path) -> Result<Cow<'a, [u8]>> {
+    match &entry.source {
+        Source::Inline(bytes) => Ok(Cow::Borrowed(bytes)),
+        Source::File(path) => fs::read(path)
+            .map(Cow::Owned)
+            .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display()))),
+        Source::Object(path) => read_object(path).map(Cow::Owned),
+        Source::Git(object) => {
+            git::run_bytes(project_path, &["cat-file", "blob", object]).map(Cow::Owned)
+        }
+    }
+}
+
+fn diff_listings(
+    project_path: &Path,
+    from: &Listing,
+    to: &Listing,
+) -> Result<Vec<SnapshotFileDiff>> {
+    let scratch = std::env::temp_dir().join(format!(
+        "codex-desktop-snapshot-diff-{}",
+        uuid::Uuid::new_v4()
+    ));
+    fs::create_dir_all(&scratch)
+        .map_err(|e| Error::Other(format!("Failed to create diff directory: {e}")))?;
+    let result = diff_into(project_path, from, to, &scratch);
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+fn diff_into(
+    project_path: &Path,
+    from: &Listing,
+    to: &Listing,
+    scratch: &Path,
+) -> Result<Vec<SnapshotFileDiff>> {
+    let paths: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    let mut files = Vec::new();
+    let mut diffed = 0;
+
+    for path in paths {
+        let (old, new) = (from.get(path), to.get(path));
+        if let (Some(old), Some(new)) = (old, new) {
+            if old.blob.is_some() && old.blob == new.blob {
+                continue;
+            }
+        }
+        let old = old
+            .map(|entry| read_source(entry, project_path))
+            .transpose()?;
+        let new = new
+            .map(|entry| read_source(entry, project_path))
+            .transpose()?;
+        if old == new {
+            continue;
+        }
+        let status = match (&old, &new) {
+            (None, _) => FileChangeStatus::Added,
+            (_, None) => FileChangeStatus::Deleted,
+            _ => FileChangeStatus::Modified,
+        };
+        let old = old.as_deref().unwrap_or_default();
+        let new = new.as_deref().unwrap_or_default();
+
+        let mut file = SnapshotFileDiff {
+            path: path.clone(),
+            status,
+            diff: String::new(),
+            insertions: 0,
+            deletions: 0,
+            binary: is_binary(old) || is_binary(new),
+            truncated: false,
+        };
+        if file.binary {
+            files.push(file);
+            continue;
+        }
+        if diffed >= MAX_DIFFED_FILES {
+            file.truncated = true;
+            files.push(file);
+            continue;
+        }
+        diffed += 1;
+
+        let mut diff = unified_diff(scratch, old, new)?;
+        for line in diff.lines() {
+            if line.starts_with('+') {
+                file.insertions += 1;
+            } else if line.starts_with('-') {
+                file.deletions += 1;
+            }
+        }
+        file.truncated = truncate_at_line(&mut diff, MAX_FILE_DIFF_BYTES);
+        file.diff = diff;
+        files.push(file);
+    }
+    Ok(files)
+}
+
+fn is_binary(contents: &[u8]) -> bool {
+    contents[..contents.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// Hunks of the diff from `old` to `new`, without file headers
+fn unified_diff(scratch: &Path, old: &[u8], new: &[u8]) -> Result<String> {
+    let old_path = scratch.join("old");
+    let new_path = scratch.join("new");
+    fs::write(&old_path, old)
+        .and_then(|_| fs::write(&new_path, new))
+        .map_err(|e| Error::Other(format!("Failed to write diff input: {e}")))?;
+
+    // Relative to `scratch`, where git runs
+    let output = git::output(
+        scratch,
+        &[
+            "diff",
+            "--no-index",
+            "--no-color",
+            "--no-ext-diff",
+            "--",
+            "old",
+            "new",
+        ],
+    )?;
+    // --no-index exits with 1 when the files differ
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Git(format!("git diff failed: {}", stderr.trim())));
+    }
+
+    // The headers name the temporary files; keep from the first hunk on
+    let text = String::from_utf8_lossy(&output.stdout);
+    Ok(text
+        .find("\n@@")
+        .map(|start| text[start + 1..].to_string())
+        .unwrap_or_default())
+}
+
+/// Cut `text` to at most `max_bytes`, at a line end; returns whether it was cut
+fn truncate_at_line(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let cut = text.as_bytes()[..max_bytes]
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map_or(0, |newline| newline + 1);
+    text.truncate(cut);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .expect("git runs");
+        assert!(status.success(), "git {args:?}");
+    }
+
+    #[test]
+    fn test_diff_git_snapshot_to_worktree() {
+        let (temp_dir, db) = crate::database::test_db();
+        let repo = temp_dir.path().join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        fs::write(repo.join("keep.txt"), "same\n").unwrap();
+        fs::write(repo.join("edit.txt"), "one\ntwo\n").unwrap();
+        fs::write(repo.join("gone.txt"), "bye\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        let snapshot = super::super::create_snapshot(&db, "s1", &repo, None).unwrap();
+        fs::write(repo.join("edit.txt"), "one\nTWO\nthree\n").unwrap();
+        fs::remove_file(repo.join("gone.txt")).unwrap();
+        fs::write(repo.join("new.bin"), b"\x00\x01").unwrap();
+
+        let diff = diff_snapshot_to_worktree(&db, &snapshot.id, &repo).unwrap();
+        assert_eq!(diff.to_snapshot_id, None);
+        let summary: Vec<(&str, FileChangeStatus, bool)> = diff
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.status, file.binary))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("edit.txt", FileChangeStatus::Modified, false),
+                ("gone.txt", FileChangeStatus::Deleted, false),
+                ("new.bin", FileChangeStatus::Added, true),
+            ]
+        );
+        let edit = &diff.files[0];
+        assert!(edit.diff.starts_with("@@ "));
+        assert!(edit.diff.contains("+TWO\n"));
+        assert_eq!((edit.insertions, edit.deletions), (2, 1));
+        assert_eq!((diff.insertions, diff.deletions), (2, 2));
+    }
+
+    #[test]
+    fn test_diff_file_backup_snapshots() {
+        let (temp_dir, db) = crate::database::test_db();
+        let project = temp_dir.path().join("project");
+        let snapshots_dir = temp_dir.path().join("snapshots");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("a.txt"), "alpha\n").unwrap();
+
+        let first =
+            super::super::create_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        fs::write(project.join("a.txt"), "alpha\nbeta\n").unwrap();
+        fs::write(project.join("b.txt"), "new\n").unwrap();
+        let second = super::super::create_snapshot(&db, "s1", &project, None).unwrap();
+
+        let diff = diff_snapshots(&db, &first.id, &second.id, &project).unwrap();
+        let paths: Vec<(&str, FileChangeStatus)> = diff
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.status))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("a.txt", FileChangeStatus::Modified),
+                ("b.txt", FileChangeStatus::Added)
+            ]
+        );
+        assert_eq!(diff.files[0].diff, "@@ -1 +1,2 @@\n alpha\n+beta\n");
+        assert!(diff_snapshot_to_worktree(&db, &second.id, &project)
+            .unwrap()
+            .files
+            .is_empty());
+    }
+
+    #[test]
+    fn test_truncate_at_line() {
+        let mut text = "+one\n+two\n+three\n".to_string();
+        assert!(!truncate_at_line(&mut text, 64));
+        assert!(truncate_at_line(&mut text, 12));
+        assert_eq!(text, "+one\n+two\n");
+    }
+}
@@ -1,18 +1,39 @@
 //! Snapshot management for change revert functionality
 //!
-//! Supports two snapshot types:
+//! Supports three snapshot types:
 //! - Git ghost commits for git repositories
+//! - Git working-tree commits, taken before a turn or a revert without
+//!   touching the working tree
 //! - File backups for non-git directories
+//!
+//! Old snapshots are pruned by the policy in `retention`; `patch` carries a
+//! snapshot's changes to another worktree.
+
+mod diff;
+mod patch;
+mod restore;
+mod retention;
+mod store;
+
+pub use diff::{
+    diff_snapshot_to_worktree, diff_snapshots, FileChangeStatus, SnapshotDiff, SnapshotFileDiff,
+};
+pub use patch::{
+    apply_snapshot_to_path, export_snapshot_patch, PatchApplyReport, PatchFile, SnapshotPatch,
+};
+pub use restore::{restore_files_from_snapshot, FileRestore, FileRestoreReport, RestoreAction};
+pub use retention::{apply_retention, storage_usage, RetentionPolicy, SnapshotStorageUsage};
+pub use store::{compact, snapshot_sizes, CompactionReport, SnapshotSize};
 
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use serde::{Deserialize, Serialize};
 
 use crate::database::{Database, Snapshot};
+use crate::git;
 use crate::{Error, Result};
 
 /// Path validation error types for detailed error reporting
@@ -281,12 +302,21 @@ struct FileBackupMetadata {
     files: HashMap<String, String>,
     /// Description of what was backed up
     description: String,
+    /// Set on backups taken automatically before reverting to this snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pre_revert_of: Option<String>,
 }
 
 /// Prefix used to indicate a file path reference in snapshot metadata
 /// When a value in the files HashMap starts with this prefix, the rest is a path to the file on disk
 const FILE_REF_PREFIX: &str = "file://";
 
+/// Metadata key marking a snapshot taken automatically before a revert
+const PRE_REVERT_KEY: &str = "pre_revert_of";
+
+/// Larger files are left out of file backups
+const MAX_BACKUP_FILE_BYTES: u64 = 1_000_000;
+
 /// Check if a path is a git repository
 pub fn is_git_repo(path: &Path) -> bool {
     path.join(".git").exists()
@@ -310,10 +340,74 @@ pub fn create_snapshot(
     if is_git_repo(&canonical_path) {
         create_git_snapshot(db, session_id, &canonical_path)
     } else {
-        create_file_backup_snapshot(db, session_id, &canonical_path, snapshots_dir)
+        create_file_backup_snapshot(db, session_id, &canonical_path, snapshots_dir, None)
     }
 }
 
+/// Snapshot a project before a turn starts
+///
+/// Unlike `create_snapshot`, this leaves the working tree alone, so the turn
+/// sees the project as the user left it: git repositories get a detached
+/// commit of the whole working tree instead of a stash. Blocking.
+pub fn create_turn_snapshot(
+    db: &Database,
+    session_id: &str,
+    project_path: &Path,
+    snapshots_dir: Option<&Path>,
+) -> Result<Snapshot> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+
+    if is_git_repo(&canonical_path) {
+        create_git_worktree_snapshot(db, session_id, &canonical_path, None)
+    } else {
+        create_file_backup_snapshot(db, session_id, &canonical_path, snapshots_dir, None)
+    }
+}
+
+/// ID of the snapshot a revert went to, if `snapshot` was taken
+/// automatically before that revert
+pub fn pre_revert_target(snapshot: &Snapshot) -> Option<String> {
+    let metadata: serde_json::Value =
+        serde_json::from_str(snapshot.metadata_json.as_deref()?).ok()?;
+    metadata.get(PRE_REVERT_KEY)?.as_str().map(str::to_string)
+}
+
+/// Capture the current state before reverting to `target`
+///
+/// Unlike `create_snapshot`, this never modifies the working tree: git
+/// repositories get a detached commit of the whole working tree (including
+/// untracked, non-ignored files) instead of a stash.
+fn create_pre_revert_snapshot(
+    db: &Database,
+    target: &Snapshot,
+    project_path: &Path,
+    snapshots_dir: Option<&Path>,
+) -> Result<Snapshot> {
+    if is_git_repo(project_path) {
+        create_git_worktree_snapshot(db, &target.session_id, project_path, Some(&target.id))
+    } else {
+        create_file_backup_snapshot(
+            db,
+            &target.session_id,
+            project_path,
+            snapshots_dir,
+            Some(&target.id),
+        )
+    }
+}
+
+/// Whether file backups leave out entries with this name
+fn skipped_in_backups(name: &str) -> bool {
+    name.starts_with('.')
+        || name == "node_modules"
+        || name == "target"
+        || name == "dist"
+        || name == "build"
+        || name == "__pycache__"
+}
+
 /// Collect all files in a directory (excluding hidden files and common ignore patterns)
 fn collect_project_files(project_path: &Path) -> Result<Vec<std::path::PathBuf>> {
     let mut files = Vec::new();
@@ -332,14 +426,7 @@ fn collect_project_files(project_path: &Path) -> Result<Vec<std::path::PathBuf>>
             let name = file_name.to_string_lossy();
 
             // Skip hidden files, node_modules, target, etc.
-            if name.starts_with('.')
-                || name == "node_modules"
-                || name == "target"
-                || name == "dist"
-                || name == "build"
-                || name == "__pycache__"
-                || name == ".git"
-            {
+            if skipped_in_backups(&name) {
                 continue;
             }
 
@@ -358,27 +445,24 @@ fn collect_project_files(project_path: &Path) -> Result<Vec<std::path::PathBuf>>
 
 /// Create a file backup snapshot for non-git directories
 ///
-/// When `snapshots_dir` is provided, file contents are written to disk under
-/// `<snapshots_dir>/<snapshot_id>/` and the metadata stores `file://` references.
+/// When `snapshots_dir` is provided, file contents go to the deduplicated
+/// object store under it and the metadata stores `object://` references.
 /// Otherwise, file contents are stored inline as base64 (legacy behavior).
 fn create_file_backup_snapshot(
     db: &Database,
     session_id: &str,
     project_path: &Path,
     snapshots_dir: Option<&Path>,
+    pre_revert_of: Option<&str>,
 ) -> Result<Snapshot> {
     let files = collect_project_files(project_path)?;
 
-    // Pre-generate snapshot ID so we can use it for the disk directory
-    let snapshot_id = uuid::Uuid::new_v4().to_string();
-    let disk_dir = snapshots_dir.map(|d| d.join(&snapshot_id));
-
     let mut backup_files: HashMap<String, String> = HashMap::new();
 
     for file_path in &files {
         // Only backup small files (< 1MB)
         if let Ok(metadata) = fs::metadata(file_path) {
-            if metadata.len() > 1_000_000 {
+            if metadata.len() > MAX_BACKUP_FILE_BYTES {
                 continue;
             }
         }
@@ -390,56 +474,32 @@ fn create_file_backup_snapshot(
 
             let rel_str = relative_path.to_string_lossy().to_string();
 
-            if let Some(ref dir) = disk_dir {
-                // Store on disk: write raw bytes to snapshots/<id>/<relative_path>
-                let dest = dir.join(&rel_str);
-                if let Some(parent) = dest.parent() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| Error::Other(format!("Failed to create snapshot dir: {e}")))?;
-                }
-                fs::write(&dest, &contents)
-                    .map_err(|e| Error::Other(format!("Failed to write snapshot file: {e}")))?;
-
-                // Store file reference in metadata
-                backup_files.insert(
-                    rel_str,
-                    format!("{}{}", FILE_REF_PREFIX, dest.to_string_lossy()),
-                );
-            } else {
+            let value = match snapshots_dir {
+                Some(dir) => store::store_object(dir, &contents)?,
                 // Legacy: store inline as base64
-                backup_files.insert(rel_str, BASE64.encode(&contents));
-            }
+                None => BASE64.encode(&contents),
+            };
+            backup_files.insert(rel_str, value);
         }
     }
 
     let metadata = FileBackupMetadata {
         files: backup_files.clone(),
         description: format!("Backup of {} files", backup_files.len()),
+        pre_revert_of: pre_revert_of.map(str::to_string),
     };
 
     let metadata_json = serde_json::to_string(&metadata)
         .map_err(|e| Error::Other(format!("Failed to serialize metadata: {e}")))?;
 
-    let snapshot = Snapshot::new_file_backup_with_id(&snapshot_id, session_id, &metadata_json);
+    let snapshot = Snapshot::new_file_backup(session_id, &metadata_json);
     db.insert_snapshot(&snapshot)?;
 
-    // Cleanup: Keep only 10 most recent snapshots per session
-    match db.cleanup_old_snapshots(session_id, 10) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("Cleaned up {} old snapshots for session {}", count, session_id);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("Failed to cleanup old snapshots: {}", e);
-        }
-    }
-
     tracing::info!(
         "Created file backup snapshot: {} ({} files, storage: {})",
         snapshot.id,
         backup_files.len(),
-        if disk_dir.is_some() { "disk" } else { "inline" }
+        if snapshots_dir.is_some() { "objects" } else { "inline" }
     );
 
     Ok(snapshot)
@@ -448,11 +508,10 @@ fn create_file_backup_snapshot(
 /// Create a git ghost commit snapshot
 fn create_git_snapshot(db: &Database, session_id: &str, project_path: &Path) -> Result<Snapshot> {
     // Stash any uncommitted changes
-    let stash_output = Command::new("git")
-        .args(["stash", "push", "-u", "-m", "codex-desktop-snapshot"])
-        .current_dir(project_path)
-        .output()
-        .map_err(|e| Error::Git(format!("Failed to run git stash: {e}")))?;
+    let stash_output = git::output(
+        project_path,
+        &["stash", "push", "-u", "-m", "codex-desktop-snapshot"],
+    )?;
 
     let stash_created = String::from_utf8_lossy(&stash_output.stdout)
         .contains("Saved working directory");
@@ -460,13 +519,8 @@ fn create_git_snapshot(db: &Database, session_id: &str, project_path: &Path) ->
     // Get the current HEAD or stash ref
     let ref_name = if stash_created {
         // Get the stash reference
-        let stash_list = Command::new("git")
-            .args(["stash", "list", "-1"])
-            .current_dir(project_path)
-            .output()
-            .map_err(|e| Error::Git(format!("Failed to get stash list: {e}")))?;
-
-        let stash_info = String::from_utf8_lossy(&stash_list.stdout);
+        let stash_info = git::output(project_path, &["stash", "list", "-1"])?;
+        let stash_info = String::from_utf8_lossy(&stash_info.stdout);
         if stash_info.contains("codex-desktop-snapshot") {
             "stash@{0}".to_string()
         } else {
@@ -479,52 +533,168 @@ fn create_git_snapshot(db: &Database, session_id: &str, project_path: &Path) ->
     let snapshot = Snapshot::new_git_ghost(session_id, &ref_name);
     db.insert_snapshot(&snapshot)?;
 
-    // Cleanup: Keep only 10 most recent snapshots per session
-    match db.cleanup_old_snapshots(session_id, 10) {
-        Ok(count) => {
-            if count > 0 {
-                tracing::info!("Cleaned up {} old snapshots for session {}", count, session_id);
-            }
-        }
-        Err(e) => {
-            tracing::warn!("Failed to cleanup old snapshots: {}", e);
-        }
-    }
-
     tracing::info!("Created git snapshot: {} -> {}", snapshot.id, ref_name);
 
     Ok(snapshot)
 }
 
-/// Get the current HEAD commit SHA
-fn get_current_head(project_path: &Path) -> Result<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "HEAD"])
-        .current_dir(project_path)
-        .output()
-        .map_err(|e| Error::Git(format!("Failed to get HEAD: {e}")))?;
+/// Commit the whole working tree through a temporary index, leaving the
+/// real index, HEAD and the working tree untouched
+fn commit_working_tree(
+    project_path: &Path,
+    head: Option<&str>,
+    index_file: &Path,
+    message: &str,
+) -> Result<String> {
+    if let Some(head) = head {
+        git::run_with_index(project_path, index_file, &["read-tree", head])?;
+    }
+    git::run_with_index(project_path, index_file, &["add", "-A"])?;
+    let tree = git::run_with_index(project_path, index_file, &["write-tree"])?;
+
+    // The commit is never pushed; a fixed identity avoids failing on
+    // machines without `user.name` configured
+    let mut args = vec![
+        "-c",
+        "user.name=Codex Desktop",
+        "-c",
+        "user.email=codex-desktop@localhost",
+        "commit-tree",
+        tree.trim(),
+        "-m",
+        message,
+    ];
+    if let Some(head) = head {
+        args.extend(["-p", head]);
+    }
+    git::value(project_path, &args)
+}
 
-    if !output.status.success() {
-        return Err(Error::Git("Failed to get HEAD commit".to_string()));
+/// Create a git working-tree snapshot, marked as taken before reverting to
+/// `pre_revert_of` if given
+///
+/// The commit is not referenced by any branch; git keeps such objects for
+/// at least its prune window (two weeks by default).
+fn create_git_worktree_snapshot(
+    db: &Database,
+    session_id: &str,
+    project_path: &Path,
+    pre_revert_of: Option<&str>,
+) -> Result<Snapshot> {
+    let head = git::value(project_path, &["rev-parse", "--verify", "-q", "HEAD"]).ok();
+    let index_file =
+        std::env::temp_dir().join(format!("codex-desktop-index-{}", uuid::Uuid::new_v4()));
+    let message = if pre_revert_of.is_some() {
+        "codex-desktop pre-revert snapshot"
+    } else {
+        "codex-desktop snapshot"
+    };
+    let commit = commit_working_tree(project_path, head.as_deref(), &index_file, message);
+    let _ = fs::remove_file(&index_file);
+    let commit = commit?;
+
+    let mut metadata = serde_json::json!({
+        "commit_sha": commit,
+        "head": head,
+    });
+    if let Some(target) = pre_revert_of {
+        metadata[PRE_REVERT_KEY] = target.into();
     }
+    let snapshot = Snapshot::new_git_worktree(session_id, &metadata.to_string());
+    db.insert_snapshot(&snapshot)?;
+
+    tracing::info!("Created git working-tree snapshot: {} -> {}", snapshot.id, commit);
 
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    Ok(snapshot)
+}
+
+/// Get the current HEAD commit SHA
+fn get_current_head(project_path: &Path) -> Result<String> {
+    git::value(project_path, &["rev-parse", "HEAD"])
 }
 
 /// Revert to a snapshot
-pub fn revert_to_snapshot(db: &Database, snapshot_id: &str, project_path: &Path) -> Result<()> {
+///
+/// With `backup`, the current state is captured first as a pre-revert
+/// snapshot (returned) so `undo_last_revert` can bring it back; the revert
+/// is aborted if that backup fails.
+pub fn revert_to_snapshot(
+    db: &Database,
+    snapshot_id: &str,
+    project_path: &Path,
+    backup: bool,
+    snapshots_dir: Option<&Path>,
+) -> Result<Option<Snapshot>> {
     // Security: Canonicalize path to prevent symlink attacks and traversal
     let canonical_path = project_path
         .canonicalize()
         .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
 
+    // Loaded before the backup, whose cleanup may prune the oldest snapshots
     let snapshot = db
         .get_snapshot(snapshot_id)?
         .ok_or_else(|| Error::SnapshotNotFound(snapshot_id.to_string()))?;
 
+    let pre_revert = if backup {
+        Some(create_pre_revert_snapshot(db, &snapshot, &canonical_path, snapshots_dir)?)
+    } else {
+        None
+    };
+
+    apply_snapshot(&snapshot, &canonical_path)?;
+    Ok(pre_revert)
+}
+
+/// Undo a session's most recent revert by restoring its pre-revert snapshot
+///
+/// Refused when newer snapshots exist, since restoring would discard the
+/// changes made after the revert. The pre-revert snapshot is consumed, so
+/// calling this again undoes the revert before it.
+pub fn undo_last_revert(
+    db: &Database,
+    session_id: &str,
+    project_path: &Path,
+    snapshots_dir: Option<&Path>,
+) -> Result<Snapshot> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+
+    let snapshots = db.get_snapshots_for_session(session_id)?;
+    let position = snapshots
+        .iter()
+        .position(|snapshot| pre_revert_target(snapshot).is_some())
+        .ok_or_else(|| Error::Other("No revert to undo for this session".to_string()))?;
+    // Snapshots are listed newest first
+    if position > 0 {
+        return Err(Error::Other(
+            "Changes were made after the last revert; revert to a snapshot instead".to_string(),
+        ));
+    }
+
+    let pre_revert = &snapshots[position];
+    apply_snapshot(pre_revert, &canonical_path)?;
+
+    db.delete_snapshot(&pre_revert.id)?;
+    if let Some(dir) = snapshots_dir {
+        let disk_dir = dir.join(&pre_revert.id);
+        if disk_dir.is_dir() {
+            if let Err(e) = fs::remove_dir_all(&disk_dir) {
+                tracing::warn!("Failed to remove snapshot files {:?}: {}", disk_dir, e);
+            }
+        }
+    }
+
+    tracing::info!("Undid revert using snapshot {}", pre_revert.id);
+    Ok(pre_revert.clone())
+}
+
+/// Restore the project to a snapshot's state
+fn apply_snapshot(snapshot: &Snapshot, canonical_path: &Path) -> Result<()> {
     match snapshot.snapshot_type.as_str() {
-        "git_ghost" => revert_git_snapshot(&snapshot, &canonical_path),
-        "file_backup" => revert_file_backup_snapshot(&snapshot, &canonical_path),
+        "git_ghost" => revert_git_snapshot(snapshot, canonical_path),
+        "git_worktree" => revert_git_worktree_snapshot(snapshot, canonical_path),
+        "file_backup" => revert_file_backup_snapshot(snapshot, canonical_path),
         _ => Err(Error::Other(format!(
             "Unknown snapshot type: {}",
             snapshot.snapshot_type
@@ -532,6 +702,39 @@ pub fn revert_to_snapshot(db: &Database, snapshot_id: &str, project_path: &Path)
     }
 }
 
+/// Revert to a git working-tree snapshot
+///
+/// HEAD is moved back to where it was, then the working tree is set to the
+/// captured commit and the index reset to HEAD, so changes that were
+/// uncommitted come back as uncommitted (unstaged) changes.
+fn revert_git_worktree_snapshot(snapshot: &Snapshot, project_path: &Path) -> Result<()> {
+    let metadata: serde_json::Value = snapshot
+        .metadata_json
+        .as_ref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .ok_or_else(|| Error::Other("Invalid snapshot metadata".to_string()))?;
+
+    let commit_sha = metadata["commit_sha"]
+        .as_str()
+        .ok_or_else(|| Error::Other("Missing commit_sha in snapshot".to_string()))?;
+    validate_commit_sha(commit_sha)?;
+    let head = metadata["head"].as_str();
+    if let Some(head) = head {
+        validate_commit_sha(head)?;
+        git::run(project_path, &["reset", "-q", "--hard", head])?;
+    }
+
+    git::run(project_path, &["read-tree", "-u", "--reset", commit_sha])?;
+    match head {
+        Some(_) => git::run(project_path, &["reset", "-q"])?,
+        // No commits yet: everything goes back to untracked
+        None => git::run(project_path, &["read-tree", "--empty"])?,
+    };
+
+    tracing::info!("Reverted to git working-tree snapshot: {}", snapshot.id);
+    Ok(())
+}
+
 /// Revert to a file backup snapshot
 ///
 /// This function restores files from a backup snapshot with comprehensive security checks:
@@ -573,8 +776,13 @@ fn revert_file_backup_snapshot(snapshot: &Snapshot, project_path: &Path) -> Resu
             }
         };
 
-        // Resolve file contents: either read from disk (file:// ref) or decode inline base64
-        let contents = if let Some(file_path) = content_or_ref.strip_prefix(FILE_REF_PREFIX) {
+        // Resolve file contents: from the object store (object:// ref), from
+        // disk (file:// ref) or decoded from inline base64
+        let contents = if let Some(object) = store::object_path(content_or_ref) {
+            store::read_object(&object).map_err(|e| {
+                Error::Other(format!("Failed to read snapshot object for '{relative_path}': {e}"))
+            })?
+        } else if let Some(file_path) = content_or_ref.strip_prefix(FILE_REF_PREFIX) {
             // Disk-based storage: read raw bytes from the referenced file
             fs::read(file_path)
                 .map_err(|e| Error::Other(format!("Failed to read snapshot file for '{relative_path}': {e}")))?
@@ -648,31 +856,13 @@ fn revert_git_snapshot(snapshot: &Snapshot, project_path: &Path) -> Result<()> {
             return Err(Error::Other("Invalid stash reference format".to_string()));
         }
 
-        let output = Command::new("git")
-            .args(["stash", "pop"])
-            .current_dir(&canonical_path)
-            .output()
-            .map_err(|e| Error::Git(format!("Failed to pop stash: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(format!("Failed to pop stash: {stderr}")));
-        }
+        git::run(&canonical_path, &["stash", "pop"])?;
     } else {
         // Security: Validate commit SHA to prevent command injection
         validate_commit_sha(commit_sha)?;
 
         // Reset to the commit
-        let output = Command::new("git")
-            .args(["reset", "--hard", commit_sha])
-            .current_dir(&canonical_path)
-            .output()
-            .map_err(|e| Error::Git(format!("Failed to reset: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(Error::Git(format!("Failed to reset to {commit_sha}: {stderr}")));
-        }
+        git::run(&canonical_path, &["reset", "--hard", commit_sha])?;
     }
 
     tracing::info!("Reverted to snapshot: {}", snapshot.id);
@@ -1006,4 +1196,68 @@ mod tests {
             );
         }
     }
+
+    // ==================== pre-revert snapshot tests ====================
+
+    fn git(dir: &Path, args: &[&str]) -> bool {
+        let identity = ["-c", "user.name=test", "-c", "user.email=test@example.com"];
+        crate::git::run(dir, &[&identity[..], args].concat()).is_ok()
+    }
+
+    #[test]
+    fn test_revert_then_undo_restores_working_tree() {
+        let temp_dir = create_test_dir();
+        let project = temp_dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("a.txt"), "v1").unwrap();
+        if !git(&project, &["init", "-q"]) || !git(&project, &["add", "-A"]) {
+            return; // git unavailable
+        }
+        assert!(git(&project, &["commit", "-q", "-m", "init"]));
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let target = create_snapshot(&db, "s1", &project, None).unwrap();
+
+        fs::write(project.join("a.txt"), "v2").unwrap();
+        fs::write(project.join("new.txt"), "untracked").unwrap();
+
+        let pre_revert = revert_to_snapshot(&db, &target.id, &project, true, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pre_revert.snapshot_type, "git_worktree");
+        assert_eq!(pre_revert_target(&pre_revert).as_deref(), Some(target.id.as_str()));
+        assert_eq!(fs::read_to_string(project.join("a.txt")).unwrap(), "v1");
+
+        let undone = undo_last_revert(&db, "s1", &project, None).unwrap();
+        assert_eq!(undone.id, pre_revert.id);
+        assert_eq!(fs::read_to_string(project.join("a.txt")).unwrap(), "v2");
+        assert_eq!(fs::read_to_string(project.join("new.txt")).unwrap(), "untracked");
+        assert!(db.get_snapshot(&pre_revert.id).unwrap().is_none());
+
+        // The pre-revert snapshot was consumed
+        assert!(undo_last_revert(&db, "s1", &project, None).is_err());
+    }
+
+    #[test]
+    fn test_undo_refused_after_newer_snapshot() {
+        let temp_dir = create_test_dir();
+        let project = temp_dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("a.txt"), "v1").unwrap();
+
+        let db = Database::new(&temp_dir.path().join("test.db")).unwrap();
+        let snapshots_dir = temp_dir.path().join("snapshots");
+        let target = create_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        fs::write(project.join("a.txt"), "v2").unwrap();
+
+        let pre_revert =
+            revert_to_snapshot(&db, &target.id, &project, true, Some(&snapshots_dir))
+                .unwrap()
+                .unwrap();
+        assert_eq!(pre_revert.snapshot_type, "file_backup");
+        assert_eq!(fs::read_to_string(project.join("a.txt")).unwrap(), "v1");
+
+        create_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        assert!(undo_last_revert(&db, "s1", &project, Some(&snapshots_dir)).is_err());
+    }
 }
@@ -0,0 +1,490 @@
+//! Snapshots as patches, to carry their changes to another worktree
+//!
+//! A snapshot's patch is the diff from its base to the snapshot: the commit
+//! a git snapshot was taken on, or another snapshot given as the base (file
+//! backups have no commit, so they need one). Both sides of the changed
+//! files are written to a scratch repository and diffed there with `git
+//! diff --binary`, so every snapshot type yields a patch with git headers
+//! that carries binary files and applies with `git apply`. Applying checks
+//! the whole patch first and changes nothing unless every hunk applies.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::diff::{list_git_tree, list_snapshot, load_snapshot, read_source, Listing};
+use super::{prepare_restore_path, validate_commit_sha, validate_restore_path};
+use crate::database::{Database, Snapshot};
+use crate::git;
+use crate::{Error, Result};
+
+/// A file the patch changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchFile {
+    /// Relative to the project root
+    pub path: String,
+    pub insertions: u32,
+    pub deletions: u32,
+    pub binary: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotPatch {
+    pub snapshot_id: String,
+    /// Commit or snapshot ID the patch applies on top of; `None` for the
+    /// empty tree
+    pub base: Option<String>,
+    /// Unified diff with git headers; empty when nothing changed
+    pub patch: String,
+    /// Sorted by path
+    pub files: Vec<PatchFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchApplyReport {
+    pub snapshot_id: String,
+    pub base: Option<String>,
+    pub target_path: String,
+    pub dry_run: bool,
+    /// Every hunk applies; unless `dry_run`, the patch was applied
+    pub applied: bool,
+    /// Why the patch does not apply, as git reports it
+    pub error: Option<String>,
+    pub files: Vec<PatchFile>,
+}
+
+/// The changes a snapshot holds on top of its base, as a patch
+///
+/// `base_snapshot_id` overrides the base; it is required for file backups.
+/// Blocking.
+pub fn export_snapshot_patch(
+    db: &Database,
+    snapshot_id: &str,
+    project_path: &Path,
+    base_snapshot_id: Option<&str>,
+) -> Result<SnapshotPatch> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+    let snapshot = load_snapshot(db, snapshot_id)?;
+    let listing = list_snapshot(&snapshot, &canonical_path)?;
+    let (base, base_listing) = match base_snapshot_id {
+        Some(base_id) => {
+            let base = load_snapshot(db, base_id)?;
+            let base_listing = list_snapshot(&base, &canonical_path)?;
+            (Some(base.id), base_listing)
+        }
+        None => default_base(&snapshot, &canonical_path)?,
+    };
+
+    let (patch, files) = build_patch(&canonical_path, &base_listing, &listing)?;
+    Ok(SnapshotPatch {
+        snapshot_id: snapshot.id,
+        base,
+        patch,
+        files,
+    })
+}
+
+/// Apply a snapshot's patch to another directory, typically a worktree of
+/// the same repository on another branch
+///
+/// A patch that does not apply is reported, not an error, and leaves the
+/// target untouched; with `dry_run` the patch is only checked. Blocking.
+pub fn apply_snapshot_to_path(
+    db: &Database,
+    snapshot_id: &str,
+    project_path: &Path,
+    base_snapshot_id: Option<&str>,
+    target_path: &Path,
+    dry_run: bool,
+) -> Result<PatchApplyReport> {
+    let target = target_path
+        .canonicalize()
+        .ok()
+        .filter(|path| path.is_dir())
+        .ok_or_else(|| {
+            Error::InvalidPath(format!(
+                "Target must be an existing directory: {}",
+                target_path.display()
+            ))
+        })?;
+    let export = export_snapshot_patch(db, snapshot_id, project_path, base_snapshot_id)?;
+
+    let error = if export.patch.is_empty() {
+        None
+    } else {
+        let patch_file = std::env::temp_dir().join(format!(
+            "codex-desktop-snapshot-{}.patch",
+            uuid::Uuid::new_v4()
+        ));
+        fs::write(&patch_file, &export.patch)
+            .map_err(|e| Error::Other(format!("Failed to write patch: {e}")))?;
+        let result = apply_patch(&target, &patch_file, dry_run);
+        let _ = fs::remove_file(&patch_file);
+        result?
+    };
+
+    if !dry_run && error.is_none() && !export.files.is_empty() {
+        tracing::info!(
+            "Applied snapshot {} to {} ({} files)",
+            export.snapshot_id,
+            target.display(),
+            export.files.len()
+        );
+    }
+    Ok(PatchApplyReport {
+        snapshot_id: export.snapshot_id,
+        base: export.base,
+        target_path: target.to_string_lossy().to_string(),
+        dry_run,
+        applied: error.is_none(),
+        error,
+        files: export.files,
+    })
+}
+
+/// The tree a git snapshot was taken on
+fn default_base(snapshot: &Snapshot, project_path: &Path) -> Result<(Option<String>, Listing)> {
+    let metadata: serde_json::Value = snapshot
+        .metadata_json
+        .as_ref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or_default();
+    let base = match snapshot.snapshot_type.as_str() {
+        "git_worktree" => match metadata["head"].as_str() {
+            Some(head) => {
+                validate_commit_sha(head)?;
+                head.to_string()
+            }
+            // Taken before the first commit
+            None => return Ok((None, Listing::new())),
+        },
+        "git_ghost" => {
+            // `list_snapshot` has validated it. A stash's first parent is
+            // the commit it was made on; a plain commit means the working
+            // tree was clean.
+            let commit_sha = metadata["commit_sha"].as_str().unwrap_or_default();
+            let rev = if commit_sha.starts_with("stash@") {
+                format!("{commit_sha}^1")
+            } else {
+                commit_sha.to_string()
+            };
+            git::value(project_path, &["rev-parse", "--verify", "-q", &rev])?
+        }
+        _ => {
+            return Err(Error::Other(
+                "File backup snapshots have no base commit; choose a base snapshot".to_string(),
+            ))
+        }
+    };
+    let listing = list_git_tree(project_path, &base)?;
+    Ok((Some(base), listing))
+}
+
+fn build_patch(
+    project_path: &Path,
+    from: &Listing,
+    to: &Listing,
+) -> Result<(String, Vec<PatchFile>)> {
+    // (path, old contents, new contents) of the files that differ
+    let mut changes = Vec::new();
+    let paths: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    for path in paths {
+        let (old, new) = (from.get(path), to.get(path));
+        if let (Some(old), Some(new)) = (old, new) {
+            if old.blob.is_some() && old.blob == new.blob {
+                continue;
+            }
+        }
+        let old = old
+            .map(|entry| read_source(entry, project_path).map(|c| c.into_owned()))
+            .transpose()?;
+        let new = new
+            .map(|entry| read_source(entry, project_path).map(|c| c.into_owned()))
+            .transpose()?;
+        if old != new {
+            changes.push((path.as_str(), old, new));
+        }
+    }
+    if changes.is_empty() {
+        return Ok((String::new(), Vec::new()));
+    }
+
+    let scratch = std::env::temp_dir().join(format!(
+        "codex-desktop-snapshot-patch-{}",
+        uuid::Uuid::new_v4()
+    ));
+    fs::create_dir_all(&scratch)
+        .map_err(|e| Error::Other(format!("Failed to create patch directory: {e}")))?;
+    let result = scratch
+        .canonicalize()
+        .map_err(|e| Error::Other(format!("Failed to create patch directory: {e}")))
+        .and_then(|scratch| diff_in_scratch_repo(&scratch, &changes));
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+type Change<'a> = (&'a str, Option<Vec<u8>>, Option<Vec<u8>>);
+
+/// Commit the old side of `changes` to a scratch repository's index, then
+/// diff the new side against it
+fn diff_in_scratch_repo(scratch: &Path, changes: &[Change]) -> Result<(String, Vec<PatchFile>)> {
+    scratch_git(scratch, &["init", "-q"])?;
+
+    // Text that is not UTF-8 goes into the patch as binary, so the patch
+    // survives being passed around as a string
+    let mut attributes = String::new();
+    for (path, old, new) in changes {
+        let not_utf8 = [old, new]
+            .into_iter()
+            .flatten()
+            .any(|contents| std::str::from_utf8(contents).is_err());
+        if not_utf8 {
+            attributes.push_str(&format!("/{} binary\n", attribute_pattern(path)));
+        }
+        if let Some(old) = old {
+            write_file(scratch, path, old)?;
+        }
+    }
+    let info = scratch.join(".git").join("info");
+    fs::create_dir_all(&info)
+        .and_then(|_| fs::write(info.join("attributes"), attributes))
+        .map_err(|e| Error::Other(format!("Failed to write patch attributes: {e}")))?;
+
+    scratch_git(scratch, &["add", "-A", "-f"])?;
+    let base_tree = String::from_utf8_lossy(&scratch_git(scratch, &["write-tree"])?)
+        .trim()
+        .to_string();
+
+    for (path, _, new) in changes {
+        match new {
+            Some(new) => write_file(scratch, path, new)?,
+            None => {
+                let target = validate_restore_path(path, scratch).map_err(|e| {
+                    Error::Other(format!("Path validation failed for '{path}': {e}"))
+                })?;
+                fs::remove_file(target.as_path())
+                    .map_err(|e| Error::Other(format!("Failed to stage '{path}': {e}")))?;
+            }
+        }
+    }
+    scratch_git(scratch, &["add", "-A", "-f"])?;
+
+    let diff = [
+        "diff",
+        "--cached",
+        "--no-renames",
+        "--no-color",
+        "--no-ext-diff",
+    ];
+    let patch = scratch_git(
+        scratch,
+        &[
+            &diff[..],
+            &[
+                "--binary",
+                "--full-index",
+                "--src-prefix=a/",
+                "--dst-prefix=b/",
+            ],
+            &[base_tree.as_str()],
+        ]
+        .concat(),
+    )?;
+    let numstat = scratch_git(
+        scratch,
+        &[&diff[..], &["--numstat", "-z", base_tree.as_str()]].concat(),
+    )?;
+
+    let patch = String::from_utf8(patch)
+        .map_err(|_| Error::Other("Patch is not valid UTF-8".to_string()))?;
+    Ok((patch, parse_numstat(&numstat)))
+}
+
+fn write_file(scratch: &Path, path: &str, contents: &[u8]) -> Result<()> {
+    let target = prepare_restore_path(path, scratch)?;
+    fs::write(target.as_path(), contents)
+        .map_err(|e| Error::Other(format!("Failed to stage '{path}': {e}")))
+}
+
+/// A gitattributes pattern matching `path`; characters patterns cannot
+/// hold become `?`, which may match a few more files, all harmlessly
+fn attribute_pattern(path: &str) -> String {
+    path.chars()
+        .map(|c| {
+            if c.is_whitespace() || matches!(c, '*' | '?' | '[' | '\\' | '"') {
+                '?'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Parse `--numstat -z` output: "<insertions>\t<deletions>\t<path>\0",
+/// with "-" counts for binary files
+fn parse_numstat(output: &[u8]) -> Vec<PatchFile> {
+    output
+        .split(|byte| *byte == 0)
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            let record = String::from_utf8_lossy(record);
+            let mut fields = record.splitn(3, '\t');
+            let (insertions, deletions, path) = (fields.next()?, fields.next()?, fields.next()?);
+            Some(PatchFile {
+                path: path.to_string(),
+                insertions: insertions.parse().unwrap_or(0),
+                deletions: deletions.parse().unwrap_or(0),
+                binary: insertions == "-",
+            })
+        })
+        .collect()
+}
+
+/// Run git in the scratch repository, overriding user settings that would
+/// change the patch
+fn scratch_git(scratch: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let overrides = ["-c", "core.autocrlf=false", "-c", "core.safecrlf=false"];
+    git::run_bytes(scratch, &[&overrides[..], args].concat())
+}
+
+/// Check the patch against `target`, then apply it unless `dry_run`;
+/// returns git's complaint when it does not apply
+fn apply_patch(target: &Path, patch_file: &Path, dry_run: bool) -> Result<Option<String>> {
+    // Inside a repository git takes patch paths from its top level
+    let prefix = git::value(target, &["rev-parse", "--show-prefix"]).unwrap_or_default();
+    let directory = (!prefix.is_empty()).then(|| format!("--directory={prefix}"));
+
+    let patch_file = patch_file.to_string_lossy();
+    let run = |check: bool| -> Result<Option<String>> {
+        let mut args = vec!["apply", "--whitespace=nowarn"];
+        args.extend(check.then_some("--check"));
+        args.extend(directory.as_deref());
+        args.push(&patch_file);
+        let output = git::output(target, &args)?;
+        Ok((!output.status.success())
+            .then(|| String::from_utf8_lossy(&output.stderr).trim().to_string()))
+    };
+
+    if let Some(error) = run(true)? {
+        return Ok(Some(error));
+    }
+    if dry_run {
+        return Ok(None);
+    }
+    run(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        if let Err(err) = crate::git::run(dir, args) {
+            panic!("git {args:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_apply_snapshot_to_other_worktree() {
+        let (temp_dir, db) = crate::database::test_db();
+        let repo = temp_dir.path().join("repo");
+        let other = temp_dir.path().join("other");
+        fs::create_dir_all(&repo).unwrap();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "test@example.com"]);
+        git(&repo, &["config", "user.name", "Test"]);
+        fs::write(repo.join("edit.txt"), "one\ntwo\n").unwrap();
+        fs::write(repo.join("gone.txt"), "bye\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        git(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "other",
+                other.to_str().unwrap(),
+            ],
+        );
+
+        fs::write(repo.join("edit.txt"), "one\nTWO\n").unwrap();
+        fs::remove_file(repo.join("gone.txt")).unwrap();
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("src/new.txt"), "new\n").unwrap();
+        fs::write(repo.join("data.bin"), b"\x00\xff\x01").unwrap();
+        let snapshot = super::super::create_turn_snapshot(&db, "s1", &repo, None).unwrap();
+
+        let export = export_snapshot_patch(&db, &snapshot.id, &repo, None).unwrap();
+        assert!(export.patch.starts_with("diff --git a/data.bin b/data.bin"));
+        assert!(export.patch.contains("GIT binary patch"));
+        let summary: Vec<(&str, u32, u32, bool)> = export
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.path.as_str(),
+                    file.insertions,
+                    file.deletions,
+                    file.binary,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("data.bin", 0, 0, true),
+                ("edit.txt", 1, 1, false),
+                ("gone.txt", 0, 1, false),
+                ("src/new.txt", 1, 0, false),
+            ]
+        );
+
+        let check = apply_snapshot_to_path(&db, &snapshot.id, &repo, None, &other, true).unwrap();
+        assert!(check.applied && check.error.is_none());
+        assert!(other.join("gone.txt").exists());
+
+        let applied =
+            apply_snapshot_to_path(&db, &snapshot.id, &repo, None, &other, false).unwrap();
+        assert!(applied.applied);
+        assert_eq!(
+            fs::read_to_string(other.join("edit.txt")).unwrap(),
+            "one\nTWO\n"
+        );
+        assert_eq!(fs::read(other.join("data.bin")).unwrap(), b"\x00\xff\x01");
+        assert!(other.join("src/new.txt").exists());
+        assert!(!other.join("gone.txt").exists());
+
+        // Already applied: reported, nothing changes
+        let again = apply_snapshot_to_path(&db, &snapshot.id, &repo, None, &other, false).unwrap();
+        assert!(!again.applied && again.error.is_some());
+        assert_eq!(
+            fs::read_to_string(other.join("edit.txt")).unwrap(),
+            "one\nTWO\n"
+        );
+    }
+
+    #[test]
+    fn test_file_backup_patch_needs_base() {
+        let (temp_dir, db) = crate::database::test_db();
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("a.txt"), "alpha\n").unwrap();
+        let first = super::super::create_snapshot(&db, "s1", &project, None).unwrap();
+        fs::write(project.join("a.txt"), "alpha\nbeta\n").unwrap();
+        let second = super::super::create_snapshot(&db, "s1", &project, None).unwrap();
+
+        assert!(export_snapshot_patch(&db, &second.id, &project, None).is_err());
+        let export = export_snapshot_patch(&db, &second.id, &project, Some(&first.id)).unwrap();
+        assert_eq!(export.base.as_deref(), Some(first.id.as_str()));
+        assert!(export.patch.contains("+beta\n"));
+    }
+}
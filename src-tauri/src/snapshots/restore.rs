@@ -0,0 +1,283 @@
+//! Restoring chosen files from a snapshot, leaving the rest of the tree as is
+//!
+//! Files the snapshot has are written back; files it lacks are deleted,
+//! unless snapshots would never have captured them (ignored by git, or left
+//! out of file backups). A dry run reports the same plan without touching
+//! anything. Like a full revert, a real restore first takes a pre-revert
+//! snapshot when `backup` is set, so `undo_last_revert` can bring the files
+//! back.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::diff::{list_snapshot, read_source};
+use super::{
+    create_pre_revert_snapshot, is_git_repo, prepare_restore_path, skipped_in_backups,
+    validate_restore_path, MAX_BACKUP_FILE_BYTES,
+};
+use crate::database::{Database, Snapshot};
+use crate::git;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreAction {
+    /// Overwritten with the snapshot's contents
+    Restore,
+    /// Deleted since the snapshot; written back
+    Recreate,
+    /// Added since the snapshot; removed
+    Delete,
+    /// Already matches the snapshot, or is never captured by snapshots
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRestore {
+    pub path: String,
+    pub action: RestoreAction,
+    /// Why the file was left alone or could not be restored; other files
+    /// are still restored
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRestoreReport {
+    pub snapshot_id: String,
+    pub dry_run: bool,
+    /// In the order requested
+    pub files: Vec<FileRestore>,
+    /// Snapshot of the state before the restore, if one was taken
+    pub backup: Option<Snapshot>,
+}
+
+/// Restore `paths` (relative to the project root) from a snapshot
+///
+/// Every path is validated before anything changes; a path neither in the
+/// snapshot nor in the working tree is an error. Blocking.
+pub fn restore_files_from_snapshot(
+    db: &Database,
+    snapshot_id: &str,
+    project_path: &Path,
+    paths: &[String],
+    dry_run: bool,
+    backup: bool,
+    snapshots_dir: Option<&Path>,
+) -> Result<FileRestoreReport> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| Error::Other("Invalid or non-existent path".to_string()))?;
+    let snapshot = db
+        .get_snapshot(snapshot_id)?
+        .ok_or_else(|| Error::SnapshotNotFound(snapshot_id.to_string()))?;
+    let listing = list_snapshot(&snapshot, &canonical_path)?;
+
+    // (plan, contents to write)
+    let mut plan: Vec<(FileRestore, Option<Vec<u8>>)> = Vec::new();
+    for path in paths {
+        if plan.iter().any(|(file, _)| &file.path == path) {
+            continue;
+        }
+        let target = validate_restore_path(path, &canonical_path)
+            .map_err(|e| Error::Other(format!("Path validation failed for '{path}': {e}")))?;
+        let current = if target.as_path().is_file() {
+            Some(
+                fs::read(target.as_path())
+                    .map_err(|e| Error::Other(format!("Failed to read '{path}': {e}")))?,
+            )
+        } else {
+            None
+        };
+
+        let mut file = FileRestore {
+            path: path.clone(),
+            action: RestoreAction::Unchanged,
+            error: None,
+        };
+        let contents = match (listing.get(path), current) {
+            (Some(entry), current) => {
+                let contents = read_source(entry, &canonical_path)?.into_owned();
+                file.action = match current {
+                    Some(current) if current == contents => RestoreAction::Unchanged,
+                    Some(_) => RestoreAction::Restore,
+                    None => RestoreAction::Recreate,
+                };
+                Some(contents)
+            }
+            (None, Some(_)) => {
+                if captured_by_snapshots(&canonical_path, path, target.as_path()) {
+                    file.action = RestoreAction::Delete;
+                } else {
+                    file.error = Some("Not captured by snapshots; left in place".to_string());
+                }
+                None
+            }
+            (None, None) => {
+                return Err(Error::Other(format!(
+                    "'{path}' is neither in the snapshot nor in the project"
+                )));
+            }
+        };
+        plan.push((file, contents));
+    }
+
+    let changes = plan
+        .iter()
+        .any(|(file, _)| file.action != RestoreAction::Unchanged);
+    let backup = if !dry_run && backup && changes {
+        Some(create_pre_revert_snapshot(
+            db,
+            &snapshot,
+            &canonical_path,
+            snapshots_dir,
+        )?)
+    } else {
+        None
+    };
+
+    let mut files = Vec::with_capacity(plan.len());
+    for (mut file, contents) in plan {
+        if !dry_run {
+            if let Err(err) = apply(&canonical_path, &file, contents.as_deref()) {
+                tracing::warn!("Failed to restore {} from snapshot: {}", file.path, err);
+                file.error = Some(err.to_string());
+            }
+        }
+        files.push(file);
+    }
+
+    if !dry_run {
+        tracing::info!(
+            "Restored {} files from snapshot {}",
+            files
+                .iter()
+                .filter(|file| file.action != RestoreAction::Unchanged && file.error.is_none())
+                .count(),
+            snapshot.id
+        );
+    }
+    Ok(FileRestoreReport {
+        snapshot_id: snapshot.id,
+        dry_run,
+        files,
+        backup,
+    })
+}
+
+fn apply(project_path: &Path, file: &FileRestore, contents: Option<&[u8]>) -> Result<()> {
+    match (file.action, contents) {
+        (RestoreAction::Restore | RestoreAction::Recreate, Some(contents)) => {
+            let target = prepare_restore_path(&file.path, project_path)?;
+            // Final symlink check right before writing (TOCTOU mitigation)
+            if fs::symlink_metadata(target.as_path()).is_ok_and(|m| m.file_type().is_symlink()) {
+                return Err(Error::Other("Symlink detected at write time".to_string()));
+            }
+            fs::write(target.as_path(), contents)
+                .map_err(|e| Error::Other(format!("Failed to write file '{}': {e}", file.path)))
+        }
+        (RestoreAction::Delete, _) => {
+            let target = validate_restore_path(&file.path, project_path)
+                .map_err(|e| Error::Other(format!("Path security check failed: {e}")))?;
+            fs::remove_file(target.as_path())
+                .map_err(|e| Error::Other(format!("Failed to delete file '{}': {e}", file.path)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether a snapshot taken now would include the file, so its absence
+/// from the snapshot means it was added later
+fn captured_by_snapshots(project_path: &Path, relative_path: &str, target: &Path) -> bool {
+    if is_git_repo(project_path) {
+        // check-ignore exits with 1 when the path is not ignored
+        return git::output(project_path, &["check-ignore", "-q", "--", relative_path])
+            .is_ok_and(|output| output.status.code() == Some(1));
+    }
+    let small = fs::metadata(target).is_ok_and(|m| m.len() <= MAX_BACKUP_FILE_BYTES);
+    small
+        && Path::new(relative_path)
+            .components()
+            .all(|component| !skipped_in_backups(&component.as_os_str().to_string_lossy()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_selected_files() {
+        let (temp_dir, db) = crate::database::test_db();
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("src/a.txt"), "a1\n").unwrap();
+        fs::write(project.join("b.txt"), "b1\n").unwrap();
+        fs::write(project.join("c.txt"), "c1\n").unwrap();
+        let snapshot = super::super::create_snapshot(&db, "s1", &project, None).unwrap();
+
+        fs::write(project.join("src/a.txt"), "a2\n").unwrap();
+        fs::write(project.join("b.txt"), "b2\n").unwrap();
+        fs::remove_file(project.join("c.txt")).unwrap();
+        fs::write(project.join("new.txt"), "new\n").unwrap();
+        fs::create_dir_all(project.join(".cache")).unwrap();
+        fs::write(project.join(".cache/blob"), "x").unwrap();
+
+        let paths: Vec<String> = ["src/a.txt", "c.txt", "new.txt", ".cache/blob"]
+            .iter()
+            .map(|path| path.to_string())
+            .collect();
+        let restore = |dry_run| {
+            restore_files_from_snapshot(&db, &snapshot.id, &project, &paths, dry_run, true, None)
+                .unwrap()
+        };
+
+        let preview = restore(true);
+        let actions: Vec<RestoreAction> = preview.files.iter().map(|file| file.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                RestoreAction::Restore,
+                RestoreAction::Recreate,
+                RestoreAction::Delete,
+                RestoreAction::Unchanged,
+            ]
+        );
+        assert!(preview.files[3].error.is_some());
+        assert!(preview.backup.is_none());
+        assert_eq!(
+            fs::read_to_string(project.join("src/a.txt")).unwrap(),
+            "a2\n"
+        );
+
+        let report = restore(false);
+        assert!(report.backup.is_some());
+        assert!(report
+            .files
+            .iter()
+            .all(|file| file.path == ".cache/blob" || file.error.is_none()));
+        assert_eq!(
+            fs::read_to_string(project.join("src/a.txt")).unwrap(),
+            "a1\n"
+        );
+        assert_eq!(fs::read_to_string(project.join("c.txt")).unwrap(), "c1\n");
+        assert!(!project.join("new.txt").exists());
+        assert!(project.join(".cache/blob").exists());
+        // Not selected
+        assert_eq!(fs::read_to_string(project.join("b.txt")).unwrap(), "b2\n");
+
+        let unknown = vec!["missing.txt".to_string()];
+        assert!(restore_files_from_snapshot(
+            &db,
+            &snapshot.id,
+            &project,
+            &unknown,
+            true,
+            true,
+            None
+        )
+        .is_err());
+    }
+}
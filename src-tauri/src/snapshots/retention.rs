@@ -0,0 +1,298 @@
+//! Pruning old snapshots by count, age and disk usage
+//!
+//! A snapshot's size is its physical size from the store, with objects
+//! shared between snapshots split among them; git-backed snapshots live in
+//! the repository's object store and count only their metadata. Pruning
+//! always removes the oldest snapshots first, and the disk limit never
+//! removes the newest snapshot. Objects left unreferenced are deleted by
+//! the next compaction.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::store::sizes;
+use crate::database::{Database, Snapshot};
+use crate::global_state::SnapshotSettings;
+use crate::Result;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Limits snapshots are pruned to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Most recent snapshots kept per session; 0 keeps all
+    pub max_per_session: usize,
+    pub max_age_secs: Option<i64>,
+    pub max_total_bytes: Option<u64>,
+}
+
+impl From<&SnapshotSettings> for RetentionPolicy {
+    fn from(settings: &SnapshotSettings) -> Self {
+        Self {
+            max_per_session: settings.max_per_session,
+            max_age_secs: settings
+                .max_age_days
+                .map(|days| i64::from(days) * SECS_PER_DAY),
+            max_total_bytes: settings.max_disk_bytes,
+        }
+    }
+}
+
+/// Disk used by snapshots, for `get_snapshot_storage_usage`
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotStorageUsage {
+    pub count: usize,
+    /// Metadata stored in the database, including inline file backups
+    pub database_bytes: u64,
+    /// Objects and older per-snapshot directories under the snapshots
+    /// directory
+    pub disk_bytes: u64,
+    pub total_bytes: u64,
+    /// Uncompressed size of everything snapshots hold, before deduplication
+    pub logical_bytes: u64,
+    /// Unix seconds the oldest snapshot was taken
+    pub oldest_created_at: Option<i64>,
+}
+
+/// A snapshot as far as retention is concerned
+#[derive(Debug, Clone)]
+struct Stored {
+    id: String,
+    session_id: String,
+    created_at: i64,
+    bytes: u64,
+}
+
+/// Delete the snapshots `policy` no longer allows, with their file backups;
+/// returns how many were deleted. Blocking.
+pub fn apply_retention(
+    db: &Database,
+    policy: &RetentionPolicy,
+    snapshots_dir: Option<&Path>,
+) -> Result<usize> {
+    if *policy == RetentionPolicy::default() {
+        return Ok(0);
+    }
+    let stored = measure(&db.get_all_snapshots()?);
+    let expired = select_expired(&stored, policy, chrono::Utc::now().timestamp());
+    if expired.is_empty() {
+        return Ok(0);
+    }
+
+    let deleted = db.delete_snapshots(&expired)?;
+    if let Some(dir) = snapshots_dir {
+        for id in &expired {
+            if let Some(backup) = backup_dir(dir, id).filter(|path| path.is_dir()) {
+                if let Err(e) = fs::remove_dir_all(&backup) {
+                    tracing::warn!(
+                        "Failed to delete snapshot files {}: {}",
+                        backup.display(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    tracing::info!("Pruned {} old snapshots", deleted);
+    Ok(deleted)
+}
+
+/// Report how much space snapshots take. Blocking.
+pub fn storage_usage(db: &Database, snapshots_dir: Option<&Path>) -> Result<SnapshotStorageUsage> {
+    let snapshots = db.get_all_snapshots()?;
+    let database_bytes = snapshots.iter().map(metadata_bytes).sum();
+    let disk_bytes = snapshots_dir.map_or(0, dir_bytes);
+    Ok(SnapshotStorageUsage {
+        count: snapshots.len(),
+        database_bytes,
+        disk_bytes,
+        total_bytes: database_bytes + disk_bytes,
+        logical_bytes: sizes(&snapshots)
+            .iter()
+            .map(|size| size.logical_bytes)
+            .sum(),
+        oldest_created_at: snapshots.iter().map(|snapshot| snapshot.created_at).min(),
+    })
+}
+
+fn measure(snapshots: &[Snapshot]) -> Vec<Stored> {
+    snapshots
+        .iter()
+        .zip(sizes(snapshots))
+        .map(|(snapshot, size)| Stored {
+            id: snapshot.id.clone(),
+            session_id: snapshot.session_id.clone(),
+            created_at: snapshot.created_at,
+            bytes: size.physical_bytes,
+        })
+        .collect()
+}
+
+/// IDs of the snapshots to delete; `stored` is oldest first
+fn select_expired(stored: &[Stored], policy: &RetentionPolicy, now: i64) -> Vec<String> {
+    let mut expired = vec![false; stored.len()];
+
+    if let Some(max_age) = policy.max_age_secs {
+        for (index, snapshot) in stored.iter().enumerate() {
+            expired[index] = snapshot.created_at < now - max_age;
+        }
+    }
+
+    if policy.max_per_session > 0 {
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        for (index, snapshot) in stored.iter().enumerate().rev() {
+            if expired[index] {
+                continue;
+            }
+            let count = kept.entry(&snapshot.session_id).or_default();
+            if *count >= policy.max_per_session {
+                expired[index] = true;
+            } else {
+                *count += 1;
+            }
+        }
+    }
+
+    if let Some(max_bytes) = policy.max_total_bytes {
+        let mut total: u64 = stored
+            .iter()
+            .zip(&expired)
+            .filter(|(_, expired)| !**expired)
+            .map(|(snapshot, _)| snapshot.bytes)
+            .sum();
+        let newest = stored.len().saturating_sub(1);
+        for (index, snapshot) in stored.iter().enumerate().take(newest) {
+            if total <= max_bytes {
+                break;
+            }
+            if !expired[index] {
+                expired[index] = true;
+                total -= snapshot.bytes;
+            }
+        }
+    }
+
+    stored
+        .iter()
+        .zip(expired)
+        .filter(|(_, expired)| *expired)
+        .map(|(snapshot, _)| snapshot.id.clone())
+        .collect()
+}
+
+fn metadata_bytes(snapshot: &Snapshot) -> u64 {
+    snapshot
+        .metadata_json
+        .as_ref()
+        .map_or(0, |json| json.len() as u64)
+}
+
+/// File backup directory of a snapshot; `None` for IDs that are not plain
+/// UUIDs, so a tampered row cannot point outside the snapshots dir
+fn backup_dir(snapshots_dir: &Path, id: &str) -> Option<PathBuf> {
+    uuid::Uuid::parse_str(id).ok()?;
+    Some(snapshots_dir.join(id))
+}
+
+pub(super) fn dir_bytes(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(id: &str, session_id: &str, created_at: i64, bytes: u64) -> Stored {
+        Stored {
+            id: id.to_string(),
+            session_id: session_id.to_string(),
+            created_at,
+            bytes,
+        }
+    }
+
+    #[test]
+    fn test_select_expired_oldest_first() {
+        let now = 100 * SECS_PER_DAY;
+        let snapshots = vec![
+            stored("a1", "a", now - 40 * SECS_PER_DAY, 10),
+            stored("a2", "a", now - 3 * SECS_PER_DAY, 10),
+            stored("b1", "b", now - 2 * SECS_PER_DAY, 50),
+            stored("a3", "a", now - 2 * SECS_PER_DAY, 10),
+            stored("a4", "a", now - SECS_PER_DAY, 10),
+            stored("b2", "b", now, 500),
+        ];
+
+        let by_age = RetentionPolicy {
+            max_age_secs: Some(30 * SECS_PER_DAY),
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(select_expired(&snapshots, &by_age, now), vec!["a1"]);
+
+        let by_count = RetentionPolicy {
+            max_per_session: 2,
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(select_expired(&snapshots, &by_count, now), vec!["a1", "a2"]);
+
+        let by_size = RetentionPolicy {
+            max_total_bytes: Some(530),
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(
+            select_expired(&snapshots, &by_size, now),
+            vec!["a1", "a2", "b1"]
+        );
+
+        // The newest snapshot stays even when it alone is over the limit
+        let tiny = RetentionPolicy {
+            max_total_bytes: Some(1),
+            ..by_age
+        };
+        assert_eq!(
+            select_expired(&snapshots, &tiny, now),
+            vec!["a1", "a2", "b1", "a3", "a4"]
+        );
+    }
+
+    #[test]
+    fn test_apply_retention() {
+        let (temp_dir, db) = crate::database::test_db();
+        let snapshots_dir = temp_dir.path().join("snapshots");
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("a.txt"), "hello").unwrap();
+
+        let first =
+            super::super::create_turn_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        let second =
+            super::super::create_turn_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        let usage = storage_usage(&db, Some(&snapshots_dir)).unwrap();
+        assert_eq!(usage.count, 2);
+        // Both snapshots share one object
+        assert!(usage.logical_bytes >= 10);
+        assert!(usage.disk_bytes > 0);
+
+        let policy = RetentionPolicy {
+            max_per_session: 1,
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(
+            apply_retention(&db, &policy, Some(&snapshots_dir)).unwrap(),
+            1
+        );
+        assert!(db.get_snapshot(&first.id).unwrap().is_none());
+        assert!(db.get_snapshot(&second.id).unwrap().is_some());
+    }
+}
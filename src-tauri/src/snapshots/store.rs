@@ -0,0 +1,389 @@
+//! Content-addressed storage for file backup contents
+//!
+//! Each distinct file content is stored once, gzip-compressed, under
+//! `<snapshots_dir>/objects/<first two hex digits>/<sha256>`, and file
+//! backups reference it as `object://<path>`, so unchanged files cost
+//! nothing in later snapshots. Compaction moves older per-snapshot
+//! directories (`file://` references) and inline base64 contents into the
+//! store, and deletes objects and directories no snapshot references.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+
+use super::{FileBackupMetadata, FILE_REF_PREFIX};
+use crate::database::{Database, Snapshot};
+use crate::{Error, Result};
+
+/// Prefix of references to objects in the store
+pub(super) const OBJECT_REF_PREFIX: &str = "object://";
+
+const OBJECTS_DIR: &str = "objects";
+
+/// Unreferenced objects and directories younger than this may belong to a
+/// snapshot still being written
+const UNREFERENCED_MIN_AGE: Duration = Duration::from_secs(3600);
+
+/// Logical and physical size of one snapshot
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotSize {
+    pub snapshot_id: String,
+    /// Uncompressed size of the backed-up files plus metadata
+    pub logical_bytes: u64,
+    /// Bytes stored, with shared objects split between their snapshots
+    pub physical_bytes: u64,
+    /// Bytes deleting this snapshot would free
+    pub exclusive_bytes: u64,
+}
+
+/// What a compaction pass did
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionReport {
+    /// Snapshots moved from per-snapshot directories or inline contents
+    /// into the object store
+    pub snapshots_migrated: usize,
+    pub objects_removed: usize,
+    pub directories_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Store `contents`, returning the reference to keep in the metadata
+pub(super) fn store_object(snapshots_dir: &Path, contents: &[u8]) -> Result<String> {
+    let hash = crate::utils::sha256_hex(contents);
+    let path = snapshots_dir.join(OBJECTS_DIR).join(&hash[..2]).join(&hash);
+    if path.is_file() {
+        // Keeps compaction from taking it for unreferenced until the new
+        // snapshot's row is written
+        if let Err(e) = fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            tracing::debug!("Failed to touch snapshot object {}: {}", hash, e);
+        }
+    } else {
+        let parent = path.parent().unwrap_or(snapshots_dir);
+        fs::create_dir_all(parent)
+            .map_err(|e| Error::Other(format!("Failed to create snapshot object dir: {e}")))?;
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+        // Written beside the object and renamed, so a crash never leaves a
+        // truncated object under its hash
+        let temp_path = parent.join(format!(".{hash}.tmp-{}", uuid::Uuid::new_v4().simple()));
+        fs::write(&temp_path, &compressed)
+            .and_then(|()| fs::rename(&temp_path, &path))
+            .map_err(|e| {
+                let _ = fs::remove_file(&temp_path);
+                Error::Other(format!("Failed to write snapshot object: {e}"))
+            })?;
+    }
+    Ok(format!("{OBJECT_REF_PREFIX}{}", path.to_string_lossy()))
+}
+
+pub(super) fn read_object(path: &Path) -> Result<Vec<u8>> {
+    let file = fs::File::open(path)
+        .map_err(|e| Error::Other(format!("Failed to open snapshot object: {e}")))?;
+    let mut contents = Vec::new();
+    GzDecoder::new(file)
+        .read_to_end(&mut contents)
+        .map_err(|e| Error::Other(format!("Failed to read snapshot object: {e}")))?;
+    Ok(contents)
+}
+
+/// Uncompressed size of an object, from the gzip trailer (exact below
+/// 4 GiB, far above the backup size limit)
+fn object_logical_size(path: &Path) -> Option<u64> {
+    let mut file = fs::File::open(path).ok()?;
+    file.seek(SeekFrom::End(-4)).ok()?;
+    let mut trailer = [0u8; 4];
+    file.read_exact(&mut trailer).ok()?;
+    Some(u64::from(u32::from_le_bytes(trailer)))
+}
+
+/// The values of a file backup's `files` map; empty for other snapshots
+fn backup_values(snapshot: &Snapshot) -> Vec<String> {
+    if snapshot.snapshot_type != "file_backup" {
+        return Vec::new();
+    }
+    snapshot
+        .metadata_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<FileBackupMetadata>(json).ok())
+        .map(|metadata| metadata.files.into_values().collect())
+        .unwrap_or_default()
+}
+
+/// Sizes of `snapshots`, in the same order
+pub(super) fn sizes(snapshots: &[Snapshot]) -> Vec<SnapshotSize> {
+    let values: Vec<Vec<String>> = snapshots.iter().map(backup_values).collect();
+    let mut refcounts: HashMap<&str, u64> = HashMap::new();
+    for value in values.iter().flatten() {
+        if let Some(path) = value.strip_prefix(OBJECT_REF_PREFIX) {
+            *refcounts.entry(path).or_default() += 1;
+        }
+    }
+    // (logical, physical)
+    let objects: HashMap<&str, (u64, u64)> = refcounts
+        .keys()
+        .map(|path| {
+            let physical = fs::metadata(path).map_or(0, |m| m.len());
+            let logical = object_logical_size(Path::new(path)).unwrap_or(0);
+            (*path, (logical, physical))
+        })
+        .collect();
+
+    snapshots
+        .iter()
+        .zip(&values)
+        .map(|(snapshot, values)| {
+            let metadata = snapshot
+                .metadata_json
+                .as_ref()
+                .map_or(0, |json| json.len() as u64);
+            let mut size = SnapshotSize {
+                snapshot_id: snapshot.id.clone(),
+                logical_bytes: metadata,
+                physical_bytes: metadata,
+                exclusive_bytes: metadata,
+            };
+            for value in values {
+                if let Some(path) = value.strip_prefix(OBJECT_REF_PREFIX) {
+                    let (logical, physical) = objects.get(path).copied().unwrap_or_default();
+                    let refs = refcounts.get(path).copied().unwrap_or(1);
+                    size.logical_bytes += logical;
+                    size.physical_bytes += physical / refs;
+                    if refs == 1 {
+                        size.exclusive_bytes += physical;
+                    }
+                } else if let Some(path) = value.strip_prefix(FILE_REF_PREFIX) {
+                    let bytes = fs::metadata(path).map_or(0, |m| m.len());
+                    size.logical_bytes += bytes;
+                    size.physical_bytes += bytes;
+                    size.exclusive_bytes += bytes;
+                } else {
+                    // Inline contents are already counted in the metadata
+                    size.logical_bytes += (value.len() as u64 / 4) * 3;
+                }
+            }
+            size
+        })
+        .collect()
+}
+
+/// Logical and physical size of every snapshot, oldest first. Blocking.
+pub fn snapshot_sizes(db: &Database) -> Result<Vec<SnapshotSize>> {
+    Ok(sizes(&db.get_all_snapshots()?))
+}
+
+/// Move file backups into the object store and delete what no snapshot
+/// references any more. Blocking.
+///
+/// A snapshot that fails to migrate is logged and left as it was.
+pub fn compact(db: &Database, snapshots_dir: &Path) -> Result<CompactionReport> {
+    let mut report = CompactionReport::default();
+    if !snapshots_dir.is_dir() {
+        return Ok(report);
+    }
+    let before = super::retention::dir_bytes(snapshots_dir);
+
+    for snapshot in db.get_all_snapshots()? {
+        match migrate(db, &snapshot, snapshots_dir) {
+            Ok(true) => report.snapshots_migrated += 1,
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to compact snapshot {}: {}", snapshot.id, e),
+        }
+    }
+
+    // Read again: retention may have deleted snapshots meanwhile
+    let snapshots = db.get_all_snapshots()?;
+    let snapshot_ids: HashSet<&str> = snapshots.iter().map(|s| s.id.as_str()).collect();
+    let referenced: HashSet<String> = snapshots
+        .iter()
+        .flat_map(backup_values)
+        .filter_map(|value| {
+            let path = value.strip_prefix(OBJECT_REF_PREFIX)?;
+            Some(Path::new(path).file_name()?.to_string_lossy().into_owned())
+        })
+        .collect();
+
+    let entries = fs::read_dir(snapshots_dir)
+        .map_err(|e| Error::Other(format!("Failed to read snapshots dir: {e}")))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        if name == OBJECTS_DIR {
+            report.objects_removed += remove_unreferenced_objects(&path, &referenced);
+        } else if uuid::Uuid::parse_str(&name).is_ok()
+            && !snapshot_ids.contains(name.as_str())
+            && is_stale(&path)
+        {
+            match fs::remove_dir_all(&path) {
+                Ok(()) => report.directories_removed += 1,
+                Err(e) => tracing::warn!("Failed to remove snapshot dir {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    report.bytes_freed = before.saturating_sub(super::retention::dir_bytes(snapshots_dir));
+    if report.snapshots_migrated + report.objects_removed + report.directories_removed > 0 {
+        tracing::info!(
+            "Compacted snapshots: {} migrated, {} objects and {} directories removed, {} bytes freed",
+            report.snapshots_migrated,
+            report.objects_removed,
+            report.directories_removed,
+            report.bytes_freed
+        );
+    }
+    Ok(report)
+}
+
+/// Rewrite a file backup to reference objects; returns whether it changed
+fn migrate(db: &Database, snapshot: &Snapshot, snapshots_dir: &Path) -> Result<bool> {
+    if snapshot.snapshot_type != "file_backup" {
+        return Ok(false);
+    }
+    let Some(json) = snapshot.metadata_json.as_deref() else {
+        return Ok(false);
+    };
+    let mut metadata: FileBackupMetadata = serde_json::from_str(json)
+        .map_err(|e| Error::Other(format!("Failed to parse file backup metadata: {e}")))?;
+    if metadata
+        .files
+        .values()
+        .all(|value| value.starts_with(OBJECT_REF_PREFIX))
+    {
+        return Ok(false);
+    }
+
+    for (relative_path, value) in metadata.files.iter_mut() {
+        if value.starts_with(OBJECT_REF_PREFIX) {
+            continue;
+        }
+        let contents = match value.strip_prefix(FILE_REF_PREFIX) {
+            Some(path) => fs::read(path).map_err(|e| {
+                Error::Other(format!(
+                    "Failed to read snapshot file for '{relative_path}': {e}"
+                ))
+            })?,
+            None => BASE64.decode(value.as_bytes()).map_err(|e| {
+                Error::Other(format!(
+                    "Failed to decode file content for '{relative_path}': {e}"
+                ))
+            })?,
+        };
+        *value = store_object(snapshots_dir, &contents)?;
+    }
+
+    let json = serde_json::to_string(&metadata)
+        .map_err(|e| Error::Other(format!("Failed to serialize metadata: {e}")))?;
+    if !db.update_snapshot_metadata(&snapshot.id, &json)? {
+        return Ok(false);
+    }
+    let legacy_dir = snapshots_dir.join(&snapshot.id);
+    if uuid::Uuid::parse_str(&snapshot.id).is_ok() && legacy_dir.is_dir() {
+        if let Err(e) = fs::remove_dir_all(&legacy_dir) {
+            tracing::warn!(
+                "Failed to remove snapshot dir {}: {}",
+                legacy_dir.display(),
+                e
+            );
+        }
+    }
+    Ok(true)
+}
+
+fn remove_unreferenced_objects(objects_dir: &Path, referenced: &HashSet<String>) -> usize {
+    let mut removed = 0;
+    for entry in walkdir::WalkDir::new(objects_dir)
+        .min_depth(2)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        let name = entry.file_name().to_string_lossy();
+        if !entry.file_type().is_file() || referenced.contains(name.as_ref()) {
+            continue;
+        }
+        if is_stale(entry.path()) && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+fn is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age >= UNREFERENCED_MIN_AGE)
+}
+
+/// Location of an object reference
+pub(super) fn object_path(value: &str) -> Option<PathBuf> {
+    value.strip_prefix(OBJECT_REF_PREFIX).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objects_are_shared_and_compacted() {
+        let (temp_dir, db) = crate::database::test_db();
+        let snapshots_dir = temp_dir.path().join("snapshots");
+        let project = temp_dir.path().join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("same.txt"), "unchanged ".repeat(100)).unwrap();
+        fs::write(project.join("edit.txt"), "v1").unwrap();
+
+        let first =
+            super::super::create_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        fs::write(project.join("edit.txt"), "v2").unwrap();
+        super::super::create_snapshot(&db, "s1", &project, Some(&snapshots_dir)).unwrap();
+        // Legacy snapshot with inline contents
+        let legacy = super::super::create_snapshot(&db, "s2", &project, None).unwrap();
+
+        let sizes = snapshot_sizes(&db).unwrap();
+        let objects = |dir: &Path| {
+            walkdir::WalkDir::new(dir.join(OBJECTS_DIR))
+                .min_depth(2)
+                .into_iter()
+                .filter(|entry| entry.as_ref().unwrap().file_type().is_file())
+                .count()
+        };
+        // same.txt, edit.txt v1 and v2
+        assert_eq!(objects(&snapshots_dir), 3);
+        assert!(sizes[0].logical_bytes > sizes[0].physical_bytes);
+        assert!(sizes[0].exclusive_bytes < sizes[0].physical_bytes + 1000);
+
+        let report = compact(&db, &snapshots_dir).unwrap();
+        assert_eq!(report.snapshots_migrated, 1);
+        // The legacy snapshot's files were all stored already
+        assert_eq!(objects(&snapshots_dir), 3);
+        let migrated = db.get_snapshot(&legacy.id).unwrap().unwrap();
+        assert!(backup_values(&migrated)
+            .iter()
+            .all(|value| value.starts_with(OBJECT_REF_PREFIX)));
+
+        db.delete_snapshot(&first.id).unwrap();
+        // Too recent to be removed
+        assert_eq!(compact(&db, &snapshots_dir).unwrap().objects_removed, 0);
+
+        fs::write(project.join("edit.txt"), "restored?").unwrap();
+        super::super::revert_to_snapshot(&db, &legacy.id, &project, false, Some(&snapshots_dir))
+            .unwrap();
+        assert_eq!(fs::read_to_string(project.join("edit.txt")).unwrap(), "v2");
+    }
+}
@@ -0,0 +1,158 @@
+//! Staged startup progress for the splash screen.
+//!
+//! `AppState::new` and `start_background_tasks` report each phase as it
+//! completes with a `startup-progress` event and a log line carrying how
+//! long the phase took, so the splash screen can show real progress and a
+//! slow launch can be pinned on one phase. Events sent before the renderer
+//! is ready are buffered like any other and arrive in a burst; `step`
+//! orders them.
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::events::AppEventEmitter;
+
+/// Phases slower than this are logged as a warning
+const SLOW_PHASE: Duration = Duration::from_secs(5);
+
+/// Startup phases, in the order they complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StartupPhase {
+    /// `codex-global-state.json` loaded
+    StateLoaded,
+    /// SQLite database opened
+    DatabaseOpened,
+    /// Schema created and pending migrations applied
+    MigrationsRun,
+    /// Supervisors, watchdogs and periodic tasks running
+    WatchersStarted,
+    /// The app-server is being spawned
+    AppServerSpawning,
+    /// Startup finished; `detail` holds the app-server error if it failed
+    Ready,
+}
+
+impl StartupPhase {
+    const ALL: [StartupPhase; 6] = [
+        Self::StateLoaded,
+        Self::DatabaseOpened,
+        Self::MigrationsRun,
+        Self::WatchersStarted,
+        Self::AppServerSpawning,
+        Self::Ready,
+    ];
+
+    fn step(self) -> u32 {
+        self as u32 + 1
+    }
+}
+
+/// Payload of `startup-progress`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupProgressEvent {
+    pub phase: StartupPhase,
+    /// 1-based position of `phase` among `total_steps`
+    pub step: u32,
+    pub total_steps: u32,
+    /// Milliseconds since startup began
+    pub elapsed_ms: u64,
+    /// Milliseconds since the previous phase completed
+    pub phase_ms: u64,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct StartupProgress {
+    started: Instant,
+    last: Mutex<Instant>,
+}
+
+impl StartupProgress {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            last: Mutex::new(started),
+        }
+    }
+
+    /// Record that `phase` completed at `now`
+    pub fn record(
+        &self,
+        phase: StartupPhase,
+        detail: Option<String>,
+        now: Instant,
+    ) -> StartupProgressEvent {
+        let previous = std::mem::replace(&mut *self.last.lock(), now);
+        StartupProgressEvent {
+            phase,
+            step: phase.step(),
+            total_steps: StartupPhase::ALL.len() as u32,
+            elapsed_ms: now.saturating_duration_since(self.started).as_millis() as u64,
+            phase_ms: now.saturating_duration_since(previous).as_millis() as u64,
+            detail,
+        }
+    }
+
+    /// Record `phase`, log it and emit `startup-progress` in the background
+    pub fn report(&self, events: &AppEventEmitter, phase: StartupPhase, detail: Option<String>) {
+        let progress = self.record(phase, detail, Instant::now());
+        if progress.phase_ms >= SLOW_PHASE.as_millis() as u64 {
+            tracing::warn!(
+                "Slow startup phase {:?}: {} ms ({} ms since launch)",
+                progress.phase,
+                progress.phase_ms,
+                progress.elapsed_ms
+            );
+        } else {
+            tracing::info!(
+                "Startup phase {:?} after {} ms ({} ms since launch)",
+                progress.phase,
+                progress.phase_ms,
+                progress.elapsed_ms
+            );
+        }
+        let events = events.clone();
+        tauri::async_runtime::spawn(async move {
+            events.emit("startup-progress", progress).await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_phases() {
+        let started = Instant::now();
+        let progress = StartupProgress::new(started);
+
+        let opened = progress.record(
+            StartupPhase::DatabaseOpened,
+            None,
+            started + Duration::from_millis(40),
+        );
+        assert_eq!((opened.step, opened.total_steps), (2, 6));
+        assert_eq!((opened.elapsed_ms, opened.phase_ms), (40, 40));
+
+        let migrated = progress.record(
+            StartupPhase::MigrationsRun,
+            None,
+            started + Duration::from_millis(250),
+        );
+        assert_eq!(migrated.step, 3);
+        assert_eq!((migrated.elapsed_ms, migrated.phase_ms), (250, 210));
+
+        let ready = progress.record(
+            StartupPhase::Ready,
+            Some("spawn failed".to_string()),
+            started + Duration::from_millis(300),
+        );
+        assert_eq!(ready.step, ready.total_steps);
+        assert_eq!(ready.detail.as_deref(), Some("spawn failed"));
+    }
+}
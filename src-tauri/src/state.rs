@@ -7,13 +7,46 @@ use tauri::{AppHandle, Manager};
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing_appender::non_blocking::WorkerGuard;
 
-use crate::app_server::{AppServerEvent, AppServerProcess};
-use crate::database::Database;
+use crate::app_server::ipc_bridge::{
+    AccountInfo, ApprovalDecision, ApprovalResponseResult, TurnInterruptParams,
+    TurnStartParams, TurnStartResponse, UserInput,
+};
+use crate::app_server::{
+    ActiveTurns, AppServerEvent, AppServerProcess, AuthMonitor, IdleShutdown, LoginRequired, RateLimitTracker, RpcTrace, ServerAvailability,
+    TranscriptJournal, TurnBudgetExceeded, TurnBudgets, TurnWaiters,
+};
+use crate::app_lock::AppLock;
+use crate::approvals::ApprovalContext;
+use crate::background_tasks::{self, BackgroundTasks};
+use crate::ci_status::CiWatchers;
+use crate::codex_import::CodexSessionIndex;
+use crate::content_search::ContentSearches;
+use crate::database::{Database, SessionStatus, TurnOutcome, TurnOutcomeRecord};
 use crate::events::AppEventEmitter;
+use crate::file_index::FileIndexManager;
+use crate::followups::FollowupQueues;
 use crate::global_state::{unix_timestamp_millis, unix_timestamp_secs, GlobalStateStore};
 use crate::health::RendererHealth;
+use crate::i18n;
+use crate::live_diff::{self, LiveDiffTracker};
+use crate::notifications::{NotificationKind, Notifier};
+use crate::pending_approvals::PendingApproval;
+use crate::pending_work::PendingWorkCache;
+use crate::safe_mode::{self, SafeModeReason};
+use crate::session_preview;
+use crate::turn_queue::{self, TurnQueue};
+use crate::session_stats;
+use crate::startup_progress::{StartupPhase, StartupProgress};
+use crate::terminal_output::TerminalScrollback;
+use crate::turn_timings::TurnTimingTracker;
+use crate::usage;
+use crate::watcher::WatcherManager;
 use crate::Result;
 
+/// Files in the app data directory
+pub const DATABASE_FILE: &str = "codex-desktop.db";
+pub const GLOBAL_STATE_FILE: &str = "codex-global-state.json";
+
 /// Global application state
 pub struct AppState {
     /// Database connection for projects, sessions, and metadata
@@ -34,6 +67,81 @@ pub struct AppState {
     /// Renderer health tracker
     pub renderer_health: Arc<RendererHealth>,
 
+    /// In-memory file indexes for project file search
+    pub file_index: Arc<FileIndexManager>,
+
+    /// Per-project filesystem watchers emitting change events
+    pub watchers: Arc<WatcherManager>,
+
+    /// Cancellation flags for running content searches
+    pub content_searches: Arc<ContentSearches>,
+
+    /// Per-project CI status pollers emitting change events
+    pub ci_watchers: Arc<CiWatchers>,
+
+    /// Whole output lines of recent terminal commands
+    pub terminal_scrollback: Arc<TerminalScrollback>,
+
+    /// Follow-up prompts sent automatically after successful turns
+    pub followups: Arc<FollowupQueues>,
+
+    /// Worktree commit counts reused by the pending work digest
+    pub pending_work: Arc<PendingWorkCache>,
+
+    /// Backend-driven waiters for turn completion
+    pub turn_waiters: Arc<TurnWaiters>,
+
+    /// Latest account rate limits and turns held back by them
+    pub rate_limits: Arc<RateLimitTracker>,
+
+    /// Phase timings of running turns
+    pub turn_timings: Arc<TurnTimingTracker>,
+
+    /// Per-session diffs built from applied patches and command writes
+    pub live_diffs: Arc<LiveDiffTracker>,
+
+    /// Token and cost ceilings of pending and running turns
+    pub turn_budgets: Arc<TurnBudgets>,
+
+    /// Search index over Codex CLI rollouts, built on first search
+    pub codex_index: Arc<CodexSessionIndex>,
+
+    /// Whether the login still works, for `login-required` prompts
+    pub auth: Arc<AuthMonitor>,
+
+    /// Enable flags and concurrency limits of background workloads
+    pub background_tasks: Arc<BackgroundTasks>,
+
+    /// Recorder of app-server JSON-RPC traffic (off unless enabled)
+    pub rpc_trace: Arc<RpcTrace>,
+
+    /// Adds parsed command context to exec approval requests
+    pub approvals: Arc<ApprovalContext>,
+
+    /// On-disk journal of the turns in flight, replayed after a crash
+    pub transcript_journal: Arc<TranscriptJournal>,
+
+    /// Degraded-mode tracking when the app-server cannot be started
+    pub availability: Arc<ServerAvailability>,
+
+    /// Stopping the app-server while idle and starting it again on demand
+    pub idle: Arc<IdleShutdown>,
+
+    /// Turns in flight per thread, bounded by the concurrency settings
+    pub active_turns: Arc<ActiveTurns>,
+
+    /// Prompts lined up per project, run one after another
+    pub turn_queue: Arc<TurnQueue>,
+
+    /// Passphrase lock gating commands on shared machines
+    pub app_lock: Arc<AppLock>,
+
+    /// Native notifications for finished turns, approvals and crashes
+    pub notifier: Arc<Notifier>,
+
+    /// Set when the app booted in safe mode after a crash loop
+    pub safe_mode: Option<SafeModeReason>,
+
     /// App server event channel (supervisor)
     app_server_events_tx: mpsc::Sender<AppServerEvent>,
     app_server_events_rx: StdMutex<Option<mpsc::Receiver<AppServerEvent>>>,
@@ -41,6 +149,9 @@ pub struct AppState {
     /// Restart lock to avoid concurrent start/stop
     app_server_restart_lock: Arc<Mutex<()>>,
 
+    /// Phases reported as `startup-progress` while launching
+    startup_progress: Arc<StartupProgress>,
+
     /// Keep tracing worker guard alive for file logging
     #[allow(dead_code)]
     log_guard: StdMutex<Option<WorkerGuard>>,
@@ -58,31 +169,76 @@ impl AppState {
         // Ensure the directory exists
         std::fs::create_dir_all(&app_data_dir)?;
 
+        let events = AppEventEmitter::new(app_handle.clone());
+        let startup_progress = Arc::new(StartupProgress::new(Instant::now()));
+
+        // Global state store (loaded first so crash-loop detection sees every launch)
+        let global_state_path = app_data_dir.join(GLOBAL_STATE_FILE);
+        let global_state = Arc::new(GlobalStateStore::load(global_state_path)?);
+        startup_progress.report(&events, StartupPhase::StateLoaded, None);
+
+        let mut safe_mode = None;
+        global_state.update(|state| {
+            let now_ms = unix_timestamp_millis();
+            state.startup.app_started_at_ms = Some(now_ms);
+            state.startup.renderer_ready_latency_ms = None;
+            safe_mode = safe_mode::record_startup(&mut state.startup, now_ms);
+        });
+        // Persist the unstable mark now; a crash later in startup must still count
+        if let Err(err) = global_state.flush() {
+            tracing::warn!("Failed to flush global state at startup: {}", err);
+        }
+        if let Some(reason) = &safe_mode {
+            tracing::warn!("Starting in safe mode: {}", reason.message);
+        }
+
         // Initialize database
-        let db_path = app_data_dir.join("codex-desktop.db");
-        let database = Arc::new(Database::new(&db_path)?);
+        let db_path = app_data_dir.join(DATABASE_FILE);
+        let database = Arc::new(Database::open(&db_path)?);
+        startup_progress.report(&events, StartupPhase::DatabaseOpened, None);
+        database.migrate()?;
+        startup_progress.report(&events, StartupPhase::MigrationsRun, None);
 
         tracing::info!("Database initialized at {:?}", db_path);
 
         // Run periodic database VACUUM (weekly)
-        match database.vacuum_if_needed(7) {
-            Ok(true) => tracing::info!("Weekly database VACUUM completed"),
-            Ok(false) => tracing::debug!("Database VACUUM not needed yet"),
-            Err(e) => tracing::warn!("Database VACUUM failed (non-fatal): {}", e),
+        if safe_mode.is_none() {
+            match database.vacuum_if_needed(7) {
+                Ok(true) => tracing::info!("Weekly database VACUUM completed"),
+                Ok(false) => tracing::debug!("Database VACUUM not needed yet"),
+                Err(e) => tracing::warn!("Database VACUUM failed (non-fatal): {}", e),
+            }
         }
 
-        // Global state store
-        let global_state_path = app_data_dir.join("codex-global-state.json");
-        let global_state = Arc::new(GlobalStateStore::load(global_state_path)?);
+        match database.interrupt_stale_comparison_runs() {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Marked {} unfinished comparison runs interrupted", count),
+            Err(e) => tracing::warn!("Failed to update unfinished comparison runs: {}", e),
+        }
 
-        global_state.update(|state| {
-            state.startup.app_started_at_ms = Some(unix_timestamp_millis());
-            state.startup.renderer_ready_latency_ms = None;
-        });
+        let transcript_journal = Arc::new(TranscriptJournal::new(app_data_dir.join("transcripts")));
+        match transcript_journal.recover(&database) {
+            0 => {}
+            count => tracing::info!("Recovered {} unfinished turns from journals", count),
+        }
 
-        let events = AppEventEmitter::new(app_handle.clone());
         let renderer_health = Arc::new(RendererHealth::new());
         let (app_server_events_tx, app_server_events_rx) = mpsc::channel(16);
+        let rpc_trace = Arc::new(RpcTrace::new(
+            &app_data_dir.join("logs"),
+            global_state.read(|state| state.rpc_trace.clone()),
+        ));
+        let approvals = Arc::new(ApprovalContext::new(database.clone()));
+        let turn_queue = Arc::new(TurnQueue::new(database.clone()));
+        let background_tasks = Arc::new(BackgroundTasks::new(
+            global_state.read(|state| state.background_tasks.clone()),
+        ));
+        let app_lock = Arc::new(global_state.read(|state| AppLock::new(&state.app_lock)));
+        if app_lock.is_locked() {
+            events.hold();
+        }
+        let notifier = Arc::new(Notifier::new(app_handle.clone(), global_state.clone()));
+        let file_index = Arc::new(FileIndexManager::new());
 
         Ok(Self {
             database,
@@ -91,15 +247,48 @@ impl AppState {
             events,
             global_state,
             renderer_health,
+            file_index: file_index.clone(),
+            watchers: Arc::new(WatcherManager::new(file_index)),
+            content_searches: Arc::new(ContentSearches::new()),
+            ci_watchers: Arc::new(CiWatchers::new()),
+            terminal_scrollback: Arc::new(TerminalScrollback::new()),
+            followups: Arc::new(FollowupQueues::new()),
+            pending_work: Arc::new(PendingWorkCache::new()),
+            turn_waiters: Arc::new(TurnWaiters::new()),
+            rate_limits: Arc::new(RateLimitTracker::new()),
+            turn_timings: Arc::new(TurnTimingTracker::new()),
+            live_diffs: Arc::new(LiveDiffTracker::new()),
+            turn_budgets: Arc::new(TurnBudgets::new()),
+            codex_index: Arc::new(CodexSessionIndex::new(background_tasks.clone())),
+            auth: Arc::new(AuthMonitor::new()),
+            background_tasks,
+            rpc_trace,
+            approvals,
+            transcript_journal,
+            availability: Arc::new(ServerAvailability::new()),
+            idle: Arc::new(IdleShutdown::new()),
+            active_turns: Arc::new(ActiveTurns::new()),
+            turn_queue,
+            app_lock,
+            notifier,
+            safe_mode,
             app_server_events_tx,
             app_server_events_rx: StdMutex::new(Some(app_server_events_rx)),
             app_server_restart_lock: Arc::new(Mutex::new(())),
+            startup_progress,
             log_guard: StdMutex::new(log_guard),
         })
     }
 
     /// Start the app server process
+    ///
+    /// In safe mode this never spawns the process; an explicit restart still does.
     pub async fn start_app_server(&self) -> Result<()> {
+        if self.safe_mode.is_some() && self.app_server.read().await.is_none() {
+            return Err(crate::Error::AppServer(
+                "App server autostart is disabled in safe mode".to_string(),
+            ));
+        }
         self.handle().start_app_server().await
     }
 
@@ -113,7 +302,56 @@ impl AppState {
         self.handle().restart_app_server().await
     }
 
+    /// Record a rate-limit snapshot, warn on newly crossed thresholds and
+    /// release held-back turns once the limits allow
+    pub async fn observe_rate_limits(&self, payload: &serde_json::Value) {
+        self.handle().observe_rate_limits(payload).await;
+    }
+
+    /// Track an `account/read` result, emitting `login-required` or
+    /// `login-restored` when the login state changed
+    pub async fn observe_account(&self, info: &AccountInfo) {
+        self.handle().observe_account(info).await;
+    }
+
+    /// Take a turn concurrency slot for `thread_id`, failing when a limit
+    /// is reached
+    ///
+    /// The slot must be followed by `send_reserved_turn_start`, which gives
+    /// it back if the turn does not start.
+    pub async fn reserve_turn(&self, thread_id: &str) -> Result<()> {
+        self.handle().reserve_turn(thread_id).await
+    }
+
+    /// Send `turn/start` for a turn whose slot `reserve_turn` took
+    pub async fn send_reserved_turn_start(
+        &self,
+        params: &TurnStartParams,
+        requested_at_ms: i64,
+    ) -> Result<TurnStartResponse> {
+        self.handle()
+            .send_reserved_turn_start(params, requested_at_ms)
+            .await
+    }
+
+    /// Start the next due entry of every idle turn queue, in the background
+    ///
+    /// Queues do not run in safe mode.
+    pub fn dispatch_queued_turns(&self) {
+        if self.safe_mode.is_some() {
+            return;
+        }
+        let handle = self.handle();
+        tauri::async_runtime::spawn(async move {
+            handle.dispatch_queued_turns().await;
+        });
+    }
+
     /// Start background supervisors (app-server watchdog, renderer heartbeat)
+    ///
+    /// In safe mode only the app-server event monitor runs: a server the
+    /// user starts by hand would otherwise block once its event channel
+    /// fills. The app-server is not started and no maintenance runs.
     pub fn start_background_tasks(&self) {
         if let Some(rx) = self.app_server_events_rx.lock().unwrap().take() {
             let handle = self.handle();
@@ -122,6 +360,25 @@ impl AppState {
             });
         }
 
+        if self.safe_mode.is_some() {
+            tracing::warn!("Safe mode: background tasks not started");
+            self.startup_progress.report(
+                &self.events,
+                StartupPhase::Ready,
+                Some("Safe mode: background tasks and the app-server are not started".to_string()),
+            );
+            return;
+        }
+
+        let global_state = self.global_state.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(safe_mode::CRASH_WINDOW_SECS)).await;
+            global_state.update(|state| safe_mode::mark_stable(&mut state.startup));
+            if let Err(err) = global_state.flush() {
+                tracing::warn!("Failed to flush global state after stable startup: {}", err);
+            }
+        });
+
         let renderer_health = self.renderer_health.clone();
         let app_handle = self.app_handle.clone();
         let events = self.events.clone();
@@ -129,6 +386,73 @@ impl AppState {
         tauri::async_runtime::spawn(async move {
             monitor_renderer(renderer_health, app_handle, events, global_state).await;
         });
+
+        let heartbeat = BackendHeartbeatSources {
+            handle: self.handle(),
+            watchers: self.watchers.clone(),
+        };
+        tauri::async_runtime::spawn(async move {
+            emit_backend_heartbeats(heartbeat).await;
+        });
+
+        let database = self.database.clone();
+        let tasks = self.background_tasks.clone();
+        tauri::async_runtime::spawn(async move {
+            run_periodic_gc(database, tasks).await;
+        });
+
+        let app_lock = self.app_lock.clone();
+        let global_state = self.global_state.clone();
+        let events = self.events.clone();
+        tauri::async_runtime::spawn(async move {
+            run_app_lock_timer(app_lock, global_state, events).await;
+        });
+
+        if let Ok(app_data_dir) = self.app_handle.path().app_data_dir() {
+            let database = self.database.clone();
+            let tasks = self.background_tasks.clone();
+            tauri::async_runtime::spawn(async move {
+                run_snapshot_compaction(database, tasks, app_data_dir.join("snapshots")).await;
+            });
+        }
+
+        let handle = self.handle();
+        tauri::async_runtime::spawn(async move {
+            release_rate_limited_turns(handle).await;
+        });
+
+        let handle = self.handle();
+        tauri::async_runtime::spawn(async move {
+            run_turn_queue_schedule(handle).await;
+        });
+
+        let handle = self.handle();
+        tauri::async_runtime::spawn(async move {
+            poll_login_state(handle).await;
+        });
+
+        let handle = self.handle();
+        tauri::async_runtime::spawn(async move {
+            run_idle_shutdown(handle).await;
+        });
+
+        let global_state = self.global_state.clone();
+        let events = self.events.clone();
+        tauri::async_runtime::spawn(async move {
+            check_codex_version(global_state, events).await;
+        });
+
+        self.startup_progress
+            .report(&self.events, StartupPhase::WatchersStarted, None);
+
+        // Spawned up front so the first session does not wait for it
+        let handle = self.handle();
+        let startup_progress = self.startup_progress.clone();
+        tauri::async_runtime::spawn(async move {
+            startup_progress.report(&handle.events, StartupPhase::AppServerSpawning, None);
+            let detail = handle.start_app_server().await.err().map(|err| err.to_string());
+            startup_progress.report(&handle.events, StartupPhase::Ready, detail);
+        });
     }
 
     fn handle(&self) -> AppStateHandle {
@@ -137,7 +461,24 @@ impl AppState {
             app_server_events_tx: self.app_server_events_tx.clone(),
             events: self.events.clone(),
             global_state: self.global_state.clone(),
+            database: self.database.clone(),
+            followups: self.followups.clone(),
+            turn_waiters: self.turn_waiters.clone(),
+            rate_limits: self.rate_limits.clone(),
+            turn_timings: self.turn_timings.clone(),
+            live_diffs: self.live_diffs.clone(),
+            turn_budgets: self.turn_budgets.clone(),
+            auth: self.auth.clone(),
+            background_tasks: self.background_tasks.clone(),
+            rpc_trace: self.rpc_trace.clone(),
+            approvals: self.approvals.clone(),
+            transcript_journal: self.transcript_journal.clone(),
+            availability: self.availability.clone(),
+            idle: self.idle.clone(),
+            active_turns: self.active_turns.clone(),
+            turn_queue: self.turn_queue.clone(),
             restart_lock: self.app_server_restart_lock.clone(),
+            notifier: self.notifier.clone(),
         }
     }
 }
@@ -148,13 +489,35 @@ struct AppStateHandle {
     app_server_events_tx: mpsc::Sender<AppServerEvent>,
     events: AppEventEmitter,
     global_state: Arc<GlobalStateStore>,
+    database: Arc<Database>,
+    followups: Arc<FollowupQueues>,
+    turn_waiters: Arc<TurnWaiters>,
+    rate_limits: Arc<RateLimitTracker>,
+    turn_timings: Arc<TurnTimingTracker>,
+    live_diffs: Arc<LiveDiffTracker>,
+    turn_budgets: Arc<TurnBudgets>,
+    auth: Arc<AuthMonitor>,
+    background_tasks: Arc<BackgroundTasks>,
+    rpc_trace: Arc<RpcTrace>,
+    approvals: Arc<ApprovalContext>,
+    transcript_journal: Arc<TranscriptJournal>,
+    availability: Arc<ServerAvailability>,
+    idle: Arc<IdleShutdown>,
+    active_turns: Arc<ActiveTurns>,
+    turn_queue: Arc<TurnQueue>,
     restart_lock: Arc<Mutex<()>>,
+    notifier: Arc<Notifier>,
 }
 
 impl AppStateHandle {
     async fn start_app_server(&self) -> Result<()> {
         let _guard = self.restart_lock.lock().await;
-        self.start_app_server_inner().await
+        let was_degraded = self.availability.degraded().is_some();
+        self.start_app_server_inner().await?;
+        if was_degraded {
+            self.events.emit("app-server-reconnected", ()).await;
+        }
+        Ok(())
     }
 
     async fn stop_app_server(&self) -> Result<()> {
@@ -165,6 +528,8 @@ impl AppStateHandle {
     async fn restart_app_server(&self) -> Result<()> {
         let _guard = self.restart_lock.lock().await;
         tracing::info!("Restarting app server...");
+        // An explicit restart always tries, even while degraded
+        self.availability.reset_backoff();
         self.stop_app_server_inner().await?;
         self.start_app_server_inner().await?;
 
@@ -178,22 +543,86 @@ impl AppStateHandle {
         Ok(())
     }
 
+    /// Start the app-server if needed, tracking degraded mode
+    ///
+    /// While degraded, starts within the retry window fail immediately with
+    /// the recorded reason instead of waiting out another handshake.
     async fn start_app_server_inner(&self) -> Result<()> {
+        {
+            let mut server = self.app_server.write().await;
+            if server.as_mut().is_some_and(|process| process.is_running()) {
+                return Ok(());
+            }
+        }
+        if let Some(degraded) = self.availability.should_skip_start() {
+            return Err(crate::Error::AppServer(format!(
+                "App server unavailable: {}",
+                degraded.reason
+            )));
+        }
+
+        match self.spawn_app_server().await {
+            Ok(()) => {
+                if self.availability.mark_available() {
+                    tracing::info!("App server available again");
+                }
+                Ok(())
+            }
+            Err(err) => {
+                let degraded = self.availability.mark_failed(&err.to_string());
+                tracing::warn!(
+                    "App server unavailable ({} failed attempts): {}",
+                    degraded.failed_attempts,
+                    degraded.reason
+                );
+                self.events.emit("app-server-unavailable", degraded).await;
+                Err(err)
+            }
+        }
+    }
+
+    async fn spawn_app_server(&self) -> Result<()> {
+        let (mut launch, providers) = self.global_state.read(|state| {
+            (
+                state.app_server_launch.clone(),
+                state.model_providers.providers.clone(),
+            )
+        });
+        // Provider API keys come from the keychain, never from the saved settings
+        let provider_env = crate::utils::spawn_blocking_io(move || {
+            Ok(crate::model_providers::provider_env(&providers))
+        })
+        .await?;
+        launch.env.extend(provider_env);
         let mut server = self.app_server.write().await;
         match server.as_mut() {
             None => {
-                let process =
-                    AppServerProcess::spawn(self.events.clone(), self.app_server_events_tx.clone())
-                        .await?;
+                let process = AppServerProcess::spawn(
+                    self.events.clone(),
+                    self.app_server_events_tx.clone(),
+                    self.rpc_trace.clone(),
+                    self.approvals.clone(),
+                    self.transcript_journal.clone(),
+                    &launch,
+                )
+                .await?;
+                self.note_process_started(process.generation());
                 *server = Some(process);
                 tracing::info!("App server started");
             }
             Some(existing) => {
                 if !existing.is_running() {
                     tracing::warn!("App server was not running, respawning...");
-                    let process =
-                        AppServerProcess::spawn(self.events.clone(), self.app_server_events_tx.clone())
-                            .await?;
+                    let process = AppServerProcess::spawn(
+                        self.events.clone(),
+                        self.app_server_events_tx.clone(),
+                        self.rpc_trace.clone(),
+                        self.approvals.clone(),
+                        self.transcript_journal.clone(),
+                        &launch,
+                    )
+                    .await?;
+                    self.note_process_started(process.generation());
                     *server = Some(process);
                     tracing::info!("App server restarted");
                 }
@@ -202,6 +631,638 @@ impl AppStateHandle {
         Ok(())
     }
 
+    /// Send `turn/start`, holding a concurrency slot for the thread
+    ///
+    /// The slot is taken before the request so parallel starts cannot
+    /// overshoot the limits, and given back if the request fails. The
+    /// request only needs a read lock, so turns on other threads are not
+    /// held up by it.
+    async fn send_turn_start(
+        &self,
+        params: &TurnStartParams,
+        requested_at_ms: i64,
+    ) -> Result<TurnStartResponse> {
+        self.reserve_turn(&params.thread_id).await?;
+        self.send_reserved_turn_start(params, requested_at_ms).await
+    }
+
+    async fn reserve_turn(&self, thread_id: &str) -> Result<()> {
+        let database = self.database.clone();
+        let session_id = thread_id.to_string();
+        let project_id =
+            crate::utils::spawn_blocking_io(move || database.get_session_by_id(&session_id))
+                .await
+                .ok()
+                .flatten()
+                .map(|session| session.project_id);
+        let limits = self.global_state.read(|state| state.turn_concurrency.clone());
+        self.active_turns.reserve(
+            thread_id,
+            project_id.as_deref(),
+            &limits,
+            unix_timestamp_millis(),
+        )
+    }
+
+    async fn send_reserved_turn_start(
+        &self,
+        params: &TurnStartParams,
+        requested_at_ms: i64,
+    ) -> Result<TurnStartResponse> {
+        self.turn_timings
+            .turn_requested(&params.thread_id, requested_at_ms);
+        self.idle.touch(Instant::now());
+
+        let woken = self.wake_for_thread(&params.thread_id).await;
+        let result: Result<TurnStartResponse> = match woken {
+            Ok(()) => {
+                let server = self.app_server.read().await;
+                match server.as_ref() {
+                    Some(process) => process.send_request("turn/start", params).await,
+                    None => Err(crate::Error::AppServer("App server not running".to_string())),
+                }
+            }
+            Err(err) => Err(err),
+        };
+        if result.is_err() {
+            self.active_turns.release(&params.thread_id);
+        }
+        result
+    }
+
+    /// Start the app-server again if it was stopped for being idle, and
+    /// resume `thread_id` on it when the thread was loaded before the stop
+    async fn wake_for_thread(&self, thread_id: &str) -> Result<()> {
+        if self.idle.is_stopped() {
+            tracing::info!("Starting the idle-stopped app server for thread {}", thread_id);
+            self.start_app_server().await?;
+        }
+        if !self.idle.needs_resume(thread_id) {
+            return Ok(());
+        }
+        let params = crate::commands::thread::resume_params(
+            self.database.clone(),
+            thread_id,
+            Some(1),
+            None,
+        )
+        .await?;
+        {
+            let server = self.app_server.read().await;
+            let process = server
+                .as_ref()
+                .ok_or_else(|| crate::Error::AppServer("App server not running".to_string()))?;
+            process
+                .send_request::<_, serde_json::Value>("thread/resume", params)
+                .await?;
+        }
+        self.idle.mark_resumed(thread_id);
+        Ok(())
+    }
+
+    fn note_process_started(&self, generation: u64) {
+        if self.idle.process_started(generation) {
+            tracing::info!("App server started again after an idle stop");
+        }
+        self.idle.touch(Instant::now());
+    }
+
+    /// Whether a turn or approval still needs the app-server
+    fn has_pending_work(&self) -> bool {
+        !self.active_turns.list().is_empty() || !self.approvals.pending.list().is_empty()
+    }
+
+    /// Stop the app-server if it is still unused, marking the stop as
+    /// intentional so the supervisor does not restart it
+    async fn stop_idle_app_server(&self, minutes: u32) {
+        let _guard = self.restart_lock.lock().await;
+        let mut server = self.app_server.write().await;
+        let Some(process) = server.as_mut().filter(|process| process.is_running()) else {
+            return;
+        };
+        // Turns reserve their slot before waking the server, so one that is
+        // about to start shows up here
+        if self.has_pending_work() || process.pending_request_count().await > 0 {
+            self.idle.touch(Instant::now());
+            return;
+        }
+
+        tracing::info!("Stopping app server after {} idle minutes", minutes);
+        self.idle.mark_stopped();
+        if let Some(mut process) = server.take() {
+            if let Err(err) = process.shutdown().await {
+                tracing::warn!("Failed to stop idle app server: {}", err);
+            }
+        }
+        drop(server);
+
+        self.global_state.update(|state| {
+            state.app_server.idle_stop_count += 1;
+            state.app_server.last_idle_stop_at = Some(unix_timestamp_secs());
+        });
+        self.events
+            .emit("app-server-idle-stopped", json!({ "idleMinutes": minutes }))
+            .await;
+    }
+
+    /// Add a completed item's file changes to its session's live diff and
+    /// emit `live-diff-updated`
+    ///
+    /// Files written by commands are diffed against `HEAD` off the
+    /// supervisor loop, since that runs git.
+    fn track_live_diff(&self, thread_id: &str, item: &serde_json::Value) {
+        let item_type = item.get("type").and_then(|value| value.as_str());
+        if item_type == Some("fileChange") {
+            let now = unix_timestamp_millis();
+            let Some(update) = self.live_diffs.apply_file_change(thread_id, item, now) else {
+                return;
+            };
+            let events = self.events.clone();
+            tauri::async_runtime::spawn(async move {
+                events.emit("live-diff-updated", update).await;
+            });
+            return;
+        }
+
+        if item_type != Some("commandExecution")
+            || item.get("status").and_then(|value| value.as_str()) != Some("completed")
+        {
+            return;
+        }
+        let Some(cwd) = item.get("cwd").and_then(|value| value.as_str()) else {
+            return;
+        };
+        let paths = crate::approvals::command_text(item.get("command"))
+            .map(|command| crate::approvals::written_paths(&command))
+            .unwrap_or_default();
+        if paths.is_empty() {
+            return;
+        }
+        let cwd = std::path::PathBuf::from(cwd);
+        let thread_id = thread_id.to_string();
+        let live_diffs = self.live_diffs.clone();
+        let events = self.events.clone();
+        tauri::async_runtime::spawn(async move {
+            let diffs = match crate::utils::spawn_blocking_io(move || {
+                Ok(live_diff::command_file_diffs(&cwd, &paths))
+            })
+            .await
+            {
+                Ok(diffs) => diffs,
+                Err(err) => {
+                    tracing::warn!("Failed to diff files written by a command: {}", err);
+                    return;
+                }
+            };
+            if let Some(update) =
+                live_diffs.record_command_writes(&thread_id, diffs, unix_timestamp_millis())
+            {
+                events.emit("live-diff-updated", update).await;
+            }
+        });
+    }
+
+    /// Mark a session running and emit `session-status-changed`
+    fn mark_session_running(&self, thread_id: &str) {
+        let database = self.database.clone();
+        let events = self.events.clone();
+        let thread_id = thread_id.to_string();
+        tauri::async_runtime::spawn(async move {
+            let session_id = thread_id.clone();
+            let exists = crate::utils::spawn_blocking_io(move || {
+                database.update_session_status(&session_id, &SessionStatus::Running)
+            })
+            .await;
+            match exists {
+                Ok(true) => {
+                    events
+                        .emit(
+                            "session-status-changed",
+                            json!({
+                                "sessionId": thread_id,
+                                "status": SessionStatus::Running,
+                                "outcome": null,
+                                "turnId": null,
+                                "error": null,
+                            }),
+                        )
+                        .await;
+                }
+                Ok(false) => {}
+                Err(err) => tracing::warn!("Failed to mark session running: {}", err),
+            }
+        });
+    }
+
+    /// Store a finished turn's outcome, roll it up into the session's status
+    /// and emit `session-status-changed`
+    fn record_turn_outcome(&self, record: TurnOutcomeRecord) {
+        tracing::info!(
+            "Turn {} on thread {} ended: {}",
+            record.turn_id,
+            record.session_id,
+            record.outcome.as_str()
+        );
+        let database = self.database.clone();
+        let events = self.events.clone();
+        tauri::async_runtime::spawn(async move {
+            let stored = record.clone();
+            let status =
+                crate::utils::spawn_blocking_io(move || database.record_turn_outcome(&stored))
+                    .await;
+            match status {
+                Ok(Some(status)) => {
+                    events
+                        .emit(
+                            "session-status-changed",
+                            json!({
+                                "sessionId": record.session_id,
+                                "status": status,
+                                "outcome": record.outcome,
+                                "turnId": record.turn_id,
+                                "error": record.error,
+                            }),
+                        )
+                        .await;
+                }
+                Ok(None) => {}
+                Err(err) => tracing::warn!("Failed to record turn outcome: {}", err),
+            }
+        });
+    }
+
+    /// Interrupt a turn that crossed its token or cost ceiling and emit
+    /// `turn-budget-exceeded`
+    async fn stop_over_budget_turn(&self, exceeded: TurnBudgetExceeded) {
+        tracing::info!(
+            "Turn {} on thread {} exceeded its {} budget; interrupting",
+            exceeded.turn_id,
+            exceeded.thread_id,
+            exceeded.limit
+        );
+        let params = TurnInterruptParams {
+            thread_id: exceeded.thread_id.clone(),
+            turn_id: Some(exceeded.turn_id.clone()),
+        };
+        let result: Result<serde_json::Value> = {
+            let server = self.app_server.read().await;
+            match server.as_ref() {
+                Some(process) => process.send_request("turn/interrupt", params).await,
+                None => Err(crate::Error::AppServer("App server not running".to_string())),
+            }
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to interrupt over-budget turn: {}", err);
+        }
+        self.events.emit("turn-budget-exceeded", exceeded).await;
+    }
+
+    /// Accept an approval request the project's approval preset allows
+    ///
+    /// Emits `approval-auto-approved`; if the answer cannot be sent, the
+    /// request goes to the renderer as usual so the user can answer it.
+    async fn auto_approve(&self, request_id: u64, reason: String, mut params: serde_json::Value) {
+        let result = {
+            let server = self.app_server.read().await;
+            match server.as_ref() {
+                Some(process) => {
+                    let response = ApprovalResponseResult {
+                        decision: ApprovalDecision::Accept,
+                    };
+                    process.send_response(request_id, response).await
+                }
+                None => Err(crate::Error::AppServer("App server not running".to_string())),
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                tracing::info!("Auto-approved request {}: {}", request_id, reason);
+                if let serde_json::Value::Object(map) = &mut params {
+                    map.insert("_autoApprovalReason".to_string(), json!(reason));
+                }
+                self.events.emit_json("approval-auto-approved", params).await;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to auto-approve request {}: {}", request_id, err);
+                let approval = PendingApproval::from_request(
+                    request_id,
+                    "item/commandExecution/requestApproval",
+                    &params,
+                );
+                self.notify_approval_requested(&approval);
+                self.approvals.pending.record(approval);
+                self.events
+                    .emit_json("item-commandExecution-requestApproval", params)
+                    .await;
+            }
+        }
+    }
+
+    /// Notify that an approval request waits on the user
+    fn notify_approval_requested(&self, approval: &PendingApproval) {
+        let body = approval
+            .summary
+            .clone()
+            .unwrap_or_else(|| i18n::t("notification.approval_requested.body", &[]));
+        self.notifier.notify(
+            NotificationKind::ApprovalRequested,
+            &i18n::t("notification.approval_requested.title", &[]),
+            &body,
+        );
+    }
+
+    /// Start a turn with the next queued follow-up for a session, if any
+    async fn dispatch_next_followup(&self, thread_id: String) {
+        let Some(followup) = self.followups.pop(&thread_id) else {
+            return;
+        };
+
+        let params = TurnStartParams {
+            thread_id: thread_id.clone(),
+            input: vec![UserInput::Text {
+                text: followup.prompt.clone(),
+            }],
+            effort: None,
+            summary: None,
+            cwd: None,
+            approval_policy: None,
+            sandbox_policy: None,
+            model: None,
+        };
+
+        let result = self.send_turn_start(&params, unix_timestamp_millis()).await;
+
+        match result {
+            Ok(response) => {
+                session_preview::record_turn_started(&self.database, &params);
+                tracing::info!(
+                    "Dispatched follow-up {} for session {} (turn {})",
+                    followup.id,
+                    thread_id,
+                    response.turn.id
+                );
+                self.events
+                    .emit(
+                        "followup-dispatched",
+                        json!({
+                            "sessionId": thread_id,
+                            "followupId": followup.id,
+                            "turnId": response.turn.id,
+                        }),
+                    )
+                    .await;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to dispatch follow-up for session {}: {}", thread_id, err);
+                let followup_id = followup.id.clone();
+                self.followups.requeue_front(&thread_id, followup);
+                self.events
+                    .emit(
+                        "followup-failed",
+                        json!({
+                            "sessionId": thread_id,
+                            "followupId": followup_id,
+                            "error": err.to_string(),
+                        }),
+                    )
+                    .await;
+            }
+        }
+    }
+
+    /// Start the next due entry of every idle, unpaused project queue
+    ///
+    /// An entry whose thread is busy with another turn waits for that turn
+    /// to complete. An entry that fails to start is marked failed and
+    /// pauses its project's queue.
+    async fn dispatch_queued_turns(&self) {
+        let _guard = self.turn_queue.lock_dispatch().await;
+        let scheduler = self.global_state.read(|state| state.scheduler.clone());
+        if scheduler.paused {
+            return;
+        }
+        let now_ms = unix_timestamp_millis();
+        let projects = match self.database.projects_with_due_turns(now_ms) {
+            Ok(projects) => projects,
+            Err(err) => {
+                tracing::warn!("Failed to read turn queues: {}", err);
+                return;
+            }
+        };
+        let due: Vec<_> = projects
+            .into_iter()
+            .filter(|project_id| !scheduler.paused_projects.contains(project_id))
+            .filter_map(|project_id| {
+                self.database
+                    .next_due_turn(&project_id, now_ms)
+                    .ok()
+                    .flatten()
+            })
+            .filter(|entry| self.active_turns.get(&entry.thread_id).is_none())
+            .collect();
+        if due.is_empty() {
+            return;
+        }
+        if let Err(err) = self.start_app_server().await {
+            tracing::warn!("Turn queue waiting for the app server: {}", err);
+            return;
+        }
+
+        for entry in due {
+            let params = TurnStartParams {
+                thread_id: entry.thread_id.clone(),
+                input: vec![UserInput::Text {
+                    text: entry.prompt.clone(),
+                }],
+                effort: None,
+                summary: None,
+                cwd: None,
+                approval_policy: None,
+                sandbox_policy: None,
+                model: None,
+            };
+            let now_ms = unix_timestamp_millis();
+            let result = match self.send_turn_start(&params, now_ms).await {
+                Ok(response) => {
+                    session_preview::record_turn_started(&self.database, &params);
+                    tracing::info!(
+                        "Started queued turn {} on thread {} (turn {})",
+                        entry.id,
+                        entry.thread_id,
+                        response.turn.id
+                    );
+                    self.database
+                        .mark_queued_turn_running(&entry.id, &response.turn.id, now_ms)
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to start queued turn {}: {}", entry.id, err);
+                    let reason = err.to_string();
+                    self.pause_turn_queue(&entry.project_id, &reason).await;
+                    self.database
+                        .finish_queued_turn(&entry.id, "failed", Some(&reason), now_ms)
+                }
+            };
+            if let Err(err) = result {
+                tracing::warn!("Failed to update queued turn {}: {}", entry.id, err);
+            }
+            self.emit_queued_turn(&entry.id).await;
+        }
+    }
+
+    /// Record the outcome of a queued entry whose turn finished on `thread_id`
+    async fn finish_queued_turn(&self, thread_id: &str, turn_status: &str) {
+        let entry = match self.database.running_queued_turn(thread_id) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::warn!("Failed to read turn queue: {}", err);
+                return;
+            }
+        };
+        let status = turn_queue::finished_status(turn_status);
+        let error = (status != "completed").then(|| format!("Turn {turn_status}"));
+        if let Err(err) = self.database.finish_queued_turn(
+            &entry.id,
+            status,
+            error.as_deref(),
+            unix_timestamp_millis(),
+        ) {
+            tracing::warn!("Failed to update queued turn {}: {}", entry.id, err);
+        }
+        if let Some(error) = &error {
+            self.pause_turn_queue(&entry.project_id, error).await;
+        }
+        self.emit_queued_turn(&entry.id).await;
+    }
+
+    /// Hold a project's queue back until the user resumes it
+    async fn pause_turn_queue(&self, project_id: &str, reason: &str) {
+        self.global_state.update(|state| {
+            state.scheduler.paused_projects.insert(project_id.to_string());
+        });
+        if let Err(err) = self.global_state.flush() {
+            tracing::warn!("Failed to persist paused turn queue: {}", err);
+        }
+        self.events
+            .emit(
+                "turn-queue-paused",
+                json!({ "projectId": project_id, "reason": reason }),
+            )
+            .await;
+    }
+
+    async fn emit_queued_turn(&self, queued_id: &str) {
+        if let Ok(Some(entry)) = self.database.get_queued_turn(queued_id) {
+            self.events.emit("turn-queue-updated", entry).await;
+        }
+    }
+
+    async fn observe_account(&self, info: &AccountInfo) {
+        let (required, restored) = self.auth.observe_account(info);
+        if let Some(required) = required {
+            self.report_login_required(required).await;
+        }
+        if restored {
+            self.report_login_restored().await;
+        }
+    }
+
+    async fn report_login_required(&self, required: LoginRequired) {
+        tracing::warn!("Login required ({}); prompting to log in again", required.reason);
+        self.events.emit("login-required", required).await;
+    }
+
+    async fn report_login_restored(&self) {
+        tracing::info!("Login restored");
+        self.events
+            .emit("login-restored", json!({ "loginType": self.auth.login_type() }))
+            .await;
+    }
+
+    async fn observe_rate_limits(&self, payload: &serde_json::Value) {
+        let settings = self.global_state.read(|state| state.rate_limits.clone());
+        let crossed = self.rate_limits.observe(payload, unix_timestamp_secs(), &settings);
+        for window in crossed {
+            tracing::warn!(
+                "Rate limit window {} at {:.0}% ({:?})",
+                window.name,
+                window.used_percent,
+                window.level
+            );
+            self.events
+                .emit(
+                    "rate-limit-warning",
+                    json!({
+                        "kind": "threshold",
+                        "window": window,
+                        "queueing": settings.queue_when_limited,
+                    }),
+                )
+                .await;
+        }
+        if self.rate_limits.queued_len() > 0 {
+            let handle = self.clone();
+            tauri::async_runtime::spawn(async move {
+                handle.dispatch_rate_limited_turns().await;
+            });
+        }
+    }
+
+    /// Start held-back turns in order while the limits allow
+    async fn dispatch_rate_limited_turns(&self) {
+        loop {
+            let settings = self.global_state.read(|state| state.rate_limits.clone());
+            if self
+                .rate_limits
+                .limited_until(unix_timestamp_secs(), &settings)
+                .is_some()
+            {
+                return;
+            }
+            let Some(queued) = self.rate_limits.pop() else {
+                return;
+            };
+            // Time held back by the rate limits counts as queued
+            let result = self
+                .send_turn_start(&queued.params, queued.queued_at.saturating_mul(1000))
+                .await;
+
+            match result {
+                Ok(response) => {
+                    session_preview::record_turn_started(&self.database, &queued.params);
+                    tracing::info!(
+                        "Released rate-limited message {} for session {} (turn {})",
+                        queued.id,
+                        queued.params.thread_id,
+                        response.turn.id
+                    );
+                    self.events
+                        .emit(
+                            "rate-limit-queue-released",
+                            json!({
+                                "sessionId": queued.params.thread_id,
+                                "queuedId": queued.id,
+                                "turnId": response.turn.id,
+                            }),
+                        )
+                        .await;
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to release rate-limited message {}: {}", queued.id, err);
+                    let payload = json!({
+                        "sessionId": queued.params.thread_id,
+                        "queuedId": queued.id,
+                        "error": err.to_string(),
+                    });
+                    self.rate_limits.requeue_front(queued);
+                    self.events.emit("rate-limit-queue-failed", payload).await;
+                    return;
+                }
+            }
+        }
+    }
+
     async fn stop_app_server_inner(&self) -> Result<()> {
         let mut server = self.app_server.write().await;
         if let Some(mut process) = server.take() {
@@ -223,12 +1284,36 @@ const APP_SERVER_RESTART_WINDOW_SECS: u64 = 300;
 const APP_SERVER_BACKOFF_BASE_SECS: u64 = 1;
 const APP_SERVER_BACKOFF_MAX_SECS: u64 = 30;
 
+const BACKEND_HEARTBEAT_MIN_SECS: u64 = 1;
+const BACKEND_HEARTBEAT_MAX_SECS: u64 = 300;
+
 const RENDERER_HEARTBEAT_TIMEOUT_SECS: u64 = 20;
 const RENDERER_MONITOR_INTERVAL_SECS: u64 = 5;
 const RENDERER_MAX_RECOVERY_ATTEMPTS: u32 = 3;
 const RENDERER_RECOVERY_BASE_SECS: u64 = 2;
 const RENDERER_RECOVERY_MAX_SECS: u64 = 30;
 
+// Held-back turns are retried at least this often, so they go out once a
+// window's reset time passes even without a new rate-limit notification
+const RATE_LIMIT_QUEUE_CHECK_SECS: u64 = 30;
+
+// Catches logins that lapse while no turn runs to be rejected
+const LOGIN_POLL_INTERVAL_SECS: u64 = 5 * 60;
+
+// First background GC waits out startup I/O, then runs daily
+const GC_INITIAL_DELAY_SECS: u64 = 10 * 60;
+const GC_INTERVAL_SECS: u64 = 24 * 3600;
+
+// How often the idle timeout of the app lock is checked
+const APP_LOCK_CHECK_SECS: u64 = 30;
+
+// How often the app-server is checked for having been idle long enough to stop
+const IDLE_SHUTDOWN_CHECK_SECS: u64 = 30;
+
+// Snapshot compaction starts after the first GC and repeats a few times a day
+const SNAPSHOT_COMPACTION_INITIAL_DELAY_SECS: u64 = 15 * 60;
+const SNAPSHOT_COMPACTION_INTERVAL_SECS: u64 = 6 * 3600;
+
 fn app_server_backoff(attempt: u32) -> Duration {
     let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
     Duration::from_secs((APP_SERVER_BACKOFF_BASE_SECS * factor).min(APP_SERVER_BACKOFF_MAX_SECS))
@@ -244,8 +1329,45 @@ async fn monitor_app_server(mut rx: mpsc::Receiver<AppServerEvent>, handle: AppS
 
     while let Some(event) = rx.recv().await {
         match event {
-            AppServerEvent::Disconnected { reason } => {
+            AppServerEvent::Disconnected { reason, generation } => {
+                // A process replaced since then, e.g. after an idle stop
+                if handle.idle.is_replaced(generation) {
+                    tracing::info!("Earlier app server exited: {}", reason);
+                    continue;
+                }
+                // Stopped on purpose; the next thread command starts it again
+                if handle.idle.is_stopped() {
+                    tracing::info!("Idle app server exited: {}", reason);
+                    continue;
+                }
                 tracing::warn!("App server disconnected: {}", reason);
+                handle.notifier.notify(
+                    NotificationKind::AppServerCrashed,
+                    &i18n::t("notification.app_server_crashed.title", &[]),
+                    &i18n::t("notification.app_server_crashed.body", &[]),
+                );
+                // Turns cut off by the exit never complete; they end in error
+                let completed_at = unix_timestamp_millis();
+                for turn in handle.active_turns.list() {
+                    let Some(turn_id) = turn.turn_id else {
+                        continue;
+                    };
+                    handle.record_turn_outcome(TurnOutcomeRecord {
+                        turn_id,
+                        session_id: turn.thread_id,
+                        outcome: TurnOutcome::Error,
+                        status: "disconnected".to_string(),
+                        error: Some(format!("App server disconnected: {reason}")),
+                        completed_at,
+                    });
+                }
+                handle.turn_timings.clear();
+                handle.turn_budgets.clear();
+                handle.active_turns.clear();
+                handle
+                    .events
+                    .emit("active-turns-changed", handle.active_turns.list())
+                    .await;
                 handle.global_state.update(|state| {
                     state.app_server.last_disconnect_reason = Some(reason.clone());
                 });
@@ -265,6 +1387,10 @@ async fn monitor_app_server(mut rx: mpsc::Receiver<AppServerEvent>, handle: AppS
                             )
                             .await;
                         tracing::warn!("Restart paused: too many restarts in window");
+                        let degraded = handle
+                            .availability
+                            .mark_failed("App server keeps exiting; automatic restarts paused");
+                        handle.events.emit("app-server-unavailable", degraded).await;
                         break;
                     }
 
@@ -284,7 +1410,279 @@ async fn monitor_app_server(mut rx: mpsc::Receiver<AppServerEvent>, handle: AppS
                     }
                 }
             }
+            AppServerEvent::TurnStarted { thread_id, turn_id } => {
+                handle.mark_session_running(&thread_id);
+                handle
+                    .active_turns
+                    .started(&thread_id, &turn_id, unix_timestamp_millis());
+                handle
+                    .events
+                    .emit("active-turns-changed", handle.active_turns.list())
+                    .await;
+                handle
+                    .turn_timings
+                    .turn_started(&thread_id, &turn_id, unix_timestamp_millis());
+                let default_budget = handle.global_state.read(|state| state.turn_budget.clone());
+                handle
+                    .turn_budgets
+                    .turn_started(&thread_id, &turn_id, &default_budget);
+            }
+            AppServerEvent::ItemStarted { thread_id, item } => {
+                handle
+                    .turn_timings
+                    .item_started(&thread_id, &item, unix_timestamp_millis());
+            }
+            AppServerEvent::TurnCompleted {
+                thread_id,
+                turn_id,
+                status,
+                error,
+                approval_pending_since,
+            } => {
+                handle.turn_waiters.complete(&thread_id, &status);
+                handle.idle.touch(Instant::now());
+                match status.as_str() {
+                    "completed" => handle.notifier.notify(
+                        NotificationKind::TurnCompleted,
+                        &i18n::t("notification.turn_completed.title", &[]),
+                        &i18n::t("notification.turn_completed.body", &[]),
+                    ),
+                    "failed" => handle.notifier.notify(
+                        NotificationKind::TurnFailed,
+                        &i18n::t("notification.turn_failed.title", &[]),
+                        &error
+                            .clone()
+                            .unwrap_or_else(|| i18n::t("notification.turn_failed.body", &[])),
+                    ),
+                    // Interrupted by the user
+                    _ => {}
+                }
+                let completed_at = unix_timestamp_millis();
+                let approval_wait_ms = approval_pending_since.map(|since| completed_at - since);
+                handle.record_turn_outcome(TurnOutcomeRecord {
+                    turn_id: turn_id.clone(),
+                    session_id: thread_id.clone(),
+                    outcome: TurnOutcome::classify(&status, error.as_deref(), approval_wait_ms),
+                    status: status.clone(),
+                    error,
+                    completed_at,
+                });
+                handle.turn_budgets.turn_completed(&thread_id);
+                // Freed before follow-ups below start the thread's next turn
+                if handle.active_turns.release(&thread_id) {
+                    handle
+                        .events
+                        .emit("active-turns-changed", handle.active_turns.list())
+                        .await;
+                }
+                let timings = handle
+                    .turn_timings
+                    .turn_completed(&thread_id, &status, unix_timestamp_millis())
+                    .filter(|timings| turn_id.is_empty() || timings.turn_id == turn_id);
+                if let Some(timings) = &timings {
+                    handle.events.emit("turn-timings", timings).await;
+                }
+                let database = handle.database.clone();
+                let finished_id = thread_id.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    if let Err(err) = database.record_turn_finished(&finished_id) {
+                        tracing::warn!("Failed to record turn duration: {}", err);
+                    }
+                    if let Some(timings) = timings {
+                        if let Err(err) = database.insert_turn_timings(&timings) {
+                            tracing::warn!("Failed to record turn timings: {}", err);
+                        }
+                    }
+                });
+                // Queue entries run regardless of how this turn went; a
+                // failed entry only pauses its own project's queue
+                let queue_handle = handle.clone();
+                let queue_thread_id = thread_id.clone();
+                let queue_status = status.clone();
+                tauri::async_runtime::spawn(async move {
+                    queue_handle
+                        .finish_queued_turn(&queue_thread_id, &queue_status)
+                        .await;
+                    queue_handle.dispatch_queued_turns().await;
+                });
+                if status != "completed" {
+                    // Failed or interrupted turns pause the queue until the user acts
+                    if !handle.followups.list(&thread_id).is_empty() {
+                        handle
+                            .events
+                            .emit(
+                                "followup-queue-paused",
+                                json!({ "sessionId": thread_id, "status": status }),
+                            )
+                            .await;
+                    }
+                    continue;
+                }
+                // Dispatch off the supervisor loop so restarts are never delayed
+                let handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    handle.dispatch_next_followup(thread_id).await;
+                });
+            }
+            AppServerEvent::ItemCompleted { thread_id, item } => {
+                handle
+                    .turn_timings
+                    .item_completed(&thread_id, &item, unix_timestamp_millis());
+                handle.track_live_diff(&thread_id, &item);
+                let preview = session_preview::message_text(&item)
+                    .and_then(|text| session_preview::preview_text(&text));
+                let files_changed = session_preview::files_changed(&item);
+                let database = handle.database.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    if preview.is_some() || files_changed > 0 {
+                        if let Err(err) = database.record_session_item(
+                            &thread_id,
+                            preview.as_deref(),
+                            files_changed,
+                        ) {
+                            tracing::warn!("Failed to update session preview: {}", err);
+                        }
+                    }
+                    session_stats::record_item(&database, &thread_id, &item);
+
+                    let content = crate::handoff::render_transcript(std::slice::from_ref(&item));
+                    if !content.is_empty() {
+                        let content = crate::secret_mask::mask(&content);
+                        if let Err(err) = database.append_session_content(&thread_id, &content) {
+                            tracing::warn!("Failed to index session content for search: {}", err);
+                        }
+                    }
+                });
+            }
+            AppServerEvent::TokenUsageUpdated {
+                thread_id,
+                turn_id,
+                usage,
+            } => {
+                let handle = handle.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    let database = &handle.database;
+                    session_stats::record_token_usage(database, &thread_id, &usage);
+                    let Some((last, cost)) =
+                        usage::record_model_call(database, &thread_id, &turn_id, &usage)
+                    else {
+                        return;
+                    };
+                    if let Some(exceeded) = handle
+                        .turn_budgets
+                        .record_usage(&thread_id, &turn_id, &last, cost)
+                    {
+                        tauri::async_runtime::spawn(async move {
+                            handle.stop_over_budget_turn(exceeded).await;
+                        });
+                    }
+                });
+            }
+            AppServerEvent::RateLimitsUpdated { rate_limits } => {
+                handle.observe_rate_limits(&rate_limits).await;
+            }
+            AppServerEvent::Unauthorized { thread_id, message } => {
+                if let Some(required) = handle.auth.unauthorized(thread_id, message) {
+                    handle.report_login_required(required).await;
+                }
+            }
+            AppServerEvent::LoginStateChanged {
+                logged_in: true,
+                login_type,
+            } => {
+                if handle.auth.logged_in(login_type.as_deref()) {
+                    handle.report_login_restored().await;
+                }
+            }
+            AppServerEvent::LoginStateChanged {
+                logged_in: false, ..
+            } => {
+                if let Some(required) = handle.auth.logged_out() {
+                    handle.report_login_required(required).await;
+                }
+            }
+            AppServerEvent::ApprovalRequested { approval } => {
+                handle.notify_approval_requested(&approval);
+            }
+            AppServerEvent::ApprovalAutoApproved {
+                request_id,
+                reason,
+                params,
+            } => {
+                // Answered off the supervisor loop so a busy stdin cannot stall it
+                let handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    handle.auto_approve(request_id, reason, params).await;
+                });
+            }
+        }
+    }
+}
+
+/// Clamp a configured heartbeat interval to the supported range
+pub(crate) fn clamp_heartbeat_interval(secs: u64) -> u64 {
+    secs.clamp(BACKEND_HEARTBEAT_MIN_SECS, BACKEND_HEARTBEAT_MAX_SECS)
+}
+
+/// Everything the backend heartbeat reports on
+struct BackendHeartbeatSources {
+    handle: AppStateHandle,
+    watchers: Arc<WatcherManager>,
+}
+
+/// Periodically emit `backend-heartbeat` so the renderer can detect a wedged backend.
+///
+/// The app-server lock is only probed with `try_write`: a request in flight
+/// holds it, so a lock that stays busy across beats is itself the signal.
+async fn emit_backend_heartbeats(sources: BackendHeartbeatSources) {
+    let handle = &sources.handle;
+    let mut seq: u64 = 0;
+
+    loop {
+        let settings = handle.global_state.read(|state| state.backend_heartbeat.clone());
+        tokio::time::sleep(Duration::from_secs(clamp_heartbeat_interval(settings.interval_secs))).await;
+        if !settings.enabled || !handle.events.is_ready() {
+            continue;
         }
+
+        let (app_server_status, pending_requests) = match handle.app_server.try_write() {
+            Ok(mut server) => match server.as_mut() {
+                Some(process) if process.is_running() => {
+                    ("running", Some(process.pending_request_count().await))
+                }
+                Some(_) => ("exited", None),
+                None => ("stopped", None),
+            },
+            Err(_) => ("busy", None),
+        };
+        let restart_count = handle.global_state.read(|state| state.app_server.restart_count);
+
+        seq += 1;
+        handle
+            .events
+            .emit(
+                "backend-heartbeat",
+                json!({
+                    "seq": seq,
+                    "timestamp": unix_timestamp_millis(),
+                    "intervalSecs": clamp_heartbeat_interval(settings.interval_secs),
+                    "appServer": {
+                        "status": app_server_status,
+                        "restartCount": restart_count,
+                    },
+                    "queues": {
+                        "pendingEvents": handle.events.pending_len().await,
+                        "followups": handle.followups.total_len(),
+                        "rateLimited": handle.rate_limits.queued_len(),
+                    },
+                    "activeOperations": {
+                        "pendingRequests": pending_requests,
+                        "turnWaiters": handle.turn_waiters.pending_count(),
+                        "watchedProjects": sources.watchers.watched_count(),
+                    },
+                }),
+            )
+            .await;
     }
 }
 
@@ -346,3 +1744,156 @@ async fn monitor_renderer(
             .await;
     }
 }
+
+/// Start queued turns whose `run_after` time has come; entries left running
+/// by a previous run of the app are failed first
+async fn run_turn_queue_schedule(handle: AppStateHandle) {
+    match handle
+        .database
+        .fail_interrupted_queued_turns(unix_timestamp_millis())
+    {
+        Ok(projects) => {
+            for project_id in projects {
+                handle
+                    .pause_turn_queue(&project_id, "Interrupted by an app restart")
+                    .await;
+            }
+        }
+        Err(err) => tracing::warn!("Failed to reset interrupted queued turns: {}", err),
+    }
+    let tasks = handle.background_tasks.clone();
+    loop {
+        tokio::time::sleep(tasks.interval(Duration::from_secs(turn_queue::SCHEDULE_CHECK_SECS)))
+            .await;
+        let _permit = tasks.start(background_tasks::TURN_QUEUE_SCHEDULE).await;
+        handle.dispatch_queued_turns().await;
+    }
+}
+
+async fn release_rate_limited_turns(handle: AppStateHandle) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RATE_LIMIT_QUEUE_CHECK_SECS));
+    loop {
+        interval.tick().await;
+        if handle.rate_limits.queued_len() > 0 {
+            handle.dispatch_rate_limited_turns().await;
+        }
+    }
+}
+
+/// Periodically read the account from a running app-server so an expired
+/// or removed login is noticed before the next turn fails on it
+async fn poll_login_state(handle: AppStateHandle) {
+    let tasks = handle.background_tasks.clone();
+    loop {
+        tokio::time::sleep(tasks.interval(Duration::from_secs(LOGIN_POLL_INTERVAL_SECS))).await;
+        let _permit = tasks.start(background_tasks::LOGIN_POLL).await;
+        let result: Option<Result<AccountInfo>> = {
+            let server = handle.app_server.read().await;
+            match server.as_ref() {
+                Some(process) if process.is_running() => {
+                    Some(process.send_request("account/read", json!({})).await)
+                }
+                _ => None,
+            }
+        };
+        match result {
+            Some(Ok(info)) => handle.observe_account(&info).await,
+            Some(Err(err)) => tracing::debug!("Login state poll failed: {}", err),
+            None => {}
+        }
+    }
+}
+
+/// Startup check of the codex CLI version; the frontend is told how to
+/// upgrade when it is outside the supported range
+async fn check_codex_version(global_state: Arc<GlobalStateStore>, events: AppEventEmitter) {
+    let launch = global_state.read(|state| state.app_server_launch.clone());
+    let check = crate::app_server::compat::check(&launch).await;
+    if check.compatible {
+        tracing::info!("{}", check.message);
+        return;
+    }
+    tracing::warn!("{}", check.message);
+    events.emit("codex-version-incompatible", check).await;
+}
+
+async fn run_periodic_gc(database: Arc<Database>, tasks: Arc<BackgroundTasks>) {
+    tokio::time::sleep(tasks.interval(Duration::from_secs(GC_INITIAL_DELAY_SECS))).await;
+    loop {
+        let permit = tasks.start(background_tasks::PERIODIC_GC).await;
+        let db = database.clone();
+        if let Err(err) = crate::utils::spawn_blocking_io(move || crate::gc::run(&db)).await {
+            tracing::warn!("Background GC failed: {}", err);
+        }
+        drop(permit);
+        tokio::time::sleep(tasks.interval(Duration::from_secs(GC_INTERVAL_SECS))).await;
+    }
+}
+
+/// Lock the app once the user has been idle for the configured timeout
+async fn run_app_lock_timer(
+    app_lock: Arc<AppLock>,
+    global_state: Arc<GlobalStateStore>,
+    events: AppEventEmitter,
+) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(APP_LOCK_CHECK_SECS)).await;
+        let settings = global_state.read(|state| state.app_lock.clone());
+        if app_lock.idle_expired(&settings) && app_lock.lock() {
+            events.hold();
+            tracing::info!(
+                "Locked the app after {} idle minutes",
+                settings.idle_timeout_mins.unwrap_or_default()
+            );
+            events.emit("app-lock-changed", app_lock.status(settings)).await;
+        }
+    }
+}
+
+async fn run_idle_shutdown(handle: AppStateHandle) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(IDLE_SHUTDOWN_CHECK_SECS)).await;
+        let minutes = handle
+            .global_state
+            .read(|state| state.app_server_launch.idle_shutdown_mins);
+        if handle.has_pending_work() {
+            handle.idle.touch(Instant::now());
+            continue;
+        }
+        if handle.idle.is_due(minutes, Instant::now()) {
+            handle.stop_idle_app_server(minutes.unwrap_or_default()).await;
+        }
+    }
+}
+
+async fn run_snapshot_compaction(
+    database: Arc<Database>,
+    tasks: Arc<BackgroundTasks>,
+    snapshots_dir: std::path::PathBuf,
+) {
+    tokio::time::sleep(tasks.interval(Duration::from_secs(
+        SNAPSHOT_COMPACTION_INITIAL_DELAY_SECS,
+    )))
+    .await;
+    loop {
+        let permit = tasks.start(background_tasks::SNAPSHOT_COMPACTION).await;
+        let db = database.clone();
+        let dir = snapshots_dir.clone();
+        match crate::utils::spawn_blocking_io(move || crate::snapshots::compact(&db, &dir)).await {
+            Ok(report) if report.bytes_freed > 0 || report.snapshots_migrated > 0 => {
+                tracing::info!(
+                    "Compacted snapshots: {} migrated, {} bytes freed",
+                    report.snapshots_migrated,
+                    report.bytes_freed
+                );
+            }
+            Ok(_) => {}
+            Err(err) => tracing::warn!("Snapshot compaction failed: {}", err),
+        }
+        drop(permit);
+        tokio::time::sleep(tasks.interval(Duration::from_secs(
+            SNAPSHOT_COMPACTION_INTERVAL_SECS,
+        )))
+        .await;
+    }
+}
@@ -0,0 +1,209 @@
+//! Terminal command output on its way to the renderer.
+//!
+//! Each line is masked and cut to `TerminalOutputSettings::max_line_chars`
+//! before it goes out as `terminal:stdout` or `terminal:stderr`, so a
+//! single megabyte-long line cannot freeze the webview. The whole lines of
+//! recent runs stay in a bounded scrollback the renderer pages through.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::global_state::TerminalOutputSettings;
+use crate::{Error, Result};
+
+/// Shortest `max_line_chars` accepted
+const MIN_LINE_CHARS: u32 = 80;
+
+/// Runs kept in the scrollback; older ones are forgotten
+const MAX_RUNS: usize = 20;
+
+/// Bytes of output kept per run; the oldest lines are dropped past this
+const MAX_RUN_BYTES: usize = 16 * 1024 * 1024;
+
+pub const DEFAULT_PAGE_LINES: usize = 500;
+pub const MAX_PAGE_LINES: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TerminalStream {
+    Stdout,
+    Stderr,
+}
+
+impl TerminalStream {
+    pub fn event(self) -> &'static str {
+        match self {
+            Self::Stdout => "terminal:stdout",
+            Self::Stderr => "terminal:stderr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackLine {
+    pub stream: TerminalStream,
+    pub text: String,
+}
+
+/// A slice of one run's scrollback
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrollbackPage {
+    pub run_id: String,
+    /// Index of the first line in `lines` among the kept lines
+    pub offset: usize,
+    pub lines: Vec<ScrollbackLine>,
+    /// Lines kept for the run
+    pub total_lines: usize,
+    /// Oldest lines dropped once the run passed `MAX_RUN_BYTES`
+    pub dropped_lines: usize,
+    /// The command has exited
+    pub finished: bool,
+}
+
+pub fn validate_settings(settings: &TerminalOutputSettings) -> Result<()> {
+    if settings.max_line_chars < MIN_LINE_CHARS {
+        return Err(Error::Other(format!(
+            "Maximum line length must be at least {MIN_LINE_CHARS} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// `line` masked as configured
+pub fn redact<'a>(line: &'a str, settings: &TerminalOutputSettings) -> Cow<'a, str> {
+    if settings.redact_secrets {
+        crate::secret_mask::mask(line)
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// `line` cut to `max_chars` characters, saying how many were left out
+pub fn truncate_line(line: &str, max_chars: usize) -> Cow<'_, str> {
+    match line.char_indices().nth(max_chars) {
+        None => Cow::Borrowed(line),
+        Some((at, _)) => {
+            let omitted = line[at..].chars().count();
+            Cow::Owned(format!(
+                "{}… [{omitted} more characters in scrollback]",
+                &line[..at]
+            ))
+        }
+    }
+}
+
+struct Run {
+    id: String,
+    lines: VecDeque<ScrollbackLine>,
+    bytes: usize,
+    dropped: usize,
+    finished: bool,
+}
+
+/// Whole output lines of the most recent terminal runs
+#[derive(Default)]
+pub struct TerminalScrollback {
+    runs: Mutex<VecDeque<Run>>,
+}
+
+impl TerminalScrollback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start recording a run; returns its id
+    pub fn start(&self) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut runs = self.runs.lock();
+        if runs.len() == MAX_RUNS {
+            runs.pop_front();
+        }
+        runs.push_back(Run {
+            id: id.clone(),
+            lines: VecDeque::new(),
+            bytes: 0,
+            dropped: 0,
+            finished: false,
+        });
+        id
+    }
+
+    pub fn push(&self, run_id: &str, stream: TerminalStream, text: String) {
+        let mut runs = self.runs.lock();
+        let Some(run) = runs.iter_mut().find(|run| run.id == run_id) else {
+            return;
+        };
+        run.bytes += text.len();
+        run.lines.push_back(ScrollbackLine { stream, text });
+        while run.bytes > MAX_RUN_BYTES && run.lines.len() > 1 {
+            if let Some(line) = run.lines.pop_front() {
+                run.bytes -= line.text.len();
+                run.dropped += 1;
+            }
+        }
+    }
+
+    pub fn finish(&self, run_id: &str) {
+        if let Some(run) = self.runs.lock().iter_mut().find(|run| run.id == run_id) {
+            run.finished = true;
+        }
+    }
+
+    /// Up to `limit` lines of a run from `offset`; `None` for an unknown or
+    /// forgotten run
+    pub fn page(&self, run_id: &str, offset: usize, limit: usize) -> Option<ScrollbackPage> {
+        let runs = self.runs.lock();
+        let run = runs.iter().find(|run| run.id == run_id)?;
+        Some(ScrollbackPage {
+            run_id: run.id.clone(),
+            offset,
+            lines: run.lines.iter().skip(offset).take(limit).cloned().collect(),
+            total_lines: run.lines.len(),
+            dropped_lines: run.dropped,
+            finished: run.finished,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_line() {
+        assert_eq!(truncate_line("short", 10), "short");
+        assert_eq!(truncate_line("exactly10!", 10), "exactly10!");
+        assert_eq!(
+            truncate_line("ééééé-tail", 5),
+            "ééééé… [5 more characters in scrollback]"
+        );
+    }
+
+    #[test]
+    fn test_scrollback_pages_and_limits() {
+        let scrollback = TerminalScrollback::new();
+        let run_id = scrollback.start();
+        for i in 0..5 {
+            scrollback.push(&run_id, TerminalStream::Stdout, format!("line {i}"));
+        }
+        scrollback.push(&run_id, TerminalStream::Stderr, "x".repeat(MAX_RUN_BYTES));
+        scrollback.finish(&run_id);
+
+        // The huge line pushed every earlier one out, but is kept whole
+        let page = scrollback.page(&run_id, 0, 10).unwrap();
+        assert_eq!((page.total_lines, page.dropped_lines), (1, 5));
+        assert_eq!(page.lines[0].stream, TerminalStream::Stderr);
+        assert_eq!(page.lines[0].text.len(), MAX_RUN_BYTES);
+        assert!(page.finished);
+
+        for _ in 0..MAX_RUNS {
+            scrollback.start();
+        }
+        assert!(scrollback.page(&run_id, 0, 10).is_none());
+    }
+}
@@ -0,0 +1,458 @@
+//! Render a session's thread items to Markdown, standalone HTML or JSON.
+//!
+//! Items are first reduced to `Entry` values so Markdown and HTML stay in
+//! step; JSON keeps the raw items untouched.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Output format of `export_session`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+/// Session details shown in the export header
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMeta {
+    pub session_id: String,
+    pub title: String,
+    pub cwd: String,
+    pub model: Option<String>,
+    /// Unix timestamp of the export
+    pub exported_at: i64,
+}
+
+struct FileDiff {
+    path: String,
+    kind: String,
+    diff: String,
+}
+
+enum Entry {
+    User { text: String, images: usize },
+    Agent(String),
+    Reasoning(Vec<String>),
+    Command {
+        command: String,
+        exit_code: Option<i64>,
+        output: String,
+        status: Option<String>,
+    },
+    FileChange {
+        changes: Vec<FileDiff>,
+        status: Option<String>,
+    },
+    Tool {
+        name: String,
+        arguments: String,
+        result: Option<String>,
+        error: Option<String>,
+    },
+    WebSearch(String),
+    Review(String),
+}
+
+/// Render `items` in `format`
+pub fn render(format: ExportFormat, meta: &TranscriptMeta, items: &[JsonValue]) -> crate::Result<String> {
+    Ok(match format {
+        ExportFormat::Markdown => render_markdown(meta, &entries(items)),
+        ExportFormat::Html => render_html(meta, &entries(items)),
+        ExportFormat::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "session": meta,
+            "items": items,
+        }))?,
+    })
+}
+
+fn entries(items: &[JsonValue]) -> Vec<Entry> {
+    items.iter().filter_map(entry).collect()
+}
+
+fn str_field(item: &JsonValue, key: &str) -> String {
+    item.get(key).and_then(JsonValue::as_str).unwrap_or_default().to_string()
+}
+
+/// Item status when it is worth calling out (declined, failed, ...)
+fn notable_status(item: &JsonValue) -> Option<String> {
+    item.get("status")
+        .and_then(JsonValue::as_str)
+        .filter(|status| *status != "completed")
+        .map(str::to_string)
+}
+
+fn entry(item: &JsonValue) -> Option<Entry> {
+    let entry = match item.get("type")?.as_str()? {
+        "userMessage" => {
+            let content = item.get("content")?.as_array()?;
+            let text: Vec<&str> = content
+                .iter()
+                .filter_map(|part| part.get("text").and_then(JsonValue::as_str))
+                .collect();
+            let images = content
+                .iter()
+                .filter(|part| {
+                    matches!(
+                        part.get("type").and_then(JsonValue::as_str),
+                        Some("image" | "localImage")
+                    )
+                })
+                .count();
+            Entry::User {
+                text: text.join("\n"),
+                images,
+            }
+        }
+        "agentMessage" => Entry::Agent(str_field(item, "text")),
+        "reasoning" => {
+            let summary: Vec<String> = item
+                .get("summary")?
+                .as_array()?
+                .iter()
+                .filter_map(|point| point.as_str().map(str::to_string))
+                .collect();
+            if summary.is_empty() {
+                return None;
+            }
+            Entry::Reasoning(summary)
+        }
+        "commandExecution" => Entry::Command {
+            command: match item.get("command") {
+                Some(JsonValue::Array(parts)) => parts
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => str_field(item, "command"),
+            },
+            exit_code: item.get("exitCode").and_then(JsonValue::as_i64),
+            output: str_field(item, "aggregatedOutput"),
+            status: notable_status(item),
+        },
+        "fileChange" => Entry::FileChange {
+            changes: item
+                .get("changes")?
+                .as_array()?
+                .iter()
+                .map(|change| FileDiff {
+                    path: str_field(change, "path"),
+                    kind: match change.get("kind") {
+                        Some(JsonValue::String(kind)) => kind.to_lowercase(),
+                        Some(kind) => str_field(kind, "type"),
+                        None => "update".to_string(),
+                    },
+                    diff: str_field(change, "diff"),
+                })
+                .collect(),
+            status: notable_status(item),
+        },
+        "mcpToolCall" => Entry::Tool {
+            name: format!("{}/{}", str_field(item, "server"), str_field(item, "tool")),
+            arguments: item
+                .get("arguments")
+                .map(|args| serde_json::to_string_pretty(args).unwrap_or_default())
+                .unwrap_or_default(),
+            result: item
+                .get("result")
+                .filter(|result| !result.is_null())
+                .map(|result| serde_json::to_string_pretty(result).unwrap_or_default()),
+            error: item.get("error").filter(|error| !error.is_null()).map(|error| {
+                error
+                    .get("message")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| error.to_string())
+            }),
+        },
+        "webSearch" => Entry::WebSearch(str_field(item, "query")),
+        "enteredReviewMode" | "exitedReviewMode" => Entry::Review(str_field(item, "review")),
+        _ => return None,
+    };
+    Some(entry)
+}
+
+/// A fence longer than any backtick run in `text`
+fn fence(text: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+pub(crate) fn code_block(lang: &str, text: &str) -> String {
+    let fence = fence(text);
+    format!("{fence}{lang}\n{}\n{fence}\n\n", text.trim_end())
+}
+
+fn header_lines(meta: &TranscriptMeta) -> Vec<(&'static str, String)> {
+    let mut lines = vec![("Session", meta.session_id.clone()), ("Project", meta.cwd.clone())];
+    if let Some(model) = &meta.model {
+        lines.push(("Model", model.clone()));
+    }
+    let exported = chrono::DateTime::from_timestamp(meta.exported_at, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    lines.push(("Exported", exported));
+    lines
+}
+
+fn render_markdown(meta: &TranscriptMeta, entries: &[Entry]) -> String {
+    let mut out = format!("# {}\n\n", meta.title);
+    for (label, value) in header_lines(meta) {
+        out.push_str(&format!("- **{label}**: {value}\n"));
+    }
+    out.push('\n');
+
+    for entry in entries {
+        match entry {
+            Entry::User { text, images } => {
+                out.push_str(&format!("## User\n\n{}\n\n", text.trim()));
+                if *images > 0 {
+                    out.push_str(&format!("*{images} image(s) attached*\n\n"));
+                }
+            }
+            Entry::Agent(text) => out.push_str(&format!("## Agent\n\n{}\n\n", text.trim())),
+            Entry::Reasoning(summary) => {
+                out.push_str("### Reasoning\n\n");
+                for point in summary {
+                    out.push_str(&format!("- {}\n", point.trim()));
+                }
+                out.push('\n');
+            }
+            Entry::Command {
+                command,
+                exit_code,
+                output,
+                status,
+            } => {
+                out.push_str("### Command\n\n");
+                out.push_str(&code_block("sh", command));
+                if !output.trim().is_empty() {
+                    out.push_str(&code_block("", output));
+                }
+                match (status, exit_code) {
+                    (Some(status), _) => out.push_str(&format!("*{status}*\n\n")),
+                    (None, Some(code)) => out.push_str(&format!("*exit {code}*\n\n")),
+                    (None, None) => {}
+                }
+            }
+            Entry::FileChange { changes, status } => {
+                out.push_str("### File changes\n\n");
+                if let Some(status) = status {
+                    out.push_str(&format!("*{status}*\n\n"));
+                }
+                for change in changes {
+                    out.push_str(&format!("**{}** `{}`\n\n", change.kind, change.path));
+                    if !change.diff.trim().is_empty() {
+                        out.push_str(&code_block("diff", &change.diff));
+                    }
+                }
+            }
+            Entry::Tool {
+                name,
+                arguments,
+                result,
+                error,
+            } => {
+                out.push_str(&format!("### Tool: {name}\n\n"));
+                out.push_str(&code_block("json", arguments));
+                if let Some(result) = result {
+                    out.push_str(&code_block("json", result));
+                }
+                if let Some(error) = error {
+                    out.push_str(&format!("**Error**: {error}\n\n"));
+                }
+            }
+            Entry::WebSearch(query) => out.push_str(&format!("### Web search\n\n*{query}*\n\n")),
+            Entry::Review(text) => out.push_str(&format!("### Review\n\n{}\n\n", text.trim())),
+        }
+    }
+    out
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub(crate) const HTML_STYLE: &str = "body{font:14px/1.5 -apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;\
+max-width:900px;margin:2rem auto;padding:0 1rem;color:#1f2328}\
+h1{font-size:1.5rem}dl{display:grid;grid-template-columns:max-content 1fr;gap:.25rem 1rem;color:#59636e}\
+dt{font-weight:600}dd{margin:0}section{border-top:1px solid #d1d9e0;padding:.75rem 0}\
+h2{font-size:.8rem;text-transform:uppercase;letter-spacing:.05em;color:#59636e;margin:0 0 .5rem}\
+.text{white-space:pre-wrap}pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;border-radius:6px}\
+.add{color:#1a7f37}.del{color:#cf222e}.status{color:#9a6700;font-style:italic}";
+
+fn html_pre(text: &str) -> String {
+    format!("<pre>{}</pre>", escape_html(text.trim_end()))
+}
+
+pub(crate) fn html_diff(diff: &str) -> String {
+    let lines: Vec<String> = diff
+        .lines()
+        .map(|line| {
+            let class = match line.chars().next() {
+                Some('+') if !line.starts_with("+++") => "add",
+                Some('-') if !line.starts_with("---") => "del",
+                _ => "",
+            };
+            if class.is_empty() {
+                escape_html(line)
+            } else {
+                format!("<span class=\"{class}\">{}</span>", escape_html(line))
+            }
+        })
+        .collect();
+    format!("<pre>{}</pre>", lines.join("\n"))
+}
+
+fn render_html(meta: &TranscriptMeta, entries: &[Entry]) -> String {
+    let title = escape_html(&meta.title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<dl>"
+    );
+    for (label, value) in header_lines(meta) {
+        out.push_str(&format!("<dt>{label}</dt><dd>{}</dd>", escape_html(&value)));
+    }
+    out.push_str("</dl>\n");
+
+    for entry in entries {
+        out.push_str("<section>");
+        match entry {
+            Entry::User { text, images } => {
+                out.push_str(&format!("<h2>User</h2><div class=\"text\">{}</div>", escape_html(text.trim())));
+                if *images > 0 {
+                    out.push_str(&format!("<p class=\"status\">{images} image(s) attached</p>"));
+                }
+            }
+            Entry::Agent(text) => {
+                out.push_str(&format!("<h2>Agent</h2><div class=\"text\">{}</div>", escape_html(text.trim())));
+            }
+            Entry::Reasoning(summary) => {
+                out.push_str("<h2>Reasoning</h2><ul>");
+                for point in summary {
+                    out.push_str(&format!("<li>{}</li>", escape_html(point.trim())));
+                }
+                out.push_str("</ul>");
+            }
+            Entry::Command {
+                command,
+                exit_code,
+                output,
+                status,
+            } => {
+                out.push_str("<h2>Command</h2>");
+                out.push_str(&html_pre(command));
+                if !output.trim().is_empty() {
+                    out.push_str(&html_pre(output));
+                }
+                let note = status.clone().or_else(|| exit_code.map(|code| format!("exit {code}")));
+                if let Some(note) = note {
+                    out.push_str(&format!("<p class=\"status\">{}</p>", escape_html(&note)));
+                }
+            }
+            Entry::FileChange { changes, status } => {
+                out.push_str("<h2>File changes</h2>");
+                if let Some(status) = status {
+                    out.push_str(&format!("<p class=\"status\">{}</p>", escape_html(status)));
+                }
+                for change in changes {
+                    out.push_str(&format!(
+                        "<p><strong>{}</strong> <code>{}</code></p>",
+                        escape_html(&change.kind),
+                        escape_html(&change.path)
+                    ));
+                    if !change.diff.trim().is_empty() {
+                        out.push_str(&html_diff(&change.diff));
+                    }
+                }
+            }
+            Entry::Tool {
+                name,
+                arguments,
+                result,
+                error,
+            } => {
+                out.push_str(&format!("<h2>Tool: {}</h2>", escape_html(name)));
+                out.push_str(&html_pre(arguments));
+                if let Some(result) = result {
+                    out.push_str(&html_pre(result));
+                }
+                if let Some(error) = error {
+                    out.push_str(&format!("<p class=\"status\">Error: {}</p>", escape_html(error)));
+                }
+            }
+            Entry::WebSearch(query) => {
+                out.push_str(&format!("<h2>Web search</h2><p>{}</p>", escape_html(query)));
+            }
+            Entry::Review(text) => {
+                out.push_str(&format!("<h2>Review</h2><div class=\"text\">{}</div>", escape_html(text.trim())));
+            }
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> (TranscriptMeta, Vec<JsonValue>) {
+        let meta = TranscriptMeta {
+            session_id: "s1".to_string(),
+            title: "Fix <parser>".to_string(),
+            cwd: "/tmp/example".to_string(),
+            model: Some("gpt-5".to_string()),
+            exported_at: 0,
+        };
+        let items = vec![
+            json!({ "type": "userMessage", "content": [{ "type": "text", "text": "Fix it" }] }),
+            json!({ "type": "commandExecution", "command": "grep -r '```' .", "exitCode": 1, "status": "declined" }),
+            json!({ "type": "fileChange", "status": "completed", "changes": [
+                { "path": "src/lib.rs", "kind": { "type": "update" }, "diff": "-a\n+b" }
+            ] }),
+            json!({ "type": "agentMessage", "text": "Done" }),
+        ];
+        (meta, items)
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let (meta, items) = sample();
+        let markdown = render(ExportFormat::Markdown, &meta, &items).unwrap();
+        assert!(markdown.starts_with("# Fix <parser>\n"));
+        assert!(markdown.contains("````sh\ngrep -r '```' .\n````"));
+        assert!(markdown.contains("*declined*"));
+        assert!(markdown.contains("**update** `src/lib.rs`"));
+        assert!(markdown.contains("## Agent\n\nDone"));
+    }
+
+    #[test]
+    fn test_render_html_escapes() {
+        let (meta, items) = sample();
+        let html = render(ExportFormat::Html, &meta, &items).unwrap();
+        assert!(html.contains("<h1>Fix &lt;parser&gt;</h1>"));
+        assert!(html.contains("<span class=\"add\">+b</span>"));
+        assert!(!html.contains("<parser>"));
+    }
+}
@@ -0,0 +1,331 @@
+//! Parse shared transcripts back into thread items.
+//!
+//! Accepts the JSON written by `export_session` and Codex CLI rollout files
+//! (JSONL, one event per line). Rollout response items are converted to the
+//! app-server item shapes the GUI already renders; anything without a
+//! counterpart (developer messages, token counts, ...) is dropped.
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::codex_import::CodexSessionMeta;
+use crate::{Error, Result};
+
+/// Where an imported transcript came from
+pub const SOURCE_EXPORT: &str = "export";
+pub const SOURCE_ROLLOUT: &str = "rollout";
+
+/// Function-call names the CLI uses for shell commands
+const SHELL_TOOLS: &[&str] = &["shell", "container.exec", "shell_command", "exec_command"];
+
+/// Prefixes of user messages the CLI injects as context, not typed by the user
+const INJECTED_PREFIXES: &[&str] = &["<environment_context>", "<user_instructions>", "# AGENTS.md"];
+
+/// A transcript file reduced to thread items plus whatever metadata it had
+#[derive(Debug, Default)]
+pub struct ParsedTranscript {
+    /// `SOURCE_EXPORT` or `SOURCE_ROLLOUT`
+    pub source: &'static str,
+    pub title: Option<String>,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+    /// Unix timestamp the original session started, if recorded
+    pub created_at: Option<i64>,
+    pub items: Vec<JsonValue>,
+}
+
+/// Parse an exported JSON transcript or a CLI rollout file
+pub fn parse(text: &str) -> Result<ParsedTranscript> {
+    let parsed = match serde_json::from_str::<JsonValue>(text) {
+        Ok(export) if export.get("items").is_some_and(JsonValue::is_array) => parse_export(export),
+        _ => parse_rollout(text),
+    };
+    if parsed.items.is_empty() {
+        return Err(Error::Other(
+            "No messages found; expected an exported JSON transcript or a Codex rollout file"
+                .to_string(),
+        ));
+    }
+    Ok(parsed)
+}
+
+fn non_empty(value: Option<&JsonValue>) -> Option<String> {
+    value
+        .and_then(JsonValue::as_str)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_export(mut export: JsonValue) -> ParsedTranscript {
+    let session = export.get("session").cloned().unwrap_or_default();
+    let items = match export.get_mut("items").map(JsonValue::take) {
+        Some(JsonValue::Array(items)) => items
+            .into_iter()
+            .filter(|item| item.get("type").is_some_and(JsonValue::is_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    ParsedTranscript {
+        source: SOURCE_EXPORT,
+        title: non_empty(session.get("title")),
+        cwd: non_empty(session.get("cwd")),
+        model: non_empty(session.get("model")),
+        created_at: None,
+        items,
+    }
+}
+
+fn parse_rollout(text: &str) -> ParsedTranscript {
+    let mut parsed = ParsedTranscript {
+        source: SOURCE_ROLLOUT,
+        ..Default::default()
+    };
+    let mut calls: HashMap<String, usize> = HashMap::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<JsonValue>(line) else {
+            // A session killed mid-write can leave a truncated last line
+            tracing::debug!("Skipping unparseable rollout line {}", idx + 1);
+            continue;
+        };
+        // Older rollouts have no event envelope: a bare meta line, then items
+        let kind = event.get("type").and_then(JsonValue::as_str);
+        let (kind, payload) = match (kind, event.get("payload")) {
+            (Some(kind), Some(payload)) => (kind, payload),
+            (Some(_), None) => ("response_item", &event),
+            (None, _) => ("session_meta", &event),
+        };
+
+        match kind {
+            "session_meta" => {
+                if let Ok(meta) = serde_json::from_value::<CodexSessionMeta>(payload.clone()) {
+                    parsed.cwd = Some(meta.cwd).filter(|cwd| !cwd.is_empty());
+                    parsed.created_at = chrono::DateTime::parse_from_rfc3339(&meta.timestamp)
+                        .ok()
+                        .map(|date| date.timestamp());
+                }
+            }
+            "turn_context" => {
+                if let Some(model) = non_empty(payload.get("model")) {
+                    parsed.model = Some(model);
+                }
+            }
+            "response_item" => convert_response_item(payload, &mut parsed.items, &mut calls),
+            _ => {}
+        }
+    }
+    parsed
+}
+
+fn str_field(item: &JsonValue, key: &str) -> String {
+    item.get(key).and_then(JsonValue::as_str).unwrap_or_default().to_string()
+}
+
+/// Text parts of a rollout message's `content`
+fn content_text(item: &JsonValue) -> String {
+    item.get("content")
+        .and_then(JsonValue::as_array)
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(JsonValue::as_str))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
+}
+
+fn convert_response_item(
+    item: &JsonValue,
+    items: &mut Vec<JsonValue>,
+    calls: &mut HashMap<String, usize>,
+) {
+    let id = format!("imported-{}", items.len());
+    let converted = match item.get("type").and_then(JsonValue::as_str).unwrap_or_default() {
+        "message" => {
+            let text = content_text(item);
+            if text.trim().is_empty() {
+                return;
+            }
+            match item.get("role").and_then(JsonValue::as_str) {
+                Some("user") => {
+                    if INJECTED_PREFIXES.iter().any(|prefix| text.trim_start().starts_with(prefix)) {
+                        return;
+                    }
+                    json!({
+                        "type": "userMessage",
+                        "id": id,
+                        "content": [{ "type": "text", "text": text }],
+                    })
+                }
+                Some("assistant") => json!({ "type": "agentMessage", "id": id, "text": text }),
+                _ => return,
+            }
+        }
+        "reasoning" => {
+            let summary: Vec<String> = item
+                .get("summary")
+                .and_then(JsonValue::as_array)
+                .map(|points| {
+                    points
+                        .iter()
+                        .filter_map(|point| point.get("text").and_then(JsonValue::as_str))
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if summary.is_empty() {
+                return;
+            }
+            json!({ "type": "reasoning", "id": id, "summary": summary })
+        }
+        "function_call" | "custom_tool_call" | "local_shell_call" => {
+            let name = str_field(item, "name");
+            let arguments = match item.get("arguments").and_then(JsonValue::as_str) {
+                Some(raw) => serde_json::from_str(raw).unwrap_or_else(|_| json!(raw)),
+                None => item
+                    .get("input")
+                    .or_else(|| item.get("action"))
+                    .cloned()
+                    .unwrap_or_default(),
+            };
+            let converted = if SHELL_TOOLS.contains(&name.as_str()) || name.is_empty() {
+                json!({
+                    "type": "commandExecution",
+                    "id": id,
+                    "command": arguments.get("command").cloned().unwrap_or_default(),
+                    "aggregatedOutput": "",
+                    "exitCode": null,
+                    "status": "completed",
+                })
+            } else {
+                json!({
+                    "type": "mcpToolCall",
+                    "id": id,
+                    "server": "",
+                    "tool": name,
+                    "arguments": arguments,
+                    "result": null,
+                    "error": null,
+                })
+            };
+            if let Some(call_id) = item.get("call_id").and_then(JsonValue::as_str) {
+                calls.insert(call_id.to_string(), items.len());
+            }
+            converted
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            let Some(&idx) = item
+                .get("call_id")
+                .and_then(JsonValue::as_str)
+                .and_then(|call_id| calls.get(call_id))
+            else {
+                return;
+            };
+            attach_output(&mut items[idx], item.get("output"));
+            return;
+        }
+        "web_search_call" => {
+            let query = item
+                .get("action")
+                .and_then(|action| action.get("query"))
+                .and_then(JsonValue::as_str)
+                .unwrap_or_default();
+            json!({ "type": "webSearch", "id": id, "query": query })
+        }
+        _ => return,
+    };
+    items.push(converted);
+}
+
+/// Fill in a call's result from its `*_output` item
+///
+/// Shell output is usually a JSON string `{"output": ..., "metadata": {"exit_code": ...}}`.
+fn attach_output(call: &mut JsonValue, output: Option<&JsonValue>) {
+    let raw = match output {
+        Some(JsonValue::String(text)) => text.clone(),
+        Some(other) => other
+            .get("content")
+            .map_or_else(|| other.to_string(), JsonValue::to_string),
+        None => return,
+    };
+    let structured: Option<JsonValue> = serde_json::from_str(&raw).ok();
+    let text = structured
+        .as_ref()
+        .and_then(|value| value.get("output"))
+        .and_then(JsonValue::as_str)
+        .map_or(raw, str::to_string);
+
+    if call["type"] == "commandExecution" {
+        call["aggregatedOutput"] = json!(text);
+        if let Some(code) = structured
+            .as_ref()
+            .and_then(|value| value.pointer("/metadata/exit_code"))
+            .and_then(JsonValue::as_i64)
+        {
+            call["exitCode"] = json!(code);
+            if code != 0 {
+                call["status"] = json!("failed");
+            }
+        }
+    } else {
+        call["result"] = json!(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rollout() {
+        let rollout = [
+            r#"{"timestamp":"2025-01-02T03:04:05Z","type":"session_meta","payload":{"id":"abc","timestamp":"2025-01-02T03:04:05.000Z","cwd":"/work/app"}}"#,
+            r#"{"timestamp":"t","type":"turn_context","payload":{"model":"gpt-5","cwd":"/work/app"}}"#,
+            r#"{"timestamp":"t","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"<environment_context>cwd</environment_context>"}]}}"#,
+            r#"{"timestamp":"t","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"Run the tests"}]}}"#,
+            r#"{"timestamp":"t","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"cargo\",\"test\"]}","call_id":"c1"}}"#,
+            r#"{"timestamp":"t","type":"response_item","payload":{"type":"function_call_output","call_id":"c1","output":"{\"output\":\"1 failed\",\"metadata\":{\"exit_code\":101}}"}}"#,
+            r#"{"timestamp":"t","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"One test fails."}]}}"#,
+            r#"{"timestamp":"t","type":"event_msg","payload":{"type":"token_count"}}"#,
+            r#"{"timestamp":"t","type":"response_item","pay"#,
+        ]
+        .join("\n");
+
+        let parsed = parse(&rollout).unwrap();
+        assert_eq!(parsed.source, SOURCE_ROLLOUT);
+        assert_eq!(parsed.cwd.as_deref(), Some("/work/app"));
+        assert_eq!(parsed.model.as_deref(), Some("gpt-5"));
+        assert!(parsed.created_at.is_some());
+
+        let types: Vec<&str> = parsed
+            .items
+            .iter()
+            .map(|item| item["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, vec!["userMessage", "commandExecution", "agentMessage"]);
+        assert_eq!(parsed.items[1]["command"], json!(["cargo", "test"]));
+        assert_eq!(parsed.items[1]["aggregatedOutput"], "1 failed");
+        assert_eq!(parsed.items[1]["exitCode"], 101);
+    }
+
+    #[test]
+    fn test_parse_export_and_reject_empty() {
+        let export = json!({
+            "session": { "sessionId": "s1", "title": "Fix parser", "cwd": "/work", "model": null },
+            "items": [{ "type": "agentMessage", "id": "i1", "text": "Done" }, { "bogus": true }],
+        });
+        let parsed = parse(&export.to_string()).unwrap();
+        assert_eq!(parsed.source, SOURCE_EXPORT);
+        assert_eq!(parsed.title.as_deref(), Some("Fix parser"));
+        assert!(parsed.model.is_none());
+        assert_eq!(parsed.items.len(), 1);
+
+        assert!(parse("not a transcript").is_err());
+        assert!(parse(r#"{"items": []}"#).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+//! Tray icon (menu bar on macOS) with the backend's status and quick actions.
+//!
+//! The icon stays while the main window is closed to the tray, so its
+//! tooltip and menu are fed from backend state rather than the renderer:
+//! every `STATUS_INTERVAL` the app-server state, running turns, pending
+//! approvals, queue pause and keep-awake are read again, and the menu is
+//! rebuilt when any of them changed. Actions besides opening the window and
+//! quitting do nothing while the app is locked; the renderer is told about
+//! the others through `tray-action`. Menu item ids come from
+//! `actions::tray`, so the tray's actions carry their registry ids.
+
+use std::time::Duration;
+
+use serde_json::json;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Window};
+
+use crate::actions::tray::{KEEP_AWAKE, OPEN_WINDOW, PAUSE_QUEUE, QUIT, STOP_TURNS};
+use crate::commands::app_server::{server_state, ServerState};
+use crate::commands::system::CaffeinateState;
+use crate::i18n::{self, Locale};
+use crate::quick_prompt::show_main_window;
+use crate::state::AppState;
+use crate::{Error, Result};
+
+const TRAY_ID: &str = "main";
+
+/// How often the status is read again
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Id of the status line, which cannot be picked
+const STATUS: &str = "tray.status";
+
+/// What the tray shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayStatus {
+    pub server: ServerState,
+    pub active_turns: usize,
+    /// Zero unless `show_pending_count` is on
+    pub pending_approvals: usize,
+    pub queue_paused: bool,
+    pub keep_awake: bool,
+    /// Labels are rebuilt in a new locale too
+    pub locale: Locale,
+}
+
+impl TrayStatus {
+    /// One line for the tooltip and the top of the menu
+    pub fn summary(&self) -> String {
+        let server = match self.server {
+            ServerState::Running => "tray.server.running",
+            ServerState::Stopped => "tray.server.stopped",
+            ServerState::Idle => "tray.server.idle",
+            ServerState::Degraded => "tray.server.degraded",
+        };
+        let mut parts = vec![i18n::t(server, &[])];
+        if self.active_turns > 0 {
+            let count = self.active_turns.to_string();
+            parts.push(i18n::t("tray.active_turns", &[("count", &count)]));
+        }
+        if self.pending_approvals > 0 {
+            let count = self.pending_approvals.to_string();
+            parts.push(i18n::t("tray.pending_approvals", &[("count", &count)]));
+        }
+        parts.join(" · ")
+    }
+}
+
+async fn read_status(app: &AppHandle) -> Option<TrayStatus> {
+    let state = app.try_state::<AppState>()?;
+    let (server, _) = server_state(&state).await;
+    let (show_pending, queue_paused) = state
+        .global_state
+        .read(|global| (global.tray.show_pending_count, global.scheduler.paused));
+    let keep_awake = match app.try_state::<CaffeinateState>() {
+        Some(caffeinate) => crate::commands::system::is_keep_awake_active(caffeinate)
+            .await
+            .unwrap_or(false),
+        None => false,
+    };
+    Some(TrayStatus {
+        server,
+        active_turns: state.active_turns.list().len(),
+        pending_approvals: if show_pending {
+            state.approvals.pending.list().len()
+        } else {
+            0
+        },
+        queue_paused,
+        keep_awake,
+        locale: i18n::current(),
+    })
+}
+
+fn build_menu(app: &AppHandle, status: &TrayStatus) -> tauri::Result<Menu<tauri::Wry>> {
+    let label = |key: &str| i18n::t(key, &[]);
+    Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, STATUS, status.summary(), false, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, OPEN_WINDOW, label("tray.open"), true, None::<&str>)?,
+            &CheckMenuItem::with_id(
+                app,
+                PAUSE_QUEUE,
+                label("tray.pause"),
+                true,
+                status.queue_paused,
+                None::<&str>,
+            )?,
+            &MenuItem::with_id(
+                app,
+                STOP_TURNS,
+                label("tray.stop_turns"),
+                status.active_turns > 0,
+                None::<&str>,
+            )?,
+            &CheckMenuItem::with_id(
+                app,
+                KEEP_AWAKE,
+                label("tray.keep_awake"),
+                true,
+                status.keep_awake,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, QUIT, label("tray.quit"), true, None::<&str>)?,
+        ],
+    )
+}
+
+/// Add the tray icon unless it is already there
+pub async fn create(app: &AppHandle) -> Result<()> {
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return Ok(());
+    }
+    let Some(status) = read_status(app).await else {
+        return Ok(());
+    };
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(status.summary())
+        .menu(&build_menu(app, &status).map_err(|e| Error::Tauri(e.to_string()))?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder
+        .build(app)
+        .map_err(|e| Error::Tauri(e.to_string()))?;
+    tracing::info!("Tray icon added");
+    Ok(())
+}
+
+pub fn remove(app: &AppHandle) {
+    if app.remove_tray_by_id(TRAY_ID).is_some() {
+        tracing::info!("Tray icon removed");
+    }
+}
+
+/// Keep the tray in step with the backend while the app runs
+pub async fn run_status_updates(app: AppHandle) {
+    let mut shown: Option<TrayStatus> = None;
+    loop {
+        tokio::time::sleep(STATUS_INTERVAL).await;
+        let Some(tray) = app.tray_by_id(TRAY_ID) else {
+            shown = None;
+            continue;
+        };
+        let Some(status) = read_status(&app).await else {
+            continue;
+        };
+        if shown.as_ref() == Some(&status) {
+            continue;
+        }
+        let _ = tray.set_tooltip(Some(status.summary()));
+        // macOS shows this next to the icon
+        let title = (status.pending_approvals > 0).then(|| status.pending_approvals.to_string());
+        let _ = tray.set_title(title);
+        match build_menu(&app, &status) {
+            Ok(menu) => {
+                let _ = tray.set_menu(Some(menu));
+            }
+            Err(err) => tracing::warn!("Failed to rebuild tray menu: {}", err),
+        }
+        shown = Some(status);
+    }
+}
+
+/// Hide the window instead of closing it when the tray settings ask for
+/// that; returns whether it was hidden
+pub fn hide_to_tray(window: &Window) -> bool {
+    let app = window.app_handle();
+    let Some(state) = app.try_state::<AppState>() else {
+        return false;
+    };
+    let settings = state.global_state.read(|global| global.tray.clone());
+    if !settings.enabled || !settings.close_to_tray || app.tray_by_id(TRAY_ID).is_none() {
+        return false;
+    }
+    window.hide().is_ok()
+}
+
+fn on_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        OPEN_WINDOW => show_main_window(app),
+        QUIT => app.exit(0),
+        PAUSE_QUEUE | STOP_TURNS | KEEP_AWAKE => {
+            let app = app.clone();
+            let id = id.to_string();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = run_action(&app, &id).await {
+                    tracing::warn!("Tray action {} failed: {}", id, err);
+                }
+            });
+        }
+        _ => {}
+    }
+}
+
+async fn run_action(app: &AppHandle, id: &str) -> Result<()> {
+    let state = app.state::<AppState>();
+    if state.app_lock.is_locked() {
+        return Err(Error::Locked);
+    }
+    match id {
+        PAUSE_QUEUE => {
+            let paused = state.global_state.read(|global| global.scheduler.paused);
+            crate::commands::turn_queue::set_queue_paused(app.state(), None, !paused).await?;
+        }
+        STOP_TURNS => {
+            let interrupted = crate::commands::thread::interrupt_all_turns(app.state()).await?;
+            tracing::info!("Stopped {} turns from the tray", interrupted);
+        }
+        KEEP_AWAKE => {
+            let active = crate::commands::system::is_keep_awake_active(app.state())
+                .await
+                .map_err(Error::Other)?;
+            let result = if active {
+                crate::commands::system::stop_keep_awake(app.state()).await
+            } else {
+                crate::commands::system::start_keep_awake(app.state()).await
+            };
+            result.map_err(Error::Other)?;
+        }
+        _ => return Ok(()),
+    }
+    state
+        .events
+        .emit("tray-action", json!({ "action": id }))
+        .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut status = TrayStatus {
+            server: ServerState::Running,
+            active_turns: 0,
+            pending_approvals: 0,
+            queue_paused: false,
+            keep_awake: false,
+            locale: Locale::En,
+        };
+        assert_eq!(status.summary(), "App server running");
+        status.active_turns = 2;
+        status.pending_approvals = 1;
+        assert_eq!(
+            status.summary(),
+            "App server running · Turns running: 2 · Approvals pending: 1"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Per-project queues of turns run one after another.
+//!
+//! Prompts are lined up against a project's threads and run in queue order,
+//! one at a time per project: the next entry starts once the previous
+//! entry's turn completes, or once its `run_after` time has come. Entries
+//! live in the database so a queue survives restarts. A failed or
+//! interrupted entry pauses its project's queue (recorded in the scheduler
+//! section of the global state) until the user resumes it, so later steps
+//! do not run on top of a broken one.
+
+use std::sync::Arc;
+
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::database::{Database, QueuedTurn};
+use crate::global_state::unix_timestamp_millis;
+use crate::{Error, Result};
+
+/// Unfinished entries allowed in one project's queue
+const MAX_QUEUED_PER_PROJECT: usize = 100;
+
+/// How often entries waiting for their `run_after` time are checked, in seconds
+pub const SCHEDULE_CHECK_SECS: u64 = 15;
+
+/// Queue entry status for a finished turn's status
+pub fn finished_status(turn_status: &str) -> &'static str {
+    match turn_status {
+        "completed" => "completed",
+        "interrupted" => "cancelled",
+        _ => "failed",
+    }
+}
+
+pub struct TurnQueue {
+    database: Arc<Database>,
+    /// Held while picking and starting entries so two dispatches cannot
+    /// start the same project's next entry
+    dispatch_lock: Mutex<()>,
+}
+
+impl TurnQueue {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            dispatch_lock: Mutex::new(()),
+        }
+    }
+
+    /// Append a prompt to a project's queue
+    ///
+    /// The thread must belong to the project.
+    pub fn enqueue(
+        &self,
+        project_id: &str,
+        thread_id: &str,
+        prompt: &str,
+        run_after: Option<i64>,
+    ) -> Result<QueuedTurn> {
+        if prompt.trim().is_empty() {
+            return Err(Error::Other("Queued prompt cannot be empty".to_string()));
+        }
+        let session = self
+            .database
+            .get_session_by_id(thread_id)?
+            .ok_or_else(|| Error::SessionNotFound(thread_id.to_string()))?;
+        if session.project_id != project_id {
+            return Err(Error::Other(format!(
+                "Thread {thread_id} does not belong to project {project_id}"
+            )));
+        }
+        if self.database.count_queued_turns(project_id)? >= MAX_QUEUED_PER_PROJECT {
+            return Err(Error::Other(format!(
+                "Turn queue is full (max {MAX_QUEUED_PER_PROJECT} prompts)"
+            )));
+        }
+        self.database.insert_queued_turn(
+            project_id,
+            thread_id,
+            prompt,
+            run_after,
+            unix_timestamp_millis(),
+        )
+    }
+
+    pub fn list(
+        &self,
+        project_id: Option<&str>,
+        include_finished: bool,
+    ) -> Result<Vec<QueuedTurn>> {
+        self.database.list_turn_queue(project_id, include_finished)
+    }
+
+    /// Reorder a project's waiting entries; returns the project's queue
+    pub fn reorder(&self, project_id: &str, queued_ids: &[String]) -> Result<Vec<QueuedTurn>> {
+        self.database.reorder_turn_queue(project_id, queued_ids)?;
+        self.list(Some(project_id), false)
+    }
+
+    /// Cancel a waiting entry; returns it, or `None` if it had already started
+    pub fn cancel(&self, queued_id: &str) -> Result<Option<QueuedTurn>> {
+        if !self
+            .database
+            .cancel_queued_turn(queued_id, unix_timestamp_millis())?
+        {
+            return Ok(None);
+        }
+        self.database.get_queued_turn(queued_id)
+    }
+
+    pub(crate) async fn lock_dispatch(&self) -> MutexGuard<'_, ()> {
+        self.dispatch_lock.lock().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finished_status() {
+        assert_eq!(finished_status("completed"), "completed");
+        assert_eq!(finished_status("interrupted"), "cancelled");
+        assert_eq!(finished_status("failed"), "failed");
+    }
+}
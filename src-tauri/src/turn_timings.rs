@@ -0,0 +1,237 @@
+//! Per-turn timing breakdown.
+//!
+//! A turn's wall-clock time is split into phases from app-server events:
+//! - queued: from the `turn/start` request until `turn/started`
+//! - tools: while command, MCP or web-search items are running
+//! - patches: while file-change items are being applied
+//! - thinking: the rest of the running turn (model sampling, reasoning)
+//!
+//! Items can overlap, so each phase is the union of its item intervals and
+//! thinking excludes any time covered by tools or patches. Timings of
+//! running turns live in memory; finished turns are stored in `turn_timings`.
+
+use std::collections::HashMap;
+
+use parking_lot::Mutex;
+use serde_json::Value as JsonValue;
+
+use crate::database::TurnTimings;
+
+/// Item types timed as tool execution
+const TOOL_ITEM_TYPES: &[&str] = &["commandExecution", "mcpToolCall", "webSearch"];
+
+/// Item type timed as applying patches
+const PATCH_ITEM_TYPE: &str = "fileChange";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Tool,
+    Patch,
+}
+
+fn item_phase(item: &JsonValue) -> Option<Phase> {
+    match item.get("type").and_then(JsonValue::as_str)? {
+        PATCH_ITEM_TYPE => Some(Phase::Patch),
+        kind if TOOL_ITEM_TYPES.contains(&kind) => Some(Phase::Tool),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct ActiveTurn {
+    turn_id: String,
+    requested_at: Option<i64>,
+    started_at: i64,
+    /// Item id -> (phase, started at) for items still running
+    open_items: HashMap<String, (Phase, i64)>,
+    /// Finished item intervals, in milliseconds
+    intervals: Vec<(Phase, i64, i64)>,
+}
+
+/// Timing state of running turns, keyed by thread id
+#[derive(Debug, Default)]
+pub struct TurnTimingTracker {
+    /// When `turn/start` was sent for a thread whose turn has not started yet
+    requested: Mutex<HashMap<String, i64>>,
+    active: Mutex<HashMap<String, ActiveTurn>>,
+}
+
+impl TurnTimingTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note that a `turn/start` request is about to be sent
+    pub fn turn_requested(&self, thread_id: &str, at_ms: i64) {
+        self.requested.lock().insert(thread_id.to_string(), at_ms);
+    }
+
+    pub fn turn_started(&self, thread_id: &str, turn_id: &str, at_ms: i64) {
+        let requested_at = self.requested.lock().remove(thread_id);
+        self.active.lock().insert(
+            thread_id.to_string(),
+            ActiveTurn {
+                turn_id: turn_id.to_string(),
+                requested_at: requested_at.filter(|requested| *requested <= at_ms),
+                started_at: at_ms,
+                open_items: HashMap::new(),
+                intervals: Vec::new(),
+            },
+        );
+    }
+
+    pub fn item_started(&self, thread_id: &str, item: &JsonValue, at_ms: i64) {
+        let (Some(phase), Some(id)) =
+            (item_phase(item), item.get("id").and_then(JsonValue::as_str))
+        else {
+            return;
+        };
+        if let Some(turn) = self.active.lock().get_mut(thread_id) {
+            turn.open_items.insert(id.to_string(), (phase, at_ms));
+        }
+    }
+
+    pub fn item_completed(&self, thread_id: &str, item: &JsonValue, at_ms: i64) {
+        let Some(id) = item.get("id").and_then(JsonValue::as_str) else {
+            return;
+        };
+        if let Some(turn) = self.active.lock().get_mut(thread_id) {
+            if let Some((phase, started)) = turn.open_items.remove(id) {
+                turn.intervals.push((phase, started, at_ms.max(started)));
+            }
+        }
+    }
+
+    /// Finish a thread's turn and return its breakdown
+    pub fn turn_completed(&self, thread_id: &str, status: &str, at_ms: i64) -> Option<TurnTimings> {
+        let turn = self.active.lock().remove(thread_id)?;
+        let mut timings = breakdown(thread_id, &turn, at_ms);
+        timings.status = Some(status.to_string());
+        timings.completed_at = Some(at_ms);
+        Some(timings)
+    }
+
+    /// Breakdown so far of a running turn
+    pub fn snapshot(&self, turn_id: &str, now_ms: i64) -> Option<TurnTimings> {
+        let active = self.active.lock();
+        let (thread_id, turn) = active.iter().find(|(_, turn)| turn.turn_id == turn_id)?;
+        Some(breakdown(thread_id, turn, now_ms))
+    }
+
+    /// Drop all running turns (e.g. after the app-server disconnects)
+    pub fn clear(&self) {
+        self.requested.lock().clear();
+        self.active.lock().clear();
+    }
+}
+
+/// Total length of the union of `intervals`
+fn union_ms(mut intervals: Vec<(i64, i64)>) -> i64 {
+    intervals.sort_unstable();
+    let mut total = 0;
+    let mut current: Option<(i64, i64)> = None;
+    for (start, end) in intervals {
+        match current {
+            Some((_, current_end)) if start <= current_end => {
+                current = current.map(|(s, e)| (s, e.max(end)));
+            }
+            _ => {
+                if let Some((s, e)) = current {
+                    total += e - s;
+                }
+                current = Some((start, end));
+            }
+        }
+    }
+    total + current.map_or(0, |(s, e)| e - s)
+}
+
+fn breakdown(thread_id: &str, turn: &ActiveTurn, end_ms: i64) -> TurnTimings {
+    let end_ms = end_ms.max(turn.started_at);
+    // Items still running count up to `end_ms`
+    let intervals: Vec<(Phase, i64, i64)> = turn
+        .intervals
+        .iter()
+        .copied()
+        .chain(
+            turn.open_items
+                .values()
+                .map(|&(phase, start)| (phase, start, end_ms)),
+        )
+        .map(|(phase, start, end)| (phase, start.clamp(turn.started_at, end_ms), end.min(end_ms)))
+        .collect();
+    let phase_ms = |phase: Option<Phase>| {
+        union_ms(
+            intervals
+                .iter()
+                .filter(|(p, _, _)| phase.is_none_or(|phase| *p == phase))
+                .map(|&(_, start, end)| (start, end))
+                .collect(),
+        )
+    };
+    let count = |phase: Phase| intervals.iter().filter(|(p, _, _)| *p == phase).count() as u32;
+
+    let running_ms = end_ms - turn.started_at;
+    let queued_ms = turn
+        .requested_at
+        .map_or(0, |requested| turn.started_at - requested);
+    TurnTimings {
+        turn_id: turn.turn_id.clone(),
+        session_id: thread_id.to_string(),
+        status: None,
+        requested_at: turn.requested_at,
+        started_at: turn.started_at,
+        completed_at: None,
+        elapsed_ms: queued_ms + running_ms,
+        queued_ms,
+        thinking_ms: running_ms - phase_ms(None),
+        tool_ms: phase_ms(Some(Phase::Tool)),
+        patch_ms: phase_ms(Some(Phase::Patch)),
+        tool_calls: count(Phase::Tool),
+        patches: count(Phase::Patch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_union_ms_merges_overlaps() {
+        assert_eq!(union_ms(vec![]), 0);
+        assert_eq!(union_ms(vec![(0, 10), (5, 15), (20, 25)]), 20);
+        assert_eq!(union_ms(vec![(20, 25), (0, 30)]), 30);
+    }
+
+    #[test]
+    fn test_turn_breakdown() {
+        let tracker = TurnTimingTracker::new();
+        tracker.turn_requested("t", 1_000);
+        tracker.turn_started("t", "turn-1", 1_500);
+
+        let cmd = |id: &str| json!({ "type": "commandExecution", "id": id });
+        tracker.item_started("t", &cmd("a"), 2_000);
+        tracker.item_started("t", &cmd("b"), 2_500);
+        tracker.item_completed("t", &cmd("a"), 3_000);
+        tracker.item_completed("t", &cmd("b"), 4_000);
+        let patch = json!({ "type": "fileChange", "id": "p" });
+        tracker.item_started("t", &patch, 5_000);
+        tracker.item_started("t", &json!({ "type": "agentMessage", "id": "m" }), 5_500);
+
+        let running = tracker.snapshot("turn-1", 6_000).unwrap();
+        assert_eq!(running.patch_ms, 1_000);
+        assert!(running.completed_at.is_none());
+
+        tracker.item_completed("t", &patch, 5_800);
+        let done = tracker.turn_completed("t", "completed", 7_500).unwrap();
+        assert_eq!(done.queued_ms, 500);
+        assert_eq!(done.tool_ms, 2_000);
+        assert_eq!(done.patch_ms, 800);
+        assert_eq!(done.thinking_ms, 6_000 - 2_800);
+        assert_eq!(done.elapsed_ms, 6_500);
+        assert_eq!((done.tool_calls, done.patches), (2, 1));
+        assert_eq!(done.status.as_deref(), Some("completed"));
+        assert!(tracker.snapshot("turn-1", 8_000).is_none());
+    }
+}
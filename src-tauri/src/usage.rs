@@ -0,0 +1,292 @@
+//! Per-turn token usage and estimated cost.
+//!
+//! Every `thread/tokenUsage/updated` notification reports the tokens of the
+//! model call that just finished (`last`); those are added to the turn's row
+//! in `turn_usage` together with a cost estimate from `PRICING`. Summaries
+//! and CSV exports are built from the stored rows.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::database::{Database, TokenUsage, TurnUsage};
+
+/// USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub cached_input: f64,
+    pub output: f64,
+}
+
+const fn price(input: f64, cached_input: f64, output: f64) -> ModelPrice {
+    ModelPrice {
+        input,
+        cached_input,
+        output,
+    }
+}
+
+/// Public list prices by model-name prefix; the longest matching prefix wins
+const PRICING: &[(&str, ModelPrice)] = &[
+    ("gpt-5", price(1.25, 0.125, 10.0)),
+    ("gpt-5-mini", price(0.25, 0.025, 2.0)),
+    ("gpt-5-nano", price(0.05, 0.005, 0.4)),
+    ("gpt-4.1", price(2.0, 0.5, 8.0)),
+    ("gpt-4.1-mini", price(0.4, 0.1, 1.6)),
+    ("gpt-4.1-nano", price(0.1, 0.025, 0.4)),
+    ("gpt-4o", price(2.5, 1.25, 10.0)),
+    ("gpt-4o-mini", price(0.15, 0.075, 0.6)),
+    ("o3", price(2.0, 0.5, 8.0)),
+    ("o3-mini", price(1.1, 0.55, 4.4)),
+    ("o3-pro", price(20.0, 20.0, 80.0)),
+    ("o4-mini", price(1.1, 0.275, 4.4)),
+    ("codex-mini", price(1.5, 0.375, 6.0)),
+];
+
+/// Pricing for `model`, if known
+pub fn model_price(model: &str) -> Option<ModelPrice> {
+    let model = model.to_ascii_lowercase();
+    PRICING
+        .iter()
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| *price)
+}
+
+/// Estimated USD cost of `usage` on `model`
+///
+/// Cached input is billed at the cached rate; reasoning tokens are already
+/// part of the output count.
+pub fn estimate_cost(model: &str, usage: &TokenUsage) -> Option<f64> {
+    let price = model_price(model)?;
+    let cached = usage.cached_input_tokens.min(usage.input_tokens);
+    let uncached = usage.input_tokens - cached;
+    Some(
+        (uncached as f64 * price.input
+            + cached as f64 * price.cached_input
+            + usage.output_tokens as f64 * price.output)
+            / 1_000_000.0,
+    )
+}
+
+/// Add the model call reported by a `thread/tokenUsage/updated` notification
+/// to its turn
+///
+/// Returns the call's tokens and estimated cost. Failures are logged; usage
+/// tracking never interrupts a session.
+pub fn record_model_call(
+    database: &Database,
+    thread_id: &str,
+    turn_id: &str,
+    usage: &JsonValue,
+) -> Option<(TokenUsage, Option<f64>)> {
+    let last = usage
+        .get("last")
+        .and_then(|last| serde_json::from_value::<TokenUsage>(last.clone()).ok())?;
+    let model = database
+        .get_session_by_id(thread_id)
+        .ok()
+        .flatten()
+        .and_then(|session| session.last_model);
+    let cost = model.as_deref().and_then(|model| estimate_cost(model, &last));
+    if !turn_id.is_empty() {
+        if let Err(err) = database.add_turn_usage(thread_id, turn_id, &last, cost) {
+            tracing::warn!("Failed to record turn usage: {}", err);
+        }
+    }
+    Some((last, cost))
+}
+
+/// Usage summed over a group of turns
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+    /// Project ID, model, day (YYYY-MM-DD, UTC), or "all" for the totals
+    pub key: String,
+    pub turns: u32,
+    pub token_usage: TokenUsage,
+    /// Estimated USD cost of the priced turns
+    pub cost_usd: f64,
+    /// Turns whose model has no known pricing
+    pub unpriced_turns: u32,
+}
+
+impl UsageBucket {
+    fn keyed(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, turn: &TurnUsage) {
+        self.turns += 1;
+        self.token_usage.add(&turn.token_usage);
+        match turn.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_turns += 1,
+        }
+    }
+}
+
+/// Result of `get_usage_summary`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub totals: UsageBucket,
+    /// Turns without a project are grouped under "none"
+    pub by_project: Vec<UsageBucket>,
+    /// Turns with an unknown model are grouped under "unknown"
+    pub by_model: Vec<UsageBucket>,
+    /// Oldest first
+    pub by_day: Vec<UsageBucket>,
+}
+
+fn day_of(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Group turns by project, model and day
+pub fn summarize(turns: &[TurnUsage]) -> UsageSummary {
+    let mut totals = UsageBucket::keyed("all");
+    let mut by_project: BTreeMap<String, UsageBucket> = BTreeMap::new();
+    let mut by_model: BTreeMap<String, UsageBucket> = BTreeMap::new();
+    let mut by_day: BTreeMap<String, UsageBucket> = BTreeMap::new();
+
+    for turn in turns {
+        totals.add(turn);
+        let project = turn.project_id.as_deref().unwrap_or("none");
+        by_project
+            .entry(project.to_string())
+            .or_insert_with(|| UsageBucket::keyed(project))
+            .add(turn);
+        let model = turn.model.as_deref().unwrap_or("unknown");
+        by_model
+            .entry(model.to_string())
+            .or_insert_with(|| UsageBucket::keyed(model))
+            .add(turn);
+        let day = day_of(turn.recorded_at);
+        by_day
+            .entry(day.clone())
+            .or_insert_with(|| UsageBucket::keyed(&day))
+            .add(turn);
+    }
+
+    UsageSummary {
+        totals,
+        by_project: by_project.into_values().collect(),
+        by_model: by_model.into_values().collect(),
+        by_day: by_day.into_values().collect(),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One CSV row per turn, with a header line
+pub fn to_csv(turns: &[TurnUsage]) -> String {
+    let mut csv = String::from(
+        "recorded_at,session_id,turn_id,project_id,model,input_tokens,cached_input_tokens,\
+         output_tokens,reasoning_output_tokens,total_tokens,cost_usd\n",
+    );
+    for turn in turns {
+        let recorded_at = chrono::DateTime::from_timestamp(turn.recorded_at, 0)
+            .map(|date| date.to_rfc3339())
+            .unwrap_or_default();
+        let usage = &turn.token_usage;
+        let fields = [
+            recorded_at,
+            csv_field(&turn.session_id),
+            csv_field(&turn.turn_id),
+            csv_field(turn.project_id.as_deref().unwrap_or_default()),
+            csv_field(turn.model.as_deref().unwrap_or_default()),
+            usage.input_tokens.to_string(),
+            usage.cached_input_tokens.to_string(),
+            usage.output_tokens.to_string(),
+            usage.reasoning_output_tokens.to_string(),
+            usage.total_tokens.to_string(),
+            turn.cost_usd.map(|cost| format!("{cost:.6}")).unwrap_or_default(),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(model: Option<&str>, input: u64, cached: u64, output: u64) -> TurnUsage {
+        let token_usage = TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: cached,
+            output_tokens: output,
+            total_tokens: input + output,
+            ..Default::default()
+        };
+        TurnUsage {
+            session_id: "s1".to_string(),
+            turn_id: format!("t{input}"),
+            project_id: Some("p1".to_string()),
+            model: model.map(str::to_string),
+            cost_usd: model.and_then(|model| estimate_cost(model, &token_usage)),
+            token_usage,
+            recorded_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_estimate_cost_uses_longest_prefix() {
+        assert_eq!(model_price("gpt-5-mini-2025"), Some(price(0.25, 0.025, 2.0)));
+        assert_eq!(model_price("GPT-5-codex"), Some(price(1.25, 0.125, 10.0)));
+        assert!(model_price("llama3").is_none());
+
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 400_000,
+            output_tokens: 100_000,
+            ..Default::default()
+        };
+        let cost = estimate_cost("gpt-5", &usage).unwrap();
+        assert!((cost - (0.75 + 0.05 + 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_model_variants_are_priced_separately() {
+        assert_eq!(model_price("o3-2025-04-16"), Some(price(2.0, 0.5, 8.0)));
+        assert_eq!(model_price("o3-mini"), Some(price(1.1, 0.55, 4.4)));
+        assert_eq!(model_price("o3-pro"), Some(price(20.0, 20.0, 80.0)));
+    }
+
+    #[test]
+    fn test_summarize_and_csv() {
+        let turns = vec![
+            turn(Some("gpt-5"), 1000, 0, 100),
+            turn(None, 500, 0, 50),
+            turn(Some("gpt-5"), 2000, 1000, 200),
+        ];
+        let summary = summarize(&turns);
+        assert_eq!(summary.totals.turns, 3);
+        assert_eq!(summary.totals.unpriced_turns, 1);
+        assert_eq!(summary.by_model.len(), 2);
+        assert_eq!(summary.by_model[0].key, "gpt-5");
+        assert_eq!(summary.by_model[0].token_usage.input_tokens, 3000);
+        assert_eq!(summary.by_day[0].key, "1970-01-01");
+
+        let csv = to_csv(&turns);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(2).unwrap().ends_with(",550,"));
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
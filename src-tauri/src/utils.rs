@@ -1,8 +1,16 @@
 //! Utility functions for codex-desktop
 
-use std::path::Path;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::database::SymlinkPolicy;
 use crate::Result;
 
+/// Whether the platform's default filesystem compares names case-insensitively
+const CASE_INSENSITIVE_FS: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
 /// Run blocking IO work on a dedicated thread.
 pub async fn spawn_blocking_io<F, T>(f: F) -> Result<T>
 where
@@ -24,7 +32,12 @@ pub fn validate_and_canonicalize_path(path: &str) -> Result<std::path::PathBuf>
     // Canonicalize to resolve symlinks and traversal
     let canonical_path = project_path
         .canonicalize()
-        .map_err(|_| crate::Error::InvalidPath(format!("Invalid or non-existent path: {path}")))?;
+        .map_err(|err| match err.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                crate::Error::PermissionDenied(path.to_string())
+            }
+            _ => crate::Error::InvalidPath(format!("Invalid or non-existent path: {path}")),
+        })?;
 
     // Ensure it's absolute (canonicalize returns absolute path)
     if !canonical_path.is_absolute() {
@@ -34,6 +47,149 @@ pub fn validate_and_canonicalize_path(path: &str) -> Result<std::path::PathBuf>
     Ok(canonical_path)
 }
 
+/// Check that `path` is `root` or lies beneath it, comparing component by component.
+///
+/// On case-insensitive filesystems `/Proj/src` and `/proj/SRC` name the same
+/// directory, so components are compared case-insensitively there.
+pub fn path_is_within(path: &Path, root: &Path, case_insensitive: bool) -> bool {
+    let mut path_components = path.components();
+    for root_component in root.components() {
+        let Some(path_component) = path_components.next() else {
+            return false;
+        };
+        let (a, b) = (path_component.as_os_str(), root_component.as_os_str());
+        let equal = if case_insensitive {
+            a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+        } else {
+            a == b
+        };
+        if !equal {
+            return false;
+        }
+    }
+    true
+}
+
+/// Resolve a validated project-relative path against a canonical project root.
+///
+/// Each existing component is inspected without following links first. Symlinks
+/// are then handled according to `policy`: rejected outright, followed only when
+/// their target stays inside the root, or followed anywhere. Components that do
+/// not exist yet are appended as-is so the result can be used as a write target.
+pub fn resolve_project_path(
+    project_root: &Path,
+    relative_path: &str,
+    policy: SymlinkPolicy,
+) -> Result<PathBuf> {
+    let mut current = project_root.to_path_buf();
+    let mut components = Path::new(relative_path).components();
+
+    while let Some(component) = components.next() {
+        let name = match component {
+            Component::Normal(name) => name,
+            Component::CurDir => continue,
+            _ => {
+                return Err(crate::Error::InvalidPath(format!(
+                    "Path must be relative to the project: {relative_path}"
+                )))
+            }
+        };
+        current.push(name);
+
+        let metadata = match std::fs::symlink_metadata(&current) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                // Remaining components do not exist yet
+                for rest in components.by_ref() {
+                    match rest {
+                        Component::Normal(rest_name) => current.push(rest_name),
+                        Component::CurDir => {}
+                        _ => {
+                            return Err(crate::Error::InvalidPath(format!(
+                                "Path must be relative to the project: {relative_path}"
+                            )))
+                        }
+                    }
+                }
+                break;
+            }
+        };
+
+        if !metadata.file_type().is_symlink() {
+            continue;
+        }
+
+        if policy == SymlinkPolicy::Deny {
+            return Err(crate::Error::InvalidPath(format!(
+                "Symbolic links are not allowed in this project: {}",
+                current.strip_prefix(project_root).unwrap_or(&current).display()
+            )));
+        }
+
+        let target = current.canonicalize().map_err(|_| {
+            crate::Error::InvalidPath(format!("Broken symbolic link: {relative_path}"))
+        })?;
+
+        if policy == SymlinkPolicy::FollowWithinRoot
+            && !path_is_within(&target, project_root, CASE_INSENSITIVE_FS)
+        {
+            return Err(crate::Error::InvalidPath(format!(
+                "Symbolic link '{}' points outside the project root",
+                current.strip_prefix(project_root).unwrap_or(&current).display()
+            )));
+        }
+        current = target;
+    }
+
+    if policy != SymlinkPolicy::FollowAll && !path_is_within(&current, project_root, CASE_INSENSITIVE_FS) {
+        return Err(crate::Error::InvalidPath(
+            "File is outside project directory".to_string(),
+        ));
+    }
+
+    Ok(current)
+}
+
+/// Hex-encoded SHA-256 of `bytes`, used as a content version for files
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Atomically replace `path` with `contents`.
+///
+/// Writes to a temp file in the same directory, syncs it, then renames it over
+/// the target so readers never observe a partially written file. Existing
+/// file permissions are preserved.
+pub fn atomic_write(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| crate::Error::InvalidPath(format!("No parent directory: {}", path.display())))?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| crate::Error::InvalidPath(format!("Not a file path: {}", path.display())))?;
+    let temp_path = parent.join(format!(
+        ".{}.codex-tmp-{}",
+        file_name.to_string_lossy(),
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +216,60 @@ mod tests {
         // This will fail because the path doesn't exist
         assert!(result.is_err() || result.unwrap().is_absolute());
     }
+
+    #[test]
+    fn test_path_is_within_case_handling() {
+        let root = Path::new("/Users/me/Project");
+        assert!(path_is_within(Path::new("/Users/me/Project/src"), root, false));
+        assert!(!path_is_within(Path::new("/Users/me/project/src"), root, false));
+        assert!(path_is_within(Path::new("/Users/me/project/src"), root, true));
+        assert!(!path_is_within(Path::new("/Users/me/ProjectX/src"), root, true));
+        assert!(!path_is_within(Path::new("/Users/me"), root, true));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_project_path_symlink_policies() {
+        use std::os::unix::fs::symlink;
+
+        let project = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let root = project.path().canonicalize().unwrap();
+        std::fs::create_dir(root.join("real")).unwrap();
+        std::fs::write(root.join("real/file.txt"), "ok").unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+        symlink(root.join("real"), root.join("inside_link")).unwrap();
+        symlink(outside.path(), root.join("escape_link")).unwrap();
+
+        let policy = SymlinkPolicy::FollowWithinRoot;
+        assert!(resolve_project_path(&root, "real/file.txt", policy).is_ok());
+        assert!(resolve_project_path(&root, "inside_link/file.txt", policy).is_ok());
+        assert!(resolve_project_path(&root, "escape_link/secret.txt", policy).is_err());
+        assert!(resolve_project_path(&root, "new/dir/file.txt", policy).is_ok());
+
+        let deny = SymlinkPolicy::Deny;
+        assert!(resolve_project_path(&root, "inside_link/file.txt", deny).is_err());
+
+        let follow_all = SymlinkPolicy::FollowAll;
+        assert!(resolve_project_path(&root, "escape_link/secret.txt", follow_all).is_ok());
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        std::fs::write(&path, "old").unwrap();
+
+        atomic_write(&path, b"new contents").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new contents");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
 }
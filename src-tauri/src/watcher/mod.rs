@@ -0,0 +1,327 @@
+//! Per-project filesystem watchers
+//!
+//! Each watched project gets a recursive notify watcher whose raw events are
+//! funneled into a debounce task. Once changes settle, the task emits a single
+//! `project:file-changed` event listing the project-relative paths that moved,
+//! so the GUI can refresh diffs, git status and the file tree in one pass.
+//! Each batch is also remembered per project, newest first, for
+//! `get_recently_modified_files`, and marks the project's file index stale.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::events::AppEventEmitter;
+use crate::file_index::{is_ignored_change, FileIndexManager};
+use crate::global_state::unix_timestamp_secs;
+use crate::Result;
+
+/// Event emitted after a burst of changes settles
+pub const FILE_CHANGED_EVENT: &str = "project:file-changed";
+
+/// Quiet period required before a batch is flushed
+const DEBOUNCE_QUIET: Duration = Duration::from_millis(300);
+
+/// Upper bound on how long a continuous stream of changes can delay a flush
+const DEBOUNCE_MAX_WAIT: Duration = Duration::from_secs(2);
+
+/// Maximum number of paths reported in a single event
+const MAX_PATHS_PER_EVENT: usize = 500;
+
+/// Maximum number of changed paths remembered per project
+const MAX_RECENT_CHANGES: usize = 1000;
+
+/// Changed path -> unix timestamp of the batch that last reported it
+type RecentChanges = Arc<Mutex<HashMap<String, i64>>>;
+
+/// Payload of the `project:file-changed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChangedPayload {
+    pub project_id: String,
+    /// Changed paths relative to the project root, sorted
+    pub paths: Vec<String>,
+    /// Whether more paths changed than `paths` lists
+    pub truncated: bool,
+}
+
+/// A running watcher; dropping it closes the channel and ends the debounce task
+struct ProjectWatcher {
+    root: PathBuf,
+    recent: RecentChanges,
+    _watcher: notify::RecommendedWatcher,
+}
+
+/// Active project watchers keyed by project id
+pub struct WatcherManager {
+    watchers: Mutex<HashMap<String, ProjectWatcher>>,
+    file_index: Arc<FileIndexManager>,
+}
+
+impl WatcherManager {
+    pub fn new(file_index: Arc<FileIndexManager>) -> Self {
+        Self {
+            watchers: Mutex::new(HashMap::new()),
+            file_index,
+        }
+    }
+
+    /// Start watching `root` for a project; a no-op if it is already watched
+    pub fn watch(&self, project_id: &str, root: &Path, events: AppEventEmitter) -> Result<()> {
+        let mut watchers = self.watchers.lock();
+        if watchers.get(project_id).is_some_and(|w| w.root == root) {
+            return Ok(());
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel::<String>();
+        let watcher = start_watcher(root, tx)?;
+
+        let recent = RecentChanges::default();
+        let project_id_owned = project_id.to_string();
+        let recent_owned = recent.clone();
+        let root_owned = root.to_path_buf();
+        let file_index = self.file_index.clone();
+        tauri::async_runtime::spawn(async move {
+            debounce_changes(rx, project_id_owned, events, recent_owned, root_owned, file_index)
+                .await;
+        });
+
+        tracing::info!("Watching project {} at {}", project_id, root.display());
+        watchers.insert(
+            project_id.to_string(),
+            ProjectWatcher {
+                root: root.to_path_buf(),
+                recent,
+                _watcher: watcher,
+            },
+        );
+        Ok(())
+    }
+
+    /// Number of projects currently watched
+    pub fn watched_count(&self) -> usize {
+        self.watchers.lock().len()
+    }
+
+    /// Whether `root` is watched for the project
+    pub fn is_watching(&self, project_id: &str, root: &Path) -> bool {
+        self.watchers
+            .lock()
+            .get(project_id)
+            .is_some_and(|w| w.root == root)
+    }
+
+    /// Paths changed since the project started being watched, as
+    /// (relative path, unix timestamp), newest first
+    ///
+    /// `None` when the project is not watched. Deleted paths are included;
+    /// callers check what still exists.
+    pub fn recent_changes(&self, project_id: &str) -> Option<Vec<(String, i64)>> {
+        let recent = self.watchers.lock().get(project_id)?.recent.clone();
+        let mut changes: Vec<(String, i64)> = recent
+            .lock()
+            .iter()
+            .map(|(path, at)| (path.clone(), *at))
+            .collect();
+        changes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Some(changes)
+    }
+
+    /// Stop watching a project; returns whether a watcher was running
+    pub fn unwatch(&self, project_id: &str) -> bool {
+        let removed = self.watchers.lock().remove(project_id).is_some();
+        if removed {
+            tracing::info!("Stopped watching project {}", project_id);
+        }
+        removed
+    }
+
+    /// Stop every watcher; returns how many were running
+    pub fn unwatch_all(&self) -> usize {
+        let removed = std::mem::take(&mut *self.watchers.lock()).len();
+        if removed > 0 {
+            tracing::info!("Stopped watching {} projects", removed);
+        }
+        removed
+    }
+}
+
+/// Create a recursive watcher that forwards relevant relative paths to `tx`
+fn start_watcher(
+    root: &Path,
+    tx: mpsc::UnboundedSender<String>,
+) -> Result<notify::RecommendedWatcher> {
+    let watch_root = root.to_path_buf();
+    let (gitignore, _) = ignore::gitignore::Gitignore::new(root.join(".gitignore"));
+
+    let handler = move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(err) => {
+                tracing::debug!("File watcher error under {}: {}", watch_root.display(), err);
+                return;
+            }
+        };
+        if matches!(event.kind, notify::EventKind::Access(_)) {
+            return;
+        }
+        for path in &event.paths {
+            if is_ignored_change(&watch_root, &gitignore, path) {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&watch_root) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if relative.is_empty() {
+                continue;
+            }
+            let _ = tx.send(relative);
+        }
+    };
+
+    let mut watcher = notify::recommended_watcher(handler).map_err(|err| {
+        crate::Error::Other(format!(
+            "Failed to create file watcher for {}: {err}",
+            root.display()
+        ))
+    })?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|err| crate::Error::Other(format!("Failed to watch {}: {err}", root.display())))?;
+
+    Ok(watcher)
+}
+
+/// Emit one event per settled batch until the watcher is dropped
+async fn debounce_changes(
+    mut rx: mpsc::UnboundedReceiver<String>,
+    project_id: String,
+    events: AppEventEmitter,
+    recent: RecentChanges,
+    root: PathBuf,
+    file_index: Arc<FileIndexManager>,
+) {
+    while let Some(paths) = next_batch(&mut rx, DEBOUNCE_QUIET, DEBOUNCE_MAX_WAIT).await {
+        file_index.invalidate(&root);
+        remember_changes(&mut recent.lock(), &paths, unix_timestamp_secs());
+        let truncated = paths.len() > MAX_PATHS_PER_EVENT;
+        let paths: Vec<String> = paths.into_iter().take(MAX_PATHS_PER_EVENT).collect();
+        tracing::debug!("Project {} changed: {} paths", project_id, paths.len());
+        events
+            .emit(
+                FILE_CHANGED_EVENT,
+                FileChangedPayload {
+                    project_id: project_id.clone(),
+                    paths,
+                    truncated,
+                },
+            )
+            .await;
+    }
+}
+
+/// Record a batch, evicting the oldest paths beyond `MAX_RECENT_CHANGES`
+fn remember_changes(recent: &mut HashMap<String, i64>, paths: &BTreeSet<String>, now: i64) {
+    for path in paths {
+        recent.insert(path.clone(), now);
+    }
+    if recent.len() <= MAX_RECENT_CHANGES {
+        return;
+    }
+    let mut by_age: Vec<(i64, String)> =
+        recent.iter().map(|(path, at)| (*at, path.clone())).collect();
+    by_age.sort();
+    for (_, path) in by_age.into_iter().take(recent.len() - MAX_RECENT_CHANGES) {
+        recent.remove(&path);
+    }
+}
+
+/// Wait for a change, then collect until `quiet` passes without one or
+/// `max_wait` elapses. Returns `None` once the sender side is gone.
+pub(crate) async fn next_batch(
+    rx: &mut mpsc::UnboundedReceiver<String>,
+    quiet: Duration,
+    max_wait: Duration,
+) -> Option<BTreeSet<String>> {
+    let first = rx.recv().await?;
+    let deadline = Instant::now() + max_wait;
+    let mut paths = BTreeSet::from([first]);
+
+    loop {
+        let wait = quiet.min(deadline.saturating_duration_since(Instant::now()));
+        match tokio::time::timeout(wait, rx.recv()).await {
+            Ok(Some(path)) => {
+                paths.insert(path);
+            }
+            // Channel closed or quiet period elapsed: flush what we have
+            Ok(None) | Err(_) => break,
+        }
+    }
+
+    Some(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_next_batch_coalesces_until_quiet() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tx.send("src/a.rs".to_string()).unwrap();
+        tx.send("src/b.rs".to_string()).unwrap();
+        tx.send("src/a.rs".to_string()).unwrap();
+
+        let batch = next_batch(&mut rx, Duration::from_millis(20), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(batch.into_iter().collect::<Vec<_>>(), vec!["src/a.rs", "src/b.rs"]);
+
+        drop(tx);
+        assert!(next_batch(&mut rx, Duration::from_millis(20), Duration::from_secs(1))
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn test_remember_changes_evicts_oldest() {
+        let mut recent = HashMap::new();
+        let old: BTreeSet<String> = (0..MAX_RECENT_CHANGES).map(|i| format!("old{i}")).collect();
+        remember_changes(&mut recent, &old, 1);
+        remember_changes(&mut recent, &BTreeSet::from(["old0".to_string(), "new".to_string()]), 2);
+
+        assert_eq!(recent.len(), MAX_RECENT_CHANGES);
+        assert_eq!(recent["old0"], 2);
+        assert_eq!(recent["new"], 2);
+        assert!(!recent.contains_key("old1"));
+    }
+
+    #[tokio::test]
+    async fn test_next_batch_flushes_at_max_wait() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let sender = tokio::spawn(async move {
+            for i in 0..50 {
+                if tx.send(format!("file{i}")).is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+
+        let started = Instant::now();
+        let batch = next_batch(&mut rx, Duration::from_millis(100), Duration::from_millis(80))
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(400));
+        assert!(batch.len() < 50);
+        sender.abort();
+    }
+}
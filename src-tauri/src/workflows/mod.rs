@@ -0,0 +1,340 @@
+//! Built-in parameterized workflows
+//!
+//! A workflow is a thread template: it declares the parameters it needs and
+//! assembles the first prompt server-side from live project context (working
+//! tree diff, test output, file contents) so Codex starts with everything in
+//! hand instead of rediscovering it.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::database::SymlinkPolicy;
+use crate::utils::resolve_project_path;
+use crate::{Error, Result};
+
+/// Maximum bytes of any single context block included in a prompt
+const MAX_CONTEXT_BYTES: usize = 48 * 1024;
+
+/// Timeout for the test command run by `fix-failing-tests`
+const TEST_COMMAND_TIMEOUT_SECS: u64 = 120;
+
+/// Manifests inspected by `upgrade-dependency`, relative to the project root
+const DEPENDENCY_MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "requirements.txt",
+    "go.mod",
+    "Gemfile",
+    "pom.xml",
+    "build.gradle",
+];
+
+/// A parameter accepted by a workflow
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowParam {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub required: bool,
+}
+
+/// Description of a built-in workflow, as listed to the GUI
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowDefinition {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub params: Vec<WorkflowParam>,
+}
+
+/// All built-in workflows
+pub fn builtin_workflows() -> Vec<WorkflowDefinition> {
+    vec![
+        WorkflowDefinition {
+            id: "fix-failing-tests",
+            title: "Fix failing tests",
+            description: "Run the test suite and fix whatever fails, using the current diff as context",
+            params: vec![WorkflowParam {
+                name: "testCommand",
+                label: "Test command",
+                description: "Shell command that runs the tests, e.g. `cargo test` or `npm test`",
+                required: true,
+            }],
+        },
+        WorkflowDefinition {
+            id: "upgrade-dependency",
+            title: "Upgrade dependency",
+            description: "Upgrade a dependency and fix any breakage it causes",
+            params: vec![
+                WorkflowParam {
+                    name: "dependency",
+                    label: "Dependency",
+                    description: "Package or crate name to upgrade",
+                    required: true,
+                },
+                WorkflowParam {
+                    name: "version",
+                    label: "Target version",
+                    description: "Version to upgrade to; latest compatible if omitted",
+                    required: false,
+                },
+            ],
+        },
+        WorkflowDefinition {
+            id: "add-test-coverage",
+            title: "Add test coverage",
+            description: "Write tests covering an existing file",
+            params: vec![WorkflowParam {
+                name: "file",
+                label: "File",
+                description: "Project-relative path of the file to cover",
+                required: true,
+            }],
+        },
+    ]
+}
+
+/// Look up a built-in workflow by id
+pub fn find_workflow(id: &str) -> Result<WorkflowDefinition> {
+    builtin_workflows()
+        .into_iter()
+        .find(|workflow| workflow.id == id)
+        .ok_or_else(|| Error::Other(format!("Unknown workflow: {id}")))
+}
+
+/// Check required params are present and no unknown params were passed
+fn validate_params(workflow: &WorkflowDefinition, params: &HashMap<String, String>) -> Result<()> {
+    for param in &workflow.params {
+        let missing = params.get(param.name).is_none_or(|v| v.trim().is_empty());
+        if param.required && missing {
+            return Err(Error::Other(format!(
+                "Workflow {} requires parameter {}",
+                workflow.id, param.name
+            )));
+        }
+    }
+    if let Some(unknown) = params
+        .keys()
+        .find(|key| !workflow.params.iter().any(|p| p.name == key.as_str()))
+    {
+        return Err(Error::Other(format!(
+            "Workflow {} does not accept parameter {unknown}",
+            workflow.id
+        )));
+    }
+    Ok(())
+}
+
+fn param<'a>(params: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    params.get(name).map(|v| v.trim()).filter(|v| !v.is_empty())
+}
+
+/// Assemble the opening prompt for a workflow from live project context
+pub async fn assemble_prompt(
+    workflow: &WorkflowDefinition,
+    project_root: &Path,
+    symlink_policy: SymlinkPolicy,
+    params: &HashMap<String, String>,
+) -> Result<String> {
+    validate_params(workflow, params)?;
+
+    match workflow.id {
+        "fix-failing-tests" => {
+            let test_command = param(params, "testCommand").unwrap_or_default();
+            let test_output = run_test_command(project_root, test_command).await?;
+            let diff = working_tree_diff(project_root).await?;
+
+            let mut prompt = format!(
+                "The test suite is failing. Run `{test_command}` to reproduce, find the root cause, \
+                 and fix it. Prefer fixing the code under test over weakening assertions.\n\n"
+            );
+            push_block(&mut prompt, &format!("Output of `{test_command}`"), "text", &test_output);
+            if !diff.trim().is_empty() {
+                push_block(&mut prompt, "Uncommitted changes", "diff", &diff);
+            }
+            Ok(prompt)
+        }
+        "upgrade-dependency" => {
+            let dependency = param(params, "dependency").unwrap_or_default();
+            let target = match param(params, "version") {
+                Some(version) => format!("version {version}"),
+                None => "the latest compatible version".to_string(),
+            };
+            let root = project_root.to_path_buf();
+            let dep = dependency.to_string();
+            let manifests =
+                crate::utils::spawn_blocking_io(move || Ok(manifests_mentioning(&root, &dep))).await?;
+
+            let mut prompt = format!(
+                "Upgrade the dependency `{dependency}` to {target}. Update the manifest and lockfile, \
+                 adapt any code broken by API changes, and run the build and tests to confirm.\n\n"
+            );
+            if manifests.is_empty() {
+                prompt.push_str(&format!(
+                    "No manifest in the project root mentions `{dependency}`; locate where it is declared first.\n"
+                ));
+            }
+            for (name, contents) in &manifests {
+                push_block(&mut prompt, name, "", contents);
+            }
+            Ok(prompt)
+        }
+        "add-test-coverage" => {
+            let file = param(params, "file").unwrap_or_default();
+            let normalized = crate::commands::projects::validate_relative_project_path(file)?;
+            let root = project_root.to_path_buf();
+            let contents = crate::utils::spawn_blocking_io(move || {
+                let path = resolve_project_path(&root, &normalized, symlink_policy)?;
+                let bytes = std::fs::read(&path)
+                    .map_err(|_| Error::InvalidPath(format!("File does not exist: {normalized}")))?;
+                Ok(String::from_utf8_lossy(&bytes).into_owned())
+            })
+            .await?;
+
+            let mut prompt = format!(
+                "Add tests covering `{file}`. Follow the project's existing test layout and \
+                 conventions, cover edge cases and error paths, and run the new tests to confirm \
+                 they pass.\n\n"
+            );
+            push_block(&mut prompt, file, "", &contents);
+            Ok(prompt)
+        }
+        other => Err(Error::Other(format!("Unknown workflow: {other}"))),
+    }
+}
+
+/// Append a titled fenced block, keeping only the tail of oversized content
+fn push_block(prompt: &mut String, title: &str, lang: &str, content: &str) {
+    let content = truncate_tail(content, MAX_CONTEXT_BYTES);
+    prompt.push_str(&format!("### {title}\n```{lang}\n{}\n```\n\n", content.trim_end()));
+}
+
+/// Keep the last `max_bytes` of `content` on a char boundary
+fn truncate_tail(content: &str, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content.to_string();
+    }
+    let mut start = content.len() - max_bytes;
+    while !content.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... truncated ...]\n{}", &content[start..])
+}
+
+/// Root-level manifests whose contents mention `dependency`
+fn manifests_mentioning(root: &Path, dependency: &str) -> Vec<(String, String)> {
+    DEPENDENCY_MANIFESTS
+        .iter()
+        .filter_map(|name| {
+            let contents = std::fs::read_to_string(root.join(name)).ok()?;
+            contents
+                .contains(dependency)
+                .then(|| (name.to_string(), contents))
+        })
+        .collect()
+}
+
+async fn working_tree_diff(project_root: &Path) -> Result<String> {
+    let path = project_root.to_string_lossy().to_string();
+    let diff = crate::commands::projects::get_project_git_diff(path).await?;
+    Ok(diff.diff)
+}
+
+/// Run the test command and return its combined output with the exit status
+async fn run_test_command(cwd: &Path, command: &str) -> Result<String> {
+    let (shell, shell_arg) = if cfg!(target_os = "windows") {
+        ("cmd", "/C")
+    } else {
+        ("/bin/sh", "-c")
+    };
+
+    let child = tokio::process::Command::new(shell)
+        .arg(shell_arg)
+        .arg(command)
+        .current_dir(cwd)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::Other(format!("Failed to spawn test command: {e}")))?;
+
+    let output = tokio::time::timeout(
+        Duration::from_secs(TEST_COMMAND_TIMEOUT_SECS),
+        child.wait_with_output(),
+    )
+    .await
+    .map_err(|_| {
+        Error::Other(format!(
+            "Test command timed out after {TEST_COMMAND_TIMEOUT_SECS} seconds"
+        ))
+    })?
+    .map_err(|e| Error::Other(format!("Failed to run test command: {e}")))?;
+
+    let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+    combined.push_str(&String::from_utf8_lossy(&output.stderr));
+    let status = match output.status.code() {
+        Some(code) => format!("exit code {code}"),
+        None => "terminated by signal".to_string(),
+    };
+    combined.push_str(&format!("\n[{status}]"));
+    Ok(combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let workflow = find_workflow("upgrade-dependency").unwrap();
+        assert!(validate_params(&workflow, &params(&[("dependency", "serde")])).is_ok());
+        assert!(validate_params(&workflow, &params(&[("dependency", "  ")])).is_err());
+        assert!(validate_params(&workflow, &params(&[("version", "2")])).is_err());
+        assert!(validate_params(&workflow, &params(&[("dependency", "serde"), ("x", "1")])).is_err());
+        assert!(find_workflow("nope").is_err());
+    }
+
+    #[test]
+    fn test_truncate_tail_keeps_end() {
+        assert_eq!(truncate_tail("short", 10), "short");
+        let truncated = truncate_tail("aaaaébbbb", 5);
+        assert!(truncated.ends_with("bbbb"));
+        assert!(truncated.starts_with("[... truncated ...]"));
+    }
+
+    #[tokio::test]
+    async fn test_assemble_upgrade_dependency_prompt() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("package.json"), r#"{"dependencies":{"react":"^18.0.0"}}"#).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "[dependencies]\nserde = \"1\"\n").unwrap();
+
+        let workflow = find_workflow("upgrade-dependency").unwrap();
+        let prompt = assemble_prompt(
+            &workflow,
+            root,
+            SymlinkPolicy::default(),
+            &params(&[("dependency", "react"), ("version", "19")]),
+        )
+        .await
+        .unwrap();
+
+        assert!(prompt.contains("`react` to version 19"));
+        assert!(prompt.contains("### package.json"));
+        assert!(!prompt.contains("### Cargo.toml"));
+    }
+}
@@ -6,7 +6,6 @@ import { ToastProvider } from './components/ui/Toast'
 import { ErrorBoundary } from './components/ui/ErrorBoundary'
 import { ConnectionStatus } from './components/ui/ConnectionStatus'
 import { GlobalErrorHandler } from './components/ui/GlobalErrorHandler'
-import { NotificationListener } from './components/NotificationListener'
 import { useProjectsStore } from './stores/projects'
 import { useSessionsStore } from './stores/sessions'
 import { useThreadStore, cleanupThreadResources } from './stores/thread/index'
@@ -70,12 +69,17 @@ function App() {
       // Approvals - routes by threadId internally
       onCommandApprovalRequested: (event) => useThreadStore.getState().handleCommandApprovalRequested(event),
       onFileChangeApprovalRequested: (event) => useThreadStore.getState().handleFileChangeApprovalRequested(event),
+      onApprovalAnsweredByShortcut: (event) =>
+        useThreadStore.getState().handleApprovalAnsweredByShortcut(event),
       // Turn lifecycle - routes by threadId internally
       onTurnStarted: (event) => useThreadStore.getState().handleTurnStarted(event),
       onTurnCompleted: (event) => useThreadStore.getState().handleTurnCompleted(event),
       onTurnDiffUpdated: (event) => useThreadStore.getState().handleTurnDiffUpdated(event),
       onTurnPlanUpdated: (event) => useThreadStore.getState().handleTurnPlanUpdated(event),
       onThreadCompacted: (event) => useThreadStore.getState().handleThreadCompacted(event),
+      // Session status - rolled up from turn outcomes by the backend
+      onSessionStatusChanged: (event) =>
+        useSessionsStore.getState().applySessionStatus(event.sessionId, event.status),
       // Command execution output - routes by threadId internally
       onCommandExecutionOutputDelta: (event) =>
         useThreadStore.getState().handleCommandExecutionOutputDelta(event),
@@ -163,7 +167,6 @@ function App() {
     <ErrorBoundary>
       <ToastProvider>
         <GlobalErrorHandler />
-        <NotificationListener />
         <RouterProvider router={router} />
         <ConnectionStatus />
       </ToastProvider>
@@ -502,7 +502,7 @@ export default memo(function ChatInputArea({
           />
           <FileMentionPopup
             query={fileMentionQuery}
-            projectPath={projects.find((p) => p.id === selectedProjectId)?.path ?? ''}
+            projectId={selectedProjectId ?? ''}
             onSelect={handleFileMentionSelect}
             onClose={() => setShowFileMention(false)}
             isVisible={showFileMention && !!selectedProjectId}
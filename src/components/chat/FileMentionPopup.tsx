@@ -10,8 +10,8 @@ import { useToast } from '../ui/useToast'
 interface FileMentionPopupProps {
   /** The text after @ that we're filtering by */
   query: string
-  /** Project path for file listing */
-  projectPath: string
+  /** Project whose files are searched */
+  projectId: string
   /** Called when a file is selected */
   onSelect: (file: FileEntry) => void
   /** Called when popup should close */
@@ -22,7 +22,7 @@ interface FileMentionPopupProps {
 
 export function FileMentionPopup({
   query,
-  projectPath,
+  projectId,
   onSelect,
   onClose,
   isVisible,
@@ -32,7 +32,7 @@ export function FileMentionPopup({
   const listRef = useRef<HTMLDivElement>(null)
   const debounceRef = useRef<ReturnType<typeof setTimeout> | undefined>(undefined)
   const requestIdRef = useRef(0)
-  const projectPathRef = useRef(projectPath)
+  const projectIdRef = useRef(projectId)
   const { toast } = useToast()
   const toastRef = useRef(toast)
   const prefersReducedMotion = useReducedMotion()
@@ -52,13 +52,13 @@ export function FileMentionPopup({
 
   // Fetch files with debouncing and error handling
   useEffect(() => {
-    projectPathRef.current = projectPath
+    projectIdRef.current = projectId
     toastRef.current = toast
-  }, [projectPath, toast])
+  }, [projectId, toast])
 
   const fetchFiles = useCallback(async (searchQuery: string) => {
-    const currentProjectPath = projectPathRef.current
-    if (!currentProjectPath) return
+    const currentProjectId = projectIdRef.current
+    if (!currentProjectId) return
 
     if (!isTauriAvailable()) {
       setFiles([])
@@ -70,22 +70,18 @@ export function FileMentionPopup({
 
     requestIdRef.current += 1
     const requestId = requestIdRef.current
-    const pathAtRequest = currentProjectPath
+    const projectAtRequest = currentProjectId
 
     setIsLoading(true)
     try {
-      const result = await projectApi.listFiles(
-        currentProjectPath,
-        searchQuery || undefined,
-        50
-      )
-      if (requestId !== requestIdRef.current || projectPathRef.current !== pathAtRequest) {
+      const result = await projectApi.searchFiles(currentProjectId, searchQuery, 50)
+      if (requestId !== requestIdRef.current || projectIdRef.current !== projectAtRequest) {
         return
       }
       setFiles(result)
       setError(null) // Clear any previous errors on success
     } catch (err) {
-      if (requestId !== requestIdRef.current || projectPathRef.current !== pathAtRequest) {
+      if (requestId !== requestIdRef.current || projectIdRef.current !== projectAtRequest) {
         return
       }
       const errorMessage = err instanceof Error ? err.message : 'Failed to load files'
@@ -97,7 +93,7 @@ export function FileMentionPopup({
       })
     } finally {
       // Only update loading state if this request is still current
-      if (requestId === requestIdRef.current && projectPathRef.current === pathAtRequest) {
+      if (requestId === requestIdRef.current && projectIdRef.current === projectAtRequest) {
         setIsLoading(false)
         setIsRetrying(false)
       }
@@ -109,7 +105,7 @@ export function FileMentionPopup({
     setFiles([])
     setError(null)
     setIsLoading(false)
-  }, [projectPath])
+  }, [projectId])
 
   // Retry handler
   const handleRetry = useCallback(() => {
@@ -1,8 +1,18 @@
 import { useState, useEffect, useCallback, useRef } from 'react'
-import { GitBranch, GitCommit as GitCommitIcon, GitPullRequest, Upload, X, Check, FileText, Plus, Minus, CircleDot, ChevronDown, ChevronRight, Loader2 } from 'lucide-react'
+import { listen } from '@tauri-apps/api/event'
+import { GitBranch, GitCommit as GitCommitIcon, GitPullRequest, Upload, X, Check, FileText, Plus, Minus, CircleDot, ChevronDown, ChevronRight, Loader2, Sparkles } from 'lucide-react'
 import { cn } from '../../lib/utils'
 import { useProjectsStore } from '../../stores/projects'
-import { projectApi, type GitFileStatus, type GitRemoteInfo } from '../../lib/api'
+import {
+  projectApi,
+  type GitFileStatus,
+  type GitHooks,
+  type GitRemoteInfo,
+  type SigningConfig,
+  type SigningFailureKind,
+} from '../../lib/api'
+import { getSigningError } from '../../lib/errorUtils'
+import type { GitHookOutputEvent } from '../../lib/events'
 import { useFocusTrap } from '../../hooks/useFocusTrap'
 import { Button } from '../ui/Button'
 import { IconButton } from '../ui/IconButton'
@@ -26,6 +36,23 @@ const STATUS_ICONS: Record<string, { icon: React.ReactNode; color: string }> = {
   '?': { icon: <CircleDot size={14} />, color: 'text-status-warning' },
 }
 
+const SIGNING_HINTS: Record<SigningFailureKind, string> = {
+  missingKey: 'Set user.signingKey to a key you have, or turn off commit.gpgSign.',
+  programNotFound: 'Install the signing program or point gpg.program at it.',
+  passphraseRequired: 'Unlock the key in your agent (gpg-agent or ssh-agent) and try again.',
+  other: 'Check your signing setup with git commit -S in a terminal.',
+}
+
+/** Hook output lines kept in the dialog */
+const MAX_HOOK_OUTPUT_LINES = 200
+
+/** Message for a failed commit, with a hint when signing is what failed */
+function commitErrorMessage(err: unknown, fallback: string): string {
+  const signing = getSigningError(err)
+  if (signing) return `Commit signing failed: ${signing.message}\n${SIGNING_HINTS[signing.kind]}`
+  return err instanceof Error ? err.message : fallback
+}
+
 export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: CommitDialogProps) {
   const { selectedProjectId, projects, gitInfo } = useProjectsStore()
   const selectedProject = projects.find((p) => p.id === selectedProjectId)
@@ -40,9 +67,14 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
   const [selectedFiles, setSelectedFiles] = useState<Set<string>>(new Set())
   const [isLoadingStatus, setIsLoadingStatus] = useState(false)
   const [isCommitting, setIsCommitting] = useState(false)
+  const [isSuggesting, setIsSuggesting] = useState(false)
   const [isPushing, setIsPushing] = useState(false)
   const [step, setStep] = useState<CommitStep>('review')
   const [remoteInfo, setRemoteInfo] = useState<GitRemoteInfo | null>(null)
+  const [signing, setSigning] = useState<SigningConfig | null>(null)
+  const [hooks, setHooks] = useState<GitHooks | null>(null)
+  const [skipHooks, setSkipHooks] = useState(false)
+  const [hookOutput, setHookOutput] = useState<string[]>([])
   const [error, setError] = useState<string | null>(null)
   const [showStagedFiles, setShowStagedFiles] = useState(true)
   const [showUnstagedFiles, setShowUnstagedFiles] = useState(true)
@@ -73,6 +105,9 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
   const unstagedFiles = files.filter((f) => !f.isStaged)
   const selectedCount = selectedFiles.size
   const primaryActionLabel = remoteInfo?.remote ? 'Commit & Push' : 'Commit'
+  const activeHooks = (hooks?.hooks ?? []).filter((h) => h.executable && h.operation !== null)
+  const interactiveHooks = activeHooks.filter((h) => h.readsTerminal)
+  const noVerify = skipHooks || undefined
 
   const fetchStatus = useCallback(async () => {
     if (!selectedProject?.path) return
@@ -80,12 +115,16 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
     setIsLoadingStatus(true)
     setError(null)
     try {
-      const [statusResult, remoteResult] = await Promise.all([
+      const [statusResult, remoteResult, signingResult, hooksResult] = await Promise.all([
         projectApi.gitStatus(selectedProject.path),
         projectApi.gitRemoteInfo(selectedProject.path),
+        projectApi.getSigningConfig(selectedProject.path).catch(() => null),
+        projectApi.listGitHooks(selectedProject.path).catch(() => null),
       ])
       setFiles(statusResult)
       setRemoteInfo(remoteResult)
+      setSigning(signingResult)
+      setHooks(hooksResult)
 
       // Auto-select all staged files
       const stagedPaths = new Set(
@@ -107,9 +146,28 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
       setCommitSha(null)
       setError(tauriAvailable ? null : 'Unavailable in web mode')
       setPushCompleted(false)
+      setSkipHooks(false)
+      setHookOutput([])
     }
   }, [isOpen, fetchStatus, tauriAvailable])
 
+  // Hooks can take a while; show what they print as they run
+  useEffect(() => {
+    if (!isOpen || !tauriAvailable) return
+    let unlisten: (() => void) | undefined
+    let mounted = true
+    void listen<GitHookOutputEvent>('git-hook-output', (event) => {
+      setHookOutput((prev) => [...prev, event.payload.line].slice(-MAX_HOOK_OUTPUT_LINES))
+    }).then((fn) => {
+      if (mounted) unlisten = fn
+      else fn()
+    })
+    return () => {
+      mounted = false
+      unlisten?.()
+    }
+  }, [isOpen, tauriAvailable])
+
   const handleToggleFile = (file: GitFileStatus) => {
     const key = `${file.isStaged ? 'staged' : 'unstaged'}:${file.path}`
     setSelectedFiles((prev) => {
@@ -140,6 +198,26 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
     })
   }
 
+  const handleSuggestMessage = useCallback(async () => {
+    if (!selectedProject?.path) return
+
+    setIsSuggesting(true)
+    try {
+      const suggestion = await projectApi.suggestCommitMessage(selectedProject.path)
+      setCommitMessage(suggestion.message)
+      if (suggestion.fallbackReason) {
+        toast.info('Message built from file stats', { message: suggestion.fallbackReason })
+      }
+      commitMessageRef.current?.focus()
+    } catch (err) {
+      toast.error('Could not suggest a message', {
+        message: err instanceof Error ? err.message : String(err),
+      })
+    } finally {
+      setIsSuggesting(false)
+    }
+  }, [selectedProject?.path, toast])
+
   const handleCommit = useCallback(async () => {
     if (!selectedProject?.path || selectedFiles.size === 0) return
 
@@ -148,6 +226,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
 
     setIsCommitting(true)
     setError(null)
+    setHookOutput([])
 
     try {
       // Stage selected unstaged files
@@ -170,7 +249,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
 
       // Perform commit
       const message = commitMessage.trim() || 'Update'
-      const sha = await projectApi.gitCommit(selectedProject.path, message)
+      const sha = await projectApi.gitCommit(selectedProject.path, message, { noVerify })
       setCommitSha(sha)
 
       toast.success('Commit created', {
@@ -179,13 +258,13 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
 
       setStep('done')
     } catch (err) {
-      const errMsg = err instanceof Error ? err.message : 'Commit failed'
+      const errMsg = commitErrorMessage(err, 'Commit failed')
       setError(errMsg)
       toast.error('Commit failed', { message: errMsg })
     } finally {
       setIsCommitting(false)
     }
-  }, [commitMessage, files, selectedFiles, selectedProject?.path, toast])
+  }, [commitMessage, files, noVerify, selectedFiles, selectedProject?.path, toast])
 
   const handlePush = useCallback(async () => {
     if (!selectedProject?.path || !remoteInfo?.remote || !remoteInfo?.branch) return
@@ -197,7 +276,8 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
       await projectApi.gitPush(
         selectedProject.path,
         remoteInfo.remote,
-        remoteInfo.branch
+        remoteInfo.branch,
+        { noVerify }
       )
 
       toast.success('Push successful', {
@@ -213,7 +293,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
     } finally {
       setIsPushing(false)
     }
-  }, [remoteInfo?.branch, remoteInfo?.remote, selectedProject?.path, toast])
+  }, [noVerify, remoteInfo?.branch, remoteInfo?.remote, selectedProject?.path, toast])
 
   const handleCommitAndPush = useCallback(async () => {
     if (!selectedProject?.path || selectedFiles.size === 0) return
@@ -223,6 +303,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
 
     setIsCommitting(true)
     setError(null)
+    setHookOutput([])
 
     try {
       // Stage/unstage as needed
@@ -243,7 +324,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
       }
 
       const message = commitMessage.trim() || 'Update'
-      const sha = await projectApi.gitCommit(selectedProject.path, message)
+      const sha = await projectApi.gitCommit(selectedProject.path, message, { noVerify })
       setCommitSha(sha)
 
       toast.success('Commit created', {
@@ -259,7 +340,8 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
         await projectApi.gitPush(
           selectedProject.path,
           remoteInfo.remote,
-          remoteInfo.branch
+          remoteInfo.branch,
+          { noVerify }
         )
 
         toast.success('Push successful', {
@@ -273,14 +355,14 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
         setError('No remote configured for push')
       }
     } catch (err) {
-      const errMsg = err instanceof Error ? err.message : 'Operation failed'
+      const errMsg = commitErrorMessage(err, 'Operation failed')
       setError(errMsg)
       toast.error('Operation failed', { message: errMsg })
     } finally {
       setIsCommitting(false)
       setIsPushing(false)
     }
-  }, [commitMessage, files, remoteInfo?.branch, remoteInfo?.remote, selectedFiles, selectedProject?.path, toast])
+  }, [commitMessage, files, noVerify, remoteInfo?.branch, remoteInfo?.remote, selectedFiles, selectedProject?.path, toast])
 
   useEffect(() => {
     if (!isOpen) return
@@ -358,6 +440,12 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
     )
   }
 
+  const hookOutputPane = hookOutput.length > 0 && (
+    <pre className="mx-5 mt-3 max-h-[120px] overflow-y-auto rounded-sm border border-stroke/20 bg-surface-hover/[0.03] px-3 py-2 text-[11px] text-text-2 font-mono whitespace-pre-wrap">
+      {hookOutput.join('\n')}
+    </pre>
+  )
+
   if (!isOpen) return null
 
   if (!tauriAvailable) {
@@ -495,7 +583,16 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
         {/* Error banner */}
         {error && (
           <div className="mx-5 mt-3 rounded-sm border border-status-error/30 bg-status-error-muted px-3 py-2">
-            <p className="text-[12px] text-status-error">{error}</p>
+            <p className="whitespace-pre-line text-[12px] text-status-error">{error}</p>
+          </div>
+        )}
+
+        {step === 'review' && signing?.required && signing.problem && (
+          <div className="mx-5 mt-3 rounded-sm border border-status-warning/30 bg-status-warning-muted px-3 py-2">
+            <p className="text-[12px] text-status-warning">
+              This repository signs every commit, but signing will fail:{' '}
+              {signing.problem.message}. {SIGNING_HINTS[signing.problem.kind]}
+            </p>
           </div>
         )}
 
@@ -589,12 +686,25 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
 
             {/* Commit message */}
             <div className="px-5 pt-4">
-              <label
-                htmlFor="commit-message"
-                className="block text-[10px] font-semibold uppercase tracking-wider text-text-3 mb-2"
-              >
-                Commit Message
-              </label>
+              <div className="mb-2 flex items-center justify-between">
+                <label
+                  htmlFor="commit-message"
+                  className="block text-[10px] font-semibold uppercase tracking-wider text-text-3"
+                >
+                  Commit Message
+                </label>
+                <Button
+                  size="sm"
+                  variant="ghost"
+                  onClick={() => void handleSuggestMessage()}
+                  disabled={stagedFiles.length === 0 || isSuggesting}
+                  loading={isSuggesting}
+                  title="Suggest a conventional-commit message for the staged files"
+                >
+                  <Sparkles size={12} />
+                  Suggest
+                </Button>
+              </div>
               <textarea
                 id="commit-message"
                 value={commitMessage}
@@ -603,8 +713,28 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
                 className="w-full h-[72px] rounded-sm border border-stroke/20 bg-surface-hover/[0.03] px-3 py-2 text-[13px] text-text-1 placeholder:text-text-3 focus:border-stroke/30 focus:outline-none resize-none"
                 ref={commitMessageRef}
               />
+              {activeHooks.length > 0 && (
+                <label className="mt-2 flex items-center gap-2 text-[11px] text-text-3 cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={skipHooks}
+                    onChange={(e) => setSkipHooks(e.target.checked)}
+                  />
+                  <span>
+                    Skip hooks ({activeHooks.map((h) => h.name).join(', ')}); this is recorded
+                  </span>
+                </label>
+              )}
+              {interactiveHooks.length > 0 && !skipHooks && (
+                <p className="mt-1 text-[11px] text-status-warning">
+                  {interactiveHooks.map((h) => h.name).join(', ')} may wait for terminal input,
+                  which the app cannot give. Skip hooks if the commit hangs.
+                </p>
+              )}
             </div>
 
+            {hookOutputPane}
+
             {/* Action buttons */}
             <div className="p-5 pt-3">
               <div className="mb-2 flex items-center justify-between text-[11px] text-text-3">
@@ -650,6 +780,7 @@ export function CommitDialog({ isOpen, initialIntent = 'commit', onClose }: Comm
                 {remoteInfo.remote}/{remoteInfo.branch}
               </p>
             )}
+            <div className="w-full">{hookOutputPane}</div>
           </div>
         )}
 
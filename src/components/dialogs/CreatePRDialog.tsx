@@ -3,7 +3,7 @@ import { GitPullRequest, X, Check, ExternalLink, AlertTriangle, Copy, Loader2 }
 import { cn } from '../../lib/utils'
 import { copyTextToClipboard } from '../../lib/clipboard'
 import { useProjectsStore } from '../../stores/projects'
-import { projectApi, type GitBranch } from '../../lib/api'
+import { projectApi, type ForgeStatus, type GitBranch } from '../../lib/api'
 import { useFocusTrap } from '../../hooks/useFocusTrap'
 import { Button } from '../ui/Button'
 import { IconButton } from '../ui/IconButton'
@@ -15,7 +15,7 @@ import { useToast } from '../ui/useToast'
 import { isTauriAvailable } from '../../lib/tauri'
 
 type PRStep = 'form' | 'creating' | 'done'
-type GhStatus = 'checking' | 'ready' | 'not-installed' | 'not-authenticated'
+type ForgeCheck = 'checking' | ForgeStatus['status']
 
 interface CreatePRDialogProps {
   isOpen: boolean
@@ -39,7 +39,8 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
 
   // UI state
   const [step, setStep] = useState<PRStep>('form')
-  const [ghStatus, setGhStatus] = useState<GhStatus>('checking')
+  const [ghStatus, setGhStatus] = useState<ForgeCheck>('checking')
+  const [forge, setForge] = useState<ForgeStatus | null>(null)
   const [branches, setBranches] = useState<GitBranch[]>([])
   const [error, setError] = useState<string | null>(null)
   const [prUrl, setPrUrl] = useState<string | null>(null)
@@ -63,6 +64,7 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
     }
 
     setGhStatus('checking')
+    setForge(null)
     setError(null)
     setStep('form')
     setPrUrl(null)
@@ -72,8 +74,8 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
     setIsDraft(false)
 
     try {
-      const [ghCliStatus, branchList, currentBranch] = await Promise.all([
-        projectApi.checkGhCli(selectedProject.path),
+      const [forgeStatus, branchList, currentBranch] = await Promise.all([
+        projectApi.checkForgeCli(selectedProject.path),
         projectApi.getGitBranches(selectedProject.path),
         projectApi.getCurrentBranch(selectedProject.path),
       ])
@@ -87,7 +89,8 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
       const defaultBase = mainBranch?.name || masterBranch?.name || branchList.find((b) => !b.isCurrent)?.name || ''
       setBaseBranch(defaultBase)
 
-      setGhStatus(ghCliStatus)
+      setForge(forgeStatus)
+      setGhStatus(forgeStatus.status)
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to initialize')
       setGhStatus('not-installed')
@@ -108,7 +111,7 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
     setError(null)
 
     try {
-      const url = await projectApi.createPullRequest(
+      const created = await projectApi.createPullRequest(
         selectedProject.path,
         title.trim(),
         body,
@@ -117,9 +120,9 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
         isDraft
       )
 
-      setPrUrl(url)
+      setPrUrl(created.url)
       setStep('done')
-      toast.success('Pull request created', {
+      toast.success(created.provider === 'gitlab' ? 'Merge request created' : 'Pull request created', {
         message: isDraft ? 'Created as draft PR' : undefined,
       })
     } catch (err) {
@@ -289,11 +292,11 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
           </IconButton>
         </div>
 
-        {/* GH CLI Status Warning */}
+        {/* Git host CLI / credentials warning */}
         {ghStatus === 'checking' && (
           <div className="flex items-center gap-2 mx-5 mt-4 px-3 py-2 rounded-sm border border-stroke/20 bg-surface-hover/[0.03]">
             <Loader2 size={14} className="animate-spin text-text-3" />
-            <span className="text-[12px] text-text-3">Checking GitHub CLI...</span>
+            <span className="text-[12px] text-text-3">Checking {forge?.toolName ?? 'git host'}...</span>
           </div>
         )}
 
@@ -304,13 +307,12 @@ export function CreatePRDialog({ isOpen, onClose }: CreatePRDialogProps) {
               <div>
                 <p className="text-[12px] font-medium text-text-1">
                   {ghStatus === 'not-installed'
-                    ? 'GitHub CLI not found'
-                    : 'GitHub CLI not authenticated'}
+                    ? `${forge?.toolName ?? 'Git host CLI'} not found`
+                    : `${forge?.toolName ?? 'Git host CLI'} not authenticated`}
                 </p>
                 <p className="text-[11px] text-text-3 mt-0.5">
-                  {ghStatus === 'not-installed'
-                    ? 'Install GitHub CLI, then run: gh auth login'
-                    : 'Run: gh auth login'}
+                  {forge?.setupHint ??
+                    'Pull requests work with GitHub, GitLab and Bitbucket remotes'}
                 </p>
               </div>
             </div>
@@ -9,6 +9,7 @@ import {
 } from '../../stores/settings'
 import { ConfirmDialog } from '../ui/ConfirmDialog'
 import { logError } from '../../lib/errorUtils'
+import { mergePatch } from '../../lib/utils'
 import { useToast } from '../ui/Toast'
 import { useDialogKeyboardShortcut } from '../../hooks/useDialogKeyboardShortcut'
 import { BaseDialog } from '../ui/BaseDialog'
@@ -39,6 +40,8 @@ export function ProjectSettingsDialog({
   const { models } = useModelsStore()
   const { showToast } = useToast()
   const [settings, setSettings] = useState<ProjectSettings>({})
+  // As loaded, so saving can send what changed with cleared keys as null
+  const [savedSettings, setSavedSettings] = useState<ProjectSettings>({})
   const [isSaving, setIsSaving] = useState(false)
   const [newEnvKey, setNewEnvKey] = useState('')
   const [newEnvValue, setNewEnvValue] = useState('')
@@ -70,9 +73,11 @@ export function ProjectSettingsDialog({
         ? JSON.parse(project.settingsJson)
         : {}
       setSettings(parsed)
+      setSavedSettings(parsed)
     } catch {
       // Invalid JSON in settingsJson; fall back to defaults
       setSettings({})
+      setSavedSettings({})
     }
   }, [project])
 
@@ -87,7 +92,12 @@ export function ProjectSettingsDialog({
     if (!projectId) return
     setIsSaving(true)
     try {
-      await projectApi.update(projectId, undefined, settings as Record<string, unknown>)
+      // update_project applies a merge patch: keys left out are kept
+      const patch = mergePatch(
+        savedSettings as Record<string, unknown>,
+        settings as Record<string, unknown>
+      )
+      await projectApi.update(projectId, undefined, patch)
       await useProjectsStore.getState().fetchProjects()
       showToast('Project settings saved successfully', 'success')
       onClose()
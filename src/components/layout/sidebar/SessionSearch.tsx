@@ -1,6 +1,7 @@
 import { memo, useState, useRef, useEffect, useCallback } from 'react'
 import { X } from 'lucide-react'
 import { useSessionsStore } from '../../../stores/sessions'
+import { useProjectsStore } from '../../../stores/projects'
 import { Input } from '../../ui/Input'
 
 interface SessionSearchProps {
@@ -48,7 +49,10 @@ export const SessionSearch = memo(function SessionSearch({ visible }: SessionSea
     searchTimeoutRef.current = setTimeout(() => {
       searchTimeoutRef.current = null
       if (query.trim()) {
-        void useSessionsStore.getState().searchSessions(query)
+        const { selectedProjectId } = useProjectsStore.getState()
+        void useSessionsStore
+          .getState()
+          .searchSessions(query, undefined, undefined, selectedProjectId)
       } else {
         void useSessionsStore.getState().clearSearch()
       }
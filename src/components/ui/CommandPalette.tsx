@@ -221,7 +221,10 @@ export function CommandPalette({
       return
     }
     const timerId = setTimeout(() => {
-      void useSessionsStore.getState().searchSessions(q)
+      const { selectedProjectId } = useProjectsStore.getState()
+      void useSessionsStore
+        .getState()
+        .searchSessions(q, undefined, undefined, selectedProjectId)
     }, 200)
     return () => clearTimeout(timerId)
   }, [search])
@@ -92,13 +92,6 @@ export {
   type UseVoiceInputReturn,
 } from './useVoiceInput'
 
-// Notifications
-export {
-  useNotifications,
-  type NotificationType,
-  type UseNotificationsReturn,
-} from './useNotifications'
-
 // Keep awake (caffeinate)
 export {
   useKeepAwake,
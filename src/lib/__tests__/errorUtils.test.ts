@@ -2,6 +2,8 @@ import { describe, it, expect, vi, beforeEach, afterEach } from 'vitest'
 import {
   parseError,
   getErrorInfo,
+  getErrorCode,
+  getErrorHint,
   isErrorType,
   ErrorTypes,
   emitError,
@@ -91,6 +93,23 @@ describe('getErrorInfo', () => {
   })
 })
 
+describe('getErrorCode and getErrorHint', () => {
+  it('reads the code and hint of backend errors', () => {
+    const error = {
+      message: 'Not a git repository',
+      code: 'not_a_repo',
+      hint: 'Open a folder inside a git repository, or run git init in it.',
+    }
+    expect(getErrorCode(error)).toBe('not_a_repo')
+    expect(getErrorHint(error)).toContain('git init')
+  })
+
+  it('returns undefined for errors without them', () => {
+    expect(getErrorCode(new Error('plain'))).toBeUndefined()
+    expect(getErrorHint({ message: 'no hint', code: 'other' })).toBeUndefined()
+  })
+})
+
 describe('isErrorType', () => {
   it('returns true when error type matches', () => {
     const error = {
@@ -0,0 +1,29 @@
+import { describe, it, expect } from 'vitest'
+import { mergePatch } from '../utils'
+
+describe('mergePatch', () => {
+  it('sends null for cleared settings and removed env vars', () => {
+    const before = {
+      model: 'o3',
+      sandboxMode: 'workspace-write',
+      envVars: { A: '1', B: '2' },
+      futureFlag: true,
+    }
+    const after = {
+      model: undefined,
+      envVars: { A: '1', C: '3' },
+      futureFlag: true,
+    }
+    expect(mergePatch(before, after)).toEqual({
+      model: null,
+      sandboxMode: null,
+      envVars: { B: null, C: '3' },
+    })
+  })
+
+  it('leaves unchanged settings out', () => {
+    const settings = { model: 'o3', envVars: { A: '1' } }
+    expect(mergePatch(settings, { ...settings, envVars: { A: '1' } })).toEqual({})
+    expect(mergePatch({}, { cwd: 'src' })).toEqual({ cwd: 'src' })
+  })
+})
@@ -2,6 +2,7 @@ import { invoke } from '@tauri-apps/api/core'
 import { log } from './logger'
 import { withCache, clearCache, clearAllCache, CACHE_KEYS, CACHE_TTL } from './apiCache'
 import { isTauriAvailable } from './tauri'
+import type { CommandContext, LiveFileDiff } from './events'
 
 // ==================== Timeout Utility ====================
 
@@ -54,6 +55,10 @@ export interface Project {
   createdAt: number
   lastOpenedAt: number | null
   settingsJson: string | null
+  /** Accent color as #rrggbb */
+  color: string | null
+  icon: string | null
+  emoji: string | null
 }
 
 // Thread mode for session creation
@@ -68,6 +73,224 @@ export interface TaskItem {
   status: 'pending' | 'in_progress' | 'completed'
 }
 
+/** Result of a bulk session operation, also emitted as `sessions:bulk-updated` */
+export interface BulkSessionSummary {
+  action: 'delete' | 'archive' | 'unarchive' | 'move'
+  sessionIds: string[]
+  /** Sessions actually changed (missing or already-matching ones are skipped) */
+  affected: number
+  /** Destination project for `move` */
+  projectId: string | null
+}
+
+export type SessionExportFormat = 'markdown' | 'html' | 'json'
+
+export interface SessionExport {
+  path: string
+  bytes: number
+  items: number
+  /** Values masked by the redaction pass (0 when it was skipped) */
+  redactions: number
+}
+
+/** What is masked in session exports */
+export interface RedactionSettings {
+  /** Redact exports unless the export asks otherwise */
+  enabled: boolean
+  /** API keys, tokens, passwords and private keys */
+  maskSecrets: boolean
+  /** Project directory becomes `<project>`, home directory `~` */
+  maskPaths: boolean
+  /** Local user name and `usernames` become `[user]` */
+  maskUsernames: boolean
+  usernames: string[]
+  /** Regular expressions whose matches become `[redacted]` */
+  customPatterns: string[]
+}
+
+/**
+ * Masking of credentials, custom patterns and secret project variables in
+ * logs, events and stored session text
+ */
+export interface SecretMaskingSettings {
+  enabled: boolean
+  /** Regular expressions masked besides the built-in credential patterns */
+  customPatterns: string[]
+}
+
+export interface RedactionChange {
+  itemIndex: number
+  before: string
+  after: string
+}
+
+export interface RedactionReport {
+  total: number
+  /** Matches per rule: 'custom', 'secret', 'path' or 'username' */
+  byRule: Record<string, number>
+  changes: RedactionChange[]
+  /** More lines changed than `changes` holds */
+  truncated: boolean
+}
+
+/** Sessions created by `sessionApi.import` have no app-server thread */
+export const IMPORTED_SESSION_PREFIX = 'imported-'
+
+export const isImportedSession = (sessionId: string) =>
+  sessionId.startsWith(IMPORTED_SESSION_PREFIX)
+
+export interface SessionTokenUsage {
+  inputTokens: number
+  cachedInputTokens: number
+  outputTokens: number
+  reasoningOutputTokens: number
+  totalTokens: number
+}
+
+export interface SessionStats {
+  sessionId: string
+  projectId: string
+  title: string
+  model: string | null
+  createdAt: number
+  lastActivityAt: number | null
+  turnCount: number
+  tokenUsage: SessionTokenUsage
+  /** Completed tool items by item type */
+  toolCalls: Record<string, number>
+  filesTouched: number
+  activeSeconds: number
+}
+
+export interface SessionStatsBucket {
+  /** YYYY-MM-DD (UTC), project ID, or 'all' */
+  key: string
+  sessions: number
+  turns: number
+  tokenUsage: SessionTokenUsage
+  toolCalls: Record<string, number>
+  filesTouched: number
+  activeSeconds: number
+  /** Turns per model */
+  models: Record<string, number>
+}
+
+export interface SessionStatsReport {
+  sessions: SessionStats[]
+  byDay: SessionStatsBucket[]
+  byProject: SessionStatsBucket[]
+  totals: SessionStatsBucket
+}
+
+/** Phase breakdown of a turn; durations are in milliseconds */
+export interface TurnTimings {
+  turnId: string
+  sessionId: string
+  status: string | null
+  /** Unix timestamps in milliseconds */
+  requestedAt: number | null
+  startedAt: number
+  /** `null` while the turn is running */
+  completedAt: number | null
+  elapsedMs: number
+  queuedMs: number
+  thinkingMs: number
+  toolMs: number
+  patchMs: number
+  toolCalls: number
+  patches: number
+}
+
+/** How a turn ended, as classified by the backend */
+export type TurnOutcome = 'completed' | 'interrupted' | 'error' | 'needs-approval-timeout'
+
+export interface TurnOutcomeRecord {
+  turnId: string
+  sessionId: string
+  outcome: TurnOutcome
+  /** The app-server's own turn status */
+  status: string
+  error: string | null
+  /** Unix timestamp in milliseconds */
+  completedAt: number
+}
+
+/** The journaled part of a turn the app exited in the middle of */
+export interface RecoveredTurn {
+  sessionId: string
+  turnId: string
+  /** In the order they started */
+  items: Array<{ id: string; type: string; partial?: boolean } & Record<string, unknown>>
+  /** Unix timestamps in milliseconds */
+  startedAt: number
+  lastEventAt: number
+  recoveredAt: number
+}
+
+/** Worktree and branch created for a session when its project has `worktreePerSession` on */
+export interface SessionWorktree {
+  sessionId: string
+  projectId: string
+  path: string
+  branch: string
+  baseBranch: string | null
+  baseCommit: string | null
+  createdAt: number
+}
+
+export interface SessionWorktreeStatus extends SessionWorktree {
+  exists: boolean
+  dirty: boolean
+  commitsAhead: number
+}
+
+export type WorktreeCleanup = 'merge' | 'delete'
+
+export interface WorktreeCleanupReport {
+  sessionId: string
+  action: WorktreeCleanup
+  /** On conflicts the merge is aborted and nothing is removed */
+  merge: GitMergeResult | null
+  removed: boolean
+}
+
+/** Files changed in a session so far; see `LiveDiffUpdatedEvent` */
+export interface LiveDiff {
+  sessionId: string
+  version: number
+  /** Sorted by path */
+  files: LiveFileDiff[]
+  insertions: number
+  deletions: number
+}
+
+/** Everything that changed since a session started, commits included */
+export interface SessionBaselineDiff {
+  baselineCommit: string
+  /** Unix seconds */
+  recordedAt: number
+  headCommit: string | null
+  diff: string
+}
+
+export interface SessionTagCount {
+  tag: string
+  count: number
+}
+
+export interface SessionSearchHit extends SessionMetadata {
+  /** BM25 rank (lower is more relevant); 0 for substring-only matches */
+  rank: number
+  /** Final ranking score with title, recency and project boosts (higher is more relevant); 0 for substring-only matches */
+  score: number
+  /** Excerpt around the best match, without markup */
+  snippet: string | null
+  /** [start, end) offsets of matched terms within `snippet` */
+  highlights: [number, number][]
+  /** [start, end) offsets of matched word prefixes within `title` */
+  titleHighlights: [number, number][]
+}
+
 export interface SessionMetadata {
   sessionId: string
   projectId: string
@@ -89,6 +312,15 @@ export interface SessionMetadata {
   status: SessionStatus
   firstMessage: string | null
   tasksJson: string | null
+  /** Project-relative subdirectory used as the agent cwd, if any */
+  cwdSubpath?: string | null
+  // Denormalized list-row data, maintained by the backend
+  /** Clipped text of the most recent user or agent message */
+  lastMessagePreview?: string | null
+  turnCount?: number
+  filesChangedCount?: number
+  /** Model used for the most recent turn, if known */
+  lastModel?: string | null
   // Worktree mode fields (frontend-only, stored in tags/settings JSON)
   mode?: ThreadMode
   worktreePath?: string | null
@@ -179,11 +411,345 @@ export interface TurnStartResponse {
   turn: TurnInfo
 }
 
+export interface QueuedFollowup {
+  id: string
+  prompt: string
+  createdAt: number
+}
+
+export type QueuedTurnStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled'
+
+/** A prompt lined up in a project's turn queue */
+export interface QueuedTurn {
+  id: string
+  projectId: string
+  threadId: string
+  prompt: string
+  position: number
+  status: QueuedTurnStatus
+  /** Unix ms before which the entry is not started */
+  runAfter: number | null
+  createdAt: number
+  startedAt: number | null
+  finishedAt: number | null
+  turnId: string | null
+  error: string | null
+}
+
+export interface TurnQueueStatus {
+  paused: boolean
+  /** Projects held back, e.g. after a failed entry */
+  pausedProjects: string[]
+}
+
+export interface HandoffResponse {
+  summary: string
+  thread: ThreadStartResponse
+  turn: TurnStartResponse
+}
+
+export type OversizePolicy = 'truncate' | 'summarize' | 'omit'
+
+export interface AttachmentBudgetSettings {
+  /** Estimated tokens allowed across all attachments of one prompt */
+  maxTotalTokens: number
+  /** Estimated tokens allowed for a single attachment */
+  maxFileTokens: number
+  oversizePolicy: OversizePolicy
+}
+
+export type AttachmentAction = 'included' | 'truncated' | 'summarized' | 'omitted'
+
+export interface AttachmentBreakdown {
+  path: string
+  action: AttachmentAction
+  /** Estimated tokens of the whole file; 0 when it could not be read */
+  originalTokens: number
+  /** Estimated tokens that made it into the prompt */
+  includedTokens: number
+  reason: string | null
+}
+
+export interface AttachmentBudget {
+  attachments: AttachmentBreakdown[]
+  /** Fenced blocks of the included files, ready to append to the prompt */
+  context: string
+  totalTokens: number
+  maxTotalTokens: number
+}
+
+export interface BatchTarget {
+  projectId: string
+  /** Worktree of the project to run in instead of the project root */
+  worktreePath?: string
+}
+
+export type BatchTargetStatus = 'running' | 'completed' | 'failed' | 'interrupted' | 'timedOut'
+
+export interface DiffSummary {
+  files: string[]
+  insertions: number
+  deletions: number
+}
+
+export interface BatchTargetResult {
+  projectId: string
+  worktreePath: string | null
+  /** Directory the thread ran in */
+  cwd: string
+  threadId: string | null
+  status: BatchTargetStatus
+  error: string | null
+  /** Working-tree changes after the turn; null outside git repositories */
+  diff: DiffSummary | null
+  /** The tree already had changes before the turn, so `diff` includes them */
+  hadPriorChanges: boolean
+  durationMs: number
+}
+
+export interface BatchProgress {
+  total: number
+  finished: number
+  succeeded: number
+  failed: number
+}
+
+export interface BatchReport extends BatchProgress {
+  batchId: string
+  results: BatchTargetResult[]
+}
+
+/** Model and reasoning effort for one comparison run; unset ones use the project's defaults */
+export interface ComparisonVariant {
+  model?: string
+  effort?: string
+}
+
+export interface ComparisonGroup {
+  id: string
+  projectId: string
+  prompt: string
+  baseBranch: string | null
+  /** Commit every run's worktree started from */
+  baseCommit: string
+  winnerRunId: string | null
+  /** Unix seconds; set once a run is kept or all are discarded */
+  resolvedAt: number | null
+  createdAt: number
+}
+
+export interface ComparisonRun {
+  id: string
+  groupId: string
+  position: number
+  model: string | null
+  effort: string | null
+  worktreePath: string
+  branch: string
+  threadId: string | null
+  status: BatchTargetStatus
+  error: string | null
+  durationMs: number
+}
+
+export interface ComparisonRunResult extends ComparisonRun {
+  /** Changes since `baseCommit`, committed or not; null once the worktree is gone */
+  diff: string | null
+  summary: DiffSummary | null
+  /** `diff` was cut off; `summary` still covers everything */
+  diffTruncated: boolean
+}
+
+export interface ComparisonResults {
+  group: ComparisonGroup
+  runs: ComparisonRunResult[]
+}
+
+export interface ComparisonResolution {
+  groupId: string
+  winnerRunId: string | null
+  /** On conflicts the merge is aborted and nothing is removed */
+  merge: GitMergeResult | null
+  resolved: boolean
+}
+
+export interface WorkflowParam {
+  name: string
+  label: string
+  description: string
+  required: boolean
+}
+
+export interface WorkflowDefinition {
+  id: string
+  title: string
+  description: string
+  params: WorkflowParam[]
+}
+
+export interface WorkflowStartResponse {
+  thread: ThreadStartResponse
+  turn: TurnStartResponse
+}
+
+export interface BackendHeartbeatSettings {
+  enabled: boolean
+  intervalSecs: number
+}
+
+export type BackgroundTaskId =
+  | 'codexSessionIndex'
+  | 'projectWatchers'
+  | 'turnQueueSchedule'
+  | 'loginPoll'
+  | 'periodicGc'
+
+export interface BackgroundTaskSettings {
+  /** Hold every background task until resumed */
+  paused: boolean
+  /** One task at a time, with loop intervals stretched */
+  cpuFriendly: boolean
+  /** Tasks doing work at once; 0 means unlimited */
+  maxConcurrent: number
+  disabled: BackgroundTaskId[]
+}
+
+export interface BackgroundTaskStatus {
+  id: BackgroundTaskId
+  kind: 'indexer' | 'watcher' | 'scheduler' | 'maintenance'
+  description: string
+  /** Not disabled and not paused */
+  enabled: boolean
+  running: boolean
+  /** Unix ms the last unit of work finished */
+  lastRunAt: number | null
+}
+
+export interface BackgroundTasksInfo {
+  settings: BackgroundTaskSettings
+  tasks: BackgroundTaskStatus[]
+}
+
+/** Why the app booted in safe mode after repeated failed startups */
+export interface SafeModeReason {
+  failedStartups: number
+  crashWindowSecs: number
+  /** Launch time (ms) of the most recent failed startup */
+  lastFailedStartMs: number | null
+  message: string
+}
+
+/** Payload of the periodic `backend-heartbeat` event */
+export interface BackendHeartbeatEvent {
+  seq: number
+  timestamp: number
+  intervalSecs: number
+  appServer: {
+    /** "busy" means a request held the app-server lock when the beat fired */
+    status: 'running' | 'exited' | 'stopped' | 'busy'
+    restartCount: number
+  }
+  queues: {
+    pendingEvents: number
+    followups: number
+  }
+  activeOperations: {
+    pendingRequests: number | null
+    turnWaiters: number
+    watchedProjects: number
+  }
+}
+
+/** Why the app-server could not be started */
+export interface AppServerDegradedInfo {
+  reason: string
+  /** Unix ms of the first failure */
+  since: number
+  failedAttempts: number
+  /** Unix ms after which the next start is attempted */
+  retryAt: number
+}
+
+/** A turn running on one of several threads */
+export interface ActiveTurn {
+  threadId: string
+  /** `null` until the app-server reports the turn as started */
+  turnId: string | null
+  projectId: string | null
+  /** Unix ms */
+  requestedAt: number
+}
+
+/** Limits on turns running at once; 0 means unlimited */
+export interface TurnConcurrencySettings {
+  maxConcurrentTurns: number
+  maxTurnsPerProject: number
+}
+
+/**
+ * Ceiling on what one turn may spend; the turn is interrupted and
+ * `turn-budget-exceeded` emitted once it is crossed. `null` limits do not
+ * apply; cost only applies to models with known pricing.
+ */
+export interface TurnBudget {
+  /** Input plus output tokens across the turn's model calls */
+  maxTokens: number | null
+  maxCostUsd: number | null
+}
+
 export interface ServerStatus {
   isRunning: boolean
+  /**
+   * `degraded`: the last start failed; sessions and git tools still work.
+   * `idle`: stopped after going unused; the next thread command starts it.
+   */
+  state: 'running' | 'stopped' | 'idle' | 'degraded'
+  degraded: AppServerDegradedInfo | null
   version: string | null
 }
 
+/** How the `codex app-server` process is launched */
+export interface AppServerLaunchSettings {
+  /** Absolute path of the codex binary; `null` searches PATH */
+  codexPath: string | null
+  /** Required version ("0.46" or "0.46.1"); spawning another version fails */
+  pinnedVersion: string | null
+  /** Appended after `app-server` */
+  extraArgs: string[]
+  env: Record<string, string>
+  /** Stop the app-server after this many unused minutes; `null` or 0 never does */
+  idleShutdownMins: number | null
+}
+
+export interface CodexInstallation {
+  path: string
+  /** The binary after following symlinks */
+  resolvedPath: string
+  source: 'path' | 'homebrew' | 'npm' | 'cargo' | 'other'
+  version: string | null
+  /** The binary the app-server is spawned from with the current settings */
+  selected: boolean
+}
+
+/** The selected codex CLI checked against the versions the app supports */
+export interface CodexVersionCheck {
+  /** `unknown`: the version could not be read; `notFound`: no binary */
+  status: 'ok' | 'tooOld' | 'tooNew' | 'unknown' | 'notFound'
+  compatible: boolean
+  version: string | null
+  binaryPath: string | null
+  installSource: 'homebrew' | 'npm' | 'cargo' | 'other' | null
+  /** Inclusive */
+  minVersion: string
+  /** Exclusive */
+  maxVersion: string
+  /** Summary with upgrade instructions */
+  message: string
+  upgradeCommand: string | null
+  /** Whether `upgradeCodex` can run `upgradeCommand` itself */
+  canAutoUpgrade: boolean
+}
+
 export interface AccountDetails {
   type: string
   email: string | null
@@ -214,21 +780,182 @@ export interface RateLimitSnapshot {
   planType?: string | null
 }
 
+/** An OpenAI-compatible endpoint written to the Codex config as `model_providers.<id>` */
+export interface CustomModelProvider {
+  /** Lowercase letters, digits, `-` and `_` */
+  id: string
+  name: string
+  baseUrl: string
+  wireApi: 'chat' | 'responses'
+  /** The first model is the default for new threads */
+  models: string[]
+  requiresApiKey: boolean
+}
+
+/** Set a project's `modelProvider` setting to a provider id to use it there */
+export interface ModelProviderInfo extends CustomModelProvider {
+  /** Environment variable the app server reads the key from */
+  envKey: string
+  hasApiKey: boolean
+}
+
+export interface ProviderConnectionTest {
+  ok: boolean
+  statusCode: number | null
+  latencyMs: number
+  models: string[]
+  /** Configured models the endpoint did not list */
+  missingModels: string[]
+  message: string
+}
+
+/** A Codex login saved for switching; set a project's `accountId` setting to use it there */
+export interface SavedAccount {
+  id: string
+  label: string
+  email: string | null
+  authMode: 'chatgpt' | 'apiKey'
+  planType: string | null
+  createdAt: number
+  lastUsedAt: number | null
+  isActive: boolean
+}
+
 export interface AccountRateLimitsResponse {
   rateLimits: RateLimitSnapshot
 }
 
+export type RateLimitLevel = 'ok' | 'warning' | 'exhausted'
+
+export interface RateLimitSettings {
+  /** Hold `sendMessage` turns back while a window is above `queuePercent` */
+  queueWhenLimited: boolean
+  warningPercent: number
+  queuePercent: number
+}
+
+export interface RpcTraceSettings {
+  enabled: boolean
+  /** Messages kept in memory (100-50000) */
+  capacity: number
+  /** Also append messages to `logs/rpc-trace.jsonl` */
+  logToFile: boolean
+}
+
+export interface RpcTraceEntry {
+  seq: number
+  /** Unix timestamp in milliseconds */
+  timestamp: number
+  direction: 'outgoing' | 'incoming'
+  kind: 'request' | 'response' | 'error' | 'notification' | 'invalid'
+  id: number | null
+  /** For responses and errors, the method of the request they answer */
+  method: string | null
+  /** Round-trip time, on responses and errors */
+  durationMs: number | null
+  size: number
+  /** Credentials are redacted; the raw line for invalid messages */
+  message: unknown
+}
+
+export interface RpcTraceSnapshot {
+  settings: RpcTraceSettings
+  /** Oldest first */
+  entries: RpcTraceEntry[]
+  /** Entries evicted from the ring buffer since the last clear */
+  dropped: number
+  filePath: string | null
+}
+
+export interface WindowForecast {
+  name: string
+  usedPercent: number
+  windowDurationMins: number | null
+  resetsAt: number | null
+  percentPerHour: number | null
+  /** When the window runs out at the current rate, if before it resets */
+  exhaustedAt: number | null
+  level: RateLimitLevel
+}
+
+export interface QueuedTurnInfo {
+  id: string
+  threadId: string
+  queuedAt: number
+}
+
+export interface RateLimitForecast {
+  windows: WindowForecast[]
+  level: RateLimitLevel
+  updatedAt: number | null
+  resumesAt: number | null
+  queued: QueuedTurnInfo[]
+  settings: RateLimitSettings
+}
+
 export interface LoginResponse {
   loginType: string
   loginId: string | null
   authUrl: string | null
 }
 
+export type ActionCategory =
+  | 'project'
+  | 'files'
+  | 'git'
+  | 'session'
+  | 'turn'
+  | 'snapshot'
+  | 'account'
+  | 'server'
+  | 'approvals'
+  | 'settings'
+  | 'maintenance'
+  | 'system'
+
+/** What the caller must supply before an action can run */
+export type ActionContext = 'project' | 'file' | 'session' | 'snapshot'
+
+/** An invokable action from the backend's action registry */
+export interface Action {
+  /** Stable id, safe to use in deep links */
+  id: string
+  /** Backend command that performs the action */
+  command: string
+  title: string
+  category: ActionCategory
+  context: ActionContext[]
+  /** Deletes or overwrites data; ask before running */
+  destructive: boolean
+}
+
 export interface AppPaths {
   appDataDir: string | null
   logDir: string | null
 }
 
+export type DiagnosticStatus = 'ok' | 'warning' | 'failed'
+
+export interface DiagnosticCheck {
+  /** Stable name, e.g. `codexCli`, `appServer`, `diskSpace` */
+  id: string
+  status: DiagnosticStatus
+  summary: string
+  /** What to do about a warning or failure */
+  hint: string | null
+}
+
+export interface DiagnosticsReport {
+  appVersion: string
+  /** e.g. `macos aarch64` */
+  platform: string
+  /** Unix seconds */
+  generatedAt: number
+  checks: DiagnosticCheck[]
+  /** Plain-text report to copy into a bug report */
+  text: string
+}
+
 export interface LogTailResponse {
   file: string | null
   content: string
@@ -315,6 +1042,111 @@ export interface FileEntry {
   isDir: boolean
 }
 
+export interface ProjectFileContent {
+  mimeType: string
+  isBinary: boolean
+  /** "utf-8" | "utf-16le" | "utf-16be" | "latin-1"; null for binary content */
+  encoding: string | null
+  text: string | null
+  lineCount: number | null
+  /** Syntax highlighting hint derived from the file name */
+  language: string | null
+  size: number
+  /** SHA-256 of the raw bytes; usable as `expectedHash` when saving */
+  hash: string
+}
+
+export interface FileWriteResult {
+  /** SHA-256 of the written contents; pass as `expectedHash` on the next save */
+  hash: string
+  size: number
+}
+
+/** Result of `searchFiles`, best match first */
+export interface FileMatch extends FileEntry {
+  score: number
+}
+
+export interface FileListResponse {
+  files: FileEntry[]
+  truncated: boolean
+}
+
+/** One level of a project directory, for lazy tree expansion */
+export interface DirectoryEntry extends FileEntry {
+  /** Files only */
+  size: number | null
+  /** Unix seconds */
+  modifiedAt: number | null
+  /** Directories report 'M' for any change beneath them, '?' when all untracked */
+  gitStatus: string | null
+}
+
+export interface ProjectPathResult {
+  /** New project-relative path */
+  path: string
+  /** Whether the change went through `git mv` */
+  usedGit: boolean
+}
+
+export interface DirectoryChildren {
+  entries: DirectoryEntry[]
+  truncated: boolean
+}
+
+export interface RecentFile {
+  path: string
+  /** Unix seconds: mtime, or when the watcher saw the change if later */
+  modifiedAt: number
+  gitStatus: string | null
+}
+
+/** Payload of the `project:file-changed` event */
+export interface ProjectFileChangedEvent {
+  projectId: string
+  paths: string[]
+  truncated: boolean
+}
+
+export interface ContentSearchOptions {
+  isRegex?: boolean
+  caseSensitive?: boolean
+  /** Lines of context around each match (default 2, max 10) */
+  contextLines?: number
+  /** Stop after this many matches (default 1000, max 10000) */
+  maxResults?: number
+}
+
+export interface ContentMatch {
+  /** 1-based line number */
+  lineNumber: number
+  line: string
+  contextBefore: string[]
+  contextAfter: string[]
+}
+
+/** Payload of the `project-search:match` event (one per file) */
+export interface ProjectSearchMatchEvent {
+  searchId: string
+  /** Path relative to the searched directory */
+  path: string
+  matches: ContentMatch[]
+}
+
+export interface ContentSearchSummary {
+  totalMatches: number
+  filesWithMatches: number
+  filesSearched: number
+  /** Whether `maxResults` stopped the search early */
+  truncated: boolean
+  cancelled: boolean
+}
+
+/** Payload of the `project-search:done` event */
+export interface ProjectSearchDoneEvent extends ContentSearchSummary {
+  searchId: string
+}
+
 export interface GitBranch {
   name: string
   isCurrent: boolean
@@ -328,6 +1160,24 @@ export interface GitCommit {
   date: string
 }
 
+/** Unix seconds, inclusive at both ends */
+export interface ReportRange {
+  from: number
+  to: number
+}
+
+export interface ChangeReportExport {
+  content: string
+  /** Where the report was written, if a path was given */
+  path: string | null
+  sessions: number
+  commits: number
+  pullRequests: number
+  files: number
+  /** Values masked by the redaction pass (0 when it was skipped) */
+  redactions: number
+}
+
 export interface GitFileStatus {
   path: string
   status: string
@@ -335,6 +1185,148 @@ export interface GitFileStatus {
   statusLabel: string
 }
 
+export interface StagedFile {
+  path: string
+  /** `git diff --name-status` letter: 'A', 'M', 'D', ... */
+  status: string
+  /** null for binary files */
+  additions: number | null
+  deletions: number | null
+}
+
+/** A conventional-commit message for the staged changes */
+export interface CommitMessageSuggestion {
+  message: string
+  commitType: string
+  scope: string | null
+  /** 'fallback' messages are built from the file stats */
+  source: 'model' | 'fallback'
+  /** Why the model's message was not used */
+  fallbackReason: string | null
+  files: StagedFile[]
+}
+
+export type SigningFormat = 'openpgp' | 'ssh' | 'x509'
+
+export type SigningFailureKind = 'missingKey' | 'programNotFound' | 'passphraseRequired' | 'other'
+
+/** Why a commit could not be signed; `signingError` of a failed `gitCommit` */
+export interface SigningFailure {
+  kind: SigningFailureKind
+  message: string
+}
+
+export interface SigningConfig {
+  /** `commit.gpgSign` is on */
+  required: boolean
+  format: SigningFormat
+  signingKey: string | null
+  program: string
+  /** `null` when it cannot be checked */
+  keyAvailable: boolean | null
+  /** What would make a signed commit fail */
+  problem: SigningFailure | null
+}
+
+export type PatchIssueKind =
+  | 'outsideProject'
+  | 'contextMismatch'
+  | 'lineEndings'
+  | 'whitespace'
+  | 'alreadyExists'
+  | 'missing'
+  | 'conflict'
+  | 'other'
+
+export interface PatchIssue {
+  kind: PatchIssueKind
+  /** `error` issues keep the patch from applying */
+  severity: 'error' | 'warning'
+  path: string | null
+  /** Line in the file for context mismatches, in the patch for whitespace */
+  line: number | null
+  message: string
+}
+
+export interface PatchFile {
+  path: string
+  /** Set when the patch renames the file */
+  oldPath: string | null
+  created: boolean
+  deleted: boolean
+  crlfLines: number
+  lfLines: number
+}
+
+/** `patchPreflight` of a rejected `gitApplyPatch` */
+export interface PatchPreflight {
+  applies: boolean
+  files: PatchFile[]
+  issues: PatchIssue[]
+  autocrlf: string | null
+  coreEol: string | null
+}
+
+/** A region `git apply --3way` left between conflict markers */
+export interface ConflictHunk {
+  /** 1-based line of `<<<<<<<` */
+  startLine: number
+  /** 1-based line of `>>>>>>>` */
+  endLine: number
+  ours: string
+  /** Only with `merge.conflictStyle=diff3` */
+  base: string | null
+  /** What the patch wanted */
+  theirs: string
+}
+
+export interface ConflictedFile {
+  path: string
+  hunks: ConflictHunk[]
+}
+
+export interface PatchApplyResult {
+  /** Files a three-way apply left conflict markers in */
+  conflicts: ConflictedFile[]
+  /** Preflight warnings, e.g. whitespace */
+  warnings: PatchIssue[]
+}
+
+export type HookOperation = 'commit' | 'push'
+
+export interface GitHook {
+  /** e.g. `pre-commit` */
+  name: string
+  path: string
+  /** Git skips hooks without the executable bit */
+  executable: boolean
+  operation: HookOperation | null
+  /** From the `#!` line */
+  interpreter: string | null
+  /** The script reads from `/dev/tty` and may wait for an answer */
+  readsTerminal: boolean
+}
+
+export interface GitHooks {
+  hooksDir: string
+  /** `core.hooksPath` is set */
+  customHooksPath: boolean
+  hooks: GitHook[]
+}
+
+/** A commit or push made with `--no-verify` */
+export interface HookBypass {
+  id: string
+  repoPath: string
+  operation: HookOperation
+  /** Commit subject, or remote/branch pushed to */
+  detail: string
+  /** Hooks that were skipped */
+  hooks: string[]
+  /** Unix seconds */
+  createdAt: number
+}
+
 export interface GitRemoteInfo {
   remote: string | null
   branch: string | null
@@ -342,7 +1334,132 @@ export interface GitRemoteInfo {
   behind: number
 }
 
-export type GhCliStatus = 'ready' | 'not-installed' | 'not-authenticated'
+export type ForgeProvider = 'github' | 'gitlab' | 'bitbucket'
+
+export type ForgeAuthStatus = 'ready' | 'not-installed' | 'not-authenticated'
+
+/** Whether the CLI or credentials for the project's git host are set up */
+export interface ForgeStatus {
+  provider: ForgeProvider
+  host: string
+  /** e.g. "GitLab CLI (glab)" */
+  toolName: string
+  status: ForgeAuthStatus
+  /** How to get to `ready`; null once there */
+  setupHint: string | null
+  webUrl: string
+}
+
+export interface ForgeLinks {
+  provider: ForgeProvider
+  repositoryUrl: string
+  branchUrl: string
+  /** Page that starts a pull request from the head into the base branch */
+  newPullRequestUrl: string
+}
+
+export interface CreatedPullRequest {
+  provider: ForgeProvider
+  url: string
+}
+
+export type PullRequestFilter = 'open' | 'closed' | 'merged' | 'all'
+
+export interface PullRequestSummary {
+  number: number
+  title: string
+  author: string | null
+  headBranch: string
+  baseBranch: string
+  isDraft: boolean
+  state: 'OPEN' | 'CLOSED' | 'MERGED'
+  url: string
+  updatedAt: string | null
+  /** `null` when the repository requires no review */
+  reviewDecision: 'APPROVED' | 'CHANGES_REQUESTED' | 'REVIEW_REQUIRED' | null
+}
+
+export interface PullRequestComment {
+  /** REST id of an inline comment, which replies go to */
+  id: number | null
+  /** The inline comment this one replies to, i.e. its thread */
+  inReplyToId: number | null
+  kind: 'conversation' | 'review' | 'inline'
+  author: string | null
+  body: string
+  createdAt: string | null
+  url: string | null
+  /** File and line of an inline comment; `line` is null once outdated */
+  path: string | null
+  line: number | null
+  reviewState: 'APPROVED' | 'CHANGES_REQUESTED' | 'COMMENTED' | 'DISMISSED' | null
+}
+
+export interface PullRequestCheck {
+  name: string
+  workflow: string | null
+  state: string
+  bucket: 'pass' | 'fail' | 'pending' | 'skipping' | 'cancel' | null
+  link: string | null
+}
+
+export interface PullRequestDetail extends PullRequestSummary {
+  body: string
+  diff: string
+  diffTruncated: boolean
+  /** Oldest first */
+  comments: PullRequestComment[]
+  checks: PullRequestCheck[]
+  /** Prompt asking Codex to address the review comments */
+  addressCommentsPrompt: string
+}
+
+export type CiState = 'passing' | 'failing' | 'pending' | 'unknown'
+
+/** A GitHub Actions workflow run */
+export interface WorkflowRun {
+  id: number
+  /** Workflow name */
+  name: string
+  title: string
+  status: string
+  /** null until the run completed */
+  conclusion: string | null
+  event: string
+  headSha: string
+  url: string
+  createdAt: string | null
+  updatedAt: string | null
+}
+
+/** CI status of a project's current branch */
+export interface CiStatus {
+  branch: string
+  /** Local commit the branch points at */
+  headSha: string | null
+  /** Number of the branch's open pull request */
+  pullRequest: number | null
+  /** From the pull request's checks when there are any, else the latest runs */
+  state: CiState
+  /** Newest first */
+  runs: WorkflowRun[]
+  checks: PullRequestCheck[]
+}
+
+/** A thread started on a PR's head branch to address review comments */
+export interface PrReviewThread {
+  pullRequest: PullRequestSummary
+  /** Checkout the thread runs in */
+  cwd: string
+  prompt: string
+  thread: ThreadStartResponse
+  turn: TurnStartResponse
+}
+
+export type GitHeadState =
+  | { state: 'branch'; name: string }
+  | { state: 'detached'; sha: string }
+  | { state: 'unborn'; name: string }
 
 export interface WorktreeInfo {
   path: string
@@ -351,6 +1468,17 @@ export interface WorktreeInfo {
   headCommit: string
 }
 
+/** `listWorktrees` entry; `ahead`/`behind` compare with the main worktree's branch */
+export interface WorktreeStatus extends WorktreeInfo {
+  dirty: boolean
+  ahead: number | null
+  behind: number | null
+  /** Session started in this worktree, if any */
+  sessionId: string | null
+  /** Bytes, not counting `.git` */
+  diskUsage: number
+}
+
 export interface GitMergeResult {
   success: boolean
   conflictFiles: string[]
@@ -386,8 +1514,190 @@ export interface Snapshot {
   metadataJson: string | null
 }
 
+export interface SnapshotFileDiff {
+  path: string
+  status: 'added' | 'deleted' | 'modified'
+  /** Unified diff hunks without file headers; empty for binary files */
+  diff: string
+  insertions: number
+  deletions: number
+  binary: boolean
+  /** `diff` was cut at the size limit, or left out past the file limit */
+  truncated: boolean
+}
+
+export interface SnapshotDiff {
+  fromSnapshotId: string
+  /** `null` when compared with the working tree */
+  toSnapshotId: string | null
+  files: SnapshotFileDiff[]
+  insertions: number
+  deletions: number
+}
+
+export interface PatchFile {
+  path: string
+  insertions: number
+  deletions: number
+  binary: boolean
+}
+
+export interface SnapshotPatch {
+  snapshotId: string
+  /** Commit or snapshot ID the patch applies on top of; `null` for the empty tree */
+  base: string | null
+  /** Unified diff with git headers; empty when nothing changed */
+  patch: string
+  files: PatchFile[]
+}
+
+export interface PatchApplyReport {
+  snapshotId: string
+  base: string | null
+  targetPath: string
+  dryRun: boolean
+  /** Every hunk applies; unless `dryRun`, the patch was applied */
+  applied: boolean
+  /** Why the patch does not apply, as git reports it */
+  error: string | null
+  files: PatchFile[]
+}
+
+export interface FileRestore {
+  path: string
+  /** `unchanged` also covers files snapshots never capture (see `error`) */
+  action: 'restore' | 'recreate' | 'delete' | 'unchanged'
+  error: string | null
+}
+
+export interface FileRestoreReport {
+  snapshotId: string
+  dryRun: boolean
+  files: FileRestore[]
+  /** Snapshot of the state before the restore, if one was taken */
+  backup: Snapshot | null
+}
+
+export interface SnapshotSettings {
+  /** Snapshot the current state before every revert so it can be undone */
+  backupBeforeRevert: boolean
+  /** Most recent snapshots kept per session; 0 keeps all */
+  maxPerSession: number
+  maxAgeDays: number | null
+  /** Oldest snapshots are deleted while all of them together take more */
+  maxDiskBytes: number | null
+}
+
+export interface SnapshotStorageUsage {
+  count: number
+  /** Metadata in the database, including inline file backups */
+  databaseBytes: number
+  /** Objects and older per-snapshot directories under the snapshots directory */
+  diskBytes: number
+  totalBytes: number
+  /** Uncompressed size of everything snapshots hold, before deduplication */
+  logicalBytes: number
+  /** Unix seconds */
+  oldestCreatedAt: number | null
+}
+
+export interface SnapshotSize {
+  snapshotId: string
+  /** Uncompressed size of the backed-up files plus metadata */
+  logicalBytes: number
+  /** Bytes stored, with shared objects split between their snapshots */
+  physicalBytes: number
+  /** Bytes deleting this snapshot would free */
+  exclusiveBytes: number
+}
+
+export interface CompactionReport {
+  snapshotsMigrated: number
+  objectsRemoved: number
+  directoriesRemoved: number
+  bytesFreed: number
+}
+
+/** A project moved out of the live database into an archive file */
+export interface ArchivedProject {
+  id: string
+  path: string
+  displayName: string | null
+  archivePath: string
+  /** Unix seconds */
+  archivedAt: number
+  sessionCount: number
+}
+
+/** What decided whether a path is listed by file listing and search */
+export type IgnoreSource =
+  | 'projectRule'
+  | 'gitignore'
+  | 'defaultExclude'
+  | 'builtinDirectory'
+  | 'hidden'
+
+export interface IgnoreExplanation {
+  /** Relative to the project root */
+  path: string
+  ignored: boolean
+  /** null when no rule applies */
+  source: IgnoreSource | null
+  pattern: string | null
+  /** Ancestor directory whose rule hides this path */
+  via: string | null
+}
+
+export interface UsageBucket {
+  /** Project ID ('none'), model ('unknown'), YYYY-MM-DD (UTC) or 'all' */
+  key: string
+  turns: number
+  tokenUsage: SessionTokenUsage
+  /** Estimated USD cost of the priced turns */
+  costUsd: number
+  /** Turns whose model has no known pricing */
+  unpricedTurns: number
+}
+
+export interface UsageSummary {
+  totals: UsageBucket
+  byProject: UsageBucket[]
+  byModel: UsageBucket[]
+  byDay: UsageBucket[]
+}
+
+export interface UsageExport {
+  path: string
+  rows: number
+}
+
+/** `since`/`until` are Unix timestamps bounding when each turn started */
+export interface UsageFilter {
+  projectId?: string
+  since?: number
+  until?: number
+}
+
+export const usageApi = {
+  getSummary: (filter: UsageFilter = {}) =>
+    invoke<UsageSummary>('get_usage_summary', { ...filter }),
+
+  /** `path` is an absolute file path chosen by the user */
+  exportCsv: (path: string, filter: UsageFilter = {}) =>
+    invoke<UsageExport>('export_usage_csv', { path, ...filter }),
+}
+
 // ==================== Project API ====================
 
+export interface ProjectEnvVar {
+  name: string
+  value: string | null
+  /** Masked in logs, events and session text */
+  secret: boolean
+  /** Unix seconds */
+  updatedAt: number
+}
+
 export const projectApi = {
   list: () => invokeOrFallback<Project[]>([], 'list_projects'),
 
@@ -395,8 +1705,38 @@ export const projectApi = {
 
   remove: (id: string) => invoke<void>('remove_project', { id }),
 
-  update: (id: string, displayName?: string, settings?: Record<string, unknown>) =>
-    invoke<Project>('update_project', { id, displayName, settings }),
+  /** `destination` is a directory (file name generated) or an absolute file path */
+  archive: (id: string, destination: string) =>
+    invoke<ArchivedProject>('archive_project', { id, destination }),
+
+  unarchive: (archivePath: string) => invoke<Project>('unarchive_project', { archivePath }),
+
+  listArchived: () => invokeOrFallback<ArchivedProject[]>([], 'list_archived_projects'),
+
+  /** `settings` is merged into the stored settings; set a key to null to clear it */
+  update: (
+    id: string,
+    displayName?: string,
+    settings?: Record<string, unknown>,
+    lastOpenedAt?: number
+  ) => invoke<Project>('update_project', { id, displayName, settings, lastOpenedAt }),
+
+  /** Replaces color, icon and emoji; omit color to restore the default */
+  setAppearance: (
+    id: string,
+    appearance: { color?: string; icon?: string; emoji?: string }
+  ) => invoke<Project>('set_project_appearance', { id, ...appearance }),
+
+  /** Stored encrypted; reaches terminal commands and threads started afterwards */
+  setEnv: (projectId: string, name: string, value: string, secret = true) =>
+    invoke<void>('set_project_env', { projectId, name, value, secret }),
+
+  /** Values are `null` unless `reveal` is set */
+  listEnv: (projectId: string, reveal = false) =>
+    invoke<ProjectEnvVar[]>('list_project_env', { projectId, reveal }),
+
+  deleteEnv: (projectId: string, name: string) =>
+    invoke<boolean>('delete_project_env', { projectId, name }),
 
   getGitInfo: (path: string) =>
     withCache(
@@ -418,29 +1758,129 @@ export const projectApi = {
       : Promise.resolve({ diff: null, isGitRepo: false }),
   gitDiffBranch: (projectPath: string, baseBranch: string) =>
     invokeWithTimeout<string>('git_diff_branch', { projectPath, baseBranch }, 20000),
+  /** `truncated` is set when more files matched than `limit` allowed */
   listFiles: (path: string, query?: string, limit?: number) =>
     isTauriAvailable()
-      ? invoke<FileEntry[]>('list_project_files', { path, query, limit })
+      ? invoke<FileListResponse>('list_project_files', { path, query, limit })
+      : Promise.reject(new Error('Unavailable in web mode')),
+  /** Ranked fuzzy search over the project's file index */
+  searchFiles: (projectId: string, query: string, limit?: number) =>
+    isTauriAvailable()
+      ? invoke<FileMatch[]>('search_project_files', { projectId, query, limit })
       : Promise.reject(new Error('Unavailable in web mode')),
+  /** `relativeDir` is relative to the project root; '' lists the root */
+  getDirectoryChildren: (projectId: string, relativeDir: string) =>
+    isTauriAvailable()
+      ? invoke<DirectoryChildren>('get_directory_children', { projectId, relativeDir })
+      : Promise.reject(new Error('Unavailable in web mode')),
+
+  /** Newest first; watched projects also include files the watcher saw change */
+  getRecentlyModifiedFiles: (projectId: string, limit?: number) =>
+    isTauriAvailable()
+      ? invoke<RecentFile[]>('get_recently_modified_files', { projectId, limit })
+      : Promise.reject(new Error('Unavailable in web mode')),
+  searchContent: (
+    searchId: string,
+    path: string,
+    query: string,
+    options: ContentSearchOptions = {}
+  ) =>
+    isTauriAvailable()
+      ? invoke<ContentSearchSummary>('search_project_content', {
+          searchId,
+          path,
+          query,
+          ...options,
+        })
+      : Promise.reject(new Error('Unavailable in web mode')),
+  cancelContentSearch: (searchId: string) =>
+    invokeOrFallback(false, 'cancel_project_content_search', { searchId }),
+  /** Omit `rules` to explain with the project's saved `ignoreRules` setting */
+  previewIgnoreEffect: (path: string, rules?: string[]) =>
+    invoke<IgnoreExplanation>('preview_ignore_effect', { path, rules }),
   validateDirectory: (path: string) =>
     invoke<string>('validate_project_directory', { path }),
   readProjectFile: (projectId: string, relativePath: string) =>
     isTauriAvailable()
       ? invoke<number[]>('read_project_file', { projectId, relativePath })
       : Promise.reject(new Error('Unavailable in web mode')),
+  readProjectFileContent: (projectId: string, relativePath: string) =>
+    isTauriAvailable()
+      ? invoke<ProjectFileContent>('read_project_file_content', { projectId, relativePath })
+      : Promise.reject(new Error('Unavailable in web mode')),
+  writeProjectFile: (
+    projectId: string,
+    relativePath: string,
+    contents: string,
+    expectedHash?: string
+  ) =>
+    invoke<FileWriteResult>('write_project_file', {
+      projectId,
+      relativePath,
+      contents,
+      expectedHash,
+    }),
+  createProjectFile: (projectId: string, relativePath: string, contents?: string) =>
+    invoke<FileWriteResult>('create_project_file', { projectId, relativePath, contents }),
+  /** `newName` is a bare name; tracked paths are renamed with `git mv` */
+  renameProjectPath: (projectId: string, relativePath: string, newName: string) =>
+    invoke<ProjectPathResult>('rename_project_path', { projectId, relativePath, newName }),
+  /** `destinationDir` is project-relative; '' moves to the root */
+  moveProjectPath: (projectId: string, relativePath: string, destinationDir: string) =>
+    invoke<ProjectPathResult>('move_project_path', { projectId, relativePath, destinationDir }),
+  duplicateProjectPath: (projectId: string, relativePath: string) =>
+    invoke<ProjectPathResult>('duplicate_project_path', { projectId, relativePath }),
+  createDirectory: (projectId: string, relativePath: string) =>
+    invoke<ProjectPathResult>('create_directory', { projectId, relativePath }),
   getGitBranches: (path: string) => invoke<GitBranch[]>('get_git_branches', { path }),
   getGitCommits: (path: string, limit?: number) =>
     invoke<GitCommit[]>('get_git_commits', { path, limit }),
+  /**
+   * Sessions, commits, pull requests and diffs within `range`, for standups
+   * and sprint reviews; also written to `path` when given
+   */
+  generateChangeReport: (
+    projectId: string,
+    range: ReportRange,
+    format: SessionExportFormat,
+    options?: { includeDiff?: boolean; redact?: boolean; path?: string }
+  ) =>
+    invokeWithTimeout<ChangeReportExport>(
+      'generate_change_report',
+      { projectId, range, format, ...options },
+      120000
+    ),
   gitStatus: (path: string) =>
     invokeOrFallback<GitFileStatus[]>([], 'git_status', { path }),
   gitStageFiles: (path: string, files: string[]) =>
     invoke<void>('git_stage_files', { path, files }),
   gitUnstageFiles: (path: string, files: string[]) =>
     invoke<void>('git_unstage_files', { path, files }),
-  gitCommit: (path: string, message: string) =>
-    invoke<string>('git_commit', { path, message }),
-  gitPush: (path: string, remote: string, branch: string) =>
-    invoke<void>('git_push', { path, remote, branch }),
+  /**
+   * `sign` forces signing on or off; omitted, `commit.gpgSign` decides.
+   * `noVerify` skips the hooks and is recorded as a hook bypass.
+   */
+  gitCommit: (path: string, message: string, options?: { sign?: boolean; noVerify?: boolean }) =>
+    invoke<string>('git_commit', {
+      path,
+      message,
+      sign: options?.sign,
+      noVerify: options?.noVerify,
+    }),
+  getSigningConfig: (path: string) => invoke<SigningConfig>('get_signing_config', { path }),
+  /** Asks a lightweight turn unless `useModel` is false; falls back to file stats */
+  suggestCommitMessage: (path: string, options?: { useModel?: boolean; model?: string }) =>
+    invokeWithTimeout<CommitMessageSuggestion>(
+      'suggest_commit_message',
+      { path, useModel: options?.useModel, model: options?.model || undefined },
+      120000
+    ),
+  gitPush: (path: string, remote: string, branch: string, options?: { noVerify?: boolean }) =>
+    invoke<void>('git_push', { path, remote, branch, noVerify: options?.noVerify }),
+  listGitHooks: (path: string) => invoke<GitHooks>('list_git_hooks', { path }),
+  /** Newest first */
+  listHookBypasses: (path: string, limit?: number) =>
+    invoke<HookBypass[]>('list_hook_bypasses', { path, limit }),
   gitRemoteInfo: (path: string) =>
     invokeOrFallback<GitRemoteInfo>(
       { remote: null, branch: null, ahead: 0, behind: 0 },
@@ -448,8 +1888,14 @@ export const projectApi = {
       { path }
     ),
   // PR operations
-  checkGhCli: (projectPath: string) =>
-    invokeOrFallback<GhCliStatus>('not-installed', 'check_gh_cli', { projectPath }),
+  /** Detect the git host (GitHub, GitLab, Bitbucket) and check its CLI or credentials */
+  checkForgeCli: (projectPath: string) =>
+    invoke<ForgeStatus>('check_forge_cli', { projectPath }),
+  getForgeLinks: (projectPath: string, baseBranch: string, headBranch: string) =>
+    invoke<ForgeLinks>('get_forge_links', { projectPath, baseBranch, headBranch }),
+  /** Store the Bitbucket app password used for pull requests; omit it to remove them */
+  setBitbucketCredentials: (username: string, appPassword?: string) =>
+    invoke<void>('set_bitbucket_credentials', { username, appPassword }),
   getCurrentBranch: (projectPath: string) =>
     invoke<string>('get_current_branch', { projectPath }),
   createPullRequest: (
@@ -460,15 +1906,92 @@ export const projectApi = {
     headBranch: string,
     draft: boolean
   ) =>
-    invokeWithTimeout<string>(
+    invokeWithTimeout<CreatedPullRequest>(
       'create_pull_request',
       { projectPath, title, body, baseBranch, headBranch, draft },
       30000
     ),
+  listPullRequests: (projectPath: string, state?: PullRequestFilter, limit?: number) =>
+    invokeWithTimeout<PullRequestSummary[]>(
+      'list_pull_requests',
+      { projectPath, state, limit },
+      30000
+    ),
+  getPullRequest: (projectPath: string, number: number) =>
+    invokeWithTimeout<PullRequestDetail>('get_pull_request', { projectPath, number }, 60000),
+  /** `branch` defaults to the PR's head branch name */
+  checkoutPullRequest: (projectPath: string, number: number, branch?: string) =>
+    invokeWithTimeout<GitHeadState>(
+      'checkout_pull_request',
+      { projectPath, number, branch },
+      60000
+    ),
+  listPrReviewComments: (projectPath: string, number: number) =>
+    invokeWithTimeout<PullRequestComment[]>(
+      'list_pr_review_comments',
+      { projectPath, number },
+      30000
+    ),
+  /** Replies in `commentId`'s thread, or on the PR conversation without one */
+  replyToPrComment: (projectPath: string, number: number, body: string, commentId?: number) =>
+    invokeWithTimeout<PullRequestComment>(
+      'reply_to_pr_comment',
+      { projectPath, number, commentId, body },
+      30000
+    ),
+  /** Starts a thread on the PR's head branch seeded with the selected comments */
+  startPrReviewThread: (
+    projectId: string,
+    number: number,
+    commentIds: number[],
+    options?: {
+      instructions?: string
+      model?: string
+      sandbox?: string
+      approvalPolicy?: string
+    }
+  ) =>
+    invokeWithTimeout<PrReviewThread>(
+      'start_pr_review_thread',
+      {
+        projectId,
+        number,
+        commentIds,
+        instructions: options?.instructions || undefined,
+        model: options?.model || undefined,
+        sandbox: options?.sandbox || undefined,
+        approvalPolicy: options?.approvalPolicy || undefined,
+      },
+      120000
+    ),
+
+  // CI status of the current branch
+  getCiStatus: (projectPath: string) =>
+    invokeWithTimeout<CiStatus>('get_ci_status', { projectPath }, 60000),
+  /** Polls the current branch's CI status, emitting `ci-status-changed` */
+  watchCiStatus: (projectId: string) => invoke<void>('watch_ci_status', { projectId }),
+  unwatchCiStatus: (projectId: string) => invoke<boolean>('unwatch_ci_status', { projectId }),
 
   // Patch operations
-  gitApplyPatch: (projectPath: string, patch: string, cached: boolean, reverse = false) =>
-    invoke<void>('git_apply_patch', { projectPath, patch, cached, reverse }),
+  /**
+   * Checked first; a patch with errors is rejected with `patchPreflight`.
+   * `threeWay` merges drifted hunks, leaving conflict markers where needed.
+   */
+  gitApplyPatch: (
+    projectPath: string,
+    patch: string,
+    cached: boolean,
+    reverse = false,
+    threeWay = false
+  ) =>
+    invoke<PatchApplyResult>('git_apply_patch', { projectPath, patch, cached, reverse, threeWay }),
+  preflightPatch: (
+    projectPath: string,
+    patch: string,
+    cached: boolean,
+    reverse = false,
+    threeWay = false
+  ) => invoke<PatchPreflight>('preflight_patch', { projectPath, patch, cached, reverse, threeWay }),
 
   // Worktree operations
   createWorktree: (projectPath: string, branchName: string, worktreePath?: string) =>
@@ -476,20 +1999,35 @@ export const projectApi = {
   removeWorktree: (projectPath: string, worktreePath: string) =>
     invoke<void>('remove_worktree', { projectPath, worktreePath }),
   listWorktrees: (projectPath: string) =>
-    invokeOrFallback<WorktreeInfo[]>([], 'list_worktrees', { projectPath }),
+    invokeOrFallback<WorktreeStatus[]>([], 'list_worktrees', { projectPath }),
 
   // Swarm git operations
   gitCheckoutBranch: (projectPath: string, branchName: string) =>
     invoke<void>('git_checkout_branch', { projectPath, branchName }),
   gitMergeNoFf: (projectPath: string, branchName: string, message: string) =>
     invoke<GitMergeResult>('git_merge_no_ff', { projectPath, branchName, message }),
+
+  // Filesystem watching (emits `project:file-changed`)
+  watch: (projectId: string) =>
+    invokeOrFallback<void>(undefined, 'watch_project', { projectId }),
+  unwatch: (projectId: string) =>
+    invokeOrFallback<boolean>(false, 'unwatch_project', { projectId }),
 }
 
 // ==================== Session API ====================
 
 export const sessionApi = {
-  list: (projectId: string) =>
-    invokeOrFallback<SessionMetadata[]>([], 'list_sessions', { projectId }),
+  /**
+   * Favorites first; `tags` keeps only sessions carrying every listed tag and
+   * `archived` lists archived sessions instead of active ones
+   */
+  list: (projectId: string, tags?: string[], favoritesOnly?: boolean, archived?: boolean) =>
+    invokeOrFallback<SessionMetadata[]>([], 'list_sessions', {
+      projectId,
+      tags,
+      favoritesOnly,
+      archived,
+    }),
 
   get: (sessionId: string) =>
     invoke<SessionMetadata | null>('get_session', { sessionId }),
@@ -532,19 +2070,140 @@ export const sessionApi = {
   delete: (sessionId: string) =>
     invoke<void>('delete_session', { sessionId }),
 
+  /** Deletes all given sessions or none of them */
+  deleteMany: (sessionIds: string[]) =>
+    invoke<BulkSessionSummary>('delete_sessions', { sessionIds }),
+
+  archiveMany: (sessionIds: string[], archived: boolean) =>
+    invoke<BulkSessionSummary>('archive_sessions', { sessionIds, archived }),
+
+  /** Moves all given sessions or none of them */
+  moveMany: (sessionIds: string[], projectId: string) =>
+    invoke<BulkSessionSummary>('move_sessions_to_project', { sessionIds, projectId }),
+
+  /** Attach an imported session to a project */
+  adopt: (sessionId: string, projectId: string) =>
+    invoke<SessionMetadata>('adopt_session', { sessionId, projectId }),
+
+  /** Returns the session's tags after the change */
+  addTag: (sessionId: string, tag: string) =>
+    invoke<string[]>('add_session_tag', { sessionId, tag }),
+
+  /** Returns the session's tags after the change */
+  removeTag: (sessionId: string, tag: string) =>
+    invoke<string[]>('remove_session_tag', { sessionId, tag }),
+
+  /** Tags in use with session counts, across all projects or within one */
+  listTags: (projectId?: string) =>
+    invokeOrFallback<SessionTagCount[]>([], 'list_session_tags', { projectId }),
+
+  setFavorite: (sessionId: string, favorite: boolean) =>
+    invoke<void>('set_session_favorite', { sessionId, favorite }),
+
+  /**
+   * Full-text search across all projects' sessions
+   * Matches titles, first messages, tags and conversation content, archived
+   * sessions included; every term must match and the last one matches as a
+   * prefix. Results are ordered by `score`: relevance boosted for titles
+   * starting with the query, recent use and sessions of `projectId`, the
+   * project searched from. Sessions whose id, title, first message or tags
+   * contain the query as typed come last.
+   */
+  search: (
+    query: string,
+    tagsFilter?: string[],
+    favoritesOnly?: boolean,
+    limit?: number,
+    projectId?: string | null
+  ) =>
+    invoke<SessionSearchHit[]>('search_sessions', {
+      query,
+      tagsFilter,
+      favoritesOnly,
+      projectId,
+      limit,
+    }),
+
   /**
-   * Search sessions across all projects with relevance scoring
-   * Results are sorted by relevance score (descending):
-   * - Exact title match: 100 points
-   * - Title prefix match: 80 points
-   * - Title contains match: 60 points
-   * - firstMessage match: 40-50 points
-   * - Tag match: 30-35 points
-   * - sessionId match: 10 points
-   * - Favorites bonus: +5 points
+   * Render the whole session (messages, commands, diffs) and write it to
+   * `path`, an absolute file path chosen by the user
    */
-  search: (query: string, tagsFilter?: string[], favoritesOnly?: boolean) =>
-    invoke<SessionMetadata[]>('search_sessions', { query, tagsFilter, favoritesOnly }),
+  export: (sessionId: string, format: SessionExportFormat, path: string, redact?: boolean) =>
+    invokeWithTimeout<SessionExport>(
+      'export_session',
+      { sessionId, format, path, redact },
+      120000
+    ),
+
+  /** What `export` would mask; `settings` overrides the saved ones for this preview */
+  previewRedaction: (sessionId: string, settings?: RedactionSettings) =>
+    invokeWithTimeout<RedactionReport>(
+      'preview_session_redaction',
+      { sessionId, settings },
+      120000
+    ),
+
+  getRedactionSettings: () => invoke<RedactionSettings>('get_redaction_settings'),
+
+  setRedactionSettings: (settings: RedactionSettings) =>
+    invoke<RedactionSettings>('set_redaction_settings', { settings }),
+
+  getSecretMaskingSettings: () =>
+    invoke<SecretMaskingSettings>('get_secret_masking_settings'),
+
+  /** Applies immediately */
+  setSecretMaskingSettings: (settings: SecretMaskingSettings) =>
+    invoke<SecretMaskingSettings>('set_secret_masking_settings', { settings }),
+
+  /** Create a read-only session from an exported JSON transcript or a Codex rollout file */
+  import: (projectId: string, path: string) =>
+    invokeWithTimeout<SessionMetadata>('import_session', { projectId, path }, 120000),
+
+  /** `since` is a Unix timestamp; sessions are bucketed by the day they were created */
+  getStats: (filter: { projectId?: string; sessionId?: string; since?: number } = {}) =>
+    invoke<SessionStatsReport>('get_session_stats', filter),
+
+  /** `null` for turns that ran before timings were recorded */
+  getTurnTimings: (turnId: string) =>
+    invoke<TurnTimings | null>('get_turn_timings', { turnId }),
+
+  /** How the session's turns ended, newest first */
+  listTurnOutcomes: (sessionId: string, limit?: number) =>
+    invoke<TurnOutcomeRecord[]>('list_turn_outcomes', { sessionId, limit }),
+
+  /** Turns cut off by a crash, recovered from their on-disk journals */
+  listRecoveredTurns: (sessionId: string) =>
+    invoke<RecoveredTurn[]>('list_recovered_turns', { sessionId }),
+
+  /** Patches and command writes since the app started */
+  getLiveDiff: (sessionId: string) =>
+    invoke<LiveDiff>('get_session_live_diff', { sessionId }),
+
+  clearLiveDiff: (sessionId: string) =>
+    invoke<boolean>('clear_session_live_diff', { sessionId }),
+
+  /** `null` unless the session was started in a worktree of its own */
+  getWorktree: (sessionId: string) =>
+    invoke<SessionWorktreeStatus | null>('get_session_worktree', { sessionId }),
+
+  /** Merge the session's branch back (committing leftovers) or delete it, then remove the worktree */
+  cleanupWorktree: (sessionId: string, action: WorktreeCleanup, message?: string) =>
+    invokeWithTimeout<WorktreeCleanupReport>(
+      'cleanup_session_worktree',
+      { sessionId, action, message },
+      120000
+    ),
+
+  /** `null` for sessions without a recorded baseline (older or outside git) */
+  getBaselineDiff: (sessionId: string) =>
+    invoke<SessionBaselineDiff | null>('get_session_baseline_diff', { sessionId }),
+
+  /** Items of an imported session, in place of `threadApi.resume` */
+  getImportedItems: (sessionId: string) =>
+    invoke<Array<{ id: string; type: string } & Record<string, unknown>>>(
+      'get_imported_session_items',
+      { sessionId }
+    ),
 
   // Lightweight status update
   updateStatus: (sessionId: string, status: SessionStatus) =>
@@ -568,6 +2227,14 @@ export const sessionApi = {
 
 // ==================== Thread API ====================
 
+/** Thread options a project sets for its new threads */
+export interface ThreadOptions {
+  model: string | null
+  reasoningEffort: string | null
+  sandbox: string | null
+  approvalPolicy: string | null
+}
+
 export const threadApi = {
   list: (limit?: number, cursor?: string) =>
     invoke<ThreadListResponse>('list_threads', { limit, cursor }),
@@ -582,6 +2249,8 @@ export const threadApi = {
       baseInstructions?: string
       developerInstructions?: string
       config?: Record<string, unknown>
+      /** Run the agent in a subdirectory of the project (e.g. a monorepo package) */
+      subpath?: string
     }
   ) =>
     invoke<ThreadStartResponse>('start_thread', {
@@ -594,11 +2263,21 @@ export const threadApi = {
       baseInstructions: options?.baseInstructions || undefined,
       developerInstructions: options?.developerInstructions || undefined,
       config: options?.config,
+      subpath: options?.subpath || undefined,
     }),
 
+  /** Project defaults `start` falls back to; `null` leaves an option to the Codex config */
+  getEffectiveOptions: (projectId: string) =>
+    invoke<ThreadOptions>('get_effective_thread_options', { projectId }),
+
   resume: (threadId: string) =>
     invokeWithTimeout<ThreadResumeResponse>('resume_thread', { threadId }, 45000), // 45s timeout for resume
 
+  /**
+   * Start a turn. While rate-limit queueing is on and a window is nearly
+   * exhausted, the turn is held back: the result has status 'queued' and
+   * `rate-limit-queue-released` reports the real turn id later.
+   */
   sendMessage: (
     threadId: string,
     text: string,
@@ -610,6 +2289,8 @@ export const threadApi = {
       model?: string
       approvalPolicy?: string
       sandboxPolicy?: string
+      /** Replaces the default turn budget for this turn */
+      budget?: TurnBudget
     }
   ) =>
     invokeWithTimeout<TurnStartResponse>(
@@ -627,6 +2308,23 @@ export const threadApi = {
   interrupt: (threadId: string) =>
     invoke<void>('interrupt_turn', { threadId }),
 
+  listActiveTurns: () => invoke<ActiveTurn[]>('list_active_turns'),
+
+  /** Returns how many turns were interrupted */
+  interruptAll: () => invoke<number>('interrupt_all_turns'),
+
+  getConcurrencySettings: () =>
+    invoke<TurnConcurrencySettings>('get_turn_concurrency_settings'),
+
+  setConcurrencySettings: (settings: TurnConcurrencySettings) =>
+    invoke<TurnConcurrencySettings>('set_turn_concurrency_settings', { settings }),
+
+  /** Default budget of every turn */
+  getBudgetSettings: () => invoke<TurnBudget>('get_turn_budget_settings'),
+
+  setBudgetSettings: (settings: TurnBudget) =>
+    invoke<TurnBudget>('set_turn_budget_settings', { settings }),
+
   respondToApproval: (
     threadId: string,
     itemId: string,
@@ -641,6 +2339,138 @@ export const threadApi = {
       requestId,
       execpolicyAmendment,
     }),
+
+  // Follow-up queue: prompts sent automatically after each successful turn
+  queueFollowup: (sessionId: string, prompt: string) =>
+    invoke<QueuedFollowup>('queue_followup', { sessionId, prompt }),
+  listFollowups: (sessionId: string) =>
+    invokeOrFallback<QueuedFollowup[]>([], 'list_followups', { sessionId }),
+  removeFollowup: (sessionId: string, followupId: string) =>
+    invoke<boolean>('remove_followup', { sessionId, followupId }),
+  clearFollowups: (sessionId: string) =>
+    invokeOrFallback<number>(0, 'clear_followups', { sessionId }),
+
+  // Turn queue: prompts run one after another per project, persisted across restarts
+  enqueueTurn: (projectId: string, threadId: string, prompt: string, runAfter?: number) =>
+    invoke<QueuedTurn>('enqueue_turn', { projectId, threadId, prompt, runAfter }),
+  listQueue: (projectId?: string, includeFinished = false) =>
+    invokeOrFallback<QueuedTurn[]>([], 'list_queue', { projectId, includeFinished }),
+  /** `queuedIds` must list every waiting entry of the project */
+  reorderQueue: (projectId: string, queuedIds: string[]) =>
+    invoke<QueuedTurn[]>('reorder_queue', { projectId, queuedIds }),
+  cancelQueuedTurn: (queuedId: string) => invoke<boolean>('cancel_queued_turn', { queuedId }),
+  getQueueStatus: () =>
+    invokeOrFallback<TurnQueueStatus>({ paused: false, pausedProjects: [] }, 'get_queue_status'),
+  /** Without `projectId` pauses or resumes every queue */
+  setQueuePaused: (paused: boolean, projectId?: string) =>
+    invoke<TurnQueueStatus>('set_queue_paused', { projectId, paused }),
+
+  // Attachment budget: what of the attached files fits into a prompt
+  /** `settings` overrides the saved budget for this call only */
+  prepareAttachments: (projectId: string, paths: string[], settings?: AttachmentBudgetSettings) =>
+    invoke<AttachmentBudget>('prepare_attachments', { projectId, paths, settings }),
+  getAttachmentBudgetSettings: () =>
+    invoke<AttachmentBudgetSettings>('get_attachment_budget_settings'),
+  setAttachmentBudgetSettings: (settings: AttachmentBudgetSettings) =>
+    invoke<AttachmentBudgetSettings>('set_attachment_budget_settings', { settings }),
+
+  // Summarize a session and continue it as a new thread in another project
+  handoffSession: (
+    fromSession: string,
+    toProject: string,
+    options?: {
+      instructions?: string
+      model?: string
+      sandbox?: string
+      approvalPolicy?: string
+    }
+  ) =>
+    invokeWithTimeout<HandoffResponse>(
+      'handoff_session',
+      {
+        fromSession,
+        toProject,
+        instructions: options?.instructions || undefined,
+        model: options?.model || undefined,
+        sandbox: options?.sandbox || undefined,
+        approvalPolicy: options?.approvalPolicy || undefined,
+      },
+      360000 // 360s timeout: waits for the summarizer turn
+    ),
+
+  listWorkflows: () => invokeOrFallback<WorkflowDefinition[]>([], 'list_workflows'),
+
+  startWorkflow: (
+    projectId: string,
+    workflowId: string,
+    params: Record<string, string>,
+    options?: { model?: string; sandbox?: string; approvalPolicy?: string }
+  ) =>
+    invokeWithTimeout<WorkflowStartResponse>(
+      'start_workflow',
+      {
+        projectId,
+        workflowId,
+        params,
+        model: options?.model || undefined,
+        sandbox: options?.sandbox || undefined,
+        approvalPolicy: options?.approvalPolicy || undefined,
+      },
+      180000 // 180s timeout: may run the project's test suite first
+    ),
+
+  /**
+   * Run one prompt in several projects or worktrees, each in a new thread.
+   * Resolves once every target has finished; progress arrives as `batch-progress`.
+   */
+  runBatch: (
+    prompt: string,
+    targets: BatchTarget[],
+    options?: { model?: string; sandbox?: string; approvalPolicy?: string }
+  ) =>
+    invoke<BatchReport>('run_batch', {
+      prompt,
+      targets,
+      model: options?.model || undefined,
+      sandbox: options?.sandbox || undefined,
+      approvalPolicy: options?.approvalPolicy || undefined,
+    }),
+
+  /**
+   * Run one prompt in several new worktrees of a project, one per variant.
+   * Resolves once the worktrees exist; runs report through `comparison-updated`.
+   */
+  runComparison: (
+    projectId: string,
+    prompt: string,
+    variants: ComparisonVariant[],
+    options?: { sandbox?: string; approvalPolicy?: string }
+  ) =>
+    invokeWithTimeout<ComparisonResults>(
+      'run_comparison',
+      {
+        projectId,
+        prompt,
+        variants,
+        sandbox: options?.sandbox || undefined,
+        approvalPolicy: options?.approvalPolicy || undefined,
+      },
+      120000
+    ),
+
+  listComparisons: (projectId: string) =>
+    invokeOrFallback<ComparisonGroup[]>([], 'list_comparisons', { projectId }),
+
+  getComparisonResults: (groupId: string) =>
+    invoke<ComparisonResults>('get_comparison_results', { groupId }),
+
+  /** Merge the winner's branch (if any) into the project, then remove every run's worktree */
+  resolveComparison: (groupId: string, winnerRunId: string | null, message?: string) =>
+    invokeWithTimeout<ComparisonResolution>(
+      'resolve_comparison',
+      { groupId, winnerRunId, message },
+      120000
+    ),
 }
 
 // ==================== Snapshot API ====================
@@ -649,8 +2479,59 @@ export const snapshotApi = {
   create: (sessionId: string, projectPath: string) =>
     invokeWithTimeout<Snapshot>('create_snapshot', { sessionId, projectPath }, 45000), // 45s timeout for snapshot creation
 
+  /** Returns the snapshot taken of the state before the revert, if enabled */
   revert: (snapshotId: string, projectPath: string) =>
-    invokeWithTimeout<void>('revert_to_snapshot', { snapshotId, projectPath }, 45000), // 45s timeout for snapshot revert
+    invokeWithTimeout<Snapshot | null>('revert_to_snapshot', { snapshotId, projectPath }, 45000), // 45s timeout for snapshot revert
+
+  /** Restore only `paths`; `dryRun` reports the plan without changing files */
+  restoreFiles: (snapshotId: string, projectPath: string, paths: string[], dryRun = false) =>
+    invokeWithTimeout<FileRestoreReport>(
+      'restore_files_from_snapshot',
+      { snapshotId, projectPath, paths, dryRun },
+      45000
+    ),
+
+  undoLastRevert: (sessionId: string, projectPath: string) =>
+    invokeWithTimeout<Snapshot>('undo_last_revert', { sessionId, projectPath }, 45000),
+
+  diff: (snapshotA: string, snapshotB: string, projectPath: string) =>
+    invokeWithTimeout<SnapshotDiff>('diff_snapshots', { snapshotA, snapshotB, projectPath }, 45000),
+
+  /** What reverting to the snapshot would undo */
+  diffToWorktree: (snapshotId: string, projectPath: string) =>
+    invokeWithTimeout<SnapshotDiff>('diff_snapshot_to_worktree', { snapshotId, projectPath }, 45000),
+
+  /** `baseSnapshotId` is required for file backup snapshots */
+  exportPatch: (snapshotId: string, projectPath: string, baseSnapshotId?: string, path?: string) =>
+    invokeWithTimeout<SnapshotPatch>(
+      'export_snapshot_patch',
+      { snapshotId, projectPath, baseSnapshotId, path },
+      45000
+    ),
+
+  applyToPath: (
+    snapshotId: string,
+    projectPath: string,
+    targetPath: string,
+    options: { baseSnapshotId?: string; dryRun?: boolean } = {}
+  ) =>
+    invokeWithTimeout<PatchApplyReport>(
+      'apply_snapshot_to_path',
+      { snapshotId, projectPath, targetPath, ...options },
+      45000
+    ),
+
+  getSettings: () => invoke<SnapshotSettings>('get_snapshot_settings'),
+
+  setSettings: (settings: SnapshotSettings) =>
+    invoke<SnapshotSettings>('set_snapshot_settings', { settings }),
+
+  getStorageUsage: () => invoke<SnapshotStorageUsage>('get_snapshot_storage_usage'),
+
+  getSizes: () => invoke<SnapshotSize[]>('get_snapshot_sizes'),
+
+  /** Deduplicate snapshot files and delete ones no snapshot references */
+  compact: () => invokeWithTimeout<CompactionReport>('compact_snapshot_storage', undefined, 120000),
 
   list: (sessionId: string) =>
     invoke<Snapshot[]>('list_snapshots', { sessionId }),
@@ -662,12 +2543,32 @@ export const serverApi = {
   getStatus: () =>
     withCache(
       CACHE_KEYS.SERVER_STATUS,
-      () => invokeOrFallback<ServerStatus>({ isRunning: false, version: null }, 'get_server_status'),
+      () => invokeOrFallback<ServerStatus>(
+        { isRunning: false, state: 'stopped', degraded: null, version: null },
+        'get_server_status'
+      ),
       CACHE_TTL.SERVER_STATUS
     ),
 
   restart: () => (isTauriAvailable() ? invoke<void>('restart_server') : Promise.resolve()),
 
+  getLaunchSettings: () => invoke<AppServerLaunchSettings>('get_app_server_launch_settings'),
+
+  /** Takes effect on the next restart */
+  setLaunchSettings: (settings: AppServerLaunchSettings) =>
+    invoke<AppServerLaunchSettings>('set_app_server_launch_settings', { settings }),
+
+  /** Runs `codex --version` on every binary found, so it can take a few seconds */
+  detectInstallations: () =>
+    invokeWithTimeout<CodexInstallation[]>('detect_codex_installations', {}, 60000),
+
+  checkCodexVersion: () =>
+    invokeWithTimeout<CodexVersionCheck>('check_codex_version', {}, 30000),
+
+  /** Runs the package manager; restarts the app-server if it is running */
+  upgradeCodex: () =>
+    invokeWithTimeout<CodexVersionCheck>('upgrade_codex', {}, 330000),
+
   getAccountInfo: () =>
     withCache(
       CACHE_KEYS.ACCOUNT_INFO,
@@ -682,9 +2583,33 @@ export const serverApi = {
       CACHE_TTL.RATE_LIMITS
     ),
 
+  getRateLimitForecast: () =>
+    isTauriAvailable()
+      ? invoke<RateLimitForecast>('get_rate_limit_forecast')
+      : Promise.reject(new Error('Unavailable in web mode')),
+
+  /** Thresholds must satisfy 0 < warning <= queue <= 100 */
+  setRateLimitSettings: (queueWhenLimited: boolean, warningPercent?: number, queuePercent?: number) =>
+    invoke<RateLimitSettings>('set_rate_limit_settings', { queueWhenLimited, warningPercent, queuePercent }),
+
+  cancelRateLimitedMessage: (queuedId: string) =>
+    invoke<boolean>('cancel_rate_limited_message', { queuedId }),
+
+  /** Recorded JSON-RPC traffic; `method` filters by substring */
+  getRpcTrace: (options: { limit?: number; method?: string } = {}) =>
+    invoke<RpcTraceSnapshot>('get_rpc_trace', options),
+
+  clearRpcTrace: () => invoke<void>('clear_rpc_trace'),
+
+  setRpcTraceConfig: (enabled: boolean, capacity?: number, logToFile?: boolean) =>
+    invoke<RpcTraceSettings>('set_rpc_trace_config', { enabled, capacity, logToFile }),
+
   startLogin: (loginType: 'chatgpt' | 'apiKey' = 'chatgpt', apiKey?: string) =>
     invoke<LoginResponse>('start_login', { loginType, apiKey }),
 
+  /** Restart the login reported by `login-required`; fails for API key logins */
+  startReauth: () => invoke<LoginResponse>('start_reauth'),
+
   /**
    * Log out of account
    * P2.2: Clear all caches on logout to ensure correct state reset
@@ -695,6 +2620,37 @@ export const serverApi = {
     return result
   },
 
+  listAccounts: () => invokeOrFallback<SavedAccount[]>([], 'list_accounts'),
+
+  /** Save the current login; saving it again refreshes credentials and label */
+  saveCurrentAccount: (label?: string) => invoke<SavedAccount>('save_current_account', { label }),
+
+  /** Restarts the app server, interrupting running turns */
+  switchAccount: async (accountId: string) => {
+    const result = await invoke<SavedAccount>('switch_account', { accountId })
+    clearAllCache() // Account info, models and rate limits belong to the old login
+    return result
+  },
+
+  removeAccount: (accountId: string) => invoke<boolean>('remove_account', { accountId }),
+
+  listModelProviders: () => invokeOrFallback<ModelProviderInfo[]>([], 'list_model_providers'),
+
+  /** A given `apiKey` is stored in the keychain ('' removes it) and restarts the app server */
+  saveModelProvider: (provider: CustomModelProvider, apiKey?: string) =>
+    invoke<ModelProviderInfo>('save_model_provider', { provider, apiKey }),
+
+  removeModelProvider: (providerId: string) =>
+    invoke<boolean>('remove_model_provider', { providerId }),
+
+  /** Restarts the app server; refused while turns are running */
+  setModelProviderApiKey: (providerId: string, apiKey: string | null) =>
+    invoke<void>('set_model_provider_api_key', { providerId, apiKey }),
+
+  /** Tests unsaved settings too; without `apiKey` the stored key is used */
+  testProviderConnection: (provider: CustomModelProvider, apiKey?: string) =>
+    invokeWithTimeout<ProviderConnectionTest>('test_provider_connection', { provider, apiKey }, 20000),
+
   /**
    * Get available models list
    * P2.2: Add 5-minute cache since models rarely change
@@ -758,15 +2714,92 @@ export const configApi = {
 
 // ==================== Allowlist API ====================
 
+/**
+ * Which command approvals a project answers without asking. Deny-rule
+ * matches and file changes are always asked.
+ */
+export type ApprovalPreset = 'autoApproveRead' | 'askForEverything'
+
+export interface AllowlistSegmentMatch {
+  /** The segment as parsed, words joined by spaces */
+  text: string
+  patterns: string[]
+}
+
+export interface AllowlistCheck {
+  /** The whole command, or every segment of it, is allowlisted */
+  allowlisted: boolean
+  matchedPatterns: string[]
+  segments: AllowlistSegmentMatch[]
+}
+
+/**
+ * Allowlist patterns are literal commands, globs (`npm run *`, `cargo test*`;
+ * `?` matches one character, `\` escapes) or `re:` regexes, matched against
+ * each command of a compound command line.
+ */
 export const allowlistApi = {
   get: (projectId: string) =>
     invoke<string[]>('get_allowlist', { projectId }),
 
+  /** Returns the pattern as stored (whitespace normalized) */
   add: (projectId: string, commandPattern: string) =>
-    invoke<void>('add_to_allowlist', { projectId, commandPattern }),
+    invoke<string>('add_to_allowlist', { projectId, commandPattern }),
 
   remove: (projectId: string, commandPattern: string) =>
     invoke<void>('remove_from_allowlist', { projectId, commandPattern }),
+
+  /** Tests `pattern` alone when given, otherwise the project's allowlist */
+  checkMatch: (projectId: string, command: string, pattern?: string) =>
+    invoke<AllowlistCheck>('check_allowlist_match', { projectId, command, pattern }),
+
+  /** Denylist patterns use the allowlist syntax; matches are never auto-approved */
+  getDenylist: (projectId: string) =>
+    invoke<string[]>('get_denylist', { projectId }),
+
+  addToDenylist: (projectId: string, commandPattern: string) =>
+    invoke<string>('add_to_denylist', { projectId, commandPattern }),
+
+  removeFromDenylist: (projectId: string, commandPattern: string) =>
+    invoke<void>('remove_from_denylist', { projectId, commandPattern }),
+
+  /** Risk level, score and reasons, as attached to approval requests */
+  classifyCommand: (command: string, options?: { cwd?: string; projectId?: string }) =>
+    invoke<CommandContext>('classify_command', {
+      command,
+      cwd: options?.cwd,
+      projectId: options?.projectId,
+    }),
+
+  getApprovalPolicy: (projectId: string) =>
+    invoke<ApprovalPreset>('get_approval_policy', { projectId }),
+
+  setApprovalPolicy: (projectId: string, preset: ApprovalPreset) =>
+    invoke<ApprovalPreset>('set_approval_policy', { projectId, preset }),
+}
+
+// ==================== Settings Transfer API ====================
+
+export interface SettingsImportSummary {
+  /** Global settings sections replaced */
+  globalSections: string[]
+  /** Allowlist patterns added */
+  allowlistPatterns: number
+  projectsAdded: number
+  projectsUpdated: number
+  /** Entries left out, with the reason */
+  skipped: string[]
+  /** Renderer-owned settings (prompt library, keybindings) from the file */
+  renderer: unknown | null
+}
+
+export const settingsTransferApi = {
+  /** `renderer` is stored as given and handed back on import */
+  exportSettings: (path: string, includeProjects = false, renderer?: unknown) =>
+    invoke<string>('export_settings', { path, includeProjects, renderer }),
+
+  importSettings: (path: string) =>
+    invoke<SettingsImportSummary>('import_settings', { path }),
 }
 
 // ==================== Lifecycle API ====================
@@ -777,10 +2810,257 @@ export const lifecycleApi = {
 
   rendererHeartbeat: () =>
     invokeOrFallback<void>(undefined, 'renderer_heartbeat'),
+
+  getBackendHeartbeatConfig: () =>
+    invokeOrFallback<BackendHeartbeatSettings>(
+      { enabled: false, intervalSecs: 10 },
+      'get_backend_heartbeat_config'
+    ),
+
+  setBackendHeartbeatConfig: (enabled: boolean, intervalSecs?: number) =>
+    invoke<BackendHeartbeatSettings>('set_backend_heartbeat_config', { enabled, intervalSecs }),
+
+  getBackgroundTasks: () => invoke<BackgroundTasksInfo>('get_background_tasks'),
+
+  /** Applies immediately; turning project watchers off stops running ones */
+  setBackgroundTaskSettings: (settings: BackgroundTaskSettings) =>
+    invoke<BackgroundTasksInfo>('set_background_task_settings', { settings }),
+
+  getSafeModeReason: () =>
+    invokeOrFallback<SafeModeReason | null>(null, 'get_safe_mode_reason'),
+
+  /** Resets the crash-loop counter and relaunches the app */
+  exitSafeMode: () => invoke<void>('exit_safe_mode'),
+}
+
+// ==================== App Lock API ====================
+
+export interface AppLockSettings {
+  /** A passphrase is set; changed only through `setPassphrase` */
+  enabled: boolean
+  lockOnLaunch: boolean
+  /** Lock after this many minutes without user activity; `null` never */
+  idleTimeoutMins: number | null
+}
+
+export interface AppLockStatus extends AppLockSettings {
+  locked: boolean
+}
+
+/** While locked, every other command fails with "Codex Desktop is locked" */
+export const appLockApi = {
+  getStatus: () => invoke<AppLockStatus>('get_app_lock_status'),
+
+  lock: () => invoke<AppLockStatus>('lock_app'),
+
+  /** Wrong passphrases are answered after a growing delay */
+  unlock: (passphrase: string) => invoke<AppLockStatus>('unlock_app', { passphrase }),
+
+  /** Pass no `passphrase` to remove the lock; `current` is required once one is set */
+  setPassphrase: (current: string | null, passphrase: string | null) =>
+    invoke<AppLockStatus>('set_app_lock_passphrase', { current, passphrase }),
+
+  setSettings: (settings: AppLockSettings) =>
+    invoke<AppLockStatus>('set_app_lock_settings', { settings }),
+
+  /** Postpones the idle lock; call on user input, throttled */
+  reportActivity: () => invokeOrFallback<void>(undefined, 'report_user_activity'),
+}
+
+// ==================== Approval Shortcuts API ====================
+
+export interface PendingApproval {
+  requestId: number
+  /** e.g. `item/commandExecution/requestApproval` */
+  method: string
+  threadId: string | null
+  itemId: string | null
+  /** The command to run, or the reason given for a file change */
+  summary: string | null
+  /** Risk of a command; `null` for file changes */
+  risk: 'low' | 'medium' | 'high' | null
+  /** The command matches a built-in deny rule or the project denylist */
+  denyMatched: boolean
+  /** Unix milliseconds */
+  receivedAt: number
+}
+
+export interface ApprovalShortcutSettings {
+  enabled: boolean
+  /** Accelerators like "CommandOrControl+Alt+Shift+Y"; `null` for none */
+  approve: string | null
+  deny: string | null
+  /** Show a notification saying what a shortcut answered */
+  confirmNotification: boolean
+}
+
+/** System-wide shortcuts that approve or deny the newest pending approval */
+export const approvalShortcutsApi = {
+  listPending: () => invoke<PendingApproval[]>('list_pending_approvals'),
+
+  getSettings: () => invoke<ApprovalShortcutSettings>('get_approval_shortcut_settings'),
+
+  /** Fails without saving when a shortcut is taken by another app */
+  setSettings: (settings: ApprovalShortcutSettings) =>
+    invoke<ApprovalShortcutSettings>('set_approval_shortcut_settings', { settings }),
+}
+
+// ==================== Quick Prompt API ====================
+
+export interface SummonShortcutSettings {
+  enabled: boolean
+  /** Accelerators like "CommandOrControl+Shift+Space"; `null` for none */
+  showWindow: string | null
+  /** Shows the window and opens the quick prompt palette */
+  quickPrompt: string | null
+}
+
+/** Where a quick prompt goes by default */
+export interface QuickPromptTarget {
+  projectId: string
+  projectPath: string
+  projectName: string | null
+  /** Latest unarchived thread; `null` starts a new one */
+  threadId: string | null
+  threadTitle: string | null
+}
+
+export const quickPromptApi = {
+  getShortcutSettings: () => invoke<SummonShortcutSettings>('get_summon_shortcut_settings'),
+
+  /** Fails without saving when a shortcut is taken, here or by another app */
+  setShortcutSettings: (settings: SummonShortcutSettings) =>
+    invoke<SummonShortcutSettings>('set_summon_shortcut_settings', { settings }),
+
+  /** `null` before any project was added */
+  getTarget: () => invoke<QuickPromptTarget | null>('get_quick_prompt_target'),
+}
+
+// ==================== Notifications API ====================
+
+/** Which native notifications the backend shows, and when */
+export interface NotificationPreferences {
+  version: number
+  enabled: boolean
+  turnCompleted: boolean
+  approvalRequested: boolean
+  /** Turns that failed */
+  errors: boolean
+  appServerCrashed: boolean
+  /** Hold notifications while the app window is focused */
+  onlyWhenUnfocused: boolean
+  sound: boolean
+  /** Local times as "HH:MM"; nothing is shown in between */
+  doNotDisturb: { start: string; end: string } | null
+}
+
+export const notificationsApi = {
+  getPreferences: () => invoke<NotificationPreferences>('get_notification_preferences'),
+
+  /** Fails when a do-not-disturb time is not "HH:MM" */
+  setPreferences: (settings: NotificationPreferences) =>
+    invoke<NotificationPreferences>('set_notification_preferences', { settings }),
+}
+
+// ==================== Tray API ====================
+
+export interface TraySettings {
+  version: number
+  enabled: boolean
+  /** Closing the main window hides it to the tray instead of quitting */
+  closeToTray: boolean
+  /** Show the number of pending approvals on the tray icon */
+  showPendingCount: boolean
+}
+
+export const trayApi = {
+  getSettings: () => invoke<TraySettings>('get_tray_settings'),
+
+  /** Adds or removes the tray icon right away */
+  setSettings: (settings: TraySettings) =>
+    invoke<TraySettings>('set_tray_settings', { settings }),
+}
+
+// ==================== Locale API ====================
+
+export type Locale = 'en' | 'zh-CN'
+
+export interface LocaleInfo {
+  locale: Locale
+  /** Locales with bundled translations */
+  available: Locale[]
+}
+
+/** Language of error messages and notifications produced by the backend */
+export const localeApi = {
+  get: () => invoke<LocaleInfo>('get_locale'),
+
+  /** Takes a tag like `navigator.language`; unsupported languages fall back to English */
+  set: (tag: string) => invoke<LocaleInfo>('set_locale', { tag }),
+}
+
+// ==================== Garbage Collection API ====================
+
+export interface OrphanGroup {
+  count: number
+  bytes: number
+}
+
+export interface GcReport {
+  database: {
+    sessions: OrphanGroup
+    snapshots: OrphanGroup
+    allowlist: OrphanGroup
+    searchIndex: OrphanGroup
+  }
+  /** Stale image attachments in the temp directory */
+  attachments: OrphanGroup
+  /** Approximate reclaimable bytes across all categories */
+  totalBytes: number
+}
+
+export const gcApi = {
+  /** What `run` would remove, without deleting anything */
+  preview: () => invoke<GcReport>('get_gc_preview'),
+  /** Remove orphaned data; returns what was removed */
+  run: () => invoke<GcReport>('run_gc'),
 }
 
 // ==================== System API (Keep Awake) ====================
 
+/** Prompts waiting to be sent on one session */
+export interface QueuedMessages {
+  sessionId: string
+  projectId: string | null
+  /** Follow-ups sent once the current turn completes */
+  followups: number
+  /** Waiting entries of the project's turn queue */
+  queuedTurns: number
+}
+
+/** A session worktree branch with commits its base branch lacks */
+export interface UnpushedBranch {
+  sessionId: string
+  projectId: string
+  branch: string
+  commits: number
+}
+
+export interface PendingWorkSummary {
+  /** Turns still running */
+  unfinishedTurns: ActiveTurn[]
+  queuedMessages: QueuedMessages[]
+  pendingApprovals: PendingApproval[]
+  /** The failed entry holding back each paused project queue */
+  failedTasks: QueuedTurn[]
+  /** Counted at most once a minute */
+  unpushedCommits: UnpushedBranch[]
+  /** Number of entries above */
+  total: number
+  /** Unix ms */
+  computedAt: number
+}
+
 export const systemApi = {
   startKeepAwake: () => invoke<void>('start_keep_awake'),
   stopKeepAwake: () => invoke<void>('stop_keep_awake'),
@@ -788,6 +3068,11 @@ export const systemApi = {
   getAppPaths: () => invokeOrFallback<AppPaths>({ appDataDir: null, logDir: null }, 'get_app_paths'),
   getLogTail: (maxBytes?: number) =>
     invokeOrFallback<LogTailResponse>({ file: null, content: '', truncated: false }, 'get_log_tail', { maxBytes }),
+  listActions: () => invokeOrFallback<Action[]>([], 'list_actions'),
+  /** Check the CLI, git, gh, database, disk, app-server and permissions; takes a few seconds */
+  runDiagnostics: () => invoke<DiagnosticsReport>('run_diagnostics'),
+  /** What needs the user across all projects; cheap enough to call on every focus */
+  getPendingWorkSummary: () => invoke<PendingWorkSummary>('get_pending_work_summary'),
 }
 
 // ==================== Codex CLI Import Types ====================
@@ -904,11 +3189,63 @@ export const codexImportApi = {
 
 export interface TerminalOutput {
   exitCode: number | null
+  /** Scrollback run holding the whole output */
+  runId: string
+  /** Lines cut to `maxLineChars` in `terminal:stdout` / `terminal:stderr` */
+  truncatedLines: number
+}
+
+/** How terminal output is cleaned up before it reaches the renderer */
+export interface TerminalOutputSettings {
+  /** Longer lines are cut in terminal events; the scrollback keeps them whole */
+  maxLineChars: number
+  /** Mask secrets, scrollback included */
+  redactSecrets: boolean
+}
+
+export interface ScrollbackLine {
+  stream: 'stdout' | 'stderr'
+  text: string
+}
+
+export interface ScrollbackPage {
+  runId: string
+  offset: number
+  lines: ScrollbackLine[]
+  totalLines: number
+  /** Oldest lines dropped once the run's output grew too large */
+  droppedLines: number
+  finished: boolean
+}
+
+/** Restrictions on the terminal and user shell commands a project runs */
+export interface SandboxProfile {
+  /** `workspace` allows writes to the project, `writableRoots` and temp directories */
+  filesystem: 'full' | 'workspace' | 'readOnly'
+  blockNetwork: boolean
+  writableRoots: string[]
+}
+
+export interface SandboxSupport {
+  backend: 'sandboxExec' | 'bubblewrap' | 'unshare' | null
+  filesystem: boolean
+  network: boolean
 }
 
 export const terminalApi = {
-  execute: (cwd: string, command: string) =>
-    invokeWithTimeout<TerminalOutput>('execute_terminal_command', { cwd, command }, 120000), // 2 minute timeout for terminal commands
+  /** Runs in the sandbox of `projectId`, or of the project containing `cwd` */
+  execute: (cwd: string, command: string, projectId?: string) =>
+    invokeWithTimeout<TerminalOutput>('execute_terminal_command', { cwd, command, projectId }, 120000), // 2 minute timeout for terminal commands
+
+  getSandboxSupport: () => invoke<SandboxSupport>('get_sandbox_support'),
+
+  /** Whole lines of one of the most recent runs */
+  getScrollback: (runId: string, offset?: number, limit?: number) =>
+    invoke<ScrollbackPage>('get_terminal_scrollback', { runId, offset, limit }),
+  getOutputSettings: () => invoke<TerminalOutputSettings>('get_terminal_output_settings'),
+  /** Applies to commands started afterwards */
+  setOutputSettings: (settings: TerminalOutputSettings) =>
+    invoke<TerminalOutputSettings>('set_terminal_output_settings', { settings }),
 }
 
 // ==================== Cache Utilities (P2.2) ====================
@@ -2,6 +2,7 @@
 
 import { log } from './logger'
 import { isRecord, isError, isTauriError, type TauriErrorShape } from './typeGuards'
+import type { PatchPreflight, SigningFailure } from './api'
 
 // ==================== Centralized Error Logging ====================
 // Provides a unified interface for error logging across the application
@@ -58,8 +59,35 @@ export function logError(error: unknown, options: LogErrorOptions = {}): void {
   }
 }
 
+/** `Error::code()` of the backend */
+export type ErrorCode =
+  | 'database'
+  | 'io'
+  | 'json'
+  | 'app_server'
+  | 'app_server_crashed'
+  | 'codex'
+  | 'not_found'
+  | 'invalid_path'
+  | 'file_conflict'
+  | 'git'
+  | 'not_a_repo'
+  | 'detached_head'
+  | 'unborn_branch'
+  | 'git_not_found'
+  | 'cli_not_installed'
+  | 'signing_failed'
+  | 'patch_rejected'
+  | 'timeout'
+  | 'permission_denied'
+  | 'tauri'
+  | 'locked'
+  | 'other'
+
 export interface TauriError {
   message: string
+  code?: ErrorCode
+  hint?: string
   errorInfo?: {
     type?: string
     httpStatusCode?: number
@@ -220,6 +248,46 @@ export function getErrorInfo(error: unknown): TauriError['errorInfo'] | undefine
   return undefined
 }
 
+/**
+ * Machine-readable kind of a backend error
+ */
+export function getErrorCode(error: unknown): ErrorCode | undefined {
+  if (isTauriError(error) && typeof error.code === 'string') {
+    return error.code as ErrorCode
+  }
+  return undefined
+}
+
+/**
+ * What the user can do about a backend error, when the backend knows
+ */
+export function getErrorHint(error: unknown): string | undefined {
+  if (isTauriError(error) && typeof error.hint === 'string') {
+    return error.hint
+  }
+  return undefined
+}
+
+/**
+ * Why a commit could not be signed, if that is what failed
+ */
+export function getSigningError(error: unknown): SigningFailure | undefined {
+  if (isRecord(error) && isRecord(error.signingError)) {
+    return error.signingError as unknown as SigningFailure
+  }
+  return undefined
+}
+
+/**
+ * What the preflight check found when a patch was rejected
+ */
+export function getPatchPreflight(error: unknown): PatchPreflight | undefined {
+  if (isRecord(error) && isRecord(error.patchPreflight)) {
+    return error.patchPreflight as unknown as PatchPreflight
+  }
+  return undefined
+}
+
 /**
  * Check if error is a specific type
  */
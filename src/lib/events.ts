@@ -1,4 +1,23 @@
 import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+import type {
+  Action,
+  ActiveTurn,
+  AppServerDegradedInfo,
+  BatchProgress,
+  BatchTargetResult,
+  CiStatus,
+  CodexVersionCheck,
+  ComparisonRun,
+  HookOperation,
+  PendingApproval,
+  QuickPromptTarget,
+  QueuedTurn,
+  SessionStatus,
+  SessionWorktreeStatus,
+  TurnBudget,
+  TurnOutcome,
+} from './api'
+import type { TauriError } from './errorUtils'
 import { log } from './logger'
 
 // ==================== Event Types ====================
@@ -51,6 +70,46 @@ export interface TurnDiffUpdatedEvent {
   diff: string
 }
 
+/** A file's accumulated changes in a session's live diff */
+export interface LiveFileDiff {
+  path: string
+  kind: 'add' | 'delete' | 'update'
+  /** `command` diffs are the whole file against `HEAD` */
+  source: 'patch' | 'command'
+  diff: string
+  insertions: number
+  deletions: number
+  /** The diff text stopped growing at the size limit */
+  truncated: boolean
+  updatedAt: number
+}
+
+export interface LiveDiffUpdatedEvent {
+  sessionId: string
+  /** Ignore events older than the diff already loaded */
+  version: number
+  /** Changed files, in full */
+  files: LiveFileDiff[]
+  /** Files no longer in the diff */
+  removed: string[]
+  insertions: number
+  deletions: number
+}
+
+/** An archived session still has a worktree; offer to merge or delete it */
+export type SessionWorktreeCleanupOfferedEvent = SessionWorktreeStatus
+
+/** A turn crossed its budget and was interrupted */
+export interface TurnBudgetExceededEvent {
+  threadId: string
+  turnId: string
+  limit: 'tokens' | 'cost'
+  budget: TurnBudget
+  tokens: number
+  /** `null` when the model has no known pricing */
+  costUsd: number | null
+}
+
 export interface TurnPlanUpdatedEvent {
   threadId: string
   turnId: string
@@ -173,6 +232,20 @@ export interface StreamErrorEvent {
   willRetry: boolean
 }
 
+/** The login stopped working; offer `serverApi.startReauth` when `canReauth` */
+export interface LoginRequiredEvent {
+  reason: 'unauthorized' | 'loggedOut'
+  threadId: string | null
+  message: string | null
+  loginType: 'chatgpt' | 'apiKey'
+  /** False for API key logins, which need a new key */
+  canReauth: boolean
+}
+
+export interface LoginRestoredEvent {
+  loginType: 'chatgpt' | 'apiKey'
+}
+
 // Rate limit event
 export interface RateLimitExceededEvent {
   threadId: string
@@ -180,6 +253,39 @@ export interface RateLimitExceededEvent {
   retryAfterMs?: number
 }
 
+/** One simple command of a compound command line */
+export interface CommandIntent {
+  binary: string
+  subcommand: string | null
+  /** Arguments and redirect targets that look like file paths */
+  paths: string[]
+  text: string
+}
+
+/** Parsed command added by the backend to exec approval requests */
+export interface CommandContext {
+  command: string
+  cwd: string | null
+  intents: CommandIntent[]
+  risk: 'low' | 'medium' | 'high'
+  /** 0 for low, 20-60 for medium, 70-100 for high risk */
+  score: number
+  /** Why the command is not low risk */
+  reasons: string[]
+  /** May change files, the repository or packages, or runs project tooling */
+  writes: boolean
+  /**
+   * Uses command or process substitution, or expands or sets variables;
+   * never auto-approved or allowlisted
+   */
+  expands: boolean
+  /** Built-in deny rules and denylist patterns matched; any match makes the command high risk */
+  denyMatches: string[]
+  allowlistMatches: string[]
+  /** The whole command, or every segment of it, is allowlisted */
+  allowlisted: boolean
+}
+
 export interface CommandApprovalRequestedEvent {
   itemId: string
   threadId: string
@@ -187,6 +293,50 @@ export interface CommandApprovalRequestedEvent {
   reason?: string | null
   proposedExecpolicyAmendment?: { command: string[] } | null
   _requestId: number // JSON-RPC request ID for responding
+  /** Missing when the command could not be determined */
+  _commandContext?: CommandContext
+}
+
+/** A command approval the project's approval preset answered without asking */
+export interface ApprovalAutoApprovedEvent extends CommandApprovalRequestedEvent {
+  _autoApprovalReason: string
+}
+
+/** The newest pending approval, answered with a global shortcut */
+export interface ApprovalAnsweredByShortcutEvent extends PendingApproval {
+  decision: 'accept' | 'decline'
+}
+
+/** A quick action picked from the tray menu, after it was carried out */
+export interface TrayActionEvent {
+  /** Id of the registry action (see `listActions`) */
+  action: Action['id']
+}
+
+/** The quick prompt shortcut was pressed; open the palette on `target` */
+export interface QuickPromptRequestedEvent {
+  target: QuickPromptTarget | null
+}
+
+/** Where a `codex-desktop://` link leads */
+export type DeepLinkRoute =
+  | { kind: 'project'; projectId: string }
+  | { kind: 'session'; projectId: string; sessionId: string }
+  | {
+      kind: 'newThread'
+      /** `null` with `repo` unset means the most recent project */
+      projectId: string | null
+      /** Canonical path of a repo that is not a project yet */
+      repo: string | null
+      /** Only fills the composer; it is never sent on its own */
+      prompt: string | null
+    }
+
+export interface DeepLinkEvent {
+  url: string
+  route: DeepLinkRoute | null
+  /** Why the link could not be opened */
+  error: TauriError | null
 }
 
 export interface FileChangeApprovalRequestedEvent {
@@ -200,6 +350,99 @@ export interface FileChangeApprovalRequestedEvent {
 
 export type ServerDisconnectedEvent = Record<string, never>
 
+/** Full list of running turns, sent whenever it changes */
+export type ActiveTurnsChangedEvent = ActiveTurn[]
+
+/** A turn queue entry was added, started, finished or cancelled */
+export type TurnQueueUpdatedEvent = QueuedTurn
+
+/** A project's turn queue stopped after a failed or interrupted entry */
+export interface TurnQueuePausedEvent {
+  projectId: string
+  reason: string
+}
+
+/** Emitted when starting the app-server fails and it enters degraded mode */
+export type ServerUnavailableEvent = AppServerDegradedInfo
+
+/** The app-server was stopped after `idleMinutes` unused; it starts again on demand */
+export interface ServerIdleStoppedEvent {
+  idleMinutes: number
+}
+
+/** Emitted at startup when the codex CLI is outside the supported range */
+export type CodexVersionIncompatibleEvent = CodexVersionCheck
+
+/** A backend warning or error, redacted and rate-limited */
+export interface BackendLogEvent {
+  level: 'warn' | 'error'
+  target: string
+  message: string
+  fields: Record<string, string>
+  /** Unix milliseconds */
+  timestamp: number
+  /** Records dropped by the rate limit since the previous one */
+  suppressed: number
+}
+
+/** A startup phase completed; `step` orders events that arrive in a burst */
+export interface StartupProgressEvent {
+  phase:
+    | 'stateLoaded'
+    | 'databaseOpened'
+    | 'migrationsRun'
+    | 'watchersStarted'
+    | 'appServerSpawning'
+    | 'ready'
+  step: number
+  totalSteps: number
+  /** Milliseconds since startup began */
+  elapsedMs: number
+  /** Milliseconds the phase took */
+  phaseMs: number
+  /** On `ready`, why the app-server did not start */
+  detail: string | null
+}
+
+/** A batch target started or finished; totals cover the whole batch */
+export interface BatchProgressEvent extends BatchProgress {
+  batchId: string
+  target: BatchTargetResult
+}
+
+/** A comparison run started or finished */
+export interface ComparisonUpdatedEvent {
+  groupId: string
+  run: ComparisonRun
+}
+
+/**
+ * A session's status changed because one of its turns started or ended;
+ * `outcome` is set when a turn ended
+ */
+export interface SessionStatusChangedEvent {
+  sessionId: string
+  status: SessionStatus
+  outcome: TurnOutcome | null
+  turnId: string | null
+  error: string | null
+}
+
+/** A watched project's CI status changed; also sent once when watching starts */
+export interface CiStatusChangedEvent {
+  projectId: string
+  status: CiStatus
+}
+
+/** A line printed by `git commit` or `git push` or one of their hooks */
+export interface GitHookOutputEvent {
+  /** Repository the command runs in */
+  path: string
+  operation: HookOperation
+  stream: 'stdout' | 'stderr'
+  line: string
+}
+
 // ==================== Event Handlers ====================
 
 export type EventHandlers = {
@@ -207,7 +450,16 @@ export type EventHandlers = {
   onThreadStarted?: (event: ThreadStartedEvent) => void
   onTurnStarted?: (event: TurnStartedEvent) => void
   onTurnCompleted?: (event: TurnCompletedEvent) => void
+  onActiveTurnsChanged?: (event: ActiveTurnsChangedEvent) => void
+  onTurnQueueUpdated?: (event: TurnQueueUpdatedEvent) => void
+  onTurnQueuePaused?: (event: TurnQueuePausedEvent) => void
+  onBatchProgress?: (event: BatchProgressEvent) => void
+  onComparisonUpdated?: (event: ComparisonUpdatedEvent) => void
+  onSessionStatusChanged?: (event: SessionStatusChangedEvent) => void
   onTurnDiffUpdated?: (event: TurnDiffUpdatedEvent) => void
+  onLiveDiffUpdated?: (event: LiveDiffUpdatedEvent) => void
+  onSessionWorktreeCleanupOffered?: (event: SessionWorktreeCleanupOfferedEvent) => void
+  onTurnBudgetExceeded?: (event: TurnBudgetExceededEvent) => void
   onTurnPlanUpdated?: (event: TurnPlanUpdatedEvent) => void
   onThreadCompacted?: (event: ThreadCompactedEvent) => void
 
@@ -236,13 +488,27 @@ export type EventHandlers = {
   // Approvals
   onCommandApprovalRequested?: (event: CommandApprovalRequestedEvent) => void
   onFileChangeApprovalRequested?: (event: FileChangeApprovalRequestedEvent) => void
+  onApprovalAutoApproved?: (event: ApprovalAutoApprovedEvent) => void
+  onApprovalAnsweredByShortcut?: (event: ApprovalAnsweredByShortcutEvent) => void
 
   // Errors
   onStreamError?: (event: StreamErrorEvent) => void
   onServerDisconnected?: (event: ServerDisconnectedEvent) => void
+  onServerUnavailable?: (event: ServerUnavailableEvent) => void
+  onServerIdleStopped?: (event: ServerIdleStoppedEvent) => void
+  onCodexVersionIncompatible?: (event: CodexVersionIncompatibleEvent) => void
+  onStartupProgress?: (event: StartupProgressEvent) => void
+  onBackendLog?: (event: BackendLogEvent) => void
+
+  // Login
+  onLoginRequired?: (event: LoginRequiredEvent) => void
+  onLoginRestored?: (event: LoginRestoredEvent) => void
 
   // Rate limiting
   onRateLimitExceeded?: (event: RateLimitExceededEvent) => void
+
+  // CI
+  onCiStatusChanged?: (event: CiStatusChangedEvent) => void
 }
 
 // ==================== Setup Event Listeners ====================
@@ -261,7 +527,16 @@ export async function setupEventListeners(
     ['thread-started', handlers.onThreadStarted],
     ['turn-started', handlers.onTurnStarted],
     ['turn-completed', handlers.onTurnCompleted],
+    ['active-turns-changed', handlers.onActiveTurnsChanged],
+    ['turn-queue-updated', handlers.onTurnQueueUpdated],
+    ['turn-queue-paused', handlers.onTurnQueuePaused],
+    ['batch-progress', handlers.onBatchProgress],
+    ['comparison-updated', handlers.onComparisonUpdated],
+    ['session-status-changed', handlers.onSessionStatusChanged],
     ['turn-diff-updated', handlers.onTurnDiffUpdated],
+    ['live-diff-updated', handlers.onLiveDiffUpdated],
+    ['session-worktree-cleanup-offered', handlers.onSessionWorktreeCleanupOffered],
+    ['turn-budget-exceeded', handlers.onTurnBudgetExceeded],
     ['turn-plan-updated', handlers.onTurnPlanUpdated],
     ['thread-compacted', handlers.onThreadCompacted],
     // Item lifecycle
@@ -283,11 +558,23 @@ export async function setupEventListeners(
     // Approvals
     ['item-commandExecution-requestApproval', handlers.onCommandApprovalRequested],
     ['item-fileChange-requestApproval', handlers.onFileChangeApprovalRequested],
+    ['approval-auto-approved', handlers.onApprovalAutoApproved],
+    ['approval-answered-by-shortcut', handlers.onApprovalAnsweredByShortcut],
     // Errors
     ['error', handlers.onStreamError],
     ['app-server-disconnected', handlers.onServerDisconnected],
+    ['app-server-unavailable', handlers.onServerUnavailable],
+    ['app-server-idle-stopped', handlers.onServerIdleStopped],
+    ['codex-version-incompatible', handlers.onCodexVersionIncompatible],
+    ['startup-progress', handlers.onStartupProgress],
+    ['backend-log', handlers.onBackendLog],
+    // Login
+    ['login-required', handlers.onLoginRequired],
+    ['login-restored', handlers.onLoginRestored],
     // Rate limiting
     ['turn-rateLimitExceeded', handlers.onRateLimitExceeded],
+    // CI
+    ['ci-status-changed', handlers.onCiStatusChanged],
   ]
 
   // Register all listeners in parallel for faster startup
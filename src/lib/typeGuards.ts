@@ -273,6 +273,10 @@ export function isErrorLike(value: unknown): value is { message: string } {
  */
 export interface TauriErrorShape {
   message: string
+  /** Machine-readable kind, see `ErrorCode` in errorUtils */
+  code?: string
+  /** What the user can do about it */
+  hint?: string
   errorInfo?: {
     type?: string
     httpStatusCode?: number
@@ -139,3 +139,34 @@ export function truncate(str: string, length: number): string {
   if (str.length <= length) return str
   return str.slice(0, length) + '...'
 }
+
+type JsonObject = Record<string, unknown>
+
+function isPlainObject(value: unknown): value is JsonObject {
+  return typeof value === 'object' && value !== null && !Array.isArray(value)
+}
+
+/**
+ * JSON merge patch (RFC 7386) turning `before` into `after`.
+ *
+ * Keys missing from `after` or set to `undefined` become `null`, which the
+ * backend reads as "clear"; nested objects are diffed key by key and
+ * unchanged keys are left out.
+ */
+export function mergePatch(before: JsonObject, after: JsonObject): JsonObject {
+  const patch: JsonObject = {}
+  for (const key of Object.keys(before)) {
+    if (after[key] === undefined) patch[key] = null
+  }
+  for (const [key, value] of Object.entries(after)) {
+    if (value === undefined) continue
+    const previous = before[key]
+    if (isPlainObject(previous) && isPlainObject(value)) {
+      const nested = mergePatch(previous, value)
+      if (Object.keys(nested).length > 0) patch[key] = nested
+    } else if (JSON.stringify(previous) !== JSON.stringify(value)) {
+      patch[key] = value
+    }
+  }
+  return patch
+}
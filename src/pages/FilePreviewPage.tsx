@@ -13,6 +13,9 @@ import { openInTerminal, openInVSCode, revealInFinder } from '../lib/hostActions
 import { parseError } from '../lib/errorUtils'
 import { useToast } from '../components/ui/Toast'
 
+/** Most files listed in the tree */
+const FILE_LIST_LIMIT = 500
+
 export function FilePreviewPage() {
   const { projects, selectedProjectId } = useProjectsStore()
   const selectedProject = selectedProjectId ? projects.find((p) => p.id === selectedProjectId) ?? null : null
@@ -20,6 +23,7 @@ export function FilePreviewPage() {
   const { showToast } = useToast()
 
   const [files, setFiles] = useState<FileEntry[]>([])
+  const [filesTruncated, setFilesTruncated] = useState(false)
   const [filesLoading, setFilesLoading] = useState(false)
   const [filesError, setFilesError] = useState<string | null>(null)
 
@@ -43,12 +47,14 @@ export function FilePreviewPage() {
     setFilesError(null)
 
     try {
-      const entries = await projectApi.listFiles(selectedProject.path, undefined, 500)
+      const response = await projectApi.listFiles(selectedProject.path, undefined, FILE_LIST_LIMIT)
       if (seq !== listSeqRef.current) return
-      setFiles(entries)
+      setFiles(response.files)
+      setFilesTruncated(response.truncated)
     } catch (err) {
       if (seq !== listSeqRef.current) return
       setFiles([])
+      setFilesTruncated(false)
       setFilesError(parseError(err))
     } finally {
       if (seq === listSeqRef.current) setFilesLoading(false)
@@ -200,7 +206,12 @@ export function FilePreviewPage() {
           {filesError && (
             <span className="ml-auto text-xs text-status-error truncate">{filesError}</span>
           )}
-          {!filesError && <span className="ml-auto" />}
+          {!filesError && filesTruncated && (
+            <span className="ml-auto text-xs text-text-3/70 truncate">
+              Showing the first {FILE_LIST_LIMIT} files
+            </span>
+          )}
+          {!filesError && !filesTruncated && <span className="ml-auto" />}
           <div className="flex items-center gap-1.5">
             <IconButton
               variant="ghost"
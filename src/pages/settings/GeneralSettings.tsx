@@ -6,6 +6,8 @@ import { useSettingsStore } from '../../stores/settings'
 import { useModelsStore, modelSupportsReasoning } from '../../stores/models'
 import { parseReasoningEffort } from '../../lib/validation'
 import { Switch } from '../../components/ui/Switch'
+import { notificationsApi, type NotificationPreferences } from '../../lib/api'
+import { isTauriAvailable } from '../../lib/tauri'
 import {
   SettingsSection,
   SettingsCard,
@@ -57,8 +59,11 @@ export const GeneralSettings = memo(function GeneralSettings() {
   const { settings, updateSetting } = useSettingsStore()
   const { models, isLoading, error } = useModelsStore()
 
-  // Notification and keep-awake use localStorage since they are local-only prefs
-  const notificationsEnabled = localStorage.getItem('codex-notifications') !== 'false'
+  // Native notifications are shown by the backend, which keeps their preferences
+  const [notificationPrefs, setNotificationPrefs] = useState<NotificationPreferences | null>(
+    null
+  )
+  // Keep-awake uses localStorage since it is a local-only pref
   const keepAwakeEnabled = localStorage.getItem('codex-keep-awake') === 'true'
 
   // Font size mode
@@ -84,6 +89,14 @@ export const GeneralSettings = memo(function GeneralSettings() {
     void useModelsStore.getState().fetchModels()
   }, [])
 
+  useEffect(() => {
+    if (!isTauriAvailable()) return
+    notificationsApi
+      .getPreferences()
+      .then(setNotificationPrefs)
+      .catch(() => setNotificationPrefs(null))
+  }, [])
+
   // Auto-select default model if none is selected
   useEffect(() => {
     if (!settings.model && models.length > 0) {
@@ -112,9 +125,11 @@ export const GeneralSettings = memo(function GeneralSettings() {
   }
 
   const handleNotificationsToggle = (checked: boolean) => {
-    localStorage.setItem('codex-notifications', checked ? 'true' : 'false')
-    // Force re-render by triggering a state update
-    window.dispatchEvent(new Event('storage'))
+    if (!notificationPrefs) return
+    notificationsApi
+      .setPreferences({ ...notificationPrefs, enabled: checked })
+      .then(setNotificationPrefs)
+      .catch(() => {})
   }
 
   const handleKeepAwakeToggle = (checked: boolean) => {
@@ -385,10 +400,11 @@ export const GeneralSettings = memo(function GeneralSettings() {
           <SettingsList>
             <SettingsRow
               title="Notifications"
-              description="Show a notification when a task completes."
+              description="Show a notification when a task finishes or needs approval."
             >
               <Switch
-                checked={notificationsEnabled}
+                checked={notificationPrefs?.enabled ?? false}
+                disabled={!notificationPrefs}
                 onChange={handleNotificationsToggle}
                 aria-label="Enable notifications"
               />
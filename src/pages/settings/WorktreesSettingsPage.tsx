@@ -3,7 +3,7 @@ import { FolderOpen, RefreshCw, SquareTerminal, Code2, Trash2, GitBranch } from
 
 import { SettingsSection, SettingsCard, SettingsList, SettingsRow } from '../../components/settings/SettingsLayout'
 import { useProjectsStore } from '../../stores/projects'
-import { projectApi, type WorktreeInfo } from '../../lib/api'
+import { projectApi, type WorktreeStatus } from '../../lib/api'
 import { parseError } from '../../lib/errorUtils'
 import { isTauriAvailable } from '../../lib/tauri'
 import { revealInFinder, openInTerminal, openInVSCode } from '../../lib/hostActions'
@@ -13,6 +13,26 @@ import { BaseDialog } from '../../components/ui/BaseDialog'
 import { useToast } from '../../components/ui/useToast'
 import { cn } from '../../lib/utils'
 
+function formatSize(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(1)} GB`
+}
+
+/** e.g. "modified · 2 ahead, 1 behind · session 1a2b3c4d · 12.3 MB" */
+function describeWorktree(wt: WorktreeStatus): string {
+  const parts = [wt.dirty ? 'modified' : 'clean']
+  if (wt.ahead !== null && wt.behind !== null) {
+    parts.push(
+      wt.ahead === 0 && wt.behind === 0 ? 'up to date' : `${wt.ahead} ahead, ${wt.behind} behind`
+    )
+  }
+  if (wt.sessionId) parts.push(`session ${wt.sessionId.slice(0, 8)}`)
+  parts.push(formatSize(wt.diskUsage))
+  return parts.join(' · ')
+}
+
 export function WorktreesSettingsPage() {
   const selectedProjectId = useProjectsStore((state) => state.selectedProjectId)
   const projects = useProjectsStore((state) => state.projects)
@@ -25,7 +45,7 @@ export function WorktreesSettingsPage() {
     return project?.path ?? null
   }, [projects, selectedProjectId])
 
-  const [worktrees, setWorktrees] = useState<WorktreeInfo[]>([])
+  const [worktrees, setWorktrees] = useState<WorktreeStatus[]>([])
   const [isLoading, setIsLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
 
@@ -33,7 +53,7 @@ export function WorktreesSettingsPage() {
   const [isCreating, setIsCreating] = useState(false)
 
   const [removeDialogOpen, setRemoveDialogOpen] = useState(false)
-  const [worktreeToRemove, setWorktreeToRemove] = useState<WorktreeInfo | null>(null)
+  const [worktreeToRemove, setWorktreeToRemove] = useState<WorktreeStatus | null>(null)
   const [isRemoving, setIsRemoving] = useState(false)
 
   const suggestedWorktreePath = useMemo(() => {
@@ -128,7 +148,7 @@ export function WorktreesSettingsPage() {
     }
   }, [requireTauri, toast])
 
-  const openRemoveDialog = useCallback((wt: WorktreeInfo) => {
+  const openRemoveDialog = useCallback((wt: WorktreeStatus) => {
     setWorktreeToRemove(wt)
     setRemoveDialogOpen(true)
   }, [])
@@ -246,6 +266,9 @@ export function WorktreesSettingsPage() {
                         <div className="mt-1 truncate text-[11px] text-text-3 font-mono" title={wt.path}>
                           {wt.path}
                         </div>
+                        <div className="mt-0.5 truncate text-[11px] text-text-3">
+                          {describeWorktree(wt)}
+                        </div>
                       </div>
 
                       <div className="flex items-center gap-1.5 flex-shrink-0">
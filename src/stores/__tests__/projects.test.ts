@@ -33,6 +33,9 @@ function makeProject(overrides: Partial<Project> = {}): Project {
     createdAt: 1000,
     lastOpenedAt: null,
     settingsJson: null,
+    color: null,
+    icon: null,
+    emoji: null,
     ...overrides,
   }
 }
@@ -148,6 +148,12 @@ describe('mergeProjectSettings', () => {
     expect(result.approvalPolicy).toBe('never')
   })
 
+  it('overrides reasoningEffort from project settings', () => {
+    const json = JSON.stringify({ reasoningEffort: 'x_high' })
+    const result = mergeProjectSettings(defaultSettings, json)
+    expect(result.reasoningEffort).toBe('xhigh')
+  })
+
   it('falls back to global settings for invalid project values', () => {
     const json = JSON.stringify({ sandboxMode: 'invalid-mode' })
     const result = mergeProjectSettings(defaultSettings, json)
@@ -29,6 +29,11 @@ let monitorInterval: ReturnType<typeof setInterval> | null = null
 let inFlightStatus: Promise<void> | null = null
 let inFlightReconnect: Promise<void> | null = null
 
+/** An idle-stopped server starts again on demand, so it is not a disconnect */
+function isAvailable(status: ServerStatus): boolean {
+  return status.isRunning || status.state === 'idle'
+}
+
 function backoffDelay(attempt: number): number {
   const base = Math.min(CONNECTION_RETRY.BASE_DELAY * attempt, CONNECTION_RETRY.MAX_DELAY)
   const jitter = Math.floor(Math.random() * CONNECTION_RETRY.JITTER)
@@ -67,16 +72,17 @@ export const useServerConnectionStore = create<ServerConnectionState>((set, get)
     inFlightStatus = (async () => {
       try {
         const status = await serverApi.getStatus()
+        const available = isAvailable(status)
         set((state) => ({
           status,
-          isConnected: status.isRunning,
+          isConnected: available,
           hasConnectedOnce: state.hasConnectedOnce || status.isRunning,
           lastError: null,
           lastCheckedAt: Date.now(),
-          consecutiveFailures: status.isRunning ? 0 : state.consecutiveFailures,
+          consecutiveFailures: available ? 0 : state.consecutiveFailures,
         }))
 
-        if (!status.isRunning && get().hasConnectedOnce && !get().isReconnecting) {
+        if (!available && get().hasConnectedOnce && !get().isReconnecting) {
           void get().attemptReconnect()
         }
       } catch (error) {
@@ -114,7 +120,7 @@ export const useServerConnectionStore = create<ServerConnectionState>((set, get)
         set({ retryCount: attempt })
         try {
           const status = await serverApi.getStatus()
-          if (status.isRunning) {
+          if (isAvailable(status)) {
             get().markConnected(status)
             return
           }
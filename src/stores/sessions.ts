@@ -49,11 +49,18 @@ export interface SessionsState {
 
   // Status management actions
   updateSessionStatus: (sessionId: string, status: SessionStatus) => Promise<void>
+  /** Apply a status the backend already stored (no round-trip) */
+  applySessionStatus: (sessionId: string, status: SessionStatus) => void
   setSessionFirstMessage: (sessionId: string, firstMessage: string) => Promise<void>
   updateSessionTasks: (sessionId: string, tasks: TaskItem[]) => Promise<void>
 
   // Search actions
-  searchSessions: (query: string, tagsFilter?: string[], favoritesOnly?: boolean) => Promise<void>
+  searchSessions: (
+    query: string,
+    tagsFilter?: string[],
+    favoritesOnly?: boolean,
+    projectId?: string | null
+  ) => Promise<void>
   clearSearch: () => void
 
   // Helper to get session display name
@@ -285,6 +292,16 @@ export const useSessionsStore = create<SessionsState>((set, get) => {
     }
   },
 
+  applySessionStatus: (sessionId: string, status: SessionStatus) => {
+    // Supersedes any optimistic update still in flight
+    statusUpdateSeq.set(sessionId, (statusUpdateSeq.get(sessionId) ?? 0) + 1)
+    set((state) => ({
+      sessions: state.sessions.map((s) =>
+        s.sessionId === sessionId ? { ...s, status } : s
+      ),
+    }))
+  },
+
   // Set first message - only updates if not already set
   setSessionFirstMessage: async (sessionId: string, firstMessage: string) => {
     const session = get().sessions.find((s) => s.sessionId === sessionId)
@@ -357,7 +374,12 @@ export const useSessionsStore = create<SessionsState>((set, get) => {
     }
   },
 
-  searchSessions: async (query: string, tagsFilter?: string[], favoritesOnly?: boolean) => {
+  searchSessions: async (
+    query: string,
+    tagsFilter?: string[],
+    favoritesOnly?: boolean,
+    projectId?: string | null
+  ) => {
     if (!query.trim()) {
       searchSessionsSeq += 1
       set({ searchQuery: '', searchResults: [], isSearching: false })
@@ -368,7 +390,7 @@ export const useSessionsStore = create<SessionsState>((set, get) => {
     const requestId = searchSessionsSeq
     set({ searchQuery: query, isSearching: true })
     try {
-      const results = await sessionApi.search(query, tagsFilter, favoritesOnly)
+      const results = await sessionApi.search(query, tagsFilter, favoritesOnly, undefined, projectId)
       if (requestId !== searchSessionsSeq) return
       set({ searchResults: results, isSearching: false })
     } catch (error) {
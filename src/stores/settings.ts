@@ -6,6 +6,7 @@ import {
   normalizeReasoningSummary,
   normalizeSandboxMode,
 } from '../lib/normalize'
+import type { ApprovalPreset, SandboxProfile } from '../lib/api'
 
 // Reasoning effort levels supported by Codex (matches API schema)
 export type ReasoningEffort = 'none' | 'minimal' | 'low' | 'medium' | 'high' | 'xhigh'
@@ -126,8 +127,15 @@ export interface ProjectSettings {
   cwd?: string
   envVars?: Record<string, string>
   model?: string
+  reasoningEffort?: string
   sandboxMode?: string
   askForApproval?: string // maps to approvalPolicy
+  modelProvider?: string // id of a custom model provider
+  approvalPreset?: ApprovalPreset // approvals answered without asking
+  sandboxProfile?: SandboxProfile // restrictions on terminal commands
+  autoSnapshot?: boolean // snapshot the project before every turn
+  worktreePerSession?: boolean // start each session in a worktree and branch of its own
+  worktreeBranchTemplate?: string // e.g. codex/{project}/{date}-{id}
   [key: string]: unknown // Index signature to allow additional properties from JSON
 }
 
@@ -153,6 +161,9 @@ export function mergeProjectSettings(
       approvalPolicy:
         (normalizeApprovalPolicy(projectSettings.askForApproval) as ApprovalPolicy) ||
         globalSettings.approvalPolicy,
+      reasoningEffort:
+        (normalizeReasoningEffort(projectSettings.reasoningEffort) as ReasoningEffort) ||
+        globalSettings.reasoningEffort,
     }
   } catch {
     // Project settingsJson is invalid JSON — fall back to global settings
@@ -105,6 +105,7 @@ function createMockState(threads: Record<string, SingleThreadState>): ThreadStat
     handleAgentMessageDelta: vi.fn(),
     handleCommandApprovalRequested: vi.fn(),
     handleFileChangeApprovalRequested: vi.fn(),
+    handleApprovalAnsweredByShortcut: vi.fn(),
     handleTurnStarted: vi.fn(),
     handleTurnCompleted: vi.fn(),
     handleTurnDiffUpdated: vi.fn(),
@@ -143,6 +143,7 @@ function createMockState(
     handleAgentMessageDelta: vi.fn(),
     handleCommandApprovalRequested: vi.fn(),
     handleFileChangeApprovalRequested: vi.fn(),
+    handleApprovalAnsweredByShortcut: vi.fn(),
     handleTurnStarted: vi.fn(),
     handleTurnCompleted: vi.fn(),
     handleTurnDiffUpdated: vi.fn(),
@@ -14,6 +14,7 @@ import {
   isFileChangeContent,
 } from '../../../lib/typeGuards'
 import type {
+  ApprovalAnsweredByShortcutEvent,
   CommandApprovalRequestedEvent,
   FileChangeApprovalRequestedEvent,
 } from '../../../lib/events'
@@ -155,6 +156,39 @@ export function createHandleFileChangeApprovalRequested(
   }
 }
 
+// ==================== Approval Answered By Shortcut Handler ====================
+
+export function createHandleApprovalAnsweredByShortcut(
+  set: (fn: (state: WritableDraft<ThreadState>) => ThreadState | void) => void
+) {
+  return (event: ApprovalAnsweredByShortcutEvent) => {
+    const { threadId, itemId } = event
+    if (!threadId || !itemId) return
+
+    set((state) => {
+      const threadState = state.threads[threadId]
+      if (!threadState) return
+
+      const approved = event.decision === 'accept'
+      const item = threadState.items[itemId]
+      if (isCommandExecutionContent(item?.content)) {
+        item.content.needsApproval = false
+        item.content.approved = approved
+      } else if (isFileChangeContent(item?.content)) {
+        item.content.needsApproval = false
+        item.content.approved = approved
+        if (approved) {
+          item.content.applied = true
+        }
+      }
+
+      threadState.pendingApprovals = threadState.pendingApprovals.filter(
+        (approval) => approval.requestId !== event.requestId
+      )
+    })
+  }
+}
+
 // ==================== Stale Approvals Cleanup ====================
 
 /**
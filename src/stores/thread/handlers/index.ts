@@ -35,5 +35,6 @@ export {
 export {
   createHandleCommandApprovalRequested,
   createHandleFileChangeApprovalRequested,
+  createHandleApprovalAnsweredByShortcut,
   createCleanupStaleApprovals,
 } from './approval-handlers'
@@ -73,15 +73,7 @@ export function createHandleTurnStarted(
 
     clearTurnTimeout(threadId)
 
-    // P1 Fix: Use event bus instead of dynamic import to avoid circular dependencies
-    try {
-      // Check thread still exists to prevent race condition with closeThread
-      if (getThreadStore().threads[threadId]) {
-        eventBus.emit('session:status-update', { sessionId: threadId, status: 'running' })
-      }
-    } catch (err) {
-      handleAsyncError(err, 'handleTurnStarted session sync', 'thread')
-    }
+    // The session's status is updated by the backend (`session-status-changed`)
 
     // Set turn timeout for this specific thread
     const turnId = event.turn.id
@@ -135,7 +127,7 @@ export function createHandleTurnStarted(
 export function createHandleTurnCompleted(
   set: (fn: (state: WritableDraft<ThreadState>) => ThreadState | void) => void,
   get: () => ThreadState,
-  getThreadStore: () => ThreadState,
+  _getThreadStore: () => ThreadState,
   dispatchNextQueuedMessage: (threadId: string) => Promise<void>
 ) {
   return (event: TurnCompletedEvent) => {
@@ -163,18 +155,8 @@ export function createHandleTurnCompleted(
         ? 'interrupted'
         : 'completed'
 
-    // P1 Fix: Use event bus instead of dynamic import to avoid circular dependencies
-    try {
-      // Check thread still exists to prevent race condition with closeThread
-      if (getThreadStore().threads[threadId]) {
-        const sessionStatus = nextTurnStatus === 'failed' ? 'failed'
-          : nextTurnStatus === 'interrupted' ? 'interrupted'
-          : 'completed'
-        eventBus.emit('session:status-update', { sessionId: threadId, status: sessionStatus })
-      }
-    } catch (err) {
-      handleAsyncError(err, 'handleTurnCompleted session sync', 'thread')
-    }
+    // The backend classifies the outcome and rolls it up into the session's
+    // status (`session-status-changed`)
 
     set((state) => {
       const threadState = state.threads[threadId]
@@ -51,6 +51,7 @@ import {
   createHandleServerDisconnected,
   createHandleCommandApprovalRequested,
   createHandleFileChangeApprovalRequested,
+  createHandleApprovalAnsweredByShortcut,
   createCleanupStaleApprovals,
 } from './handlers'
 
@@ -186,6 +187,7 @@ export const useThreadStore: UseBoundStore<StoreApi<ThreadState>> = create<Threa
       handleAgentMessageDelta: createHandleAgentMessageDelta(get),
       handleCommandApprovalRequested: createHandleCommandApprovalRequested(typedSet),
       handleFileChangeApprovalRequested: createHandleFileChangeApprovalRequested(typedSet),
+      handleApprovalAnsweredByShortcut: createHandleApprovalAnsweredByShortcut(typedSet),
       handleTurnStarted: createHandleTurnStarted(typedSet, get, getThreadStore),
       handleTurnCompleted: createHandleTurnCompleted(typedSet, get, getThreadStore, dispatchNextQueuedMessage),
       handleTurnDiffUpdated: createHandleTurnDiffUpdated(typedSet),
@@ -11,6 +11,7 @@ import type {
   SkillInput,
 } from '../../lib/api'
 import type {
+  ApprovalAnsweredByShortcutEvent,
   CommandApprovalRequestedEvent,
   FileChangeApprovalRequestedEvent,
   ItemStartedEvent,
@@ -367,6 +368,7 @@ export interface ThreadState {
   handleAgentMessageDelta: (event: AgentMessageDeltaEvent) => void
   handleCommandApprovalRequested: (event: CommandApprovalRequestedEvent) => void
   handleFileChangeApprovalRequested: (event: FileChangeApprovalRequestedEvent) => void
+  handleApprovalAnsweredByShortcut: (event: ApprovalAnsweredByShortcutEvent) => void
   handleTurnStarted: (event: TurnStartedEvent) => void
   handleTurnCompleted: (event: TurnCompletedEvent) => void
   handleTurnDiffUpdated: (event: TurnDiffUpdatedEvent) => void